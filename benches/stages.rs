@@ -0,0 +1,73 @@
+//! Per-stage generation benchmarks.
+//!
+//! Each benchmark runs `generate` and measures only the time one stage
+//! reports in `LevelStats::stage_timings`, so stages are measured inside the
+//! real pipeline without exposing them. Seeds vary per iteration to average
+//! over layouts. The `batch_*` benchmarks compare whole generations with
+//! fresh buffers against a reused `GeneratorScratch`. Run with
+//! `cargo bench --bench stages`; `wfc_large` shows what the `parallel`
+//! feature gains when run again with `--features parallel`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::time::Duration;
+
+use level_generator::{generate, generate_with_scratch, GenerationMode, GeneratorParams, GeneratorScratch};
+
+/// Total time `stage` took over `iters` levels generated from `params`.
+fn stage_time(params: &GeneratorParams, stage: &str, iters: u64) -> Duration {
+    (0..iters)
+        .map(|i| {
+            let level = generate(&GeneratorParams { seed: Some(i), ..params.clone() });
+            let micros: u64 = level.stats.stage_timings.iter().filter(|t| t.stage == stage).map(|t| t.micros).sum();
+            Duration::from_micros(micros)
+        })
+        .sum()
+}
+
+fn bench_stage(c: &mut Criterion, name: &str, params: GeneratorParams, stage: &'static str) {
+    c.bench_function(name, |b| b.iter_custom(|iters| stage_time(&params, stage, iters)));
+}
+
+fn classic() -> GeneratorParams {
+    GeneratorParams { width: 200, height: 120, rooms: 40, ..Default::default() }
+}
+
+fn marble() -> GeneratorParams {
+    GeneratorParams { mode: GenerationMode::Marble, enable_elevation: true, ..classic() }
+}
+
+fn stages(c: &mut Criterion) {
+    bench_stage(c, "room_placement", classic(), "rooms");
+    bench_stage(c, "corridor_carving", classic(), "connect");
+    bench_stage(c, "marble_conversion", marble(), "marble_tiles");
+    bench_stage(c, "elevation_smoothing", marble(), "elevation");
+    let wfc = GeneratorParams { mode: GenerationMode::Wfc, width: 60, height: 40, ..Default::default() };
+    bench_stage(c, "wfc", wfc, "wfc");
+    let wfc_large = GeneratorParams { mode: GenerationMode::Wfc, width: 150, height: 100, ..Default::default() };
+    bench_stage(c, "wfc_large", wfc_large, "wfc");
+}
+
+fn batch(c: &mut Criterion) {
+    let params = marble();
+    let mut seed = 0;
+    c.bench_function("batch_fresh", |b| {
+        b.iter(|| {
+            seed += 1;
+            generate(&GeneratorParams { seed: Some(seed), ..params.clone() })
+        })
+    });
+    let mut scratch = GeneratorScratch::new();
+    c.bench_function("batch_scratch", |b| {
+        b.iter(|| {
+            seed += 1;
+            generate_with_scratch(&GeneratorParams { seed: Some(seed), ..params.clone() }, &mut scratch)
+        })
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(20);
+    targets = stages, batch
+}
+criterion_main!(benches);
@@ -1,4 +1,4 @@
-use level_generator::{generate, GeneratorParams, GenerationMode, TileType};
+use level_generator::{generate, GeneratorParams, GenerationMode};
 
 fn main() {
     // Generate a level and analyze it
@@ -13,29 +13,17 @@ fn main() {
     };
     
     let level = generate(&params);
+    let stats = &level.stats;
     
-    // Analyze the generated level
-    if let Some(marble_tiles) = &level.marble_tiles {
-        let mut tile_counts = std::collections::HashMap::new();
-        
-        for row in marble_tiles {
-            for tile in row {
-                *tile_counts.entry(format!("{:?}", tile.tile_type)).or_insert(0) += 1;
-            }
-        }
-        
-        println!("Tile Distribution:");
-        for (tile_type, count) in tile_counts {
-            println!("  {}: {}", tile_type, count);
-        }
-        
-        // Find slopes
-        let slope_count: usize = marble_tiles
-            .iter()
-            .flat_map(|row| row.iter())
-            .filter(|t| t.tile_type == TileType::Slope)
-            .count();
-        
-        println!("\nSlopes for elevation changes: {}", slope_count);
+    println!("Tile Distribution:");
+    for (tile_type, count) in &stats.tile_histogram {
+        println!("  {}: {}", tile_type, count);
     }
+    
+    // Find slopes
+    let slope_count = stats.tile_histogram.get("Slope").copied().unwrap_or(0);
+    println!("\nSlopes for elevation changes: {}", slope_count);
+    
+    println!("Rooms placed: {} / {}", stats.rooms_placed, stats.rooms_requested);
+    println!("Floor ratio: {:.1}%", stats.floor_ratio * 100.0);
 }
@@ -0,0 +1,163 @@
+/* Generated with cbindgen; do not edit by hand.
+ * Regenerate with: cbindgen --config cbindgen.toml --output include/level_generator.h
+ */
+
+#ifndef LEVEL_GENERATOR_H
+#define LEVEL_GENERATOR_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Generation mode value for classic dungeons.
+ */
+#define LG_MODE_CLASSIC 0
+
+/**
+ * Generation mode value for marble tracks.
+ */
+#define LG_MODE_MARBLE 1
+
+/**
+ * Generation mode value for WFC mazes.
+ */
+#define LG_MODE_WFC 2
+
+/**
+ * Generation mode value for overworld terrain.
+ */
+#define LG_MODE_OVERWORLD 3
+
+/**
+ * Generation mode value for settlements.
+ */
+#define LG_MODE_SETTLEMENT 4
+
+/**
+ * Generation mode value for room archetype WFC levels.
+ */
+#define LG_MODE_ROOM_WFC 5
+
+/**
+ * Opaque handle to a generated level.
+ */
+typedef struct LgLevel LgLevel;
+
+/**
+ * C-compatible mirror of `GeneratorParams`.
+ */
+typedef struct LgParams {
+  uint32_t width;
+  uint32_t height;
+  uint32_t rooms;
+  uint32_t min_room;
+  uint32_t max_room;
+  /**
+   * RNG seed; only used when `has_seed` is true
+   */
+  uint64_t seed;
+  bool has_seed;
+  /**
+   * One of `LG_MODE_CLASSIC`, `LG_MODE_MARBLE`, `LG_MODE_WFC`, `LG_MODE_OVERWORLD`, `LG_MODE_SETTLEMENT`, `LG_MODE_ROOM_WFC`
+   */
+  uint32_t mode;
+  uint32_t channel_width;
+  uint32_t corner_radius;
+  bool enable_elevation;
+  int32_t max_elevation;
+  bool enable_obstacles;
+  float obstacle_density;
+  int32_t max_elevation_change;
+} LgParams;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Returns parameters matching `GeneratorParams::default()`.
+ */
+struct LgParams lg_params_default(void);
+
+/**
+ * Generate a level. Returns null if `params` is null or generation panics.
+ */
+struct LgLevel *lg_generate(const struct LgParams *params);
+
+/**
+ * Release a level handle. Passing null is a no-op.
+ */
+void lg_level_free(struct LgLevel *level);
+
+/**
+ * Level width in tiles (0 for a null handle).
+ */
+uint32_t lg_level_width(const struct LgLevel *level);
+
+/**
+ * Level height in tiles (0 for a null handle).
+ */
+uint32_t lg_level_height(const struct LgLevel *level);
+
+/**
+ * Seed actually used for generation (0 for a null handle).
+ */
+uint64_t lg_level_seed(const struct LgLevel *level);
+
+/**
+ * Number of rooms placed (0 for a null handle).
+ */
+uint32_t lg_level_room_count(const struct LgLevel *level);
+
+/**
+ * Copy the ASCII tile grid as Unicode code points, row-major.
+ *
+ * Returns the number of cells in the level (`width * height`). At most
+ * `len` values are written, so call with a null `out` to query the size.
+ */
+size_t lg_level_copy_chars(const struct LgLevel *level, uint32_t *out, size_t len);
+
+/**
+ * Copy marble tile types (`TileType` discriminants), row-major.
+ *
+ * Non-marble levels report every cell as `Empty` (0). Returns the number of
+ * cells; see `lg_level_copy_chars` for the buffer contract.
+ */
+size_t lg_level_copy_tile_types(const struct LgLevel *level, uint8_t *out, size_t len);
+
+/**
+ * Copy marble tile rotations (0-3), row-major. Returns the number of cells.
+ */
+size_t lg_level_copy_rotations(const struct LgLevel *level, uint8_t *out, size_t len);
+
+/**
+ * Copy tile elevations (marble or overworld), row-major. Returns the number of cells.
+ */
+size_t lg_level_copy_elevations(const struct LgLevel *level, int32_t *out, size_t len);
+
+/**
+ * Copy overworld terrain types (0 grass, 1 rock, 2 water, 3 path), row-major.
+ *
+ * Non-overworld levels report every cell as 0. Returns the number of cells.
+ */
+size_t lg_level_copy_terrain(const struct LgLevel *level, uint8_t *out, size_t len);
+
+/**
+ * Serialize the level to a JSON string. Free it with `lg_string_free`.
+ * Returns null for a null handle or a level that fails to serialize.
+ */
+char *lg_level_to_json(const struct LgLevel *level);
+
+/**
+ * Release a string returned by this library. Passing null is a no-op.
+ */
+void lg_string_free(char *s);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* LEVEL_GENERATOR_H */
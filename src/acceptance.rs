@@ -0,0 +1,225 @@
+//! Acceptance constraints checked against finished levels.
+//!
+//! A [`Constraint`] is a condition a level must meet to be kept: a range for
+//! the main path length, the number of junctions, or the elevation drop,
+//! tile types that must appear, or a cap on dead ends. [`generate_matching`]
+//! generates from one seed after another until a level meets all of them,
+//! which replaces filtering seeds by hand. The level it returns records the
+//! seed that produced it.
+//!
+//! Constraints also parse from a short text form, as taken by the CLI's
+//! `--accept`:
+//!
+//! - `path=MIN..MAX`: [`Level::main_path_length`], in tiles
+//! - `junctions=MIN..MAX`: tiles joining three or more ways
+//! - `drop=MIN..MAX`: highest minus lowest passable elevation
+//! - `tiles=NAME+NAME`: marble tile types that must be present
+//! - `max-dead-ends=N`: corridor tiles with a single way out
+//!
+//! Either end of a range may be left out (`path=40..`, `drop=..3`), and a
+//! single number means exactly that many.
+//!
+//! ```
+//! use level_generator::acceptance::{generate_matching, Constraint};
+//! use level_generator::GeneratorParams;
+//!
+//! let constraints: Vec<Constraint> = ["path=30..", "max-dead-ends=4"].iter().map(|c| c.parse().unwrap()).collect();
+//! let level = generate_matching(&GeneratorParams { seed: Some(1), ..Default::default() }, &constraints, 50).unwrap();
+//! assert!(constraints.iter().all(|c| c.check(&level)));
+//! ```
+
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use rand::Rng;
+
+use crate::dead_ends::dead_ends;
+use crate::dungeon::{generate, is_walkable_char, GeneratorParams, Level};
+use crate::seeds::{stage_seed, SeedStage};
+use crate::tiles::TileType;
+
+/// A condition on a finished level; see the module docs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Constraint {
+    /// Main path length in tiles; levels without a path fail
+    PathLength(RangeInclusive<u32>),
+    /// Junctions: marble tiles with three or more connections, or corridor
+    /// tiles outside rooms with three or more walkable neighbors
+    Junctions(RangeInclusive<u32>),
+    /// Highest minus lowest passable elevation (0 on flat levels)
+    ElevationDrop(RangeInclusive<u32>),
+    /// Marble tile types that must each appear at least once
+    RequiredTiles(Vec<TileType>),
+    /// Most dead ends allowed (see [`dead_ends`])
+    MaxDeadEnds(u32),
+}
+
+impl Constraint {
+    /// Whether `level` meets the constraint.
+    pub fn check(&self, level: &Level) -> bool {
+        match self {
+            Constraint::PathLength(range) => level.main_path_length().is_some_and(|length| range.contains(&length)),
+            Constraint::Junctions(range) => range.contains(&junction_count(level)),
+            Constraint::ElevationDrop(range) => {
+                let drop = level.stats.elevation_max.zip(level.stats.elevation_min).map_or(0, |(max, min)| (max - min) as u32);
+                range.contains(&drop)
+            }
+            Constraint::RequiredTiles(types) => match &level.marble_tiles {
+                Some(tiles) => types.iter().all(|t| tiles.iter().flatten().any(|tile| tile.tile_type == *t)),
+                None => types.is_empty(),
+            },
+            Constraint::MaxDeadEnds(max) => {
+                let grid: Vec<Vec<char>> = level.ascii_tiles().iter().map(|row| row.chars().collect()).collect();
+                dead_ends(&grid, &level.rooms, level.topology).len() <= *max as usize
+            }
+        }
+    }
+}
+
+/// Junctions as counted by [`Constraint::Junctions`].
+pub(crate) fn junction_count(level: &Level) -> u32 {
+    junction_tiles(level).len() as u32
+}
+
+/// Tiles counted by [`Constraint::Junctions`], as `(x, y)` in row order.
+pub(crate) fn junction_tiles(level: &Level) -> Vec<(i32, i32)> {
+    if let Some(tiles) = &level.marble_tiles {
+        return (0..tiles.len())
+            .flat_map(|y| (0..tiles[y].len()).map(move |x| (x, y)))
+            .filter(|&(x, y)| tiles[y][x].tile_type.is_passable() && tiles[y][x].connections().len() >= 3)
+            .map(|(x, y)| (x as i32, y as i32))
+            .collect();
+    }
+    let grid: Vec<Vec<char>> = level.ascii_tiles().iter().map(|row| row.chars().collect()).collect();
+    let walkable = |x: i32, y: i32| y >= 0 && x >= 0 && grid.get(y as usize).and_then(|row| row.get(x as usize)).is_some_and(|&c| is_walkable_char(c));
+    let in_room = |x: i32, y: i32| level.rooms.iter().any(|r| r.contains(x, y));
+    (0..grid.len() as i32)
+        .flat_map(|y| (0..grid[y as usize].len() as i32).map(move |x| (x, y)))
+        .filter(|&(x, y)| walkable(x, y) && !in_room(x, y))
+        .filter(|&(x, y)| level.topology.neighbors(x, y).into_iter().filter(|&(nx, ny)| walkable(nx, ny)).count() >= 3)
+        .collect()
+}
+
+fn parse_range(text: &str) -> Result<RangeInclusive<u32>, String> {
+    let number = |n: &str, default: u32| if n.trim().is_empty() { Ok(default) } else { n.trim().parse::<u32>().map_err(|_| format!("invalid number: {}", n)) };
+    match text.split_once("..") {
+        Some((min, max)) => Ok(number(min, 0)?..=number(max, u32::MAX)?),
+        None => {
+            let n = number(text, 0)?;
+            Ok(n..=n)
+        }
+    }
+}
+
+impl FromStr for Constraint {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, value) = s.split_once('=').ok_or_else(|| format!("invalid constraint: {} (expected NAME=VALUE)", s))?;
+        match name.trim().to_ascii_lowercase().as_str() {
+            "path" => Ok(Constraint::PathLength(parse_range(value)?)),
+            "junctions" => Ok(Constraint::Junctions(parse_range(value)?)),
+            "drop" => Ok(Constraint::ElevationDrop(parse_range(value)?)),
+            "tiles" => value
+                .split('+')
+                .map(|name| {
+                    TileType::ALL.iter().copied().find(|t| format!("{:?}", t).eq_ignore_ascii_case(name.trim())).ok_or_else(|| format!("unknown tile type: {}", name))
+                })
+                .collect::<Result<_, _>>()
+                .map(Constraint::RequiredTiles),
+            "max-dead-ends" => value.trim().parse().map(Constraint::MaxDeadEnds).map_err(|_| format!("invalid number: {}", value)),
+            other => Err(format!("unknown constraint: {} (expected path|junctions|drop|tiles|max-dead-ends)", other)),
+        }
+    }
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let range = |f: &mut fmt::Formatter<'_>, name: &str, range: &RangeInclusive<u32>| match (*range.start(), *range.end()) {
+            (min, max) if min == max => write!(f, "{}={}", name, min),
+            (min, u32::MAX) => write!(f, "{}={}..", name, min),
+            (min, max) => write!(f, "{}={}..{}", name, min, max),
+        };
+        match self {
+            Constraint::PathLength(r) => range(f, "path", r),
+            Constraint::Junctions(r) => range(f, "junctions", r),
+            Constraint::ElevationDrop(r) => range(f, "drop", r),
+            Constraint::RequiredTiles(types) => {
+                write!(f, "tiles={}", types.iter().map(|t| format!("{:?}", t)).collect::<Vec<_>>().join("+"))
+            }
+            Constraint::MaxDeadEnds(max) => write!(f, "max-dead-ends={}", max),
+        }
+    }
+}
+
+/// Error from [`generate_matching`]: no level met every constraint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoMatchingLevel {
+    /// Levels generated
+    pub attempts: u32,
+    /// How many levels failed each constraint, in the order given
+    pub failures: Vec<(Constraint, u32)>,
+}
+
+impl fmt::Display for NoMatchingLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no level in {} attempts met every constraint (failures:", self.attempts)?;
+        for (constraint, count) in &self.failures {
+            write!(f, " {} {}x", constraint, count)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl std::error::Error for NoMatchingLevel {}
+
+/// Generate from `params` until a level meets every constraint, trying up
+/// to `max_attempts` seeds. The first seed is `params.seed` (or a random
+/// one); each next seed derives from the last, as for `min_path_length`.
+pub fn generate_matching(params: &GeneratorParams, constraints: &[Constraint], max_attempts: u32) -> Result<Level, NoMatchingLevel> {
+    let mut seed = params.seed.unwrap_or_else(|| rand::rng().random());
+    let mut failures: Vec<(Constraint, u32)> = constraints.iter().map(|c| (c.clone(), 0)).collect();
+    for _ in 0..max_attempts {
+        let level = generate(&GeneratorParams { seed: Some(seed), ..params.clone() });
+        let mut met = true;
+        for (constraint, count) in failures.iter_mut() {
+            if !constraint.check(&level) {
+                *count += 1;
+                met = false;
+            }
+        }
+        if met {
+            return Ok(level);
+        }
+        seed = stage_seed(seed, SeedStage::Retry);
+    }
+    Err(NoMatchingLevel { attempts: max_attempts, failures })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::GenerationMode;
+
+    #[test]
+    fn constraints_parse_and_select_levels() {
+        for text in ["path=30..60", "junctions=2..", "drop=3", "tiles=Bridge+LoopDeLoop", "max-dead-ends=0"] {
+            assert_eq!(text.parse::<Constraint>().unwrap().to_string(), text);
+        }
+        assert_eq!("drop=..3".parse::<Constraint>(), Ok(Constraint::ElevationDrop(0..=3)));
+        assert!("tiles=Nope".parse::<Constraint>().is_err());
+        assert!("width=3".parse::<Constraint>().is_err());
+
+        let params = GeneratorParams { seed: Some(2), mode: GenerationMode::Marble, enable_elevation: true, ..Default::default() };
+        let constraints = vec![Constraint::Junctions(1..=u32::MAX), Constraint::ElevationDrop(2..=u32::MAX)];
+        let level = generate_matching(&params, &constraints, 50).unwrap();
+        assert!(constraints.iter().all(|c| c.check(&level)));
+        // The recorded seed reproduces the level directly
+        let again = generate(&GeneratorParams { seed: Some(level.seed), ..params.clone() });
+        assert_eq!(again.tiles, level.tiles);
+
+        let err = generate_matching(&params, &[Constraint::PathLength(10_000..=10_000), Constraint::MaxDeadEnds(u32::MAX)], 3).unwrap_err();
+        assert_eq!(err.attempts, 3);
+        assert_eq!(err.failures.iter().map(|(_, n)| *n).collect::<Vec<_>>(), [3, 0]);
+    }
+}
@@ -0,0 +1,177 @@
+//! Keeping levels to the marble tile types an engine implements.
+//!
+//! [`GeneratorParams::allowed_tile_types`](crate::dungeon::GeneratorParams::allowed_tile_types)
+//! names the tile types a level may contain. After every generation pass,
+//! [`Level::restrict_tile_types`] swaps each other tile for the closest
+//! allowed one, so the guarantee holds whichever heuristics placed them:
+//!
+//! - The swap keeps the tile's height and connects every side the tile or
+//!   its neighbor linked through, picking among `Straight`, `Curve90`,
+//!   `TJunction`, `YJunction`, `Merge`, `CrossJunction`, and `OpenPlatform`
+//!   the one with the fewest extra openings. `Slope` comes first where a
+//!   linked neighbor sits one level up or down, so a loop-de-loop or half-pipe
+//!   on a ramp becomes a slope and one on the flat a straight.
+//! - Obstacles go away when `Obstacle` is not allowed, giving back the tiles
+//!   they covered, and structures (spiral ramps, funnels) go away when any
+//!   of their tiles is swapped.
+//! - Voxels on the swapped tiles follow them; stacked voxels of other types
+//!   (bridge decks, spiral columns) are removed.
+//! - A tile with no allowed stand-in at all becomes wall, which may cut the
+//!   track; allow at least the basic pieces.
+//!
+//! Walls, anchors, and stats are worked out again. Levels without marble
+//! tiles are left alone.
+//!
+//! ```
+//! use std::collections::HashSet;
+//! use level_generator::{generate, GenerationMode, GeneratorParams, TileType};
+//!
+//! let allowed: HashSet<TileType> = TileType::ALL.into_iter().filter(|t| !matches!(t, TileType::LoopDeLoop | TileType::HalfPipe)).collect();
+//! let level = generate(&GeneratorParams { seed: Some(5), mode: GenerationMode::Marble, allowed_tile_types: Some(allowed.clone()), ..Default::default() });
+//! assert!(level.marble_tiles.unwrap().iter().flatten().all(|t| allowed.contains(&t.tile_type)));
+//! ```
+
+use std::collections::HashSet;
+
+use crate::anchors::add_anchors;
+use crate::dungeon::{Level, TILE_WALL};
+use crate::obstacles::remove_obstacles;
+use crate::tiles::{compute_walls, Direction, MarbleTile, TileType};
+use crate::voxel::VoxelMap;
+
+/// Stand-ins for a disallowed tile, most preferred first.
+const STAND_INS: [TileType; 7] = [
+    TileType::Straight,
+    TileType::Curve90,
+    TileType::TJunction,
+    TileType::YJunction,
+    TileType::Merge,
+    TileType::CrossJunction,
+    TileType::OpenPlatform,
+];
+
+impl Level {
+    /// Swap every marble tile whose type is not in `allowed` for the closest
+    /// allowed type; see the module docs.
+    pub fn restrict_tile_types(&mut self, allowed: &HashSet<TileType>) {
+        let Some(tiles) = self.marble_tiles.as_mut() else { return };
+        if !allowed.contains(&TileType::Obstacle) {
+            remove_obstacles(tiles, &self.obstacles);
+            self.obstacles.clear();
+        }
+        let permitted = |tile: &MarbleTile| tile.tile_type == TileType::Empty || allowed.contains(&tile.tile_type);
+        if tiles.iter().flatten().all(permitted) {
+            return;
+        }
+
+        let before = tiles.clone();
+        let mut swapped = Vec::new();
+        for (y, row) in before.iter().enumerate() {
+            for (x, tile) in row.iter().enumerate() {
+                if permitted(tile) {
+                    continue;
+                }
+                let (x, y) = (x as i32, y as i32);
+                let stand_in = stand_in(&before, x, y, allowed);
+                // Lean levels read their walls off the marble tiles
+                if let Some(row) = self.tiles.get_mut(y as usize).filter(|_| stand_in.tile_type == TileType::Empty) {
+                    let mut chars: Vec<char> = row.chars().collect();
+                    chars[x as usize] = TILE_WALL;
+                    *row = chars.into_iter().collect();
+                }
+                tiles[y as usize][x as usize] = stand_in;
+                swapped.push((x, y));
+            }
+        }
+        self.structures.retain(|s| !s.tiles.iter().any(|at| swapped.contains(at)));
+        compute_walls(tiles);
+        add_anchors(tiles, &self.obstacles);
+
+        if let Some(voxels) = &self.voxels {
+            let mut map = VoxelMap::new(self.width, self.height);
+            for (x, y, z, voxel) in voxels.iter() {
+                let ground = &tiles[y as usize][x as usize];
+                if permitted(voxel) {
+                    map.insert(x, y, z, voxel.clone());
+                } else if ground.elevation == z && ground.tile_type != TileType::Empty {
+                    map.insert(x, y, z, ground.clone());
+                }
+            }
+            self.voxels = Some(map);
+        }
+        self.refresh_stats();
+    }
+}
+
+/// The allowed tile to put at `(x, y)` of `tiles` in place of the one there.
+fn stand_in(tiles: &[Vec<MarbleTile>], x: i32, y: i32, allowed: &HashSet<TileType>) -> MarbleTile {
+    let tile = &tiles[y as usize][x as usize];
+    let neighbor = |d: Direction| {
+        let (dx, dy) = d.to_delta();
+        tiles.get((y + dy) as usize).and_then(|row| row.get((x + dx) as usize)).filter(|n| n.tile_type.is_passable())
+    };
+    // Sides linked from either end
+    let linked: Vec<Direction> = Direction::ALL.into_iter().filter(|&d| neighbor(d).is_some_and(|n| tile.connects(d) || n.connects(d.opposite()))).collect();
+    let ramp = linked.iter().any(|&d| neighbor(d).is_some_and(|n| (n.elevation - tile.elevation).abs() == 1));
+
+    let slope = (ramp && allowed.contains(&TileType::Slope)).then_some(TileType::Slope);
+    let candidates = slope.into_iter().chain(STAND_INS.into_iter().filter(|t| allowed.contains(t)));
+    // Cover the most linked sides with the fewest extra openings
+    let best = candidates
+        .enumerate()
+        .flat_map(|(rank, tile_type)| (0..4u8).map(move |rotation| (rank, tile_type, rotation)))
+        .min_by_key(|&(rank, tile_type, rotation)| {
+            let opens: Vec<Direction> = tile_type.base_connections().into_iter().map(|d| d.rotate(rotation)).collect();
+            let missed = linked.iter().filter(|d| !opens.contains(d)).count();
+            (missed, opens.len() - (linked.len() - missed), rank, rotation)
+        });
+    match best {
+        Some((_, tile_type, rotation)) => MarbleTile::with_params(tile_type, tile.elevation, rotation, tile_type.has_default_walls()),
+        None => MarbleTile::empty(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams};
+
+    #[test]
+    fn disallowed_types_are_swapped_for_connecting_stand_ins() {
+        let params = GeneratorParams {
+            seed: Some(9),
+            width: 60,
+            height: 30,
+            mode: GenerationMode::Marble,
+            enable_elevation: true,
+            enable_obstacles: true,
+            enable_funnels: true,
+            enable_voxels: true,
+            ..Default::default()
+        };
+        let full = generate(&params);
+        let used: HashSet<TileType> = full.marble_tiles.as_ref().unwrap().iter().flatten().map(|t| t.tile_type).collect();
+        let banned = [TileType::LoopDeLoop, TileType::HalfPipe, TileType::Obstacle, TileType::Funnel, TileType::Merge];
+        assert!(banned.iter().filter(|t| used.contains(t)).count() >= 2, "{:?}", used);
+
+        let allowed: HashSet<TileType> = TileType::ALL.into_iter().filter(|t| !banned.contains(t)).collect();
+        let level = generate(&GeneratorParams { allowed_tile_types: Some(allowed.clone()), ..params });
+        let tiles = level.marble_tiles.as_ref().unwrap();
+        assert!(tiles.iter().flatten().all(|t| t.tile_type == TileType::Empty || allowed.contains(&t.tile_type)));
+        assert!(level.voxels.as_ref().unwrap().iter().all(|(_, _, _, t)| allowed.contains(&t.tile_type)));
+        assert!(level.obstacles.is_empty() && level.structures.iter().all(|s| s.tiles.iter().all(|&(x, y)| allowed.contains(&tiles[y as usize][x as usize].tile_type))));
+        assert!(level.check_invariants().is_empty(), "{:?}", level.check_invariants());
+        assert!(!level.stats.tile_histogram.keys().any(|k| banned.iter().any(|b| format!("{:?}", b) == *k)));
+
+        // A loop on the flat becomes a straight with the same heading; on a
+        // ramp, a slope
+        let only = |types: &[TileType]| types.iter().copied().collect::<HashSet<_>>();
+        let mut row = vec![MarbleTile::with_params(TileType::Straight, 0, 1, true); 3];
+        row[1] = MarbleTile::with_params(TileType::LoopDeLoop, 0, 1, true);
+        let swapped = stand_in(&[row.clone()], 1, 0, &only(&[TileType::Straight, TileType::Slope]));
+        assert_eq!((swapped.tile_type, swapped.rotation), (TileType::Straight, 1));
+        row[2].elevation = 1;
+        assert_eq!(stand_in(&[row.clone()], 1, 0, &only(&[TileType::Straight, TileType::Slope])).tile_type, TileType::Slope);
+        assert_eq!(stand_in(&[row], 1, 0, &only(&[])).tile_type, TileType::Empty);
+    }
+}
@@ -0,0 +1,221 @@
+//! Ambience zones for driving audio from the export.
+//!
+//! [`Level::ambience_zones`] lays sound regions over the walkable tiles:
+//!
+//! - a base bed per zone (or one for the whole level without zones):
+//!   [`Ambience::Cave`] drips and echo underground, [`Ambience::Outdoors`] on
+//!   levels with terrain or plazas
+//! - [`Ambience::Wind`] on tiles at least [`WIND_HEIGHT`] steps above the
+//!   lowest walkable tile
+//! - [`Ambience::Rumble`] within [`RUMBLE_RADIUS`] tiles of obstacles and
+//!   moving hazards
+//! - [`Ambience::Water`] within [`WATER_RADIUS`] tiles of water or a river
+//!
+//! Base beds partition the floor; the other kinds are layered on top and
+//! may overlap them and each other. Each zone comes as polygons on tile
+//! corners (`(0, 0)` is the top-left corner of the map, `y` grows south):
+//! rings run clockwise on screen around the covered area and
+//! counterclockwise around holes in it, and tiles touching only at a
+//! corner get separate rings. [`ExportOptions::ambience`] adds the zones to
+//! the JSON.
+//!
+//! [`ExportOptions::ambience`]: crate::export::ExportOptions::ambience
+//!
+//! ```
+//! use level_generator::ambience::Ambience;
+//! use level_generator::{generate, GeneratorParams};
+//!
+//! let level = generate(&GeneratorParams { seed: Some(1), ..Default::default() });
+//! let zones = level.ambience_zones();
+//! assert_eq!(zones[0].ambience, Ambience::Cave);
+//! assert!(!zones[0].polygons.is_empty());
+//! ```
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dungeon::{is_walkable_char, Level, TILE_WATER};
+use crate::visibility::tile_elevation;
+
+/// Steps above the lowest walkable tile from which wind blows
+pub const WIND_HEIGHT: i32 = 2;
+/// Reach of the rumble around obstacles and moving hazards, in tiles
+pub const RUMBLE_RADIUS: i32 = 3;
+/// Reach of water sounds, in tiles
+pub const WATER_RADIUS: i32 = 2;
+
+/// Kind of sound a zone plays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Ambience {
+    /// Underground bed: drips and echo
+    Cave,
+    /// Open-air bed: breeze and birds
+    Outdoors,
+    /// Wind on high ground
+    Wind,
+    /// Low rumble near obstacles and moving hazards
+    Rumble,
+    /// Flowing or lapping water
+    Water,
+}
+
+/// One ambience region; see the module docs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AmbienceZone {
+    pub ambience: Ambience,
+    /// Zone id (in `Level::zones`) of a base bed on a zoned level
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone: Option<usize>,
+    /// Tiles covered
+    pub tile_count: usize,
+    /// Outline rings as tile-corner points
+    pub polygons: Vec<Vec<(i32, i32)>>,
+}
+
+/// Outline rings of the `true` cells of `mask`, wound as in the module docs.
+pub fn outline(mask: &[Vec<bool>]) -> Vec<Vec<(i32, i32)>> {
+    let inside = |x: i32, y: i32| x >= 0 && y >= 0 && mask.get(y as usize).and_then(|row| row.get(x as usize)).copied().unwrap_or(false);
+    // Boundary edges, each with the covered cell on its right
+    let mut edges: BTreeMap<(i32, i32), Vec<(i32, i32)>> = BTreeMap::new();
+    for (y, row) in mask.iter().enumerate() {
+        for (x, _) in row.iter().enumerate().filter(|(_, &m)| m) {
+            let (x, y) = (x as i32, y as i32);
+            let sides = [
+                ((0, -1), (x, y), (x + 1, y)),
+                ((1, 0), (x + 1, y), (x + 1, y + 1)),
+                ((0, 1), (x + 1, y + 1), (x, y + 1)),
+                ((-1, 0), (x, y + 1), (x, y)),
+            ];
+            for ((dx, dy), from, to) in sides {
+                if !inside(x + dx, y + dy) {
+                    edges.entry(from).or_default().push(to);
+                }
+            }
+        }
+    }
+
+    let mut rings = Vec::new();
+    while let Some((&start, _)) = edges.iter().find(|(_, ends)| !ends.is_empty()) {
+        let mut ring = vec![start];
+        let mut at = edges.get_mut(&start).and_then(Vec::pop).expect("found above");
+        let mut heading = (at.0 - start.0, at.1 - start.1);
+        while at != start {
+            ring.push(at);
+            let ends = edges.get_mut(&at).expect("boundary edges form closed rings");
+            // Where two rings touch at a corner, turn right to stay on this one
+            let (right, left) = ((-heading.1, heading.0), (heading.1, -heading.0));
+            let pick = [right, heading, left]
+                .iter()
+                .find_map(|&(dx, dy)| ends.iter().position(|&e| e == (at.0 + dx, at.1 + dy)))
+                .unwrap_or(0);
+            let next = ends.swap_remove(pick);
+            heading = (next.0 - at.0, next.1 - at.1);
+            at = next;
+        }
+        edges.retain(|_, ends| !ends.is_empty());
+        // Keep only the corners
+        let n = ring.len();
+        let corners: Vec<(i32, i32)> = (0..n)
+            .filter(|&i| {
+                let (p, c, q) = (ring[(i + n - 1) % n], ring[i], ring[(i + 1) % n]);
+                (c.0 - p.0, c.1 - p.1) != (q.0 - c.0, q.1 - c.1)
+            })
+            .map(|i| ring[i])
+            .collect();
+        rings.push(corners);
+    }
+    rings
+}
+
+impl Level {
+    /// Ambience zones over the walkable tiles; see the module docs.
+    pub fn ambience_zones(&self) -> Vec<AmbienceZone> {
+        let grid: Vec<Vec<char>> = self.ascii_tiles().iter().map(|row| row.chars().collect()).collect();
+        let walkable = |x: usize, y: usize| is_walkable_char(grid[y][x]);
+        let near = |x: usize, y: usize, radius: i32, hit: &dyn Fn(i32, i32) -> bool| {
+            (-radius..=radius).any(|dy| (-radius..=radius).any(|dx| hit(x as i32 + dx, y as i32 + dy)))
+        };
+        let mut zones = Vec::new();
+        let mut push = |ambience: Ambience, zone: Option<usize>, member: &dyn Fn(usize, usize) -> bool| {
+            let mask: Vec<Vec<bool>> = grid.iter().enumerate().map(|(y, row)| (0..row.len()).map(|x| walkable(x, y) && member(x, y)).collect()).collect();
+            let tile_count = mask.iter().flatten().filter(|&&m| m).count();
+            if tile_count > 0 {
+                zones.push(AmbienceZone { ambience, zone, tile_count, polygons: outline(&mask) });
+            }
+        };
+
+        let base = if self.terrain.is_some() || !self.plazas.is_empty() { Ambience::Outdoors } else { Ambience::Cave };
+        match &self.zone_map {
+            Some(zone_map) if !self.zones.is_empty() => {
+                for zone in &self.zones {
+                    push(base, Some(zone.id), &|x, y| zone_map[y][x] == zone.id as i32);
+                }
+            }
+            _ => push(base, None, &|_, _| true),
+        }
+
+        let lowest = (0..grid.len()).flat_map(|y| (0..grid[y].len()).map(move |x| (x, y))).filter(|&(x, y)| walkable(x, y)).map(|(x, y)| tile_elevation(self, x as i32, y as i32)).min();
+        if let Some(lowest) = lowest {
+            push(Ambience::Wind, None, &|x, y| tile_elevation(self, x as i32, y as i32) - lowest >= WIND_HEIGHT);
+        }
+
+        let hazard = |x: i32, y: i32| self.obstacles.iter().any(|o| o.tiles.contains(&(x, y))) || self.dynamics.iter().any(|d| (d.x, d.y) == (x, y));
+        push(Ambience::Rumble, None, &|x, y| near(x, y, RUMBLE_RADIUS, &hazard));
+
+        let water = |x: i32, y: i32| {
+            x >= 0
+                && y >= 0
+                && (grid.get(y as usize).and_then(|row| row.get(x as usize)) == Some(&TILE_WATER)
+                    || self.river_map.as_ref().and_then(|map| map.get(y as usize)?.get(x as usize)).is_some_and(|&river| river >= 0))
+        };
+        push(Ambience::Water, None, &|x, y| near(x, y, WATER_RADIUS, &water));
+        zones
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams};
+    use crate::zones::ZoneStrategy;
+
+    /// Twice the signed area (positive for clockwise rings on screen)
+    fn area2(ring: &[(i32, i32)]) -> i32 {
+        (0..ring.len()).map(|i| ring[i].0 * ring[(i + 1) % ring.len()].1 - ring[(i + 1) % ring.len()].0 * ring[i].1).sum()
+    }
+
+    #[test]
+    fn outlines_cover_the_mask_with_holes_and_split_corners() {
+        // A ring of tiles with a hole, plus a tile touching it only at a corner
+        let mask: Vec<Vec<bool>> = ["###.", "#.#.", "###.", "...#"].iter().map(|row| row.chars().map(|c| c == '#').collect()).collect();
+        let mut rings = outline(&mask);
+        rings.sort_by_key(|ring| -area2(ring).abs());
+        assert_eq!(rings, vec![vec![(0, 0), (3, 0), (3, 3), (0, 3)], vec![(1, 1), (1, 2), (2, 2), (2, 1)], vec![(3, 3), (4, 3), (4, 4), (3, 4)]]);
+        assert_eq!(rings.iter().map(|r| area2(r)).sum::<i32>() / 2, 9);
+
+        let params = GeneratorParams {
+            seed: Some(5),
+            mode: GenerationMode::Marble,
+            enable_elevation: true,
+            max_elevation: 3,
+            enable_obstacles: true,
+            zoning: ZoneStrategy::DistanceBands { band_width: 12 },
+            ..Default::default()
+        };
+        let level = generate(&params);
+        let zones = level.ambience_zones();
+        let beds: Vec<&AmbienceZone> = zones.iter().filter(|z| z.ambience == Ambience::Cave).collect();
+        assert_eq!(beds.len(), level.zones.len());
+        let floor = level.zones.iter().map(|z| z.tile_count).sum::<usize>();
+        assert_eq!(beds.iter().map(|z| z.tile_count).sum::<usize>(), floor);
+        for zone in &zones {
+            let area: i32 = zone.polygons.iter().map(|r| area2(r)).sum::<i32>() / 2;
+            assert_eq!(area as usize, zone.tile_count);
+        }
+        assert!(zones.iter().any(|z| z.ambience == Ambience::Rumble));
+        assert!(zones.iter().any(|z| z.ambience == Ambience::Wind));
+    }
+}
@@ -0,0 +1,17 @@
+//! Inspecting finished levels: structural checks, statistics, diffs, the
+//! room graph, the path graph of the track network, dead ends, one-way gate
+//! reachability, visibility queries, fingerprints, traffic simulation, run time estimates, and
+//! (through `Level::difficulty`) difficulty scores.
+
+pub use crate::dead_ends::dead_ends;
+pub use crate::diff::{LevelDiff, MarbleTileChange, TileChange};
+pub use crate::duration::{RunEstimate, RunModel};
+pub use crate::fingerprint::Fingerprint;
+pub use crate::gates::Reachability;
+pub use crate::graph::{room_connections, RoomGraph};
+pub use crate::invariants::{InvariantViolation, MAX_SLOPE_DELTA};
+pub use crate::path_graph::{PathEdge, PathGraph, PathNode, PathNodeKind, RouteTag, StraightRun};
+pub use crate::stats::{LevelStats, StageTiming};
+pub use crate::traffic::{Hotspot, JunctionLoad, TrafficConfig, TrafficReport};
+pub use crate::visibility::{blocks_movement, blocks_sight, field_of_view, line_of_sight, tile_elevation};
+pub use crate::zones::{compute_zones, Zone, Zoning};
@@ -0,0 +1,154 @@
+//! Anchor points for placing prefabs on special marble tiles.
+//!
+//! Launch pads, loop-de-loops, and obstacle tiles get an `anchor` entry in
+//! their `metadata` JSON next to whatever else is stored there:
+//!
+//! ```json
+//! {"anchor": {"position": [12.5, 4.5, 1.0], "forward": [1.0, 0.0, 0.0]}}
+//! ```
+//!
+//! Coordinates are continuous tile units: `x` grows east and `y` south as in
+//! the grid, `z` is the elevation, and a tile's center is `(x + 0.5,
+//! y + 0.5)`. `forward` is a unit vector: the launch direction of a pad
+//! (toward the track it feeds, tilted by the height difference to it), the
+//! track direction of a loop (toward the side with the larger height
+//! change, which the loop climbs or drops to), and for obstacles the long
+//! axis of a wall segment or north for every other shape. Engines can place
+//! a prefab at `position` facing `forward` without re-deriving the geometry.
+//!
+//! Generated and imported marble levels carry anchors; after editing tiles
+//! by hand, [`add_anchors`] brings them up to date.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::obstacles::{Obstacle, ObstacleShape};
+use crate::tiles::{Direction, MarbleTile, TileType};
+
+/// Where and facing which way a prefab goes; see the module docs.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Anchor {
+    /// `[x, y, z]` in tile units
+    pub position: [f32; 3],
+    /// Unit vector `[x, y, z]`
+    pub forward: [f32; 3],
+}
+
+fn unit(x: f32, y: f32, z: f32) -> [f32; 3] {
+    let length = (x * x + y * y + z * z).sqrt();
+    if length == 0.0 {
+        [0.0, -1.0, 0.0]
+    } else {
+        [x / length, y / length, z / length]
+    }
+}
+
+/// Anchor of the tile at `(x, y)`, or `None` for tiles without one.
+/// `obstacles` gives the shape of obstacle tiles.
+pub fn tile_anchor(tiles: &[Vec<MarbleTile>], obstacles: &[Obstacle], x: usize, y: usize) -> Option<Anchor> {
+    let tile = tiles.get(y)?.get(x)?;
+    let neighbor = |d: Direction| {
+        let (dx, dy) = d.to_delta();
+        tiles.get((y as i32 + dy) as usize)?.get((x as i32 + dx) as usize).filter(|t| t.tile_type.is_passable())
+    };
+    let horizontal = |d: Direction| {
+        let (dx, dy) = d.to_delta();
+        (dx as f32, dy as f32)
+    };
+    let forward = match tile.tile_type {
+        TileType::LaunchPad => {
+            // The pad sits at the closed end of a straight run; launch along its axis into the run
+            let axis = tile.connections().first().copied().unwrap_or(Direction::North);
+            let (d, next) = [axis, axis.opposite()].into_iter().find_map(|d| Some((d, neighbor(d)?))).unwrap_or((axis, tile));
+            let (dx, dy) = horizontal(d);
+            unit(dx, dy, (next.elevation - tile.elevation) as f32)
+        }
+        TileType::LoopDeLoop => {
+            let axis = tile.connections().first().copied().unwrap_or(Direction::North);
+            let change = |d: Direction| neighbor(d).map_or(0, |n| (n.elevation - tile.elevation).abs());
+            let d = if change(axis.opposite()) > change(axis) { axis.opposite() } else { axis };
+            let (dx, dy) = horizontal(d);
+            unit(dx, dy, 0.0)
+        }
+        TileType::Obstacle => {
+            let shape = obstacles.iter().find(|o| o.tiles.contains(&(x as i32, y as i32)));
+            match shape {
+                Some(o) if o.shape == ObstacleShape::WallSegment && o.tiles.iter().all(|t| t.1 == o.tiles[0].1) => [1.0, 0.0, 0.0],
+                Some(o) if o.shape == ObstacleShape::WallSegment => [0.0, 1.0, 0.0],
+                _ => [0.0, -1.0, 0.0],
+            }
+        }
+        _ => return None,
+    };
+    Some(Anchor { position: [x as f32 + 0.5, y as f32 + 0.5, tile.elevation as f32], forward })
+}
+
+/// Write the anchor of every launch pad, loop, and obstacle tile into its
+/// metadata, replacing any earlier anchor and keeping the other entries.
+pub fn add_anchors(tiles: &mut [Vec<MarbleTile>], obstacles: &[Obstacle]) {
+    for y in 0..tiles.len() {
+        for x in 0..tiles[y].len() {
+            let Some(anchor) = tile_anchor(tiles, obstacles, x, y) else { continue };
+            let tile = &mut tiles[y][x];
+            let mut fields = match serde_json::from_str::<Value>(&tile.metadata) {
+                Ok(Value::Object(fields)) => fields,
+                _ => Map::new(),
+            };
+            fields.insert("anchor".into(), serde_json::to_value(anchor).expect("anchors always serialize"));
+            tile.metadata = Value::Object(fields).to_string();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams, Level};
+    use crate::marble::AdvancedTileConfig;
+
+    fn anchor_of(tile: &MarbleTile) -> Option<Anchor> {
+        let meta: Value = serde_json::from_str(&tile.metadata).ok()?;
+        serde_json::from_value(meta.get("anchor")?.clone()).ok()
+    }
+
+    #[test]
+    fn special_tiles_carry_anchors_in_their_metadata() {
+        let params = GeneratorParams { seed: Some(5), mode: GenerationMode::Marble, enable_obstacles: true, ..Default::default() };
+        let level = generate(&params);
+        let tiles = level.marble_tiles.as_ref().unwrap();
+        for (y, row) in tiles.iter().enumerate() {
+            for (x, tile) in row.iter().enumerate() {
+                let special = matches!(tile.tile_type, TileType::LaunchPad | TileType::LoopDeLoop | TileType::Obstacle);
+                assert_eq!(anchor_of(tile).is_some(), special);
+                if let Some(anchor) = anchor_of(tile) {
+                    assert_eq!(anchor.position, [x as f32 + 0.5, y as f32 + 0.5, tile.elevation as f32]);
+                    assert!((anchor.forward.iter().map(|v| v * v).sum::<f32>() - 1.0).abs() < 1e-5);
+                }
+            }
+        }
+        // Obstacle tiles keep their id next to the anchor
+        let (x, y) = level.obstacles[0].tiles[0];
+        assert!(tiles[y as usize][x as usize].metadata.starts_with(&format!("{{\"obstacle\":{},\"anchor\":", level.obstacles[0].id)));
+
+        // A pad at the closed end of a corridor launches into it, tilted
+        // toward the next tile; a loop faces the side it climbs to
+        let level = Level::from_ascii_with("#######\n#....##\n#######", AdvancedTileConfig::none()).unwrap();
+        let mut tiles = level.marble_tiles.unwrap();
+        tiles[1][1] = MarbleTile::with_params(TileType::LaunchPad, 0, 3, true);
+        tiles[1][2].elevation = 1;
+        tiles[1][3] = MarbleTile::with_params(TileType::LoopDeLoop, 1, 1, true);
+        tiles[1][4].elevation = 3;
+        add_anchors(&mut tiles, &[]);
+        let pad = anchor_of(&tiles[1][1]).unwrap();
+        assert_eq!(pad.position, [1.5, 1.5, 0.0]);
+        assert!((pad.forward[0] - 0.5f32.sqrt()).abs() < 1e-6 && (pad.forward[2] - 0.5f32.sqrt()).abs() < 1e-6);
+        assert_eq!(anchor_of(&tiles[1][3]).unwrap().forward, [1.0, 0.0, 0.0]);
+
+        // Running the pass again replaces the anchors
+        tiles[1][1].elevation = 1;
+        add_anchors(&mut tiles, &[]);
+        assert_eq!(anchor_of(&tiles[1][1]).unwrap().forward, [1.0, 0.0, 0.0]);
+        assert_eq!(tiles[1][1].metadata.matches("anchor").count(), 1);
+    }
+}
@@ -0,0 +1,188 @@
+//! Banking (superelevation) suggestions for marble curves.
+//!
+//! A 3D track builder cannot tell from a single curve tile how fast a marble
+//! arrives or how wide the turn really is; the generator can. With
+//! `GeneratorParams::enable_banking` set, every turn of the
+//! [channel splines](crate::splines) gets a suggested bank, written into the
+//! `metadata` of the channel tiles around the turn:
+//!
+//! ```json
+//! {"bank_angle": 26.6, "bank_toward": [0.707, -0.707]}
+//! ```
+//!
+//! `bank_angle` is in degrees and `bank_toward` is the unit vector `[x, y]`
+//! pointing to the inside of the turn, the side the track tilts down to.
+//!
+//! The angle is the one at which a marble takes the turn without sliding
+//! sideways, `atan(v² / (g·r))` (see [`bank_angle`]). The radius `r` is that
+//! of the arc fitting the turn between the spline's control points, and the
+//! speed comes from the approach: the straight run into the turn counts as
+//! a gentle incline of [`RUN_UP_GRADE`], plus any drop in elevation along
+//! it. Turns gentler than [`MIN_TURN_DEGREES`] are not banked, and no bank
+//! is steeper than [`MAX_BANK_ANGLE`]. Rooms, obstacles, and empty tiles
+//! are never banked; a tile near two turns keeps the steeper bank.
+//!
+//! Only `+`, `-`, `*`, `/`, and `sqrt` go into a bank, all of them exactly
+//! rounded, so a seed banks its turns the same on every platform: turn
+//! angles come from dot and cross products, and the arctangent from a
+//! polynomial good to a hundredth of a degree.
+//!
+//! After editing tiles by hand, [`Level::add_banking`] brings the banks up
+//! to date.
+
+use serde_json::{Map, Value};
+
+use crate::dungeon::Level;
+use crate::tiles::TileType;
+
+/// Steepest bank suggested, in degrees.
+pub const MAX_BANK_ANGLE: f32 = 45.0;
+/// Smallest change of direction, in degrees, that counts as a turn.
+pub const MIN_TURN_DEGREES: f32 = 20.0;
+/// Incline the straight run-up into a turn counts as, in levels per tile.
+pub const RUN_UP_GRADE: f32 = 0.05;
+
+/// Cosine of [`MIN_TURN_DEGREES`].
+const MIN_TURN_COS: f32 = 0.939_692_6;
+
+/// Bank angle in degrees and the unit vector toward the inside of the turn.
+type Bank = (f32, [f32; 2]);
+
+/// Bank in degrees for a turn of `radius` tiles entered after `approach`
+/// tiles of run-up that drop `drop` levels; see the module docs.
+pub fn bank_angle(radius: f32, approach: f32, drop: f32) -> f32 {
+    // v² / g from the height the marble has rolled down
+    let speed = 2.0 * (RUN_UP_GRADE * approach + drop).max(0.0);
+    atan_degrees(speed / radius.max(0.5)).min(MAX_BANK_ANGLE)
+}
+
+/// `atan(x)` in degrees for `x >= 0`, from a minimax polynomial on `0..1`
+/// rather than the platform's `atan`; 45 degrees and up are never needed.
+fn atan_degrees(x: f32) -> f32 {
+    if x >= 1.0 {
+        return 45.0;
+    }
+    let x2 = x * x;
+    let radians = x * (0.999_866 + x2 * (-0.330_299_5 + x2 * (0.180_141 + x2 * (-0.085_133 + x2 * 0.020_835_1))));
+    radians * (180.0 / std::f32::consts::PI)
+}
+
+impl Level {
+    /// Write the bank of every turn into the metadata of the channel tiles
+    /// around it, replacing earlier banks; see the module docs. Does
+    /// nothing for levels without marble tiles.
+    pub fn add_banking(&mut self) {
+        if self.marble_tiles.is_none() {
+            return;
+        }
+        let mut banks: Vec<(usize, usize, Bank)> = Vec::new();
+        for spline in self.channel_splines() {
+            let reach = (spline.width as f32 / 2.0 + 0.5).max(1.0);
+            for turn in spline.points.windows(3) {
+                let [a, b, c] = [turn[0], turn[1], turn[2]];
+                let (u, v) = ([b[0] - a[0], b[1] - a[1]], [c[0] - b[0], c[1] - b[1]]);
+                let (lu, lv) = ((u[0] * u[0] + u[1] * u[1]).sqrt(), (v[0] * v[0] + v[1] * v[1]).sqrt());
+                if lu == 0.0 || lv == 0.0 {
+                    continue;
+                }
+                let (dot, cross) = (u[0] * v[0] + u[1] * v[1], (u[0] * v[1] - u[1] * v[0]).abs());
+                if dot / (lu * lv) > MIN_TURN_COS {
+                    continue;
+                }
+                // Arc tangent to both legs, meeting the shorter one halfway
+                // along: tan(turn / 2) = sin / (1 + cos), and a U-turn has none
+                let radius = if cross == 0.0 { 0.0 } else { lu.min(lv) / 2.0 * (lu * lv + dot) / cross };
+                let angle = bank_angle(radius, lu, a[2] - b[2]);
+                let inside = [v[0] / lv - u[0] / lu, v[1] / lv - u[1] / lu];
+                let length = (inside[0] * inside[0] + inside[1] * inside[1]).sqrt();
+                if angle < 0.05 || length == 0.0 {
+                    continue;
+                }
+                let toward = [inside[0] / length, inside[1] / length];
+                let (x0, x1) = ((b[0] - reach).floor().max(0.0) as usize, (b[0] + reach) as usize);
+                let (y0, y1) = ((b[1] - reach).floor().max(0.0) as usize, (b[1] + reach) as usize);
+                for y in y0..=y1 {
+                    for x in x0..=x1 {
+                        let (dx, dy) = (x as f32 + 0.5 - b[0], y as f32 + 0.5 - b[1]);
+                        if dx * dx + dy * dy < reach * reach {
+                            banks.push((x, y, (angle, toward)));
+                        }
+                    }
+                }
+            }
+        }
+
+        let in_room = |x: usize, y: usize| self.rooms.iter().any(|r| r.contains(x as i32, y as i32));
+        let mut steepest: Vec<Vec<Option<Bank>>> = vec![vec![None; self.width as usize]; self.height as usize];
+        for (x, y, bank) in banks {
+            if let Some(slot) = steepest.get_mut(y).and_then(|row| row.get_mut(x)) {
+                if slot.is_none_or(|(steeper, _)| bank.0 > steeper) && !in_room(x, y) {
+                    *slot = Some(bank);
+                }
+            }
+        }
+        let Some(tiles) = &mut self.marble_tiles else { return };
+        for (tile, bank) in tiles.iter_mut().flatten().zip(steepest.into_iter().flatten()) {
+            let bankable = tile.tile_type.is_passable() && !matches!(tile.tile_type, TileType::OpenPlatform | TileType::Obstacle);
+            let mut fields = match serde_json::from_str::<Value>(&tile.metadata) {
+                Ok(Value::Object(fields)) => fields,
+                _ if tile.metadata.is_empty() => Map::new(),
+                // Leave metadata that is not a JSON object alone
+                _ => continue,
+            };
+            let had_bank = fields.remove("bank_angle").is_some() | fields.remove("bank_toward").is_some();
+            let bank = bank.filter(|_| bankable);
+            if let Some((angle, toward)) = bank {
+                fields.insert("bank_angle".into(), Value::from((f64::from(angle) * 10.0).round() / 10.0));
+                fields.insert("bank_toward".into(), serde_json::to_value(toward).expect("floats always serialize"));
+            }
+            if bank.is_some() || had_bank {
+                tile.metadata = if fields.is_empty() { String::new() } else { Value::Object(fields).to_string() };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::marble::AdvancedTileConfig;
+
+    fn bank_of(level: &Level, x: usize, y: usize) -> Option<(f64, Vec<f64>)> {
+        let meta: Value = serde_json::from_str(&level.marble_tiles.as_ref()?[y][x].metadata).ok()?;
+        let toward = meta.get("bank_toward")?.as_array()?.iter().filter_map(Value::as_f64).collect();
+        Some((meta.get("bank_angle")?.as_f64()?, toward))
+    }
+
+    #[test]
+    fn turns_bank_toward_their_inside() {
+        // A corridor running east, then turning south
+        let map = "\
+###########
+#........##
+########.##
+########.##
+########.##
+###########";
+        let mut level = Level::from_ascii_with(map, AdvancedTileConfig::none()).unwrap();
+        level.add_banking();
+        let (angle, toward) = bank_of(&level, 8, 1).expect("the corner is banked");
+        assert!(angle > 0.0 && angle <= MAX_BANK_ANGLE as f64);
+        assert!(toward[0] < 0.0 && toward[1] > 0.0, "tilts toward the inside, south-west: {toward:?}");
+        assert!(bank_of(&level, 2, 1).is_none() && bank_of(&level, 8, 4).is_none());
+
+        // Running the pass again leaves one bank per tile
+        level.add_banking();
+        assert_eq!(level.marble_tiles.as_ref().unwrap()[1][8].metadata.matches("bank_angle").count(), 1);
+
+        // Longer run-ups, drops, and tighter turns bank steeper
+        assert!(bank_angle(2.0, 12.0, 0.0) > bank_angle(2.0, 4.0, 0.0));
+        assert!(bank_angle(2.0, 4.0, 1.0) > bank_angle(2.0, 4.0, 0.0));
+        assert!(bank_angle(1.0, 4.0, 0.0) > bank_angle(3.0, 4.0, 0.0));
+        assert_eq!(bank_angle(0.5, 100.0, 5.0), MAX_BANK_ANGLE);
+        assert!((MIN_TURN_COS - MIN_TURN_DEGREES.to_radians().cos()).abs() < 1e-6);
+        for x in [0.0f32, 0.1, 0.3, 0.5, 0.8, 0.99] {
+            assert!((atan_degrees(x) - x.atan().to_degrees()).abs() < 0.01, "{x}");
+        }
+    }
+}
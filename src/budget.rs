@@ -0,0 +1,117 @@
+//! Time budgets and cancellation.
+//!
+//! `GeneratorParams::time_budget` and `GeneratorParams::cancel` bound how long
+//! [`generate`] runs. Once the budget is spent or the token is cancelled,
+//! generation winds down rather than finishing every stage. Room placement
+//! keeps the rooms it has, and WFC stops restarting (an unsolved map comes
+//! out blank). The optional marble stages are skipped: spiral ramps, funnels,
+//! obstacles, moving hazards, rivers, and voxels. Rooms are still connected,
+//! so the best-effort level stays playable; it has `stats.budget_exceeded`
+//! set. [`try_generate`] reports it as an error instead.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::dungeon::{generate, GeneratorParams, Level};
+
+/// Shared flag that stops a running generation from another thread.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask every generation holding a clone of this token to stop.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Point at which a generation should stop, from its params.
+pub(crate) struct Deadline {
+    end: Option<Instant>,
+    cancel: Option<CancelToken>,
+}
+
+impl Deadline {
+    pub(crate) fn new(params: &GeneratorParams) -> Self {
+        Self {
+            end: params.time_budget.map(|budget| Instant::now() + budget),
+            cancel: params.cancel.clone(),
+        }
+    }
+
+    /// A deadline that never passes, for passes run outside a generation.
+    pub(crate) fn unlimited() -> Self {
+        Self { end: None, cancel: None }
+    }
+
+    /// Whether the budget is spent or the generation was cancelled.
+    pub(crate) fn expired(&self) -> bool {
+        self.end.is_some_and(|end| Instant::now() >= end) || self.cancel.as_ref().is_some_and(CancelToken::is_cancelled)
+    }
+}
+
+/// Error from [`try_generate`]: generation ran out of time or was cancelled.
+#[derive(Debug)]
+pub struct BudgetExceeded {
+    /// The best-effort level generation produced before stopping
+    pub level: Box<Level>,
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "level generation exceeded its time budget or was cancelled")
+    }
+}
+
+impl std::error::Error for BudgetExceeded {}
+
+/// Like [`generate`], but fails when the time budget runs out or the cancel
+/// token fires before generation completes.
+pub fn try_generate(params: &GeneratorParams) -> Result<Level, BudgetExceeded> {
+    let level = generate(params);
+    if level.stats.budget_exceeded {
+        Err(BudgetExceeded { level: Box::new(level) })
+    } else {
+        Ok(level)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::GenerationMode;
+    use std::time::Duration;
+
+    #[test]
+    fn spent_budgets_and_cancelled_tokens_stop_generation() {
+        let params = GeneratorParams { seed: Some(4), mode: GenerationMode::Wfc, width: 60, height: 40, ..Default::default() };
+        assert!(try_generate(&params).is_ok());
+
+        let spent = GeneratorParams { time_budget: Some(Duration::ZERO), ..params.clone() };
+        let err = try_generate(&spent).unwrap_err();
+        assert!(err.level.tiles.iter().all(|row| row.trim().is_empty()));
+
+        let token = CancelToken::new();
+        token.cancel();
+        let marble = GeneratorParams {
+            seed: Some(4),
+            mode: GenerationMode::Marble,
+            enable_obstacles: true,
+            cancel: Some(token),
+            ..Default::default()
+        };
+        let level = generate(&marble);
+        assert!(level.stats.budget_exceeded);
+        assert!(level.rooms.is_empty() && level.obstacles.is_empty());
+    }
+}
@@ -0,0 +1,225 @@
+//! Ordered series of levels with rising difficulty.
+//!
+//! A [`Campaign`] generates `levels` levels from one set of base params,
+//! with difficulty (see [`Level::difficulty`]) rising from a first to a
+//! last target. For each position it generates `candidates` levels, with
+//! params scaled by the position (more rooms, denser obstacles and hazards
+//! toward the end) and the [`Theme`]s applied in turn on top. From the
+//! whole pool it then picks the series closest to the targets in which
+//! difficulty never drops, neighbors have different themes, and no two
+//! levels are near-duplicates (a [`Level::similarity`] of
+//! [`MAX_SIMILARITY`] or more). Near-duplicates and theme changes are given
+//! up, in that order, only when the pool has no series without them.
+//!
+//! Candidate seeds come from one stream seeded by `base.seed`, so a
+//! campaign seed reproduces the whole series.
+//!
+//! ```
+//! use level_generator::campaign::Campaign;
+//! use level_generator::{GenerationMode, GeneratorParams};
+//!
+//! let campaign = Campaign::new(GeneratorParams { seed: Some(1), mode: GenerationMode::Marble, ..Default::default() }, 4);
+//! let levels = campaign.generate();
+//! assert_eq!(levels.len(), 4);
+//! assert!(levels.windows(2).all(|pair| pair[0].difficulty <= pair[1].difficulty));
+//! ```
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::dungeon::{generate, GeneratorParams, Level};
+use crate::fingerprint::Fingerprint;
+use crate::graph::ConnectionStrategy;
+
+/// Named change to the base params that gives levels a distinct feel.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub name: String,
+    pub apply: fn(&mut GeneratorParams),
+}
+
+impl Theme {
+    pub fn new(name: &str, apply: fn(&mut GeneratorParams)) -> Self {
+        Self { name: name.to_string(), apply }
+    }
+
+    /// The default themes: `halls` (fewer, larger rooms with loops),
+    /// `warrens` (many small rooms, dead ends kept), `heights` (elevation
+    /// and spiral ramps), and `gauntlet` (obstacles and moving hazards).
+    pub fn builtin() -> Vec<Theme> {
+        vec![
+            Theme::new("halls", |p| {
+                p.min_room = p.min_room.saturating_add(2);
+                p.max_room = p.max_room.saturating_add(4);
+                p.rooms = ((p.rooms as u64 * 2 / 3) as u32).max(1);
+                p.connection_strategy = ConnectionStrategy::MinimumSpanningTree { loop_fraction: 0.3 };
+            }),
+            Theme::new("warrens", |p| {
+                p.max_room = p.max_room.saturating_sub(3).max(p.min_room.saturating_add(1));
+                p.rooms = p.rooms.saturating_add(p.rooms / 2);
+                p.dead_end_removal = 0.0;
+            }),
+            Theme::new("heights", |p| {
+                p.enable_elevation = true;
+                p.max_elevation += 1;
+                p.enable_spiral_ramps = true;
+            }),
+            Theme::new("gauntlet", |p| {
+                p.enable_obstacles = true;
+                p.obstacle_density = (p.obstacle_density + 0.2).min(1.0);
+                p.enable_dynamics = true;
+            }),
+        ]
+    }
+}
+
+/// Generator for an ordered level series; see the module docs.
+#[derive(Debug, Clone)]
+pub struct Campaign {
+    /// Params every level starts from; `seed` seeds the whole campaign
+    pub base: GeneratorParams,
+    /// Number of levels
+    pub levels: usize,
+    /// Themes to vary between; an empty list leaves the params alone
+    pub themes: Vec<Theme>,
+    /// Target difficulty of the first and the last level
+    pub difficulty: (f32, f32),
+    /// Levels generated per position for the pool to choose from
+    pub candidates: u32,
+}
+
+/// One level of a campaign, in order.
+#[derive(Debug, Clone)]
+pub struct CampaignLevel {
+    /// Name of the theme applied, empty without themes
+    pub theme: String,
+    /// [`Level::difficulty`] of `level`
+    pub difficulty: f32,
+    pub level: Level,
+}
+
+/// Fingerprint similarity at which two levels count as the same structure
+pub const MAX_SIMILARITY: f32 = 0.9;
+
+impl Campaign {
+    /// Campaign of `levels` levels with the builtin themes, difficulty
+    /// rising from 0.3 to 0.7, and 8 candidates per level.
+    pub fn new(base: GeneratorParams, levels: usize) -> Self {
+        Self { base, levels, themes: Theme::builtin(), difficulty: (0.3, 0.7), candidates: 8 }
+    }
+
+    /// Generate the series, easiest first.
+    pub fn generate(&self) -> Vec<CampaignLevel> {
+        let mut rng = StdRng::seed_from_u64(self.base.seed.unwrap_or_else(|| rand::rng().random()));
+        let targets: Vec<f32> = (0..self.levels)
+            .map(|i| if self.levels > 1 { i as f32 / (self.levels - 1) as f32 } else { 0.0 })
+            .map(|t| self.difficulty.0 + (self.difficulty.1 - self.difficulty.0) * t)
+            .collect();
+
+        let mut pool: Vec<Candidate> = Vec::new();
+        for i in 0..self.levels {
+            let t = if self.levels > 1 { i as f32 / (self.levels - 1) as f32 } else { 0.0 };
+            for k in 0..self.candidates.max(1) as usize {
+                let theme = (!self.themes.is_empty()).then(|| (i + k) % self.themes.len());
+                let mut params = GeneratorParams {
+                    rooms: ((self.base.rooms as f32 * (0.6 + 0.8 * t)).round() as u32).max(1),
+                    obstacle_density: (self.base.obstacle_density * (0.5 + t)).min(1.0),
+                    dynamic_count: (self.base.dynamic_count as f32 * (0.5 + t)).round() as u32,
+                    seed: Some(rng.random()),
+                    ..self.base.clone()
+                };
+                if let Some(theme) = theme {
+                    (self.themes[theme].apply)(&mut params);
+                }
+                let level = generate(&params);
+                pool.push(Candidate { difficulty: level.difficulty(), fingerprint: level.fingerprint(), theme, level });
+            }
+        }
+        pool.sort_by(|a, b| a.difficulty.total_cmp(&b.difficulty));
+        let mut unique: Vec<Candidate> = Vec::new();
+        for candidate in pool.iter() {
+            if unique.iter().all(|u| u.fingerprint.similarity(&candidate.fingerprint) < MAX_SIMILARITY) {
+                unique.push(candidate.clone());
+            }
+        }
+        if unique.len() >= self.levels {
+            pool = unique;
+        }
+
+        let picks = plan(&pool, &targets, self.themes.len() > 1).or_else(|| plan(&pool, &targets, false)).unwrap_or_default();
+        picks
+            .into_iter()
+            .map(|c| {
+                let Candidate { difficulty, theme, level, .. } = pool[c].clone();
+                CampaignLevel { theme: theme.map_or(String::new(), |th| self.themes[th].name.clone()), difficulty, level }
+            })
+            .collect()
+    }
+}
+
+/// A generated level the campaign may pick
+#[derive(Clone)]
+struct Candidate {
+    difficulty: f32,
+    fingerprint: Fingerprint,
+    theme: Option<usize>,
+    level: Level,
+}
+
+/// Pool indices, increasing (so difficulty never drops and no level is used
+/// twice), that minimize the total distance to `targets`; with
+/// `vary_themes`, neighbors never share a theme. `None` if no such series
+/// exists.
+fn plan(pool: &[Candidate], targets: &[f32], vary_themes: bool) -> Option<Vec<usize>> {
+    let n = pool.len();
+    // cost[i][c]: best total for the first i + 1 levels ending with candidate c
+    let mut cost = vec![vec![f32::INFINITY; n]; targets.len()];
+    let mut back = vec![vec![usize::MAX; n]; targets.len()];
+    for (i, &target) in targets.iter().enumerate() {
+        for c in 0..n {
+            let own = (pool[c].difficulty - target).abs();
+            if i == 0 {
+                cost[0][c] = own;
+                continue;
+            }
+            for p in 0..c {
+                if cost[i - 1][p].is_finite() && !(vary_themes && pool[p].theme == pool[c].theme) && cost[i - 1][p] + own < cost[i][c] {
+                    cost[i][c] = cost[i - 1][p] + own;
+                    back[i][c] = p;
+                }
+            }
+        }
+    }
+    let last = targets.len().checked_sub(1)?;
+    let mut c = (0..n).filter(|&c| cost[last][c].is_finite()).min_by(|&a, &b| cost[last][a].total_cmp(&cost[last][b]))?;
+    let mut picks = vec![c];
+    for i in (1..=last).rev() {
+        c = back[i][c];
+        picks.push(c);
+    }
+    picks.reverse();
+    Some(picks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::GenerationMode;
+    use crate::roles::EndpointPlacement;
+
+    #[test]
+    fn campaigns_rise_in_difficulty_without_repeats() {
+        // Entrances in the first room keep main paths short of saturating
+        // the difficulty score, so the themes interleave
+        let base = GeneratorParams { seed: Some(3), mode: GenerationMode::Marble, endpoints: Some(EndpointPlacement::FirstRoom), ..Default::default() };
+        let levels = Campaign::new(base.clone(), 6).generate();
+        assert_eq!(levels.len(), 6);
+        assert!(levels.windows(2).all(|pair| pair[0].difficulty <= pair[1].difficulty));
+        assert!(levels.windows(2).all(|pair| pair[0].theme != pair[1].theme));
+        assert!(levels.iter().enumerate().all(|(i, a)| levels[..i].iter().all(|b| a.level.similarity(&b.level) < MAX_SIMILARITY)));
+
+        // The campaign seed reproduces the series
+        let again = Campaign::new(base, 6).generate();
+        assert!(levels.iter().zip(&again).all(|(a, b)| a.level.seed == b.level.seed));
+    }
+}
@@ -0,0 +1,103 @@
+//! Machine-readable catalog of the marble tile types.
+//!
+//! [`tile_catalog`] lists every [`TileType`] with what an engine importer
+//! needs to map it to a prefab: the directions it connects in at rotation 0
+//! and after each rotation, whether it is walled and passable by default,
+//! its ASCII character, and the color the isometric view draws it in. The
+//! catalog serializes like a level, so `serde_json::to_string_pretty` (or the
+//! CLI's `tile-catalog` command) gives the JSON form.
+//!
+//! Rotation is the number of 90° clockwise steps (0-3) applied to the rotation
+//! 0 connections. North is toward `y - 1`, and wall arrays are indexed by
+//! [`Direction`]: north, east, south, west.
+
+use serde::{Deserialize, Serialize};
+
+use crate::export::FORMAT_VERSION;
+use crate::isometric::tile_color;
+use crate::tiles::{Direction, MarbleTile, TileType};
+
+/// Every tile type with its connection templates; see the module docs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TileCatalog {
+    /// Export format version the tile types belong to
+    pub format_version: u32,
+    /// How `rotation` on a tile turns its connections
+    pub rotation: String,
+    pub tiles: Vec<TileCatalogEntry>,
+}
+
+/// One tile type in the [`TileCatalog`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TileCatalogEntry {
+    pub tile_type: TileType,
+    /// Character in the ASCII view, with the default walls
+    pub ascii: char,
+    pub passable: bool,
+    /// Whether new tiles of this type are walled on every side
+    pub default_walls: bool,
+    /// Directions connected at rotation 0
+    pub connections: Vec<Direction>,
+    /// Directions connected at rotations 0, 1, 2, and 3
+    pub rotations: Vec<Vec<Direction>>,
+    /// How many of the four rotations differ (1, 2, or 4)
+    pub distinct_rotations: u8,
+    /// Suggested color as `#rrggbb`, as in the isometric view
+    pub color: String,
+}
+
+/// Catalog of every [`TileType`], in declaration order.
+pub fn tile_catalog() -> TileCatalog {
+    TileCatalog {
+        format_version: FORMAT_VERSION,
+        rotation: "clockwise 90-degree steps applied to the rotation 0 connections; north is toward y - 1".to_string(),
+        tiles: TileType::ALL.iter().map(catalog_entry).collect(),
+    }
+}
+
+fn catalog_entry(tile_type: &TileType) -> TileCatalogEntry {
+    let rotations: Vec<Vec<Direction>> = (0..4)
+        .map(|rotation| {
+            let mut connections = MarbleTile::with_params(*tile_type, 0, rotation, false).connections();
+            connections.sort_by_key(|d| *d as u8);
+            connections
+        })
+        .collect();
+    let distinct_rotations = (0..4).filter(|&r| !rotations[..r].contains(&rotations[r])).count() as u8;
+    TileCatalogEntry {
+        tile_type: *tile_type,
+        ascii: tile_type.to_ascii(tile_type.has_default_walls()),
+        passable: tile_type.is_passable(),
+        default_walls: tile_type.has_default_walls(),
+        connections: tile_type.base_connections(),
+        rotations,
+        distinct_rotations,
+        color: tile_color(tile_type).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catalog_matches_the_tile_rules() {
+        let catalog = tile_catalog();
+        assert_eq!(catalog.tiles.len(), TileType::ALL.len());
+        for entry in &catalog.tiles {
+            for (rotation, connections) in entry.rotations.iter().enumerate() {
+                let tile = MarbleTile::with_params(entry.tile_type, 0, rotation as u8, false);
+                assert!(Direction::ALL.iter().all(|&d| tile.connects(d) == connections.contains(&d)));
+            }
+            assert_eq!(entry.color.len(), 7);
+        }
+
+        let distinct = |t: TileType| catalog.tiles.iter().find(|e| e.tile_type == t).unwrap().distinct_rotations;
+        assert_eq!((distinct(TileType::Straight), distinct(TileType::Curve90), distinct(TileType::CrossJunction)), (2, 4, 1));
+
+        let json = serde_json::to_string(&catalog).unwrap();
+        assert_eq!(serde_json::from_str::<TileCatalog>(&json).unwrap(), catalog);
+    }
+}
@@ -0,0 +1,94 @@
+//! Ceiling heights for 3D renderers.
+//!
+//! First-person renderers that extrude the map need to know how high to
+//! build. [`Level::assign_ceilings`] gives every room a `ceiling` that grows
+//! with its size: the smallest room of the level gets [`CeilingConfig::min`]
+//! and the largest [`CeilingConfig::max`], by the square root of their
+//! areas. Every connection gets an entry in `Level::corridor_ceilings`, the
+//! lower of its two rooms' ceilings capped at [`CeilingConfig::corridor`],
+//! so corridors never open up into the rooms they join.
+//!
+//! Heights are in the same units as elevations (one tile side per step).
+//! Setting `GeneratorParams::ceilings` assigns them as levels are generated;
+//! `min == max` gives every room the same height.
+//!
+//! ```
+//! use level_generator::ceilings::CeilingConfig;
+//! use level_generator::{generate, GeneratorParams};
+//!
+//! let level = generate(&GeneratorParams { seed: Some(2), ceilings: Some(CeilingConfig::default()), ..Default::default() });
+//! assert!(level.rooms.iter().all(|room| room.ceiling.is_some_and(|c| (3..=6).contains(&c))));
+//! assert_eq!(level.corridor_ceilings.len(), level.connections.len());
+//! ```
+
+use crate::dungeon::Level;
+
+/// How high ceilings are; see the module docs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CeilingConfig {
+    /// Ceiling of the smallest room
+    pub min: u32,
+    /// Ceiling of the largest room
+    pub max: u32,
+    /// Highest corridor ceiling
+    pub corridor: u32,
+}
+
+impl Default for CeilingConfig {
+    fn default() -> Self {
+        Self { min: 3, max: 6, corridor: 2 }
+    }
+}
+
+impl Level {
+    /// Set every room's `ceiling` and the `corridor_ceilings` from `config`;
+    /// see the module docs. Calling it again replaces the heights.
+    pub fn assign_ceilings(&mut self, config: &CeilingConfig) {
+        let (low, high) = (config.min.min(config.max), config.max.max(config.min));
+        let sides: Vec<f32> = self.rooms.iter().map(|r| ((r.w.max(0) * r.h.max(0)) as f32).sqrt()).collect();
+        let smallest = sides.iter().copied().fold(f32::INFINITY, f32::min);
+        let largest = sides.iter().copied().fold(0.0, f32::max);
+        for (room, side) in self.rooms.iter_mut().zip(&sides) {
+            let t = if largest > smallest { (side - smallest) / (largest - smallest) } else { 0.0 };
+            room.ceiling = Some(low + ((high - low) as f32 * t).round() as u32);
+        }
+        self.corridor_ceilings = self
+            .connections
+            .iter()
+            .map(|&(a, b)| {
+                let rooms = [a, b].map(|i| self.rooms.get(i).and_then(|r| r.ceiling).unwrap_or(config.corridor));
+                rooms[0].min(rooms[1]).min(config.corridor)
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GeneratorParams};
+
+    #[test]
+    fn larger_rooms_get_higher_ceilings() {
+        let params = GeneratorParams { seed: Some(9), rooms: 10, min_room: 3, max_room: 12, ..Default::default() };
+        let plain = generate(&params);
+        assert!(plain.rooms.iter().all(|r| r.ceiling.is_none()) && plain.corridor_ceilings.is_empty());
+
+        let config = CeilingConfig { min: 2, max: 8, corridor: 3 };
+        let level = generate(&GeneratorParams { ceilings: Some(config), ..params });
+        assert_eq!(level.tiles, plain.tiles);
+        let area = |i: usize| level.rooms[i].w * level.rooms[i].h;
+        let ceiling = |i: usize| level.rooms[i].ceiling.unwrap();
+        let (small, large) = ((0..level.rooms.len()).min_by_key(|&i| area(i)).unwrap(), (0..level.rooms.len()).max_by_key(|&i| area(i)).unwrap());
+        assert_eq!((ceiling(small), ceiling(large)), (2, 8));
+        assert!((0..level.rooms.len()).all(|i| (0..level.rooms.len()).all(|j| area(i) <= area(j) || ceiling(i) >= ceiling(j))));
+        for (&(a, b), &corridor) in level.connections.iter().zip(&level.corridor_ceilings) {
+            assert_eq!(corridor, ceiling(a).min(ceiling(b)).min(3));
+        }
+
+        let json = serde_json::to_string(&level).unwrap();
+        let back: Level = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.rooms[small].ceiling, Some(2));
+        assert_eq!(back.corridor_ceilings, level.corridor_ceilings);
+    }
+}
@@ -1,11 +1,18 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use std::path::PathBuf;
+use crate::acceptance::Constraint;
+use crate::openings::EdgeOpening;
+use crate::seeds::Date;
+use crate::tiles::{Direction, TileType};
 
 #[derive(Debug, Clone, Copy)]
 pub enum ModeArg {
     Classic,
     Marble,
     Wfc,
+    Overworld,
+    Settlement,
+    RoomWfc,
 }
 
 impl std::str::FromStr for ModeArg {
@@ -15,7 +22,351 @@ impl std::str::FromStr for ModeArg {
             "classic" | "dungeon" => Ok(ModeArg::Classic),
             "marble" | "marbles" => Ok(ModeArg::Marble),
             "wfc" | "wave" => Ok(ModeArg::Wfc),
-            other => Err(format!("invalid mode: {} (expected classic|marble)", other)),
+            "overworld" | "outdoor" => Ok(ModeArg::Overworld),
+            "settlement" | "city" | "village" => Ok(ModeArg::Settlement),
+            "room-wfc" | "roomwfc" | "archetypes" => Ok(ModeArg::RoomWfc),
+            other => Err(format!("invalid mode: {} (expected classic|marble|wfc|overworld|settlement|room-wfc)", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TopologyArg {
+    Square,
+    Hex,
+    Octile,
+}
+
+impl std::str::FromStr for TopologyArg {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "square" | "grid" => Ok(TopologyArg::Square),
+            "hex" | "hexagonal" => Ok(TopologyArg::Hex),
+            "octile" | "diagonal" => Ok(TopologyArg::Octile),
+            other => Err(format!("invalid topology: {} (expected square|hex|octile)", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum RoadLayoutArg {
+    Grid,
+    Organic,
+}
+
+impl std::str::FromStr for RoadLayoutArg {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "grid" => Ok(RoadLayoutArg::Grid),
+            "organic" => Ok(RoadLayoutArg::Organic),
+            other => Err(format!("invalid road layout: {} (expected grid|organic)", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum NameThemeArg {
+    Dungeon,
+    Cavern,
+    Ruins,
+    Town,
+    Clockwork,
+}
+
+impl std::str::FromStr for NameThemeArg {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "dungeon" => Ok(NameThemeArg::Dungeon),
+            "cavern" => Ok(NameThemeArg::Cavern),
+            "ruins" => Ok(NameThemeArg::Ruins),
+            "town" => Ok(NameThemeArg::Town),
+            "clockwork" => Ok(NameThemeArg::Clockwork),
+            other => Err(format!("invalid name theme: {} (expected dungeon|cavern|ruins|town|clockwork)", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum EndpointsArg {
+    First,
+    Farthest,
+    Elevation,
+}
+
+impl std::str::FromStr for EndpointsArg {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "first" => Ok(EndpointsArg::First),
+            "farthest" => Ok(EndpointsArg::Farthest),
+            "elevation" => Ok(EndpointsArg::Elevation),
+            other => Err(format!("invalid endpoints: {} (expected farthest|elevation|first)", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum VaultGuardArg {
+    Door,
+    Obstacle,
+}
+
+impl std::str::FromStr for VaultGuardArg {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "door" => Ok(VaultGuardArg::Door),
+            "obstacle" => Ok(VaultGuardArg::Obstacle),
+            other => Err(format!("invalid vault guard: {} (expected door|obstacle)", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ObstacleShapeArg {
+    Pillar,
+    Block,
+    Wall,
+    Bumpers,
+}
+
+impl std::str::FromStr for ObstacleShapeArg {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "pillar" => Ok(ObstacleShapeArg::Pillar),
+            "block" => Ok(ObstacleShapeArg::Block),
+            "wall" | "wall-segment" => Ok(ObstacleShapeArg::Wall),
+            "bumpers" | "bumper-cluster" => Ok(ObstacleShapeArg::Bumpers),
+            other => Err(format!("invalid obstacle shape: {} (expected pillar|block|wall|bumpers)", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ObstaclePatternArg {
+    Scatter,
+    Slalom,
+    Pinball,
+    Gauntlet,
+}
+
+impl std::str::FromStr for ObstaclePatternArg {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "scatter" => Ok(ObstaclePatternArg::Scatter),
+            "slalom" => Ok(ObstaclePatternArg::Slalom),
+            "pinball" => Ok(ObstaclePatternArg::Pinball),
+            "gauntlet" | "funnels" => Ok(ObstaclePatternArg::Gauntlet),
+            other => Err(format!("invalid obstacle pattern: {} (expected scatter|slalom|pinball|gauntlet)", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum LayerArg {
+    Elevation,
+    Distance,
+    Obstacles,
+}
+
+impl std::str::FromStr for LayerArg {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "elevation" | "height" => Ok(LayerArg::Elevation),
+            "distance" | "distance-from-start" => Ok(LayerArg::Distance),
+            "obstacles" | "obstacle-mask" => Ok(LayerArg::Obstacles),
+            other => Err(format!("invalid layer: {} (expected elevation|distance|obstacles)", other)),
+        }
+    }
+}
+
+/// `--layer` value: a layer and the `.csv` or `.pgm` file to write it to
+#[derive(Debug, Clone)]
+pub struct LayerOutputArg {
+    pub layer: LayerArg,
+    pub path: PathBuf,
+}
+
+impl std::str::FromStr for LayerOutputArg {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (layer, path) = s.split_once('=').ok_or_else(|| format!("invalid layer output: {} (expected LAYER=PATH)", s))?;
+        Ok(LayerOutputArg { layer: layer.parse()?, path: PathBuf::from(path) })
+    }
+}
+
+/// `--allow-tiles` / `--exclude-tiles` value: a marble tile type by name, any case
+#[derive(Debug, Clone, Copy)]
+pub struct TileTypeArg(pub TileType);
+
+impl std::str::FromStr for TileTypeArg {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TileType::ALL
+            .iter()
+            .find(|t| format!("{:?}", t).eq_ignore_ascii_case(s.trim()))
+            .map(|&t| TileTypeArg(t))
+            .ok_or_else(|| format!("unknown tile type: {} (expected a name such as Straight or LoopDeLoop)", s))
+    }
+}
+
+/// `--ceilings` value: room ceiling heights from the smallest to the largest room
+#[derive(Debug, Clone, Copy)]
+pub struct CeilingRangeArg {
+    pub min: u32,
+    pub max: u32,
+}
+
+impl std::str::FromStr for CeilingRangeArg {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid ceiling range: {} (expected MIN..MAX or a single height)", s);
+        let number = |n: &str| n.trim().parse::<u32>().map_err(|_| invalid());
+        match s.split_once("..") {
+            Some((min, max)) => Ok(CeilingRangeArg { min: number(min)?, max: number(max)? }),
+            None => number(s).map(|h| CeilingRangeArg { min: h, max: h }),
+        }
+    }
+}
+
+/// `--edge-opening` value: `SIDE:CENTER:WIDTH`, e.g. `north:40:3`
+#[derive(Debug, Clone, Copy)]
+pub struct EdgeOpeningArg(pub EdgeOpening);
+
+impl std::str::FromStr for EdgeOpeningArg {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid edge opening: {} (expected SIDE:CENTER:WIDTH with SIDE north|east|south|west)", s);
+        let parts: Vec<&str> = s.split(':').map(str::trim).collect();
+        let [side, center, width] = parts[..] else { return Err(invalid()) };
+        let side = match side.to_ascii_lowercase().as_str() {
+            "north" | "n" => Direction::North,
+            "east" | "e" => Direction::East,
+            "south" | "s" => Direction::South,
+            "west" | "w" => Direction::West,
+            _ => return Err(invalid()),
+        };
+        let number = |n: &str| n.parse::<u32>().map_err(|_| invalid());
+        Ok(EdgeOpeningArg(EdgeOpening::centered(side, number(center)?, number(width)?)))
+    }
+}
+
+/// `gallery --seeds` value: a range of seeds, end excluded
+#[derive(Debug, Clone, Copy)]
+pub struct SeedRangeArg {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl std::str::FromStr for SeedRangeArg {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid seed range: {} (expected START..END or a single seed)", s);
+        let number = |n: &str| n.trim().parse::<u64>().map_err(|_| invalid());
+        match s.split_once("..") {
+            Some((start, end)) => Ok(SeedRangeArg { start: number(start)?, end: number(end)? }),
+            None => number(s).map(|seed| SeedRangeArg { start: seed, end: seed + 1 }),
+        }
+    }
+}
+
+/// `--region` value: a rectangle and the mode generating it
+#[derive(Debug, Clone, Copy)]
+pub struct RegionArg {
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+    pub mode: ModeArg,
+}
+
+impl std::str::FromStr for RegionArg {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid region: {} (expected X,Y,W,H=MODE)", s);
+        let (rect, mode) = s.split_once('=').ok_or_else(invalid)?;
+        let numbers: Vec<i32> = rect.split(',').map(|n| n.trim().parse()).collect::<Result<_, _>>().map_err(|_| invalid())?;
+        let [x, y, w, h] = numbers[..] else { return Err(invalid()) };
+        Ok(RegionArg { x, y, w, h, mode: mode.parse()? })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum FormatArg {
+    Json,
+    Ron,
+    Yaml,
+}
+
+impl std::str::FromStr for FormatArg {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(FormatArg::Json),
+            "ron" => Ok(FormatArg::Ron),
+            "yaml" | "yml" => Ok(FormatArg::Yaml),
+            other => Err(format!("invalid format: {} (expected json|ron|yaml)", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum RoomSizesArg {
+    Uniform,
+    Normal,
+    Bimodal,
+}
+
+impl std::str::FromStr for RoomSizesArg {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "uniform" => Ok(RoomSizesArg::Uniform),
+            "normal" | "gaussian" => Ok(RoomSizesArg::Normal),
+            "bimodal" => Ok(RoomSizesArg::Bimodal),
+            other => Err(format!("invalid room size distribution: {} (expected uniform|normal|bimodal)", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ZoningArg {
+    None,
+    Rooms,
+    Bands,
+}
+
+impl std::str::FromStr for ZoningArg {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" | "off" => Ok(ZoningArg::None),
+            "rooms" | "clusters" => Ok(ZoningArg::Rooms),
+            "bands" | "distance" => Ok(ZoningArg::Bands),
+            other => Err(format!("invalid zoning: {} (expected none|rooms|bands)", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ConnectionArg {
+    Chain,
+    Mst,
+}
+
+impl std::str::FromStr for ConnectionArg {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "chain" => Ok(ConnectionArg::Chain),
+            "mst" | "tree" => Ok(ConnectionArg::Mst),
+            other => Err(format!("invalid connection strategy: {} (expected chain|mst)", other)),
         }
     }
 }
@@ -45,22 +396,138 @@ pub struct Args {
     #[arg(long = "max-room", short = 'M', default_value_t = 10, help = "Maximum room dimension")] 
     pub max_room: u32,
 
+    /// Room side length distribution: uniform, normal, or bimodal
+    #[arg(long = "room-sizes", default_value = "uniform", help = "Room size distribution: uniform|normal|bimodal")]
+    pub room_sizes: RoomSizesArg,
+
+    /// Hub-scale rooms placed before all others
+    #[arg(long = "large-rooms", default_value_t = 0, help = "Number of guaranteed hub-scale rooms")]
+    pub large_rooms: u32,
+
+    /// Room placement attempts per relaxation step (default: rooms * 10, at least 100)
+    #[arg(long = "room-attempts", help = "Room placement attempts (default: rooms*10, min 100)")]
+    pub room_attempts: Option<u32>,
+
+    /// Let rooms touch, then shrink sizes, when the requested rooms don't fit
+    #[arg(long = "relax-rooms", default_value_t = false, help = "Relax margins, then sizes, when rooms don't fit")]
+    pub relax_rooms: bool,
+
+    /// Solid wall tiles kept around the carved level
+    #[arg(long = "border", default_value_t = 1, help = "Solid wall thickness around the level (classic and marble mode)")]
+    pub border: u32,
+
+    /// Void tiles along the map edge, outside the border
+    #[arg(long = "skirt", default_value_t = 0, help = "Void skirt width along the map edge, outside the border (classic and marble mode)")]
+    pub skirt: u32,
+
+    /// Wall-clock limit in milliseconds; past it optional stages are skipped
+    #[arg(long = "time-budget", help = "Time budget in milliseconds; returns a best-effort level when exceeded")]
+    pub time_budget: Option<u64>,
+
+    /// Minimum entrance-to-exit walk in tiles; shorter levels are regenerated
+    #[arg(long = "min-path-length", help = "Regenerate until the entrance-to-exit path is at least this many tiles")]
+    pub min_path_length: Option<u32>,
+
+    /// Regenerations allowed for `--min-path-length`
+    #[arg(long = "path-retries", default_value_t = 20, help = "Regenerations allowed for --min-path-length")]
+    pub path_retries: u32,
+
+    /// Fraction of the map the level should fill
+    #[arg(long = "target-floor-ratio", help = "Fraction of the map (0.0-1.0) the level should fill; rooms and channels are adjusted to meet it")]
+    pub target_floor_ratio: Option<f32>,
+
+    /// Allowed distance from `--target-floor-ratio`
+    #[arg(long = "floor-ratio-tolerance", default_value_t = 0.05, help = "Allowed distance from --target-floor-ratio")]
+    pub floor_ratio_tolerance: f32,
+
+    /// Upper bound on the level's tiles (track pieces in marble mode)
+    #[arg(long = "max-total-tiles", help = "Maximum number of tiles (track pieces in marble mode); rooms and channels are reduced to meet it")]
+    pub max_total_tiles: Option<u32>,
+
+    /// Adjustments allowed for the tile budget
+    #[arg(long = "budget-retries", default_value_t = 10, help = "Room count and channel width adjustments allowed for --target-floor-ratio and --max-total-tiles")]
+    pub budget_retries: u32,
+
     /// RNG seed for reproducible dungeons
     #[arg(long = "seed", short = 's', help = "RNG seed for reproducible dungeons")] 
     pub seed: Option<u64>,
 
-    /// Generation mode: classic (rooms+tunnels) or marble (rounded channels)
-    #[arg(long = "mode", default_value = "classic", help = "Generation mode: classic|marble")] 
+    /// Generate the level of the day: today (UTC) or the given `YYYY-MM-DD`; see `seeds::Seed::daily`
+    #[arg(long = "daily", value_name = "DATE", num_args = 0..=1, conflicts_with = "seed", help = "Use the seed of the day, today (UTC) or the given YYYY-MM-DD, instead of --seed")]
+    pub daily: Option<Option<Date>>,
+
+    /// Salt of `--daily`, e.g. a game or mode name
+    #[arg(long = "daily-salt", value_name = "SALT", default_value = "", help = "Salt mixed into the --daily seed, e.g. a game or mode name")]
+    pub daily_salt: String,
+
+    /// Generation mode: classic (rooms+tunnels), marble (rounded channels), wfc, overworld, settlement, or room-wfc
+    #[arg(long = "mode", default_value = "classic", help = "Generation mode: classic|marble|wfc|overworld|settlement|room-wfc")] 
     pub mode: ModeArg,
 
+    /// Generate a rectangle of the map with its own mode, e.g. `--region 0,0,40,30=classic`; regions are joined in order
+    #[arg(long = "region", value_name = "X,Y,W,H=MODE", help = "Generate a region with its own mode (hybrid level); repeatable")]
+    pub regions: Vec<RegionArg>,
+
+    /// Acceptance constraint the level must meet, e.g. `--accept path=30..60`; seeds are tried until all are met
+    #[arg(long = "accept", value_name = "CONSTRAINT", help = "Keep generating until the level meets this constraint (path=MIN..MAX, junctions=, drop=, tiles=A+B, max-dead-ends=N); repeatable")]
+    pub accept: Vec<Constraint>,
+
+    /// Seeds to try for `--accept`
+    #[arg(long = "accept-attempts", default_value_t = 50, help = "Seeds to try before giving up on --accept")]
+    pub accept_attempts: u32,
+
+    /// Load a hand-drawn ASCII map, or a black-and-white `.png` stencil (`png` feature), instead of generating
+    #[arg(long = "import", value_name = "PATH", help = "Convert an ASCII map or .png stencil instead of generating a level")]
+    pub import: Option<PathBuf>,
+
+    /// Grid topology: square, hex (classic mode), or octile (classic and marble mode)
+    #[arg(long = "topology", default_value = "square", help = "Grid topology: square|hex|octile")]
+    pub topology: TopologyArg,
+
+    /// Room connection strategy: chain (by center x) or mst (spanning tree plus loops)
+    #[arg(long = "connections", default_value = "chain", help = "Room connection strategy: chain|mst")]
+    pub connections: ConnectionArg,
+
+    /// Fraction of non-tree room pairs re-added as loops for `--connections mst`
+    #[arg(long = "loop-fraction", default_value_t = 0.1, help = "Fraction of extra room pairs joined as loops (mst)")]
+    pub loop_fraction: f32,
+
+    /// Classic: fraction of dead-end corridors to braid into loops or fill in
+    #[arg(long = "dead-end-removal", default_value_t = 0.0, help = "Classic: fraction of dead ends to braid or fill (0.0-1.0)")]
+    pub dead_end_removal: f32,
+
+    /// Classic: most secret passages between neighboring rooms that have no direct corridor
+    #[arg(long = "secret-passages", default_value_t = 0, help = "Classic: carve up to N secret passages (S tiles) between unconnected neighboring rooms")]
+    pub secret_passages: u32,
+
+    /// Most rooms to seal down to a single entrance and tag as vaults
+    #[arg(long = "vaults", default_value_t = 0, help = "Seal up to N rooms down to a single entrance and tag them as vaults")]
+    pub vaults: u32,
+
+    /// Guard recorded at each vault's entrance
+    #[arg(long = "vault-guard", value_name = "KIND", help = "Guard at each vault's entrance: door|obstacle")]
+    pub vault_guard: Option<VaultGuardArg>,
+
+    /// Marble: most side routes to carve off the main path and back
+    #[arg(long = "side-routes", default_value_t = 0, help = "Marble: carve up to N side routes (shortcuts or scenic loops) off the main path")]
+    pub side_routes: u32,
+
+    /// Marble: fewest edge-disjoint routes from the entrance to the exit
+    #[arg(long = "min-alternate-routes", default_value_t = 0, help = "Marble: carve side routes until N edge-disjoint routes join the entrance and exit")]
+    pub min_alternate_routes: u32,
+
     /// Marble: channel width in tiles (ignored for classic)
     #[arg(long = "channel-width", default_value_t = 2, help = "Marble: channel width in tiles")] 
     pub channel_width: u32,
 
-    /// Marble: corner radius in tiles for rounded turns (ignored for classic)
-    #[arg(long = "corner-radius", default_value_t = 2, help = "Marble: corner radius in tiles")] 
+    /// Marble: smallest radius in tiles of the arc at channel turns (ignored for classic)
+    #[arg(long = "corner-radius", default_value_t = 2, help = "Marble: smallest corner arc radius in tiles")]
     pub corner_radius: u32,
 
+    /// Marble: how far channel turns sweep into their shorter leg (0.0-1.0)
+    #[arg(long = "channel-curvature", default_value_t = 0.5, help = "Marble: channel curvature (0.0-1.0)")]
+    pub channel_curvature: f32,
+
     /// Marble: enable elevation variation
     #[arg(long = "enable-elevation", default_value_t = false, help = "Marble: enable elevation variation")]
     pub enable_elevation: bool,
@@ -77,6 +544,118 @@ pub struct Args {
     #[arg(long = "obstacle-density", default_value_t = 0.3, help = "Marble: obstacle density")]
     pub obstacle_density: f32,
 
+    /// Marble: how strongly obstacle and moving hazard density rises from start to goal
+    #[arg(long = "difficulty-ramp", default_value_t = 0.0, help = "Marble: ramp obstacle and hazard density from sparse at the start to dense at the goal (0.0-1.0)")]
+    pub difficulty_ramp: f32,
+
+    /// Marble: comma-separated obstacle shapes to pick from
+    #[arg(long = "obstacle-shapes", value_delimiter = ',', default_value = "pillar", help = "Marble: obstacle shapes pillar,block,wall,bumpers")]
+    pub obstacle_shapes: Vec<ObstacleShapeArg>,
+
+    /// Marble: comma-separated obstacle layouts to pick from per room
+    #[arg(long = "obstacle-patterns", value_delimiter = ',', default_value = "scatter", help = "Marble: obstacle patterns scatter,slalom,pinball,gauntlet")]
+    pub obstacle_patterns: Vec<ObstaclePatternArg>,
+
+    /// Marble: passable tiles every obstacle must leave beside it (default: channel width - 1)
+    #[arg(long = "obstacle-clearance", help = "Marble: min passable tiles beside obstacles (default: channel width - 1)")]
+    pub obstacle_clearance: Option<u32>,
+
+    /// Marble: minimum distance between obstacle centers in a room
+    #[arg(long = "obstacle-spacing", default_value_t = 3, help = "Marble: min distance between obstacle centers in a room")]
+    pub obstacle_spacing: u32,
+
+    /// Marble: distance obstacles keep from corridor mouths (default: 2, 0 for algorithm version 1)
+    #[arg(long = "obstacle-doorway-radius", help = "Marble: min distance between obstacles and corridor mouths (default: 2)")]
+    pub obstacle_doorway_radius: Option<u32>,
+
+    /// Marble: comma-separated tile types the level may contain; others are swapped for the closest of these
+    #[arg(long = "allow-tiles", value_delimiter = ',', value_name = "TYPES", help = "Marble: only emit these tile types, e.g. Straight,Curve90,TJunction,CrossJunction,Slope")]
+    pub allow_tiles: Vec<TileTypeArg>,
+
+    /// Marble: comma-separated tile types the level must not contain
+    #[arg(long = "exclude-tiles", value_delimiter = ',', value_name = "TYPES", help = "Marble: never emit these tile types, e.g. LoopDeLoop,HalfPipe")]
+    pub exclude_tiles: Vec<TileTypeArg>,
+
+    /// Marble: trace rivers downhill from high points (requires --enable-elevation)
+    #[arg(long = "enable-rivers", default_value_t = false, help = "Marble: trace rivers downhill (needs elevation)")]
+    pub enable_rivers: bool,
+
+    /// Marble: maximum number of rivers to trace
+    #[arg(long = "river-count", default_value_t = 2, help = "Marble: maximum number of rivers")]
+    pub river_count: u32,
+
+    /// Marble: replace slope runs dropping 3+ levels with spiral ramps
+    #[arg(long = "enable-spiral-ramps", default_value_t = false, help = "Marble: spiral ramps for big drops")]
+    pub enable_spiral_ramps: bool,
+
+    /// Marble: sink a funnel bowl draining one level down into large rooms
+    #[arg(long = "enable-funnels", default_value_t = false, help = "Marble: funnel bowls in large rooms")]
+    pub enable_funnels: bool,
+
+    /// Marble: most launch pads along the main path (0 for none)
+    #[arg(long = "max-launch-pads", default_value_t = 3, help = "Marble: most launch pads along the main path")]
+    pub max_launch_pads: u32,
+
+    /// Marble: make rooms open platforms with rim walls only on their outer boundary
+    #[arg(long = "open-bowl-rooms", default_value_t = false, help = "Marble: rooms as open bowls with rim walls")]
+    pub open_bowl_rooms: bool,
+
+    /// Marble: keep T-junctions instead of detecting smooth Y-junctions
+    #[arg(long = "no-y-junctions", default_value_t = false, help = "Marble: disable Y-junction detection")]
+    pub no_y_junctions: bool,
+
+    /// Marble: emit moving hazard markers (pendulums, rotating bars, timed gates)
+    #[arg(long = "enable-dynamics", default_value_t = false, help = "Marble: moving hazard markers on wide channels")]
+    pub enable_dynamics: bool,
+
+    /// Marble: maximum number of moving hazard markers
+    #[arg(long = "dynamic-count", default_value_t = 3, help = "Marble: maximum number of moving hazards")]
+    pub dynamic_count: u32,
+
+    /// Marble: build a 3D voxel map with elevated bridges, spirals, and drops
+    #[arg(long = "enable-voxels", default_value_t = false, help = "Marble: build 3D voxel map with bridges")]
+    pub enable_voxels: bool,
+
+    /// Marble: maximum number of elevated bridges in the voxel map
+    #[arg(long = "voxel-bridges", default_value_t = 2, help = "Marble: maximum number of voxel bridges")]
+    pub voxel_bridges: u32,
+
+    /// Marble: suggest bank angles for channel turns in the tile metadata
+    #[arg(long = "enable-banking", default_value_t = false, help = "Marble: bank_angle suggestions on channel turns")]
+    pub enable_banking: bool,
+
+    /// Settlement: road network style, grid or organic
+    #[arg(long = "road-layout", default_value = "grid", help = "Settlement: road layout grid|organic")]
+    pub road_layout: RoadLayoutArg,
+
+    /// Settlement: number of plazas to clear at road intersections
+    #[arg(long = "plazas", default_value_t = 1, help = "Settlement: number of plazas")]
+    pub plazas: u32,
+
+    /// Name the level, its rooms, and its zones from a theme's word lists
+    #[arg(long = "names", value_name = "THEME", help = "Name the level, rooms, and zones: dungeon|cavern|ruins|town|clockwork")]
+    pub names: Option<NameThemeArg>,
+
+    /// Room ceiling heights for 3D renderers, growing with room size
+    #[arg(long = "ceilings", value_name = "MIN..MAX", help = "Give rooms ceiling heights from MIN (smallest room) to MAX (largest room), e.g. 3..6")]
+    pub ceilings: Option<CeilingRangeArg>,
+
+    /// Highest corridor ceiling; corridors are also never higher than the rooms they join
+    #[arg(long = "corridor-ceiling", default_value_t = 2, help = "Highest corridor ceiling with --ceilings")]
+    pub corridor_ceiling: u32,
+
+    /// Required gaps in the map edges, each joined to the nearest room (repeatable)
+    #[arg(long = "edge-opening", value_name = "SIDE:CENTER:WIDTH", help = "Leave a gap in a map edge joined to the nearest room, e.g. north:40:3 (repeatable)")]
+    pub edge_openings: Vec<EdgeOpeningArg>,
+
+    /// Lock and key pairs of a mission placed on the rooms
+    #[arg(long = "mission", value_name = "LOCKS", help = "Plan a lock-and-key mission with LOCKS lock/key pairs, saved in the JSON")]
+    pub mission: Option<u32>,
+
+    /// Challenges along the mission's main line and key branches
+    #[arg(long = "mission-challenges", default_value_t = 2, help = "Challenges in the mission with --mission")]
+    pub mission_challenges: u32,
+
     /// X component of trend vector (horizontal direction for level generation)
     #[arg(long = "trend-x", help = "X component of trend vector (horizontal direction)")]
     pub trend_x: Option<f32>,
@@ -105,10 +684,26 @@ pub struct Args {
     #[arg(long = "start-z", help = "Starting point Z coordinate in world space")]
     pub start_z: Option<i32>,
 
+    /// How the entrance and exit rooms are picked without a starting point
+    #[arg(long = "endpoints", value_name = "RULE", help = "Pick entrance and exit rooms without a start point: farthest (default), elevation (highest to lowest), or first")]
+    pub endpoints: Option<EndpointsArg>,
+
     /// Maximum elevation change between adjacent rooms (only when elevation is enabled)
     #[arg(long = "max-elevation-change", default_value_t = 1, help = "Maximum elevation change between adjacent rooms")]
     pub max_elevation_change: i32,
 
+    /// Zoning pass: none, rooms (room clusters), or bands (distance bands from start)
+    #[arg(long = "zoning", default_value = "none", help = "Zoning pass: none|rooms|bands")]
+    pub zoning: ZoningArg,
+
+    /// Number of room clusters for `--zoning rooms`
+    #[arg(long = "zone-count", default_value_t = 4, help = "Number of room-cluster zones")]
+    pub zone_count: u32,
+
+    /// Band width in tiles for `--zoning bands`
+    #[arg(long = "zone-band-width", default_value_t = 12, help = "Distance band width in tiles")]
+    pub zone_band_width: u32,
+
     /// File path to write the generated level as JSON
     #[arg(long = "json-path", short = 'o', help = "Write level to JSON file path")] 
     pub json_path: Option<PathBuf>,
@@ -117,17 +712,125 @@ pub struct Args {
     #[arg(long = "print-json", default_value_t = false, help = "Print JSON to stdout")] 
     pub print_json: bool,
 
+    /// Format for `--json-path` and `--print-json`; RON and YAML need the `ron` and `yaml` features
+    #[arg(long = "format", default_value = "json", help = "Level output format: json|ron|yaml")]
+    pub format: FormatArg,
+
+    /// Add resolved connections and open edges to every marble tile in the JSON
+    #[arg(long = "tile-connections", default_value_t = false, help = "Include per-tile connections and open edges in JSON")]
+    pub tile_connections: bool,
+
+    /// Add a downsampled minimap with fog-of-war reveal regions to the JSON
+    #[arg(long = "minimap", value_name = "SCALE", help = "Include a minimap at SCALE tiles per cell, with reveal regions, in JSON")]
+    pub minimap: Option<u32>,
+
+    /// Add ambience zones (sound regions as polygons) to the JSON
+    #[arg(long = "ambience", default_value_t = false, help = "Include ambience zone polygons for audio in JSON")]
+    pub ambience: bool,
+
+    /// Add channel centerline splines to the JSON
+    #[arg(long = "splines", default_value_t = false, help = "Include Catmull-Rom centerline splines of the channels in JSON")]
+    pub splines: bool,
+
+    /// Write `tiles` and `marble_tiles` run-length encoded, as `tiles_rle`
+    #[arg(long = "tiles-rle", default_value_t = false, help = "Write the tile layers run-length encoded with a tile palette (tiles_rle) in JSON")]
+    pub tiles_rle: bool,
+
+    /// Write scalar layers as CSV or PGM (by extension), e.g. `--layer elevation=out/elevation.pgm`
+    #[arg(long = "layer", value_name = "LAYER=PATH", help = "Write a layer (elevation|distance|obstacles) to a .csv or .pgm file; repeatable")]
+    pub layers: Vec<LayerOutputArg>,
+
     /// Disable ASCII preview in stdout
     #[arg(long = "no-ascii", default_value_t = false, help = "Disable ASCII preview")] 
     pub no_ascii: bool,
 
+    /// Draw walkable tiles in the ASCII preview as their elevation digit
+    #[arg(long = "ascii-elevation", default_value_t = false, help = "Show floor elevations (0-9, or ten contour bands) in the ASCII preview")]
+    pub ascii_elevation: bool,
+
     /// File path to write isometric HTML visualization
     #[arg(long = "html-path", help = "Write isometric HTML visualization to file path")]
     pub html_path: Option<PathBuf>,
 
+    /// Print generation statistics (floor ratio, room counts, timings)
+    #[arg(long = "stats", default_value_t = false, help = "Print generation statistics")]
+    pub stats: bool,
+
+    /// Release this many marbles and print where they jam
+    #[arg(long = "traffic", value_name = "MARBLES", help = "Simulate MARBLES marbles from entrance to exit and print congestion hotspots")]
+    pub traffic: Option<u32>,
+
+    /// Marbles released together for `--traffic`
+    #[arg(long = "traffic-batch", default_value_t = 4, help = "Marbles released together with --traffic")]
+    pub traffic_batch: u32,
+
+    /// Ticks between batches for `--traffic`
+    #[arg(long = "traffic-interval", default_value_t = 2, help = "Ticks between marble batches with --traffic")]
+    pub traffic_interval: u32,
+
+    /// Print per-stage timings with their share of the total to stderr
+    #[arg(long = "bench-profile", default_value_t = false, help = "Print per-stage timings to stderr")]
+    pub bench_profile: bool,
+
     /// Only generate HTML visualization (skip ASCII and JSON output)
     #[arg(long = "html-only", default_value_t = false, help = "Only generate HTML visualization")]
     pub html_only: bool,
+
+    /// Generation algorithm version to reproduce (default: the latest)
+    #[arg(long = "algorithm-version", value_name = "N", help = "Reproduce levels of generation algorithm version N (default: latest)")]
+    pub algorithm_version: Option<u32>,
+
+    /// Subcommand; without one, a level is generated from the options above
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Subcommands that work on exported levels or tile data instead of generating a level.
+#[derive(Debug, Subcommand, Clone)]
+pub enum Command {
+    /// Compare two level JSON files and show the changed tiles
+    Diff {
+        /// Level JSON before the change
+        before: PathBuf,
+        /// Level JSON after the change
+        after: PathBuf,
+        /// File path to write an HTML page highlighting the changes
+        #[arg(long = "html-path", help = "Write highlighted diff HTML to file path")]
+        html_path: Option<PathBuf>,
+    },
+    /// Generate a series of levels with rising difficulty from the generation options given before the subcommand
+    Campaign {
+        /// Number of levels
+        #[arg(long = "levels", default_value_t = 10, help = "Number of levels in the campaign")]
+        levels: usize,
+        /// Levels generated per position to choose from
+        #[arg(long = "candidates", default_value_t = 8, help = "Levels generated per position to choose from")]
+        candidates: u32,
+        /// Directory to write `level_01.json`, `level_02.json`, ... to
+        #[arg(long = "out-dir", help = "Write each level as JSON into this directory")]
+        out_dir: Option<PathBuf>,
+    },
+    /// Generate one level per seed from the generation options given before the subcommand and write a page of thumbnails
+    Gallery {
+        /// Seeds to generate, end excluded
+        #[arg(long = "seeds", value_name = "START..END", default_value = "0..20", help = "Seeds to generate, e.g. 0..50 (end excluded)")]
+        seeds: SeedRangeArg,
+        /// File path of the gallery page; full views go into a directory of the same name
+        #[arg(long = "out", short = 'o', default_value = "gallery.html", help = "Write the gallery HTML to file path")]
+        out: PathBuf,
+    },
+    /// Check level JSON files against their checksums
+    Verify {
+        /// Level JSON files to check
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+    /// Print a JSON catalog of every marble tile type: connections per rotation, default walls, colors
+    TileCatalog {
+        /// File path to write the catalog to instead of stdout
+        #[arg(long = "json-path", short = 'o', help = "Write the catalog to JSON file path")]
+        json_path: Option<PathBuf>,
+    },
 }
 
 
@@ -0,0 +1,290 @@
+//! Core generation with `alloc` only.
+//!
+//! The part of generation that builds without the `std` feature, for
+//! handhelds and sandboxes without an operating system: rooms placed at
+//! random without overlap, joined in order of their center x by L-shaped
+//! corridors, and the marble tiles of the result. Each floor tile becomes
+//! an open platform inside a room or the track piece its floor neighbors
+//! call for in a corridor, with walls toward the void. Rooms draw from the
+//! [`SeedStage::Rooms`] stream and corridors from [`SeedStage::Corridors`].
+//!
+//! This is a smaller generator, not a `no_std` build of
+//! [`crate::dungeon::generate`]: the same params and seed give a different
+//! level. Rooms here are uniform in size, with no border, skirt, size
+//! distribution, guaranteed large rooms, or relaxed placement, and draw
+//! their random numbers in a different order. Marble tiles cover only the
+//! basic pieces, with T-junctions rotated as in
+//! [`TileType::base_connections`] rather than as classic marble levels
+//! rotate them.
+//! Only the corridor carving is shared with classic mode, so that part
+//! cannot drift: two rooms in the same order carve the same tiles. The
+//! `std` extras (elevation, obstacles, roles, statistics, export) are left
+//! out.
+//!
+//! ```
+//! use level_generator::core_gen::{generate_core, CoreParams};
+//!
+//! let level = generate_core(&CoreParams { seed: 7, ..Default::default() });
+//! assert!(!level.rooms.is_empty());
+//! let tiles = level.marble_tiles();
+//! assert_eq!(tiles.len(), level.height as usize);
+//! ```
+
+use alloc::vec;
+use alloc::vec::Vec;
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::seeds::{stage_rng, SeedStage};
+use crate::tiles::{compute_walls, Direction, Grid, MarbleTile, TileType, TILE_FLOOR, TILE_WALL};
+
+/// Minimum sensible map dimension to avoid degenerate results.
+pub const MIN_MAP_DIM: u32 = 10;
+/// Minimum sensible room dimension.
+pub const MIN_ROOM_DIM: u32 = 3;
+
+/// Placement attempts per requested room.
+const ATTEMPTS_PER_ROOM: u32 = 10;
+
+/// Parameters of [`generate_core`]; the same meaning as the
+/// [`GeneratorParams`](crate::dungeon::GeneratorParams) fields of the same name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoreParams {
+    /// Map width (at least [`MIN_MAP_DIM`])
+    pub width: u32,
+    /// Map height (at least [`MIN_MAP_DIM`])
+    pub height: u32,
+    /// Number of rooms to try to place
+    pub rooms: u32,
+    /// Minimum room side length (at least [`MIN_ROOM_DIM`])
+    pub min_room: u32,
+    /// Maximum room side length (at least `min_room + 1`)
+    pub max_room: u32,
+    /// Level seed
+    pub seed: u64,
+}
+
+impl Default for CoreParams {
+    fn default() -> Self {
+        Self { width: 80, height: 25, rooms: 12, min_room: 4, max_room: 10, seed: 0 }
+    }
+}
+
+/// Axis-aligned rectangular room.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoreRoom {
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+}
+
+impl CoreRoom {
+    /// Integer center of the room (floor division).
+    pub fn center(&self) -> (i32, i32) {
+        (self.x + self.w / 2, self.y + self.h / 2)
+    }
+
+    /// Whether tile `(x, y)` is inside the room.
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && x < self.x + self.w && y >= self.y && y < self.y + self.h
+    }
+
+    /// Whether the rooms overlap or touch.
+    fn touches(&self, other: &CoreRoom) -> bool {
+        self.x <= other.x + other.w && other.x <= self.x + self.w && self.y <= other.y + other.h && other.y <= self.y + self.h
+    }
+}
+
+/// Output of [`generate_core`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoreLevel {
+    pub width: u32,
+    pub height: u32,
+    /// Placed rooms, sorted by center x
+    pub rooms: Vec<CoreRoom>,
+    /// Walls and floors, row-major
+    pub grid: Grid,
+}
+
+impl CoreLevel {
+    /// Marble tiles of the grid: open platforms in rooms, track pieces
+    /// following the floor neighbors in corridors, walls toward the void.
+    pub fn marble_tiles(&self) -> Vec<Vec<MarbleTile>> {
+        let floor = |x: i32, y: i32| {
+            x >= 0 && y >= 0 && self.grid.get(y as usize).and_then(|row| row.get(x as usize)) == Some(&TILE_FLOOR)
+        };
+        let mut tiles: Vec<Vec<MarbleTile>> = (0..self.grid.len() as i32)
+            .map(|y| {
+                (0..self.width as i32)
+                    .map(|x| {
+                        if !floor(x, y) {
+                            MarbleTile::empty()
+                        } else if self.rooms.iter().any(|r| r.contains(x, y)) {
+                            MarbleTile::new(TileType::OpenPlatform)
+                        } else {
+                            let open = Direction::ALL.map(|d| {
+                                let (dx, dy) = d.to_delta();
+                                floor(x + dx, y + dy)
+                            });
+                            let (tile_type, rotation) = track_piece(open);
+                            MarbleTile::with_params(tile_type, 0, rotation, true)
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        compute_walls(&mut tiles);
+        tiles
+    }
+}
+
+/// Track piece and rotation joining the open sides (indexed by
+/// `Direction`); a dead end or a lone tile is a straight piece.
+fn track_piece(open: [bool; 4]) -> (TileType, u8) {
+    let candidates = match open.iter().filter(|&&o| o).count() {
+        4 => [TileType::CrossJunction].as_slice(),
+        3 => &[TileType::TJunction],
+        2 => &[TileType::Straight, TileType::Curve90],
+        _ => return (TileType::Straight, if open[Direction::East as usize] || open[Direction::West as usize] { 1 } else { 0 }),
+    };
+    for &tile_type in candidates {
+        for rotation in 0..4u8 {
+            let mut sides = [false; 4];
+            for d in tile_type.base_connections() {
+                sides[d.rotate(rotation) as usize] = true;
+            }
+            if sides == open {
+                return (tile_type, rotation);
+            }
+        }
+    }
+    (TileType::Straight, 0)
+}
+
+/// Generate rooms and corridors on a `width` x `height` grid; see the module
+/// docs.
+pub fn generate_core(params: &CoreParams) -> CoreLevel {
+    let (width, height) = (params.width.max(MIN_MAP_DIM), params.height.max(MIN_MAP_DIM));
+    let min_room = params.min_room.max(MIN_ROOM_DIM);
+    let max_room = params.max_room.max(min_room.saturating_add(1));
+    let mut grid = vec![vec![TILE_WALL; width as usize]; height as usize];
+
+    let mut rng = stage_rng(params.seed, SeedStage::Rooms);
+    let mut rooms: Vec<CoreRoom> = Vec::new();
+    for _ in 0..params.rooms.saturating_mul(ATTEMPTS_PER_ROOM) {
+        if rooms.len() as u32 == params.rooms {
+            break;
+        }
+        let w = rng.random_range(min_room..=max_room) as i32;
+        let h = rng.random_range(min_room..=max_room) as i32;
+        if w > width as i32 - 2 || h > height as i32 - 2 {
+            continue;
+        }
+        let room = CoreRoom { x: rng.random_range(1..=width as i32 - w - 1), y: rng.random_range(1..=height as i32 - h - 1), w, h };
+        if rooms.iter().all(|r| !r.touches(&room)) {
+            rooms.push(room);
+        }
+    }
+    rooms.sort_by_key(|r| r.center().0);
+    for room in &rooms {
+        for y in room.y..room.y + room.h {
+            for x in room.x..room.x + room.w {
+                grid[y as usize][x as usize] = TILE_FLOOR;
+            }
+        }
+    }
+
+    let mut rng = stage_rng(params.seed, SeedStage::Corridors);
+    for pair in rooms.windows(2) {
+        carve_corridor(&mut grid, pair[0].center(), pair[1].center(), &mut rng);
+    }
+    CoreLevel { width, height, rooms, grid }
+}
+
+/// Carve an L-shaped corridor from `a` to `b`, horizontal or vertical leg
+/// first at random.
+fn carve_corridor(grid: &mut Grid, (x1, y1): (i32, i32), (x2, y2): (i32, i32), rng: &mut StdRng) {
+    if rng.random_bool(0.5) {
+        carve_horizontal_tunnel(grid, x1, x2, y1);
+        carve_vertical_tunnel(grid, y1, y2, x2);
+    } else {
+        carve_vertical_tunnel(grid, y1, y2, x1);
+        carve_horizontal_tunnel(grid, x1, x2, y2);
+    }
+}
+
+/// Carve a horizontal tunnel from `x1..=x2` at row `y`.
+pub(crate) fn carve_horizontal_tunnel(grid: &mut [Vec<char>], x1: i32, x2: i32, y: i32) {
+    let (start, end) = if x1 <= x2 { (x1, x2) } else { (x2, x1) };
+    for x in start..=end {
+        set_floor(grid, x, y);
+    }
+}
+
+/// Carve a vertical tunnel from `y1..=y2` at column `x`.
+pub(crate) fn carve_vertical_tunnel(grid: &mut [Vec<char>], y1: i32, y2: i32, x: i32) {
+    let (start, end) = if y1 <= y2 { (y1, y2) } else { (y2, y1) };
+    for y in start..=end {
+        set_floor(grid, x, y);
+    }
+}
+
+/// Safely set the tile at `(x, y)` to floor if within bounds.
+pub(crate) fn set_floor(grid: &mut [Vec<char>], x: i32, y: i32) {
+    if y >= 0 && (y as usize) < grid.len() {
+        let row = &mut grid[y as usize];
+        if x >= 0 && (x as usize) < row.len() {
+            row[x as usize] = TILE_FLOOR;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    #[test]
+    fn core_levels_are_connected_and_tracks_line_up() {
+        let params = CoreParams { seed: 12, ..Default::default() };
+        let level = generate_core(&params);
+        assert_eq!(level, generate_core(&params));
+        assert!(level.rooms.len() >= 4);
+        assert!(level.rooms.windows(2).all(|pair| pair[0].center().0 <= pair[1].center().0));
+
+        // Every floor tile is reachable from the first room
+        let floors = level.grid.iter().flatten().filter(|&&c| c == TILE_FLOOR).count();
+        let (sx, sy) = level.rooms[0].center();
+        let mut seen = vec![vec![false; level.width as usize]; level.height as usize];
+        let mut queue = VecDeque::from([(sx, sy)]);
+        seen[sy as usize][sx as usize] = true;
+        let mut reached = 0;
+        while let Some((x, y)) = queue.pop_front() {
+            reached += 1;
+            for d in Direction::ALL {
+                let (nx, ny) = (x + d.to_delta().0, y + d.to_delta().1);
+                if level.grid[ny as usize][nx as usize] == TILE_FLOOR && !seen[ny as usize][nx as usize] {
+                    seen[ny as usize][nx as usize] = true;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+        assert_eq!(reached, floors);
+
+        // Corridor pieces open exactly toward their floor neighbors
+        let tiles = level.marble_tiles();
+        for (y, row) in tiles.iter().enumerate() {
+            for (x, tile) in row.iter().enumerate() {
+                assert_eq!(tile.tile_type == TileType::Empty, level.grid[y][x] != TILE_FLOOR);
+                if !tile.has_walls() {
+                    continue;
+                }
+                for d in tile.connections() {
+                    let (dx, dy) = d.to_delta();
+                    assert_ne!(tiles[(y as i32 + dy) as usize][(x as i32 + dx) as usize].tile_type, TileType::Empty);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! Dead-end trimming for Classic corridors.
+//!
+//! A dead end is a corridor floor tile outside every room with exactly one
+//! floor neighbor. [`remove_dead_ends`] handles a fraction of them. On square
+//! and octile grids it first tries to braid the dead end, carving a straight link of at
+//! most [`MAX_BRAID_LENGTH`] wall tiles from the tip to other floor so the
+//! corridor loops back into the map. Otherwise it fills the corridor back in,
+//! tile by tile, until it reaches a room or a junction. Filling only removes
+//! leaves and braiding only adds floor, so the floor stays connected.
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::dungeon::{Room, TILE_FLOOR, TILE_WALL};
+use crate::hex::GridTopology;
+
+/// Longest run of wall a braid link may cut through.
+pub const MAX_BRAID_LENGTH: i32 = 6;
+
+const SIDES: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+fn is_floor(grid: &[Vec<char>], x: i32, y: i32) -> bool {
+    y >= 0 && (y as usize) < grid.len() && x >= 0 && (x as usize) < grid[y as usize].len()
+        && grid[y as usize][x as usize] == TILE_FLOOR
+}
+
+fn floor_neighbors(grid: &[Vec<char>], topology: GridTopology, x: i32, y: i32) -> Vec<(i32, i32)> {
+    topology.neighbors(x, y).into_iter().filter(|&(nx, ny)| is_floor(grid, nx, ny)).collect()
+}
+
+/// Corridor tiles with exactly one floor neighbor, row-major.
+pub fn dead_ends(grid: &[Vec<char>], rooms: &[Room], topology: GridTopology) -> Vec<(i32, i32)> {
+    let mut tips = Vec::new();
+    for (y, row) in grid.iter().enumerate() {
+        for (x, &ch) in row.iter().enumerate() {
+            let (x, y) = (x as i32, y as i32);
+            if ch == TILE_FLOOR && !rooms.iter().any(|r| r.contains(x, y)) && floor_neighbors(grid, topology, x, y).len() == 1 {
+                tips.push((x, y));
+            }
+        }
+    }
+    tips
+}
+
+/// Wall tiles of a straight link from `(x, y)` to other floor, if one of at
+/// most [`MAX_BRAID_LENGTH`] tiles exists. Links never touch the map border.
+fn braid_link(grid: &[Vec<char>], x: i32, y: i32, back: (i32, i32), rng: &mut StdRng) -> Option<Vec<(i32, i32)>> {
+    let height = grid.len() as i32;
+    let width = if height > 0 { grid[0].len() as i32 } else { 0 };
+    let mut links: Vec<Vec<(i32, i32)>> = Vec::new();
+    for (dx, dy) in SIDES {
+        if (x + dx, y + dy) == back {
+            continue;
+        }
+        let mut path = Vec::new();
+        for i in 1..=MAX_BRAID_LENGTH + 1 {
+            let (nx, ny) = (x + dx * i, y + dy * i);
+            if nx <= 0 || ny <= 0 || nx >= width - 1 || ny >= height - 1 {
+                break;
+            }
+            if is_floor(grid, nx, ny) {
+                if !path.is_empty() {
+                    links.push(path);
+                }
+                break;
+            }
+            path.push((nx, ny));
+        }
+    }
+    if links.is_empty() {
+        return None;
+    }
+    Some(links.swap_remove(rng.random_range(0..links.len())))
+}
+
+/// Braid or fill in `fraction` (0.0 to 1.0) of the dead ends in `grid`.
+///
+/// Braiding is not tried on hex grids. Returns how many dead ends were
+/// removed.
+pub fn remove_dead_ends(
+    grid: &mut [Vec<char>],
+    rooms: &[Room],
+    topology: GridTopology,
+    fraction: f32,
+    rng: &mut StdRng,
+) -> usize {
+    let mut tips = dead_ends(grid, rooms, topology);
+    tips.shuffle(rng);
+    let target = (tips.len() as f32 * fraction.clamp(0.0, 1.0)).round() as usize;
+
+    let mut removed = 0;
+    for (x, y) in tips {
+        if removed == target {
+            break;
+        }
+        // An earlier braid or fill may already have changed this corridor
+        let neighbors = floor_neighbors(grid, topology, x, y);
+        if !is_floor(grid, x, y) || neighbors.len() != 1 {
+            continue;
+        }
+
+        if let Some(link) = (topology != GridTopology::Hex).then(|| braid_link(grid, x, y, neighbors[0], rng)).flatten() {
+            for (lx, ly) in link {
+                grid[ly as usize][lx as usize] = TILE_FLOOR;
+            }
+        } else {
+            let (mut cx, mut cy) = (x, y);
+            loop {
+                grid[cy as usize][cx as usize] = TILE_WALL;
+                match floor_neighbors(grid, topology, cx, cy)[..] {
+                    [(nx, ny)] if !rooms.iter().any(|r| r.contains(nx, ny)) && floor_neighbors(grid, topology, nx, ny).len() <= 1 => {
+                        (cx, cy) = (nx, ny);
+                    }
+                    _ => break,
+                }
+            }
+        }
+        removed += 1;
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn grid(rows: &[&str]) -> Vec<Vec<char>> {
+        rows.iter().map(|r| r.chars().collect()).collect()
+    }
+
+    #[test]
+    fn dead_ends_are_braided_or_filled() {
+        let rows = [
+            "##############",
+            "#...######...#",
+            "#............#",
+            "#...##.###...#",
+            "######.###...#",
+            "##########...#",
+            "##############",
+        ];
+        let rooms = vec![
+            Room { x: 1, y: 1, w: 3, h: 3, ..Default::default() },
+            Room { x: 10, y: 1, w: 3, h: 5, ..Default::default() },
+        ];
+        let mut rng = StdRng::seed_from_u64(3);
+
+        // The spur can reach the second room through three wall tiles
+        let mut braided = grid(&rows);
+        assert_eq!(dead_ends(&braided, &rooms, GridTopology::Square), vec![(6, 4)]);
+        assert_eq!(remove_dead_ends(&mut braided, &rooms, GridTopology::Square, 1.0, &mut rng), 1);
+        assert!((6..10).all(|x| braided[4][x] == TILE_FLOOR));
+
+        // Without a short link the spur is filled back to the main corridor
+        let mut filled = grid(&rows);
+        let short_rooms = [rooms[0].clone(), Room { h: 3, ..rooms[1].clone() }];
+        for row in &mut filled[4..6] {
+            row[10..13].fill(TILE_WALL);
+        }
+        assert_eq!(remove_dead_ends(&mut filled, &short_rooms, GridTopology::Square, 1.0, &mut rng), 1);
+        assert!(dead_ends(&filled, &short_rooms, GridTopology::Square).is_empty());
+        assert_eq!((filled[3][6], filled[4][6], filled[2][6]), (TILE_WALL, TILE_WALL, TILE_FLOOR));
+
+        let mut untouched = grid(&rows);
+        assert_eq!(remove_dead_ends(&mut untouched, &rooms, GridTopology::Square, 0.0, &mut rng), 0);
+        assert_eq!(untouched, grid(&rows));
+    }
+}
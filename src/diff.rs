@@ -0,0 +1,234 @@
+//! Tile-by-tile comparison of two levels.
+//!
+//! [`Level::diff`] lists every ASCII tile and marble tile that differs between
+//! two levels, so a change to a generation heuristic can be checked against
+//! golden seeds. Levels of different sizes are compared over the union of
+//! their bounds; tiles present in only one level have `None` on the other side.
+
+use serde::Serialize;
+use std::fmt;
+
+use crate::dungeon::{is_walkable_char, Level};
+use crate::tiles::MarbleTile;
+
+/// One ASCII tile that differs between two levels.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TileChange {
+    pub x: i32,
+    pub y: i32,
+    /// Tile in the first level (`None` if outside its bounds)
+    pub before: Option<char>,
+    /// Tile in the second level (`None` if outside its bounds)
+    pub after: Option<char>,
+}
+
+impl TileChange {
+    /// Marker used when rendering the change: `+` for a tile that became
+    /// walkable, `-` for one that stopped being walkable, `*` otherwise.
+    pub fn marker(&self) -> char {
+        let walkable = |ch: Option<char>| ch.is_some_and(is_walkable_char);
+        match (walkable(self.before), walkable(self.after)) {
+            (false, true) => '+',
+            (true, false) => '-',
+            _ => '*',
+        }
+    }
+}
+
+/// One marble tile that differs between two levels.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MarbleTileChange {
+    pub x: i32,
+    pub y: i32,
+    pub before: Option<MarbleTile>,
+    pub after: Option<MarbleTile>,
+}
+
+/// Differences between two levels, as returned by [`Level::diff`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LevelDiff {
+    /// `(width, height)` of the first level
+    pub size_before: (u32, u32),
+    /// `(width, height)` of the second level
+    pub size_after: (u32, u32),
+    pub rooms_before: usize,
+    pub rooms_after: usize,
+    /// Changed ASCII tiles, row-major
+    pub tiles: Vec<TileChange>,
+    /// Changed marble tiles, row-major (empty unless either level has marble tiles)
+    pub marble_tiles: Vec<MarbleTileChange>,
+}
+
+impl LevelDiff {
+    /// Returns true if the levels have the same size, room count, and tiles.
+    pub fn is_empty(&self) -> bool {
+        self.size_before == self.size_after
+            && self.rooms_before == self.rooms_after
+            && self.tiles.is_empty()
+            && self.marble_tiles.is_empty()
+    }
+
+    /// Render `after` as ASCII with every changed tile replaced by its
+    /// [`TileChange::marker`].
+    pub fn to_ascii(&self, after: &Level) -> String {
+        let mut grid = char_grid(after, self.size_before, self.size_after);
+        for change in &self.tiles {
+            grid[change.y as usize][change.x as usize] = change.marker();
+        }
+        grid.iter().map(|row| row.iter().collect::<String>()).collect::<Vec<_>>().join("\n")
+    }
+
+    /// Render a standalone HTML page showing `after` with changed tiles
+    /// highlighted; hovering a tile shows what it was before.
+    pub fn to_html(&self, after: &Level) -> String {
+        let grid = char_grid(after, self.size_before, self.size_after);
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html>\n<head>\n  <meta charset=\"UTF-8\">\n  <title>Level Diff</title>\n");
+        html.push_str("  <style>\n");
+        html.push_str("    body { margin: 0; padding: 20px; background: #1a1a1a; color: #ccc; font-family: Arial, sans-serif; }\n");
+        html.push_str("    pre { font-family: monospace; font-size: 14px; line-height: 1.1; }\n");
+        html.push_str("    .added { background: #2e7d32; color: #fff; }\n");
+        html.push_str("    .removed { background: #c62828; color: #fff; }\n");
+        html.push_str("    .changed { background: #f9a825; color: #000; }\n");
+        html.push_str("  </style>\n</head>\n<body>\n");
+        html.push_str(&format!("  <h1>Level Diff</h1>\n  <p>{}</p>\n  <pre>", self));
+
+        let mut changes = self.tiles.iter().peekable();
+        for (y, row) in grid.iter().enumerate() {
+            for (x, &ch) in row.iter().enumerate() {
+                match changes.next_if(|c| (c.x, c.y) == (x as i32, y as i32)) {
+                    Some(change) => {
+                        let class = match change.marker() {
+                            '+' => "added",
+                            '-' => "removed",
+                            _ => "changed",
+                        };
+                        let before = change.before.map_or("(none)".to_string(), |c| c.to_string());
+                        html.push_str(&format!(
+                            "<span class=\"{}\" title=\"({}, {}) was {}\">{}</span>",
+                            class, x, y, escape(before.as_str()), escape(&ch.to_string())
+                        ));
+                    }
+                    None => html.push_str(&escape(&ch.to_string())),
+                }
+            }
+            html.push('\n');
+        }
+        html.push_str("</pre>\n</body>\n</html>\n");
+        html
+    }
+}
+
+impl fmt::Display for LevelDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.size_before != self.size_after {
+            write!(
+                f,
+                "size {}x{} -> {}x{}, ",
+                self.size_before.0, self.size_before.1, self.size_after.0, self.size_after.1
+            )?;
+        }
+        if self.rooms_before != self.rooms_after {
+            write!(f, "rooms {} -> {}, ", self.rooms_before, self.rooms_after)?;
+        }
+        let count = |marker: char| self.tiles.iter().filter(|c| c.marker() == marker).count();
+        write!(
+            f,
+            "{} tiles changed (+{} -{} *{}), {} marble tiles changed",
+            self.tiles.len(),
+            count('+'),
+            count('-'),
+            count('*'),
+            self.marble_tiles.len()
+        )
+    }
+}
+
+impl Level {
+    /// Compare this level (before) against `other` (after), tile by tile.
+    pub fn diff(&self, other: &Level) -> LevelDiff {
+        let width = self.width.max(other.width) as usize;
+        let height = self.height.max(other.height) as usize;
+        let before = char_rows(self);
+        let after = char_rows(other);
+
+        let mut diff = LevelDiff {
+            size_before: (self.width, self.height),
+            size_after: (other.width, other.height),
+            rooms_before: self.rooms.len(),
+            rooms_after: other.rooms.len(),
+            ..Default::default()
+        };
+        let has_marble = self.marble_tiles.is_some() || other.marble_tiles.is_some();
+        for y in 0..height {
+            for x in 0..width {
+                let (a, b) = (char_at(&before, x, y), char_at(&after, x, y));
+                if a != b {
+                    diff.tiles.push(TileChange { x: x as i32, y: y as i32, before: a, after: b });
+                }
+                if has_marble {
+                    let (a, b) = (marble_at(self, x, y), marble_at(other, x, y));
+                    if a != b {
+                        diff.marble_tiles.push(MarbleTileChange {
+                            x: x as i32,
+                            y: y as i32,
+                            before: a.cloned(),
+                            after: b.cloned(),
+                        });
+                    }
+                }
+            }
+        }
+        diff
+    }
+}
+
+fn char_rows(level: &Level) -> Vec<Vec<char>> {
+    level.ascii_tiles().iter().map(|row| row.chars().collect()).collect()
+}
+
+fn char_at(rows: &[Vec<char>], x: usize, y: usize) -> Option<char> {
+    rows.get(y)?.get(x).copied()
+}
+
+fn marble_at(level: &Level, x: usize, y: usize) -> Option<&MarbleTile> {
+    level.marble_tiles.as_ref()?.get(y)?.get(x)
+}
+
+/// Tiles of `level` padded with spaces to the union of both sizes.
+fn char_grid(level: &Level, a: (u32, u32), b: (u32, u32)) -> Vec<Vec<char>> {
+    let (width, height) = (a.0.max(b.0) as usize, a.1.max(b.1) as usize);
+    let rows = char_rows(level);
+    (0..height)
+        .map(|y| (0..width).map(|x| char_at(&rows, x, y).unwrap_or(' ')).collect())
+        .collect()
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dungeon::{generate, GeneratorParams};
+
+    #[test]
+    fn diff_reports_carved_and_filled_tiles() {
+        let before = generate(&GeneratorParams { seed: Some(21), ..Default::default() });
+        assert!(before.diff(&before).is_empty());
+
+        let mut after = before.clone();
+        let (x, y) = after.rooms[0].center();
+        let mut rows: Vec<Vec<char>> = after.tiles.iter().map(|r| r.chars().collect()).collect();
+        rows[y as usize][x as usize] = '#';
+        rows[0][0] = '.';
+        after.tiles = rows.into_iter().map(|r| r.into_iter().collect()).collect();
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.tiles.len(), 2);
+        let ascii = diff.to_ascii(&after);
+        assert_eq!(ascii.lines().next().unwrap().chars().next(), Some('+'));
+        assert_eq!(ascii.lines().nth(y as usize).unwrap().chars().nth(x as usize), Some('-'));
+        assert!(diff.to_string().starts_with("2 tiles changed (+1 -1 *0)"));
+    }
+}
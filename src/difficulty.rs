@@ -0,0 +1,67 @@
+//! A single difficulty score per level.
+//!
+//! [`Level::difficulty`] folds the structural measures the analysis tools
+//! already report into one number between 0 and 1, for ordering levels in a
+//! campaign or bucketing a batch. Each term saturates, so no one measure
+//! dominates: a term is `x / (x + k)`, halfway at `k`.
+//!
+//! | Term | Measure | Halfway at | Weight |
+//! |------|---------|-----------:|-------:|
+//! | Length | main path length in tiles | 60 | 0.30 |
+//! | Hazards | obstacles plus moving hazards | 6 | 0.25 |
+//! | Branching | junctions (see [`Constraint::Junctions`]) | 8 | 0.15 |
+//! | Dead ends | corridor dead ends | 4 | 0.10 |
+//! | Drop | highest minus lowest passable elevation | 3 | 0.10 |
+//! | Tightness | share of the map that is not floor | - | 0.10 |
+//!
+//! Levels without a main path score 0 for length.
+//!
+//! [`Constraint::Junctions`]: crate::acceptance::Constraint::Junctions
+
+use crate::acceptance::junction_count;
+use crate::dead_ends::dead_ends;
+use crate::dungeon::Level;
+
+fn saturate(x: f32, half: f32) -> f32 {
+    x / (x + half)
+}
+
+impl Level {
+    /// Difficulty between 0 (trivial) and 1; see the module docs.
+    pub fn difficulty(&self) -> f32 {
+        let grid: Vec<Vec<char>> = self.ascii_tiles().iter().map(|row| row.chars().collect()).collect();
+        let length = self.main_path_length().unwrap_or(0) as f32;
+        let hazards = (self.obstacles.len() + self.dynamics.len()) as f32;
+        let junctions = junction_count(self) as f32;
+        let dead_ends = dead_ends(&grid, &self.rooms, self.topology).len() as f32;
+        let drop = self.stats.elevation_max.zip(self.stats.elevation_min).map_or(0, |(max, min)| max - min) as f32;
+        0.30 * saturate(length, 60.0)
+            + 0.25 * saturate(hazards, 6.0)
+            + 0.15 * saturate(junctions, 8.0)
+            + 0.10 * saturate(dead_ends, 4.0)
+            + 0.10 * saturate(drop, 3.0)
+            + 0.10 * (1.0 - self.stats.floor_ratio).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams};
+
+    #[test]
+    fn hazards_and_longer_paths_score_harder() {
+        let small = GeneratorParams { seed: Some(8), mode: GenerationMode::Marble, width: 40, height: 20, rooms: 3, ..Default::default() };
+        let big = GeneratorParams {
+            width: 100,
+            height: 40,
+            rooms: 16,
+            enable_obstacles: true,
+            enable_dynamics: true,
+            enable_elevation: true,
+            ..small.clone()
+        };
+        let (easy, hard) = (generate(&small).difficulty(), generate(&big).difficulty());
+        assert!((0.0..1.0).contains(&easy) && (0.0..1.0).contains(&hard));
+        assert!(hard > easy, "{} <= {}", hard, easy);
+    }
+}
@@ -14,27 +14,56 @@
 //! The result is a connected dungeon suitable for roguelike prototypes.
 //! The generator is seedable for reproducibility.
 use rand::rngs::StdRng;
-use rand::{Rng, SeedableRng};
-use serde::Serialize;
-use std::collections::VecDeque;
-use crate::tiles::{MarbleTile, Direction};
-
-/// 2D tile grid stored row-major as characters.
-pub type Grid = Vec<Vec<char>>;
-
-/// Wall tile character.
-pub const TILE_WALL: char = '#';
-/// Floor tile character.
-pub const TILE_FLOOR: char = '.';
-
-/// Minimum sensible map dimension to avoid degenerate results.
-pub const MIN_MAP_DIM: u32 = 10;
-/// Minimum sensible room dimension.
-pub const MIN_ROOM_DIM: u32 = 3;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+use std::time::Duration;
+use crate::budget::{CancelToken, Deadline};
+use crate::scratch::{reset, GeneratorScratch};
+use crate::graph::{connection_plan, room_connections, ConnectionStrategy, RoomGraph};
+use crate::hex::{hex_line, GridTopology};
+use crate::marble::{grid_to_marble_tiles, open_bowl_rooms, place_launch_pads, AdvancedTileConfig, MarbleTileConfig};
+use crate::ceilings::CeilingConfig;
+use crate::mission::{Mission, MissionConfig};
+use crate::naming::NameTheme;
+use crate::export::FORMAT_VERSION;
+use crate::versions::ALGORITHM_VERSION;
+use crate::generator::run_generator;
+use crate::overworld::TerrainTile;
+use crate::voxel::{add_bridges, VoxelMap};
+use crate::dead_ends::remove_dead_ends;
+use crate::dynamics::{place_dynamics, DynamicElement};
+use crate::anchors::add_anchors;
+use crate::gates::orient_gates;
+use crate::obstacles::{place_obstacles, Obstacle, ObstaclePattern, ObstacleShape};
+use crate::openings::EdgeOpening;
+use crate::structures::{place_funnels, place_spiral_ramps, Structure};
+use crate::settlement::RoadLayout;
+use crate::rivers::trace_rivers;
+use crate::ramp::DifficultyRamp;
+use crate::roles::{assign_roles_with, EndpointPlacement, RoomRole};
+use crate::routes::SideRoute;
+use crate::seeds::{stage_rng, SeedStage};
+use crate::stats::{LevelStats, StageTimer};
+use crate::tiles::{compute_walls, MarbleTile, TileType};
+use crate::vaults::VaultGuard;
+use crate::visibility::line;
+use crate::zones::{compute_zones, Zone, ZoneStrategy};
+
+pub use crate::core_gen::{MIN_MAP_DIM, MIN_ROOM_DIM};
+pub(crate) use crate::core_gen::{carve_horizontal_tunnel, carve_vertical_tunnel};
+use crate::core_gen::set_floor;
+pub use crate::tiles::{is_walkable_char, Grid, TILE_FLOOR, TILE_GRASS, TILE_ROCK, TILE_SECRET_DOOR, TILE_VOID, TILE_WALL, TILE_WATER};
 
 /// Axis-aligned rectangular room.
-#[derive(Debug, Clone, Copy, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Room {
+    /// Index of the room in `Level::rooms`, the number connections and
+    /// missions refer to it by
+    #[serde(default)]
+    pub id: usize,
     pub x: i32,
     pub y: i32,
     pub w: i32,
@@ -42,6 +71,17 @@ pub struct Room {
     /// Elevation level of this room (0 = ground level)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub elevation: Option<i32>,
+    /// Semantic role (entrance, exit, hub, ...) assigned after generation
+    pub role: RoomRole,
+    /// Flavor name such as "The Flooded Gallery" (see [`crate::naming`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Ceiling height in elevation steps (see [`crate::ceilings`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ceiling: Option<u32>,
+    /// What guards the one entrance of a vault (see [`crate::vaults`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guard: Option<VaultGuard>,
 }
 
 impl Room {
@@ -67,23 +107,126 @@ impl Room {
             self.y + self.h / 2,
         )
     }
+
+    /// Whether tile `(x, y)` is inside the room.
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && x < self.x + self.w && y >= self.y && y < self.y + self.h
+    }
+
+    /// The room grown by `margin` tiles on every side (shrunk for a negative
+    /// margin), keeping its other fields.
+    pub fn expanded(&self, margin: i32) -> Room {
+        Room { x: self.x - margin, y: self.y - margin, w: self.w + 2 * margin, h: self.h + 2 * margin, ..self.clone() }
+    }
+
+    /// The rectangle both rooms cover, if any, with this room's other fields.
+    pub fn intersection(&self, other: &Room) -> Option<Room> {
+        let (x, y) = (self.x.max(other.x), self.y.max(other.y));
+        let (w, h) = ((self.x + self.w).min(other.x + other.w) - x, (self.y + self.h).min(other.y + other.h) - y);
+        (w > 0 && h > 0).then(|| Room { x, y, w, h, ..self.clone() })
+    }
+
+    /// The room's outermost tiles, once each, clockwise from the top-left
+    /// corner.
+    pub fn perimeter_tiles(&self) -> Vec<(i32, i32)> {
+        if self.w <= 0 || self.h <= 0 {
+            return Vec::new();
+        }
+        let (right, bottom) = (self.x + self.w - 1, self.y + self.h - 1);
+        if self.w == 1 || self.h == 1 {
+            return (self.y..=bottom).flat_map(|y| (self.x..=right).map(move |x| (x, y))).collect();
+        }
+        let top = (self.x..right).map(|x| (x, self.y));
+        let east = (self.y..bottom).map(|y| (right, y));
+        let south = (self.x + 1..=right).rev().map(|x| (x, bottom));
+        let west = (self.y + 1..=bottom).rev().map(|y| (self.x, y));
+        top.chain(east).chain(south).chain(west).collect()
+    }
+
+    /// A uniformly random tile inside the room. Panics on an empty room.
+    pub fn random_point_in<R: Rng + ?Sized>(&self, rng: &mut R) -> (i32, i32) {
+        (rng.random_range(self.x..self.x + self.w), rng.random_range(self.y..self.y + self.h))
+    }
+}
+
+/// Set every room's `id` to its index.
+pub(crate) fn number_rooms(rooms: &mut [Room]) {
+    for (i, room) in rooms.iter_mut().enumerate() {
+        room.id = i;
+    }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(default)]
 pub struct Level {
+    /// JSON export format version (see [`crate::export`]): [`FORMAT_VERSION`]
+    /// for generated levels, the file's version for loaded ones (0 if it
+    /// predates versioning)
+    pub format_version: u32,
     /// Width of the level in tiles
     pub width: u32,
     /// Height of the level in tiles
     pub height: u32,
     /// RNG seed used to generate this level
     pub seed: u64,
+    /// Generation algorithm version the level was made with (see
+    /// [`crate::versions`]); 0 if it predates versioning
+    pub algorithm_version: u32,
+    /// Flavor title such as "The Sunken Depths" (see [`crate::naming`])
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
     /// Rooms that were placed on the map
     pub rooms: Vec<Room>,
+    /// Room-to-room connections as `(a, b)` index pairs into `rooms`, `a < b`
+    pub connections: Vec<(usize, usize)>,
+    /// Ceiling height of each connection's corridor, indexed like
+    /// `connections` (see [`crate::ceilings`])
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub corridor_ceilings: Vec<u32>,
+    /// Lock-and-key mission placed on the rooms (see [`crate::mission`])
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mission: Option<Mission>,
     /// ASCII tiles (row-major). `'#'` is wall, `'.'` is floor
     pub tiles: Vec<String>,
     /// Marble tile grid (optional, only for marble mode)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub marble_tiles: Option<Vec<Vec<MarbleTile>>>,
+    /// Terrain type and elevation per tile (overworld mode only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub terrain: Option<Vec<Vec<TerrainTile>>>,
+    /// River id per tile (row-major, `-1` elsewhere) when rivers are enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub river_map: Option<Vec<Vec<i32>>>,
+    /// Zone id per tile (row-major, `-1` for walls) when zoning is enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zone_map: Option<Vec<Vec<i32>>>,
+    /// Zones referenced by `zone_map`, indexed by id
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub zones: Vec<Zone>,
+    /// Obstacles placed in marble rooms, with their shapes and footprints
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub obstacles: Vec<Obstacle>,
+    /// Moving hazard markers on wide marble channels (not part of the tile grid)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dynamics: Vec<DynamicElement>,
+    /// Multi-tile marble structures (spiral ramps, ...)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub structures: Vec<Structure>,
+    /// Side routes carved off the main path (marble mode, when enabled)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub side_routes: Vec<SideRoute>,
+    /// Sparse 3D tile map with bridges and vertical connectors (marble mode, when enabled)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub voxels: Option<VoxelMap>,
+    /// Open plazas (settlement mode only)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub plazas: Vec<Room>,
+    /// Tile adjacency; hex levels use odd-r offset coordinates
+    #[serde(skip_serializing_if = "GridTopology::is_square")]
+    pub topology: GridTopology,
+    /// Generation statistics (floor ratio, room counts, timings, ...)
+    pub stats: LevelStats,
     // legend: '#' = wall, '.' = floor
 }
 
@@ -99,6 +242,23 @@ pub struct GeneratorParams {
     pub min_room: u32,
     /// Maximum room side length (at least `min_room + 1`)
     pub max_room: u32,
+    /// Distribution room side lengths are drawn from
+    pub room_size_distribution: RoomSizeDistribution,
+    /// Hub-scale rooms (`max_room` to 1.5x `max_room` per side) placed before
+    /// all other rooms; they count toward `rooms`
+    pub guaranteed_large_rooms: u32,
+    /// Room placement attempts per relaxation step (`None` uses `rooms * 10`,
+    /// at least 100)
+    pub room_attempts: Option<u32>,
+    /// When the attempts run out before `rooms` are placed, retry with rooms
+    /// allowed to touch, then also with sizes capped at the middle of the range
+    pub relax_room_placement: bool,
+    /// Classic and marble mode: solid wall tiles kept between the carved
+    /// level and the skirt (or the map edge)
+    pub border: u32,
+    /// Classic and marble mode: void tiles along the map edge, outside the
+    /// border, so exported meshes end on a clean wall rim
+    pub skirt: u32,
     /// Optional RNG seed for reproducible results
     pub seed: Option<u64>,
 
@@ -108,9 +268,14 @@ pub struct GeneratorParams {
     /// Marble mode: channel width in tiles
     pub channel_width: u32,
 
-    /// Marble mode: corner radius in tiles
+    /// Marble mode: smallest radius in tiles of the arc at a channel's turn
     pub corner_radius: u32,
 
+    /// Marble mode: how far (0.0 to 1.0) channel turns sweep into their
+    /// shorter leg. 0.0 turns on a `corner_radius` arc; 1.0 curves the whole
+    /// shorter leg, a quarter circle between rooms on a diagonal
+    pub channel_curvature: f32,
+
     /// Marble mode: enable elevation variation
     pub enable_elevation: bool,
 
@@ -123,6 +288,29 @@ pub struct GeneratorParams {
     /// Marble mode: obstacle density (0.0 to 1.0)
     pub obstacle_density: f32,
 
+    /// Marble mode: how strongly obstacle and moving hazard density rises
+    /// from the start to the goal (0.0 flat to 1.0; see [`crate::ramp`])
+    pub difficulty_ramp: f32,
+
+    /// Marble mode: obstacle shapes to pick from (uniformly) when placing obstacles
+    pub obstacle_shapes: Vec<ObstacleShape>,
+
+    /// Marble mode: obstacle layouts to pick from (uniformly) per room
+    pub obstacle_patterns: Vec<ObstaclePattern>,
+
+    /// Marble mode: passable tiles every obstacle must leave beside it across
+    /// its row and column (`None` uses `channel_width - 1`, at least 1)
+    pub obstacle_clearance: Option<u32>,
+
+    /// Marble mode: smallest distance in tiles between the centers of two
+    /// obstacles in the same room (Poisson-disk spacing)
+    pub obstacle_spacing: u32,
+
+    /// Marble mode: distance in tiles obstacles keep from the corridor mouths
+    /// of connected rooms (`None` uses 2 from algorithm version 2 on, 0
+    /// before)
+    pub obstacle_doorway_radius: Option<u32>,
+
     /// Optional 3D trend vector (x, y, z) in world coordinates for directional bias
     /// x, z: Horizontal direction (map to grid x, y)
     /// y: Vertical direction (influences elevation bias)
@@ -136,9 +324,141 @@ pub struct GeneratorParams {
     /// If not provided, use first placed room or grid center as reference
     pub start_point: Option<(i32, i32, i32)>,
 
+    /// How the entrance and exit rooms are picked without a `start_point`
+    /// (`None` uses `FarthestApart` from algorithm version 3 on, `FirstRoom`
+    /// before)
+    pub endpoints: Option<EndpointPlacement>,
+
     /// Maximum elevation change between adjacent rooms (only used when elevation is enabled)
     /// This constrains how much the elevation can differ between consecutive rooms
     pub max_elevation_change: i32,
+
+    /// Marble mode: trace rivers downhill from high points (requires elevation)
+    pub enable_rivers: bool,
+
+    /// Marble mode: maximum number of rivers to trace
+    pub river_count: u32,
+
+    /// Marble mode: replace steep slope runs (3+ levels) with spiral ramps
+    pub enable_spiral_ramps: bool,
+
+    /// Marble mode: sink a funnel bowl draining one level down into large rooms
+    pub enable_funnels: bool,
+
+    /// Marble mode: most launch pads to place along the main path (see
+    /// [`place_launch_pads`]); 0 places none
+    pub max_launch_pads: u32,
+
+    /// Marble mode: make rooms open bowls, open platforms with rim walls
+    /// only where the room borders void (see [`open_bowl_rooms`])
+    pub open_bowl_rooms: bool,
+
+    /// Marble mode: turn T-junctions whose branches split at a shallow angle
+    /// into Y-junctions
+    pub enable_y_junctions: bool,
+
+    /// Marble mode: emit moving hazard markers (pendulums, bars, gates) on wide channels
+    pub enable_dynamics: bool,
+
+    /// Marble mode: maximum number of moving hazard markers
+    pub dynamic_count: u32,
+
+    /// Marble mode: build a 3D voxel map with elevated bridges between rooms
+    pub enable_voxels: bool,
+
+    /// Marble mode: maximum number of elevated bridges in the voxel map
+    pub voxel_bridges: u32,
+
+    /// Marble mode: suggest a bank angle for every turn of the channels, in
+    /// the metadata of the tiles around it (see [`crate::banking`])
+    pub enable_banking: bool,
+
+    /// Optional zoning pass partitioning floor tiles into contiguous zones
+    pub zoning: ZoneStrategy,
+
+    /// Tile adjacency (square, hex, or octile). Hex is supported in classic
+    /// mode and octile in classic and marble mode (diagonal corridors); other
+    /// combinations generate square grids
+    pub topology: GridTopology,
+
+    /// Which room pairs to join with corridors (classic and marble mode)
+    pub connection_strategy: ConnectionStrategy,
+
+    /// Classic mode: fraction (0.0 to 1.0) of dead-end corridors to braid
+    /// into loops or fill in after carving
+    pub dead_end_removal: f32,
+
+    /// Classic mode: most secret passages to carve between neighboring
+    /// rooms without a direct connection (see [`crate::secrets`])
+    pub secret_passages: u32,
+
+    /// Most rooms to seal down to a single entrance as vaults (see
+    /// [`crate::vaults`])
+    pub vaults: u32,
+
+    /// What guards each vault's entrance, if anything
+    pub vault_guard: Option<VaultGuard>,
+
+    /// Marble mode: most side routes to carve off the main path and back
+    /// (see [`crate::routes`])
+    pub side_routes: u32,
+
+    /// Marble mode: fewest edge-disjoint routes from the entrance to the
+    /// exit, carving side routes across bottlenecks when short (see
+    /// [`crate::routes`])
+    pub min_alternate_routes: u32,
+
+    /// Settlement mode: road network style
+    pub road_layout: RoadLayout,
+
+    /// Settlement mode: number of plazas to clear at road intersections
+    pub plazas: u32,
+
+    /// Minimum length in tiles of the walk from the entrance to the exit
+    /// room. Shorter levels are regenerated from fresh seeds, up to
+    /// `path_retries` times; if none is long enough, the longest is returned
+    pub min_path_length: Option<u32>,
+    /// Regenerations allowed for `min_path_length`
+    pub path_retries: u32,
+
+    /// Fraction of the map (0.0 to 1.0) the level should fill, within
+    /// `floor_ratio_tolerance`; see [`crate::tile_budget`]
+    pub target_floor_ratio: Option<f32>,
+    pub floor_ratio_tolerance: f32,
+    /// Upper bound on `stats.tile_count` (track pieces in marble mode)
+    pub max_total_tiles: Option<u32>,
+    /// Adjustments of room count and channel width allowed for the tile budget
+    pub budget_retries: u32,
+
+    /// Theme to name the level, its rooms, and its zones from; `None`
+    /// leaves them unnamed (see [`crate::naming`])
+    pub naming: Option<NameTheme>,
+    /// Room and corridor ceiling heights for 3D renderers; `None` leaves
+    /// them unset (see [`crate::ceilings`])
+    pub ceilings: Option<CeilingConfig>,
+    /// Lock-and-key mission to grow and place on the rooms; `None` plans
+    /// none (see [`crate::mission`])
+    pub mission: Option<MissionConfig>,
+    /// Generation algorithm version to reproduce; `None` for the latest
+    /// (see [`crate::versions`])
+    pub algorithm_version: Option<u32>,
+    /// Marble tile types the level may contain; others are swapped for the
+    /// closest allowed type after every pass. `None` allows all (see
+    /// [`crate::allowed_tiles`])
+    pub allowed_tile_types: Option<HashSet<TileType>>,
+    /// Marble mode: keep the ASCII `tiles` next to `marble_tiles`. When
+    /// false `tiles` is left empty and [`Level::ascii_tiles`] derives it on
+    /// demand (see [`crate::memory`])
+    pub keep_ascii_tiles: bool,
+    /// Gaps the level must leave in its edges, each joined to the nearest
+    /// room by a corridor (see [`crate::openings`])
+    pub edge_openings: Vec<EdgeOpening>,
+
+    /// Wall-clock limit for generation; past it `generate` returns a
+    /// best-effort level (see [`crate::budget`])
+    pub time_budget: Option<Duration>,
+    /// Token that stops generation early when cancelled (see [`crate::budget`])
+    pub cancel: Option<CancelToken>,
 }
 
 impl Default for GeneratorParams {
@@ -149,27 +469,141 @@ impl Default for GeneratorParams {
             rooms: 12,
             min_room: 4,
             max_room: 10,
+            room_size_distribution: RoomSizeDistribution::Uniform,
+            guaranteed_large_rooms: 0,
+            room_attempts: None,
+            relax_room_placement: false,
+            border: 1,
+            skirt: 0,
             seed: None,
             mode: GenerationMode::Classic,
             channel_width: 2,
             corner_radius: 2,
+            channel_curvature: 0.5,
             enable_elevation: false,
             max_elevation: 2,
             enable_obstacles: false,
             obstacle_density: 0.3,
+            difficulty_ramp: 0.0,
+            obstacle_shapes: vec![ObstacleShape::Pillar],
+            obstacle_patterns: vec![ObstaclePattern::Scatter],
+            obstacle_clearance: None,
+            obstacle_spacing: 3,
+            obstacle_doorway_radius: None,
             trend_vector: None,
             trend_strength: 0.5,
             start_point: None,
+            endpoints: None,
             max_elevation_change: 1,
+            enable_rivers: false,
+            river_count: 2,
+            enable_spiral_ramps: false,
+            enable_funnels: false,
+            max_launch_pads: 3,
+            open_bowl_rooms: false,
+            enable_y_junctions: true,
+            enable_dynamics: false,
+            dynamic_count: 3,
+            enable_voxels: false,
+            voxel_bridges: 2,
+            enable_banking: false,
+            zoning: ZoneStrategy::None,
+            topology: GridTopology::Square,
+            connection_strategy: ConnectionStrategy::Chain,
+            dead_end_removal: 0.0,
+            secret_passages: 0,
+            vaults: 0,
+            vault_guard: None,
+            side_routes: 0,
+            min_alternate_routes: 0,
+            road_layout: RoadLayout::Grid,
+            plazas: 1,
+            min_path_length: None,
+            path_retries: 20,
+            target_floor_ratio: None,
+            floor_ratio_tolerance: 0.05,
+            max_total_tiles: None,
+            budget_retries: 10,
+            naming: None,
+            ceilings: None,
+            mission: None,
+            algorithm_version: None,
+            allowed_tile_types: None,
+            keep_ascii_tiles: true,
+            edge_openings: Vec::new(),
+            time_budget: None,
+            cancel: None,
         }
     }
 }
 
+impl GeneratorParams {
+    /// `obstacle_clearance`, or `channel_width - 1` when unset; at least 1.
+    pub(crate) fn effective_obstacle_clearance(&self) -> u32 {
+        self.obstacle_clearance.unwrap_or(self.channel_width.saturating_sub(1)).max(1)
+    }
+
+    /// `endpoints`, or the default of the algorithm version.
+    pub(crate) fn effective_endpoints(&self) -> EndpointPlacement {
+        self.endpoints.unwrap_or(if self.effective_algorithm_version() >= 3 { EndpointPlacement::FarthestApart } else { EndpointPlacement::FirstRoom })
+    }
+
+    /// `obstacle_doorway_radius`, or the default of the algorithm version.
+    pub(crate) fn effective_obstacle_doorway_radius(&self) -> u32 {
+        self.obstacle_doorway_radius.unwrap_or(if self.effective_algorithm_version() >= 2 { 2 } else { 0 })
+    }
+}
+
+/// Distribution of room side lengths between `min_room` and `max_room`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RoomSizeDistribution {
+    /// Every side length equally likely
+    #[default]
+    Uniform,
+    /// Side lengths cluster around the middle of the range
+    Normal,
+    /// Mostly small rooms with the occasional large one
+    Bimodal,
+}
+
+/// Share of rooms drawn from the large mode of [`RoomSizeDistribution::Bimodal`].
+const BIMODAL_LARGE_SHARE: f64 = 0.2;
+
+/// Placement attempts per guaranteed large room.
+const LARGE_ROOM_ATTEMPTS: usize = 50;
+
+impl RoomSizeDistribution {
+    /// Draw one side length in `min..=max`.
+    fn sample(&self, rng: &mut StdRng, min: i32, max: i32) -> i32 {
+        let span = (max - min) as f32;
+        let (mean, sd) = match self {
+            RoomSizeDistribution::Uniform => return rng.random_range(min..=max),
+            RoomSizeDistribution::Normal => (min as f32 + span / 2.0, span / 4.0),
+            RoomSizeDistribution::Bimodal if rng.random_bool(BIMODAL_LARGE_SHARE) => (min as f32 + span * 0.85, span / 8.0),
+            RoomSizeDistribution::Bimodal => (min as f32 + span * 0.15, span / 8.0),
+        };
+        (mean + sd * standard_normal(rng)).round().clamp(min as f32, max as f32) as i32
+    }
+}
+
+/// Approximate standard normal sample: the sum of twelve uniforms, less 6.
+/// Only additions, so unlike Box-Muller it does not depend on the platform's
+/// `ln`/`cos` and a seed gives the same sizes everywhere.
+fn standard_normal(rng: &mut StdRng) -> f32 {
+    (0..12).map(|_| rng.random::<f32>()).sum::<f32>() - 6.0
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum GenerationMode {
     Classic,
     Marble,
     Wfc,
+    /// Open heightmap terrain with points of interest joined by winding paths
+    Overworld,
+    /// Town with a road network, buildings along the roads, and plazas
+    Settlement,
+    /// Room archetypes collapsed on a coarse grid, each realized as a room
+    RoomWfc,
 }
 
 /// Normalize a 3D vector, returning (0, 0, 0) if the vector is zero or too small
@@ -188,6 +622,7 @@ fn normalize_vector(v: (f32, f32, f32)) -> (f32, f32, f32) {
 /// - candidate_center: candidate room center in grid coordinates (x, y)
 /// - trend_vector: normalized trend vector (x, y, z) in world coordinates
 /// - trend_strength: strength of bias (0.0 to 1.0)
+///
 /// Note: Grid (x, y) maps to world (x, z), so we use (trend_x, trend_z) for horizontal bias
 fn calculate_position_bias(
     reference_point: (i32, i32),
@@ -295,7 +730,7 @@ fn calculate_connection_bias(
         0.5 - vert_alignment * trend_strength * 0.5
     };
     
-    rng.random_bool(horizontal_preference as f64)
+    rng.random_bool(horizontal_preference.clamp(0.0, 1.0) as f64)
 }
 
 /// Select a candidate from a weighted list using weighted random selection
@@ -310,7 +745,7 @@ fn select_weighted_candidate<R: Rng>(rng: &mut R, candidates: &[(Room, f32)]) ->
     
     if total_weight <= 0.0 {
         // Fallback to uniform selection if all weights are non-positive
-        return candidates.first().map(|(room, _)| *room);
+        return candidates.first().map(|(room, _)| room.clone());
     }
     
     // Pick random value in [0, total_weight)
@@ -321,39 +756,67 @@ fn select_weighted_candidate<R: Rng>(rng: &mut R, candidates: &[(Room, f32)]) ->
     for (room, weight) in candidates {
         cumulative_weight += weight;
         if random_value < cumulative_weight {
-            return Some(*room);
+            return Some(room.clone());
         }
     }
     
     // Fallback (shouldn't happen, but safety)
-    candidates.first().map(|(room, _)| *room)
+    candidates.first().map(|(room, _)| room.clone())
 }
 
-/// Generate a new `Level` using basic room placement and corridor connectivity.
+/// Generate a new `Level` with the built-in generator for `params.mode`
+/// (see [`crate::generator`]).
 pub fn generate(params: &GeneratorParams) -> Level {
-    let width = params.width.max(MIN_MAP_DIM);
-    let height = params.height.max(MIN_MAP_DIM);
+    generate_with_scratch(params, &mut GeneratorScratch::default())
+}
+
+/// [`generate`], reusing the working buffers in `scratch` (see [`crate::scratch`]).
+pub fn generate_with_scratch(params: &GeneratorParams, scratch: &mut GeneratorScratch) -> Level {
+    run_generator(params.mode.generator(), params, scratch)
+}
+
+/// Map width and height and the room side limits, raised to the supported minimums.
+pub(crate) fn dimensions(params: &GeneratorParams) -> (u32, u32, u32, u32) {
     let min_room = params.min_room.max(MIN_ROOM_DIM);
-    let max_room = params.max_room.max(min_room + 1);
+    (params.width.max(MIN_MAP_DIM), params.height.max(MIN_MAP_DIM), min_room, params.max_room.max(min_room.saturating_add(1)))
+}
 
-    let seed = params.seed.unwrap_or_else(|| {
-        // derive a seed from thread_rng for reproducibility in output
-        let mut tr = rand::rng();
-        tr.random()
-    });
-    let mut rng = StdRng::seed_from_u64(seed);
+/// WFC mode: a pipe tilemap collapsed over the whole map.
+pub(crate) fn wfc_level(params: &GeneratorParams, seed: u64, rng: &mut StdRng, scratch: &mut GeneratorScratch) -> Level {
+    let (width, height, _, _) = dimensions(params);
+    let mut timer = StageTimer::start();
+    let deadline = Deadline::new(params);
+    let topology = GridTopology::Square;
+    let tiles = generate_wfc_tilemap(width as usize, height as usize, rng, &deadline, scratch);
+    timer.mark("wfc");
+    let zoning = compute_zones(&tiles, &[], &RoomGraph::default(), params.zoning, topology);
+    timer.mark("zones");
+    let (zone_map, zones) = zoning.map_or((None, Vec::new()), |z| (Some(z.zone_map), z.zones));
+    let mut level = Level { format_version: FORMAT_VERSION, width, height, seed, algorithm_version: ALGORITHM_VERSION, name: None, rooms: Vec::new(), connections: Vec::new(), corridor_ceilings: Vec::new(), mission: None, tiles, marble_tiles: None, terrain: None, river_map: None, obstacles: Vec::new(), dynamics: Vec::new(), structures: Vec::new(), side_routes: Vec::new(), voxels: None, zone_map, zones, plazas: Vec::new(), topology, stats: LevelStats::default() };
+    level.stats = LevelStats::compute(&level, params.rooms, timer.finish());
+    level.stats.budget_exceeded = deadline.expired();
+    level
+}
 
-    // Early exit for WFC mode: generate a tilemap entirely via WFC
-    if matches!(params.mode, GenerationMode::Wfc) {
-        let tiles = generate_wfc_tilemap(width as usize, height as usize, &mut rng);
-        return Level { width, height, seed, rooms: Vec::new(), tiles, marble_tiles: None };
-    }
+/// Classic and marble mode: rooms placed on a wall grid and joined by
+/// corridors, or by wide channels turned into marble tiles when `marble` is set.
+pub(crate) fn room_level(params: &GeneratorParams, seed: u64, marble: bool, scratch: &mut GeneratorScratch) -> Level {
+    let (width, height, min_room, max_room) = dimensions(params);
+    let mut timer = StageTimer::start();
+    let deadline = Deadline::new(params);
+    let topology = match (marble, params.topology) {
+        (false, topology) => topology,
+        (true, GridTopology::Octile) => GridTopology::Octile,
+        _ => GridTopology::Square,
+    };
 
-    let mut grid: Grid = vec![vec![TILE_WALL; width as usize]; height as usize];
+    let mut grid = std::mem::take(&mut scratch.grid);
+    reset(&mut grid, width as usize, height as usize, TILE_WALL);
     let mut rooms: Vec<Room> = Vec::new();
+    let mut rng = stage_rng(seed, SeedStage::Rooms);
 
     // Pre-calculate normalized trend vector if provided
-    let normalized_trend = params.trend_vector.map(|v| normalize_vector(v));
+    let normalized_trend = params.trend_vector.map(normalize_vector);
     
     // Determine initial reference point for bias calculation
     let initial_reference = if let Some((sx, _sy, sz)) = params.start_point {
@@ -364,26 +827,60 @@ pub fn generate(params: &GeneratorParams) -> Level {
         (width as i32 / 2, height as i32 / 2)
     };
 
-    let attempts = (params.rooms * 10).max(100);
-    for _ in 0..attempts {
-        if rooms.len() as u32 >= params.rooms { break; }
+    // Hub-scale rooms go first, while the map is still empty
+    let marble_elevation = params.enable_elevation && marble;
+    // Rooms stay clear of the skirt and the border inside it
+    let edge = params.skirt.saturating_add(params.border) as i32;
+    for _ in 0..params.guaranteed_large_rooms.min(params.rooms) {
+        if deadline.expired() {
+            break;
+        }
+        // Hub sizes never exceed the map, whatever max_room asks for
+        let side = max_room.min(width.max(height));
+        let large = side as i32..=(side.saturating_mul(3) / 2).min(width.max(height)) as i32;
+        let w = rng.random_range(large.clone()).min(width as i32 - 2 * edge - 4);
+        let h = rng.random_range(large).min(height as i32 - 2 * edge - 4);
+        if w < min_room as i32 || h < min_room as i32 {
+            break;
+        }
+        for _ in 0..LARGE_ROOM_ATTEMPTS {
+            let x = rng.random_range(edge..=(width as i32 - w - edge - 1));
+            let y = rng.random_range(edge..=(height as i32 - h - edge - 1));
+            let candidate = Room { x, y, w, h, elevation: marble_elevation.then_some(0), role: RoomRole::Normal, name: None, ceiling: None, guard: None, id: 0 };
+            if !rooms.iter().any(|r| intersects_with_margin(r, &candidate, 1)) {
+                carve_room(&mut grid, &candidate);
+                rooms.push(candidate);
+                break;
+            }
+        }
+    }
 
-        let w = rng.random_range(min_room as i32..=max_room as i32);
-        let h = rng.random_range(min_room as i32..=max_room as i32);
+    // Relaxation steps: 0 = as requested, 1 = rooms may touch, 2 = also at most mid-range sizes
+    let attempts = params.room_attempts.unwrap_or(params.rooms.saturating_mul(10).max(100)) as usize;
+    let steps = if params.relax_room_placement { 3 } else { 1 };
+    let mut relaxation = 0;
+    for step in (0..steps).flat_map(|step| std::iter::repeat_n(step, attempts)) {
+        if rooms.len() as u32 >= params.rooms || deadline.expired() { break; }
+        relaxation = step;
+        let margin = if step == 0 { 1 } else { 0 };
+        let max_side = if step < 2 { max_room } else { min_room.max(min_room.midpoint(max_room)) };
 
-        if w >= width as i32 - 4 || h >= height as i32 - 4 { continue; }
+        let w = params.room_size_distribution.sample(&mut rng, min_room as i32, max_side as i32);
+        let h = params.room_size_distribution.sample(&mut rng, min_room as i32, max_side as i32);
+
+        if w >= width as i32 - 2 * edge - 2 || h >= height as i32 - 2 * edge - 2 { continue; }
 
         // Generate multiple candidates and pick one with weighted selection
         let candidate_pool_size = if normalized_trend.is_some() { 5 } else { 1 };
         let mut candidates: Vec<(Room, f32)> = Vec::new();
 
         for _ in 0..candidate_pool_size {
-            let x = rng.random_range(1..=(width as i32 - w - 2));
-            let y = rng.random_range(1..=(height as i32 - h - 2));
+            let x = rng.random_range(edge..=(width as i32 - w - edge - 1));
+            let y = rng.random_range(edge..=(height as i32 - h - edge - 1));
 
             // Assign elevation if enabled, with bias if trend vector provided
             // Constrain elevation change relative to the last placed room
-            let elevation = if params.enable_elevation && matches!(params.mode, GenerationMode::Marble) {
+            let elevation = if marble_elevation {
                 // Get the elevation of the last placed room, or 0 if this is the first room
                 let last_elevation = rooms.last()
                     .and_then(|r| r.elevation)
@@ -404,22 +901,33 @@ pub fn generate(params: &GeneratorParams) -> Level {
                 };
                 
                 // Apply trend bias if provided
-                if let Some(trend) = normalized_trend {
+                let elev = if let Some(trend) = normalized_trend.filter(|_| min_allowed_elev <= max_allowed_elev) {
                     let elev_bias = calculate_elevation_bias(trend, params.trend_strength, params.max_elevation);
-                    let biased_elev = (base_elev + elev_bias)
-                        .clamp(min_allowed_elev, max_allowed_elev);
-                    Some(biased_elev)
+                    (base_elev + elev_bias)
+                        .clamp(min_allowed_elev, max_allowed_elev)
                 } else {
-                    Some(base_elev)
+                    base_elev
+                };
+
+                // No room may be more levels apart from another than tiles apart,
+                // so every corridor between them can ramp one level per step
+                let footprint = Room { x, y, w, h, elevation: None, role: RoomRole::Normal, name: None, ceiling: None, guard: None, id: 0 };
+                let (lo, hi) = rooms.iter().fold((min_allowed_elev, max_allowed_elev), |(lo, hi), r| {
+                    let (e, gap) = (r.elevation.unwrap_or(0), tile_gap(r, &footprint));
+                    (lo.max(e - gap), hi.min(e + gap))
+                });
+                if lo > hi {
+                    continue;
                 }
+                Some(elev.clamp(lo, hi))
             } else {
                 None
             };
 
-            let candidate = Room { x, y, w, h, elevation };
+            let candidate = Room { x, y, w, h, elevation, role: RoomRole::Normal, name: None, ceiling: None, guard: None, id: 0 };
 
             // Check for overlap
-            if rooms.iter().any(|r| intersects_with_margin(r, &candidate, 1)) {
+            if rooms.iter().any(|r| intersects_with_margin(r, &candidate, margin)) {
                 continue;
             }
 
@@ -449,13 +957,33 @@ pub fn generate(params: &GeneratorParams) -> Level {
         }
     }
 
+    timer.mark("rooms");
+
     // connect rooms depending on the chosen mode
     rooms.sort_by_key(|r| r.center().0);
-    match params.mode {
-        GenerationMode::Classic => {
-            for i in 1..rooms.len() {
-                let (x1, y1) = rooms[i - 1].center();
-                let (x2, y2) = rooms[i].center();
+    let plan = connection_plan(&rooms, params.connection_strategy);
+    let mut rng = stage_rng(seed, SeedStage::Corridors);
+    match marble {
+        false if topology == GridTopology::Hex => {
+            // Straight hex lines between consecutive room centers
+            for &(a, b) in &plan {
+                for (x, y) in hex_line(rooms[a].center(), rooms[b].center()) {
+                    set_floor(&mut grid, x, y);
+                }
+            }
+        }
+        false if topology == GridTopology::Octile => {
+            // Straight diagonal lines between consecutive room centers
+            for &(a, b) in &plan {
+                for (x, y) in line(rooms[a].center(), rooms[b].center()) {
+                    set_floor(&mut grid, x, y);
+                }
+            }
+        }
+        false => {
+            for &(a, b) in &plan {
+                let (x1, y1) = rooms[a].center();
+                let (x2, y2) = rooms[b].center();
                 let use_horizontal_first = calculate_connection_bias(
                     (x1, y1),
                     (x2, y2),
@@ -472,12 +1000,28 @@ pub fn generate(params: &GeneratorParams) -> Level {
                 }
             }
         }
-        GenerationMode::Marble => {
+        true if topology == GridTopology::Octile => {
+            let half = params.channel_width.max(1) as i32 / 2;
+            for &(a, b) in &plan {
+                for (x, y) in line(rooms[a].center(), rooms[b].center()) {
+                    for dy in -half..=half {
+                        for dx in -half..=half {
+                            set_floor(&mut grid, x + dx, y + dy);
+                        }
+                    }
+                }
+            }
+            // Marble tiles only join along edges, so give every diagonal step a shared side
+            close_diagonal_gaps(&mut grid);
+        }
+        true => {
             let w = params.channel_width.max(1) as i32;
-            let r = params.corner_radius.max(0) as i32;
-            for i in 1..rooms.len() {
-                let (x1, y1) = rooms[i - 1].center();
-                let (x2, y2) = rooms[i].center();
+            for &(a, b) in &plan {
+                let (x1, y1) = rooms[a].center();
+                let (x2, y2) = rooms[b].center();
+                // The arc grows with the shorter leg, from `corner_radius` up to the whole leg
+                let leg = (x2 - x1).abs().min((y2 - y1).abs());
+                let r = (params.corner_radius as i32).max((params.channel_curvature.clamp(0.0, 1.0) * leg as f32).round() as i32);
                 let use_horizontal_first = calculate_connection_bias(
                     (x1, y1),
                     (x2, y2),
@@ -485,16 +1029,16 @@ pub fn generate(params: &GeneratorParams) -> Level {
                     params.trend_strength,
                     &mut rng,
                 );
-                if use_horizontal_first {
-                    carve_wide_horizontal_with_rounded_turn(&mut grid, x1, x2, y1, w, r, true);
-                    carve_wide_vertical(&mut grid, y1, y2, x2, w);
-                } else {
-                    carve_wide_vertical_with_rounded_turn(&mut grid, y1, y2, x1, w, r, true);
-                    carve_wide_horizontal(&mut grid, x1, x2, y2, w);
-                }
+                carve_arc_channel(&mut grid, (x1, y1), (x2, y2), use_horizontal_first, w, r);
             }
         }
-        GenerationMode::Wfc => unreachable!("handled earlier"),
+    }
+    apply_border(&mut grid, params.border, params.skirt);
+    timer.mark("connect");
+
+    if !marble && params.dead_end_removal > 0.0 {
+        remove_dead_ends(&mut grid, &rooms, topology, params.dead_end_removal, &mut rng);
+        timer.mark("dead_ends");
     }
 
     let tiles: Vec<String> = grid
@@ -502,20 +1046,84 @@ pub fn generate(params: &GeneratorParams) -> Level {
         .map(|row| row.iter().collect())
         .collect();
 
+    // Derive the real connection graph from the carved tiles and label rooms
+    let connections = room_connections(&tiles, &rooms, topology);
+    let graph = RoomGraph::from_edges(rooms.len(), &connections);
+    assign_roles_with(&mut rooms, &graph, params.start_point.map(|(sx, _sy, sz)| (sx, sz)), params.effective_endpoints());
+    timer.mark("roles");
+
+    let zoning = compute_zones(&tiles, &rooms, &graph, params.zoning, topology);
+    let (zone_map, zones) = zoning.map_or((None, Vec::new()), |z| (Some(z.zone_map), z.zones));
+    timer.mark("zones");
+
     // Generate marble tile grid for marble mode
-    let marble_tiles = if matches!(params.mode, GenerationMode::Marble) {
+    let mut structures: Vec<Structure> = Vec::new();
+    let mut obstacles: Vec<Obstacle> = Vec::new();
+    let mut dynamics: Vec<DynamicElement> = Vec::new();
+    let marble_tiles = if marble {
         // Create elevation map for corridors if elevation is enabled
-        let elevation_map = if params.enable_elevation {
-            create_corridor_elevation_map(&grid, &rooms, width as usize, height as usize)
+        let mut elevation_map = std::mem::take(&mut scratch.elevation);
+        if params.enable_elevation {
+            create_corridor_elevation_map(&grid, &rooms, &mut elevation_map, scratch);
+            timer.mark("elevation");
         } else {
-            vec![vec![0; width as usize]; height as usize]
-        };
+            reset(&mut elevation_map, width as usize, height as usize, 0);
+        }
         
-        let mut tiles = grid_to_marble_tiles(&grid, &rooms, params.enable_elevation, &elevation_map);
+        let config = MarbleTileConfig {
+            rooms: &rooms,
+            elevation: params.enable_elevation.then_some(&elevation_map[..]),
+            advanced: AdvancedTileConfig { y_junctions: params.enable_y_junctions, ..Default::default() },
+        };
+        let mut tiles = grid_to_marble_tiles(&grid, &config);
+        scratch.elevation = elevation_map;
+        timer.mark("marble_tiles");
+
+        // Decorative stages are the first to go when time runs out
+        let optional = !deadline.expired();
+        if params.enable_spiral_ramps && optional {
+            structures.extend(place_spiral_ramps(&mut tiles, structures.len()));
+            timer.mark("spiral_ramps");
+        }
+
+        if params.enable_funnels && optional {
+            structures.extend(place_funnels(&mut tiles, &rooms, structures.len()));
+            timer.mark("funnels");
+        }
         
+        // Obstacles have no walls and never border empty tiles, so walls can be settled first
+        compute_walls(&mut tiles);
+        if params.open_bowl_rooms {
+            open_bowl_rooms(&mut tiles, &rooms);
+        }
+
+        let ramp = DifficultyRamp::new(&grid, &rooms, topology, params.difficulty_ramp);
+
         // Place obstacles in large rooms if enabled
-        if params.enable_obstacles {
-            place_obstacles_in_rooms(&mut tiles, &rooms, &mut rng, params.obstacle_density);
+        if params.enable_obstacles && optional {
+            obstacles = place_obstacles(
+                &mut tiles,
+                &rooms,
+                &connections,
+                params.effective_obstacle_doorway_radius(),
+                &params.obstacle_shapes,
+                &params.obstacle_patterns,
+                params.obstacle_density,
+                &ramp,
+                params.effective_obstacle_clearance(),
+                params.obstacle_spacing,
+                0,
+                &mut stage_rng(seed, SeedStage::Obstacles),
+            );
+            timer.mark("obstacles");
+        }
+        place_launch_pads(&mut tiles, &rooms, params.max_launch_pads as usize);
+        orient_gates(&mut tiles, &rooms);
+        add_anchors(&mut tiles, &obstacles);
+
+        if params.enable_dynamics && optional {
+            dynamics = place_dynamics(&tiles, &rooms, params.dynamic_count, &ramp, &mut stage_rng(seed, SeedStage::Dynamics));
+            timer.mark("dynamics");
         }
         
         Some(tiles)
@@ -523,662 +1131,137 @@ pub fn generate(params: &GeneratorParams) -> Level {
         None
     };
 
-    Level { width, height, seed, rooms, tiles, marble_tiles }
+    scratch.grid = grid;
+
+    // Trace rivers downhill across the final marble elevations
+    let river_map = match &marble_tiles {
+        Some(tiles) if params.enable_elevation && params.enable_rivers && !deadline.expired() => {
+            let map = trace_rivers(tiles, &rooms, params.river_count);
+            timer.mark("rivers");
+            Some(map)
+        }
+        _ => None,
+    };
+
+    // Lift marble tiles into 3D and add elevated bridges
+    let voxels = match &marble_tiles {
+        Some(tiles) if params.enable_voxels && !deadline.expired() => {
+            let mut map = VoxelMap::from_tiles(tiles);
+            add_bridges(&mut map, &rooms, &connections, params.voxel_bridges);
+            timer.mark("voxels");
+            Some(map)
+        }
+        _ => None,
+    };
+
+    let mut level = Level { format_version: FORMAT_VERSION, width, height, seed, algorithm_version: ALGORITHM_VERSION, name: None, rooms, connections, corridor_ceilings: Vec::new(), mission: None, tiles, marble_tiles, terrain: None, river_map, obstacles, dynamics, structures, side_routes: Vec::new(), voxels, zone_map, zones, plazas: Vec::new(), topology, stats: LevelStats::default() };
+    number_rooms(&mut level.rooms);
+    level.stats = LevelStats::compute(&level, params.rooms, timer.finish());
+    level.stats.room_relaxation = relaxation;
+    level.stats.budget_exceeded = deadline.expired();
+    level
+}
+
+/// Fewest 4-neighbor steps from a tile of `a` to a tile of `b` (0 if they overlap).
+fn tile_gap(a: &Room, b: &Room) -> i32 {
+    let dx = (b.x - (a.x + a.w - 1)).max(a.x - (b.x + b.w - 1)).max(0);
+    let dy = (b.y - (a.y + a.h - 1)).max(a.y - (b.y + b.h - 1)).max(0);
+    dx + dy
 }
 
 /// Whether `a`, expanded by `margin` tiles on each side, intersects `b`.
-fn intersects_with_margin(a: &Room, b: &Room, margin: i32) -> bool {
-    let a_expanded = Room { 
-        x: a.x - margin, 
-        y: a.y - margin, 
-        w: a.w + 2*margin, 
-        h: a.h + 2*margin,
-        elevation: a.elevation,
-    };
-    a_expanded.intersects(b)
+pub(crate) fn intersects_with_margin(a: &Room, b: &Room, margin: i32) -> bool {
+    a.expanded(margin).intersects(b)
 }
 
-/// Create elevation map for corridors between rooms with different elevations
-/// This creates smooth transitions with slope tiles where elevation changes
+/// Fill `elevation_map` with corridor elevations between rooms with different elevations.
+///
+/// Room tiles keep their room's elevation. Every other floor tile gets an
+/// upper bound, the lowest `room elevation + path distance` over all rooms,
+/// and a lower bound, the highest `room elevation - path distance`, each found
+/// in one Dijkstra sweep out of the rooms. Both bounds change by at most one
+/// level per step, so the midpoint between them does too: corridors ramp
+/// monotonically from one room's elevation to the other's, one level at a
+/// time, whenever they are long enough. Floor no room reaches stays at 0.
 fn create_corridor_elevation_map(
     grid: &Grid,
     rooms: &[Room],
-    width: usize,
-    height: usize,
-) -> Vec<Vec<i32>> {
-    use std::collections::{VecDeque, HashMap};
-    
-    let mut elevation_map = vec![vec![0i32; width]; height];
-    let mut distance_map = vec![vec![i32::MAX; width]; height];
-    
-    // First, assign elevations and distances to all room tiles
-    for room in rooms {
-        let room_elev = room.elevation.unwrap_or(0);
-        for y in room.y..room.y + room.h {
-            for x in room.x..room.x + room.w {
-                if y >= 0 && (y as usize) < height && x >= 0 && (x as usize) < width {
-                    elevation_map[y as usize][x as usize] = room_elev;
-                    distance_map[y as usize][x as usize] = 0; // Room tiles have distance 0
-                }
-            }
-        }
-    }
-    
-    // Multi-source BFS to find nearest room for each corridor tile
-    let mut queue: VecDeque<(usize, usize, i32, i32)> = VecDeque::new(); // (x, y, distance, elevation)
-    
-    // Start from all room tiles
-    for room in rooms {
-        let room_elev = room.elevation.unwrap_or(0);
-        for y in room.y..room.y + room.h {
-            for x in room.x..room.x + room.w {
-                if y >= 0 && (y as usize) < height && x >= 0 && (x as usize) < width {
-                    if grid[y as usize][x as usize] == TILE_FLOOR {
-                        queue.push_back((x as usize, y as usize, 0, room_elev));
-                    }
-                }
-            }
-        }
-    }
-    
-    // BFS to propagate elevations from rooms to corridors
-    while let Some((x, y, dist, elev)) = queue.pop_front() {
-        // Skip if we've already found a shorter path to this tile
-        if dist > distance_map[y][x] {
-            continue;
-        }
-        
-        for (dx, dy) in [(0i32, 1i32), (0, -1), (1, 0), (-1, 0)] {
-            let nx = x as i32 + dx;
-            let ny = y as i32 + dy;
-            
-            if ny >= 0 && ny < height as i32 && nx >= 0 && nx < width as i32 {
-                let nux = nx as usize;
-                let nuy = ny as usize;
-                
-                if grid[nuy][nux] == TILE_FLOOR {
-                    let new_dist = dist + 1;
-                    if new_dist < distance_map[nuy][nux] {
-                        distance_map[nuy][nux] = new_dist;
-                        elevation_map[nuy][nux] = elev;
-                        queue.push_back((nux, nuy, new_dist, elev));
-                    }
-                }
-            }
-        }
-    }
-    
-    // Second pass: smooth out large elevation jumps iteratively
-    // Keep smoothing until no tile has a neighbor with elevation difference > 1
-    let max_iterations = 50;
-    for _iter in 0..max_iterations {
-        let mut changes_made = false;
-        let mut new_elevations: HashMap<(usize, usize), i32> = HashMap::new();
-        
-        for y in 0..height {
-            for x in 0..width {
-                if grid[y][x] != TILE_FLOOR {
-                    continue;
-                }
-                
-                let current_elev = elevation_map[y][x];
-                let current_dist = distance_map[y][x];
-                
-                // Check all neighbors for large jumps
-                for (dx, dy) in [(0i32, 1i32), (0, -1), (1, 0), (-1, 0)] {
-                    let nx = x as i32 + dx;
-                    let ny = y as i32 + dy;
-                    
-                    if ny >= 0 && (ny as usize) < height && nx >= 0 && (nx as usize) < width {
-                        if grid[ny as usize][nx as usize] == TILE_FLOOR {
-                            let neighbor_elev = elevation_map[ny as usize][nx as usize];
-                            let neighbor_dist = distance_map[ny as usize][nx as usize];
-                            let diff = neighbor_elev - current_elev;
-                            
-                            // If there's a jump > 1, we need to insert intermediate elevations
-                            if diff.abs() > 1 {
-                                // Adjust this tile if it's farther from a room OR same distance
-                                if current_dist >= neighbor_dist {
-                                    let dir = diff.signum();
-                                    let new_elev = current_elev + dir;
-                                    // Only update if we haven't already scheduled a change
-                                    if !new_elevations.contains_key(&(x, y)) {
-                                        new_elevations.insert((x, y), new_elev);
-                                        changes_made = true;
-                                        break;
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        
-        // Apply all changes
-        for ((x, y), new_elev) in &new_elevations {
-            elevation_map[*y][*x] = *new_elev;
-        }
-        
-        if !changes_made {
-            break; // No more large jumps, we're done
-        }
-    }
-    
-    elevation_map
-}
-
-/// Place obstacles in large rooms
-fn place_obstacles_in_rooms(
-    marble_grid: &mut [Vec<MarbleTile>],
-    rooms: &[Room],
-    rng: &mut StdRng,
-    density: f32,
+    elevation_map: &mut Vec<Vec<i32>>,
+    scratch: &mut GeneratorScratch,
 ) {
-    use crate::tiles::TileType;
-    
-    let height = marble_grid.len();
-    let width = if height > 0 { marble_grid[0].len() } else { 0 };
-    
+    let height = grid.len();
+    let width = if height > 0 { grid[0].len() } else { 0 };
+    let room_elevation = &mut scratch.room_elevation;
+    reset(room_elevation, width, height, None);
     for room in rooms {
-        let room_area = (room.w * room.h) as f32;
-        
-        // Only place obstacles in rooms larger than 30 tiles
-        if room_area < 30.0 {
-            continue;
-        }
-        
-        // Number of obstacles based on room size and density
-        let num_obstacles = ((room_area * density * 0.1) as i32).max(1);
-        
-        for _ in 0..num_obstacles {
-            // Try to place obstacle in a random floor position within the room
-            for _ in 0..20 {  // Max 20 attempts per obstacle
-                let ox = rng.random_range(room.x + 1..room.x + room.w - 1);
-                let oy = rng.random_range(room.y + 1..room.y + room.h - 1);
-                
-                if oy >= 0 && (oy as usize) < height && ox >= 0 && (ox as usize) < width {
-                    let tile = &marble_grid[oy as usize][ox as usize];
-                    
-                    // Only place obstacle on passable tiles that aren't already obstacles
-                    if tile.tile_type.is_passable() && tile.tile_type != TileType::Obstacle {
-                        let elevation = tile.elevation;
-                        marble_grid[oy as usize][ox as usize] = MarbleTile::with_params(
-                            TileType::Obstacle,
-                            elevation,
-                            0,
-                            false,
-                        );
-                        break;
-                    }
-                }
+        for y in room.y.max(0)..(room.y + room.h).min(height as i32) {
+            for x in room.x.max(0)..(room.x + room.w).min(width as i32) {
+                room_elevation[y as usize][x as usize] = Some(room.elevation.unwrap_or(0));
             }
         }
     }
-}
-
-/// Check if a position is on the edge of any room
-fn is_on_room_edge(x: i32, y: i32, rooms: &[Room]) -> bool {
-    for room in rooms {
-        // Check if this position is adjacent to a room (within 1 tile of room boundary)
-        let room_left = room.x - 1;
-        let room_right = room.x + room.w;
-        let room_top = room.y - 1;
-        let room_bottom = room.y + room.h;
-        
-        // Check if position is on the edge of this room
-        if (x >= room_left && x <= room_right && (y == room_top || y == room_bottom)) ||
-           (y >= room_top && y <= room_bottom && (x == room_left || x == room_right)) {
-            return true;
-        }
-    }
-    false
-}
+    elevation_bound(grid, room_elevation, 1, &mut scratch.upper, &mut scratch.heap);
+    elevation_bound(grid, room_elevation, -1, &mut scratch.lower, &mut scratch.heap);
 
-/// Convert a character grid to a marble tile grid with intelligent tile type detection
-fn grid_to_marble_tiles(
-    grid: &Grid, 
-    rooms: &[Room], 
-    enable_elevation: bool,
-    elevation_map: &[Vec<i32>]
-) -> Vec<Vec<MarbleTile>> {
-    use crate::tiles::TileType;
-    
-    let height = grid.len();
-    let width = if height > 0 { grid[0].len() } else { 0 };
-    
-    let mut marble_grid = vec![vec![MarbleTile::empty(); width]; height];
-    
-    // Helper to check if a position is a floor tile
-    let is_floor = |x: i32, y: i32| -> bool {
-        if y >= 0 && (y as usize) < height && x >= 0 && (x as usize) < width {
-            grid[y as usize][x as usize] == TILE_FLOOR
-        } else {
-            false
-        }
-    };
-    
-    // Get elevation from the map
-    let get_elevation = |x: i32, y: i32| -> i32 {
-        if y >= 0 && (y as usize) < height && x >= 0 && (x as usize) < width {
-            elevation_map[y as usize][x as usize]
-        } else {
-            0
-        }
-    };
-    
-    // First pass: detect tile types based on neighbors
+    reset(elevation_map, width, height, 0);
     for y in 0..height {
         for x in 0..width {
-            if grid[y][x] != TILE_FLOOR {
-                continue;
-            }
-            
-            let ix = x as i32;
-            let iy = y as i32;
-            
-            // Check all four directions
-            let north = is_floor(ix, iy - 1);
-            let south = is_floor(ix, iy + 1);
-            let east = is_floor(ix + 1, iy);
-            let west = is_floor(ix - 1, iy);
-            
-            let connection_count = [north, south, east, west].iter().filter(|&&b| b).count();
-            
-            // Determine base elevation for this tile from the elevation map
-            let base_elevation = get_elevation(ix, iy);
-            
-            let (tile_type, rotation) = match connection_count {
-                0 | 1 => (TileType::OpenPlatform, 0), // Isolated or dead-end
-                2 => {
-                    // Straight or curve
-                    if (north && south) || (east && west) {
-                        // Straight path
-                        let rot = if north && south { 0 } else { 1 };
-                        (TileType::Straight, rot)
-                    } else {
-                        // 90-degree curve
-                        let rot = if north && east {
-                            0
-                        } else if east && south {
-                            1
-                        } else if south && west {
-                            2
-                        } else {
-                            3
-                        };
-                        (TileType::Curve90, rot)
-                    }
-                }
-                3 => {
-                    // T-junction
-                    let rot = if !south {
-                        0
-                    } else if !west {
-                        1
-                    } else if !north {
-                        2
-                    } else {
-                        3
-                    };
-                    (TileType::TJunction, rot)
-                }
-                4 => (TileType::CrossJunction, 0),
-                _ => (TileType::Straight, 0),
+            elevation_map[y][x] = match (room_elevation[y][x], scratch.upper[y][x], scratch.lower[y][x]) {
+                (Some(fixed), _, _) => fixed,
+                (None, Some(upper), Some(lower)) => (upper + lower).div_euclid(2),
+                _ => 0,
             };
-            
-            marble_grid[y][x] = MarbleTile::with_params(tile_type, base_elevation, rotation, true);
         }
     }
-    
-    // Second pass: place advanced tiles in appropriate locations (before slope conversion)
-    place_advanced_tiles(&mut marble_grid, grid, enable_elevation);
-    
-    // Third pass: detect and place slope tiles where elevation changes
-    if enable_elevation {
-        for y in 0..height {
-            for x in 0..width {
-                let tile = &marble_grid[y][x];
-                if tile.tile_type == TileType::Empty {
-                    continue;
-                }
-                
-                let ix = x as i32;
-                let iy = y as i32;
-                let current_elev = tile.elevation;
-                
-                // Only convert simple tiles to slopes (not junctions, curves, or advanced tiles)
-                if !matches!(tile.tile_type, TileType::Straight | TileType::OpenPlatform | TileType::CrossJunction) {
-                    continue;
-                }
-                
-                // Check if this tile is on the edge of a room
-                let is_on_edge = is_on_room_edge(ix, iy, rooms);
-                
-                // Check each direction for elevation changes (±1)
-                let has_elevation_change = 
-                    (is_floor(ix, iy - 1) && (get_elevation(ix, iy - 1) - current_elev).abs() == 1) ||
-                    (is_floor(ix, iy + 1) && (get_elevation(ix, iy + 1) - current_elev).abs() == 1) ||
-                    (is_floor(ix + 1, iy) && (get_elevation(ix + 1, iy) - current_elev).abs() == 1) ||
-                    (is_floor(ix - 1, iy) && (get_elevation(ix - 1, iy) - current_elev).abs() == 1);
-                
-                // Only place slopes when connecting different elevations OR on room edges
-                if has_elevation_change || is_on_edge {
-                    // Determine orientation based on the elevation change direction
-                    let vertical_change = 
-                        (is_floor(ix, iy - 1) && (get_elevation(ix, iy - 1) - current_elev).abs() == 1) ||
-                        (is_floor(ix, iy + 1) && (get_elevation(ix, iy + 1) - current_elev).abs() == 1);
-                    
-                    let horizontal_change = 
-                        (is_floor(ix + 1, iy) && (get_elevation(ix + 1, iy) - current_elev).abs() == 1) ||
-                        (is_floor(ix - 1, iy) && (get_elevation(ix - 1, iy) - current_elev).abs() == 1);
-                    
-                    // Prefer vertical orientation if there's a vertical elevation change
-                    let orientation = if vertical_change { 0 } else if horizontal_change { 1 } else { 0 };
-                    
-                    marble_grid[y][x] = MarbleTile::with_params(
-                        TileType::Slope,
-                        current_elev,
-                        orientation,
-                        true
-                    );
-                }
-            }
-        }
-    }
-    
-    marble_grid
 }
 
-/// Place advanced tiles in appropriate locations based on context
-fn place_advanced_tiles(
-    marble_grid: &mut Vec<Vec<MarbleTile>>,
+/// Fill `best` with, for every floor tile, the tightest `room elevation +
+/// sign * distance` over all rooms, walking floor tiles only (`None` where no
+/// room is reachable). Room tiles are fixed at their room's elevation.
+fn elevation_bound(
     grid: &Grid,
-    enable_elevation: bool,
+    room_elevation: &[Vec<Option<i32>>],
+    sign: i32,
+    best: &mut Vec<Vec<Option<i32>>>,
+    heap: &mut BinaryHeap<Reverse<(i32, usize, usize)>>,
 ) {
-    use crate::tiles::TileType;
-    
-    let height = marble_grid.len();
-    let width = if height > 0 { marble_grid[0].len() } else { 0 };
-    
-    // Helper to check if a position is a floor tile
-    let is_floor = |x: i32, y: i32| -> bool {
-        if y >= 0 && (y as usize) < height && x >= 0 && (x as usize) < width {
-            grid[y as usize][x as usize] == TILE_FLOOR
-        } else {
-            false
-        }
-    };
-    
-    // Place Y-junctions where we have smooth 3-way connections
-    for y in 1..height-1 {
-        for x in 1..width-1 {
-            let tile = &marble_grid[y][x];
-            if tile.tile_type != TileType::TJunction {
-                continue;
-            }
-            
-            let ix = x as i32;
-            let iy = y as i32;
-            
-            // Check if this T-junction could be a smooth Y-junction
-            // Look for diagonal connections that suggest smooth curves
-            let north = is_floor(ix, iy - 1);
-            let south = is_floor(ix, iy + 1);
-            let east = is_floor(ix + 1, iy);
-            let west = is_floor(ix - 1, iy);
-            
-            // Check for diagonal patterns that suggest Y-junction
-            let has_diagonal = (north && east && is_floor(ix + 1, iy - 1)) ||
-                              (east && south && is_floor(ix + 1, iy + 1)) ||
-                              (south && west && is_floor(ix - 1, iy + 1)) ||
-                              (west && north && is_floor(ix - 1, iy - 1));
-            
-            if has_diagonal {
-                marble_grid[y][x] = MarbleTile::with_params(
-                    TileType::YJunction,
-                    tile.elevation,
-                    tile.rotation,
-                    true
-                );
+    let height = grid.len();
+    let width = if height > 0 { grid[0].len() } else { 0 };
+    // Flipping the sign turns the lower bound into the same shortest-path problem
+    reset(best, width, height, None);
+    heap.clear();
+    for y in 0..height {
+        for x in 0..width {
+            if let (Some(elevation), TILE_FLOOR) = (room_elevation[y][x], grid[y][x]) {
+                best[y][x] = Some(sign * elevation);
+                heap.push(Reverse((sign * elevation, x, y)));
             }
         }
     }
-    
-    // Place merge tiles where multiple paths converge to a single output
-    for y in 1..height-1 {
-        for x in 1..width-1 {
-            let tile = &marble_grid[y][x];
-            if tile.tile_type != TileType::CrossJunction {
-                continue;
-            }
-            
-            let ix = x as i32;
-            let iy = y as i32;
-            
-            // Check if this cross junction has a clear "output" direction
-            // (one direction with more connections downstream)
-            let north_connections = count_connections_downstream(marble_grid, grid, ix, iy - 1, Direction::North);
-            let south_connections = count_connections_downstream(marble_grid, grid, ix, iy + 1, Direction::South);
-            let east_connections = count_connections_downstream(marble_grid, grid, ix + 1, iy, Direction::East);
-            let west_connections = count_connections_downstream(marble_grid, grid, ix - 1, iy, Direction::West);
-            
-            let connections = [north_connections, south_connections, east_connections, west_connections];
-            let max_connections = connections.iter().max().unwrap_or(&0);
-            
-            // If one direction has significantly more connections, it's likely a merge
-            if *max_connections >= 3 && connections.iter().filter(|&&c| c > 0).count() >= 3 {
-                // Determine the output direction (the one with most connections)
-                let output_dir = if north_connections == *max_connections { 0 }
-                                else if east_connections == *max_connections { 1 }
-                                else if south_connections == *max_connections { 2 }
-                                else { 3 };
-                
-                marble_grid[y][x] = MarbleTile::with_params(
-                    TileType::Merge,
-                    tile.elevation,
-                    output_dir,
-                    true
-                );
-            }
+    while let Some(Reverse((value, x, y))) = heap.pop() {
+        if best[y][x] != Some(value) {
+            continue;
         }
-    }
-    
-    // Place one-way gates in narrow passages (relaxed conditions)
-    for y in 1..height-1 {
-        for x in 1..width-1 {
-            let tile = &marble_grid[y][x];
-            if tile.tile_type != TileType::Straight {
+        for (dx, dy) in [(0i32, -1i32), (1, 0), (0, 1), (-1, 0)] {
+            let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+            if nx < 0 || ny < 0 || nx >= width as i32 || ny >= height as i32 {
                 continue;
             }
-            
-            let ix = x as i32;
-            let iy = y as i32;
-            
-            // Check if this is a narrow passage (straight line with walls on sides)
-            // Relaxed: only need walls on one side, not both
-            let is_narrow_passage = match tile.rotation {
-                0 | 2 => { // Vertical passage
-                    (!is_floor(ix - 1, iy) || !is_floor(ix + 1, iy)) &&
-                    is_floor(ix, iy - 1) && is_floor(ix, iy + 1)
-                },
-                1 | 3 => { // Horizontal passage
-                    (!is_floor(ix, iy - 1) || !is_floor(ix, iy + 1)) &&
-                    is_floor(ix - 1, iy) && is_floor(ix + 1, iy)
-                },
-                _ => false,
-            };
-            
-            if is_narrow_passage {
-                marble_grid[y][x] = MarbleTile::with_params(
-                    TileType::OneWayGate,
-                    tile.elevation,
-                    tile.rotation,
-                    true
-                );
-            }
-        }
-    }
-    
-    // Place loop-de-loops where we have elevation changes of +2 or more
-    if enable_elevation {
-        for y in 1..height-1 {
-            for x in 1..width-1 {
-                let tile = &marble_grid[y][x];
-                if tile.tile_type != TileType::Straight {
-                    continue;
-                }
-                
-                let ix = x as i32;
-                let iy = y as i32;
-                let current_elev = tile.elevation;
-                
-                // Check for large elevation changes that could support a loop
-                let has_large_elevation_change = 
-                    (is_floor(ix, iy - 1) && (get_elevation(marble_grid, ix, iy - 1) - current_elev).abs() >= 2) ||
-                    (is_floor(ix, iy + 1) && (get_elevation(marble_grid, ix, iy + 1) - current_elev).abs() >= 2) ||
-                    (is_floor(ix + 1, iy) && (get_elevation(marble_grid, ix + 1, iy) - current_elev).abs() >= 2) ||
-                    (is_floor(ix - 1, iy) && (get_elevation(marble_grid, ix - 1, iy) - current_elev).abs() >= 2);
-                
-                if has_large_elevation_change {
-                    marble_grid[y][x] = MarbleTile::with_params(
-                        TileType::LoopDeLoop,
-                        current_elev,
-                        tile.rotation,
-                        true
-                    );
-                }
-            }
-        }
-    }
-    
-    // Place half-pipes in curved sections with elevation changes
-    if enable_elevation {
-        for y in 1..height-1 {
-            for x in 1..width-1 {
-                let tile = &marble_grid[y][x];
-                if tile.tile_type != TileType::Curve90 {
-                    continue;
-                }
-                
-                let ix = x as i32;
-                let iy = y as i32;
-                let current_elev = tile.elevation;
-                
-                // Check if this curve has elevation changes
-                let has_elevation_change = 
-                    (is_floor(ix, iy - 1) && (get_elevation(marble_grid, ix, iy - 1) - current_elev).abs() == 1) ||
-                    (is_floor(ix, iy + 1) && (get_elevation(marble_grid, ix, iy + 1) - current_elev).abs() == 1) ||
-                    (is_floor(ix + 1, iy) && (get_elevation(marble_grid, ix + 1, iy) - current_elev).abs() == 1) ||
-                    (is_floor(ix - 1, iy) && (get_elevation(marble_grid, ix - 1, iy) - current_elev).abs() == 1);
-                
-                if has_elevation_change {
-                    marble_grid[y][x] = MarbleTile::with_params(
-                        TileType::HalfPipe,
-                        current_elev,
-                        tile.rotation,
-                        true
-                    );
-                }
-            }
-        }
-    }
-    
-    // Place launch pads at the start of straight sections (relaxed conditions)
-    for y in 1..height-1 {
-        for x in 1..width-1 {
-            let tile = &marble_grid[y][x];
-            if tile.tile_type != TileType::Straight {
+            let (nx, ny) = (nx as usize, ny as usize);
+            if grid[ny][nx] != TILE_FLOOR || room_elevation[ny][nx].is_some() {
                 continue;
             }
-            
-            let ix = x as i32;
-            let iy = y as i32;
-            
-            // Check if this is the start of a straight section (relaxed: just need continuation)
-            let is_launch_pad = match tile.rotation {
-                0 | 2 => { // Vertical
-                    !is_floor(ix, iy - 1) && is_floor(ix, iy + 1)
-                },
-                1 | 3 => { // Horizontal
-                    !is_floor(ix - 1, iy) && is_floor(ix + 1, iy)
-                },
-                _ => false,
-            };
-            
-            if is_launch_pad {
-                marble_grid[y][x] = MarbleTile::with_params(
-                    TileType::LaunchPad,
-                    tile.elevation,
-                    tile.rotation,
-                    true
-                );
+            if best[ny][nx].is_none_or(|b| value + 1 < b) {
+                best[ny][nx] = Some(value + 1);
+                heap.push(Reverse((value + 1, nx, ny)));
             }
         }
     }
-}
-
-/// Helper function to count connections downstream from a position
-fn count_connections_downstream(
-    marble_grid: &Vec<Vec<MarbleTile>>,
-    grid: &Grid,
-    start_x: i32,
-    start_y: i32,
-    direction: Direction,
-) -> usize {
-    use crate::tiles::TileType;
-    if start_y < 0 || (start_y as usize) >= marble_grid.len() ||
-       start_x < 0 || (start_x as usize) >= marble_grid[0].len() {
-        return 0;
-    }
-    
-    let mut count = 0;
-    let mut x = start_x;
-    let mut y = start_y;
-    
-    // Follow the path in the given direction
-    for _ in 0..10 { // Limit to prevent infinite loops
-        let (dx, dy) = match direction {
-            Direction::North => (0, -1),
-            Direction::South => (0, 1),
-            Direction::East => (1, 0),
-            Direction::West => (-1, 0),
-        };
-        
-        x += dx;
-        y += dy;
-        
-        if y < 0 || (y as usize) >= marble_grid.len() ||
-           x < 0 || (x as usize) >= marble_grid[0].len() {
-            break;
-        }
-        
-        if grid[y as usize][x as usize] != TILE_FLOOR {
-            break;
-        }
-        
-        count += 1;
-        
-        // Stop if we hit a junction or dead end
-        let tile = &marble_grid[y as usize][x as usize];
-        if tile.tile_type == TileType::TJunction || 
-           tile.tile_type == TileType::CrossJunction ||
-           tile.tile_type == TileType::YJunction {
-            break;
+    for row in best.iter_mut() {
+        for bound in row.iter_mut().flatten() {
+            *bound *= sign;
         }
     }
-    
-    count
-}
-
-/// Helper function to get elevation from marble grid
-fn get_elevation(marble_grid: &Vec<Vec<MarbleTile>>, x: i32, y: i32) -> i32 {
-    if y >= 0 && (y as usize) < marble_grid.len() &&
-       x >= 0 && (x as usize) < marble_grid[0].len() {
-        marble_grid[y as usize][x as usize].elevation
-    } else {
-        0
-    }
 }
 
 /// Fill the rectangle defined by `room` with floor tiles.
@@ -1190,28 +1273,19 @@ fn carve_room(grid: &mut [Vec<char>], room: &Room) {
     }
 }
 
-/// Carve a horizontal tunnel from `x1..=x2` at row `y`.
-fn carve_horizontal_tunnel(grid: &mut [Vec<char>], x1: i32, x2: i32, y: i32) {
-    let (start, end) = if x1 <= x2 { (x1, x2) } else { (x2, x1) };
-    for x in start..=end {
-        set_floor(grid, x, y);
-    }
-}
-
-/// Carve a vertical tunnel from `y1..=y2` at column `x`.
-fn carve_vertical_tunnel(grid: &mut [Vec<char>], y1: i32, y2: i32, x: i32) {
-    let (start, end) = if y1 <= y2 { (y1, y2) } else { (y2, y1) };
-    for y in start..=end {
-        set_floor(grid, x, y);
-    }
-}
-
-/// Safely set the tile at `(x, y)` to floor if within bounds.
-fn set_floor(grid: &mut [Vec<char>], x: i32, y: i32) {
-    if y >= 0 && (y as usize) < grid.len() {
-        let row = &mut grid[y as usize];
-        if x >= 0 && (x as usize) < row.len() {
-            row[x as usize] = TILE_FLOOR;
+/// Wall in the `border` tiles inside the `skirt` and clear the skirt to
+/// void, trimming channels that were carved into either.
+fn apply_border(grid: &mut [Vec<char>], border: u32, skirt: u32) {
+    let height = grid.len();
+    for (y, row) in grid.iter_mut().enumerate() {
+        let width = row.len();
+        for (x, tile) in row.iter_mut().enumerate() {
+            let depth = x.min(y).min(width - 1 - x).min(height - 1 - y) as u32;
+            if depth < skirt {
+                *tile = TILE_VOID;
+            } else if depth < skirt.saturating_add(border) {
+                *tile = TILE_WALL;
+            }
         }
     }
 }
@@ -1242,9 +1316,54 @@ fn wfc_tileset() -> Vec<WfcTile> {
     ]
 }
 
+/// Pipe edges `[up, right, down, left]` of a WFC maze character, or `None`
+/// if `ch` is not part of the WFC tileset.
+pub(crate) fn wfc_edges(ch: char) -> Option<[bool; 4]> {
+    wfc_tileset().into_iter().find(|t| t.ch == ch).map(|t| t.edges)
+}
+
+/// Index of a WFC maze character in the tileset.
+pub(crate) fn wfc_tile_index(ch: char) -> Option<usize> {
+    wfc_tileset().iter().position(|t| t.ch == ch)
+}
+
 fn opposite(dir: usize) -> usize { (dir + 2) % 4 }
 
-fn generate_wfc_tilemap(width: usize, height: usize, rng: &mut StdRng) -> Vec<String> {
+fn generate_wfc_tilemap(
+    width: usize,
+    height: usize,
+    rng: &mut StdRng,
+    deadline: &Deadline,
+    scratch: &mut GeneratorScratch,
+) -> Vec<String> {
+    // Fallback: empty grid if all attempts failed or time ran out
+    collapse_wfc(width, height, &vec![WfcCell::Free; width * height], rng, deadline, scratch).unwrap_or_else(|| vec![" ".repeat(width); height])
+}
+
+/// A cell of a [`collapse_wfc`] run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum WfcCell {
+    /// Collapsed by the run
+    Free,
+    /// Keeps this tileset index; its neighbors must meet its edges
+    Fixed(usize),
+    /// Outside the run; its neighbors may open toward it or not
+    Open,
+}
+
+/// Collapse a `width` x `height` pipe tilemap over the [`WfcCell::Free`]
+/// cells of `cells`. Fixed and open cells constrain their neighbors as
+/// described there and come out as they were (open ones as blanks). `None`
+/// if every attempt hit a contradiction or time ran out.
+#[allow(clippy::needless_range_loop)]
+pub(crate) fn collapse_wfc(
+    width: usize,
+    height: usize,
+    cells: &[WfcCell],
+    rng: &mut StdRng,
+    deadline: &Deadline,
+    scratch: &mut GeneratorScratch,
+) -> Option<Vec<String>> {
     let tiles = wfc_tileset();
     let num_tiles = tiles.len();
     let all_mask: u32 = if num_tiles >= 32 { u32::MAX } else { (1u32 << num_tiles) - 1 };
@@ -1262,13 +1381,36 @@ fn generate_wfc_tilemap(width: usize, height: usize, rng: &mut StdRng) -> Vec<St
             compat[i][dir] = mask;
         }
     }
+    // Open cells hold one extra tile that fits next to anything, so they
+    // never narrow their neighbors nor get narrowed
+    let open = 1u32 << num_tiles;
+    for allowed in compat.iter_mut().flatten() {
+        *allowed |= open;
+    }
+    compat.push([all_mask | open; 4]);
+
+    // support[domain][dir] = tiles the neighbor in `dir` may still be, for
+    // every domain mask
+    let support: Vec<[u32; 4]> = (0..=all_mask | open)
+        .map(|d| {
+            let mut allowed = [0u32; 4];
+            for t in 0..=num_tiles {
+                if (d & (1u32 << t)) != 0 {
+                    for dir in 0..4 { allowed[dir] |= compat[t][dir]; }
+                }
+            }
+            allowed
+        })
+        .collect();
 
     let idx = |x: usize, y: usize| -> usize { y * width + x };
 
     let mut attempts = 0;
-    while attempts < 10 {
+    while attempts < 10 && !deadline.expired() {
         attempts += 1;
-        let mut domains: Vec<u32> = vec![all_mask; width * height];
+        let domains = &mut scratch.wfc_domains;
+        domains.clear();
+        domains.resize(width * height, all_mask);
 
         // Border constraints: disallow tiles whose connections go off-grid
         for y in 0..height {
@@ -1290,37 +1432,38 @@ fn generate_wfc_tilemap(width: usize, height: usize, rng: &mut StdRng) -> Vec<St
                     // left must be false
                     mask &= allowed_without_connection(&tiles, 3);
                 }
-                domains[idx(x, y)] &= mask;
+                // Fixed neighbors: match their edges
+                for (dir, n) in [(0, (y > 0).then(|| idx(x, y - 1))), (1, (x + 1 < width).then(|| idx(x + 1, y))), (2, (y + 1 < height).then(|| idx(x, y + 1))), (3, (x > 0).then(|| idx(x - 1, y)))] {
+                    if let Some(WfcCell::Fixed(t)) = n.map(|n| cells[n]) {
+                        mask &= compat[t][opposite(dir)];
+                    }
+                }
+                domains[idx(x, y)] = match cells[idx(x, y)] {
+                    WfcCell::Free => domains[idx(x, y)] & mask,
+                    WfcCell::Fixed(t) => 1u32 << t,
+                    WfcCell::Open => open,
+                };
             }
         }
 
-        let mut queue: VecDeque<usize> = VecDeque::new();
+        let frontier = &mut scratch.wfc_frontier;
+        frontier.clear();
 
         loop {
-            // Pick cell with lowest entropy > 1
-            let mut best_i = None;
-            let mut best_count = usize::MAX;
-            for i in 0..domains.len() {
-                let d = domains[i];
-                let c = d.count_ones() as usize;
-                if c > 1 && c < best_count {
-                    best_count = c;
-                    best_i = Some(i);
-                }
+            if deadline.expired() {
+                break;
             }
-
-            if let Some(i) = best_i {
+            if let Some(i) = lowest_entropy(domains) {
                 // Collapse: choose random tile from domain
                 let d = domains[i];
-                if d == 0 { break; }
                 let mut options: Vec<usize> = Vec::new();
                 for t in 0..num_tiles { if (d & (1u32 << t)) != 0 { options.push(t); } }
                 let choice = options[rng.random_range(0..options.len())];
                 domains[i] = 1u32 << choice;
-                queue.push_back(i);
+                frontier.push(i);
             } else {
                 // No cells with entropy >1: finished or contradiction
-                if domains.iter().any(|&d| d == 0) {
+                if domains.contains(&0) {
                     break;
                 }
                 // Success
@@ -1334,44 +1477,17 @@ fn generate_wfc_tilemap(width: usize, height: usize, rng: &mut StdRng) -> Vec<St
                     }
                     out.push(row);
                 }
-                return out;
+                return Some(out);
             }
 
-            // Propagate constraints
-            while let Some(i0) = queue.pop_front() {
-                let x0 = i0 % width;
-                let y0 = i0 / width;
-                let d0 = domains[i0];
-                if d0 == 0 { break; }
-
-                for dir in 0..4 {
-                    let nx = match dir { 1 => x0 + 1, 3 => x0.wrapping_sub(1), _ => x0 };
-                    let ny = match dir { 0 => y0.wrapping_sub(1), 2 => y0 + 1, _ => y0 };
-                    if nx >= width || ny >= height { continue; }
-                    let ni = idx(nx, ny);
-
-                    // Allowed neighbor set from current domain
-                    let mut allowed = 0u32;
-                    for t in 0..num_tiles { if (d0 & (1u32 << t)) != 0 { allowed |= compat[t][dir]; } }
-
-                    let before = domains[ni];
-                    let after = before & allowed;
-                    if after != before {
-                        domains[ni] = after;
-                        // Early contradiction; continue to allow restart
-                        if after == 0 { break; }
-                        queue.push_back(ni);
-                    }
-                }
+            // Propagate constraints; restart on a contradiction
+            if !propagate(domains, width, &support, frontier, &mut scratch.wfc_constraints) {
+                break;
             }
-            // If any domain zeroed, restart
-            if domains.iter().any(|&d| d == 0) { break; }
         }
         // restart on failure
     }
-
-    // Fallback: empty grid if all attempts failed
-    vec![" ".repeat(width); height]
+    None
 }
 
 fn allowed_without_connection(tiles: &[WfcTile], dir: usize) -> u32 {
@@ -1382,101 +1498,171 @@ fn allowed_without_connection(tiles: &[WfcTile], dir: usize) -> u32 {
     mask
 }
 
-/// Carve a horizontal channel of width `width_tiles` centered on `y`.
-fn carve_wide_horizontal(grid: &mut [Vec<char>], x1: i32, x2: i32, y: i32, width_tiles: i32) {
-    let (start, end) = if x1 <= x2 { (x1, x2) } else { (x2, x1) };
-    let half = width_tiles / 2;
-    for x in start..=end {
-        for dy in -half..=half {
-            set_floor(grid, x, y + dy);
-        }
+/// Domains at or above this many cells are searched for the lowest entropy
+/// on all threads with the `parallel` feature.
+#[cfg(feature = "parallel")]
+const PARALLEL_CELLS: usize = 16_384;
+
+/// Waves narrowing at least this many neighbors are spread over all threads
+/// with the `parallel` feature.
+#[cfg(feature = "parallel")]
+const PARALLEL_WAVE: usize = 512;
+
+/// The undecided cell with the fewest options left, the first one on ties.
+fn lowest_entropy(domains: &[u32]) -> Option<usize> {
+    let key = |(i, d): (usize, &u32)| (d.count_ones() > 1).then_some((d.count_ones(), i));
+    #[cfg(feature = "parallel")]
+    if domains.len() >= PARALLEL_CELLS {
+        use rayon::prelude::*;
+        return domains.par_iter().enumerate().filter_map(key).min().map(|(_, i)| i);
     }
+    domains.iter().enumerate().filter_map(key).min().map(|(_, i)| i)
 }
 
-/// Carve a vertical channel of width `width_tiles` centered on `x`.
-fn carve_wide_vertical(grid: &mut [Vec<char>], y1: i32, y2: i32, x: i32, width_tiles: i32) {
-    let (start, end) = if y1 <= y2 { (y1, y2) } else { (y2, y1) };
-    let half = width_tiles / 2;
-    for y in start..=end {
-        for dx in -half..=half {
-            set_floor(grid, x + dx, y);
+/// Narrow the domains around the cells in `frontier` until nothing changes,
+/// one wave at a time: each wave narrows every neighbor of the cells the
+/// last wave changed, and the cells it changes make the next frontier.
+/// Narrowing only intersects masks, so the domains end up the same in
+/// whatever order it runs, which lets the `parallel` feature split large
+/// waves over threads without changing the level. Returns false on a
+/// contradiction.
+fn propagate(domains: &mut [u32], width: usize, support: &[[u32; 4]], frontier: &mut Vec<usize>, constraints: &mut Vec<(usize, u32)>) -> bool {
+    let height = domains.len() / width;
+    while !frontier.is_empty() {
+        let cells: &[u32] = domains;
+        // (neighbor, tiles it may still be) for every side of a frontier cell
+        let narrow = |&i: &usize| {
+            let (x, y) = (i % width, i / width);
+            let allowed = support[cells[i] as usize];
+            [
+                (y > 0).then(|| (i - width, allowed[0])),
+                (x + 1 < width).then(|| (i + 1, allowed[1])),
+                (y + 1 < height).then(|| (i + width, allowed[2])),
+                (x > 0).then(|| (i - 1, allowed[3])),
+            ]
+            .into_iter()
+            .flatten()
+        };
+        constraints.clear();
+        #[cfg(feature = "parallel")]
+        if frontier.len() * 4 >= PARALLEL_WAVE {
+            use rayon::prelude::*;
+            constraints.par_extend(frontier.par_iter().flat_map_iter(narrow));
+            constraints.par_sort_unstable_by_key(|&(i, _)| i);
+        }
+        if constraints.is_empty() {
+            constraints.extend(frontier.iter().flat_map(narrow));
+            constraints.sort_unstable_by_key(|&(i, _)| i);
+        }
+
+        frontier.clear();
+        for group in constraints.chunk_by(|a, b| a.0 == b.0) {
+            let i = group[0].0;
+            let narrowed = group.iter().fold(domains[i], |d, &(_, allowed)| d & allowed);
+            if narrowed != domains[i] {
+                if narrowed == 0 {
+                    return false;
+                }
+                domains[i] = narrowed;
+                frontier.push(i);
+            }
         }
     }
+    true
 }
 
-/// Carve a rounded quarter-circle at the L-turn from horizontal to vertical.
-/// If `turn_right` is true, the horizontal moves to the right before turning; otherwise to the left.
-fn carve_wide_horizontal_with_rounded_turn(
-    grid: &mut [Vec<char>], x1: i32, x2: i32, y: i32, width_tiles: i32, radius: i32, turn_down: bool,
-) {
-    carve_wide_horizontal(grid, x1, x2, y, width_tiles);
-    // Draw a quarter disk at the corner (center near (x2, y))
-    carve_quarter_disk(grid, x2, y, radius.max(width_tiles / 2), width_tiles, if turn_down { Quadrant::Down } else { Quadrant::Up });
+/// Floor one corner tile wherever two floor tiles touch only diagonally, so
+/// every 8-connected floor region is also 4-connected.
+fn close_diagonal_gaps(grid: &mut [Vec<char>]) {
+    // A new corner tile can itself touch the row above only diagonally
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for y in 0..grid.len().saturating_sub(1) {
+            for x in 0..grid[y].len().saturating_sub(1) {
+                let (a, b) = (grid[y][x] == TILE_FLOOR, grid[y + 1][x + 1] == TILE_FLOOR);
+                let (c, d) = (grid[y][x + 1] == TILE_FLOOR, grid[y + 1][x] == TILE_FLOOR);
+                if (a && b || c && d) && !(a && c) && !(b && d) {
+                    grid[y][x + a as usize] = TILE_FLOOR;
+                    changed = true;
+                }
+            }
+        }
+    }
 }
 
-/// Carve a rounded quarter-circle at the L-turn from vertical to horizontal.
-fn carve_wide_vertical_with_rounded_turn(
-    grid: &mut [Vec<char>], y1: i32, y2: i32, x: i32, width_tiles: i32, radius: i32, turn_right: bool,
-) {
-    carve_wide_vertical(grid, y1, y2, x, width_tiles);
-    carve_quarter_disk(grid, x, y2, radius.max(width_tiles / 2), width_tiles, if turn_right { Quadrant::Right } else { Quadrant::Left });
+/// Centerline of a channel from `from` to `to`: a leg along one axis
+/// (horizontal first if `horizontal_first`), a quarter-circle arc of
+/// `radius` tiles tangent to both legs, and a leg along the other axis.
+/// Points are no more than half a tile apart; the arc uses the rational
+/// parametrization of the circle, so no trigonometry is involved.
+fn arc_centerline(from: (i32, i32), to: (i32, i32), horizontal_first: bool, radius: i32) -> Vec<(f32, f32)> {
+    let r = radius.clamp(0, (to.0 - from.0).abs().min((to.1 - from.1).abs())) as f32;
+    let ((x1, y1), (x2, y2)) = ((from.0 as f32, from.1 as f32), (to.0 as f32, to.1 as f32));
+    let (sx, sy) = ((x2 - x1).signum(), (y2 - y1).signum());
+    // Arc center, and the arc's start and end relative to it
+    let (center, u, v) = if horizontal_first {
+        ((x2 - sx * r, y1 + sy * r), (0.0, -sy * r), (sx * r, 0.0))
+    } else {
+        ((x1 + sx * r, y2 - sy * r), (-sx * r, 0.0), (0.0, sy * r))
+    };
+    let (arc_start, arc_end) = ((center.0 + u.0, center.1 + u.1), (center.0 + v.0, center.1 + v.1));
+
+    let segment = |a: (f32, f32), b: (f32, f32)| {
+        let steps = (((b.0 - a.0).abs() + (b.1 - a.1).abs()) * 2.0).ceil().max(1.0) as usize;
+        (0..=steps).map(move |i| {
+            let t = i as f32 / steps as f32;
+            (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)
+        })
+    };
+    let arc_steps = (r * 4.0).ceil() as usize;
+    let arc = (1..arc_steps).map(|i| {
+        let t = i as f32 / arc_steps as f32;
+        let (a, b) = ((1.0 - t * t) / (1.0 + t * t), 2.0 * t / (1.0 + t * t));
+        (center.0 + a * u.0 + b * v.0, center.1 + a * u.1 + b * v.1)
+    });
+    segment((x1, y1), arc_start).chain(arc).chain(segment(arc_end, (x2, y2))).collect()
 }
 
-#[derive(Clone, Copy)]
-enum Quadrant { Up, Down, Left, Right }
-
-/// Approximate a quarter disk for rounding corners, thickened by channel width.
-fn carve_quarter_disk(grid: &mut [Vec<char>], cx: i32, cy: i32, radius: i32, width_tiles: i32, quad: Quadrant) {
-    if radius <= 0 { return; }
-    let inner = (radius - width_tiles / 2).max(0);
-    let outer = radius + width_tiles / 2;
-    match quad {
-        Quadrant::Down => {
-            for dy in 0..=outer {
-                for dx in -outer..=outer {
-                    let d2 = dx*dx + dy*dy;
-                    if d2 <= outer*outer && d2 >= inner*inner {
-                        set_floor(grid, cx + dx, cy + dy);
-                    }
-                }
-            }
-        }
-        Quadrant::Up => {
-            for dy in -outer..=0 {
-                for dx in -outer..=outer {
-                    let d2 = dx*dx + dy*dy;
-                    if d2 <= outer*outer && d2 >= inner*inner {
-                        set_floor(grid, cx + dx, cy + dy);
-                    }
+/// Carve a channel `width_tiles` wide along [`arc_centerline`]. The
+/// centerline is rasterized into a 4-connected run of tiles (a thin channel
+/// is a clean staircase of curves) and widened by a round brush, so straight
+/// legs come out exactly `width_tiles / 2` tiles to either side.
+pub(crate) fn carve_arc_channel(grid: &mut [Vec<char>], from: (i32, i32), to: (i32, i32), horizontal_first: bool, width_tiles: i32, radius: i32) {
+    let half = width_tiles / 2;
+    let brush = |grid: &mut [Vec<char>], (x, y): (i32, i32)| {
+        for dy in -half..=half {
+            for dx in -half..=half {
+                if dx * dx + dy * dy <= half * half + half {
+                    set_floor(grid, x + dx, y + dy);
                 }
             }
         }
-        Quadrant::Right => {
-            for dx in 0..=outer {
-                for dy in -outer..=outer {
-                    let d2 = dx*dx + dy*dy;
-                    if d2 <= outer*outer && d2 >= inner*inner {
-                        set_floor(grid, cx + dx, cy + dy);
-                    }
-                }
+    };
+    let mut last: Option<(i32, i32)> = None;
+    for (px, py) in arc_centerline(from, to, horizontal_first, radius) {
+        let cell = ((px + 0.5).floor() as i32, (py + 0.5).floor() as i32);
+        if let Some(prev) = last {
+            if prev == cell {
+                continue;
             }
-        }
-        Quadrant::Left => {
-            for dx in -outer..=0 {
-                for dy in -outer..=outer {
-                    let d2 = dx*dx + dy*dy;
-                    if d2 <= outer*outer && d2 >= inner*inner {
-                        set_floor(grid, cx + dx, cy + dy);
-                    }
-                }
+            // Step through the side neighbour nearer the curve so tiles share an edge
+            if prev.0 != cell.0 && prev.1 != cell.1 {
+                let near = |(x, y): (i32, i32)| (x as f32 - px) * (x as f32 - px) + (y as f32 - py) * (y as f32 - py);
+                let (a, b) = ((cell.0, prev.1), (prev.0, cell.1));
+                brush(grid, if near(b) < near(a) { b } else { a });
             }
         }
+        brush(grid, cell);
+        last = Some(cell);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tiles::TileType;
+    use rand::SeedableRng;
 
     fn params_base() -> GeneratorParams {
         GeneratorParams {
@@ -1485,18 +1671,70 @@ mod tests {
             rooms: 10,
             min_room: 4,
             max_room: 10,
+            room_size_distribution: RoomSizeDistribution::Uniform,
+            guaranteed_large_rooms: 0,
+            room_attempts: None,
+            relax_room_placement: false,
+            border: 1,
+            skirt: 0,
             seed: Some(42),
             mode: GenerationMode::Classic,
             channel_width: 2,
             corner_radius: 2,
+            channel_curvature: 0.5,
             enable_elevation: false,
             max_elevation: 2,
             enable_obstacles: false,
             obstacle_density: 0.3,
+            difficulty_ramp: 0.0,
+            obstacle_shapes: vec![ObstacleShape::Pillar],
+            obstacle_patterns: vec![ObstaclePattern::Scatter],
+            obstacle_clearance: None,
+            obstacle_spacing: 3,
+            obstacle_doorway_radius: None,
             trend_vector: None,
             trend_strength: 0.5,
             start_point: None,
+            endpoints: None,
             max_elevation_change: 1,
+            enable_rivers: false,
+            river_count: 2,
+            enable_spiral_ramps: false,
+            enable_funnels: false,
+            max_launch_pads: 3,
+            open_bowl_rooms: false,
+            enable_y_junctions: true,
+            enable_dynamics: false,
+            dynamic_count: 3,
+            enable_voxels: false,
+            voxel_bridges: 2,
+            enable_banking: false,
+            zoning: ZoneStrategy::None,
+            topology: GridTopology::Square,
+            connection_strategy: ConnectionStrategy::Chain,
+            dead_end_removal: 0.0,
+            secret_passages: 0,
+            vaults: 0,
+            vault_guard: None,
+            side_routes: 0,
+            min_alternate_routes: 0,
+            road_layout: RoadLayout::Grid,
+            plazas: 1,
+            min_path_length: None,
+            path_retries: 20,
+            target_floor_ratio: None,
+            floor_ratio_tolerance: 0.05,
+            max_total_tiles: None,
+            budget_retries: 10,
+            naming: None,
+            ceilings: None,
+            mission: None,
+            algorithm_version: None,
+            allowed_tile_types: None,
+            keep_ascii_tiles: true,
+            edge_openings: Vec::new(),
+            time_budget: None,
+            cancel: None,
         }
     }
 
@@ -1525,6 +1763,36 @@ mod tests {
         assert!(all_chars_in_set(&a.tiles, &[TILE_WALL, TILE_FLOOR]));
     }
 
+    #[test]
+    fn channel_turns_are_arcs_that_detect_as_curve_runs() {
+        // Thin channels turning every way come out as one chain of straights and curves
+        for (to, horizontal_first) in [((25, 17), true), ((25, 17), false), ((4, 2), true), ((25, 2), false), ((4, 17), true), ((3, 18), false)] {
+            let mut grid = vec![vec![TILE_WALL; 30]; 20];
+            carve_arc_channel(&mut grid, (14, 9), to, horizontal_first, 1, 10);
+            let tiles = grid_to_marble_tiles(&grid, &MarbleTileConfig { advanced: AdvancedTileConfig::none(), ..Default::default() });
+            let kinds: Vec<TileType> = tiles.iter().flatten().map(|t| t.tile_type).filter(|&t| t != TileType::Empty).collect();
+            assert_eq!(kinds.iter().filter(|&&t| t == TileType::OpenPlatform).count(), 2, "one end each");
+            assert!(kinds.iter().all(|t| matches!(t, TileType::Straight | TileType::Curve90 | TileType::OpenPlatform)));
+            assert!(kinds.iter().filter(|&&t| t == TileType::Curve90).count() >= 6);
+        }
+
+        // Wide straight legs keep their width; the turn sweeps through the shorter leg
+        let mut grid = vec![vec![TILE_WALL; 30]; 20];
+        carve_arc_channel(&mut grid, (2, 3), (25, 17), true, 3, 8);
+        assert!((2..=4).all(|y| grid[y][6] == TILE_FLOOR) && grid[1][6] == TILE_WALL && grid[5][6] == TILE_WALL);
+        assert!((24..=26).all(|x| grid[16][x] == TILE_FLOOR) && grid[16][23] == TILE_WALL && grid[16][27] == TILE_WALL);
+        // An L-turn would carve the outer corner
+        assert_eq!(grid[3][25], TILE_WALL);
+
+        let mut p = params_base();
+        p.mode = GenerationMode::Marble;
+        p.seed = Some(4);
+        let sharp = generate(&GeneratorParams { corner_radius: 0, channel_curvature: 0.0, ..p.clone() });
+        let swept = generate(&GeneratorParams { channel_curvature: 1.0, ..p });
+        assert_ne!(sharp.tiles, swept.tiles);
+        assert!(swept.check_invariants().is_empty());
+    }
+
     #[test]
     fn marble_deterministic_with_seed() {
         let mut p = params_base();
@@ -1538,11 +1806,36 @@ mod tests {
         assert!(all_chars_in_set(&a.tiles, &[TILE_WALL, TILE_FLOOR]));
     }
 
+    #[test]
+    fn border_and_skirt_frame_the_level() {
+        for mode in [GenerationMode::Classic, GenerationMode::Marble] {
+            let mut p = params_base();
+            p.mode = mode;
+            p.channel_width = 4;
+            let level = generate(&GeneratorParams { border: 3, skirt: 2, ..p });
+            let grid = parse_grid(&level.tiles);
+            let (w, h) = (grid[0].len(), grid.len());
+            for (y, row) in grid.iter().enumerate() {
+                for (x, &ch) in row.iter().enumerate() {
+                    match x.min(y).min(w - 1 - x).min(h - 1 - y) {
+                        0 | 1 => assert_eq!(ch, TILE_VOID, "skirt at ({x}, {y})"),
+                        2..=4 => assert_eq!(ch, TILE_WALL, "border at ({x}, {y})"),
+                        _ => {}
+                    }
+                }
+            }
+            assert!(!level.rooms.is_empty());
+            assert!(level.rooms.iter().all(|r| r.x >= 5 && r.y >= 5 && r.x + r.w <= w as i32 - 5 && r.y + r.h <= h as i32 - 5));
+            assert!(level.check_invariants().is_empty());
+        }
+    }
+
     fn parse_grid(tiles: &[String]) -> Vec<Vec<char>> {
         tiles.iter().map(|r| r.chars().collect::<Vec<char>>()).collect::<Vec<_>>()
     }
 
     #[test]
+    #[allow(clippy::needless_range_loop)]
     fn classic_connectivity_of_floors() {
         let mut p = params_base();
         p.mode = GenerationMode::Classic;
@@ -1583,6 +1876,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::needless_range_loop)]
     fn wfc_deterministic_and_valid_adjacency() {
         let mut p = params_base();
         p.mode = GenerationMode::Wfc;
@@ -1632,4 +1926,130 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn wfc_wave_propagation_matches_a_queue() {
+        // Narrowing one neighbor at a time from a FIFO queue, as propagation
+        // used to run, must reach the same domains as the waves
+        let (width, height) = (160, 120);
+        let tiles = wfc_tileset();
+        let compat: Vec<[u32; 4]> = tiles
+            .iter()
+            .map(|t| std::array::from_fn(|dir| tiles.iter().enumerate().filter(|(_, n)| t.edges[dir] == n.edges[opposite(dir)]).fold(0, |m, (j, _)| m | 1 << j)))
+            .collect();
+        let all_mask = (1u32 << tiles.len()) - 1;
+        let support: Vec<[u32; 4]> = (0..=all_mask)
+            .map(|d| std::array::from_fn(|dir| (0..tiles.len()).filter(|t| d & 1 << t != 0).fold(0, |m, t| m | compat[t][dir])))
+            .collect();
+        let queue_propagate = |domains: &mut [u32], starts: &[usize]| {
+            let mut queue = std::collections::VecDeque::from(starts.to_vec());
+            while let Some(i) = queue.pop_front() {
+                let (x, y) = (i % width, i / width);
+                for (dir, n) in [(0, (y > 0).then(|| i - width)), (1, (x + 1 < width).then(|| i + 1)), (2, (y + 1 < height).then(|| i + width)), (3, (x > 0).then(|| i - 1))] {
+                    let Some(n) = n else { continue };
+                    let after = domains[n] & support[domains[i] as usize][dir];
+                    if after != domains[n] {
+                        domains[n] = after;
+                        queue.push_back(n);
+                    }
+                }
+            }
+        };
+
+        // A random mask per cell gives one wave over the whole grid
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut waves: Vec<u32> = (0..width * height).map(|_| all_mask & !(1 << rng.random_range(0..tiles.len()))).collect();
+        let mut queued = waves.clone();
+        let mut frontier: Vec<usize> = (0..waves.len()).collect();
+        let mut constraints = Vec::new();
+        assert!(propagate(&mut waves, width, &support, &mut frontier, &mut constraints));
+        queue_propagate(&mut queued, &(0..waves.len()).collect::<Vec<_>>());
+        assert_eq!(waves, queued);
+
+        // Then collapse cells the way generation does
+        for _ in 0..300 {
+            let i = lowest_entropy(&waves).unwrap();
+            let options: Vec<u32> = (0..tiles.len() as u32).filter(|t| waves[i] & 1 << t != 0).collect();
+            let tile = 1 << options[rng.random_range(0..options.len())];
+            (waves[i], queued[i]) = (tile, tile);
+            frontier.push(i);
+            let consistent = propagate(&mut waves, width, &support, &mut frontier, &mut constraints);
+            queue_propagate(&mut queued, &[i]);
+            assert!(consistent && !queued.contains(&0));
+            assert_eq!(waves, queued);
+        }
+    }
+
+    #[test]
+    fn room_size_distributions_and_large_rooms() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let samples = |dist: RoomSizeDistribution, rng: &mut StdRng| -> Vec<i32> {
+            (0..2000).map(|_| dist.sample(rng, 4, 20)).collect()
+        };
+        let small = |v: &[i32]| v.iter().filter(|&&s| s <= 8).count();
+        let large = |v: &[i32]| v.iter().filter(|&&s| s >= 16).count();
+
+        let normal = samples(RoomSizeDistribution::Normal, &mut rng);
+        assert!(normal.iter().all(|s| (4..=20).contains(s)));
+        let extreme = normal.iter().filter(|&&s| s <= 6 || s >= 18).count();
+        assert!(extreme < 400 && normal.iter().filter(|&&s| (10..=14).contains(&s)).count() > 800);
+        // Mostly small rooms, a few large ones, little in between
+        let bimodal = samples(RoomSizeDistribution::Bimodal, &mut rng);
+        assert!(small(&bimodal) > 1200 && (200..800).contains(&large(&bimodal)));
+
+        let mut p = params_base();
+        p.guaranteed_large_rooms = 2;
+        let level = generate(&p);
+        let big = level.rooms.iter().filter(|r| r.w >= p.max_room as i32 && r.h >= p.max_room as i32).count();
+        assert!(big >= 2);
+        assert!(level.rooms.len() <= p.rooms as usize);
+    }
+
+    #[test]
+    fn corridor_elevations_ramp_one_level_per_step() {
+        // Rooms at elevations 0 and 3 joined by a six-tile corridor with a spur
+        let rows = ["############", "#..######..#", "#..........#", "#..###.##..#", "######.#####", "######.#####"];
+        let grid: Grid = rows.iter().map(|r| r.chars().collect()).collect();
+        let rooms = [
+            Room { x: 1, y: 1, w: 2, h: 3, elevation: Some(0), ..Default::default() },
+            Room { x: 9, y: 1, w: 2, h: 3, elevation: Some(3), ..Default::default() },
+        ];
+        let mut map = Vec::new();
+        create_corridor_elevation_map(&grid, &rooms, &mut map, &mut GeneratorScratch::default());
+        let corridor: Vec<i32> = (1..11).map(|x| map[2][x]).collect();
+        assert_eq!(corridor, [0, 0, 0, 0, 1, 2, 3, 3, 3, 3]);
+        assert!(corridor.windows(2).all(|w| w[1] - w[0] <= 1 && w[1] >= w[0]));
+        // The spur takes the elevation of the corridor tile it branches from
+        assert_eq!((map[3][6], map[4][6], map[5][6]), (map[2][6], map[2][6], map[2][6]));
+    }
+
+    #[test]
+    fn room_geometry_helpers() {
+        let room = Room { x: 2, y: 3, w: 4, h: 3, ..Default::default() };
+        assert!(room.contains(2, 3) && room.contains(5, 5) && !room.contains(6, 5) && !room.contains(2, 6));
+
+        let grown = room.expanded(1);
+        assert_eq!((grown.x, grown.y, grown.w, grown.h), (1, 2, 6, 5));
+        let other = Room { x: 4, y: 1, w: 5, h: 3, ..Default::default() };
+        let overlap = room.intersection(&other).unwrap();
+        assert_eq!((overlap.x, overlap.y, overlap.w, overlap.h), (4, 3, 2, 1));
+        assert!(room.intersection(&Room { x: 6, ..other }).is_none());
+
+        let ring = room.perimeter_tiles();
+        assert_eq!(ring.len(), 10);
+        assert_eq!(&ring[..4], &[(2, 3), (3, 3), (4, 3), (5, 3)]);
+        assert!(ring.iter().all(|&(x, y)| room.contains(x, y) && !room.expanded(-1).contains(x, y)));
+        assert_eq!(Room { w: 1, ..room.clone() }.perimeter_tiles(), [(2, 3), (2, 4), (2, 5)]);
+
+        let mut rng = StdRng::seed_from_u64(1);
+        assert!((0..100).all(|_| {
+            let (x, y) = room.random_point_in(&mut rng);
+            room.contains(x, y)
+        }));
+
+        let level = generate(&params_base());
+        assert!(level.rooms.iter().enumerate().all(|(i, r)| r.id == i));
+        let json = serde_json::to_string(&level).unwrap();
+        assert!(json.contains("\"id\":1,"));
+    }
 }
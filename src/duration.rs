@@ -0,0 +1,162 @@
+//! Track length and run time of the main path.
+//!
+//! [`Level::estimate_run`] rolls a marble along the main path (the shortest
+//! walk from the entrance room's center to the exit room's center, as in
+//! [`Level::main_path_length`]) with a simple rolling-ball model, for "about
+//! 45 s" labels in level selection without a physics engine:
+//!
+//! - Each step to the next tile covers [`RunModel::tile_meters`] across and
+//!   the climb or drop between the two tiles' elevations, at
+//!   [`RunModel::level_meters`] per level.
+//! - A rolling solid ball accelerates at `5/7 · g` along the slope and loses
+//!   speed to rolling friction in proportion to [`RunModel::rolling_friction`].
+//! - The marble starts at [`RunModel::start_speed`] and never drops below
+//!   [`RunModel::min_speed`], so flat stretches and climbs take a nudge
+//!   rather than stopping it.
+//!
+//! Obstacles, launch pads, and turns do not change the speed. `stats` reports
+//! the estimate for the default [`RunModel`] as `main_path_meters` and
+//! `main_path_seconds` on marble levels.
+//!
+//! ```
+//! use level_generator::{generate, GenerationMode, GeneratorParams};
+//! use level_generator::analysis::RunModel;
+//!
+//! let level = generate(&GeneratorParams { seed: Some(4), mode: GenerationMode::Marble, enable_elevation: true, ..Default::default() });
+//! let run = level.estimate_run(&RunModel::default()).unwrap();
+//! assert_eq!(level.stats.main_path_seconds, Some(run.seconds));
+//! println!("about {:.0} s over {:.1} m", run.seconds, run.meters);
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::dungeon::Level;
+use crate::roles::RoomRole;
+use crate::visibility::tile_elevation;
+use crate::zones::{bfs, start_tile};
+
+/// Gravity in m/s².
+const GRAVITY: f32 = 9.81;
+/// Share of gravity a rolling solid ball accelerates with.
+const ROLLING_SHARE: f32 = 5.0 / 7.0;
+
+/// Physical scale and friction of [`Level::estimate_run`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RunModel {
+    /// Length of a tile in meters
+    pub tile_meters: f32,
+    /// Height of one elevation level in meters
+    pub level_meters: f32,
+    /// Rolling friction coefficient
+    pub rolling_friction: f32,
+    /// Speed at the entrance in m/s
+    pub start_speed: f32,
+    /// Lowest speed in m/s
+    pub min_speed: f32,
+}
+
+impl Default for RunModel {
+    fn default() -> Self {
+        Self { tile_meters: 0.1, level_meters: 0.05, rolling_friction: 0.02, start_speed: 0.5, min_speed: 0.25 }
+    }
+}
+
+/// Result of [`Level::estimate_run`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RunEstimate {
+    /// Length of the main path along the track, slopes included, in meters
+    pub meters: f32,
+    /// Time to roll it, in seconds
+    pub seconds: f32,
+    /// Speed at the exit in m/s
+    pub exit_speed: f32,
+}
+
+impl Level {
+    /// Tiles of the shortest walk from the entrance to the exit room's
+    /// center, both ends included; the walk [`Level::main_path_length`]
+    /// counts the steps of. `None` without an exit or a walk to it.
+    pub fn main_path(&self) -> Option<Vec<(i32, i32)>> {
+        let exit = self.rooms.iter().find(|r| r.role == RoomRole::Exit)?;
+        let grid: Vec<Vec<char>> = self.ascii_tiles().iter().map(|row| row.chars().collect()).collect();
+        let start = start_tile(&grid, &self.rooms)?;
+        let (distance, _) = bfs(&grid, &[(start, 0)], self.topology);
+        let at = |(x, y): (i32, i32)| *distance.get(usize::try_from(y).ok()?)?.get(usize::try_from(x).ok()?)?;
+        // Walk back from the exit, one step closer to the entrance at a time
+        let mut tile = exit.center();
+        let mut steps = at(tile)?;
+        let mut path = vec![tile];
+        while steps > 0 {
+            tile = self.topology.neighbors(tile.0, tile.1).into_iter().find(|&n| at(n) == Some(steps - 1))?;
+            path.push(tile);
+            steps -= 1;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// Length and rolling time of the main path; see the module docs.
+    /// `None` for levels without marble tiles or a main path.
+    pub fn estimate_run(&self, model: &RunModel) -> Option<RunEstimate> {
+        self.marble_tiles.as_ref()?;
+        let path = self.main_path()?;
+        let mut run = RunEstimate { meters: 0.0, seconds: 0.0, exit_speed: model.start_speed.max(model.min_speed) };
+        for step in path.windows(2) {
+            let ((x0, y0), (x1, y1)) = (step[0], step[1]);
+            let across = model.tile_meters;
+            let rise = (tile_elevation(self, x1, y1) - tile_elevation(self, x0, y0)) as f32 * model.level_meters;
+            let length = across.hypot(rise);
+            // Energy per unit mass: drop minus friction along the step
+            let gain = 2.0 * ROLLING_SHARE * GRAVITY * (-rise - model.rolling_friction * across);
+            let speed = (run.exit_speed * run.exit_speed + gain).max(0.0).sqrt().max(model.min_speed);
+            run.meters += length;
+            run.seconds += 2.0 * length / (run.exit_speed + speed);
+            run.exit_speed = speed;
+        }
+        Some(run)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams, Room};
+    use crate::marble::AdvancedTileConfig;
+
+    #[test]
+    fn drops_roll_faster_than_flats() {
+        let map = "\
+##########
+#........#
+##########";
+        let mut flat = Level::from_ascii_with(map, AdvancedTileConfig::none()).unwrap();
+        flat.rooms = vec![
+            Room { x: 1, y: 1, w: 1, h: 1, role: RoomRole::Entrance, ..Default::default() },
+            Room { x: 8, y: 1, w: 1, h: 1, role: RoomRole::Exit, ..Default::default() },
+        ];
+        let path = flat.main_path().expect("entrance and exit are joined");
+        assert_eq!(path.len() as u32, flat.main_path_length().unwrap() + 1);
+        let model = RunModel::default();
+        let level_run = flat.estimate_run(&model).unwrap();
+        assert!((level_run.meters - model.tile_meters * (path.len() - 1) as f32).abs() < 1e-4);
+
+        // The same track tilted down toward the exit
+        let mut downhill = flat.clone();
+        let exit = path.last().copied().unwrap();
+        for row in downhill.marble_tiles.as_mut().unwrap() {
+            for (x, tile) in row.iter_mut().enumerate() {
+                tile.elevation = (exit.0 - x as i32).max(0);
+            }
+        }
+        let down_run = downhill.estimate_run(&model).unwrap();
+        assert!(down_run.seconds < level_run.seconds && down_run.exit_speed > level_run.exit_speed);
+        assert!(down_run.meters > level_run.meters);
+
+        // Stats carry the estimate on marble levels only
+        let marble = generate(&GeneratorParams { seed: Some(1176), mode: GenerationMode::Marble, enable_elevation: true, ..Default::default() });
+        let run = marble.estimate_run(&model).unwrap();
+        assert_eq!((marble.stats.main_path_meters, marble.stats.main_path_seconds), (Some(run.meters), Some(run.seconds)));
+        let classic = generate(&GeneratorParams { seed: Some(1176), ..Default::default() });
+        assert!(classic.stats.main_path_seconds.is_none() && classic.estimate_run(&model).is_none());
+    }
+}
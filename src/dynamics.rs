@@ -0,0 +1,154 @@
+//! Moving hazard markers for marble channels.
+//!
+//! Dynamic elements (pendulums, rotating bars, timed gates) are not tiles:
+//! the tile grid stays unchanged and each element is emitted as a marker in
+//! `Level::dynamics` with the channel section it spans and its timing, so the
+//! game engine can animate it. Markers are placed on corridor sections that
+//! are at least [`MIN_DYNAMIC_WIDTH`] tiles wide, away from rooms and from
+//! each other.
+
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::dungeon::Room;
+use crate::ramp::DifficultyRamp;
+use crate::tiles::{Direction, MarbleTile, TileType};
+
+/// Narrowest channel cross-section that can hold a moving element.
+pub const MIN_DYNAMIC_WIDTH: i32 = 3;
+
+/// Smallest Manhattan distance between two markers.
+const MIN_SPACING: i32 = 8;
+
+/// Kind of moving hazard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum DynamicKind {
+    /// Weight swinging across the channel from above
+    Pendulum,
+    /// Bar spinning flat around the center of the channel
+    RotatingBar,
+    /// Gate that periodically closes the whole channel
+    TimedGate,
+}
+
+impl DynamicKind {
+    /// All kinds, in declaration order.
+    pub const ALL: [DynamicKind; 3] = [DynamicKind::Pendulum, DynamicKind::RotatingBar, DynamicKind::TimedGate];
+}
+
+/// A moving hazard marker.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DynamicElement {
+    pub id: usize,
+    pub kind: DynamicKind,
+    /// Center tile of the channel section
+    pub x: i32,
+    pub y: i32,
+    pub elevation: i32,
+    /// Direction the channel runs; the element moves across it
+    pub axis: Direction,
+    /// Tiles covered across the channel, including the center
+    pub span: u32,
+    /// Seconds per full cycle
+    pub period: f32,
+    /// Cycle offset at time zero, 0.0 to 1.0
+    pub phase: f32,
+}
+
+fn open(tile: &MarbleTile) -> bool {
+    tile.tile_type.is_passable() && !matches!(tile.tile_type, TileType::Obstacle | TileType::Funnel)
+}
+
+/// Length of the open run through `(x, y)` along `(dx, dy)`, and the
+/// position of `(x, y)` within it.
+fn run(tiles: &[Vec<MarbleTile>], x: i32, y: i32, dx: i32, dy: i32) -> (i32, i32) {
+    let open_at = |x: i32, y: i32| {
+        y >= 0 && (y as usize) < tiles.len() && x >= 0 && (x as usize) < tiles[0].len() && open(&tiles[y as usize][x as usize])
+    };
+    let before = (1..).take_while(|&i| open_at(x - dx * i, y - dy * i)).count() as i32;
+    let after = (1..).take_while(|&i| open_at(x + dx * i, y + dy * i)).count() as i32;
+    (before + after + 1, before)
+}
+
+/// Place up to `count` dynamic element markers on wide corridor sections,
+/// favoring sections far along `ramp` (see [`crate::ramp`]).
+pub fn place_dynamics(tiles: &[Vec<MarbleTile>], rooms: &[Room], count: u32, ramp: &DifficultyRamp, rng: &mut StdRng) -> Vec<DynamicElement> {
+    let in_room = |x: i32, y: i32| rooms.iter().any(|r| r.expanded(1).contains(x, y));
+
+    // Center tiles of wide channel cross-sections
+    let mut candidates: Vec<(i32, i32, Direction, i32)> = Vec::new();
+    for (y, row) in tiles.iter().enumerate() {
+        for (x, tile) in row.iter().enumerate() {
+            let (x, y) = (x as i32, y as i32);
+            if !open(tile) || tile.tile_type == TileType::Slope || in_room(x, y) {
+                continue;
+            }
+            let (run_x, pos_x) = run(tiles, x, y, 1, 0);
+            let (run_y, pos_y) = run(tiles, x, y, 0, 1);
+            // The channel runs along the longer axis; the element spans the shorter one
+            let (axis, width, pos) =
+                if run_x >= run_y { (Direction::East, run_y, pos_y) } else { (Direction::South, run_x, pos_x) };
+            if width >= MIN_DYNAMIC_WIDTH && pos == width / 2 {
+                candidates.push((x, y, axis, width));
+            }
+        }
+    }
+
+    let mut placed: Vec<DynamicElement> = Vec::new();
+    while placed.len() < count as usize && !candidates.is_empty() {
+        let (x, y, axis, width) = candidates.swap_remove(rng.random_range(0..candidates.len()));
+        if placed.iter().any(|d| (d.x - x).abs() + (d.y - y).abs() < MIN_SPACING) {
+            continue;
+        }
+        if ramp.strength() > 0.0 && !rng.random_bool(f64::from(ramp.scale(x, y) / (1.0 + ramp.strength())).clamp(0.0, 1.0)) {
+            continue;
+        }
+        placed.push(DynamicElement {
+            id: placed.len(),
+            kind: DynamicKind::ALL[rng.random_range(0..DynamicKind::ALL.len())],
+            x,
+            y,
+            elevation: tiles[y as usize][x as usize].elevation,
+            axis,
+            span: width as u32,
+            period: rng.random_range(1.5..4.0),
+            phase: rng.random_range(0.0..1.0),
+        });
+    }
+    placed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    #[allow(clippy::needless_range_loop)]
+    fn markers_sit_on_wide_channels_only() {
+        // A 3-wide horizontal channel and a 1-wide vertical one
+        let mut tiles = vec![vec![MarbleTile::empty(); 30]; 12];
+        for y in 2..5 {
+            for x in 0..30 {
+                tiles[y][x] = MarbleTile::with_params(TileType::Straight, 0, 1, true);
+            }
+        }
+        for y in 5..12 {
+            tiles[y][20] = MarbleTile::with_params(TileType::Straight, 0, 0, true);
+        }
+        let mut rng = StdRng::seed_from_u64(1);
+
+        let dynamics = place_dynamics(&tiles, &[], 10, &DifficultyRamp::default(), &mut rng);
+        assert!(!dynamics.is_empty());
+        for d in &dynamics {
+            assert_eq!((d.y, d.axis, d.span), (3, Direction::East, 3));
+            assert!((0.0..1.0).contains(&d.phase) && d.period >= 1.5);
+        }
+        for pair in dynamics.windows(2) {
+            assert!((pair[0].x - pair[1].x).abs() >= MIN_SPACING);
+        }
+    }
+}
@@ -0,0 +1,310 @@
+//! Editing finished levels.
+//!
+//! [`Level::set_tile`], [`Level::carve_rect`], [`Level::fill_rect`], and
+//! [`Level::stamp_prefab`] change the ASCII tiles; anything outside the map
+//! is clipped, and a lean level (see [`crate::memory`]) gets its rows back. On marble levels the marble grid is stale after an edit until
+//! it is re-derived. [`Level::update_marble_tiles`] re-runs tile detection
+//! (types, advanced tiles, slopes, walls) over a dirty region the caller
+//! names, plus a one-tile border, the reach of every neighbor check.
+//! [`Level::rebuild_marble_tiles`] finds the region itself: the bounding box
+//! of the tiles whose floor state no longer matches their marble tile.
+//!
+//! Obstacles and structures touching the rebuilt area are taken out whole and
+//! moving hazards inside it are dropped. Rooms, connections, rivers, and
+//! voxels are left as they are, and so are launch pads and gate directions
+//! outside the rebuilt area. Inside it, no launch pads are placed, gates
+//! get their detected direction back, and open bowl rooms go back to channel
+//! tiles until [`open_bowl_rooms`](crate::marble::open_bowl_rooms) runs again.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, VecDeque};
+
+use crate::dungeon::{Grid, Level, Room, TILE_FLOOR, TILE_WALL};
+use crate::marble::{detect_marble_tiles, MarbleTileConfig};
+use crate::memory::ascii_char;
+use crate::obstacles::remove_obstacles;
+use crate::tiles::{compute_walls, MarbleTile, TileType};
+
+/// Tiles of context read around an updated area. Merge detection follows a
+/// channel up to ten tiles out; one more covers the neighbors of those tiles.
+const DETECTION_REACH: i32 = 11;
+
+/// Axis-aligned rectangle of tiles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, w: i32, h: i32) -> Self {
+        Self { x, y, w, h }
+    }
+
+    /// Whether `(x, y)` lies inside the rectangle.
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && x < self.x + self.w && y >= self.y && y < self.y + self.h
+    }
+
+    /// The rectangle grown by `n` tiles on every side.
+    pub fn expand(&self, n: i32) -> Rect {
+        Rect::new(self.x - n, self.y - n, self.w + 2 * n, self.h + 2 * n)
+    }
+
+    /// The part of the rectangle inside a `width` x `height` map.
+    pub(crate) fn clip(&self, width: i32, height: i32) -> Rect {
+        let (x0, y0) = (self.x.clamp(0, width), self.y.clamp(0, height));
+        let (x1, y1) = ((self.x + self.w).clamp(0, width), (self.y + self.h).clamp(0, height));
+        Rect::new(x0, y0, x1 - x0, y1 - y0)
+    }
+
+    /// The smallest rectangle covering both this one and `(x, y)`.
+    pub(crate) fn cover(&self, x: i32, y: i32) -> Rect {
+        let (x0, y0) = (self.x.min(x), self.y.min(y));
+        let (x1, y1) = ((self.x + self.w).max(x + 1), (self.y + self.h).max(y + 1));
+        Rect::new(x0, y0, x1 - x0, y1 - y0)
+    }
+}
+
+/// A small ASCII pattern for [`Level::stamp_prefab`]. Spaces are transparent
+/// and leave the level's tile as it is.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Prefab {
+    pub rows: Vec<String>,
+}
+
+impl Prefab {
+    pub fn new(rows: &[&str]) -> Self {
+        Self { rows: rows.iter().map(|r| r.to_string()).collect() }
+    }
+}
+
+impl Level {
+    /// Set the ASCII tile at `(x, y)`.
+    pub fn set_tile(&mut self, x: i32, y: i32, tile: char) {
+        self.edit_row(y, x, [Some(tile)]);
+    }
+
+    /// Turn every tile in `rect` into floor.
+    pub fn carve_rect(&mut self, rect: Rect) {
+        for y in rect.y..rect.y + rect.h {
+            self.edit_row(y, rect.x, std::iter::repeat_n(Some(TILE_FLOOR), rect.w.max(0) as usize));
+        }
+    }
+
+    /// Turn every tile in `rect` into wall.
+    pub fn fill_rect(&mut self, rect: Rect) {
+        for y in rect.y..rect.y + rect.h {
+            self.edit_row(y, rect.x, std::iter::repeat_n(Some(TILE_WALL), rect.w.max(0) as usize));
+        }
+    }
+
+    /// Copy `prefab` onto the map with its top-left corner at `(x, y)`.
+    pub fn stamp_prefab(&mut self, prefab: &Prefab, x: i32, y: i32) {
+        for (dy, row) in prefab.rows.iter().enumerate() {
+            self.edit_row(y + dy as i32, x, row.chars().map(|ch| (ch != ' ').then_some(ch)));
+        }
+    }
+
+    /// Bring the marble grid back in line with the ASCII tiles after edits
+    /// (see the module docs). Does nothing for levels without marble tiles
+    /// or without edits.
+    pub fn rebuild_marble_tiles(&mut self) {
+        let Some(marble) = &self.marble_tiles else { return };
+        let mut edited: Option<Rect> = None;
+        for (y, (row, tiles)) in self.tiles.iter().zip(marble).enumerate() {
+            for (x, (ch, tile)) in row.chars().zip(tiles).enumerate() {
+                if (ch == TILE_FLOOR) == (tile.tile_type == TileType::Empty) {
+                    let (x, y) = (x as i32, y as i32);
+                    edited = Some(edited.map_or(Rect::new(x, y, 1, 1), |r| r.cover(x, y)));
+                }
+            }
+        }
+        if let Some(rect) = edited {
+            self.update_marble_tiles(rect);
+            self.refresh_stats();
+        }
+    }
+
+    /// Overwrite row `y` from column `x` on; `None` cells are skipped. A
+    /// lean level gets its ASCII rows back, since they hold the edit.
+    fn edit_row(&mut self, y: i32, x: i32, cells: impl IntoIterator<Item = Option<char>>) {
+        self.restore_ascii_tiles();
+        let Some(row) = usize::try_from(y).ok().and_then(|y| self.tiles.get_mut(y)) else { return };
+        let mut chars: Vec<char> = row.chars().collect();
+        for (i, cell) in cells.into_iter().enumerate() {
+            let Some(ch) = cell else { continue };
+            if let Some(slot) = usize::try_from(x + i as i32).ok().and_then(|x| chars.get_mut(x)) {
+                *slot = ch;
+            }
+        }
+        *row = chars.into_iter().collect();
+    }
+
+    /// Re-derive the marble tiles in `region` plus a one-tile border, e.g.
+    /// after an editor changed a few ASCII tiles inside it.
+    ///
+    /// Tile types, advanced tiles, slopes, and walls are recomputed from a
+    /// window of the map around the region, so the cost scales with the
+    /// region rather than the map. Statistics are not refreshed; use
+    /// [`Level::rebuild_marble_tiles`] for that. Does nothing for levels
+    /// without marble tiles.
+    pub fn update_marble_tiles(&mut self, region: Rect) {
+        let Some(tiles) = self.marble_tiles.as_mut() else { return };
+        let height = tiles.len() as i32;
+        let width = tiles.first().map_or(0, |row| row.len() as i32);
+
+        // Obstacles and structures are kept or taken out whole, so grow the
+        // dirty area over every one it touches
+        let touches = |region: &Rect, footprint: &[(i32, i32)]| footprint.iter().any(|&(x, y)| region.contains(x, y));
+        let mut dirty = region.expand(1);
+        loop {
+            let before = dirty;
+            let footprints = self.obstacles.iter().map(|o| &o.tiles).chain(self.structures.iter().map(|s| &s.tiles));
+            for footprint in footprints.filter(|f| touches(&before, f)) {
+                dirty = footprint.iter().fold(dirty, |r, &(x, y)| r.cover(x, y));
+            }
+            if dirty == before {
+                break;
+            }
+        }
+        let dirty = dirty.clip(width, height);
+        if dirty.w <= 0 || dirty.h <= 0 {
+            return;
+        }
+        let (removed, kept) = std::mem::take(&mut self.obstacles).into_iter().partition::<Vec<_>, _>(|o| touches(&dirty, &o.tiles));
+        remove_obstacles(tiles, &removed);
+        self.obstacles = kept;
+        self.structures.retain(|s| !touches(&dirty, &s.tiles));
+        self.dynamics.retain(|d| !dirty.contains(d.x, d.y));
+
+        // Detect on a copy of the window around the dirty area, in window coordinates
+        let window = dirty.expand(DETECTION_REACH).clip(width, height);
+        let (wx, wy, ww, wh) = (window.x as usize, window.y as usize, window.w as usize, window.h as usize);
+        let grid: Grid = if self.tiles.is_empty() {
+            // Lean levels hold their floor in the marble tiles
+            tiles[wy..wy + wh].iter().map(|row| row[wx..wx + ww].iter().map(ascii_char).collect()).collect()
+        } else {
+            self.tiles[wy..wy + wh].iter().map(|row| row.chars().skip(wx).take(ww).collect()).collect()
+        };
+        let mut local: Vec<Vec<MarbleTile>> = tiles[wy..wy + wh].iter().map(|row| row[wx..wx + ww].to_vec()).collect();
+        let rooms: Vec<Room> = self.rooms.iter().map(|r| Room { x: r.x - window.x, y: r.y - window.y, ..r.clone() }).collect();
+        let xs = dirty.x as usize - wx..(dirty.x + dirty.w) as usize - wx;
+        let ys = dirty.y as usize - wy..(dirty.y + dirty.h) as usize - wy;
+        let is_floor = |x: i32, y: i32| x >= 0 && y >= 0 && x < window.w && y < window.h && grid[y as usize][x as usize] == TILE_FLOOR;
+
+        // New floor takes its room's elevation, or spreads from the floor it joins
+        let mut elevation: Vec<Vec<i32>> = local.iter().map(|row| row.iter().map(|t| t.elevation).collect()).collect();
+        // Ordered so the spread, and which neighbour a tile copies, is the same every run
+        let mut fresh: BTreeSet<(i32, i32)> = BTreeSet::new();
+        for y in ys.clone() {
+            for x in xs.clone() {
+                if grid[y][x] != TILE_FLOOR || local[y][x].tile_type != TileType::Empty {
+                    continue;
+                }
+                let (ix, iy) = (x as i32, y as i32);
+                match rooms.iter().find(|r| r.contains(ix, iy)) {
+                    Some(room) => elevation[y][x] = room.elevation.unwrap_or(0),
+                    None => {
+                        fresh.insert((ix, iy));
+                    }
+                }
+            }
+        }
+        let sides = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+        let mut queue: VecDeque<(i32, i32)> = fresh
+            .iter()
+            .flat_map(|&(x, y)| sides.map(|(dx, dy)| (x + dx, y + dy)))
+            .filter(|&(x, y)| is_floor(x, y) && !fresh.contains(&(x, y)))
+            .collect();
+        while let Some((x, y)) = queue.pop_front() {
+            for (dx, dy) in sides {
+                if fresh.remove(&(x + dx, y + dy)) {
+                    elevation[(y + dy) as usize][(x + dx) as usize] = elevation[y as usize][x as usize];
+                    queue.push_back((x + dx, y + dy));
+                }
+            }
+        }
+
+        let enable_elevation = self.rooms.iter().any(|r| r.elevation.is_some());
+        let config = MarbleTileConfig { rooms: &rooms, elevation: enable_elevation.then_some(&elevation[..]), ..Default::default() };
+        detect_marble_tiles(&mut local, &grid, &config, xs.clone(), ys.clone());
+        compute_walls(&mut local);
+        for y in ys {
+            tiles[wy + y][wx + xs.start..wx + xs.end].clone_from_slice(&local[y][xs.clone()]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams};
+    use crate::marble::grid_to_marble_tiles;
+
+    #[test]
+    fn edits_rebuild_the_same_tiles_as_a_full_pass() {
+        // Launch pads follow the main path, which detection does not trace
+        let params = GeneratorParams { seed: Some(8), mode: GenerationMode::Marble, max_launch_pads: 0, ..Default::default() };
+        let mut level = generate(&params);
+        let original = level.clone();
+        level.rebuild_marble_tiles();
+        assert_eq!(level.marble_tiles, original.marble_tiles);
+
+        let room = level.rooms[0].clone();
+        level.fill_rect(Rect::new(room.x, room.y, 2, room.h));
+        level.carve_rect(Rect::new(-2, -2, 5, 4));
+        level.stamp_prefab(&Prefab::new(&["#.#", " . ", "#.#"]), room.x + 2, room.y);
+        level.set_tile(room.x + room.w - 1, room.y + room.h - 1, TILE_WALL);
+        assert_eq!(&level.tiles[1][..3], "...");
+        assert_ne!(level.tiles, original.tiles);
+        level.rebuild_marble_tiles();
+
+        let grid: Grid = level.tiles.iter().map(|row| row.chars().collect()).collect();
+        let mut expected = grid_to_marble_tiles(&grid, &MarbleTileConfig { rooms: &level.rooms, ..Default::default() });
+        compute_walls(&mut expected);
+        assert_eq!(level.marble_tiles, Some(expected));
+    }
+
+    #[test]
+    fn updating_a_region_leaves_the_rest_untouched() {
+        let params = GeneratorParams {
+            seed: Some(21),
+            mode: GenerationMode::Marble,
+            width: 160,
+            height: 120,
+            rooms: 20,
+            enable_elevation: true,
+            max_launch_pads: 0,
+            ..Default::default()
+        };
+        let mut level = generate(&params);
+        let original = level.clone();
+        let room = level.rooms[1].clone();
+        let dirty = Rect::new(room.x + room.w - 1, room.y + 1, 4, 2);
+        level.carve_rect(dirty);
+        level.update_marble_tiles(dirty);
+
+        let (before, after) = (original.marble_tiles.unwrap(), level.marble_tiles.clone().unwrap());
+        assert_ne!(before, after);
+        let border = dirty.expand(1);
+        for (y, (old, new)) in before.iter().zip(&after).enumerate() {
+            for (x, (old, new)) in old.iter().zip(new).enumerate() {
+                if !border.contains(x as i32, y as i32) {
+                    assert_eq!(old, new, "tile ({x}, {y}) outside the region changed");
+                }
+            }
+        }
+        // New floor joins the room at the room's elevation
+        assert_eq!(after[dirty.y as usize][(dirty.x + 1) as usize].elevation, room.elevation.unwrap());
+
+        let grid: Grid = level.tiles.iter().map(|row| row.chars().collect()).collect();
+        let elevation: Vec<Vec<i32>> = after.iter().map(|row| row.iter().map(|t| t.elevation).collect()).collect();
+        let config = MarbleTileConfig { rooms: &level.rooms, elevation: Some(&elevation), ..Default::default() };
+        let mut expected = grid_to_marble_tiles(&grid, &config);
+        compute_walls(&mut expected);
+        assert_eq!(after, expected);
+    }
+}
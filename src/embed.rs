@@ -0,0 +1,131 @@
+//! Sub-levels generated inside a room of a finished level.
+//!
+//! [`Level::embed_level`] fills one room with a level of its own, a small
+//! maze as a vault or a curly marble section inside an open bowl, say. The
+//! sub-level is generated at the room's size and replaces the room's tiles;
+//! then every corridor that used to lead into the room is carved on to the
+//! nearest floor tile of the sub-level, so the room keeps its entrances.
+//! WFC sub-levels become floor along the maze's pipes.
+//!
+//! Rooms, connections, and statistics of the parent are left as they are.
+//! Marble levels get their marble tiles re-derived over the room, as after
+//! any edit (see [`Level::update_marble_tiles`]).
+//!
+//! ```
+//! use level_generator::{generate, GenerationMode, GeneratorParams};
+//!
+//! let mut level = generate(&GeneratorParams { seed: Some(3), guaranteed_large_rooms: 1, max_room: 14, ..Default::default() });
+//! let (vault, _) = level.rooms.iter().enumerate().max_by_key(|(_, r)| r.w * r.h).unwrap();
+//! level.embed_level(vault, &GeneratorParams { mode: GenerationMode::Wfc, ..Default::default() }).unwrap();
+//! ```
+
+use std::fmt;
+
+use crate::dungeon::{GeneratorParams, Level, MIN_MAP_DIM, TILE_FLOOR};
+use crate::editing::Rect;
+use crate::generator::{generate_with, LevelGenerator};
+use crate::hybrid::{carve_corridor, map_char};
+use crate::seeds::{stage_seed, SeedStage};
+
+/// Error from [`Level::embed_level`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmbedError {
+    /// The level has no room with this index
+    NoSuchRoom(usize),
+    /// The room is narrower or shorter than [`MIN_MAP_DIM`] tiles
+    RoomTooSmall { w: i32, h: i32 },
+}
+
+impl fmt::Display for EmbedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmbedError::NoSuchRoom(room) => write!(f, "no room {}", room),
+            EmbedError::RoomTooSmall { w, h } => write!(f, "room is {}x{}, sub-levels need at least {}x{}", w, h, MIN_MAP_DIM, MIN_MAP_DIM),
+        }
+    }
+}
+
+impl std::error::Error for EmbedError {}
+
+impl Level {
+    /// Generate a sub-level with `params.mode` inside room `room`; see the
+    /// module docs. The size comes from the room. Without `params.seed`, the
+    /// sub-level's seed is derived from the level seed and the room index.
+    pub fn embed_level(&mut self, room: usize, params: &GeneratorParams) -> Result<(), EmbedError> {
+        self.embed_level_with(room, params.mode.generator(), params)
+    }
+
+    /// [`embed_level`](Level::embed_level) with a custom generator.
+    pub fn embed_level_with(&mut self, room: usize, generator: &dyn LevelGenerator, params: &GeneratorParams) -> Result<(), EmbedError> {
+        let r = self.rooms.get(room).ok_or(EmbedError::NoSuchRoom(room))?.clone();
+        if r.w < MIN_MAP_DIM as i32 || r.h < MIN_MAP_DIM as i32 {
+            return Err(EmbedError::RoomTooSmall { w: r.w, h: r.h });
+        }
+        let area = Rect::new(r.x, r.y, r.w, r.h);
+        let seed = params.seed.unwrap_or_else(|| stage_seed(self.seed.wrapping_add(room as u64 + 1), SeedStage::Layout));
+        let sub_params = GeneratorParams { width: r.w as u32, height: r.h as u32, seed: Some(seed), start_point: None, ..params.clone() };
+        let sub = generate_with(generator, &sub_params);
+
+        let mut grid: Vec<Vec<char>> = self.ascii_tiles().iter().map(|row| row.chars().collect()).collect();
+        let (width, height) = (self.width as i32, self.height as i32);
+        let outside_floor =
+            |grid: &[Vec<char>], x: i32, y: i32| !area.contains(x, y) && x >= 0 && y >= 0 && x < width && y < height && grid[y as usize][x as usize] == TILE_FLOOR;
+        // Room edge tiles a corridor runs into, and whether it arrives from above or below
+        let mouths: Vec<((i32, i32), bool)> = (area.y..area.y + area.h)
+            .flat_map(|y| (area.x..area.x + area.w).map(move |x| (x, y)))
+            .filter_map(|(x, y)| {
+                let vertical = outside_floor(&grid, x, y - 1) || outside_floor(&grid, x, y + 1);
+                (vertical || outside_floor(&grid, x - 1, y) || outside_floor(&grid, x + 1, y)).then_some(((x, y), vertical))
+            })
+            .collect();
+
+        for (y, row) in sub.ascii_tiles().iter().take(r.h as usize).enumerate() {
+            for (x, ch) in row.chars().take(r.w as usize).enumerate() {
+                grid[(r.y as usize) + y][(r.x as usize) + x] = map_char(ch);
+            }
+        }
+        for ((mx, my), vertical) in mouths {
+            let nearest = (area.y..area.y + area.h)
+                .flat_map(|y| (area.x..area.x + area.w).map(move |x| (x, y)))
+                .filter(|&(x, y)| (x, y) != (mx, my) && grid[y as usize][x as usize] == TILE_FLOOR)
+                .min_by_key(|&(x, y)| (x - mx).abs() + (y - my).abs());
+            match nearest {
+                // The corridor's last leg runs straight in through the mouth
+                Some(target) if vertical => carve_corridor(&mut grid, target, (mx, my)),
+                Some(target) => carve_corridor(&mut grid, (mx, my), target),
+                None => {}
+            }
+        }
+
+        self.set_ascii_tiles(grid, |level| level.update_marble_tiles(area));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode};
+    use crate::zones::bfs;
+
+    #[test]
+    fn sub_levels_fill_the_room_and_keep_its_entrances() {
+        let params = GeneratorParams { seed: Some(11), guaranteed_large_rooms: 1, max_room: 14, ..Default::default() };
+        let mut level = generate(&params);
+        let (vault, room) = level.rooms.iter().enumerate().max_by_key(|(_, r)| r.w * r.h).map(|(i, r)| (i, r.clone())).unwrap();
+        let before = level.tiles.clone();
+        level.embed_level(vault, &GeneratorParams { mode: GenerationMode::Wfc, ..Default::default() }).unwrap();
+
+        // Only the room changed, and the whole map is still one floor region
+        let changed = |x: usize, y: usize| before[y].as_bytes()[x] != level.tiles[y].as_bytes()[x];
+        assert!((0..level.height as usize).all(|y| (0..level.width as usize).all(|x| !changed(x, y) || Rect::new(room.x, room.y, room.w, room.h).contains(x as i32, y as i32))));
+        assert!((0..level.height as usize).any(|y| (0..level.width as usize).any(|x| changed(x, y))));
+        let grid: Vec<Vec<char>> = level.tiles.iter().map(|row| row.chars().collect()).collect();
+        let (sx, sy) = level.rooms[(vault + 1) % level.rooms.len()].center();
+        let (distance, _) = bfs(&grid, &[((sx as usize, sy as usize), 0)], level.topology);
+        let floor_reached = grid.iter().flatten().zip(distance.iter().flatten()).all(|(&c, d)| c != TILE_FLOOR || d.is_some());
+        assert!(floor_reached);
+
+        assert_eq!(level.embed_level(99, &params), Err(EmbedError::NoSuchRoom(99)));
+    }
+}
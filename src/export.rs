@@ -0,0 +1,424 @@
+//! Saving and loading levels.
+//!
+//! [`to_json`] writes the JSON format the CLI produces (see the README's
+//! schema) and [`from_json`] reads it back. [`to_json_with`] can also
+//! resolve every marble tile's connections, so importers need not repeat
+//! [`MarbleTile::connections`]. With the `ron` and `yaml` features,
+//! [`to_format`] also writes RON and YAML. [`snapshot`] is the compact
+//! text form used for golden-level tests, and [`VoxelMap`] holds the 3D
+//! export of marble levels. Single scalar layers go out as CSV or PGM via
+//! [`Level::layer`] and [`layer_to_csv`] / [`layer_to_pgm`], and
+//! [`tile_catalog`] describes every tile type for engine importers.
+//! [`Minimap`]s with fog-of-war reveal regions, [`AmbienceZone`]s for
+//! audio, and [`ChannelSpline`]s for spline-based track builders can ride
+//! along in the JSON.
+//!
+//! # Run-length encoded tiles
+//!
+//! Large maps repeat the same few tiles over long stretches. With
+//! [`ExportOptions::tiles_rle`], `tiles` and `marble_tiles` are replaced by
+//! one `tiles_rle` object holding each layer as a palette of distinct
+//! entries and one string per row:
+//!
+//! ```json
+//! "tiles_rle": {
+//!   "ascii": { "palette": ["#", "."], "rows": ["0*12", "0,1*10,0"] },
+//!   "marble": { "palette": [{"tile_type": "Empty", ...}, ...], "rows": ["0*12", "0,1,2*8,3,0"] }
+//! }
+//! ```
+//!
+//! A row lists runs from west to east, separated by commas: `I*N` is `N`
+//! tiles of palette entry `I`, and a plain `I` is one. Palette entries are
+//! numbered in order of first appearance, row by row; the ASCII palette
+//! holds one-character strings and the marble palette whole tile objects
+//! (with `connections` and `open_edges` under
+//! [`ExportOptions::tile_connections`]). `marble` is absent for levels
+//! without marble tiles. [`from_json`] expands `tiles_rle` again, and
+//! refuses rows longer than the level's `width` and layers with more rows
+//! than its `height`.
+//!
+//! # Checksums
+//!
+//! JSON and YAML exports carry a `checksum` of the tile data, which
+//! [`verify`] checks when reading a level back; see [`crate::integrity`].
+//!
+//! # Format versions
+//!
+//! Exported JSON starts with `format_version`, which is [`FORMAT_VERSION`]
+//! at the time of writing. The version goes up with every change to the
+//! exported shape: added, removed, or renamed fields, new enum variants, and
+//! changed meanings alike, each listed in the README. Readers should check
+//! it and treat an unknown version as an error rather than guess.
+//! [`from_json`] reads every older version (missing fields take their
+//! defaults) and refuses newer ones. Optional fields such as those from
+//! [`ExportOptions`] belong to the version too. With the `schema` feature,
+//! [`json_schema`] describes the current version.
+
+pub use crate::ambience::{Ambience, AmbienceZone};
+pub use crate::catalog::{tile_catalog, TileCatalog, TileCatalogEntry};
+pub use crate::import::AsciiImportError;
+pub use crate::integrity::{verify, IntegrityError, CHECKSUM_PREFIX};
+pub use crate::layers::{layer_to_csv, layer_to_pgm, Layer};
+pub use crate::minimap::{Minimap, MinimapRect, RegionKey, RevealRegion};
+pub use crate::splines::ChannelSpline;
+pub use crate::testing::snapshot;
+pub use crate::voxel::VoxelMap;
+
+use std::collections::HashMap;
+
+use serde_json::{json, Map, Value};
+
+use crate::dungeon::Level;
+use crate::tiles::{Direction, MarbleTile};
+
+/// Version of the JSON format written by this crate; see the module docs.
+pub const FORMAT_VERSION: u32 = 19;
+
+/// Options for [`to_json_with`] and the other writers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExportOptions {
+    /// Add `connections` and `open_edges` to every marble tile: the
+    /// directions it connects in after rotation, and `[north, east, south,
+    /// west]` flags for the sides a marble can roll out of (connected and
+    /// not walled)
+    pub tile_connections: bool,
+    /// Add a top-level `minimap` at this many tiles per cell (see
+    /// [`Level::minimap`])
+    pub minimap: Option<u32>,
+    /// Add a top-level `ambience` list of sound zones (see
+    /// [`Level::ambience_zones`])
+    pub ambience: bool,
+    /// Add a top-level `splines` list of channel centerlines (see
+    /// [`Level::channel_splines`])
+    pub splines: bool,
+    /// Replace `tiles` and `marble_tiles` with their run-length encoded
+    /// `tiles_rle`; see the module docs
+    pub tiles_rle: bool,
+}
+
+/// Pretty-printed JSON for `level`.
+pub fn to_json(level: &Level) -> String {
+    to_json_with(level, &ExportOptions::default())
+}
+
+/// Pretty-printed JSON for `level`, with the extra fields `options` asks for.
+/// Extra fields are ignored when the JSON is read back.
+pub fn to_json_with(level: &Level, options: &ExportOptions) -> String {
+    serde_json::to_string_pretty(&export_value(level, options)).expect("levels always serialize")
+}
+
+/// YAML for `level`, with the same fields as [`to_json_with`].
+#[cfg(feature = "yaml")]
+pub fn to_yaml(level: &Level, options: &ExportOptions) -> String {
+    serde_yaml::to_string(&export_value(level, options)).expect("levels always serialize")
+}
+
+/// Pretty-printed RON for `level`, in struct syntax straight from the types.
+/// RON has no room for the extra fields of [`ExportOptions`].
+#[cfg(feature = "ron")]
+pub fn to_ron(level: &Level) -> String {
+    let current = Level { format_version: FORMAT_VERSION, ..level.clone() };
+    ron::ser::to_string_pretty(&current, ron::ser::PrettyConfig::default()).expect("levels always serialize")
+}
+
+/// Text format for [`to_format`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Json,
+    /// Needs the `ron` feature
+    Ron,
+    /// Needs the `yaml` feature
+    Yaml,
+}
+
+impl Format {
+    /// Whether this build can write the format.
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            Format::Json => true,
+            Format::Ron => cfg!(feature = "ron"),
+            Format::Yaml => cfg!(feature = "yaml"),
+        }
+    }
+}
+
+/// `level` written in `format`, or `None` if the crate was built without it.
+pub fn to_format(level: &Level, format: Format, options: &ExportOptions) -> Option<String> {
+    match format {
+        Format::Json => Some(to_json_with(level, options)),
+        #[cfg(feature = "ron")]
+        Format::Ron => Some(to_ron(level)),
+        #[cfg(feature = "yaml")]
+        Format::Yaml => Some(to_yaml(level, options)),
+        #[allow(unreachable_patterns)]
+        _ => None,
+    }
+}
+
+/// The exported document: the level in the current format version, plus
+/// the optional fields.
+fn export_value(level: &Level, options: &ExportOptions) -> Value {
+    let mut value = serde_json::to_value(level).expect("levels always serialize");
+    // A loaded level is written in the current format, whatever it was read from
+    value["format_version"] = json!(FORMAT_VERSION);
+    value["checksum"] = json!(level.checksum());
+    if options.tile_connections {
+        if let (Some(tiles), Some(rows)) = (&level.marble_tiles, value["marble_tiles"].as_array_mut()) {
+            for (tile, entry) in tiles.iter().flatten().zip(rows.iter_mut().filter_map(Value::as_array_mut).flatten()) {
+                if let Value::Object(fields) = entry {
+                    fields.insert("connections".into(), json!(tile.connections()));
+                    fields.insert("open_edges".into(), json!(open_edges(tile)));
+                }
+            }
+        }
+    }
+    if let Some(scale) = options.minimap {
+        value["minimap"] = serde_json::to_value(level.minimap(scale)).expect("minimaps always serialize");
+    }
+    if options.ambience {
+        value["ambience"] = serde_json::to_value(level.ambience_zones()).expect("ambience zones always serialize");
+    }
+    if options.splines {
+        value["splines"] = serde_json::to_value(level.channel_splines()).expect("splines always serialize");
+    }
+    if options.tiles_rle {
+        encode_tiles_rle(&mut value);
+    }
+    value
+}
+
+/// Move `tiles` and `marble_tiles` of the exported document into
+/// `tiles_rle`; see the module docs.
+fn encode_tiles_rle(value: &mut Value) {
+    let Value::Object(fields) = value else { return };
+    let ascii: Vec<Vec<Value>> = match fields.remove("tiles") {
+        Some(Value::Array(rows)) => rows.iter().map(|row| row.as_str().unwrap_or_default().chars().map(|c| json!(c.to_string())).collect()).collect(),
+        _ => Vec::new(),
+    };
+    let mut layers = Map::new();
+    layers.insert("ascii".into(), rle_layer(ascii));
+    if let Some(Value::Array(rows)) = fields.remove("marble_tiles") {
+        let marble = rows.into_iter().map(|row| if let Value::Array(tiles) = row { tiles } else { Vec::new() }).collect();
+        layers.insert("marble".into(), rle_layer(marble));
+    }
+    fields.insert("tiles_rle".into(), Value::Object(layers));
+}
+
+/// Palette and run-length encoded rows of one layer.
+fn rle_layer(rows: Vec<Vec<Value>>) -> Value {
+    let mut palette: Vec<Value> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut encoded = Vec::with_capacity(rows.len());
+    for row in rows {
+        let mut runs: Vec<(usize, usize)> = Vec::new();
+        for entry in row {
+            let i = *index.entry(entry.to_string()).or_insert_with(|| {
+                palette.push(entry);
+                palette.len() - 1
+            });
+            match runs.last_mut() {
+                Some((last, count)) if *last == i => *count += 1,
+                _ => runs.push((i, 1)),
+            }
+        }
+        let runs: Vec<String> = runs.into_iter().map(|(i, count)| if count == 1 { i.to_string() } else { format!("{}*{}", i, count) }).collect();
+        encoded.push(runs.join(","));
+    }
+    json!({ "palette": palette, "rows": encoded })
+}
+
+/// Expand `tiles_rle` back into `tiles` and `marble_tiles`.
+fn decode_tiles_rle(value: &mut Value) -> Result<(), String> {
+    let Some(Value::Object(layers)) = value.as_object_mut().and_then(|fields| fields.remove("tiles_rle")) else { return Ok(()) };
+    // Runs are checked against the level size before anything is expanded
+    let size = |field: &str| value[field].as_u64().and_then(|n| usize::try_from(n).ok()).unwrap_or(0);
+    let (width, height) = (size("width"), size("height"));
+    let expand = |layer: &Value| -> Result<Vec<Vec<Value>>, String> {
+        let palette = layer["palette"].as_array().ok_or("tiles_rle layer without a palette")?;
+        let rows = layer["rows"].as_array().ok_or("tiles_rle layer without rows")?;
+        if rows.len() > height {
+            return Err(format!("tiles_rle layer has {} rows, more than the level height {}", rows.len(), height));
+        }
+        rows.iter()
+            .map(|row| {
+                let mut tiles = Vec::new();
+                for run in row.as_str().ok_or("tiles_rle rows must be strings")?.split(',').filter(|run| !run.is_empty()) {
+                    let (i, count) = run.split_once('*').unwrap_or((run, "1"));
+                    let invalid = || format!("invalid tiles_rle run: {}", run);
+                    let entry = i.parse::<usize>().ok().and_then(|i| palette.get(i)).ok_or_else(invalid)?;
+                    let count: usize = count.parse().map_err(|_| invalid())?;
+                    if count > width - tiles.len() {
+                        return Err(format!("tiles_rle row longer than the level width {}", width));
+                    }
+                    tiles.extend(std::iter::repeat_n(entry.clone(), count));
+                }
+                Ok(tiles)
+            })
+            .collect()
+    };
+    if let Some(ascii) = layers.get("ascii") {
+        let rows: Vec<String> = expand(ascii)?.into_iter().map(|row| row.iter().filter_map(Value::as_str).collect()).collect();
+        value["tiles"] = json!(rows);
+    }
+    if let Some(marble) = layers.get("marble") {
+        value["marble_tiles"] = json!(expand(marble)?);
+    }
+    Ok(())
+}
+
+/// Sides of `tile` a marble can leave through, indexed by [`Direction`].
+fn open_edges(tile: &MarbleTile) -> [bool; 4] {
+    Direction::ALL.map(|d| tile.connects(d) && !tile.has_wall(d))
+}
+
+/// Level from JSON written by [`to_json`] or the CLI, in this format version
+/// or an older one.
+pub fn from_json(json: &str) -> Result<Level, serde_json::Error> {
+    let mut value: Value = serde_json::from_str(json)?;
+    decode_tiles_rle(&mut value).map_err(serde::de::Error::custom)?;
+    let level: Level = serde_json::from_value(value)?;
+    if level.format_version > FORMAT_VERSION {
+        return Err(serde::de::Error::custom(format!(
+            "level format version {} is newer than the supported version {}",
+            level.format_version, FORMAT_VERSION
+        )));
+    }
+    Ok(level)
+}
+
+/// JSON Schema (draft 2020-12) of the exported level JSON, generated from
+/// the types.
+#[cfg(feature = "schema")]
+pub fn json_schema() -> Value {
+    let mut schema = serde_json::to_value(schemars::schema_for!(Level)).expect("schemas always serialize");
+    // Written by the exporter rather than a field of `Level`
+    schema["properties"]["checksum"] = json!({ "type": "string", "description": "Hash of the tile data, `blake3:` and 64 hex digits (see `export::verify`)" });
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams};
+
+    #[test]
+    fn json_round_trips() {
+        let params = GeneratorParams { seed: Some(8), mode: GenerationMode::Marble, enable_elevation: true, ..Default::default() };
+        let level = generate(&params);
+        let loaded = from_json(&to_json(&level)).unwrap();
+        assert_eq!(snapshot(&loaded), snapshot(&level));
+        assert!(from_json("{").is_err());
+    }
+
+    #[test]
+    fn format_versions_are_written_and_checked() {
+        let level = generate(&GeneratorParams { seed: Some(8), ..Default::default() });
+        let json = to_json(&level);
+        assert!(json.starts_with(&format!("{{\n  \"format_version\": {},", FORMAT_VERSION)));
+
+        let mut value: Value = serde_json::from_str(&json).unwrap();
+        value.as_object_mut().unwrap().remove("format_version");
+        let unversioned = from_json(&value.to_string()).unwrap();
+        assert_eq!(unversioned.format_version, 0);
+        assert!(to_json(&unversioned).starts_with(&json[..30]));
+
+        value["format_version"] = json!(FORMAT_VERSION + 1);
+        let err = from_json(&value.to_string()).unwrap_err();
+        assert!(err.to_string().contains("newer"));
+    }
+
+    #[test]
+    fn other_formats_carry_the_same_level() {
+        let level = generate(&GeneratorParams { seed: Some(8), mode: GenerationMode::Marble, ..Default::default() });
+        let options = ExportOptions::default();
+        assert_eq!(to_format(&level, Format::Json, &options), Some(to_json(&level)));
+        for format in [Format::Ron, Format::Yaml] {
+            assert_eq!(to_format(&level, format, &options).is_some(), format.is_enabled());
+        }
+        #[cfg(feature = "ron")]
+        {
+            let loaded: Level = ron::from_str(&to_ron(&level)).unwrap();
+            assert_eq!(snapshot(&loaded), snapshot(&level));
+        }
+        #[cfg(feature = "yaml")]
+        {
+            let loaded: Level = serde_yaml::from_str(&to_yaml(&level, &options)).unwrap();
+            assert_eq!(snapshot(&loaded), snapshot(&level));
+            assert!(to_yaml(&level, &options).starts_with(&format!("format_version: {}", FORMAT_VERSION)));
+        }
+    }
+
+    #[cfg(feature = "schema")]
+    #[test]
+    fn schema_covers_the_exported_fields() {
+        let schema = json_schema();
+        let level = generate(&GeneratorParams { seed: Some(8), mode: GenerationMode::Marble, ..Default::default() });
+        let value: Value = serde_json::from_str(&to_json(&level)).unwrap();
+        for field in value.as_object().unwrap().keys() {
+            assert!(schema["properties"].get(field).is_some(), "{} missing from schema", field);
+        }
+        assert!(schema["$defs"].get("MarbleTile").is_some());
+    }
+
+    #[test]
+    fn tile_connections_match_the_rust_semantics() {
+        let params = GeneratorParams { seed: Some(2), mode: GenerationMode::Marble, ..Default::default() };
+        let level = generate(&params);
+        assert!(!to_json(&level).contains("open_edges"));
+
+        let json = to_json_with(&level, &ExportOptions { tile_connections: true, ..Default::default() });
+        let value: Value = serde_json::from_str(&json).unwrap();
+        let tiles = level.marble_tiles.as_ref().unwrap();
+        for (y, row) in tiles.iter().enumerate() {
+            for (x, tile) in row.iter().enumerate() {
+                let entry = &value["marble_tiles"][y][x];
+                assert_eq!(entry["connections"], json!(tile.connections()));
+                assert_eq!(entry["open_edges"], json!(open_edges(tile)));
+            }
+        }
+        // Rotation is resolved: a quarter-turned straight runs east-west
+        let straight = MarbleTile::with_params(crate::tiles::TileType::Straight, 0, 1, true);
+        assert_eq!(json!(straight.connections()), json!(["East", "West"]));
+        assert_eq!(open_edges(&straight), [false; 4]);
+        let open = MarbleTile { walls: [true, false, true, false], ..straight };
+        assert_eq!(open_edges(&open), [false, true, false, true]);
+        assert_eq!(snapshot(&from_json(&json).unwrap()), snapshot(&level));
+    }
+
+    #[test]
+    fn rle_tiles_round_trip() {
+        let params = GeneratorParams { seed: Some(1179), mode: GenerationMode::Marble, enable_elevation: true, enable_obstacles: true, ..Default::default() };
+        let level = generate(&params);
+        let plain = to_json_with(&level, &ExportOptions { tile_connections: true, ..Default::default() });
+        let json = to_json_with(&level, &ExportOptions { tile_connections: true, tiles_rle: true, ..Default::default() });
+        assert!(json.len() * 10 < plain.len());
+        assert_eq!(snapshot(&from_json(&json).unwrap()), snapshot(&level));
+
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert!(value.get("tiles").is_none() && value.get("marble_tiles").is_none());
+        let ascii = &value["tiles_rle"]["ascii"];
+        assert_eq!(ascii["palette"], json!(["#", "."]));
+        assert_eq!(ascii["rows"][0], json!(format!("0*{}", level.width)));
+        let marble = &value["tiles_rle"]["marble"];
+        assert_eq!(marble["palette"][0]["tile_type"], json!("Empty"));
+        assert!(marble["palette"][1].get("open_edges").is_some());
+
+        // Classic levels have no marble layer, and bad runs are refused
+        let classic = generate(&GeneratorParams { seed: Some(1179), ..Default::default() });
+        let mut value: Value = serde_json::from_str(&to_json_with(&classic, &ExportOptions { tiles_rle: true, ..Default::default() })).unwrap();
+        assert!(value["tiles_rle"].get("marble").is_none());
+        assert_eq!(from_json(&value.to_string()).unwrap().tiles, classic.tiles);
+        value["tiles_rle"]["ascii"]["rows"][0] = json!("7*3");
+        assert!(from_json(&value.to_string()).unwrap_err().to_string().contains("invalid tiles_rle run"));
+
+        // Runs past the level size are refused before they are expanded
+        value["tiles_rle"]["ascii"]["rows"][0] = json!("0*18446744073709551615");
+        assert!(from_json(&value.to_string()).unwrap_err().to_string().contains("longer than the level width"));
+        value["tiles_rle"]["ascii"]["rows"][0] = json!(format!("0*{},1", classic.width));
+        assert!(from_json(&value.to_string()).is_err());
+        value["tiles_rle"]["ascii"]["rows"][0] = json!(format!("0*{}", classic.width));
+        value["tiles_rle"]["ascii"]["rows"].as_array_mut().unwrap().push(json!("0"));
+        assert!(from_json(&value.to_string()).unwrap_err().to_string().contains("more than the level height"));
+    }
+}
@@ -0,0 +1,364 @@
+//! C FFI layer for engine integration (enabled with the `capi` feature).
+//!
+//! Native engine plugins (Unity, Unreal, Godot GDExtension, ...) cannot call
+//! the Rust API directly, so this module exposes a small `extern "C"` surface:
+//!
+//! 1. Fill an [`LgParams`] (start from [`lg_params_default`]).
+//! 2. Call [`lg_generate`] to obtain an opaque `LgLevel` handle.
+//! 3. Query dimensions and copy tile data into caller-owned flat buffers
+//!    (row-major, `width * height` entries).
+//! 4. Release the handle with [`lg_level_free`].
+//!
+//! No panic unwinds into the caller: an entry point that panics returns
+//! what it returns for a null handle (null, or 0).
+//!
+//! The matching C header lives in `include/level_generator.h` and is
+//! regenerated with `cbindgen --config cbindgen.toml --output include/level_generator.h`.
+
+use std::ffi::{c_char, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+use crate::dungeon::{generate, GenerationMode, GeneratorParams, Level};
+
+/// Generation mode value for classic dungeons.
+pub const LG_MODE_CLASSIC: u32 = 0;
+/// Generation mode value for marble tracks.
+pub const LG_MODE_MARBLE: u32 = 1;
+/// Generation mode value for WFC mazes.
+pub const LG_MODE_WFC: u32 = 2;
+/// Generation mode value for overworld terrain.
+pub const LG_MODE_OVERWORLD: u32 = 3;
+/// Generation mode value for settlements.
+pub const LG_MODE_SETTLEMENT: u32 = 4;
+/// Generation mode value for room archetype WFC levels.
+pub const LG_MODE_ROOM_WFC: u32 = 5;
+
+/// C-compatible mirror of `GeneratorParams`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct LgParams {
+    pub width: u32,
+    pub height: u32,
+    pub rooms: u32,
+    pub min_room: u32,
+    pub max_room: u32,
+    /// RNG seed; only used when `has_seed` is true
+    pub seed: u64,
+    pub has_seed: bool,
+    /// One of `LG_MODE_CLASSIC`, `LG_MODE_MARBLE`, `LG_MODE_WFC`, `LG_MODE_OVERWORLD`, `LG_MODE_SETTLEMENT`, `LG_MODE_ROOM_WFC`
+    pub mode: u32,
+    pub channel_width: u32,
+    pub corner_radius: u32,
+    pub enable_elevation: bool,
+    pub max_elevation: i32,
+    pub enable_obstacles: bool,
+    pub obstacle_density: f32,
+    pub max_elevation_change: i32,
+}
+
+impl From<&LgParams> for GeneratorParams {
+    fn from(p: &LgParams) -> Self {
+        GeneratorParams {
+            width: p.width,
+            height: p.height,
+            rooms: p.rooms,
+            min_room: p.min_room,
+            max_room: p.max_room,
+            seed: if p.has_seed { Some(p.seed) } else { None },
+            mode: match p.mode {
+                LG_MODE_MARBLE => GenerationMode::Marble,
+                LG_MODE_WFC => GenerationMode::Wfc,
+                LG_MODE_OVERWORLD => GenerationMode::Overworld,
+                LG_MODE_SETTLEMENT => GenerationMode::Settlement,
+                LG_MODE_ROOM_WFC => GenerationMode::RoomWfc,
+                _ => GenerationMode::Classic,
+            },
+            channel_width: p.channel_width,
+            corner_radius: p.corner_radius,
+            enable_elevation: p.enable_elevation,
+            max_elevation: p.max_elevation,
+            enable_obstacles: p.enable_obstacles,
+            obstacle_density: p.obstacle_density,
+            max_elevation_change: p.max_elevation_change,
+            ..Default::default()
+        }
+    }
+}
+
+/// Opaque handle to a generated level.
+pub struct LgLevel {
+    level: Level,
+}
+
+/// Returns parameters matching `GeneratorParams::default()`.
+#[no_mangle]
+pub extern "C" fn lg_params_default() -> LgParams {
+    // Only allocates, and allocation failure aborts rather than panics, so no guard
+    let d = GeneratorParams::default();
+    LgParams {
+        width: d.width,
+        height: d.height,
+        rooms: d.rooms,
+        min_room: d.min_room,
+        max_room: d.max_room,
+        seed: 0,
+        has_seed: false,
+        mode: LG_MODE_CLASSIC,
+        channel_width: d.channel_width,
+        corner_radius: d.corner_radius,
+        enable_elevation: d.enable_elevation,
+        max_elevation: d.max_elevation,
+        enable_obstacles: d.enable_obstacles,
+        obstacle_density: d.obstacle_density,
+        max_elevation_change: d.max_elevation_change,
+    }
+}
+
+/// Generate a level. Returns null if `params` is null or generation panics.
+///
+/// # Safety
+/// `params` must be null or point to a valid `LgParams`. The returned handle
+/// must be released with `lg_level_free`.
+#[no_mangle]
+pub unsafe extern "C" fn lg_generate(params: *const LgParams) -> *mut LgLevel {
+    let Some(params) = params.as_ref() else {
+        return ptr::null_mut();
+    };
+    guard(ptr::null_mut(), || {
+        let level = generate(&GeneratorParams::from(params));
+        Box::into_raw(Box::new(LgLevel { level }))
+    })
+}
+
+/// Release a level handle. Passing null is a no-op.
+///
+/// # Safety
+/// `level` must be null or a handle returned by `lg_generate` that has not
+/// already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn lg_level_free(level: *mut LgLevel) {
+    if !level.is_null() {
+        guard((), || drop(Box::from_raw(level)));
+    }
+}
+
+/// Level width in tiles (0 for a null handle).
+///
+/// # Safety
+/// `level` must be null or a live handle from `lg_generate`.
+#[no_mangle]
+pub unsafe extern "C" fn lg_level_width(level: *const LgLevel) -> u32 {
+    guard(0, || level.as_ref().map_or(0, |l| l.level.width))
+}
+
+/// Level height in tiles (0 for a null handle).
+///
+/// # Safety
+/// `level` must be null or a live handle from `lg_generate`.
+#[no_mangle]
+pub unsafe extern "C" fn lg_level_height(level: *const LgLevel) -> u32 {
+    guard(0, || level.as_ref().map_or(0, |l| l.level.height))
+}
+
+/// Seed actually used for generation (0 for a null handle).
+///
+/// # Safety
+/// `level` must be null or a live handle from `lg_generate`.
+#[no_mangle]
+pub unsafe extern "C" fn lg_level_seed(level: *const LgLevel) -> u64 {
+    guard(0, || level.as_ref().map_or(0, |l| l.level.seed))
+}
+
+/// Number of rooms placed (0 for a null handle).
+///
+/// # Safety
+/// `level` must be null or a live handle from `lg_generate`.
+#[no_mangle]
+pub unsafe extern "C" fn lg_level_room_count(level: *const LgLevel) -> u32 {
+    guard(0, || level.as_ref().map_or(0, |l| l.level.rooms.len() as u32))
+}
+
+/// Copy the ASCII tile grid as Unicode code points, row-major.
+///
+/// Returns the number of cells in the level (`width * height`). At most
+/// `len` values are written, so call with a null `out` to query the size.
+///
+/// # Safety
+/// `level` must be null or a live handle; `out` must be null or valid for
+/// `len` writes of `u32`.
+#[no_mangle]
+pub unsafe extern "C" fn lg_level_copy_chars(level: *const LgLevel, out: *mut u32, len: usize) -> usize {
+    let Some(l) = level.as_ref() else { return 0 };
+    guard(0, || {
+        let rows = l.level.ascii_tiles();
+        let cells = rows.iter().flat_map(|row| row.chars()).map(|c| c as u32);
+        copy_into(cells, out, len, cell_count(&l.level))
+    })
+}
+
+/// Copy marble tile types (`TileType` discriminants), row-major.
+///
+/// Non-marble levels report every cell as `Empty` (0). Returns the number of
+/// cells; see `lg_level_copy_chars` for the buffer contract.
+///
+/// # Safety
+/// `level` must be null or a live handle; `out` must be null or valid for
+/// `len` writes of `u8`.
+#[no_mangle]
+pub unsafe extern "C" fn lg_level_copy_tile_types(level: *const LgLevel, out: *mut u8, len: usize) -> usize {
+    let Some(l) = level.as_ref() else { return 0 };
+    guard(0, || {
+        let total = cell_count(&l.level);
+        match &l.level.marble_tiles {
+            Some(tiles) => copy_into(tiles.iter().flatten().map(|t| t.tile_type as u8), out, len, total),
+            None => copy_into(std::iter::repeat_n(0u8, total), out, len, total),
+        }
+    })
+}
+
+/// Copy marble tile rotations (0-3), row-major. Returns the number of cells.
+///
+/// # Safety
+/// `level` must be null or a live handle; `out` must be null or valid for
+/// `len` writes of `u8`.
+#[no_mangle]
+pub unsafe extern "C" fn lg_level_copy_rotations(level: *const LgLevel, out: *mut u8, len: usize) -> usize {
+    let Some(l) = level.as_ref() else { return 0 };
+    guard(0, || {
+        let total = cell_count(&l.level);
+        match &l.level.marble_tiles {
+            Some(tiles) => copy_into(tiles.iter().flatten().map(|t| t.rotation), out, len, total),
+            None => copy_into(std::iter::repeat_n(0u8, total), out, len, total),
+        }
+    })
+}
+
+/// Copy tile elevations (marble or overworld), row-major. Returns the number of cells.
+///
+/// # Safety
+/// `level` must be null or a live handle; `out` must be null or valid for
+/// `len` writes of `i32`.
+#[no_mangle]
+pub unsafe extern "C" fn lg_level_copy_elevations(level: *const LgLevel, out: *mut i32, len: usize) -> usize {
+    let Some(l) = level.as_ref() else { return 0 };
+    guard(0, || {
+        let total = cell_count(&l.level);
+        match (&l.level.marble_tiles, &l.level.terrain) {
+            (Some(tiles), _) => copy_into(tiles.iter().flatten().map(|t| t.elevation), out, len, total),
+            (None, Some(terrain)) => copy_into(terrain.iter().flatten().map(|t| t.elevation), out, len, total),
+            (None, None) => copy_into(std::iter::repeat_n(0i32, total), out, len, total),
+        }
+    })
+}
+
+/// Copy overworld terrain types (0 grass, 1 rock, 2 water, 3 path), row-major.
+///
+/// Non-overworld levels report every cell as 0. Returns the number of cells.
+///
+/// # Safety
+/// `level` must be null or a live handle; `out` must be null or valid for
+/// `len` writes of `u8`.
+#[no_mangle]
+pub unsafe extern "C" fn lg_level_copy_terrain(level: *const LgLevel, out: *mut u8, len: usize) -> usize {
+    let Some(l) = level.as_ref() else { return 0 };
+    guard(0, || {
+        let total = cell_count(&l.level);
+        match &l.level.terrain {
+            Some(terrain) => copy_into(terrain.iter().flatten().map(|t| t.terrain as u8), out, len, total),
+            None => copy_into(std::iter::repeat_n(0u8, total), out, len, total),
+        }
+    })
+}
+
+/// Serialize the level to a JSON string. Free it with `lg_string_free`.
+/// Returns null for a null handle or a level that fails to serialize.
+///
+/// # Safety
+/// `level` must be null or a live handle from `lg_generate`.
+#[no_mangle]
+pub unsafe extern "C" fn lg_level_to_json(level: *const LgLevel) -> *mut c_char {
+    let Some(l) = level.as_ref() else { return ptr::null_mut() };
+    guard(ptr::null_mut(), || {
+        match serde_json::to_string(&l.level).ok().and_then(|s| CString::new(s).ok()) {
+            Some(s) => s.into_raw(),
+            None => ptr::null_mut(),
+        }
+    })
+}
+
+/// Release a string returned by this library. Passing null is a no-op.
+///
+/// # Safety
+/// `s` must be null or a pointer returned by `lg_level_to_json` that has not
+/// already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn lg_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        guard((), || drop(CString::from_raw(s)));
+    }
+}
+
+/// Run `f`, returning `fallback` if it panics.
+fn guard<T>(fallback: T, f: impl FnOnce() -> T) -> T {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or(fallback)
+}
+
+fn cell_count(level: &Level) -> usize {
+    level.width as usize * level.height as usize
+}
+
+/// Write up to `len` items from `values` into `out`, returning `total`.
+unsafe fn copy_into<T>(values: impl Iterator<Item = T>, out: *mut T, len: usize, total: usize) -> usize {
+    if !out.is_null() {
+        for (i, v) in values.take(len).enumerate() {
+            out.add(i).write(v);
+        }
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate_and_copy_buffers() {
+        let mut params = lg_params_default();
+        params.mode = LG_MODE_MARBLE;
+        params.seed = 42;
+        params.has_seed = true;
+
+        unsafe {
+            let level = lg_generate(&params);
+            assert!(!level.is_null());
+            let w = lg_level_width(level) as usize;
+            let h = lg_level_height(level) as usize;
+            assert_eq!(lg_level_seed(level), 42);
+
+            let needed = lg_level_copy_tile_types(level, ptr::null_mut(), 0);
+            assert_eq!(needed, w * h);
+            let mut types = vec![0u8; needed];
+            lg_level_copy_tile_types(level, types.as_mut_ptr(), types.len());
+            assert!(types.iter().any(|&t| t != 0));
+
+            let mut chars = vec![0u32; needed];
+            lg_level_copy_chars(level, chars.as_mut_ptr(), chars.len());
+            assert!(chars.iter().all(|&c| c == '#' as u32 || c == '.' as u32));
+
+            lg_level_free(level);
+        }
+    }
+
+    #[test]
+    fn null_handles_are_safe() {
+        unsafe {
+            assert!(lg_generate(ptr::null()).is_null());
+            assert_eq!(lg_level_width(ptr::null()), 0);
+            assert!(lg_level_to_json(ptr::null()).is_null());
+            lg_level_free(ptr::null_mut());
+            lg_string_free(ptr::null_mut());
+        }
+        assert_eq!(guard(7, || panic!("caught")), 7);
+    }
+}
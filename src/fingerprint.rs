@@ -0,0 +1,178 @@
+//! Structural fingerprints and similarity between levels.
+//!
+//! [`Level::fingerprint`] condenses a level into what makes two layouts feel
+//! alike: a hash of the room graph's shape, its degree sequence, the tile
+//! histogram (marble tile types or map characters), a coarse 8x8 floor
+//! density map, and the main path length. [`Fingerprint::similarity`]
+//! compares two fingerprints on a 0 to 1 scale, so batch pipelines can drop
+//! near-duplicates across seeds without keeping whole levels around:
+//!
+//! | Part | Compared by | Weight |
+//! |------|-------------|-------:|
+//! | Room graph | 1 for equal hashes, else shared share of the degree sequences | 0.3 |
+//! | Tile histogram | overlap of the normalized histograms | 0.3 |
+//! | Layout | 1 minus the mean density difference per cell | 0.3 |
+//! | Main path | shorter over longer length | 0.1 |
+//!
+//! The graph hash is invariant to room order: it runs three rounds of
+//! Weisfeiler-Lehman relabeling from the room degrees. Fingerprints
+//! serialize, so they can be stored next to a seed list.
+//!
+//! ```
+//! use level_generator::{generate, GeneratorParams};
+//!
+//! let a = generate(&GeneratorParams { seed: Some(1), ..Default::default() });
+//! let b = generate(&GeneratorParams { seed: Some(2), ..Default::default() });
+//! assert_eq!(a.similarity(&a), 1.0);
+//! assert!(a.similarity(&b) < 1.0);
+//! ```
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dungeon::{is_walkable_char, Level};
+use crate::graph::RoomGraph;
+
+/// Side of the coarse floor density map
+pub const LAYOUT_CELLS: usize = 8;
+
+/// Weisfeiler-Lehman relabeling rounds for the graph hash
+const WL_ROUNDS: usize = 3;
+
+/// Compact structural summary of a level; see the module docs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Fingerprint {
+    /// Hash of the room graph's shape, independent of room order
+    pub graph_hash: u64,
+    /// Room degrees, largest first
+    pub degrees: Vec<u32>,
+    /// Share of each tile type or map character among all tiles
+    pub histogram: BTreeMap<String, f32>,
+    /// Walkable share of each cell of an 8x8 grid over the map, row-major
+    pub layout: Vec<f32>,
+    /// Main path length in tiles, 0 without one
+    pub main_path_length: u32,
+}
+
+fn fnv(values: impl IntoIterator<Item = u64>) -> u64 {
+    values.into_iter().fold(0xcbf2_9ce4_8422_2325u64, |h, v| v.to_le_bytes().iter().fold(h, |h, &b| (h ^ b as u64).wrapping_mul(0x0100_0000_01b3)))
+}
+
+fn graph_hash(graph: &RoomGraph) -> u64 {
+    let mut labels: Vec<u64> = graph.adjacency.iter().map(|n| n.len() as u64).collect();
+    for _ in 0..WL_ROUNDS {
+        labels = graph
+            .adjacency
+            .iter()
+            .enumerate()
+            .map(|(room, neighbors)| {
+                let mut around: Vec<u64> = neighbors.iter().map(|&n| labels[n]).collect();
+                around.sort_unstable();
+                fnv(std::iter::once(labels[room]).chain(around))
+            })
+            .collect();
+    }
+    labels.sort_unstable();
+    fnv(std::iter::once(labels.len() as u64).chain(labels))
+}
+
+impl Level {
+    /// Structural fingerprint of the level; see the module docs.
+    pub fn fingerprint(&self) -> Fingerprint {
+        let graph = RoomGraph::from_edges(self.rooms.len(), &self.connections);
+        let mut degrees: Vec<u32> = (0..self.rooms.len()).map(|room| graph.degree(room) as u32).collect();
+        degrees.sort_unstable_by(|a, b| b.cmp(a));
+
+        let total = self.stats.tile_histogram.values().sum::<usize>().max(1) as f32;
+        let histogram = self.stats.tile_histogram.iter().map(|(tile, &count)| (tile.clone(), count as f32 / total)).collect();
+
+        let (width, height) = (self.width as usize, self.height as usize);
+        let mut walkable = vec![0usize; LAYOUT_CELLS * LAYOUT_CELLS];
+        let mut area = vec![0usize; LAYOUT_CELLS * LAYOUT_CELLS];
+        for (y, row) in self.ascii_tiles().iter().enumerate() {
+            for (x, ch) in row.chars().enumerate() {
+                let cell = (y * LAYOUT_CELLS / height.max(1)).min(LAYOUT_CELLS - 1) * LAYOUT_CELLS + (x * LAYOUT_CELLS / width.max(1)).min(LAYOUT_CELLS - 1);
+                area[cell] += 1;
+                walkable[cell] += is_walkable_char(ch) as usize;
+            }
+        }
+        let layout = walkable.iter().zip(&area).map(|(&w, &a)| if a == 0 { 0.0 } else { w as f32 / a as f32 }).collect();
+
+        Fingerprint { graph_hash: graph_hash(&graph), degrees, histogram, layout, main_path_length: self.main_path_length().unwrap_or(0) }
+    }
+
+    /// Similarity of the two levels' fingerprints, from 0 to 1.
+    pub fn similarity(&self, other: &Level) -> f32 {
+        self.fingerprint().similarity(&other.fingerprint())
+    }
+}
+
+impl Fingerprint {
+    /// How alike the two levels are, from 0 (nothing in common) to 1
+    /// (indistinguishable); see the module docs for the weights.
+    pub fn similarity(&self, other: &Fingerprint) -> f32 {
+        let graph = if self.graph_hash == other.graph_hash {
+            1.0
+        } else {
+            // Both lists are sorted largest first; count the degrees they share
+            let (mut shared, mut a, mut b) = (0, self.degrees.iter().peekable(), other.degrees.iter().peekable());
+            while let (Some(&&x), Some(&&y)) = (a.peek(), b.peek()) {
+                match x.cmp(&y) {
+                    std::cmp::Ordering::Equal => {
+                        shared += 1;
+                        a.next();
+                        b.next();
+                    }
+                    std::cmp::Ordering::Greater => {
+                        a.next();
+                    }
+                    std::cmp::Ordering::Less => {
+                        b.next();
+                    }
+                }
+            }
+            shared as f32 / self.degrees.len().max(other.degrees.len()).max(1) as f32
+        };
+        let histogram: f32 = self.histogram.iter().map(|(tile, share)| share.min(other.histogram.get(tile).copied().unwrap_or(0.0))).sum();
+        let cells = self.layout.len().max(other.layout.len()).max(1) as f32;
+        let layout = 1.0 - self.layout.iter().zip(&other.layout).map(|(a, b)| (a - b).abs()).sum::<f32>() / cells;
+        let (short, long) = (self.main_path_length.min(other.main_path_length), self.main_path_length.max(other.main_path_length));
+        let path = if long == 0 { 1.0 } else { short as f32 / long as f32 };
+        (0.3 * graph + 0.3 * histogram + 0.3 * layout + 0.1 * path).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams};
+
+    #[test]
+    fn fingerprints_tell_near_duplicates_from_different_levels() {
+        let params = GeneratorParams { seed: Some(12), mode: GenerationMode::Marble, ..Default::default() };
+        let level = generate(&params);
+        let fingerprint = level.fingerprint();
+        assert_eq!(fingerprint.layout.len(), LAYOUT_CELLS * LAYOUT_CELLS);
+        assert!((fingerprint.histogram.values().sum::<f32>() - 1.0).abs() < 1e-4);
+        assert_eq!(fingerprint.similarity(&fingerprint), 1.0);
+
+        // Reordering rooms keeps the graph hash
+        let mut reordered = level.clone();
+        reordered.rooms.reverse();
+        let last = level.rooms.len() - 1;
+        reordered.connections = level.connections.iter().map(|&(a, b)| (last - a, last - b)).collect();
+        assert_eq!(reordered.fingerprint().graph_hash, fingerprint.graph_hash);
+
+        // A small edit stays close; another seed or another mode does not
+        let mut edited = level.clone();
+        let flipped = if edited.tiles[1].starts_with("#.") { "#" } else { "." };
+        edited.tiles[1].replace_range(1..2, flipped);
+        let other = generate(&GeneratorParams { seed: Some(13), ..params.clone() });
+        let maze = generate(&GeneratorParams { mode: GenerationMode::Wfc, ..params });
+        assert!(level.similarity(&edited) > 0.95);
+        assert!(level.similarity(&other) < level.similarity(&edited));
+        assert!(level.similarity(&maze) < level.similarity(&other));
+    }
+}
@@ -0,0 +1,208 @@
+//! Parameter fuzzing: [`generate`] must not panic for any params.
+//!
+//! [`arbitrary_params`] draws params from the whole range of every field,
+//! edge values often: zero and one, tiny and oversized maps, room sizes
+//! larger than the map, densities and fractions far outside 0.0 to 1.0,
+//! NaN, and elevations in the thousands. Values `generate` cannot use as
+//! given are clamped first (see [`crate::validate`]).
+//!
+//! [`fuzz`] runs the harness from code, e.g. from a long-running soak job:
+//!
+//! ```
+//! use level_generator::fuzz::fuzz;
+//!
+//! assert!(fuzz(0, 4).is_empty());
+//! ```
+//!
+//! Map sides are drawn up to [`FUZZ_MAX_DIM`] and every case gets a time
+//! budget of at most [`FUZZ_TIME_BUDGET`], so a run stays fast; larger maps
+//! only take longer.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::budget::CancelToken;
+use crate::ceilings::CeilingConfig;
+use crate::dungeon::{generate, GenerationMode, GeneratorParams, RoomSizeDistribution};
+use crate::graph::ConnectionStrategy;
+use crate::hex::GridTopology;
+use crate::mission::MissionConfig;
+use crate::naming::NameTheme;
+use crate::obstacles::{ObstaclePattern, ObstacleShape};
+use crate::openings::EdgeOpening;
+use crate::roles::EndpointPlacement;
+use crate::settlement::RoadLayout;
+use crate::tiles::{Direction, TileType};
+use crate::vaults::VaultGuard;
+use crate::zones::ZoneStrategy;
+
+/// Largest map side [`arbitrary_params`] draws.
+pub const FUZZ_MAX_DIM: u32 = 160;
+
+/// Longest time budget [`arbitrary_params`] draws; shorter ones cut
+/// generation off part way.
+pub const FUZZ_TIME_BUDGET: Duration = Duration::from_millis(500);
+
+/// Params with every field drawn from its full range, edge values often.
+pub fn arbitrary_params(rng: &mut StdRng) -> GeneratorParams {
+    fn count(rng: &mut StdRng, max: u32) -> u32 {
+        match rng.random_range(0..4) {
+            0 => 0,
+            1 => 1,
+            2 => max,
+            _ => rng.random_range(0..=max),
+        }
+    }
+    // Sizes also reach far past any map
+    fn size(rng: &mut StdRng, max: u32) -> u32 {
+        match rng.random_range(0..8) {
+            0 => u32::MAX,
+            1 => rng.random(),
+            _ => count(rng, max),
+        }
+    }
+    fn fraction(rng: &mut StdRng) -> f32 {
+        match rng.random_range(0..8) {
+            0 => 0.0,
+            1 => 1.0,
+            2 => -1.0,
+            3 => 50.0,
+            4 => f32::NAN,
+            5 => f32::INFINITY,
+            _ => rng.random_range(0.0..1.0),
+        }
+    }
+    fn pick<T: Copy>(rng: &mut StdRng, options: &[T]) -> T {
+        options[rng.random_range(0..options.len())]
+    }
+
+    let modes = [GenerationMode::Classic, GenerationMode::Marble, GenerationMode::Wfc, GenerationMode::Overworld, GenerationMode::Settlement, GenerationMode::RoomWfc];
+    let marble = rng.random_bool(0.5);
+    let elevation = if rng.random_bool(0.3) { rng.random_range(-5000..=5000) } else { rng.random_range(-3..=6) };
+    GeneratorParams {
+        width: count(rng, FUZZ_MAX_DIM),
+        height: count(rng, FUZZ_MAX_DIM),
+        rooms: count(rng, 60),
+        min_room: size(rng, 40),
+        max_room: size(rng, 60),
+        room_size_distribution: pick(rng, &[RoomSizeDistribution::Uniform, RoomSizeDistribution::Normal, RoomSizeDistribution::Bimodal]),
+        guaranteed_large_rooms: count(rng, 4),
+        room_attempts: rng.random_bool(0.3).then(|| count(rng, 500)),
+        relax_room_placement: rng.random_bool(0.5),
+        border: size(rng, 6),
+        skirt: size(rng, 6),
+        seed: Some(rng.random()),
+        mode: if marble { GenerationMode::Marble } else { pick(rng, &modes) },
+        channel_width: count(rng, 12),
+        corner_radius: count(rng, 12),
+        channel_curvature: fraction(rng),
+        enable_elevation: rng.random_bool(0.6),
+        max_elevation: elevation,
+        enable_obstacles: rng.random_bool(0.6),
+        obstacle_density: fraction(rng),
+        difficulty_ramp: fraction(rng),
+        obstacle_shapes: ObstacleShape::ALL.into_iter().filter(|_| rng.random_bool(0.5)).collect(),
+        obstacle_patterns: ObstaclePattern::ALL.into_iter().filter(|_| rng.random_bool(0.5)).collect(),
+        obstacle_clearance: rng.random_bool(0.3).then(|| count(rng, 8)),
+        obstacle_spacing: count(rng, 10),
+        obstacle_doorway_radius: rng.random_bool(0.3).then(|| count(rng, 8)),
+        trend_vector: rng.random_bool(0.3).then(|| (fraction(rng), fraction(rng), fraction(rng))),
+        trend_strength: fraction(rng),
+        start_point: rng.random_bool(0.2).then(|| (rng.random_range(-500..500), rng.random_range(-500..500), rng.random_range(-500..500))),
+        endpoints: [None, Some(EndpointPlacement::FirstRoom), Some(EndpointPlacement::ElevationExtremes)][rng.random_range(0..3)],
+        max_elevation_change: if rng.random_bool(0.3) { rng.random_range(-5000..=5000) } else { rng.random_range(-1..=4) },
+        enable_rivers: rng.random_bool(0.4),
+        river_count: count(rng, 8),
+        enable_spiral_ramps: rng.random_bool(0.4),
+        enable_funnels: rng.random_bool(0.4),
+        max_launch_pads: count(rng, 8),
+        open_bowl_rooms: rng.random_bool(0.3),
+        enable_y_junctions: rng.random_bool(0.5),
+        enable_dynamics: rng.random_bool(0.4),
+        dynamic_count: count(rng, 8),
+        enable_voxels: rng.random_bool(0.3),
+        voxel_bridges: count(rng, 6),
+        enable_banking: rng.random_bool(0.3),
+        zoning: match rng.random_range(0..3) {
+            0 => ZoneStrategy::None,
+            1 => ZoneStrategy::RoomClusters { zones: count(rng, 10) },
+            _ => ZoneStrategy::DistanceBands { band_width: count(rng, 20) },
+        },
+        topology: pick(rng, &[GridTopology::Square, GridTopology::Hex, GridTopology::Octile]),
+        connection_strategy: if rng.random_bool(0.5) {
+            ConnectionStrategy::Chain
+        } else {
+            ConnectionStrategy::MinimumSpanningTree { loop_fraction: fraction(rng) }
+        },
+        dead_end_removal: fraction(rng),
+        secret_passages: rng.random_range(0..4),
+        vaults: rng.random_range(0..3),
+        vault_guard: rng.random_bool(0.5).then_some(VaultGuard::Door),
+        side_routes: rng.random_range(0..3),
+        min_alternate_routes: rng.random_range(0..4),
+        road_layout: pick(rng, &[RoadLayout::Grid, RoadLayout::Organic]),
+        plazas: count(rng, 8),
+        min_path_length: rng.random_bool(0.2).then(|| count(rng, 400)),
+        path_retries: count(rng, 3),
+        target_floor_ratio: rng.random_bool(0.2).then(|| fraction(rng)),
+        floor_ratio_tolerance: fraction(rng),
+        max_total_tiles: rng.random_bool(0.2).then(|| count(rng, 2000)),
+        budget_retries: count(rng, 3),
+        naming: rng.random_bool(0.3).then(|| pick(rng, &[NameTheme::Dungeon, NameTheme::Cavern, NameTheme::Ruins, NameTheme::Town, NameTheme::Clockwork])),
+        ceilings: rng.random_bool(0.3).then(|| CeilingConfig { min: count(rng, 10), max: count(rng, 10), corridor: count(rng, 10) }),
+        mission: rng.random_bool(0.3).then(|| MissionConfig { locks: count(rng, 6), challenges: count(rng, 6) }),
+        algorithm_version: rng.random_bool(0.3).then(|| count(rng, 5)),
+        allowed_tile_types: rng.random_bool(0.3).then(|| TileType::ALL.into_iter().filter(|_| rng.random_bool(0.5)).collect()),
+        keep_ascii_tiles: rng.random_bool(0.5),
+        edge_openings: (0..count(rng, 3))
+            .map(|_| EdgeOpening { side: pick(rng, &Direction::ALL), offset: count(rng, FUZZ_MAX_DIM), width: count(rng, 12) })
+            .collect(),
+        time_budget: Some(if rng.random_bool(0.3) { Duration::from_millis(count(rng, 20) as u64) } else { FUZZ_TIME_BUDGET }),
+        cancel: rng.random_bool(0.2).then(|| {
+            let token = CancelToken::new();
+            if rng.random_bool(0.5) {
+                token.cancel();
+            }
+            token
+        }),
+    }
+}
+
+/// Generate levels from `cases` arbitrary params drawn from `seed`, returning
+/// the params whose generation panicked with the panic message.
+pub fn fuzz(seed: u64, cases: usize) -> Vec<(GeneratorParams, String)> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut failures = Vec::new();
+    for _ in 0..cases {
+        let params = arbitrary_params(&mut rng);
+        if let Err(panic) = catch_unwind(AssertUnwindSafe(|| generate(&params))) {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            failures.push((params, message));
+        }
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arbitrary_params_never_panic() {
+        let failures = fuzz(1170, 60);
+        assert!(failures.is_empty(), "{} panics, first: {:#?}", failures.len(), failures.first());
+
+        // No rooms on the smallest map
+        for mode in [GenerationMode::Classic, GenerationMode::Marble, GenerationMode::RoomWfc] {
+            let level = generate(&GeneratorParams { seed: Some(1), width: 0, height: 0, rooms: 0, mode, ..Default::default() });
+            assert_eq!((level.width, level.height), (10, 10));
+        }
+    }
+}
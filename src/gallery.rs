@@ -0,0 +1,159 @@
+//! Seed galleries: one HTML page of level thumbnails for reviewing seeds.
+//!
+//! [`thumbnail_svg`] draws a small flat top-down view of a level: marble
+//! tiles, terrain, or ASCII tiles in the colors of the isometric view, with
+//! dots on the entrance (green) and exit (red) rooms. [`gallery_html`] lays
+//! out one card per level with its thumbnail, seed, name, size, room and
+//! tile counts, and difficulty under a heading that shows the params the
+//! seeds were generated with. Each card can link to a full interactive
+//! view, such as a [`generate_html`](crate::isometric::generate_html) page
+//! written next to the gallery, which is what the CLI's `gallery` command
+//! does.
+//!
+//! ```
+//! use level_generator::gallery::gallery_html;
+//! use level_generator::{generate, GeneratorParams};
+//!
+//! let levels: Vec<_> = (0..4).map(|seed| generate(&GeneratorParams { seed: Some(seed), ..Default::default() })).collect();
+//! let html = gallery_html(&levels, "--rooms 8", |level| Some(format!("seed_{}.html", level.seed)));
+//! assert_eq!(html.matches("<svg").count(), 4);
+//! ```
+
+use crate::dungeon::{Level, TILE_FLOOR, TILE_GRASS, TILE_ROCK, TILE_WATER};
+use crate::hex::GridTopology;
+use crate::isometric::{terrain_color, tile_color};
+use crate::roles::RoomRole;
+use crate::tiles::TileType;
+
+/// Width in pixels of the longer side of a thumbnail
+pub const THUMBNAIL_SIZE: u32 = 240;
+
+/// Flat top-down SVG of `level`, [`THUMBNAIL_SIZE`] pixels along its longer side.
+pub fn thumbnail_svg(level: &Level) -> String {
+    let (width, height) = (level.width.max(1), level.height.max(1));
+    let hex = level.topology == GridTopology::Hex;
+    // Odd hex rows sit half a tile to the right
+    let view_width = width as f32 + if hex { 0.5 } else { 0.0 };
+    let scale = THUMBNAIL_SIZE as f32 / view_width.max(height as f32);
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\" viewBox=\"0 0 {} {}\" shape-rendering=\"crispEdges\">",
+        view_width * scale,
+        height as f32 * scale,
+        view_width,
+        height
+    );
+    svg.push_str(&format!("<rect width=\"{}\" height=\"{}\" fill=\"#1a1a1a\"/>", view_width, height));
+    for (y, row) in level.ascii_tiles().iter().enumerate() {
+        let shift = if hex && y % 2 == 1 { 0.5 } else { 0.0 };
+        let colors: Vec<Option<&str>> = row.chars().enumerate().map(|(x, ch)| color_at(level, x, y, ch)).collect();
+        // One rect per run of equal color keeps big maps small
+        let mut x = 0;
+        while x < colors.len() {
+            let run = colors[x..].iter().take_while(|&&c| c == colors[x]).count();
+            if let Some(color) = colors[x] {
+                svg.push_str(&format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"1\" fill=\"{}\"/>", x as f32 + shift, y, run, color));
+            }
+            x += run;
+        }
+    }
+    for room in &level.rooms {
+        let color = match room.role {
+            RoomRole::Entrance => "#4caf50",
+            RoomRole::Exit => "#f44336",
+            _ => continue,
+        };
+        let (cx, cy) = room.center();
+        let radius = (width.max(height) as f32 / 60.0).max(0.8);
+        svg.push_str(&format!("<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>", cx as f32 + 0.5, cy as f32 + 0.5, radius, color));
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Fill of tile `(x, y)` with ASCII char `ch`, or `None` for the background.
+fn color_at(level: &Level, x: usize, y: usize, ch: char) -> Option<&'static str> {
+    if let Some(tile) = level.marble_tiles.as_ref().and_then(|t| t.get(y)?.get(x)) {
+        return (tile.tile_type != TileType::Empty).then(|| tile_color(&tile.tile_type));
+    }
+    if let Some(tile) = level.terrain.as_ref().and_then(|t| t.get(y)?.get(x)) {
+        return Some(terrain_color(tile.terrain));
+    }
+    match ch {
+        TILE_FLOOR => Some("#c8c8c8"),
+        TILE_GRASS => Some("#5c8a3a"),
+        TILE_WATER => Some("#2f6fb0"),
+        TILE_ROCK => Some("#8a8078"),
+        _ => None,
+    }
+}
+
+/// Standalone HTML page with one thumbnail card per level. `params` is shown
+/// as the page heading; `link` gives the page a card's thumbnail opens.
+pub fn gallery_html(levels: &[Level], params: &str, link: impl Fn(&Level) -> Option<String>) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n  <meta charset=\"UTF-8\">\n  <title>Seed Gallery</title>\n");
+    html.push_str("  <style>\n");
+    html.push_str("    body { margin: 0; padding: 20px; background: #1a1a1a; color: #ccc; font-family: Arial, sans-serif; }\n");
+    html.push_str("    code { color: #fff; }\n");
+    html.push_str("    .cards { display: flex; flex-wrap: wrap; gap: 16px; }\n");
+    html.push_str("    .card { background: #262626; padding: 10px; border-radius: 6px; width: 240px; font-size: 13px; }\n");
+    html.push_str("    .card h2 { margin: 6px 0 2px; font-size: 15px; color: #fff; }\n");
+    html.push_str("    .card p { margin: 2px 0; }\n");
+    html.push_str("    a { color: inherit; text-decoration: none; }\n");
+    html.push_str("  </style>\n</head>\n<body>\n");
+    html.push_str(&format!("  <h1>Seed Gallery</h1>\n  <p>{} levels generated with <code>{}</code></p>\n", levels.len(), escape(params)));
+    html.push_str("  <div class=\"cards\">\n");
+    for level in levels {
+        let svg = thumbnail_svg(level);
+        let thumbnail = match link(level) {
+            Some(href) => format!("<a href=\"{}\" title=\"Open the interactive view\">{}</a>", escape(&href), svg),
+            None => svg,
+        };
+        let title = match &level.name {
+            Some(name) => format!("Seed {}: {}", level.seed, escape(name)),
+            None => format!("Seed {}", level.seed),
+        };
+        html.push_str(&format!(
+            "    <div class=\"card\">\n      {}\n      <h2>{}</h2>\n      <p>{}x{}, {} rooms, {} tiles</p>\n      <p>floor {:.0}%, difficulty {:.2}</p>\n    </div>\n",
+            thumbnail,
+            title,
+            level.width,
+            level.height,
+            level.rooms.len(),
+            level.stats.tile_count,
+            level.stats.floor_ratio * 100.0,
+            level.difficulty()
+        ));
+    }
+    html.push_str("  </div>\n</body>\n</html>\n");
+    html
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams};
+
+    #[test]
+    fn galleries_show_every_seed_and_link_its_view() {
+        let levels: Vec<Level> = [GenerationMode::Classic, GenerationMode::Marble, GenerationMode::Overworld]
+            .into_iter()
+            .enumerate()
+            .map(|(seed, mode)| generate(&GeneratorParams { seed: Some(seed as u64), width: 60, height: 30, mode, ..Default::default() }))
+            .collect();
+        let html = gallery_html(&levels, "--width 60 --height 30 <all modes>", |level| (level.seed > 0).then(|| format!("views/seed_{}.html", level.seed)));
+        assert_eq!(html.matches("<svg").count(), 3);
+        assert_eq!(html.matches("<a href=").count(), 2);
+        assert!(html.contains("href=\"views/seed_2.html\"") && html.contains("&lt;all modes&gt;"));
+        assert!(html.contains("Seed 0") && html.contains("60x30"));
+
+        // Thumbnails keep the map's aspect and mark the entrance and exit
+        let svg = thumbnail_svg(&levels[1]);
+        assert!(svg.starts_with("<svg") && svg.contains("width=\"240\" height=\"120\""));
+        assert!(svg.contains("#4caf50") && svg.contains("#f44336"));
+    }
+}
@@ -0,0 +1,197 @@
+//! One-way gates and directed reachability.
+//!
+//! A [`TileType::OneWayGate`] lets marbles through in one direction only:
+//! [`gate_direction`], `Direction::North` turned clockwise by the tile's
+//! rotation. A marble enters the gate from the tile behind it and leaves to
+//! the tile in front, and never crosses it sideways or backwards. The
+//! level's floor can therefore be connected while a marble still gets stuck
+//! in a pocket it can enter but never leave toward the goal.
+//!
+//! [`Level::reachability`] walks the floor as a directed graph. It lists,
+//! for every pair of rooms, whether a marble can get from one to the other,
+//! and which tiles on the main network (the tiles joined to the entrance
+//! when gates are passed either way) have no route to the exit room.
+//!
+//! Generation runs [`orient_gates`] after placing obstacles: every gate is
+//! turned to face along the shortest route to the exit room, so the exit
+//! stays reachable from every tile of the main network. The same call fixes
+//! up hand-edited tiles.
+//!
+//! ```
+//! use level_generator::{generate, GenerationMode, GeneratorParams};
+//!
+//! let level = generate(&GeneratorParams { seed: Some(4), mode: GenerationMode::Marble, ..Default::default() });
+//! let reachability = level.reachability();
+//! assert!(reachability.trapped.is_empty());
+//! assert!(reachability.matrix.iter().enumerate().all(|(i, row)| row[i]));
+//! ```
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dungeon::{Level, Room};
+use crate::roles::RoomRole;
+use crate::tiles::{Direction, MarbleTile, TileType};
+use crate::visibility::blocks_movement;
+
+/// Direction a one-way gate lets marbles through, or `None` for other tiles.
+pub fn gate_direction(tile: &MarbleTile) -> Option<Direction> {
+    (tile.tile_type == TileType::OneWayGate).then(|| Direction::North.rotate(tile.rotation))
+}
+
+/// Whether the gates on `from` and `to` allow a step between them. With
+/// `both_ways`, gates may be passed backwards but still not sideways.
+fn gates_allow(tiles: Option<&[Vec<MarbleTile>]>, from: (i32, i32), to: (i32, i32), both_ways: bool) -> bool {
+    let step = Direction::from_delta(to.0 - from.0, to.1 - from.1);
+    let gate = |(x, y): (i32, i32)| tiles?.get(y as usize)?.get(x as usize).and_then(gate_direction);
+    [from, to].into_iter().filter_map(gate).all(|d| step == Some(d) || (both_ways && step == Some(d.opposite())))
+}
+
+/// Tiles of `room`, clipped to the map.
+fn room_tiles(room: &Room) -> impl Iterator<Item = (i32, i32)> + '_ {
+    (room.y.max(0)..room.y + room.h).flat_map(move |y| (room.x.max(0)..room.x + room.w).map(move |x| (x, y)))
+}
+
+/// Breadth-first search over the tiles `open` accepts, stepping from a tile
+/// to each neighbor `next` yields while `allow(from, to)` holds. Returns the
+/// steps from the nearest source per tile.
+fn search(
+    (width, height): (usize, usize),
+    sources: impl IntoIterator<Item = (i32, i32)>,
+    open: impl Fn(i32, i32) -> bool,
+    next: impl Fn(i32, i32) -> Vec<(i32, i32)>,
+    allow: impl Fn((i32, i32), (i32, i32)) -> bool,
+) -> Vec<Vec<Option<u32>>> {
+    let mut distance = vec![vec![None; width]; height];
+    let mut queue = VecDeque::new();
+    for (x, y) in sources {
+        if open(x, y) && distance[y as usize][x as usize].is_none() {
+            distance[y as usize][x as usize] = Some(0);
+            queue.push_back((x, y));
+        }
+    }
+    while let Some((x, y)) = queue.pop_front() {
+        let d = distance[y as usize][x as usize].unwrap_or(0);
+        for (nx, ny) in next(x, y) {
+            if open(nx, ny) && distance[ny as usize][nx as usize].is_none() && allow((x, y), (nx, ny)) {
+                distance[ny as usize][nx as usize] = Some(d + 1);
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+    distance
+}
+
+/// Turn every one-way gate in `tiles` to face along the shortest route to
+/// the exit room of `rooms`; see the module docs. Gates off every route to
+/// the exit, and all gates of levels without an exit room, are left as
+/// they are. Returns the number of gates turned.
+pub fn orient_gates(tiles: &mut [Vec<MarbleTile>], rooms: &[Room]) -> usize {
+    let Some(exit) = rooms.iter().find(|r| r.role == RoomRole::Exit) else { return 0 };
+    let (width, height) = (tiles.first().map_or(0, Vec::len), tiles.len());
+    let view: &[Vec<MarbleTile>] = tiles;
+    let open = |x: i32, y: i32| x >= 0 && y >= 0 && view.get(y as usize).and_then(|row| row.get(x as usize)).is_some_and(|t| t.tile_type.is_passable());
+    let next = |x: i32, y: i32| Direction::ALL.iter().map(|d| (x + d.to_delta().0, y + d.to_delta().1)).collect();
+    // Steps to the exit with gates passable either way along their axis
+    let distance = search((width, height), room_tiles(exit), open, next, |a, b| gates_allow(Some(view), a, b, true));
+    let steps = |(x, y): (i32, i32)| if open(x, y) { distance[y as usize][x as usize] } else { None };
+
+    let mut turns = Vec::new();
+    for (y, row) in view.iter().enumerate() {
+        for (x, tile) in row.iter().enumerate() {
+            let Some(facing) = gate_direction(tile) else { continue };
+            let ahead = |d: Direction| steps((x as i32 + d.to_delta().0, y as i32 + d.to_delta().1));
+            match (ahead(facing), ahead(facing.opposite())) {
+                (Some(front), Some(back)) if back < front => turns.push((x, y, facing.opposite())),
+                (None, Some(_)) => turns.push((x, y, facing.opposite())),
+                _ => {}
+            }
+        }
+    }
+    for &(x, y, toward) in &turns {
+        tiles[y][x].rotation = toward as u8;
+    }
+    turns.len()
+}
+
+/// Result of [`Level::reachability`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Reachability {
+    /// `matrix[a][b]`: whether a marble in room `a` can get to room `b`,
+    /// indexed like `Level::rooms` (rooms without open tiles reach nothing)
+    pub matrix: Vec<Vec<bool>>,
+    /// Main-network tiles with no route to the exit room, in row order
+    /// (none without an exit room)
+    pub trapped: Vec<(i32, i32)>,
+}
+
+impl Level {
+    /// Room-to-room reachability and trapped tiles with one-way gates
+    /// respected; see the module docs.
+    pub fn reachability(&self) -> Reachability {
+        let (width, height) = (self.width as usize, self.height as usize);
+        let tiles = self.marble_tiles.as_deref();
+        let open = |x: i32, y: i32| x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height && !blocks_movement(self, x, y);
+        let next = |x: i32, y: i32| self.topology.neighbors(x, y);
+        let forward = |from: (i32, i32), to: (i32, i32)| gates_allow(tiles, from, to, false);
+
+        let matrix = self
+            .rooms
+            .iter()
+            .map(|room| {
+                let reached = search((width, height), room_tiles(room), open, next, forward);
+                self.rooms.iter().map(|other| room_tiles(other).any(|(x, y)| open(x, y) && reached[y as usize][x as usize].is_some())).collect()
+            })
+            .collect();
+
+        let mut trapped = Vec::new();
+        let entrance = self.rooms.iter().find(|r| r.role == RoomRole::Entrance).or(self.rooms.first());
+        if let (Some(entrance), Some(exit)) = (entrance, self.rooms.iter().find(|r| r.role == RoomRole::Exit)) {
+            let network = search((width, height), room_tiles(entrance), open, next, |a, b| gates_allow(tiles, a, b, true));
+            // Search back from the exit: a tile reaches it if the step toward the exit is allowed
+            let to_exit = search((width, height), room_tiles(exit), open, next, |a, b| forward(b, a));
+            for y in 0..height {
+                for x in 0..width {
+                    if network[y][x].is_some() && to_exit[y][x].is_none() {
+                        trapped.push((x as i32, y as i32));
+                    }
+                }
+            }
+        }
+        Reachability { matrix, trapped }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gates_facing_away_from_the_exit_trap_marbles_until_turned() {
+        // Two rooms joined by a one-wide corridor of gates facing east
+        let mut level = Level::from_ascii("##########\n#..####..#\n#........#\n#..####..#\n##########").unwrap();
+        let tiles = level.marble_tiles.as_ref().unwrap();
+        let gates: Vec<usize> = (0..10).filter(|&x| tiles[2][x].tile_type == TileType::OneWayGate).collect();
+        assert!(!gates.is_empty());
+        assert!(gates.iter().all(|&x| gate_direction(&tiles[2][x]) == Some(Direction::East)));
+
+        level.rooms = vec![
+            Room { x: 7, y: 1, w: 2, h: 3, role: RoomRole::Entrance, ..Default::default() },
+            Room { x: 1, y: 1, w: 2, h: 3, role: RoomRole::Exit, ..Default::default() },
+        ];
+        let stuck = level.reachability();
+        assert_eq!(stuck.matrix, vec![vec![true, false], vec![true, true]]);
+        assert!(stuck.trapped.contains(&(8, 2)) && !stuck.trapped.contains(&(1, 2)));
+
+        let rooms = level.rooms.clone();
+        let tiles = level.marble_tiles.as_mut().unwrap();
+        assert_eq!(orient_gates(tiles, &rooms), gates.len());
+        assert!(gates.iter().all(|&x| gate_direction(&tiles[2][x]) == Some(Direction::West)));
+        assert_eq!(orient_gates(tiles, &rooms), 0);
+        let fixed = level.reachability();
+        assert!(fixed.trapped.is_empty());
+        assert_eq!(fixed.matrix, vec![vec![true, true], vec![false, true]]);
+    }
+}
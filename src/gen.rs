@@ -0,0 +1,34 @@
+//! Level generation: the entry points, their parameters, and the pluggable
+//! generators behind each mode.
+//!
+//! The implementation lives in `dungeon`, `generator`, and the per-mode
+//! modules; this namespace gathers what a caller needs to produce levels.
+
+pub use crate::acceptance::{generate_matching, Constraint, NoMatchingLevel};
+pub use crate::budget::{try_generate, BudgetExceeded, CancelToken};
+pub use crate::campaign::{Campaign, CampaignLevel, Theme};
+pub use crate::ceilings::CeilingConfig;
+pub use crate::dungeon::{generate, generate_with_scratch, GenerationMode, GeneratorParams, Level, Room, RoomSizeDistribution};
+pub use crate::generator::{
+    generate_with, ClassicGenerator, LevelGenerator, MarbleGenerator, OverworldGenerator, RoomWfcGenerator, SettlementGenerator, WfcGenerator,
+};
+pub use crate::embed::EmbedError;
+pub use crate::graph::ConnectionStrategy;
+pub use crate::hybrid::HybridGenerator;
+pub use crate::hex::GridTopology;
+pub use crate::mission::{Mission, MissionConfig, MissionNode, MissionStep};
+pub use crate::naming::NameTheme;
+pub use crate::obstacles::{ObstaclePattern, ObstacleShape};
+pub use crate::ramp::DifficultyRamp;
+pub use crate::openings::EdgeOpening;
+pub use crate::roles::{EndpointPlacement, RoomRole};
+pub use crate::routes::{SideRoute, SideRouteKind};
+pub use crate::room_wfc::{collapse_archetypes, ArchetypeCell, RoomArchetype};
+pub use crate::scratch::GeneratorScratch;
+pub use crate::sectors::SectorGrid;
+pub use crate::seeds::{stage_seed, Date, Seed, SeedStage};
+pub use crate::settlement::RoadLayout;
+pub use crate::validate::{Clamp, InvalidParams};
+pub use crate::vaults::VaultGuard;
+pub use crate::versions::ALGORITHM_VERSION;
+pub use crate::zones::ZoneStrategy;
@@ -0,0 +1,374 @@
+//! Pluggable generation algorithms.
+//!
+//! Every [`GenerationMode`] is backed by a [`LevelGenerator`], and
+//! [`generate`] runs the one `params.mode` names. Implementing the trait
+//! plugs a custom algorithm into the same entry point through
+//! [`generate_with`]: it resolves the seed as usual and hands the generator
+//! the level's layout stream. The result is an ordinary [`Level`], so
+//! invariant checks, statistics, JSON export, and the visualizers all work
+//! on it. [`Level::from_layout`] does the bookkeeping for generators that
+//! only produce tiles and rooms.
+//!
+//! ```
+//! use level_generator::generator::{generate_with, LevelGenerator};
+//! use level_generator::{GeneratorParams, Level, Room};
+//! use rand::rngs::StdRng;
+//! use rand::Rng;
+//!
+//! /// One room filling the map, with a randomly placed pillar.
+//! struct Arena;
+//!
+//! impl LevelGenerator for Arena {
+//!     fn generate(&self, params: &GeneratorParams, rng: &mut StdRng) -> Level {
+//!         let (w, h) = (params.width as usize, params.height as usize);
+//!         let mut tiles: Vec<String> = (0..h)
+//!             .map(|y| (0..w).map(|x| if x == 0 || y == 0 || x == w - 1 || y == h - 1 { '#' } else { '.' }).collect())
+//!             .collect();
+//!         let pillar = rng.random_range(2..w - 2);
+//!         tiles[h / 2].replace_range(pillar..pillar + 1, "#");
+//!         let room = Room { x: 1, y: 1, w: w as i32 - 2, h: h as i32 - 2, ..Default::default() };
+//!         Level::from_layout(params, tiles, vec![room])
+//!     }
+//! }
+//!
+//! let level = generate_with(&Arena, &GeneratorParams { width: 20, height: 10, seed: Some(3), ..Default::default() });
+//! assert_eq!(level.seed, 3);
+//! assert_eq!(level.rooms.len(), 1);
+//! ```
+//!
+//! [`generate`]: crate::dungeon::generate
+
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::budget::Deadline;
+use crate::dungeon::{dimensions, number_rooms, room_level, wfc_level, GenerationMode, GeneratorParams, Level, Room};
+use crate::export::FORMAT_VERSION;
+use crate::graph::{room_connections, RoomGraph};
+use crate::hex::GridTopology;
+use crate::overworld::{generate_overworld, TerrainTile};
+use crate::roles::assign_roles_with;
+use crate::room_wfc::generate_room_wfc;
+use crate::scratch::GeneratorScratch;
+use crate::seeds::{stage_rng, stage_seed, SeedStage};
+use crate::settlement::generate_settlement;
+use crate::stats::{LevelStats, StageTimer};
+use crate::tile_budget::{adjust, budget_miss, has_budget};
+use crate::zones::compute_zones;
+
+/// A level generation algorithm.
+pub trait LevelGenerator {
+    /// Build a level for `params`. Called through [`generate_with`],
+    /// `params.seed` is always set and `rng` is that seed's layout stream.
+    fn generate(&self, params: &GeneratorParams, rng: &mut StdRng) -> Level;
+
+    /// [`generate`](LevelGenerator::generate), reusing the buffers in
+    /// `scratch`. The default ignores them.
+    fn generate_with_scratch(&self, params: &GeneratorParams, rng: &mut StdRng, scratch: &mut GeneratorScratch) -> Level {
+        let _ = scratch;
+        self.generate(params, rng)
+    }
+}
+
+/// Rooms and L-shaped corridors ([`GenerationMode::Classic`]).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClassicGenerator;
+
+/// Rooms joined by wide channels, as marble tiles ([`GenerationMode::Marble`]).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MarbleGenerator;
+
+/// Wave function collapse pipe maze ([`GenerationMode::Wfc`]).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WfcGenerator;
+
+/// Heightmap terrain with points of interest ([`GenerationMode::Overworld`]).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OverworldGenerator;
+
+/// Roads, buildings, and plazas ([`GenerationMode::Settlement`]).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SettlementGenerator;
+
+/// Room archetypes collapsed on a coarse grid ([`GenerationMode::RoomWfc`]).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RoomWfcGenerator;
+
+// The room generators draw from per-stage streams of the seed rather than
+// `rng`, which only supplies a seed when `params` has none.
+impl LevelGenerator for ClassicGenerator {
+    fn generate(&self, params: &GeneratorParams, rng: &mut StdRng) -> Level {
+        self.generate_with_scratch(params, rng, &mut GeneratorScratch::default())
+    }
+
+    fn generate_with_scratch(&self, params: &GeneratorParams, rng: &mut StdRng, scratch: &mut GeneratorScratch) -> Level {
+        room_level(params, params.seed.unwrap_or_else(|| rng.random()), false, scratch)
+    }
+}
+
+impl LevelGenerator for MarbleGenerator {
+    fn generate(&self, params: &GeneratorParams, rng: &mut StdRng) -> Level {
+        self.generate_with_scratch(params, rng, &mut GeneratorScratch::default())
+    }
+
+    fn generate_with_scratch(&self, params: &GeneratorParams, rng: &mut StdRng, scratch: &mut GeneratorScratch) -> Level {
+        room_level(params, params.seed.unwrap_or_else(|| rng.random()), true, scratch)
+    }
+}
+
+impl LevelGenerator for WfcGenerator {
+    fn generate(&self, params: &GeneratorParams, rng: &mut StdRng) -> Level {
+        self.generate_with_scratch(params, rng, &mut GeneratorScratch::default())
+    }
+
+    fn generate_with_scratch(&self, params: &GeneratorParams, rng: &mut StdRng, scratch: &mut GeneratorScratch) -> Level {
+        let seed = params.seed.unwrap_or_else(|| rng.random());
+        wfc_level(params, seed, rng, scratch)
+    }
+}
+
+impl LevelGenerator for OverworldGenerator {
+    fn generate(&self, params: &GeneratorParams, rng: &mut StdRng) -> Level {
+        let (width, height, min_room, _) = dimensions(params);
+        let mut timer = StageTimer::start();
+        let deadline = Deadline::new(params);
+        let world = generate_overworld(width as usize, height as usize, min_room, params, rng);
+        timer.mark("overworld");
+        let mut level = finish_layout(params, world.tiles, world.rooms, Some(world.terrain), timer);
+        level.stats.budget_exceeded = deadline.expired();
+        level
+    }
+}
+
+impl LevelGenerator for SettlementGenerator {
+    fn generate(&self, params: &GeneratorParams, rng: &mut StdRng) -> Level {
+        let (width, height, min_room, max_room) = dimensions(params);
+        let mut timer = StageTimer::start();
+        let deadline = Deadline::new(params);
+        let town = generate_settlement(width as usize, height as usize, min_room, max_room, params, rng);
+        timer.mark("settlement");
+        let mut level = finish_layout(params, town.tiles, town.rooms, None, timer);
+        level.plazas = town.plazas;
+        level.stats.budget_exceeded = deadline.expired();
+        level
+    }
+}
+
+impl LevelGenerator for RoomWfcGenerator {
+    fn generate(&self, params: &GeneratorParams, rng: &mut StdRng) -> Level {
+        let (width, height, min_room, max_room) = dimensions(params);
+        let mut timer = StageTimer::start();
+        let deadline = Deadline::new(params);
+        let (tiles, rooms) = generate_room_wfc(width as usize, height as usize, min_room, max_room, rng);
+        timer.mark("room_wfc");
+        let mut level = finish_layout(params, tiles, rooms, None, timer);
+        level.stats.budget_exceeded = deadline.expired();
+        level
+    }
+}
+
+impl GenerationMode {
+    /// The built-in generator for this mode.
+    pub fn generator(&self) -> &'static dyn LevelGenerator {
+        match self {
+            GenerationMode::Classic => &ClassicGenerator,
+            GenerationMode::Marble => &MarbleGenerator,
+            GenerationMode::Wfc => &WfcGenerator,
+            GenerationMode::Overworld => &OverworldGenerator,
+            GenerationMode::Settlement => &SettlementGenerator,
+            GenerationMode::RoomWfc => &RoomWfcGenerator,
+        }
+    }
+}
+
+impl Level {
+    /// Level from a finished tile layout and its rooms, as a custom generator
+    /// produces them. Connections come from the tiles, rooms get their roles,
+    /// and zones and statistics are computed as for the built-in modes.
+    pub fn from_layout(params: &GeneratorParams, tiles: Vec<String>, rooms: Vec<Room>) -> Level {
+        finish_layout(params, tiles, rooms, None, StageTimer::start())
+    }
+}
+
+/// Generate a level with `generator` instead of the built-in one for
+/// `params.mode`. A missing seed is drawn first, so the level records it.
+pub fn generate_with(generator: &dyn LevelGenerator, params: &GeneratorParams) -> Level {
+    run_generator(generator, params, &mut GeneratorScratch::default())
+}
+
+/// Resolve the seed, run `generator` on its layout stream, record the
+/// algorithm version, carve the `params.edge_openings` and
+/// `params.secret_passages`, seal the `params.vaults`, carve the
+/// `params.side_routes` and any `params.min_alternate_routes`, and name the
+/// result, set its ceilings, plan its mission, restrict its tile types,
+/// bank its turns, and drop its ASCII
+/// tiles if `params.naming`, `params.ceilings`, `params.mission`,
+/// `params.allowed_tile_types`, `params.enable_banking`, and
+/// `params.keep_ascii_tiles` ask for it.
+pub(crate) fn run_generator(generator: &dyn LevelGenerator, params: &GeneratorParams, scratch: &mut GeneratorScratch) -> Level {
+    let mut level = fit_budget(generator, params, scratch);
+    level.algorithm_version = params.effective_algorithm_version();
+    level.carve_edge_openings(&params.edge_openings);
+    if matches!(params.mode, GenerationMode::Classic) && params.secret_passages > 0 {
+        level.add_secret_passages(params.secret_passages);
+    }
+    if params.vaults > 0 {
+        level.add_vaults(params.vaults, params.vault_guard);
+    }
+    if matches!(params.mode, GenerationMode::Marble) && params.side_routes > 0 {
+        level.add_side_routes(params.side_routes);
+    }
+    if matches!(params.mode, GenerationMode::Marble) && params.min_alternate_routes > 0 {
+        level.ensure_alternate_routes(params.min_alternate_routes);
+    }
+    if let Some(theme) = params.naming {
+        level.assign_names(theme);
+    }
+    if let Some(config) = &params.ceilings {
+        level.assign_ceilings(config);
+    }
+    if let Some(config) = &params.mission {
+        level.assign_mission(config);
+    }
+    if let Some(allowed) = &params.allowed_tile_types {
+        level.restrict_tile_types(allowed);
+    }
+    if params.enable_banking {
+        level.add_banking();
+    }
+    if !params.keep_ascii_tiles && level.marble_tiles.is_some() {
+        level.tiles = Vec::new();
+    }
+    level
+}
+
+/// Generate from the clamped params (see [`crate::validate`]), regenerating
+/// while the level misses its tile budget (see [`crate::tile_budget`]).
+fn fit_budget(generator: &dyn LevelGenerator, params: &GeneratorParams, scratch: &mut GeneratorScratch) -> Level {
+    let deadline = Deadline::new(params);
+    let seed = params.seed.unwrap_or_else(|| rand::rng().random());
+    let mut params = GeneratorParams { seed: Some(seed), ..params.clamped() };
+    if !has_budget(&params) {
+        return generate_attempts(generator, &params, scratch, &deadline);
+    }
+
+    let mut tried = Vec::new();
+    let mut best: Option<(f32, Level)> = None;
+    for _ in 0..=params.budget_retries {
+        let level = generate_attempts(generator, &params, scratch, &deadline);
+        let miss = budget_miss(&level, &params);
+        tried.push((params.rooms, params.channel_width));
+        let next = if miss > 0.0 && !deadline.expired() { adjust(&level, &params, &tried) } else { None };
+        if best.as_ref().is_none_or(|(best_miss, _)| miss < *best_miss) {
+            best = Some((miss, level));
+        }
+        match next {
+            Some(next) => params = next,
+            None => break,
+        }
+    }
+    let (miss, mut level) = best.expect("at least one attempt runs");
+    level.stats.tile_budget_met = Some(miss == 0.0);
+    level
+}
+
+/// Run `generator` from the seed in `params`, regenerating from retry seeds
+/// while the main path is shorter than `min_path_length`.
+fn generate_attempts(generator: &dyn LevelGenerator, params: &GeneratorParams, scratch: &mut GeneratorScratch, deadline: &Deadline) -> Level {
+    let mut seed = params.seed.expect("run_generator resolves the seed");
+    let mut best: Option<Level> = None;
+    for attempt in 1..=params.path_retries + 1 {
+        let params = GeneratorParams { seed: Some(seed), ..params.clone() };
+        let mut level = generator.generate_with_scratch(&params, &mut stage_rng(seed, SeedStage::Layout), scratch);
+        level.stats.attempts = attempt;
+        let Some(min) = params.min_path_length else { return level };
+        if level.stats.main_path_length.is_some_and(|length| length >= min) || deadline.expired() {
+            return level;
+        }
+        if best.as_ref().is_none_or(|b| level.stats.main_path_length > b.stats.main_path_length) {
+            best = Some(level);
+        }
+        seed = stage_seed(seed, SeedStage::Retry);
+    }
+    let mut level = best.expect("at least one attempt runs");
+    level.stats.attempts = params.path_retries + 1;
+    level
+}
+
+/// Shared tail of the whole-map generators: connections, roles, zones, stats.
+fn finish_layout(
+    params: &GeneratorParams,
+    tiles: Vec<String>,
+    mut rooms: Vec<Room>,
+    terrain: Option<Vec<Vec<TerrainTile>>>,
+    mut timer: StageTimer,
+) -> Level {
+    let (width, height) = (tiles.first().map_or(0, |row| row.chars().count()) as u32, tiles.len() as u32);
+    let topology = match params.mode {
+        GenerationMode::Classic => params.topology,
+        _ => GridTopology::Square,
+    };
+    let connections = room_connections(&tiles, &rooms, topology);
+    let graph = RoomGraph::from_edges(rooms.len(), &connections);
+    assign_roles_with(&mut rooms, &graph, params.start_point.map(|(sx, _sy, sz)| (sx, sz)), params.effective_endpoints());
+    timer.mark("roles");
+    let zoning = compute_zones(&tiles, &rooms, &graph, params.zoning, topology);
+    let (zone_map, zones) = zoning.map_or((None, Vec::new()), |z| (Some(z.zone_map), z.zones));
+    timer.mark("zones");
+    let mut level = Level {
+        format_version: FORMAT_VERSION, width, height, seed: params.seed.unwrap_or_default(), algorithm_version: params.effective_algorithm_version(), name: None, rooms, connections, corridor_ceilings: Vec::new(), mission: None, tiles, marble_tiles: None,
+        terrain, river_map: None, obstacles: Vec::new(), dynamics: Vec::new(), structures: Vec::new(), side_routes: Vec::new(), voxels: None, zone_map, zones, plazas: Vec::new(), topology,
+        stats: LevelStats::default(),
+    };
+    number_rooms(&mut level.rooms);
+    level.stats = LevelStats::compute(&level, params.rooms, timer.finish());
+    level
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::generate;
+
+    #[test]
+    fn generate_dispatches_to_the_mode_generator() {
+        for mode in [
+            GenerationMode::Classic,
+            GenerationMode::Marble,
+            GenerationMode::Wfc,
+            GenerationMode::Overworld,
+            GenerationMode::Settlement,
+            GenerationMode::RoomWfc,
+        ] {
+            let params = GeneratorParams { seed: Some(6), mode, ..Default::default() };
+            let (builtin, plugged) = (generate(&params), generate_with(mode.generator(), &params));
+            assert_eq!(builtin.tiles, plugged.tiles, "{:?}", mode);
+            assert_eq!(builtin.seed, 6);
+        }
+
+        // Called directly without a seed, a generator takes one from the stream it is given
+        let params = GeneratorParams { mode: GenerationMode::Marble, ..Default::default() };
+        let level = MarbleGenerator.generate(&params, &mut stage_rng(9, SeedStage::Layout));
+        let again = MarbleGenerator.generate(&params, &mut stage_rng(9, SeedStage::Layout));
+        assert_eq!((level.seed, &level.marble_tiles), (again.seed, &again.marble_tiles));
+    }
+
+    #[test]
+    fn short_levels_are_regenerated_until_the_path_is_long_enough() {
+        let params = GeneratorParams { seed: Some(1), ..Default::default() };
+        let first = generate(&params);
+        let min = first.stats.main_path_length.unwrap() + 1;
+        let level = generate(&GeneratorParams { min_path_length: Some(min), ..params.clone() });
+        assert!(level.stats.attempts > 1);
+        assert!(level.stats.main_path_length.unwrap() >= min);
+
+        // The level records the seed that produced it
+        let again = generate(&GeneratorParams { seed: Some(level.seed), min_path_length: Some(min), ..params.clone() });
+        assert_eq!((again.tiles, again.stats.attempts), (level.tiles, 1));
+
+        // Out of retries, the longest attempt is returned
+        let hopeless = generate(&GeneratorParams { min_path_length: Some(10_000), path_retries: 3, ..params });
+        assert_eq!(hopeless.stats.attempts, 4);
+        assert!(hopeless.stats.main_path_length.unwrap() >= first.stats.main_path_length.unwrap());
+    }
+}
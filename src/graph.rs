@@ -0,0 +1,251 @@
+//! Room connection graph.
+//!
+//! The carved grid is the source of truth for which rooms are connected:
+//! corridors frequently cross each other or clip through a third room, so the
+//! order in which rooms were joined during generation is not the real graph.
+//! Instead, floor tiles outside every room are grouped into 4-connected
+//! corridor components, and every pair of rooms touching the same component
+//! is considered connected. Adjacency follows the level's [`GridTopology`].
+//!
+//! [`connection_plan`] picks which room pairs the generator carves corridors
+//! between in the first place, according to a [`ConnectionStrategy`].
+
+use std::collections::{BTreeSet, VecDeque};
+
+use crate::dungeon::{Room, TILE_FLOOR};
+use crate::hex::GridTopology;
+
+/// Undirected graph whose nodes are room indices.
+#[derive(Debug, Clone, Default)]
+pub struct RoomGraph {
+    /// Neighbor lists, indexed by room
+    pub adjacency: Vec<Vec<usize>>,
+}
+
+impl RoomGraph {
+    /// Build a graph from a list of undirected edges over `room_count` rooms.
+    pub fn from_edges(room_count: usize, edges: &[(usize, usize)]) -> Self {
+        let mut adjacency = vec![Vec::new(); room_count];
+        for &(a, b) in edges {
+            if a < room_count && b < room_count && a != b {
+                adjacency[a].push(b);
+                adjacency[b].push(a);
+            }
+        }
+        for list in &mut adjacency {
+            list.sort_unstable();
+            list.dedup();
+        }
+        Self { adjacency }
+    }
+
+    /// Number of neighbors of `room`.
+    pub fn degree(&self, room: usize) -> usize {
+        self.adjacency.get(room).map_or(0, |n| n.len())
+    }
+
+    /// Hop distance from `start` to every room (`None` if unreachable).
+    pub fn distances_from(&self, start: usize) -> Vec<Option<usize>> {
+        let mut dist = vec![None; self.adjacency.len()];
+        if start >= dist.len() {
+            return dist;
+        }
+        let mut queue = VecDeque::new();
+        dist[start] = Some(0);
+        queue.push_back(start);
+        while let Some(room) = queue.pop_front() {
+            let d = dist[room].unwrap_or(0);
+            for &n in &self.adjacency[room] {
+                if dist[n].is_none() {
+                    dist[n] = Some(d + 1);
+                    queue.push_back(n);
+                }
+            }
+        }
+        dist
+    }
+}
+
+/// Which room pairs get a corridor during generation.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ConnectionStrategy {
+    /// Join rooms one after another in order of their center x coordinate
+    #[default]
+    Chain,
+    /// Minimum spanning tree over room-center distances, plus the shortest
+    /// `loop_fraction` (0.0 to 1.0) of the remaining room pairs as loops
+    MinimumSpanningTree { loop_fraction: f32 },
+}
+
+/// Room index pairs to carve corridors between, in carving order.
+///
+/// `Chain` expects `rooms` sorted by center x, as the generator does.
+pub fn connection_plan(rooms: &[Room], strategy: ConnectionStrategy) -> Vec<(usize, usize)> {
+    let n = rooms.len();
+    let loop_fraction = match strategy {
+        ConnectionStrategy::Chain => return (1..n).map(|i| (i - 1, i)).collect(),
+        ConnectionStrategy::MinimumSpanningTree { loop_fraction } => loop_fraction.clamp(0.0, 1.0),
+    };
+    let dist = |a: usize, b: usize| {
+        let ((ax, ay), (bx, by)) = (rooms[a].center(), rooms[b].center());
+        let (dx, dy) = ((ax - bx) as i64, (ay - by) as i64);
+        dx * dx + dy * dy
+    };
+
+    // Prim's algorithm on the complete graph
+    let mut in_tree = vec![false; n];
+    let mut best: Vec<(i64, usize)> = vec![(i64::MAX, 0); n];
+    let mut plan = Vec::with_capacity(n.saturating_sub(1));
+    if n > 0 {
+        best[0] = (0, 0);
+    }
+    for _ in 0..n {
+        let Some(next) = (0..n).filter(|&i| !in_tree[i]).min_by_key(|&i| best[i].0) else { break };
+        in_tree[next] = true;
+        if next != 0 {
+            plan.push((best[next].1.min(next), best[next].1.max(next)));
+        }
+        for i in 0..n {
+            if !in_tree[i] && dist(next, i) < best[i].0 {
+                best[i] = (dist(next, i), next);
+            }
+        }
+    }
+
+    // Re-add the shortest of the remaining pairs as loops
+    let mut rest: Vec<(usize, usize)> =
+        (0..n).flat_map(|a| (a + 1..n).map(move |b| (a, b))).filter(|e| !plan.contains(e)).collect();
+    rest.sort_by_key(|&(a, b)| (dist(a, b), a, b));
+    let loops = (rest.len() as f32 * loop_fraction).round() as usize;
+    plan.extend(rest.into_iter().take(loops));
+    plan
+}
+
+/// Derive room-to-room connections from the carved ASCII tiles.
+///
+/// Returns sorted, de-duplicated `(a, b)` pairs with `a < b`.
+pub fn room_connections(tiles: &[String], rooms: &[Room], topology: GridTopology) -> Vec<(usize, usize)> {
+    let grid: Vec<Vec<char>> = tiles.iter().map(|r| r.chars().collect()).collect();
+    let height = grid.len();
+    let width = if height > 0 { grid[0].len() } else { 0 };
+
+    // Which room (if any) owns each tile
+    let mut owner: Vec<Vec<Option<usize>>> = vec![vec![None; width]; height];
+    for (i, room) in rooms.iter().enumerate() {
+        for y in room.y.max(0)..(room.y + room.h).min(height as i32) {
+            for x in room.x.max(0)..(room.x + room.w).min(width as i32) {
+                owner[y as usize][x as usize].get_or_insert(i);
+            }
+        }
+    }
+
+    let mut visited = vec![vec![false; width]; height];
+    let mut edges: BTreeSet<(usize, usize)> = BTreeSet::new();
+
+    for sy in 0..height {
+        for sx in 0..width {
+            if visited[sy][sx] || grid[sy][sx] != TILE_FLOOR || owner[sy][sx].is_some() {
+                continue;
+            }
+
+            // Flood one corridor component, collecting the rooms it touches
+            let mut touched: BTreeSet<usize> = BTreeSet::new();
+            let mut queue = VecDeque::new();
+            visited[sy][sx] = true;
+            queue.push_back((sx, sy));
+            while let Some((x, y)) = queue.pop_front() {
+                for (nx, ny) in topology.neighbors(x as i32, y as i32) {
+                    if nx < 0 || ny < 0 || nx as usize >= width || ny as usize >= height {
+                        continue;
+                    }
+                    let (ux, uy) = (nx as usize, ny as usize);
+                    if grid[uy][ux] != TILE_FLOOR {
+                        continue;
+                    }
+                    if let Some(room) = owner[uy][ux] {
+                        touched.insert(room);
+                    } else if !visited[uy][ux] {
+                        visited[uy][ux] = true;
+                        queue.push_back((ux, uy));
+                    }
+                }
+            }
+
+            let touched: Vec<usize> = touched.into_iter().collect();
+            for i in 0..touched.len() {
+                for j in i + 1..touched.len() {
+                    edges.insert((touched[i], touched[j]));
+                }
+            }
+        }
+    }
+
+    // Rooms whose floors touch directly (no corridor in between)
+    for y in 0..height {
+        for x in 0..width {
+            let Some(a) = owner[y][x] else { continue };
+            for (nx, ny) in topology.neighbors(x as i32, y as i32) {
+                if nx < 0 || ny < 0 {
+                    continue;
+                }
+                let (nx, ny) = (nx as usize, ny as usize);
+                if nx < width && ny < height && grid[y][x] == TILE_FLOOR && grid[ny][nx] == TILE_FLOOR {
+                    if let Some(b) = owner[ny][nx] {
+                        if a != b {
+                            edges.insert((a.min(b), a.max(b)));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    edges.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn room(x: i32, y: i32, w: i32, h: i32) -> Room {
+        Room { x, y, w, h, ..Default::default() }
+    }
+
+    #[test]
+    fn corridor_links_two_rooms() {
+        let tiles: Vec<String> = [
+            "##########",
+            "#..####..#",
+            "#........#",
+            "#..####..#",
+            "##########",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let rooms = vec![room(1, 1, 2, 3), room(7, 1, 2, 3)];
+        assert_eq!(room_connections(&tiles, &rooms, GridTopology::Square), vec![(0, 1)]);
+
+        let graph = RoomGraph::from_edges(2, &[(0, 1)]);
+        assert_eq!(graph.distances_from(0), vec![Some(0), Some(1)]);
+    }
+
+    #[test]
+    fn spanning_tree_plan_joins_nearest_rooms() {
+        // Four rooms in a row, far apart, and one just below the first
+        let rooms = vec![room(0, 0, 3, 3), room(20, 0, 3, 3), room(40, 0, 3, 3), room(60, 0, 3, 3), room(0, 10, 3, 3)];
+        let tree = connection_plan(&rooms, ConnectionStrategy::MinimumSpanningTree { loop_fraction: 0.0 });
+        assert_eq!(tree.len(), 4);
+        for edge in [(0, 1), (1, 2), (2, 3), (0, 4)] {
+            assert!(tree.contains(&edge), "{:?}", tree);
+        }
+        let graph = RoomGraph::from_edges(rooms.len(), &tree);
+        assert!(graph.distances_from(0).iter().all(|d| d.is_some()));
+
+        // Six pairs remain; half of them come back as loops, shortest first
+        let looped = connection_plan(&rooms, ConnectionStrategy::MinimumSpanningTree { loop_fraction: 0.5 });
+        assert_eq!(looped.len(), 7);
+        assert_eq!(looped[4], (1, 4));
+        assert_eq!(connection_plan(&rooms, ConnectionStrategy::Chain), vec![(0, 1), (1, 2), (2, 3), (3, 4)]);
+    }
+}
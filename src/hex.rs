@@ -0,0 +1,169 @@
+//! Hex grid topology.
+//!
+//! Hex levels reuse the rectangular tile grid with "odd-r" offset
+//! coordinates: pointy-top hexes, with every odd row shifted half a tile to
+//! the right. Row-major `tiles`, rooms, and all per-tile layers keep their
+//! usual shape; only adjacency (six neighbors instead of four) and rendering
+//! change. Distances and lines are computed in cube coordinates.
+
+use serde::{Deserialize, Serialize};
+
+use crate::tiles::HexDirection;
+
+/// Tile adjacency used by generation and rendering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum GridTopology {
+    /// Square tiles with four neighbors
+    #[default]
+    Square,
+    /// Pointy-top hexes in odd-r offset layout, six neighbors
+    Hex,
+    /// Square tiles with eight neighbors (diagonals included)
+    Octile,
+}
+
+impl GridTopology {
+    /// Returns true for the default square topology.
+    pub fn is_square(&self) -> bool {
+        *self == GridTopology::Square
+    }
+
+    /// Neighbor coordinates of `(x, y)`. May lie outside the map.
+    pub fn neighbors(&self, x: i32, y: i32) -> Vec<(i32, i32)> {
+        match self {
+            GridTopology::Square => vec![(x, y - 1), (x + 1, y), (x, y + 1), (x - 1, y)],
+            GridTopology::Hex => HexDirection::ALL.iter().map(|d| hex_neighbor(x, y, *d)).collect(),
+            GridTopology::Octile => vec![
+                (x, y - 1),
+                (x + 1, y - 1),
+                (x + 1, y),
+                (x + 1, y + 1),
+                (x, y + 1),
+                (x - 1, y + 1),
+                (x - 1, y),
+                (x - 1, y - 1),
+            ],
+        }
+    }
+}
+
+/// Neighbor of offset cell `(x, y)` in direction `dir`.
+pub fn hex_neighbor(x: i32, y: i32, dir: HexDirection) -> (i32, i32) {
+    let (dx, dy) = dir.offset(y);
+    (x + dx, y + dy)
+}
+
+/// Convert odd-r offset coordinates to cube coordinates `(q, r, s)`.
+pub fn offset_to_cube(x: i32, y: i32) -> (i32, i32, i32) {
+    let q = x - (y - (y & 1)) / 2;
+    let r = y;
+    (q, r, -q - r)
+}
+
+/// Convert cube coordinates back to odd-r offset coordinates.
+pub fn cube_to_offset(q: i32, r: i32) -> (i32, i32) {
+    (q + (r - (r & 1)) / 2, r)
+}
+
+/// Number of hex steps between two offset cells.
+pub fn hex_distance(a: (i32, i32), b: (i32, i32)) -> i32 {
+    let (aq, ar, as_) = offset_to_cube(a.0, a.1);
+    let (bq, br, bs) = offset_to_cube(b.0, b.1);
+    ((aq - bq).abs() + (ar - br).abs() + (as_ - bs).abs()) / 2
+}
+
+/// Cells on the straight hex line from `a` to `b`, inclusive.
+///
+/// Consecutive cells are always hex neighbors.
+pub fn hex_line(a: (i32, i32), b: (i32, i32)) -> Vec<(i32, i32)> {
+    let n = hex_distance(a, b);
+    let (aq, ar, as_) = offset_to_cube(a.0, a.1);
+    let (bq, br, bs) = offset_to_cube(b.0, b.1);
+    // Nudge off exact midpoints so ties round consistently
+    let (aq, ar, as_) = (aq as f32 + 1e-6, ar as f32 + 2e-6, as_ as f32 - 3e-6);
+    (0..=n)
+        .map(|i| {
+            let t = if n == 0 { 0.0 } else { i as f32 / n as f32 };
+            let q = aq + (bq as f32 - aq) * t;
+            let r = ar + (br as f32 - ar) * t;
+            let s = as_ + (bs as f32 - as_) * t;
+            let (rq, rr) = cube_round(q, r, s);
+            cube_to_offset(rq, rr)
+        })
+        .collect()
+}
+
+fn cube_round(q: f32, r: f32, s: f32) -> (i32, i32) {
+    let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+    let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+    if dq > dr && dq > ds {
+        rq = -rr - rs;
+    } else if dr > ds {
+        rr = -rq - rs;
+    }
+    (rq as i32, rr as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams};
+    use crate::graph::RoomGraph;
+
+    #[test]
+    fn hex_line_steps_between_neighbors() {
+        for (a, b) in [((0, 0), (7, 5)), ((6, 1), (1, 8)), ((3, 3), (3, 3)), ((2, 9), (12, 0))] {
+            let line = hex_line(a, b);
+            assert_eq!(line.first(), Some(&a));
+            assert_eq!(line.last(), Some(&b));
+            assert_eq!(line.len() as i32, hex_distance(a, b) + 1);
+            for pair in line.windows(2) {
+                let (x, y) = pair[0];
+                assert!(GridTopology::Hex.neighbors(x, y).contains(&pair[1]), "{:?}", pair);
+            }
+        }
+    }
+
+    #[test]
+    fn hex_classic_level_is_connected() {
+        let params = GeneratorParams { seed: Some(9), topology: GridTopology::Hex, ..Default::default() };
+        let level = generate(&params);
+        assert_eq!(level.topology, GridTopology::Hex);
+        let graph = RoomGraph::from_edges(level.rooms.len(), &level.connections);
+        assert!(graph.distances_from(0).iter().all(|d| d.is_some()));
+    }
+
+    #[test]
+    #[allow(clippy::needless_range_loop)]
+    fn octile_levels_carve_diagonals_and_stay_connected() {
+        for mode in [GenerationMode::Classic, GenerationMode::Marble] {
+            let params = GeneratorParams {
+                seed: Some(9),
+                mode,
+                topology: GridTopology::Octile,
+                channel_width: 1,
+                ..Default::default()
+            };
+            let level = generate(&params);
+            assert_eq!(level.topology, GridTopology::Octile);
+            assert_eq!(level.check_invariants(), vec![], "{:?}", mode);
+            let graph = RoomGraph::from_edges(level.rooms.len(), &level.connections);
+            assert!(graph.distances_from(0).iter().all(|d| d.is_some()), "{:?}", mode);
+
+            if matches!(mode, GenerationMode::Marble) {
+                // Marble tiles only join along edges: no floor touches another only diagonally
+                let grid: Vec<Vec<char>> = level.tiles.iter().map(|r| r.chars().collect()).collect();
+                let floor = |x: usize, y: usize| grid[y][x] == '.';
+                for y in 0..grid.len() - 1 {
+                    for x in 0..grid[y].len() - 1 {
+                        let (a, b) = (floor(x, y), floor(x + 1, y + 1));
+                        let (c, d) = (floor(x + 1, y), floor(x, y + 1));
+                        let diagonal_only = (a && b || c && d) && !(a && c) && !(b && d);
+                        assert!(!diagonal_only, "({}, {})", x, y);
+                    }
+                }
+            }
+        }
+    }
+}
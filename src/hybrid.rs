@@ -0,0 +1,181 @@
+//! Levels composed from several generation modes.
+//!
+//! A [`HybridGenerator`] splits the map into regions, each generated by its
+//! own [`GenerationMode`]: classic rooms in one half and overworld terrain
+//! in the other, say. Every region is generated as a level of its own size
+//! and pasted in, and consecutive regions are then joined by an L-shaped
+//! corridor between their closest floor tiles, so listing the regions in
+//! order chains them all together.
+//!
+//! Regions are clipped to the map, later regions overwrite earlier ones
+//! where they overlap, and tiles no region covers stay wall. Each region's
+//! share of `params.rooms` follows its share of the map area. WFC regions
+//! become floor wherever the maze has a pipe. The result is a plain tile
+//! layout on a square grid, like [`Level::from_layout`]: rooms, plazas,
+//! roles, zones, and statistics carry over, marble tiles and terrain do not.
+//!
+//! ```
+//! use level_generator::generator::generate_with;
+//! use level_generator::hybrid::HybridGenerator;
+//! use level_generator::{GenerationMode, GeneratorParams, Rect};
+//!
+//! let hybrid = HybridGenerator::new(vec![
+//!     (Rect::new(0, 0, 40, 30), GenerationMode::Classic),
+//!     (Rect::new(40, 0, 40, 30), GenerationMode::Overworld),
+//! ]);
+//! let level = generate_with(&hybrid, &GeneratorParams { width: 80, height: 30, seed: Some(2), ..Default::default() });
+//! assert_eq!(level.tiles.len(), 30);
+//! ```
+
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::dungeon::{is_walkable_char, GenerationMode, GeneratorParams, Level, Room, TILE_FLOOR, TILE_GRASS, TILE_ROCK, TILE_WALL, TILE_WATER};
+use crate::editing::Rect;
+use crate::generator::LevelGenerator;
+use crate::hex::GridTopology;
+use crate::seeds::{stage_rng, SeedStage};
+use crate::zones::ZoneStrategy;
+
+/// Generator running a different mode in each region; see the module docs.
+#[derive(Debug, Clone, Default)]
+pub struct HybridGenerator {
+    /// Regions and their modes, joined in this order
+    pub regions: Vec<(Rect, GenerationMode)>,
+}
+
+impl HybridGenerator {
+    pub fn new(regions: Vec<(Rect, GenerationMode)>) -> Self {
+        Self { regions }
+    }
+}
+
+impl LevelGenerator for HybridGenerator {
+    fn generate(&self, params: &GeneratorParams, rng: &mut StdRng) -> Level {
+        let (width, height) = (params.width as i32, params.height as i32);
+        let map = Rect::new(0, 0, width, height);
+        let mut grid = vec![vec![TILE_WALL; width as usize]; height as usize];
+        let (mut rooms, mut plazas) = (Vec::new(), Vec::new());
+        let mut placed: Vec<Rect> = Vec::new();
+
+        for &(region, mode) in &self.regions {
+            // Drawn for every region, so each region's seed depends only on its position in the list
+            let seed: u64 = rng.random();
+            let Some(region) = intersect(region, map) else { continue };
+            let area_share = (region.w * region.h) as f64 / (width * height).max(1) as f64;
+            let sub_params = GeneratorParams {
+                width: region.w as u32,
+                height: region.h as u32,
+                rooms: ((params.rooms as f64 * area_share).round() as u32).max(1),
+                seed: Some(seed),
+                mode,
+                start_point: None,
+                zoning: ZoneStrategy::None,
+                topology: GridTopology::Square,
+                ..params.clone()
+            };
+            let sub = mode.generator().generate(&sub_params, &mut stage_rng(seed, SeedStage::Layout));
+
+            for (y, row) in sub.ascii_tiles().iter().take(region.h as usize).enumerate() {
+                for (x, ch) in row.chars().take(region.w as usize).enumerate() {
+                    grid[region.y as usize + y][region.x as usize + x] = map_char(ch);
+                }
+            }
+            let fits = |room: &Room| room.x + room.w <= region.w && room.y + room.h <= region.h;
+            let shift = |room: &Room| Room { x: room.x + region.x, y: room.y + region.y, ..room.clone() };
+            // Earlier rooms the region paved over are gone
+            rooms.retain(|room: &Room| !overlaps(room, region));
+            plazas.retain(|room: &Room| !overlaps(room, region));
+            rooms.extend(sub.rooms.iter().filter(|r| fits(r)).map(shift));
+            plazas.extend(sub.plazas.iter().filter(|r| fits(r)).map(shift));
+            placed.push(region);
+        }
+
+        for pair in placed.windows(2) {
+            if let (Some(from), Some(to)) = (closest_floor(&grid, pair[0], pair[1]), closest_floor(&grid, pair[1], pair[0])) {
+                carve_corridor(&mut grid, from, to);
+            }
+        }
+
+        let params = GeneratorParams { topology: GridTopology::Square, ..params.clone() };
+        let tiles = grid.iter().map(|row| row.iter().collect()).collect();
+        let mut level = Level::from_layout(&params, tiles, rooms);
+        level.plazas = plazas;
+        level
+    }
+}
+
+/// `ch` as a map character: WFC pipes become floor and blanks wall.
+pub(crate) fn map_char(ch: char) -> char {
+    match ch {
+        TILE_WALL | TILE_FLOOR | TILE_GRASS | TILE_WATER | TILE_ROCK => ch,
+        ' ' => TILE_WALL,
+        _ => TILE_FLOOR,
+    }
+}
+
+/// Overlap of two rectangles, if it is not empty.
+fn intersect(a: Rect, b: Rect) -> Option<Rect> {
+    let (x0, y0) = (a.x.max(b.x), a.y.max(b.y));
+    let (x1, y1) = ((a.x + a.w).min(b.x + b.w), (a.y + a.h).min(b.y + b.h));
+    (x1 > x0 && y1 > y0).then(|| Rect::new(x0, y0, x1 - x0, y1 - y0))
+}
+
+fn overlaps(room: &Room, region: Rect) -> bool {
+    intersect(Rect::new(room.x, room.y, room.w, room.h), region).is_some()
+}
+
+/// Floor tile of `region` closest to the center of `target`.
+fn closest_floor(grid: &[Vec<char>], region: Rect, target: Rect) -> Option<(i32, i32)> {
+    let (cx, cy) = (target.x + target.w / 2, target.y + target.h / 2);
+    (region.y..region.y + region.h)
+        .flat_map(|y| (region.x..region.x + region.w).map(move |x| (x, y)))
+        .filter(|&(x, y)| grid[y as usize][x as usize] == TILE_FLOOR)
+        .min_by_key(|&(x, y)| (x - cx).abs() + (y - cy).abs())
+}
+
+/// Carve an L-shaped corridor, horizontal leg first, through everything
+/// that is not already walkable.
+pub(crate) fn carve_corridor(grid: &mut [Vec<char>], (x1, y1): (i32, i32), (x2, y2): (i32, i32)) {
+    let horizontal = (x1.min(x2)..=x1.max(x2)).map(|x| (x, y1));
+    let vertical = (y1.min(y2)..=y1.max(y2)).map(|y| (x2, y));
+    for (x, y) in horizontal.chain(vertical) {
+        let tile = &mut grid[y as usize][x as usize];
+        if !is_walkable_char(*tile) {
+            *tile = TILE_FLOOR;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate_with;
+    use crate::zones::bfs;
+
+    #[test]
+    fn regions_get_their_own_modes_and_are_joined() {
+        let hybrid = HybridGenerator::new(vec![
+            (Rect::new(0, 0, 40, 30), GenerationMode::Classic),
+            (Rect::new(40, 0, 40, 30), GenerationMode::Overworld),
+            (Rect::new(30, 20, 60, 20), GenerationMode::Wfc),
+        ]);
+        let params = GeneratorParams { width: 80, height: 30, seed: Some(5), ..Default::default() };
+        let level = generate_with(&hybrid, &params);
+        assert_eq!((level.width, level.height), (80, 30));
+        assert_eq!(level.tiles, generate_with(&hybrid, &params).tiles);
+
+        let grid: Vec<Vec<char>> = level.tiles.iter().map(|row| row.chars().collect()).collect();
+        assert!(grid[..20].iter().all(|row| row[..30].iter().all(|&c| c == TILE_WALL || c == TILE_FLOOR)));
+        assert!(grid[..20].iter().any(|row| row[40..].iter().any(|&c| !matches!(c, TILE_WALL | TILE_FLOOR))));
+        // Rooms the WFC region paved over are gone
+        assert!(level.rooms.iter().all(|r| !overlaps(r, Rect::new(30, 20, 50, 10))));
+
+        // The classic rooms reach the overworld half
+        let (x, y) = closest_floor(&grid, Rect::new(40, 0, 40, 30), Rect::new(0, 0, 40, 30)).unwrap();
+        let (distance, _) = bfs(&grid, &[((x as usize, y as usize), 0)], GridTopology::Square);
+        let room = level.rooms.iter().find(|r| r.x + r.w <= 40).unwrap();
+        let (cx, cy) = room.center();
+        assert!(distance[cy as usize][cx as usize].is_some());
+    }
+}
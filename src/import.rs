@@ -0,0 +1,211 @@
+//! Levels from hand-drawn ASCII maps and stencil images.
+//!
+//! [`Level::from_ascii`] reads a map in the characters the generators write
+//! (`#` wall, `.` floor, `,` grass, `~` water, `^` rock, `S` secret door; a
+//! space counts as wall) and runs its floor through the same marble tile detection as marble
+//! mode, so a sketch gets typed track pieces, walls, invariant checks, and
+//! the isometric view like a generated level. Two glyphs from the marble
+//! ASCII view are also understood: `O` is a one-tile pillar obstacle and `·`
+//! an open platform without walls. Both are floor in `tiles`.
+//!
+//! Rows may differ in length; short rows are padded with wall. The imported
+//! level is flat, has seed 0, and has no rooms.
+//!
+//! With the `png` feature, [`Level::from_png`] does the same for a
+//! black-and-white stencil image: light pixels are floor, dark or
+//! transparent ones wall.
+//!
+//! ```
+//! use level_generator::{Level, TileType};
+//!
+//! let level = Level::from_ascii("#####\n#...#\n#.#O#\n#...#\n#####").unwrap();
+//! let tiles = level.marble_tiles.as_ref().unwrap();
+//! assert_eq!(tiles[1][1].tile_type, TileType::Curve90);
+//! assert_eq!(tiles[2][3].tile_type, TileType::Obstacle);
+//! assert!(level.check_invariants().is_empty());
+//! ```
+
+use std::fmt;
+
+use crate::anchors::add_anchors;
+use crate::dungeon::{GenerationMode, GeneratorParams, Level, TILE_FLOOR, TILE_GRASS, TILE_ROCK, TILE_SECRET_DOOR, TILE_WALL, TILE_WATER};
+use crate::marble::{grid_to_marble_tiles, AdvancedTileConfig, MarbleTileConfig};
+use crate::obstacles::{Obstacle, ObstacleShape};
+use crate::stats::LevelStats;
+use crate::tiles::{compute_walls, MarbleTile, TileType};
+
+/// Obstacle glyph of the marble ASCII view
+const GLYPH_OBSTACLE: char = 'O';
+/// Open platform glyph of the marble ASCII view
+const GLYPH_PLATFORM: char = '·';
+
+/// Error from [`Level::from_ascii`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AsciiImportError {
+    /// The map has no rows
+    Empty,
+    /// A character outside the accepted glyphs, at `(x, y)`
+    UnknownGlyph { x: i32, y: i32, ch: char },
+}
+
+impl fmt::Display for AsciiImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AsciiImportError::Empty => write!(f, "the map is empty"),
+            AsciiImportError::UnknownGlyph { x, y, ch } => write!(f, "unknown glyph {:?} at ({}, {})", ch, x, y),
+        }
+    }
+}
+
+impl std::error::Error for AsciiImportError {}
+
+impl Level {
+    /// Level from a hand-drawn map; see the [module docs](crate::import).
+    pub fn from_ascii(text: &str) -> Result<Level, AsciiImportError> {
+        Self::from_ascii_with(text, AdvancedTileConfig::default())
+    }
+
+    /// [`from_ascii`](Level::from_ascii), placing only the advanced tiles
+    /// `advanced` allows. [`AdvancedTileConfig::none`] keeps the sketch to
+    /// straights, curves, and junctions.
+    pub fn from_ascii_with(text: &str, advanced: AdvancedTileConfig) -> Result<Level, AsciiImportError> {
+        let rows: Vec<Vec<char>> = text.lines().map(|line| line.chars().collect()).collect();
+        glyph_level(&rows, advanced)
+    }
+
+    /// Level from a PNG stencil: pixels with a brightness of at least half
+    /// (and at least half opaque) are floor, all others wall. The floor then
+    /// goes through marble tile detection like an ASCII map.
+    #[cfg(feature = "png")]
+    pub fn from_png(bytes: &[u8], advanced: AdvancedTileConfig) -> Result<Level, png::DecodingError> {
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info()?;
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let frame = reader.next_frame(&mut pixels)?;
+        let samples = frame.color_type.samples();
+        let has_alpha = matches!(frame.color_type, png::ColorType::GrayscaleAlpha | png::ColorType::Rgba);
+        let rows: Vec<Vec<char>> = pixels[..frame.buffer_size()]
+            .chunks(frame.line_size)
+            .map(|line| {
+                line.chunks(samples)
+                    .map(|px| {
+                        let color = if has_alpha { &px[..samples - 1] } else { px };
+                        let brightness = color.iter().map(|&c| c as u32).sum::<u32>() / color.len() as u32;
+                        let opaque = !has_alpha || px[samples - 1] >= 128;
+                        if brightness >= 128 && opaque { TILE_FLOOR } else { TILE_WALL }
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(glyph_level(&rows, advanced).expect("stencil images are never empty and use only map glyphs"))
+    }
+}
+
+/// Level from rows of glyphs; see the module docs for the accepted ones.
+fn glyph_level(rows: &[Vec<char>], advanced: AdvancedTileConfig) -> Result<Level, AsciiImportError> {
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if width == 0 {
+        return Err(AsciiImportError::Empty);
+    }
+
+    let mut grid = vec![vec![TILE_WALL; width]; rows.len()];
+    for (y, row) in rows.iter().enumerate() {
+        for (x, &ch) in row.iter().enumerate() {
+            grid[y][x] = match ch {
+                TILE_WALL | TILE_FLOOR | TILE_GRASS | TILE_WATER | TILE_ROCK | TILE_SECRET_DOOR => ch,
+                ' ' => TILE_WALL,
+                GLYPH_OBSTACLE | GLYPH_PLATFORM => TILE_FLOOR,
+                _ => return Err(AsciiImportError::UnknownGlyph { x: x as i32, y: y as i32, ch }),
+            };
+        }
+    }
+
+    let mut marble_tiles = grid_to_marble_tiles(&grid, &MarbleTileConfig { advanced, ..Default::default() });
+    let mut obstacles = Vec::new();
+    for (y, row) in rows.iter().enumerate() {
+        for (x, &ch) in row.iter().enumerate() {
+            let tile = &mut marble_tiles[y][x];
+            match ch {
+                GLYPH_PLATFORM => *tile = MarbleTile::with_params(TileType::OpenPlatform, 0, 0, false),
+                GLYPH_OBSTACLE => {
+                    let id = obstacles.len();
+                    let covered = std::mem::replace(
+                        tile,
+                        MarbleTile::with_params(TileType::Obstacle, 0, 0, false).with_metadata(format!("{{\"obstacle\":{}}}", id)),
+                    );
+                    obstacles.push(Obstacle { id, shape: ObstacleShape::Pillar, tiles: vec![(x as i32, y as i32)], covered: vec![covered] });
+                }
+                _ => {}
+            }
+        }
+    }
+    compute_walls(&mut marble_tiles);
+    add_anchors(&mut marble_tiles, &obstacles);
+
+    let params = GeneratorParams { width: width as u32, height: grid.len() as u32, rooms: 0, seed: Some(0), mode: GenerationMode::Marble, ..Default::default() };
+    let tiles = grid.iter().map(|row| row.iter().collect()).collect();
+    let mut level = Level::from_layout(&params, tiles, Vec::new());
+    level.marble_tiles = Some(marble_tiles);
+    level.obstacles = obstacles;
+    level.stats = LevelStats::compute(&level, 0, Vec::new());
+    Ok(level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layers::Layer;
+    use crate::tiles::Direction;
+
+    #[test]
+    fn hand_drawn_maps_get_marble_tiles() {
+        let map = "#######\n#.....#\n#.#·#.#\n#...O.#\n#######";
+        let level = Level::from_ascii_with(map, AdvancedTileConfig::none()).unwrap();
+        assert_eq!((level.width, level.height), (7, 5));
+        assert_eq!(level.tiles[3], "#.....#");
+
+        let tiles = level.marble_tiles.as_ref().unwrap();
+        assert_eq!(tiles[1][1].tile_type, TileType::Curve90);
+        assert_eq!(tiles[1][2].tile_type, TileType::Straight);
+        assert_eq!(tiles[1][3].tile_type, TileType::TJunction);
+        assert_eq!(tiles[2][3].tile_type, TileType::OpenPlatform);
+        assert_eq!(tiles[3][4].tile_type, TileType::Obstacle);
+        assert!(tiles[1][2].has_wall(Direction::North) && !tiles[1][2].has_wall(Direction::East));
+        assert_eq!(level.obstacles.len(), 1);
+        assert_eq!(level.layer(Layer::ObstacleMask)[3][4], Some(1));
+        assert!(level.check_invariants().is_empty());
+
+        // Short rows are padded with wall
+        assert_eq!(Level::from_ascii("###\n#.").unwrap().tiles[1], "#.#");
+        assert_eq!(Level::from_ascii("#.\n#x#").unwrap_err(), AsciiImportError::UnknownGlyph { x: 1, y: 1, ch: 'x' });
+        assert_eq!(Level::from_ascii("").unwrap_err(), AsciiImportError::Empty);
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn png_stencils_become_the_same_level_as_ascii() {
+        let map = ["######", "#....#", "#.##.#", "#....#", "######"];
+        // Grey-and-alpha pixels: floor is white, walls black or transparent white
+        let pixels: Vec<u8> = map
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| row.chars().map(move |ch| (y, ch)))
+            .flat_map(|(y, ch)| match (ch, y) {
+                ('.', _) => [255, 255],
+                (_, 0) => [255, 0],
+                _ => [0, 255],
+            })
+            .collect();
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, 6, 5);
+        encoder.set_color(png::ColorType::GrayscaleAlpha);
+        encoder.write_header().unwrap().write_image_data(&pixels).unwrap();
+
+        let stencil = Level::from_png(&bytes, AdvancedTileConfig::default()).unwrap();
+        let sketch = Level::from_ascii(&map.join("\n")).unwrap();
+        assert_eq!(stencil.tiles, sketch.tiles);
+        assert_eq!(stencil.marble_tiles, sketch.marble_tiles);
+        assert!(Level::from_png(b"not a png", AdvancedTileConfig::default()).is_err());
+    }
+}
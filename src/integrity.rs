@@ -0,0 +1,157 @@
+//! Content hashes for spotting corrupted, tampered, or duplicate levels.
+//!
+//! [`Level::content_hash`] is a BLAKE3 hash of the level's tile data, and
+//! the JSON and YAML exports carry it as a top-level `checksum`:
+//!
+//! ```json
+//! "checksum": "blake3:9f2c...e41a"
+//! ```
+//!
+//! [`verify`] reads an exported level and checks it against its checksum,
+//! so a game client can refuse a damaged or edited file. Two levels with
+//! the same tiles have the same hash whatever their seed, stats, or export
+//! options, so a server can dedupe uploads by it.
+//!
+//! The hash covers, in this order: `width` and `height` as little-endian
+//! `u32`s; each ASCII row (see [`Level::ascii_tiles`]) followed by `\n`;
+//! then, if the level has marble tiles, the byte `1` and for each tile, row
+//! by row, its type name, a `0` byte, `elevation` as a little-endian `i32`,
+//! `rotation`, the four `walls` as `0`/`1` bytes, and `metadata` as a
+//! little-endian `u32` length and its bytes; otherwise the byte `0`. Rooms,
+//! connections, names, and the other lists are not covered, and neither is
+//! anything outside the tiles that the file's reader trusts.
+//!
+//! ```
+//! use level_generator::export::{to_json, verify, IntegrityError};
+//! use level_generator::{generate, GeneratorParams};
+//!
+//! let level = generate(&GeneratorParams { seed: Some(5), ..Default::default() });
+//! let json = to_json(&level);
+//! assert_eq!(verify(&json).unwrap().content_hash(), level.content_hash());
+//!
+//! let tampered = json.replacen("\"#", "\".", 1);
+//! assert!(matches!(verify(&tampered), Err(IntegrityError::Mismatch { .. })));
+//! ```
+
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::dungeon::Level;
+use crate::export::from_json;
+
+/// Prefix naming the hash function in `checksum` values.
+pub const CHECKSUM_PREFIX: &str = "blake3:";
+
+impl Level {
+    /// Hex BLAKE3 hash of the tile data; see the module docs.
+    pub fn content_hash(&self) -> String {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&self.width.to_le_bytes());
+        hasher.update(&self.height.to_le_bytes());
+        for row in self.ascii_tiles().iter() {
+            hasher.update(row.as_bytes());
+            hasher.update(b"\n");
+        }
+        match &self.marble_tiles {
+            Some(tiles) => {
+                hasher.update(&[1]);
+                for tile in tiles.iter().flatten() {
+                    hasher.update(format!("{:?}", tile.tile_type).as_bytes());
+                    hasher.update(&[0]);
+                    hasher.update(&tile.elevation.to_le_bytes());
+                    hasher.update(&[tile.rotation]);
+                    hasher.update(&tile.walls.map(u8::from));
+                    hasher.update(&(tile.metadata.len() as u32).to_le_bytes());
+                    hasher.update(tile.metadata.as_bytes());
+                }
+            }
+            None => {
+                hasher.update(&[0]);
+            }
+        }
+        hasher.finalize().to_hex().to_string()
+    }
+
+    /// The `checksum` exports carry: [`CHECKSUM_PREFIX`] and the content hash.
+    pub fn checksum(&self) -> String {
+        format!("{}{}", CHECKSUM_PREFIX, self.content_hash())
+    }
+}
+
+/// Why [`verify`] refused a level.
+#[derive(Debug)]
+pub enum IntegrityError {
+    /// Not a level, or not one this version reads (see [`from_json`])
+    Parse(serde_json::Error),
+    /// The file has no `checksum`
+    MissingChecksum,
+    /// The tiles do not match the `checksum`
+    Mismatch {
+        /// Checksum stored in the file
+        expected: String,
+        /// Checksum of the tiles read
+        actual: String,
+    },
+}
+
+impl fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityError::Parse(e) => write!(f, "{}", e),
+            IntegrityError::MissingChecksum => write!(f, "level has no checksum"),
+            IntegrityError::Mismatch { expected, actual } => write!(f, "level checksum mismatch: file says {}, tiles hash to {}", expected, actual),
+        }
+    }
+}
+
+impl std::error::Error for IntegrityError {}
+
+/// Level from exported JSON whose tiles match its `checksum`; see the
+/// module docs.
+pub fn verify(json: &str) -> Result<Level, IntegrityError> {
+    let value: Value = serde_json::from_str(json).map_err(IntegrityError::Parse)?;
+    let expected = value.get("checksum").and_then(Value::as_str).ok_or(IntegrityError::MissingChecksum)?.to_string();
+    let level = from_json(json).map_err(IntegrityError::Parse)?;
+    let actual = level.checksum();
+    if actual != expected {
+        return Err(IntegrityError::Mismatch { expected, actual });
+    }
+    Ok(level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams};
+    use crate::export::{to_json, to_json_with, ExportOptions};
+
+    #[test]
+    fn checksums_catch_changed_tiles() {
+        let params = GeneratorParams { seed: Some(1180), mode: GenerationMode::Marble, enable_elevation: true, ..Default::default() };
+        let level = generate(&params);
+        let json = to_json(&level);
+        assert!(json.contains(&format!("\"checksum\": \"{}\"", level.checksum())));
+        assert_eq!(verify(&json).unwrap().content_hash(), level.content_hash());
+
+        // Export options, dropped ASCII rows, and stats leave the hash alone
+        let rle = to_json_with(&level, &ExportOptions { tiles_rle: true, tile_connections: true, ..Default::default() });
+        assert!(verify(&rle).is_ok());
+        let lean = generate(&GeneratorParams { keep_ascii_tiles: false, ..params.clone() });
+        assert_eq!(lean.content_hash(), level.content_hash());
+
+        // Any tile edit shows
+        let mut raised = level.clone();
+        raised.marble_tiles.as_mut().unwrap()[5][5].elevation += 1;
+        assert_ne!(raised.content_hash(), level.content_hash());
+        let mut value: Value = serde_json::from_str(&json).unwrap();
+        value["marble_tiles"][5][5]["rotation"] = Value::from(3);
+        assert!(matches!(verify(&value.to_string()), Err(IntegrityError::Mismatch { .. })));
+        value.as_object_mut().unwrap().remove("checksum");
+        assert!(matches!(verify(&value.to_string()), Err(IntegrityError::MissingChecksum)));
+        assert!(matches!(verify("{"), Err(IntegrityError::Parse(_))));
+
+        let other = generate(&GeneratorParams { seed: Some(1181), ..params });
+        assert_ne!(other.content_hash(), level.content_hash());
+    }
+}
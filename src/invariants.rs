@@ -0,0 +1,257 @@
+//! Structural invariants every generated level satisfies.
+//!
+//! [`Level::check_invariants`] runs the same checks the crate's own tests rely
+//! on, so fuzzers and downstream post-processing can confirm a level is still
+//! valid:
+//!
+//! - every tile layer matches `width` x `height`
+//! - `tiles` only uses legal characters
+//! - all floor (`'.'`) tiles form one connected region
+//! - WFC mazes only join pipes whose edges match
+//! - slopes change elevation by at most one level per step
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::dungeon::{wfc_edges, Level, TILE_FLOOR, TILE_GRASS, TILE_ROCK, TILE_SECRET_DOOR, TILE_VOID, TILE_WALL, TILE_WATER};
+use crate::tiles::{Direction, TileType};
+
+/// Largest elevation change allowed across one slope step.
+pub const MAX_SLOPE_DELTA: i32 = 1;
+
+/// One broken invariant, as reported by [`Level::check_invariants`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum InvariantViolation {
+    /// A per-tile layer does not match the level size
+    LayerSize {
+        layer: &'static str,
+        width: usize,
+        height: usize,
+    },
+    /// `tiles` contains a character no generator produces
+    IllegalTile { x: i32, y: i32, ch: char },
+    /// Floor tiles fall into more than one connected region; `(x, y)` is the
+    /// first floor tile unreachable from the first floor tile
+    DisconnectedFloor { x: i32, y: i32, regions: usize },
+    /// A WFC pipe connects toward a neighbor (or the border) that does not
+    /// connect back
+    WfcEdgeMismatch { x: i32, y: i32, direction: Direction },
+    /// A slope leads, along its own direction, to a neighbor more than
+    /// [`MAX_SLOPE_DELTA`] levels away
+    SlopeTooSteep { x: i32, y: i32, direction: Direction, delta: i32 },
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvariantViolation::LayerSize { layer, width, height } => {
+                write!(f, "layer `{}` is {}x{}, expected the level size", layer, width, height)
+            }
+            InvariantViolation::IllegalTile { x, y, ch } => write!(f, "illegal tile {:?} at ({}, {})", ch, x, y),
+            InvariantViolation::DisconnectedFloor { x, y, regions } => {
+                write!(f, "floor at ({}, {}) is cut off ({} floor regions)", x, y, regions)
+            }
+            InvariantViolation::WfcEdgeMismatch { x, y, direction } => {
+                write!(f, "WFC pipe at ({}, {}) has an unmatched {:?} edge", x, y, direction)
+            }
+            InvariantViolation::SlopeTooSteep { x, y, direction, delta } => {
+                write!(f, "slope at ({}, {}) drops {} levels toward {:?}", x, y, delta, direction)
+            }
+        }
+    }
+}
+
+const SIDES: [(i32, i32, Direction); 4] = [
+    (0, -1, Direction::North),
+    (1, 0, Direction::East),
+    (0, 1, Direction::South),
+    (-1, 0, Direction::West),
+];
+
+impl Level {
+    /// Check the structural invariants listed in the module docs. Returns an
+    /// empty list for a valid level.
+    pub fn check_invariants(&self) -> Vec<InvariantViolation> {
+        let mut violations = Vec::new();
+        let (width, height) = (self.width as usize, self.height as usize);
+        let grid: Vec<Vec<char>> = self.ascii_tiles().iter().map(|row| row.chars().collect()).collect();
+
+        let mut check_size = |layer: &'static str, rows: Vec<usize>| {
+            if rows.len() != height || rows.iter().any(|&w| w != width) {
+                let w = rows.iter().copied().max().unwrap_or(0);
+                violations.push(InvariantViolation::LayerSize { layer, width: w, height: rows.len() });
+                false
+            } else {
+                true
+            }
+        };
+        let tiles_ok = check_size("tiles", grid.iter().map(Vec::len).collect());
+        let marble_ok = match &self.marble_tiles {
+            Some(tiles) => check_size("marble_tiles", tiles.iter().map(Vec::len).collect()),
+            None => false,
+        };
+        if let Some(layer) = &self.terrain {
+            check_size("terrain", layer.iter().map(Vec::len).collect());
+        }
+        if let Some(layer) = &self.river_map {
+            check_size("river_map", layer.iter().map(Vec::len).collect());
+        }
+        if let Some(layer) = &self.zone_map {
+            check_size("zone_map", layer.iter().map(Vec::len).collect());
+        }
+        if !tiles_ok {
+            return violations;
+        }
+
+        // WFC mazes use only pipe characters; everything else uses map characters
+        let is_wfc = grid.iter().flatten().all(|&ch| wfc_edges(ch).is_some());
+        if is_wfc {
+            check_wfc_edges(&grid, &mut violations);
+        } else {
+            for (y, row) in grid.iter().enumerate() {
+                for (x, &ch) in row.iter().enumerate() {
+                    if !matches!(ch, TILE_WALL | TILE_FLOOR | TILE_GRASS | TILE_WATER | TILE_ROCK | TILE_VOID | TILE_SECRET_DOOR) {
+                        violations.push(InvariantViolation::IllegalTile { x: x as i32, y: y as i32, ch });
+                    }
+                }
+            }
+            self.check_floor_connected(&grid, &mut violations);
+        }
+        if marble_ok {
+            self.check_slopes(&mut violations);
+        }
+        violations
+    }
+
+    fn check_floor_connected(&self, grid: &[Vec<char>], violations: &mut Vec<InvariantViolation>) {
+        let (width, height) = (self.width as i32, self.height as i32);
+        let mut region = vec![vec![usize::MAX; width as usize]; height as usize];
+        let mut regions = 0;
+        let mut first_cut_off = None;
+        for sy in 0..height {
+            for sx in 0..width {
+                if grid[sy as usize][sx as usize] != TILE_FLOOR || region[sy as usize][sx as usize] != usize::MAX {
+                    continue;
+                }
+                if regions == 1 && first_cut_off.is_none() {
+                    first_cut_off = Some((sx, sy));
+                }
+                let mut queue = VecDeque::from([(sx, sy)]);
+                region[sy as usize][sx as usize] = regions;
+                while let Some((x, y)) = queue.pop_front() {
+                    for (nx, ny) in self.topology.neighbors(x, y) {
+                        if nx < 0 || ny < 0 || nx >= width || ny >= height {
+                            continue;
+                        }
+                        let (ux, uy) = (nx as usize, ny as usize);
+                        if grid[uy][ux] == TILE_FLOOR && region[uy][ux] == usize::MAX {
+                            region[uy][ux] = regions;
+                            queue.push_back((nx, ny));
+                        }
+                    }
+                }
+                regions += 1;
+            }
+        }
+        if let Some((x, y)) = first_cut_off {
+            violations.push(InvariantViolation::DisconnectedFloor { x, y, regions });
+        }
+    }
+
+    fn check_slopes(&self, violations: &mut Vec<InvariantViolation>) {
+        let Some(tiles) = &self.marble_tiles else { return };
+        let (width, height) = (self.width as i32, self.height as i32);
+        for (y, row) in tiles.iter().enumerate() {
+            for (x, tile) in row.iter().enumerate() {
+                if !matches!(tile.tile_type, TileType::Slope | TileType::SpiralRamp) {
+                    continue;
+                }
+                let (x, y) = (x as i32, y as i32);
+                for (dx, dy, direction) in SIDES.into_iter().filter(|&(_, _, d)| tile.connects(d)) {
+                    let (nx, ny) = (x + dx, y + dy);
+                    if nx < 0 || ny < 0 || nx >= width || ny >= height {
+                        continue;
+                    }
+                    let neighbor = &tiles[ny as usize][nx as usize];
+                    let delta = (tile.elevation - neighbor.elevation).abs();
+                    if neighbor.tile_type.is_passable() && delta > MAX_SLOPE_DELTA {
+                        violations.push(InvariantViolation::SlopeTooSteep { x, y, direction, delta });
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn check_wfc_edges(grid: &[Vec<char>], violations: &mut Vec<InvariantViolation>) {
+    let edges = |x: i32, y: i32| -> [bool; 4] {
+        if x < 0 || y < 0 {
+            return [false; 4];
+        }
+        grid.get(y as usize)
+            .and_then(|row| row.get(x as usize))
+            .and_then(|&ch| wfc_edges(ch))
+            .unwrap_or([false; 4])
+    };
+    for (y, row) in grid.iter().enumerate() {
+        for x in 0..row.len() {
+            let (x, y) = (x as i32, y as i32);
+            let here = edges(x, y);
+            for (dx, dy, direction) in SIDES {
+                let back = direction.opposite() as usize;
+                if here[direction as usize] && !edges(x + dx, y + dy)[back] {
+                    violations.push(InvariantViolation::WfcEdgeMismatch { x, y, direction });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams};
+
+    #[test]
+    fn generated_levels_hold_invariants() {
+        for mode in [
+            GenerationMode::Classic,
+            GenerationMode::Marble,
+            GenerationMode::Wfc,
+            GenerationMode::Overworld,
+            GenerationMode::Settlement,
+            GenerationMode::RoomWfc,
+        ] {
+            for seed in 0..10 {
+                let params = GeneratorParams { seed: Some(seed), mode, ..Default::default() };
+                let level = generate(&params);
+                assert_eq!(level.check_invariants(), vec![], "{:?} seed {}", mode, seed);
+            }
+        }
+
+        // Corridors ramp one level per step, even between rooms several levels apart
+        for seed in 0..20 {
+            let params = GeneratorParams {
+                seed: Some(seed),
+                mode: GenerationMode::Marble,
+                enable_elevation: true,
+                max_elevation: 3,
+                max_elevation_change: 2,
+                ..Default::default()
+            };
+            let level = generate(&params);
+            assert_eq!(level.check_invariants(), vec![], "elevated seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn broken_levels_are_reported() {
+        let mut level = generate(&GeneratorParams { seed: Some(4), ..Default::default() });
+        // An isolated floor tile in the corner and a stray character
+        level.tiles[0].replace_range(0..2, ".x");
+        let violations = level.check_invariants();
+        assert!(violations.contains(&InvariantViolation::IllegalTile { x: 1, y: 0, ch: 'x' }));
+        assert!(violations.iter().any(|v| matches!(v, InvariantViolation::DisconnectedFloor { .. })));
+    }
+}
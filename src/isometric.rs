@@ -3,8 +3,16 @@
 //! This module provides isometric rendering of marble tile levels,
 //! showing elevation, walls, and different tile types in 3D perspective.
 
-use crate::dungeon::Level;
-use crate::tiles::{MarbleTile, TileType};
+use std::collections::BTreeMap;
+
+use crate::dungeon::{Level, TILE_WALL};
+use crate::dynamics::{DynamicElement, DynamicKind};
+use crate::hex::GridTopology;
+use crate::overworld::{TerrainTile, TerrainType};
+use crate::splines::ChannelSpline;
+use crate::structures::{Structure, StructureKind};
+use crate::tiles::{Direction, MarbleTile, TileType};
+use crate::voxel::VoxelMap;
 
 /// Tile dimensions for isometric projection
 const TILE_WIDTH: f32 = 32.0;
@@ -20,7 +28,7 @@ fn to_isometric(x: f32, y: f32, z: f32) -> (f32, f32) {
 }
 
 /// Get color for a tile type
-fn tile_color(tile_type: &TileType) -> &'static str {
+pub(crate) fn tile_color(tile_type: &TileType) -> &'static str {
     match tile_type {
         TileType::Empty => "#2b2b2b",
         TileType::Straight => "#5a9fd4",
@@ -38,6 +46,121 @@ fn tile_color(tile_type: &TileType) -> &'static str {
         TileType::LaunchPad => "#ff4444",
         TileType::Bridge => "#7fc76b",
         TileType::Tunnel => "#4c6bc7",
+        TileType::Spiral => "#d4c25a",
+        TileType::ElevatorDrop => "#c7864c",
+        TileType::SpiralRamp => "#e8c047",
+        TileType::Funnel => "#b8a58c",
+    }
+}
+
+/// Voxels in back-to-front draw order: by diagonal, then bottom to top
+fn voxel_draw_order(voxels: &VoxelMap) -> Vec<(usize, usize, &MarbleTile)> {
+    let mut order: Vec<(i32, i32, i32, &MarbleTile)> = voxels.iter().collect();
+    order.sort_by_key(|&(x, y, z, _)| (x + y, z, y));
+    order
+        .into_iter()
+        .filter(|&(x, y, _, _)| x >= 0 && y >= 0)
+        .map(|(x, y, _, tile)| (x as usize, y as usize, tile))
+        .collect()
+}
+
+/// Part of a tile a draw step paints
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum DrawLayer {
+    /// Cliff faces down to lower neighbors in front
+    Wall,
+    /// Top surface with its walls, paths and markers
+    Surface,
+}
+
+/// Heightmap tiles in back-to-front draw order: by diagonal, then bottom to
+/// top, so a high tile covers the low tiles beside it, with each tile's
+/// cliff faces before its surface.
+fn tile_draw_order(tiles: &[Vec<MarbleTile>]) -> Vec<(usize, usize, DrawLayer)> {
+    let mut order: Vec<(usize, usize, DrawLayer)> = tiles
+        .iter()
+        .enumerate()
+        .flat_map(|(y, row)| row.iter().enumerate().filter(|(_, t)| t.tile_type != TileType::Empty).map(move |(x, _)| (x, y)))
+        .flat_map(|(x, y)| [(x, y, DrawLayer::Wall), (x, y, DrawLayer::Surface)])
+        .collect();
+    order.sort_by_key(|&(x, y, layer)| (x + y, tiles[y][x].elevation, layer, y));
+    order
+}
+
+/// Draw the south and east cliff faces of the tile at (x, y) down to the
+/// surfaces of lower neighbors, so terraces read as solid steps instead of
+/// floating slabs.
+fn draw_cliff_faces(tiles: &[Vec<MarbleTile>], x: usize, y: usize, svg: &mut String) {
+    let tile = &tiles[y][x];
+    let color = darken_color(&adjust_color_for_elevation(tile_color(&tile.tile_type), tile.elevation), 0.5);
+    let (fx, fy, fz) = (x as f32, y as f32, tile.elevation as f32);
+    for (nx, ny, edge) in [(x, y + 1, [(fx, fy + 1.0), (fx + 1.0, fy + 1.0)]), (x + 1, y, [(fx + 1.0, fy), (fx + 1.0, fy + 1.0)])] {
+        let Some(below) = tiles.get(ny).and_then(|row| row.get(nx)).filter(|t| t.tile_type != TileType::Empty) else { continue };
+        if below.elevation >= tile.elevation {
+            continue;
+        }
+        let bz = below.elevation as f32;
+        let [(ax, ay), (bx, by)] = edge;
+        let corners = [to_isometric(ax, ay, fz), to_isometric(bx, by, fz), to_isometric(bx, by, bz), to_isometric(ax, ay, bz)];
+        let points: Vec<String> = corners.iter().map(|(px, py)| format!("{},{}", px, py)).collect();
+        svg.push_str(&format!(
+            "  <polygon points=\"{}\" fill=\"{}\" stroke=\"#222\" stroke-width=\"0.5\"/>\n",
+            points.join(" "), color
+        ));
+    }
+}
+
+/// Circumradius of a hex tile in pixels
+const HEX_SIZE: f32 = 10.0;
+/// Width of a pointy-top hex tile in pixels
+const HEX_WIDTH: f32 = HEX_SIZE * 1.732_050_8;
+
+/// Render one pointy-top hex tile in odd-r offset layout (flat, top-down)
+fn render_hex_svg(x: usize, y: usize, ch: char, svg: &mut String) {
+    let shift = if y % 2 == 1 { 0.5 } else { 0.0 };
+    let cx = (x as f32 + shift + 0.5) * HEX_WIDTH;
+    let cy = (y as f32 * 0.75 + 0.5) * HEX_SIZE * 2.0;
+    let points: Vec<String> = (0..6)
+        .map(|i| {
+            let angle = (60.0 * i as f32 - 30.0).to_radians();
+            format!("{:.2},{:.2}", cx + HEX_SIZE * angle.cos(), cy + HEX_SIZE * angle.sin())
+        })
+        .collect();
+    let color = if ch == TILE_WALL { "#2a2a2a" } else { "#c8c8c8" };
+    svg.push_str(&format!(
+        "  <polygon points=\"{}\" fill=\"{}\" stroke=\"#111\" stroke-width=\"0.5\"/>\n",
+        points.join(" "),
+        color
+    ));
+}
+
+/// Get color for an overworld terrain type
+pub(crate) fn terrain_color(terrain: TerrainType) -> &'static str {
+    match terrain {
+        TerrainType::Grass => "#5c8a3a",
+        TerrainType::Rock => "#8a8078",
+        TerrainType::Water => "#2f6fb0",
+        TerrainType::Path => "#c2a36b",
+    }
+}
+
+/// Render an overworld terrain tile as a flat block at its elevation
+fn render_terrain_svg(tile: &TerrainTile, x: usize, y: usize, svg: &mut String) {
+    let fx = x as f32;
+    let fy = y as f32;
+    let fz = tile.elevation as f32;
+    let color = adjust_color_for_elevation(terrain_color(tile.terrain), tile.elevation);
+
+    let (x0, y0) = to_isometric(fx, fy, fz);
+    let (x1, y1) = to_isometric(fx + 1.0, fy, fz);
+    let (x2, y2) = to_isometric(fx + 1.0, fy + 1.0, fz);
+    let (x3, y3) = to_isometric(fx, fy + 1.0, fz);
+    svg.push_str(&format!(
+        "  <polygon points=\"{},{} {},{} {},{} {},{}\" fill=\"{}\" stroke=\"#222\" stroke-width=\"0.3\"/>\n",
+        x0, y0, x1, y1, x2, y2, x3, y3, color
+    ));
+    if tile.terrain == TerrainType::Rock {
+        draw_tile_walls(fx, fy, fz, [true; 4], &color, svg);
     }
 }
 
@@ -78,16 +201,16 @@ fn render_tile_svg(tile: &MarbleTile, x: usize, y: usize, svg: &mut String) {
     let (x3, y3) = to_isometric(fx, fy + 1.0, fz);
     
     // Draw base tile surface (lighter for non-walls)
-    let surface_color = if tile.has_walls { &color } else { &lighten_color(&color, 0.3) };
+    let surface_color = if tile.has_walls() { &color } else { &lighten_color(&color, 0.3) };
     let polygon_points = format!("{},{} {},{} {},{} {},{}", x0, y0, x1, y1, x2, y2, x3, y3);
     svg.push_str(&format!(
         "  <polygon points=\"{}\" fill=\"{}\" stroke=\"#333\" stroke-width=\"0.5\" opacity=\"0.8\"/>\n",
         polygon_points, surface_color
     ));
     
-    // Draw walls if the tile has walls
-    if tile.has_walls {
-        draw_tile_walls(fx, fy, fz, &color, svg);
+    // Draw walls on the edges that have them
+    if tile.has_walls() {
+        draw_tile_walls(fx, fy, fz, tile.walls, &color, svg);
     }
     
     // Draw tile-specific shapes and paths
@@ -137,6 +260,18 @@ fn render_tile_svg(tile: &MarbleTile, x: usize, y: usize, svg: &mut String) {
         TileType::Tunnel => {
             draw_tunnel(fx, fy, fz, tile.rotation, &color, svg);
         },
+        TileType::Spiral => {
+            draw_spiral(fx, fy, fz, &color, svg);
+        },
+        TileType::ElevatorDrop => {
+            draw_elevator_drop(fx, fy, fz, &color, svg);
+        },
+        TileType::SpiralRamp => {
+            draw_spiral_ramp(fx, fy, fz, tile.rotation, &color, svg);
+        },
+        TileType::Funnel => {
+            // Base surface only; the bowl is drawn over the whole structure
+        },
         TileType::Empty => {
             // Empty tiles are handled by the early return
         },
@@ -169,8 +304,24 @@ fn lighten_color(hex: &str, factor: f32) -> String {
     format!("#{:02x}{:02x}{:02x}", r, g, b)
 }
 
-/// Draw walls for a tile
-fn draw_tile_walls(fx: f32, fy: f32, fz: f32, color: &str, svg: &mut String) {
+/// Draw a translucent water overlay on a river tile
+fn draw_river_overlay(x: usize, y: usize, elevation: i32, svg: &mut String) {
+    let fx = x as f32;
+    let fy = y as f32;
+    let fz = elevation as f32 + 0.05;
+    let (x0, y0) = to_isometric(fx + 0.2, fy + 0.2, fz);
+    let (x1, y1) = to_isometric(fx + 0.8, fy + 0.2, fz);
+    let (x2, y2) = to_isometric(fx + 0.8, fy + 0.8, fz);
+    let (x3, y3) = to_isometric(fx + 0.2, fy + 0.8, fz);
+    svg.push_str(&format!(
+        "  <polygon points=\"{},{} {},{} {},{} {},{}\" fill=\"#3fa9f5\" opacity=\"0.7\"/>\n",
+        x0, y0, x1, y1, x2, y2, x3, y3
+    ));
+}
+
+/// Draw walls for a tile. Only the front faces (south and east) are visible
+/// from the isometric camera, so north and west walls are skipped.
+fn draw_tile_walls(fx: f32, fy: f32, fz: f32, walls: [bool; 4], color: &str, svg: &mut String) {
     let (_x0, _y0) = to_isometric(fx, fy, fz);
     let (x1, y1) = to_isometric(fx + 1.0, fy, fz);
     let (x2, y2) = to_isometric(fx + 1.0, fy + 1.0, fz);
@@ -182,20 +333,24 @@ fn draw_tile_walls(fx: f32, fy: f32, fz: f32, color: &str, svg: &mut String) {
     let (bx3, by3) = to_isometric(fx, fy + 1.0, fz - WALL_HEIGHT / ELEVATION_HEIGHT);
     let (bx2, by2) = to_isometric(fx + 1.0, fy + 1.0, fz - WALL_HEIGHT / ELEVATION_HEIGHT);
     
-    let wall_points = format!("{},{} {},{} {},{} {},{}", x3, y3, x2, y2, bx2, by2, bx3, by3);
-    svg.push_str(&format!(
-        "  <polygon points=\"{}\" fill=\"{}\" stroke=\"#222\" stroke-width=\"0.5\" opacity=\"0.9\"/>\n",
-        wall_points, wall_color
-    ));
+    if walls[Direction::South as usize] {
+        let wall_points = format!("{},{} {},{} {},{} {},{}", x3, y3, x2, y2, bx2, by2, bx3, by3);
+        svg.push_str(&format!(
+            "  <polygon points=\"{}\" fill=\"{}\" stroke=\"#222\" stroke-width=\"0.5\" opacity=\"0.9\"/>\n",
+            wall_points, wall_color
+        ));
+    }
     
     // East wall (front-right face)
     let (bx1, by1) = to_isometric(fx + 1.0, fy, fz - WALL_HEIGHT / ELEVATION_HEIGHT);
     
-    let wall_points2 = format!("{},{} {},{} {},{} {},{}", x1, y1, x2, y2, bx2, by2, bx1, by1);
-    svg.push_str(&format!(
-        "  <polygon points=\"{}\" fill=\"{}\" stroke=\"#222\" stroke-width=\"0.5\" opacity=\"0.8\"/>\n",
-        wall_points2, darken_color(color, 0.6)
-    ));
+    if walls[Direction::East as usize] {
+        let wall_points2 = format!("{},{} {},{} {},{} {},{}", x1, y1, x2, y2, bx2, by2, bx1, by1);
+        svg.push_str(&format!(
+            "  <polygon points=\"{}\" fill=\"{}\" stroke=\"#222\" stroke-width=\"0.5\" opacity=\"0.8\"/>\n",
+            wall_points2, darken_color(color, 0.6)
+        ));
+    }
 }
 
 /// Draw a straight path with raised edges
@@ -278,45 +433,42 @@ fn draw_curve_path(fx: f32, fy: f32, fz: f32, rotation: u8, color: &str, svg: &m
 fn draw_t_junction(fx: f32, fy: f32, fz: f32, rotation: u8, color: &str, svg: &mut String) {
     let path_color = lighten_color(color, 1.2);
     
-    match rotation {
-        0 => { // Missing South
-            // North path
-            let (x1, y1) = to_isometric(fx + 0.3, fy + 0.2, fz + 0.1);
-            let (x2, y2) = to_isometric(fx + 0.7, fy + 0.2, fz + 0.1);
-            let (x3, y3) = to_isometric(fx + 0.7, fy + 0.5, fz + 0.1);
-            let (x4, y4) = to_isometric(fx + 0.3, fy + 0.5, fz + 0.1);
-            let north_path = format!("{},{} {},{} {},{} {},{}", x1, y1, x2, y2, x3, y3, x4, y4);
-            
-            // East path
-            let (x5, y5) = to_isometric(fx + 0.5, fy + 0.3, fz + 0.1);
-            let (x6, y6) = to_isometric(fx + 0.8, fy + 0.3, fz + 0.1);
-            let (x7, y7) = to_isometric(fx + 0.8, fy + 0.7, fz + 0.1);
-            let (x8, y8) = to_isometric(fx + 0.5, fy + 0.7, fz + 0.1);
-            let east_path = format!("{},{} {},{} {},{} {},{}", x5, y5, x6, y6, x7, y7, x8, y8);
-            
-            // West path
-            let (x9, y9) = to_isometric(fx + 0.2, fy + 0.3, fz + 0.1);
-            let (x10, y10) = to_isometric(fx + 0.5, fy + 0.3, fz + 0.1);
-            let (x11, y11) = to_isometric(fx + 0.5, fy + 0.7, fz + 0.1);
-            let (x12, y12) = to_isometric(fx + 0.2, fy + 0.7, fz + 0.1);
-            let west_path = format!("{},{} {},{} {},{} {},{}", x9, y9, x10, y10, x11, y11, x12, y12);
-            
-            svg.push_str(&format!(
-                "  <polygon points=\"{}\" fill=\"{}\" stroke=\"#444\" stroke-width=\"0.3\"/>\n",
-                north_path, path_color
-            ));
-            svg.push_str(&format!(
-                "  <polygon points=\"{}\" fill=\"{}\" stroke=\"#444\" stroke-width=\"0.3\"/>\n",
-                east_path, path_color
-            ));
-            svg.push_str(&format!(
-                "  <polygon points=\"{}\" fill=\"{}\" stroke=\"#444\" stroke-width=\"0.3\"/>\n",
-                west_path, path_color
-            ));
-        },
-        // Similar patterns for other rotations...
-        _ => {}
+    if rotation == 0 { // Missing South
+        // North path
+        let (x1, y1) = to_isometric(fx + 0.3, fy + 0.2, fz + 0.1);
+        let (x2, y2) = to_isometric(fx + 0.7, fy + 0.2, fz + 0.1);
+        let (x3, y3) = to_isometric(fx + 0.7, fy + 0.5, fz + 0.1);
+        let (x4, y4) = to_isometric(fx + 0.3, fy + 0.5, fz + 0.1);
+        let north_path = format!("{},{} {},{} {},{} {},{}", x1, y1, x2, y2, x3, y3, x4, y4);
+        
+        // East path
+        let (x5, y5) = to_isometric(fx + 0.5, fy + 0.3, fz + 0.1);
+        let (x6, y6) = to_isometric(fx + 0.8, fy + 0.3, fz + 0.1);
+        let (x7, y7) = to_isometric(fx + 0.8, fy + 0.7, fz + 0.1);
+        let (x8, y8) = to_isometric(fx + 0.5, fy + 0.7, fz + 0.1);
+        let east_path = format!("{},{} {},{} {},{} {},{}", x5, y5, x6, y6, x7, y7, x8, y8);
+        
+        // West path
+        let (x9, y9) = to_isometric(fx + 0.2, fy + 0.3, fz + 0.1);
+        let (x10, y10) = to_isometric(fx + 0.5, fy + 0.3, fz + 0.1);
+        let (x11, y11) = to_isometric(fx + 0.5, fy + 0.7, fz + 0.1);
+        let (x12, y12) = to_isometric(fx + 0.2, fy + 0.7, fz + 0.1);
+        let west_path = format!("{},{} {},{} {},{} {},{}", x9, y9, x10, y10, x11, y11, x12, y12);
+        
+        svg.push_str(&format!(
+            "  <polygon points=\"{}\" fill=\"{}\" stroke=\"#444\" stroke-width=\"0.3\"/>\n",
+            north_path, path_color
+        ));
+        svg.push_str(&format!(
+            "  <polygon points=\"{}\" fill=\"{}\" stroke=\"#444\" stroke-width=\"0.3\"/>\n",
+            east_path, path_color
+        ));
+        svg.push_str(&format!(
+            "  <polygon points=\"{}\" fill=\"{}\" stroke=\"#444\" stroke-width=\"0.3\"/>\n",
+            west_path, path_color
+        ));
     }
+    // Similar patterns for other rotations...
 }
 
 /// Draw a Y-junction with smooth curved paths
@@ -331,17 +483,14 @@ fn draw_y_junction(fx: f32, fy: f32, fz: f32, rotation: u8, color: &str, svg: &m
     ));
     
     // Add small directional indicators
-    match rotation {
-        0 => {
-            let (x1, y1) = to_isometric(fx + 0.5, fy + 0.3, fz + 0.15);
-            let (x2, y2) = to_isometric(fx + 0.7, fy + 0.4, fz + 0.15);
-            let (x3, y3) = to_isometric(fx + 0.3, fy + 0.4, fz + 0.15);
-            svg.push_str(&format!(
-                "  <polygon points=\"{},{} {},{} {},{}\" fill=\"#fff\" opacity=\"0.8\"/>\n",
-                x1, y1, x2, y2, x3, y3
-            ));
-        },
-        _ => {}
+    if rotation == 0 {
+        let (x1, y1) = to_isometric(fx + 0.5, fy + 0.3, fz + 0.15);
+        let (x2, y2) = to_isometric(fx + 0.7, fy + 0.4, fz + 0.15);
+        let (x3, y3) = to_isometric(fx + 0.3, fy + 0.4, fz + 0.15);
+        svg.push_str(&format!(
+            "  <polygon points=\"{},{} {},{} {},{}\" fill=\"#fff\" opacity=\"0.8\"/>\n",
+            x1, y1, x2, y2, x3, y3
+        ));
     }
 }
 
@@ -555,16 +704,13 @@ fn draw_half_pipe(fx: f32, fy: f32, fz: f32, rotation: u8, color: &str, svg: &mu
     let pipe_color = lighten_color(color, 1.2);
     
     // Draw half-pipe as curved path
-    match rotation {
-        0 => { // North to East curve with elevation
-            let (x1, y1) = to_isometric(fx + 0.5, fy + 0.2, fz + 0.1);
-            let (x2, y2) = to_isometric(fx + 0.8, fy + 0.5, fz + 0.2);
-            svg.push_str(&format!(
-                "  <path d=\"M {},{} Q {},{} {},{} L {},{} Q {},{} {},{} Z\" fill=\"{}\" stroke=\"#444\" stroke-width=\"0.3\"/>\n",
-                x1, y1, cx, cy, x2, y2, x1, y1, cx, cy, x1, y1, pipe_color
-            ));
-        },
-        _ => {}
+    if rotation == 0 { // North to East curve with elevation
+        let (x1, y1) = to_isometric(fx + 0.5, fy + 0.2, fz + 0.1);
+        let (x2, y2) = to_isometric(fx + 0.8, fy + 0.5, fz + 0.2);
+        svg.push_str(&format!(
+            "  <path d=\"M {},{} Q {},{} {},{} L {},{} Q {},{} {},{} Z\" fill=\"{}\" stroke=\"#444\" stroke-width=\"0.3\"/>\n",
+            x1, y1, cx, cy, x2, y2, x1, y1, cx, cy, x1, y1, pipe_color
+        ));
     }
     
     // Add half-pipe indicator
@@ -591,18 +737,15 @@ fn draw_launch_pad(fx: f32, fy: f32, fz: f32, rotation: u8, color: &str, svg: &m
     ));
     
     // Add speed lines
-    match rotation {
-        0 => { // Launching North
-            for i in 0..3 {
-                let (x1, y1) = to_isometric(fx + 0.4 + i as f32 * 0.1, fy + 0.3, fz + 0.15);
-                let (x2, y2) = to_isometric(fx + 0.4 + i as f32 * 0.1, fy + 0.1, fz + 0.15);
-                svg.push_str(&format!(
-                    "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#fff\" stroke-width=\"1\" opacity=\"0.7\"/>\n",
-                    x1, y1, x2, y2
-                ));
-            }
-        },
-        _ => {}
+    if rotation == 0 { // Launching North
+        for i in 0..3 {
+            let (x1, y1) = to_isometric(fx + 0.4 + i as f32 * 0.1, fy + 0.3, fz + 0.15);
+            let (x2, y2) = to_isometric(fx + 0.4 + i as f32 * 0.1, fy + 0.1, fz + 0.15);
+            svg.push_str(&format!(
+                "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#fff\" stroke-width=\"1\" opacity=\"0.7\"/>\n",
+                x1, y1, x2, y2
+            ));
+        }
     }
     
     // Add launch indicator
@@ -640,6 +783,143 @@ fn draw_bridge(fx: f32, fy: f32, fz: f32, rotation: u8, color: &str, svg: &mut S
     ));
 }
 
+/// Draw one level of a spiral as a helix ring around the column center
+fn draw_spiral(fx: f32, fy: f32, fz: f32, color: &str, svg: &mut String) {
+    let (cx, cy) = to_isometric(fx + 0.5, fy + 0.5, fz + 0.1);
+    let ring_color = lighten_color(color, 1.2);
+    svg.push_str(&format!(
+        "  <ellipse cx=\"{}\" cy=\"{}\" rx=\"{}\" ry=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\"/>\n",
+        cx, cy, TILE_WIDTH * 0.3, TILE_HEIGHT * 0.3, ring_color
+    ));
+    // Center post
+    let (_, top) = to_isometric(fx + 0.5, fy + 0.5, fz + 1.0);
+    svg.push_str(&format!(
+        "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#666\" stroke-width=\"1.5\"/>\n",
+        cx, cy, cx, top
+    ));
+}
+
+/// Draw a spiral ramp tile as a banked arc curling toward its descent direction
+fn draw_spiral_ramp(fx: f32, fy: f32, fz: f32, rotation: u8, color: &str, svg: &mut String) {
+    let (dx, dy) = match rotation % 4 {
+        0 => (0.0, -1.0),
+        1 => (1.0, 0.0),
+        2 => (0.0, 1.0),
+        _ => (-1.0, 0.0),
+    };
+    // Enter high on the back edge, leave low on the front edge
+    let (sx, sy) = to_isometric(fx + 0.5 - dx * 0.4, fy + 0.5 - dy * 0.4, fz + 0.5);
+    let (mx, my) = to_isometric(fx + 0.5 + dy * 0.3, fy + 0.5 - dx * 0.3, fz + 0.25);
+    let (ex, ey) = to_isometric(fx + 0.5 + dx * 0.4, fy + 0.5 + dy * 0.4, fz);
+    let ramp_color = lighten_color(color, 1.2);
+    svg.push_str(&format!(
+        "  <path d=\"M {},{} Q {},{} {},{}\" stroke=\"{}\" stroke-width=\"4\" fill=\"none\" stroke-linecap=\"round\"/>\n",
+        sx, sy, mx, my, ex, ey, ramp_color
+    ));
+    svg.push_str(&format!(
+        "  <path d=\"M {},{} Q {},{} {},{}\" stroke=\"#333\" stroke-width=\"1\" fill=\"none\"/>\n",
+        sx, sy, mx, my, ex, ey
+    ));
+}
+
+/// Draw one level of an elevator drop shaft
+fn draw_elevator_drop(fx: f32, fy: f32, fz: f32, color: &str, svg: &mut String) {
+    let (x0, y0) = to_isometric(fx + 0.3, fy + 0.5, fz);
+    let (x1, y1) = to_isometric(fx + 0.7, fy + 0.5, fz);
+    let (_, top) = to_isometric(fx + 0.5, fy + 0.5, fz + 1.0);
+    let shaft_color = darken_color(color, 0.8);
+    svg.push_str(&format!(
+        "  <polygon points=\"{},{} {},{} {},{} {},{}\" fill=\"{}\" stroke=\"#333\" stroke-width=\"0.5\" opacity=\"0.7\"/>\n",
+        x0, y0, x1, y1, x1, top + (y1 - y0), x0, top, shaft_color
+    ));
+    let (cx, cy) = to_isometric(fx + 0.5, fy + 0.5, fz + 0.5);
+    svg.push_str(&format!(
+        "  <text x=\"{}\" y=\"{}\" font-size=\"10\" fill=\"#fff\" text-anchor=\"middle\" dominant-baseline=\"middle\">↓</text>\n",
+        cx, cy
+    ));
+}
+
+/// Draw a funnel structure as a bowl of concentric rings sinking toward its drain
+fn draw_funnel(funnel: &Structure, svg: &mut String) {
+    let (cx, cy) = (funnel.exit.x as f32 + 0.5, funnel.exit.y as f32 + 0.5);
+    let rim_z = funnel.entry.elevation as f32 + 0.1;
+    let radius = funnel
+        .tiles
+        .iter()
+        .map(|&(x, y)| ((x as f32 + 0.5 - cx).powi(2) + (y as f32 + 0.5 - cy).powi(2)).sqrt())
+        .fold(0.0, f32::max)
+        + 0.5;
+    let bowl_color = tile_color(&TileType::Funnel);
+
+    // A circle on the ground plane projects to an axis-aligned ellipse
+    const RINGS: usize = 4;
+    for ring in 0..RINGS {
+        let t = 1.0 - ring as f32 / RINGS as f32;
+        let r = radius * t;
+        let (ex, ey) = to_isometric(cx, cy, rim_z - (1.0 - t) * 0.8);
+        let fill = darken_color(bowl_color, 0.5 + 0.5 * t);
+        svg.push_str(&format!(
+            "  <ellipse cx=\"{}\" cy=\"{}\" rx=\"{}\" ry=\"{}\" fill=\"{}\" stroke=\"#333\" stroke-width=\"0.5\" opacity=\"0.9\"/>\n",
+            ex, ey, r * std::f32::consts::SQRT_2 * TILE_WIDTH / 2.0, r * std::f32::consts::SQRT_2 * TILE_HEIGHT / 4.0, fill
+        ));
+    }
+
+    // Drain hole one level down
+    let (dx, dy) = to_isometric(cx, cy, funnel.exit.elevation as f32 + 0.1);
+    svg.push_str(&format!(
+        "  <ellipse cx=\"{}\" cy=\"{}\" rx=\"{}\" ry=\"{}\" fill=\"#111\" stroke=\"#000\" stroke-width=\"1\"/>\n",
+        dx, dy, TILE_WIDTH * 0.3, TILE_HEIGHT * 0.3
+    ));
+}
+
+/// Draw a moving hazard marker as a bar across its channel section
+fn draw_dynamic_marker(element: &DynamicElement, svg: &mut String) {
+    let (cx, cy) = (element.x as f32 + 0.5, element.y as f32 + 0.5);
+    let half = element.span as f32 / 2.0;
+    let z = element.elevation as f32 + 0.6;
+    // The element moves across the channel, perpendicular to its axis
+    let (dx, dy) = if element.axis == Direction::East { (0.0, half) } else { (half, 0.0) };
+    let (x0, y0) = to_isometric(cx - dx, cy - dy, z);
+    let (x1, y1) = to_isometric(cx + dx, cy + dy, z);
+    let (color, label) = match element.kind {
+        DynamicKind::Pendulum => ("#e040fb", "P"),
+        DynamicKind::RotatingBar => ("#ff6e40", "R"),
+        DynamicKind::TimedGate => ("#ffd740", "G"),
+    };
+    svg.push_str(&format!(
+        "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"3\" stroke-dasharray=\"4,2\"/>\n",
+        x0, y0, x1, y1, color
+    ));
+    let (lx, ly) = to_isometric(cx, cy, z + 0.5);
+    svg.push_str(&format!(
+        "  <text x=\"{}\" y=\"{}\" font-size=\"9\" fill=\"{}\" text-anchor=\"middle\"><title>{:?} period {:.2}s phase {:.2}</title>{}</text>\n",
+        lx, ly, color, element.kind, element.period, element.phase, label
+    ));
+}
+
+/// Draw a channel centerline spline as a chain of cubic Béziers, with its
+/// width as a translucent band under the line
+fn draw_channel_spline(spline: &ChannelSpline, svg: &mut String) {
+    let project = |p: [f32; 3]| to_isometric(p[0], p[1], p[2] + 0.6);
+    let mut d = String::new();
+    for (i, [p1, c1, c2, p2]) in spline.bezier_segments().into_iter().enumerate() {
+        if i == 0 {
+            let (x, y) = project(p1);
+            d.push_str(&format!("M {},{}", x, y));
+        }
+        let ((ax, ay), (bx, by), (ex, ey)) = (project(c1), project(c2), project(p2));
+        d.push_str(&format!(" C {},{} {},{} {},{}", ax, ay, bx, by, ex, ey));
+    }
+    svg.push_str(&format!(
+        "  <path d=\"{}\" fill=\"none\" stroke=\"#00e5ff\" stroke-opacity=\"0.25\" stroke-width=\"{}\" stroke-linecap=\"round\" stroke-linejoin=\"round\"/>\n",
+        d, spline.width as f32 * TILE_HEIGHT / 2.0
+    ));
+    svg.push_str(&format!(
+        "  <path d=\"{}\" fill=\"none\" stroke=\"#00e5ff\" stroke-width=\"1.5\"><title>Edge {} width {}</title></path>\n",
+        d, spline.edge, spline.width
+    ));
+}
+
 /// Draw a tunnel entrance
 fn draw_tunnel(fx: f32, fy: f32, fz: f32, _rotation: u8, color: &str, svg: &mut String) {
     let (cx, cy) = to_isometric(fx + 0.5, fy + 0.5, fz + 0.1);
@@ -738,8 +1018,8 @@ fn generate_legend_tile_svg(tile_type: &TileType) -> String {
         TileType::LaunchPad => {
             // Launch pad with speed lines
             svg.push_str(&format!("<line x1=\"4\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#fff\" stroke-width=\"2\"/>", center_i, size_i-4, center_i));
-            svg.push_str(&format!("<line x1=\"6\" y1=\"6\" x2=\"8\" y2=\"4\" stroke=\"#fff\" stroke-width=\"1\"/>"));
-            svg.push_str(&format!("<line x1=\"6\" y1=\"8\" x2=\"8\" y2=\"6\" stroke=\"#fff\" stroke-width=\"1\"/>"));
+            svg.push_str("<line x1=\"6\" y1=\"6\" x2=\"8\" y2=\"4\" stroke=\"#fff\" stroke-width=\"1\"/>");
+            svg.push_str("<line x1=\"6\" y1=\"8\" x2=\"8\" y2=\"6\" stroke=\"#fff\" stroke-width=\"1\"/>");
             svg.push_str(&format!("<text x=\"{}\" y=\"{}\" font-size=\"6\" fill=\"#fff\" text-anchor=\"middle\">⚡</text>", center_i, center_i+2));
         },
         TileType::Bridge => {
@@ -752,6 +1032,26 @@ fn generate_legend_tile_svg(tile_type: &TileType) -> String {
             svg.push_str(&format!("<path d=\"M 4 {} Q {} 4 {} {}\" stroke=\"#fff\" stroke-width=\"2\" fill=\"#333\"/>", center_i, center_i, size_i-4, center_i));
             svg.push_str(&format!("<text x=\"{}\" y=\"{}\" font-size=\"6\" fill=\"#fff\" text-anchor=\"middle\">🚇</text>", center_i, center_i+2));
         },
+        TileType::Spiral => {
+            // Concentric rings
+            svg.push_str(&format!("<circle cx=\"{}\" cy=\"{}\" r=\"7\" stroke=\"#fff\" stroke-width=\"1.5\" fill=\"none\"/>", center_i, center_i));
+            svg.push_str(&format!("<circle cx=\"{}\" cy=\"{}\" r=\"3\" stroke=\"#fff\" stroke-width=\"1.5\" fill=\"none\"/>", center_i, center_i));
+        },
+        TileType::SpiralRamp => {
+            // Quarter arcs winding inward
+            svg.push_str(&format!("<path d=\"M 4 {} A 8 8 0 0 1 {} 4 A 5 5 0 0 1 {} {}\" stroke=\"#fff\" stroke-width=\"2\" fill=\"none\"/>", center_i, center_i, size_i-6, center_i));
+        },
+        TileType::Funnel => {
+            // Bowl rings around a drain
+            svg.push_str(&format!("<circle cx=\"{}\" cy=\"{}\" r=\"7\" stroke=\"#fff\" stroke-width=\"1\" fill=\"none\"/>", center_i, center_i));
+            svg.push_str(&format!("<circle cx=\"{}\" cy=\"{}\" r=\"4\" stroke=\"#fff\" stroke-width=\"1\" fill=\"none\"/>", center_i, center_i));
+            svg.push_str(&format!("<circle cx=\"{}\" cy=\"{}\" r=\"2\" fill=\"#111\"/>", center_i, center_i));
+        },
+        TileType::ElevatorDrop => {
+            // Downward arrow
+            svg.push_str(&format!("<line x1=\"{}\" y1=\"4\" x2=\"{}\" y2=\"{}\" stroke=\"#fff\" stroke-width=\"2\"/>", center_i, center_i, size_i-6));
+            svg.push_str(&format!("<polygon points=\"{},{} {},{} {},{}\" fill=\"#fff\"/>", center_i-4, size_i-8, center_i+4, size_i-8, center_i, size_i-3));
+        },
         TileType::Empty => {
             // Empty tile - just background
         }
@@ -830,6 +1130,18 @@ fn render_tile_svg_pipe(tile: &MarbleTile, x: usize, y: usize, svg: &mut String)
         TileType::Tunnel => {
             draw_tunnel_pipe(fx, fy, fz, tile.rotation, &color, svg);
         },
+        TileType::Spiral => {
+            draw_spiral(fx, fy, fz, &color, svg);
+        },
+        TileType::ElevatorDrop => {
+            draw_elevator_drop(fx, fy, fz, &color, svg);
+        },
+        TileType::SpiralRamp => {
+            draw_connected_slope_pipe(fx, fy, fz, tile.rotation, &color, svg);
+        },
+        TileType::Funnel => {
+            draw_open_platform_pipe(fx, fy, fz, &color, svg);
+        },
         TileType::Empty => {
             // Empty tiles are handled by the early return
         },
@@ -837,6 +1149,7 @@ fn render_tile_svg_pipe(tile: &MarbleTile, x: usize, y: usize, svg: &mut String)
 }
 
 /// Draw a pipe segment from point A to point B with hollow interior
+#[allow(clippy::too_many_arguments)]
 fn draw_pipe_segment(start_x: f32, start_y: f32, start_z: f32, 
                     end_x: f32, end_y: f32, end_z: f32, 
                     color: &str, svg: &mut String) {
@@ -892,8 +1205,6 @@ fn draw_connected_curve_pipe(fx: f32, fy: f32, fz: f32, rotation: u8, color: &st
     let center_y = fy + 0.5;
     let center_z = fz + 0.1;
 
-    let (cx, cy) = to_isometric(center_x, center_y, center_z);
-
     // Draw the curved pipe using a path with two arcs
     let outer_radius = TILE_WIDTH * PIPE_OUTER_RADIUS;
     let inner_radius = TILE_WIDTH * PIPE_INNER_RADIUS;
@@ -1179,7 +1490,71 @@ fn draw_tunnel_pipe(fx: f32, fy: f32, fz: f32, _rotation: u8, color: &str, svg:
     ));
 }
 
+/// Elevations of the passable marble tiles or the terrain, with how many
+/// tiles sit at each, lowest first
+fn elevation_counts(level: &Level) -> BTreeMap<i32, usize> {
+    let mut counts = BTreeMap::new();
+    if let Some(tiles) = &level.marble_tiles {
+        for tile in tiles.iter().flatten().filter(|t| t.tile_type.is_passable()) {
+            *counts.entry(tile.elevation).or_insert(0) += 1;
+        }
+    } else if let Some(terrain) = &level.terrain {
+        for tile in terrain.iter().flatten() {
+            *counts.entry(tile.elevation).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/// One horizontal bar chart of `rows` (label, count), bars scaled to the
+/// largest count
+fn bar_chart(title: &str, rows: &[(String, usize)]) -> String {
+    let max = rows.iter().map(|&(_, n)| n).max().unwrap_or(0).max(1);
+    let mut html = format!("        <div class=\"stats-chart\"><strong>{}</strong>\n", title);
+    for (label, count) in rows {
+        html.push_str(&format!(
+            "          <div class=\"stats-row\"><span class=\"stats-label\">{}</span><span class=\"stats-bar\" style=\"width: {:.1}%;\"></span><span class=\"stats-count\">{}</span></div>\n",
+            label,
+            *count as f32 * 100.0 / max as f32,
+            count
+        ));
+    }
+    html.push_str("        </div>\n");
+    html
+}
+
+/// Stats panel of the HTML view: headline numbers, the tile type histogram
+/// and the elevation distribution, all from [`LevelStats`] and
+/// [`Level::difficulty`].
+///
+/// [`LevelStats`]: crate::stats::LevelStats
+fn stats_panel(level: &Level) -> String {
+    let stats = &level.stats;
+    let mut html = String::from("    <div class=\"stats\" id=\"stats-panel\">\n      <strong>Level Statistics:</strong>\n");
+    let path = stats.main_path_length.map_or("none".to_string(), |n| format!("{} tiles", n));
+    html.push_str(&format!(
+        "      <div class=\"stats-summary\">Tiles: {} | Floor: {:.0}% | Main path: {} | Difficulty: {:.2}</div>\n",
+        stats.tile_count,
+        stats.floor_ratio * 100.0,
+        path,
+        level.difficulty()
+    ));
+    html.push_str("      <div class=\"stats-charts\">\n");
+    let mut tiles: Vec<(String, usize)> = stats.tile_histogram.iter().filter(|&(name, _)| name != "Empty").map(|(name, &n)| (name.clone(), n)).collect();
+    tiles.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    if !tiles.is_empty() {
+        html.push_str(&bar_chart("Tile types", &tiles));
+    }
+    let elevations: Vec<(String, usize)> = elevation_counts(level).into_iter().rev().map(|(z, n)| (z.to_string(), n)).collect();
+    if !elevations.is_empty() {
+        html.push_str(&bar_chart("Elevation", &elevations));
+    }
+    html.push_str("      </div>\n    </div>\n");
+    html
+}
+
 /// Generate HTML with embedded SVG for isometric visualization
+#[allow(clippy::needless_range_loop)]
 pub fn generate_html(level: &Level) -> String {
     let mut html = String::new();
     
@@ -1194,6 +1569,13 @@ pub fn generate_html(level: &Level) -> String {
     html.push_str("    h1 { color: #fff; text-align: center; }\n");
     html.push_str("    .info { color: #aaa; text-align: center; margin: 10px 0; }\n");
     html.push_str("    svg { background: #0d0d0d; display: block; margin: 20px auto; border: 2px solid #333; }\n");
+    html.push_str("    .stats { color: #fff; background: #2a2a2a; padding: 15px; border-radius: 5px; margin-top: 20px; }\n");
+    html.push_str("    .stats-summary { color: #aaa; margin: 8px 0 12px; }\n");
+    html.push_str("    .stats-charts { display: grid; grid-template-columns: repeat(auto-fit, minmax(300px, 1fr)); gap: 20px; }\n");
+    html.push_str("    .stats-row { display: flex; align-items: center; margin: 3px 0; font-size: 12px; }\n");
+    html.push_str("    .stats-label { width: 110px; color: #aaa; }\n");
+    html.push_str("    .stats-bar { display: inline-block; height: 10px; min-width: 1px; background: #5a9fd4; margin-right: 6px; }\n");
+    html.push_str("    .stats-count { color: #fff; }\n");
     html.push_str("    .legend { color: #fff; background: #2a2a2a; padding: 15px; border-radius: 5px; margin-top: 20px; }\n");
     html.push_str("    .legend-item { display: inline-block; margin: 5px 15px; }\n");
     html.push_str("    .legend-color { display: inline-block; width: 20px; height: 20px; margin-right: 5px; vertical-align: middle; border: 1px solid #555; }\n");
@@ -1234,6 +1616,7 @@ pub fn generate_html(level: &Level) -> String {
     html.push_str("      <label>Visualization Mode:</label>\n");
     html.push_str("      <button id=\"cube-mode-btn\" onclick=\"switchToCubeMode()\" style=\"background: #555; color: #fff;\">Cube Mode</button>\n");
     html.push_str("      <button id=\"pipe-mode-btn\" onclick=\"switchToPipeMode()\" style=\"background: #444; color: #aaa;\">Pipe Mode</button>\n");
+    html.push_str("      <button id=\"splines-btn\" onclick=\"toggleSplines()\" style=\"background: #444; color: #aaa;\">Splines</button>\n");
     html.push_str("    </div>\n");
     html.push_str("    \n");
     html.push_str("    <div class=\"help-text\">\n");
@@ -1243,12 +1626,13 @@ pub fn generate_html(level: &Level) -> String {
     html.push_str("      • <strong>Keyboard:</strong> Arrow keys to pan<br>\n");
     html.push_str("      • <strong>+/-:</strong> Zoom in/out<br>\n");
     html.push_str("      • <strong>R:</strong> Reset view<br>\n");
-    html.push_str("      • <strong>Modes:</strong> Toggle between cube and pipe views\n");
+    html.push_str("      • <strong>Modes:</strong> Toggle between cube and pipe views<br>\n");
+    html.push_str("      • <strong>Splines:</strong> Overlay channel centerlines\n");
     html.push_str("    </div>\n");
     html.push_str("  </div>\n");
     
     html.push_str("  <div class=\"container\">\n");
-    html.push_str(&format!("    <h1>Marble Level Generator - Interactive 3D View</h1>\n"));
+    html.push_str("    <h1>Marble Level Generator - Interactive 3D View</h1>\n");
     html.push_str(&format!("    <div class=\"info\">Seed: {} | Size: {}×{} | Rooms: {}</div>\n", 
         level.seed, level.width, level.height, level.rooms.len()));
     
@@ -1290,14 +1674,33 @@ pub fn generate_html(level: &Level) -> String {
         html.push_str("        <g id=\"cube-layer\" style=\"display: block;\">\n");
         
         // Render cube tiles from back to front (isometric painter's algorithm)
-        for sum in 0..(width + height) {
-            for y in 0..height {
-                let x = sum.saturating_sub(y);
-                if x < width {
-                    render_tile_svg(&marble_tiles[y][x], x, y, &mut html);
+        if let Some(voxels) = &level.voxels {
+            for (x, y, tile) in voxel_draw_order(voxels) {
+                render_tile_svg(tile, x, y, &mut html);
+                if tile.elevation == marble_tiles[y][x].elevation
+                    && level.river_map.as_ref().is_some_and(|m| m[y][x] >= 0)
+                {
+                    draw_river_overlay(x, y, tile.elevation, &mut html);
+                }
+            }
+        } else {
+            for (x, y, layer) in tile_draw_order(marble_tiles) {
+                if layer == DrawLayer::Wall {
+                    draw_cliff_faces(marble_tiles, x, y, &mut html);
+                    continue;
+                }
+                render_tile_svg(&marble_tiles[y][x], x, y, &mut html);
+                if level.river_map.as_ref().is_some_and(|m| m[y][x] >= 0) {
+                    draw_river_overlay(x, y, marble_tiles[y][x].elevation, &mut html);
                 }
             }
         }
+        for funnel in level.structures.iter().filter(|s| s.kind == StructureKind::Funnel) {
+            draw_funnel(funnel, &mut html);
+        }
+        for element in &level.dynamics {
+            draw_dynamic_marker(element, &mut html);
+        }
         
         html.push_str("        </g>\n");
         
@@ -1306,17 +1709,84 @@ pub fn generate_html(level: &Level) -> String {
         html.push_str("        <g id=\"pipe-layer\" style=\"display: none;\">\n");
         
         // Render pipe tiles from back to front (isometric painter's algorithm)
-        for sum in 0..(width + height) {
-            for y in 0..height {
-                let x = sum.saturating_sub(y);
-                if x < width {
+        if let Some(voxels) = &level.voxels {
+            for (x, y, tile) in voxel_draw_order(voxels) {
+                render_tile_svg_pipe(tile, x, y, &mut html);
+            }
+        } else {
+            for (x, y, layer) in tile_draw_order(marble_tiles) {
+                if layer == DrawLayer::Surface {
                     render_tile_svg_pipe(&marble_tiles[y][x], x, y, &mut html);
                 }
             }
         }
+        for funnel in level.structures.iter().filter(|s| s.kind == StructureKind::Funnel) {
+            draw_funnel(funnel, &mut html);
+        }
+        for element in &level.dynamics {
+            draw_dynamic_marker(element, &mut html);
+        }
+        
+        html.push_str("        </g>\n");
         
+        // Channel splines on top of either layer, hidden until toggled
+        html.push_str("        <g id=\"spline-layer\" style=\"display: none;\">\n");
+        for spline in level.channel_splines() {
+            draw_channel_spline(&spline, &mut html);
+        }
         html.push_str("        </g>\n");
         
+        html.push_str("      </g>\n");
+        html.push_str("    </svg>\n");
+        html.push_str("    </div>\n");
+    } else if let Some(terrain) = &level.terrain {
+        let height = terrain.len();
+        let width = if height > 0 { terrain[0].len() } else { 0 };
+        let svg_width = (width as f32 + height as f32) * TILE_WIDTH / 2.0 + 200.0;
+        let svg_height = (width as f32 + height as f32) * TILE_HEIGHT / 4.0 + 400.0;
+        let offset_x = svg_width / 2.0;
+        let offset_y = 150.0;
+
+        html.push_str("    <div class=\"svg-container\" id=\"svg-container\">\n");
+        html.push_str(&format!("    <svg id=\"level-svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+            svg_width, svg_height, svg_width, svg_height));
+        html.push_str(&format!("      <g id=\"level-group\" transform=\"translate({}, {})\">\n", offset_x, offset_y));
+        html.push_str("        <g id=\"cube-layer\" style=\"display: block;\">\n");
+
+        // Render terrain from back to front (isometric painter's algorithm)
+        for sum in 0..(width + height) {
+            for y in 0..height.min(sum + 1) {
+                let x = sum - y;
+                if x < width {
+                    render_terrain_svg(&terrain[y][x], x, y, &mut html);
+                }
+            }
+        }
+
+        html.push_str("        </g>\n");
+        html.push_str("        <g id=\"pipe-layer\" style=\"display: none;\"></g>\n");
+        html.push_str("      </g>\n");
+        html.push_str("    </svg>\n");
+        html.push_str("    </div>\n");
+    } else if level.topology == GridTopology::Hex {
+        let tiles = level.ascii_tiles();
+        let height = tiles.len();
+        let width = tiles.first().map_or(0, |r| r.chars().count());
+        let svg_width = (width as f32 + 0.5) * HEX_WIDTH + 40.0;
+        let svg_height = (height as f32 * 0.75 + 0.25) * HEX_SIZE * 2.0 + 40.0;
+
+        html.push_str("    <div class=\"svg-container\" id=\"svg-container\">\n");
+        html.push_str(&format!("    <svg id=\"level-svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+            svg_width, svg_height, svg_width, svg_height));
+        html.push_str("      <g id=\"level-group\" transform=\"translate(20, 20)\">\n");
+        html.push_str("        <g id=\"cube-layer\" style=\"display: block;\">\n");
+        for (y, row) in tiles.iter().enumerate() {
+            for (x, ch) in row.chars().enumerate() {
+                render_hex_svg(x, y, ch, &mut html);
+            }
+        }
+        html.push_str("        </g>\n");
+        html.push_str("        <g id=\"pipe-layer\" style=\"display: none;\"></g>\n");
         html.push_str("      </g>\n");
         html.push_str("    </svg>\n");
         html.push_str("    </div>\n");
@@ -1324,6 +1794,8 @@ pub fn generate_html(level: &Level) -> String {
         html.push_str("    <p style=\"color: #fff; text-align: center;\">No marble tile data available. Use --mode marble to generate.</p>\n");
     }
     
+    html.push_str(&stats_panel(level));
+
     // Legend with actual tile representations
     html.push_str("    <div class=\"legend\">\n");
     html.push_str("      <strong>Legend - Tile Representations:</strong><br>\n");
@@ -1350,6 +1822,8 @@ pub fn generate_html(level: &Level) -> String {
     html.push_str("        <div style=\"border: 1px solid #444; padding: 12px; border-radius: 6px;\">\n");
     html.push_str("          <strong style=\"color: #fff; margin-bottom: 10px; display: block;\">Elevation & Movement:</strong>\n");
     html.push_str(&format!("          <div class=\"legend-item\">{}<span style=\"color: #fff; margin-left: 8px;\">Slope ⛰</span></div>\n", generate_legend_tile_svg(&TileType::Slope)));
+    html.push_str(&format!("          <div class=\"legend-item\">{}<span style=\"color: #fff; margin-left: 8px;\">Spiral Ramp ⤵</span></div>\n", generate_legend_tile_svg(&TileType::SpiralRamp)));
+    html.push_str(&format!("          <div class=\"legend-item\">{}<span style=\"color: #fff; margin-left: 8px;\">Funnel</span></div>\n", generate_legend_tile_svg(&TileType::Funnel)));
     html.push_str(&format!("          <div class=\"legend-item\">{}<span style=\"color: #fff; margin-left: 8px;\">Half-Pipe ∪</span></div>\n", generate_legend_tile_svg(&TileType::HalfPipe)));
     html.push_str(&format!("          <div class=\"legend-item\">{}<span style=\"color: #fff; margin-left: 8px;\">Loop-de-Loop ∞</span></div>\n", generate_legend_tile_svg(&TileType::LoopDeLoop)));
     html.push_str(&format!("          <div class=\"legend-item\">{}<span style=\"color: #fff; margin-left: 8px;\">Launch Pad ⚡</span></div>\n", generate_legend_tile_svg(&TileType::LaunchPad)));
@@ -1362,6 +1836,8 @@ pub fn generate_html(level: &Level) -> String {
     html.push_str(&format!("          <div class=\"legend-item\">{}<span style=\"color: #fff; margin-left: 8px;\">Obstacle</span></div>\n", generate_legend_tile_svg(&TileType::Obstacle)));
     html.push_str(&format!("          <div class=\"legend-item\">{}<span style=\"color: #fff; margin-left: 8px;\">Bridge 🌉</span></div>\n", generate_legend_tile_svg(&TileType::Bridge)));
     html.push_str(&format!("          <div class=\"legend-item\">{}<span style=\"color: #fff; margin-left: 8px;\">Tunnel 🚇</span></div>\n", generate_legend_tile_svg(&TileType::Tunnel)));
+    html.push_str(&format!("          <div class=\"legend-item\">{}<span style=\"color: #fff; margin-left: 8px;\">Spiral 🌀</span></div>\n", generate_legend_tile_svg(&TileType::Spiral)));
+    html.push_str(&format!("          <div class=\"legend-item\">{}<span style=\"color: #fff; margin-left: 8px;\">Elevator Drop ↓</span></div>\n", generate_legend_tile_svg(&TileType::ElevatorDrop)));
     html.push_str("        </div>\n");
     
     html.push_str("      </div>\n");
@@ -1441,6 +1917,15 @@ pub fn generate_html(level: &Level) -> String {
     html.push_str("      document.getElementById('cube-mode-btn').style.color = '#aaa';\n");
     html.push_str("    }\n");
     html.push_str("    \n");
+    html.push_str("    function toggleSplines() {\n");
+    html.push_str("      const layer = document.getElementById('spline-layer');\n");
+    html.push_str("      if (!layer) return;\n");
+    html.push_str("      const shown = layer.style.display === 'none';\n");
+    html.push_str("      layer.style.display = shown ? 'block' : 'none';\n");
+    html.push_str("      document.getElementById('splines-btn').style.background = shown ? '#555' : '#444';\n");
+    html.push_str("      document.getElementById('splines-btn').style.color = shown ? '#fff' : '#aaa';\n");
+    html.push_str("    }\n");
+    html.push_str("    \n");
     html.push_str("    // Mouse controls\n");
     html.push_str("    container.addEventListener('mousedown', (e) => {\n");
     html.push_str("      isDragging = true;\n");
@@ -1545,13 +2030,52 @@ mod tests {
         assert_eq!(y, TILE_HEIGHT / 4.0);
     }
 
+    #[test]
+    fn tiles_draw_back_to_front_and_bottom_to_top() {
+        let mut tiles = vec![vec![MarbleTile::with_params(TileType::OpenPlatform, 0, 0, false); 3]; 2];
+        tiles[1][0].elevation = 4;
+        tiles[0][2].tile_type = TileType::Empty;
+        let order = tile_draw_order(&tiles);
+        assert_eq!(order.len(), 10);
+        let position = |x: usize, y: usize, layer: DrawLayer| order.iter().position(|&item| item == (x, y, layer)).unwrap();
+        // The raised tile comes after the low tile on its diagonal, its cliff before its surface
+        assert!(position(1, 0, DrawLayer::Surface) < position(0, 1, DrawLayer::Wall));
+        assert!(position(0, 1, DrawLayer::Wall) < position(0, 1, DrawLayer::Surface));
+        assert!(order.windows(2).all(|pair| pair[0].0 + pair[0].1 <= pair[1].0 + pair[1].1));
+
+        // Only the raised tile has a lower neighbor in front: its east side
+        let mut svg = String::new();
+        draw_cliff_faces(&tiles, 0, 1, &mut svg);
+        assert_eq!(svg.matches("<polygon").count(), 1);
+        svg.clear();
+        draw_cliff_faces(&tiles, 0, 0, &mut svg);
+        assert!(svg.is_empty());
+    }
+
+    #[test]
+    fn html_embeds_a_stats_panel() {
+        use crate::dungeon::{generate, GenerationMode, GeneratorParams};
+        let params = GeneratorParams { seed: Some(3), mode: GenerationMode::Marble, enable_elevation: true, ..Default::default() };
+        let level = generate(&params);
+        let html = generate_html(&level);
+        assert!(html.contains("id=\"stats-panel\""));
+        assert!(html.contains(&format!("Difficulty: {:.2}", level.difficulty())));
+        for (name, count) in level.stats.tile_histogram.iter().filter(|&(name, _)| name != "Empty") {
+            assert!(html.contains(&format!("<span class=\"stats-label\">{}</span>", name)));
+            assert!(html.contains(&format!("<span class=\"stats-count\">{}</span>", count)));
+        }
+        // One elevation bar per level the passable tiles sit on
+        let rows = html.matches("class=\"stats-row\"").count();
+        assert_eq!(rows, level.stats.tile_histogram.len() - 1 + elevation_counts(&level).len());
+    }
+
     #[test]
     fn test_color_adjustment() {
         let base = "#808080";
         let elevated = adjust_color_for_elevation(base, 1);
         assert_ne!(base, elevated);
         // Higher elevation should be brighter
-        assert!(elevated > base.to_string());
+        assert!(elevated.as_str() > base);
     }
 }
 
@@ -0,0 +1,119 @@
+//! Scalar per-tile layers for spreadsheets, image tools, and ML pipelines.
+//!
+//! [`Level::layer`] extracts one number per tile, row-major, with `None`
+//! where the layer has no value:
+//!
+//! - [`Layer::Elevation`]: marble tile elevations (empty tiles have none),
+//!   overworld terrain heights, or 0 on the walkable tiles of flat modes
+//! - [`Layer::DistanceFromStart`]: walking distance from the entrance room
+//!   center, as used by distance-band zoning (unreachable tiles have none)
+//! - [`Layer::ObstacleMask`]: 1 on obstacle tiles, 0 everywhere else
+//!
+//! [`layer_to_csv`] and [`layer_to_pgm`] write a layer as CSV text or as a
+//! binary greyscale PGM image.
+
+use crate::dungeon::{is_walkable_char, Level};
+use crate::tiles::TileType;
+use crate::zones::{bfs, start_tile};
+
+/// A scalar layer of a level; see the module docs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    Elevation,
+    DistanceFromStart,
+    ObstacleMask,
+}
+
+impl Level {
+    /// Per-tile values of `layer`, indexed `[y][x]`.
+    pub fn layer(&self, layer: Layer) -> Vec<Vec<Option<i32>>> {
+        let grid: Vec<Vec<char>> = self.ascii_tiles().iter().map(|row| row.chars().collect()).collect();
+        match layer {
+            Layer::Elevation => match (&self.marble_tiles, &self.terrain) {
+                (Some(tiles), _) => tiles.iter().map(|row| row.iter().map(|t| (t.tile_type != TileType::Empty).then_some(t.elevation)).collect()).collect(),
+                (None, Some(terrain)) => terrain.iter().map(|row| row.iter().map(|t| Some(t.elevation)).collect()).collect(),
+                (None, None) => grid.iter().map(|row| row.iter().map(|&c| is_walkable_char(c).then_some(0)).collect()).collect(),
+            },
+            Layer::DistanceFromStart => match start_tile(&grid, &self.rooms) {
+                Some(start) => {
+                    let (distance, _) = bfs(&grid, &[(start, 0)], self.topology);
+                    distance.iter().map(|row| row.iter().map(|d| d.map(|d| d as i32)).collect()).collect()
+                }
+                None => grid.iter().map(|row| vec![None; row.len()]).collect(),
+            },
+            Layer::ObstacleMask => {
+                let mut mask: Vec<Vec<Option<i32>>> = grid.iter().map(|row| vec![Some(0); row.len()]).collect();
+                for &(x, y) in self.obstacles.iter().flat_map(|o| &o.tiles) {
+                    if let Some(cell) = mask.get_mut(y as usize).and_then(|row| row.get_mut(x as usize)) {
+                        *cell = Some(1);
+                    }
+                }
+                mask
+            }
+        }
+    }
+}
+
+/// One line per row, values separated by commas; tiles without a value are
+/// left blank.
+pub fn layer_to_csv(values: &[Vec<Option<i32>>]) -> String {
+    values
+        .iter()
+        .map(|row| row.iter().map(|v| v.map_or(String::new(), |v| v.to_string())).collect::<Vec<_>>().join(",") + "\n")
+        .collect()
+}
+
+/// Binary greyscale PGM (`P5`). Values are scaled linearly from their
+/// minimum to their maximum onto 1-255; tiles without a value are black (0).
+pub fn layer_to_pgm(values: &[Vec<Option<i32>>]) -> Vec<u8> {
+    let (height, width) = (values.len(), values.first().map_or(0, Vec::len));
+    let present = values.iter().flatten().flatten();
+    let (min, max) = (present.clone().min().copied().unwrap_or(0), present.max().copied().unwrap_or(0));
+    let span = (max - min).max(1) as i64;
+    let mut out = format!("P5\n{} {}\n255\n", width, height).into_bytes();
+    out.extend(values.iter().flatten().map(|v| match v {
+        Some(v) if max > min => (1 + (*v - min) as i64 * 254 / span) as u8,
+        Some(_) => 255,
+        None => 0,
+    }));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams};
+
+    #[test]
+    fn layers_export_as_csv_and_pgm() {
+        let params = GeneratorParams {
+            seed: Some(5),
+            mode: GenerationMode::Marble,
+            enable_elevation: true,
+            enable_obstacles: true,
+            obstacle_density: 0.6,
+            ..Default::default()
+        };
+        let level = generate(&params);
+        let (w, h) = (level.width as usize, level.height as usize);
+
+        let distance = level.layer(Layer::DistanceFromStart);
+        assert_eq!(distance.iter().flatten().flatten().min(), Some(&0));
+        let obstacles = level.layer(Layer::ObstacleMask);
+        let marked = obstacles.iter().flatten().filter(|v| **v == Some(1)).count();
+        assert_eq!(marked, level.obstacles.iter().map(|o| o.tiles.len()).sum::<usize>());
+        assert!(marked > 0);
+
+        let elevation = level.layer(Layer::Elevation);
+        let csv = layer_to_csv(&elevation);
+        assert_eq!(csv.lines().count(), h);
+        assert!(csv.lines().all(|line| line.split(',').count() == w));
+        assert!(csv.lines().next().unwrap().starts_with(",,"));
+
+        let pgm = layer_to_pgm(&distance);
+        let header = format!("P5\n{} {}\n255\n", w, h);
+        assert!(pgm.starts_with(header.as_bytes()));
+        assert_eq!(pgm.len(), header.len() + w * h);
+        assert_eq!(layer_to_pgm(&[vec![Some(3), None, Some(7)]])[11..], [1, 0, 255]);
+    }
+}
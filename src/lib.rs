@@ -32,6 +32,21 @@
 //! - **Classic**: Traditional roguelike dungeons with rooms and corridors
 //! - **Marble**: Wide channels with curves, elevation, slopes, and obstacles for marble games
 //! - **WFC**: Wave Function Collapse algorithm for connected mazes
+//! - **Overworld**: Open heightmap terrain with points of interest joined by winding paths
+//! - **Settlement**: Towns with a road network, buildings along the roads, and plazas
+//! - **Room WFC**: Room archetypes collapsed on a coarse grid, then realized as rooms and corridors
+//!
+//! ## API Organization
+//!
+//! - [`prelude`]: `use level_generator::prelude::*;` for the common types
+//! - [`gen`]: generation entry points, parameters, and generators
+//! - [`tiles`]: tile types, tile characters, and marble tile conversion
+//! - [`render`]: ASCII and isometric HTML output
+//! - [`export`]: JSON saving and loading, snapshots, voxels
+//! - [`analysis`]: invariants, statistics, diffs, graphs, and visibility
+//!
+//! The implementation modules (`dungeon`, `marble`, `obstacles`, ...) stay
+//! public for the less common items.
 //!
 //! ## Features
 //!
@@ -40,19 +55,221 @@
 //! - Isometric HTML/SVG visualization
 //! - 16+ tile types for complex marble tracks
 //! - Elevation system with automatic slope generation
+//! - Generation statistics attached to every `Level`
+//!
+//! ## `no_std`
+//!
+//! Everything but the core needs the default `std` feature. Without it the
+//! crate is `no_std` with `alloc` and offers [`core_gen`] (rooms, corridors,
+//! and marble tiles on a grid) with the tile types of [`tiles`] and the
+//! seed streams of [`seeds`]:
+//!
+//! ```toml
+//! level-generator = { version = "0.1", default-features = false }
+//! ```
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
 #[cfg(feature = "cli")]
 pub mod cli;
 
+// Curated namespaces over the implementation modules
+#[cfg(feature = "std")]
+pub mod analysis;
+#[cfg(feature = "std")]
+pub mod export;
+#[cfg(feature = "std")]
+pub mod gen;
+#[cfg(feature = "std")]
+pub mod prelude;
+#[cfg(feature = "std")]
+pub mod render;
+
+#[cfg(feature = "std")]
+pub mod acceptance;
+#[cfg(feature = "std")]
+pub mod allowed_tiles;
+#[cfg(feature = "std")]
+pub mod ambience;
+#[cfg(feature = "std")]
+pub mod anchors;
+#[cfg(feature = "std")]
+pub mod banking;
+#[cfg(feature = "std")]
+pub mod budget;
+#[cfg(feature = "std")]
+pub mod campaign;
+#[cfg(feature = "std")]
+pub mod catalog;
+#[cfg(feature = "std")]
+pub mod ceilings;
+pub mod core_gen;
+#[cfg(feature = "std")]
+pub mod dead_ends;
+#[cfg(feature = "std")]
+pub mod diff;
+#[cfg(feature = "std")]
+pub mod difficulty;
+#[cfg(feature = "std")]
 pub mod dungeon;
+#[cfg(feature = "std")]
+pub mod duration;
+#[cfg(feature = "std")]
+pub mod dynamics;
+#[cfg(feature = "std")]
+pub mod editing;
+#[cfg(feature = "std")]
+pub mod embed;
+#[cfg(feature = "capi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod fingerprint;
+#[cfg(feature = "std")]
+pub mod fuzz;
+#[cfg(feature = "std")]
+pub mod gallery;
+#[cfg(feature = "std")]
+pub mod gates;
+#[cfg(feature = "std")]
+pub mod generator;
+#[cfg(feature = "std")]
+pub mod graph;
+#[cfg(feature = "std")]
+pub mod hex;
+#[cfg(feature = "std")]
+pub mod hybrid;
+#[cfg(feature = "std")]
+pub mod import;
+#[cfg(feature = "std")]
+pub mod integrity;
+#[cfg(feature = "std")]
+pub mod invariants;
+#[cfg(feature = "std")]
 pub mod isometric;
+#[cfg(feature = "std")]
+pub mod layers;
+#[cfg(feature = "std")]
+pub mod marble;
+#[cfg(feature = "std")]
+pub mod memory;
+#[cfg(feature = "std")]
+pub mod minimap;
+#[cfg(feature = "std")]
+pub mod mission;
+#[cfg(feature = "std")]
+pub mod naming;
+#[cfg(feature = "std")]
+pub mod obstacles;
+#[cfg(feature = "std")]
+pub mod openings;
+#[cfg(feature = "std")]
+pub mod overworld;
+#[cfg(feature = "std")]
+pub mod path_graph;
+#[cfg(feature = "std")]
+pub mod ramp;
+#[cfg(feature = "std")]
+pub mod regenerate;
+#[cfg(feature = "std")]
+pub mod rivers;
+#[cfg(feature = "std")]
+pub mod roles;
+#[cfg(feature = "std")]
+pub mod room_wfc;
+#[cfg(feature = "std")]
+pub mod routes;
+#[cfg(feature = "std")]
+pub mod scratch;
+#[cfg(feature = "std")]
+pub mod secrets;
+#[cfg(feature = "std")]
+pub mod sectors;
+pub mod seeds;
+#[cfg(feature = "std")]
+pub mod settlement;
+#[cfg(feature = "std")]
+pub mod splines;
+#[cfg(feature = "std")]
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod stitch;
+#[cfg(feature = "std")]
+pub mod structures;
+#[cfg(feature = "std")]
+pub mod testing;
+#[cfg(feature = "std")]
+pub mod tile_budget;
 pub mod tiles;
+#[cfg(feature = "std")]
+pub mod transform;
+#[cfg(feature = "std")]
+pub mod traffic;
+#[cfg(feature = "std")]
+pub mod validate;
+#[cfg(feature = "std")]
+pub mod vaults;
+#[cfg(feature = "std")]
+pub mod versions;
+#[cfg(feature = "std")]
+pub mod visibility;
+#[cfg(feature = "std")]
 pub mod visualize;
+#[cfg(feature = "std")]
+pub mod voxel;
+#[cfg(feature = "std")]
+pub mod wfc_region;
+#[cfg(feature = "std")]
+pub mod zones;
 
 // Re-export commonly used types for convenience
-pub use dungeon::{generate, GenerationMode, GeneratorParams, Level, Room};
-pub use tiles::{Direction, MarbleTile, TileType};
+#[cfg(feature = "std")]
+pub use budget::{try_generate, BudgetExceeded, CancelToken};
+#[cfg(feature = "std")]
+pub use diff::LevelDiff;
+#[cfg(feature = "std")]
+pub use dungeon::{generate, generate_with_scratch, GenerationMode, GeneratorParams, Level, Room, RoomSizeDistribution};
+#[cfg(feature = "std")]
+pub use dynamics::{DynamicElement, DynamicKind};
+#[cfg(feature = "std")]
+pub use editing::{Prefab, Rect};
+#[cfg(feature = "std")]
+pub use generator::{generate_with, LevelGenerator};
+#[cfg(feature = "std")]
+pub use hybrid::HybridGenerator;
+pub use tiles::{Diag, Direction, HexDirection, MarbleTile, TileType};
+#[cfg(feature = "std")]
+pub use graph::ConnectionStrategy;
+#[cfg(feature = "std")]
+pub use hex::GridTopology;
+#[cfg(feature = "std")]
+pub use invariants::InvariantViolation;
+#[cfg(feature = "std")]
 pub use isometric::generate_html;
+#[cfg(feature = "std")]
+pub use obstacles::{Obstacle, ObstaclePattern, ObstacleShape};
+#[cfg(feature = "std")]
+pub use overworld::{TerrainTile, TerrainType};
+#[cfg(feature = "std")]
+pub use settlement::RoadLayout;
+#[cfg(feature = "std")]
+pub use stitch::StitchStrategy;
+#[cfg(feature = "std")]
+pub use roles::RoomRole;
+#[cfg(feature = "std")]
+pub use scratch::GeneratorScratch;
+#[cfg(feature = "std")]
+pub use stats::LevelStats;
+#[cfg(feature = "std")]
+pub use structures::{Structure, StructureKind};
+#[cfg(feature = "std")]
+pub use visibility::{field_of_view, line_of_sight};
+#[cfg(feature = "std")]
 pub use visualize::to_ascii;
+#[cfg(feature = "std")]
+pub use voxel::VoxelMap;
+#[cfg(feature = "std")]
+pub use zones::{Zone, ZoneStrategy};
 
 
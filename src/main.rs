@@ -3,17 +3,49 @@ compile_error!("The binary requires the 'cli' feature. Run with: cargo build --f
 
 use clap::Parser;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use level_generator::cli::Args;
+use level_generator::cli::Command;
+use level_generator::cli::ConnectionArg;
+use level_generator::cli::EndpointsArg;
+use level_generator::cli::FormatArg;
+use level_generator::cli::LayerArg;
 use level_generator::cli::ModeArg;
-use level_generator::dungeon::{generate, GenerationMode, GeneratorParams};
-use level_generator::isometric;
-use level_generator::visualize::to_ascii;
+use level_generator::cli::NameThemeArg;
+use level_generator::cli::{ObstaclePatternArg, ObstacleShapeArg};
+use level_generator::cli::RoadLayoutArg;
+use level_generator::cli::RoomSizesArg;
+use level_generator::cli::TopologyArg;
+use level_generator::cli::VaultGuardArg;
+use level_generator::cli::ZoningArg;
+use level_generator::export::{self, layer_to_csv, layer_to_pgm, ExportOptions, Format, Layer};
+use level_generator::gen::{
+    generate, generate_matching, generate_with, Campaign, CeilingConfig, ConnectionStrategy, Date, EndpointPlacement, GenerationMode, GeneratorParams, GridTopology, HybridGenerator, Level, MissionConfig, NameTheme, ObstaclePattern, ObstacleShape,
+    RoadLayout, RoomSizeDistribution, Seed, VaultGuard, ZoneStrategy,
+};
+use level_generator::analysis::TrafficConfig;
+use level_generator::Rect;
+use level_generator::TileType;
+use level_generator::render::{gallery_html, generate_html, to_ascii, to_ascii_elevation};
 
 fn main() {
     let args = Args::parse();
 
+    if let Some(Command::Diff { before, after, html_path }) = &args.command {
+        run_diff(before, after, html_path.as_deref());
+        return;
+    }
+    if let Some(Command::Verify { paths }) = &args.command {
+        run_verify(paths);
+        return;
+    }
+    if let Some(Command::TileCatalog { json_path }) = &args.command {
+        run_tile_catalog(json_path.as_deref());
+        return;
+    }
+
     // Build trend vector if all components are provided
     let trend_vector = match (args.trend_x, args.trend_y, args.trend_z) {
         (Some(x), Some(y), Some(z)) => Some((x, y, z)),
@@ -32,37 +64,227 @@ fn main() {
         rooms: args.rooms,
         min_room: args.min_room,
         max_room: args.max_room,
-        seed: args.seed,
-        mode: match args.mode {
-            ModeArg::Classic => GenerationMode::Classic,
-            ModeArg::Marble => GenerationMode::Marble,
-            ModeArg::Wfc => GenerationMode::Wfc,
+        room_size_distribution: match args.room_sizes {
+            RoomSizesArg::Uniform => RoomSizeDistribution::Uniform,
+            RoomSizesArg::Normal => RoomSizeDistribution::Normal,
+            RoomSizesArg::Bimodal => RoomSizeDistribution::Bimodal,
         },
+        guaranteed_large_rooms: args.large_rooms,
+        room_attempts: args.room_attempts,
+        relax_room_placement: args.relax_rooms,
+        border: args.border,
+        skirt: args.skirt,
+        seed: args.daily.map(|date| Seed::daily(date.unwrap_or_else(Date::today), &args.daily_salt).into()).or(args.seed),
+        mode: generation_mode(args.mode),
         channel_width: args.channel_width,
         corner_radius: args.corner_radius,
+        channel_curvature: args.channel_curvature,
         enable_elevation: args.enable_elevation,
         max_elevation: args.max_elevation,
         enable_obstacles: args.enable_obstacles,
         obstacle_density: args.obstacle_density,
+        difficulty_ramp: args.difficulty_ramp,
+        obstacle_shapes: args
+            .obstacle_shapes
+            .iter()
+            .map(|shape| match shape {
+                ObstacleShapeArg::Pillar => ObstacleShape::Pillar,
+                ObstacleShapeArg::Block => ObstacleShape::Block,
+                ObstacleShapeArg::Wall => ObstacleShape::WallSegment,
+                ObstacleShapeArg::Bumpers => ObstacleShape::BumperCluster,
+            })
+            .collect(),
+        obstacle_patterns: args
+            .obstacle_patterns
+            .iter()
+            .map(|pattern| match pattern {
+                ObstaclePatternArg::Scatter => ObstaclePattern::Scatter,
+                ObstaclePatternArg::Slalom => ObstaclePattern::Slalom,
+                ObstaclePatternArg::Pinball => ObstaclePattern::Pinball,
+                ObstaclePatternArg::Gauntlet => ObstaclePattern::Gauntlet,
+            })
+            .collect(),
+        obstacle_clearance: args.obstacle_clearance,
+        obstacle_spacing: args.obstacle_spacing,
+        obstacle_doorway_radius: args.obstacle_doorway_radius,
         trend_vector,
         trend_strength: args.trend_strength,
         start_point,
+        endpoints: args.endpoints.map(|rule| match rule {
+            EndpointsArg::First => EndpointPlacement::FirstRoom,
+            EndpointsArg::Farthest => EndpointPlacement::FarthestApart,
+            EndpointsArg::Elevation => EndpointPlacement::ElevationExtremes,
+        }),
         max_elevation_change: args.max_elevation_change,
+        enable_rivers: args.enable_rivers,
+        river_count: args.river_count,
+        enable_spiral_ramps: args.enable_spiral_ramps,
+        enable_funnels: args.enable_funnels,
+        max_launch_pads: args.max_launch_pads,
+        open_bowl_rooms: args.open_bowl_rooms,
+        enable_y_junctions: !args.no_y_junctions,
+        enable_dynamics: args.enable_dynamics,
+        dynamic_count: args.dynamic_count,
+        enable_voxels: args.enable_voxels,
+        voxel_bridges: args.voxel_bridges,
+        enable_banking: args.enable_banking,
+        zoning: match args.zoning {
+            ZoningArg::None => ZoneStrategy::None,
+            ZoningArg::Rooms => ZoneStrategy::RoomClusters { zones: args.zone_count },
+            ZoningArg::Bands => ZoneStrategy::DistanceBands { band_width: args.zone_band_width },
+        },
+        topology: match args.topology {
+            TopologyArg::Square => GridTopology::Square,
+            TopologyArg::Hex => GridTopology::Hex,
+            TopologyArg::Octile => GridTopology::Octile,
+        },
+        connection_strategy: match args.connections {
+            ConnectionArg::Chain => ConnectionStrategy::Chain,
+            ConnectionArg::Mst => ConnectionStrategy::MinimumSpanningTree { loop_fraction: args.loop_fraction },
+        },
+        dead_end_removal: args.dead_end_removal,
+        secret_passages: args.secret_passages,
+        vaults: args.vaults,
+        vault_guard: args.vault_guard.map(|guard| match guard {
+            VaultGuardArg::Door => VaultGuard::Door,
+            VaultGuardArg::Obstacle => VaultGuard::Obstacle,
+        }),
+        side_routes: args.side_routes,
+        min_alternate_routes: args.min_alternate_routes,
+        road_layout: match args.road_layout {
+            RoadLayoutArg::Grid => RoadLayout::Grid,
+            RoadLayoutArg::Organic => RoadLayout::Organic,
+        },
+        plazas: args.plazas,
+        min_path_length: args.min_path_length,
+        path_retries: args.path_retries,
+        target_floor_ratio: args.target_floor_ratio,
+        floor_ratio_tolerance: args.floor_ratio_tolerance,
+        max_total_tiles: args.max_total_tiles,
+        budget_retries: args.budget_retries,
+        naming: args.names.map(|theme| match theme {
+            NameThemeArg::Dungeon => NameTheme::Dungeon,
+            NameThemeArg::Cavern => NameTheme::Cavern,
+            NameThemeArg::Ruins => NameTheme::Ruins,
+            NameThemeArg::Town => NameTheme::Town,
+            NameThemeArg::Clockwork => NameTheme::Clockwork,
+        }),
+        ceilings: args.ceilings.map(|range| CeilingConfig { min: range.min, max: range.max, corridor: args.corridor_ceiling }),
+        mission: args.mission.map(|locks| MissionConfig { locks, challenges: args.mission_challenges }),
+        algorithm_version: args.algorithm_version,
+        // --allow-tiles (every type when absent) minus --exclude-tiles
+        allowed_tile_types: (!args.allow_tiles.is_empty() || !args.exclude_tiles.is_empty()).then(|| {
+            let allowed: Vec<TileType> = if args.allow_tiles.is_empty() { TileType::ALL.to_vec() } else { args.allow_tiles.iter().map(|t| t.0).collect() };
+            allowed.into_iter().filter(|t| !args.exclude_tiles.iter().any(|e| e.0 == *t)).collect()
+        }),
+        keep_ascii_tiles: true,
+        edge_openings: args.edge_openings.iter().map(|o| o.0).collect(),
+        time_budget: args.time_budget.map(Duration::from_millis),
+        cancel: None,
     };
 
-    let level = generate(&params);
+    if let Some(Command::Campaign { levels, candidates, out_dir }) = &args.command {
+        run_campaign(&params, *levels, *candidates, out_dir.as_deref());
+        return;
+    }
+    if let Some(Command::Gallery { seeds, out }) = &args.command {
+        run_gallery(&params, seeds.start..seeds.end, out);
+        return;
+    }
+
+    let level = match &args.import {
+        Some(path) => import_level(path),
+        None if !args.regions.is_empty() => {
+            let regions = args.regions.iter().map(|r| (Rect::new(r.x, r.y, r.w, r.h), generation_mode(r.mode))).collect();
+            generate_with(&HybridGenerator::new(regions), &params)
+        }
+        None if !args.accept.is_empty() => generate_matching(&params, &args.accept, args.accept_attempts).unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            std::process::exit(1);
+        }),
+        None => generate(&params),
+    };
 
-    // ASCII output
+    // ASCII output, under the level's name when it has one
     if !args.no_ascii && !args.html_only {
-        let ascii = to_ascii(&level);
+        if let Some(name) = &level.name {
+            println!("{}", name);
+        }
+        let ascii = if args.ascii_elevation { to_ascii_elevation(&level) } else { to_ascii(&level) };
         println!("{}", ascii);
     }
 
-    // JSON output
-    if !args.html_only {
-        let json = serde_json::to_string_pretty(&level).expect("serialize level");
+    // Room targets apply to classic and marble mode only
+    let uses_rooms = matches!(params.mode, GenerationMode::Classic | GenerationMode::Marble) && args.regions.is_empty() && args.import.is_none();
+    if uses_rooms && level.stats.rooms_placed < level.stats.rooms_requested {
+        eprintln!(
+            "warning: placed {} of {} requested rooms ({:.0}%); try a larger map, fewer or smaller rooms, --room-attempts, or --relax-rooms",
+            level.stats.rooms_placed,
+            level.stats.rooms_requested,
+            level.stats.room_fill * 100.0
+        );
+    }
+
+    if let Some(min) = params.min_path_length.filter(|&min| level.stats.main_path_length.unwrap_or(0) < min) {
+        eprintln!(
+            "warning: no level in {} attempts has a main path of {} tiles (longest: {}); try a larger map, more rooms, or --path-retries",
+            level.stats.attempts,
+            min,
+            level.stats.main_path_length.map_or("none".to_string(), |l| l.to_string())
+        );
+    }
+
+    if level.stats.tile_budget_met == Some(false) {
+        eprintln!(
+            "warning: no level within the tile budget (closest: {} tiles, {:.1}% floor); try another map size or --budget-retries",
+            level.stats.tile_count,
+            level.stats.floor_ratio * 100.0
+        );
+    }
+
+    if args.algorithm_version.is_some_and(|version| version != level.algorithm_version) && args.import.is_none() {
+        eprintln!("warning: algorithm version {} is not known to this build; generated with version {}", args.algorithm_version.unwrap_or_default(), level.algorithm_version);
+    }
+
+    if level.stats.budget_exceeded {
+        eprintln!("warning: time budget exceeded; the level is best-effort and skips optional stages");
+    }
+
+    // Generation statistics
+    if args.stats {
+        println!("{}", level.stats);
+    }
+    if args.bench_profile {
+        eprintln!("{}", level.stats.stage_profile());
+    }
+    if let Some(marbles) = args.traffic {
+        match level.simulate_traffic(&TrafficConfig { marbles, batch: args.traffic_batch, release_interval: args.traffic_interval, ..Default::default() }) {
+            Some(report) => println!("{}", report),
+            None => eprintln!("warning: --traffic needs an exit room reachable from the entrance"),
+        }
+    }
+
+    // Level output as JSON, RON, or YAML
+    if !args.html_only && (args.print_json || args.json_path.is_some()) {
+        let format = match args.format {
+            FormatArg::Json => Format::Json,
+            FormatArg::Ron => Format::Ron,
+            FormatArg::Yaml => Format::Yaml,
+        };
+        if matches!(format, Format::Ron) && args.tile_connections {
+            eprintln!("warning: --tile-connections only applies to JSON and YAML output");
+        }
+        if matches!(format, Format::Ron) && (args.minimap.is_some() || args.ambience || args.splines || args.tiles_rle) {
+            eprintln!("warning: --minimap, --ambience, --splines, and --tiles-rle only apply to JSON and YAML output");
+        }
+        let options = ExportOptions { tile_connections: args.tile_connections, minimap: args.minimap, ambience: args.ambience, splines: args.splines, tiles_rle: args.tiles_rle };
+        let Some(text) = export::to_format(&level, format, &options) else {
+            let name = format!("{:?}", format).to_ascii_lowercase();
+            eprintln!("error: {} output needs the `{}` feature (cargo build --features {})", name, name, name);
+            std::process::exit(2);
+        };
         if args.print_json {
-            println!("{}", json);
+            println!("{}", text);
         }
         if let Some(path) = args.json_path.as_ref() {
             let p: &Path = path.as_path();
@@ -71,13 +293,33 @@ fn main() {
                     let _ = fs::create_dir_all(parent);
                 }
             }
-            fs::write(p, json).expect("write json file");
+            fs::write(p, text).expect("write level file");
+        }
+    }
+
+    // Scalar layers as CSV or PGM
+    for output in &args.layers {
+        let values = level.layer(match output.layer {
+            LayerArg::Elevation => Layer::Elevation,
+            LayerArg::Distance => Layer::DistanceFromStart,
+            LayerArg::Obstacles => Layer::ObstacleMask,
+        });
+        let p: &Path = output.path.as_path();
+        if let Some(parent) = p.parent() {
+            if !parent.as_os_str().is_empty() {
+                let _ = fs::create_dir_all(parent);
+            }
         }
+        let bytes = match p.extension().and_then(|e| e.to_str()) {
+            Some("pgm") => layer_to_pgm(&values),
+            _ => layer_to_csv(&values).into_bytes(),
+        };
+        fs::write(p, bytes).expect("write layer file");
     }
 
     // HTML isometric visualization
     if let Some(html_path) = args.html_path.as_ref() {
-        let html = isometric::generate_html(&level);
+        let html = generate_html(&level);
         let p: &Path = html_path.as_path();
         if let Some(parent) = p.parent() {
             if !parent.as_os_str().is_empty() {
@@ -88,3 +330,121 @@ fn main() {
         println!("Isometric visualization written to: {}", html_path.display());
     }
 }
+
+fn read_level(path: &Path) -> Level {
+    let json = fs::read_to_string(path).unwrap_or_else(|e| panic!("read {}: {}", path.display(), e));
+    export::from_json(&json).unwrap_or_else(|e| panic!("parse {}: {}", path.display(), e))
+}
+
+fn generation_mode(mode: ModeArg) -> GenerationMode {
+    match mode {
+        ModeArg::Classic => GenerationMode::Classic,
+        ModeArg::Marble => GenerationMode::Marble,
+        ModeArg::Wfc => GenerationMode::Wfc,
+        ModeArg::Overworld => GenerationMode::Overworld,
+        ModeArg::Settlement => GenerationMode::Settlement,
+        ModeArg::RoomWfc => GenerationMode::RoomWfc,
+    }
+}
+
+/// Level from an ASCII map, or from a PNG stencil when the path ends in `.png`.
+fn import_level(path: &Path) -> Level {
+    if path.extension().and_then(|e| e.to_str()) == Some("png") {
+        #[cfg(feature = "png")]
+        {
+            let bytes = fs::read(path).unwrap_or_else(|e| panic!("read {}: {}", path.display(), e));
+            return Level::from_png(&bytes, Default::default()).unwrap_or_else(|e| panic!("decode {}: {}", path.display(), e));
+        }
+        #[cfg(not(feature = "png"))]
+        {
+            eprintln!("error: importing images needs the `png` feature (cargo build --features png)");
+            std::process::exit(2);
+        }
+    }
+    let text = fs::read_to_string(path).unwrap_or_else(|e| panic!("read {}: {}", path.display(), e));
+    Level::from_ascii(&text).unwrap_or_else(|e| panic!("parse {}: {}", path.display(), e))
+}
+
+/// Print the tile catalog as JSON, or write it to `json_path`.
+fn run_tile_catalog(json_path: Option<&Path>) {
+    let json = serde_json::to_string_pretty(&export::tile_catalog()).expect("catalog always serializes");
+    match json_path {
+        Some(p) => {
+            if let Some(parent) = p.parent() {
+                if !parent.as_os_str().is_empty() {
+                    let _ = fs::create_dir_all(parent);
+                }
+            }
+            fs::write(p, json).expect("write catalog file");
+        }
+        None => println!("{}", json),
+    }
+}
+
+/// Generate a campaign from `params`, print one line per level, and write the levels as JSON.
+fn run_campaign(params: &GeneratorParams, levels: usize, candidates: u32, out_dir: Option<&Path>) {
+    let campaign = Campaign { candidates, ..Campaign::new(params.clone(), levels) };
+    if let Some(dir) = out_dir {
+        fs::create_dir_all(dir).expect("create campaign directory");
+    }
+    for (i, entry) in campaign.generate().iter().enumerate() {
+        println!("{:>3}  {:<10} difficulty {:.2}  seed {}", i + 1, entry.theme, entry.difficulty, entry.level.seed);
+        if let Some(dir) = out_dir {
+            fs::write(dir.join(format!("level_{:02}.json", i + 1)), export::to_json(&entry.level)).expect("write level file");
+        }
+    }
+}
+
+/// Generate a level per seed, write each one's interactive view into a directory
+/// named after `out`, and write a gallery page linking them to `out`.
+fn run_gallery(params: &GeneratorParams, seeds: std::ops::Range<u64>, out: &Path) {
+    let stem = out.file_stem().map_or_else(|| "gallery".into(), |s| s.to_string_lossy().into_owned());
+    let views = out.with_file_name(&stem);
+    fs::create_dir_all(&views).expect("create gallery directory");
+    let levels: Vec<Level> = seeds.map(|seed| generate(&GeneratorParams { seed: Some(seed), ..params.clone() })).collect();
+    for level in &levels {
+        fs::write(views.join(format!("seed_{}.html", level.seed)), generate_html(level)).expect("write view file");
+    }
+    // The generation options, as typed before the subcommand
+    let options: Vec<String> = std::env::args().skip(1).take_while(|arg| arg != "gallery").collect();
+    let options = if options.is_empty() { "default options".to_string() } else { options.join(" ") };
+    let html = gallery_html(&levels, &options, |level| Some(format!("{}/seed_{}.html", stem, level.seed)));
+    fs::write(out, html).expect("write gallery file");
+    println!("Gallery of {} seeds written to: {}", levels.len(), out.display());
+}
+
+/// Print changed tiles between two level JSON files, optionally as HTML too.
+/// Check each file against its checksum; exits with status 1 if any fails
+fn run_verify(paths: &[PathBuf]) {
+    let mut failed = false;
+    for path in paths {
+        let json = fs::read_to_string(path).unwrap_or_else(|e| panic!("read {}: {}", path.display(), e));
+        match export::verify(&json) {
+            Ok(level) => println!("{}: ok ({})", path.display(), level.checksum()),
+            Err(e) => {
+                eprintln!("{}: {}", path.display(), e);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+fn run_diff(before: &Path, after: &Path, html_path: Option<&Path>) {
+    let (before, after) = (read_level(before), read_level(after));
+    let diff = before.diff(&after);
+    println!("{}", diff.to_ascii(&after));
+    println!("{}", diff);
+
+    if let Some(p) = html_path {
+        if let Some(parent) = p.parent() {
+            if !parent.as_os_str().is_empty() {
+                let _ = fs::create_dir_all(parent);
+            }
+        }
+        fs::write(p, diff.to_html(&after)).expect("write html file");
+        println!("Diff visualization written to: {}", p.display());
+    }
+}
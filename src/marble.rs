@@ -0,0 +1,860 @@
+//! Marble tile conversion.
+//!
+//! [`grid_to_marble_tiles`] turns any character grid into a marble track:
+//! floor tiles become straights, curves, and junctions from their
+//! neighbours, [`place_advanced_tiles`] upgrades some of them to Y-junctions,
+//! merges, gates, loops, and half-pipes, and slopes bridge one-level
+//! elevation steps. Inside the rooms of the config, tiles away from the
+//! corridor mouths are open platforms rather than junctions, walled only
+//! where they border void. [`place_launch_pads`] adds launch pads along the path
+//! from the entrance to the exit. Marble mode runs it on its carved channels,
+//! but it works on caves, mazes, or hand-drawn maps just the same.
+//!
+//! ```
+//! use level_generator::marble::{grid_to_marble_tiles, MarbleTileConfig};
+//! use level_generator::TileType;
+//!
+//! let grid: Vec<Vec<char>> = ["#####", "#...#", "#.###", "#####"].iter().map(|row| row.chars().collect()).collect();
+//! let tiles = grid_to_marble_tiles(&grid, &MarbleTileConfig::default());
+//! assert_eq!(tiles[1][1].tile_type, TileType::Curve90);
+//! assert_eq!(tiles[0][0].tile_type, TileType::Empty);
+//! ```
+
+use std::collections::VecDeque;
+use std::ops::Range;
+
+use crate::dungeon::{Room, TILE_FLOOR};
+use crate::roles::RoomRole;
+use crate::tiles::{Direction, MarbleTile, TileType};
+
+/// Options for [`grid_to_marble_tiles`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MarbleTileConfig<'a> {
+    /// Rooms of the map; with elevation on, straights on their edges become slopes
+    pub rooms: &'a [Room],
+    /// Elevation of every tile, indexed `[y][x]`. `None` keeps the track flat
+    /// and places no slopes.
+    pub elevation: Option<&'a [Vec<i32>]>,
+    /// Which advanced tiles to place
+    pub advanced: AdvancedTileConfig,
+}
+
+/// Which advanced tiles [`place_advanced_tiles`] may place. All are on by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdvancedTileConfig {
+    pub y_junctions: bool,
+    pub merges: bool,
+    pub one_way_gates: bool,
+    /// Needs elevation steps of two or more levels
+    pub loop_de_loops: bool,
+    /// Needs one-level elevation steps
+    pub half_pipes: bool,
+}
+
+impl Default for AdvancedTileConfig {
+    fn default() -> Self {
+        Self { y_junctions: true, merges: true, one_way_gates: true, loop_de_loops: true, half_pipes: true }
+    }
+}
+
+impl AdvancedTileConfig {
+    /// Only basic pieces: no advanced tiles at all.
+    pub fn none() -> Self {
+        Self { y_junctions: false, merges: false, one_way_gates: false, loop_de_loops: false, half_pipes: false }
+    }
+}
+
+/// Check if a position is on the edge of any room
+fn is_on_room_edge(x: i32, y: i32, rooms: &[Room]) -> bool {
+    for room in rooms {
+        // Check if this position is adjacent to a room (within 1 tile of room boundary)
+        let room_left = room.x - 1;
+        let room_right = room.x + room.w;
+        let room_top = room.y - 1;
+        let room_bottom = room.y + room.h;
+        
+        // Check if position is on the edge of this room
+        if (x >= room_left && x <= room_right && (y == room_top || y == room_bottom)) ||
+           (y >= room_top && y <= room_bottom && (x == room_left || x == room_right)) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Marble tile grid for a character grid, where floor (`.`) is track and
+/// everything else is empty. Each floor tile gets a straight, curve, or
+/// junction piece from its floor neighbours, then advanced tiles and slopes
+/// are placed as `config` allows. Walls are left for
+/// [`compute_walls`](crate::tiles::compute_walls).
+pub fn grid_to_marble_tiles(grid: &[Vec<char>], config: &MarbleTileConfig) -> Vec<Vec<MarbleTile>> {
+    let height = grid.len();
+    let width = if height > 0 { grid[0].len() } else { 0 };
+
+    let mut marble_grid = vec![vec![MarbleTile::empty(); width]; height];
+    detect_marble_tiles(&mut marble_grid, grid, config, 0..width, 0..height);
+    marble_grid
+}
+
+/// Re-detect tile types, advanced tiles, and slopes for the tiles in columns
+/// `xs` and rows `ys`, reading the tiles around them as they are.
+pub(crate) fn detect_marble_tiles(
+    marble_grid: &mut [Vec<MarbleTile>],
+    grid: &[Vec<char>],
+    config: &MarbleTileConfig,
+    xs: Range<usize>,
+    ys: Range<usize>,
+) {
+    let height = grid.len();
+    let width = if height > 0 { grid[0].len() } else { 0 };
+    
+    // Helper to check if a position is a floor tile
+    let is_floor = |x: i32, y: i32| -> bool {
+        if y >= 0 && (y as usize) < height && x >= 0 && (x as usize) < width {
+            grid[y as usize][x as usize] == TILE_FLOOR
+        } else {
+            false
+        }
+    };
+    
+    // Get elevation from the map
+    let get_elevation = |x: i32, y: i32| -> i32 {
+        match config.elevation {
+            Some(map) if y >= 0 && (y as usize) < height && x >= 0 && (x as usize) < width => map[y as usize][x as usize],
+            _ => 0,
+        }
+    };
+    
+    // First pass: detect tile types based on neighbors
+    for y in ys.clone() {
+        for x in xs.clone() {
+            if grid[y][x] != TILE_FLOOR {
+                marble_grid[y][x] = MarbleTile::empty();
+                continue;
+            }
+            
+            let ix = x as i32;
+            let iy = y as i32;
+            
+            // Check all four directions
+            let north = is_floor(ix, iy - 1);
+            let south = is_floor(ix, iy + 1);
+            let east = is_floor(ix + 1, iy);
+            let west = is_floor(ix - 1, iy);
+            
+            let connection_count = [north, south, east, west].iter().filter(|&&b| b).count();
+            
+            // Determine base elevation for this tile from the elevation map
+            let base_elevation = get_elevation(ix, iy);
+            
+            let (tile_type, rotation) = match connection_count {
+                0 | 1 => (TileType::OpenPlatform, 0), // Isolated or dead-end
+                2 => {
+                    // Straight or curve
+                    if (north && south) || (east && west) {
+                        // Straight path
+                        let rot = if north && south { 0 } else { 1 };
+                        (TileType::Straight, rot)
+                    } else {
+                        // 90-degree curve
+                        let rot = if north && east {
+                            0
+                        } else if east && south {
+                            1
+                        } else if south && west {
+                            2
+                        } else {
+                            3
+                        };
+                        (TileType::Curve90, rot)
+                    }
+                }
+                3 => {
+                    // T-junction
+                    let rot = if !south {
+                        0
+                    } else if !west {
+                        1
+                    } else if !north {
+                        2
+                    } else {
+                        3
+                    };
+                    (TileType::TJunction, rot)
+                }
+                4 => (TileType::CrossJunction, 0),
+                _ => (TileType::Straight, 0),
+            };
+            
+            marble_grid[y][x] = MarbleTile::with_params(tile_type, base_elevation, rotation, true);
+        }
+    }
+    
+    // Room interiors are open platforms; only tiles at a corridor mouth stay junctions
+    let in_room = |x: i32, y: i32| config.rooms.iter().any(|r| r.contains(x, y));
+    for y in ys.clone() {
+        for x in xs.clone() {
+            let tile = &mut marble_grid[y][x];
+            let (ix, iy) = (x as i32, y as i32);
+            if !matches!(tile.tile_type, TileType::Straight | TileType::Curve90 | TileType::TJunction | TileType::CrossJunction) || !in_room(ix, iy) {
+                continue;
+            }
+            let neighbors = Direction::ALL.map(|d| (ix + d.to_delta().0, iy + d.to_delta().1));
+            if neighbors.iter().any(|&(nx, ny)| is_floor(nx, ny) && !in_room(nx, ny)) {
+                continue;
+            }
+            tile.tile_type = TileType::OpenPlatform;
+            tile.rotation = 0;
+            tile.walls = neighbors.map(|(nx, ny)| !is_floor(nx, ny));
+        }
+    }
+
+    // Second pass: place advanced tiles in appropriate locations (before slope conversion)
+    place_advanced_tiles_in(marble_grid, grid, &config.advanced, xs.clone(), ys.clone());
+    
+    // Third pass: detect and place slope tiles where elevation changes
+    if config.elevation.is_some() {
+        for y in ys.clone() {
+            for x in xs.clone() {
+                let tile = &marble_grid[y][x];
+                if tile.tile_type == TileType::Empty {
+                    continue;
+                }
+                
+                let ix = x as i32;
+                let iy = y as i32;
+                let current_elev = tile.elevation;
+                
+                // Only convert simple tiles to slopes (not junctions, curves, or advanced tiles)
+                if !matches!(tile.tile_type, TileType::Straight | TileType::OpenPlatform | TileType::CrossJunction) {
+                    continue;
+                }
+                
+                // Check if this tile is on the edge of a room
+                let is_on_edge = is_on_room_edge(ix, iy, config.rooms);
+                
+                // Check each direction for elevation changes (±1)
+                let has_elevation_change = 
+                    (is_floor(ix, iy - 1) && (get_elevation(ix, iy - 1) - current_elev).abs() == 1) ||
+                    (is_floor(ix, iy + 1) && (get_elevation(ix, iy + 1) - current_elev).abs() == 1) ||
+                    (is_floor(ix + 1, iy) && (get_elevation(ix + 1, iy) - current_elev).abs() == 1) ||
+                    (is_floor(ix - 1, iy) && (get_elevation(ix - 1, iy) - current_elev).abs() == 1);
+                
+                // Only place slopes when connecting different elevations OR on room edges
+                if has_elevation_change || is_on_edge {
+                    // Determine orientation based on the elevation change direction
+                    let vertical_change = 
+                        (is_floor(ix, iy - 1) && (get_elevation(ix, iy - 1) - current_elev).abs() == 1) ||
+                        (is_floor(ix, iy + 1) && (get_elevation(ix, iy + 1) - current_elev).abs() == 1);
+                    
+                    let horizontal_change = 
+                        (is_floor(ix + 1, iy) && (get_elevation(ix + 1, iy) - current_elev).abs() == 1) ||
+                        (is_floor(ix - 1, iy) && (get_elevation(ix - 1, iy) - current_elev).abs() == 1);
+                    
+                    // Prefer vertical orientation if there's a vertical elevation change
+                    let orientation = if vertical_change { 0 } else if horizontal_change { 1 } else { 0 };
+                    
+                    marble_grid[y][x] = MarbleTile::with_params(
+                        TileType::Slope,
+                        current_elev,
+                        orientation,
+                        true
+                    );
+                }
+            }
+        }
+    }
+
+}
+
+/// Tiles followed along each arm of a junction to find its direction.
+pub const Y_JUNCTION_REACH: usize = 4;
+
+/// Whether the junction at `(x, y)` splits a stem into two branches at a
+/// shallow angle. Each arm is followed along its one-wide corridor for up
+/// to [`Y_JUNCTION_REACH`] tiles (stopping where the way forks or widens),
+/// and its direction is the offset from the junction to where it got. The
+/// junction is a Y when two arms both run within 60 degrees of straight on
+/// from the third, the stem. Straight T-junctions, room edges, and wide
+/// channels never qualify: one of their arms leaves at a right angle.
+fn diverges_smoothly(is_floor: &impl Fn(i32, i32) -> bool, x: i32, y: i32) -> bool {
+    let arms: Vec<(i32, i32)> = Direction::ALL
+        .iter()
+        .map(|d| d.to_delta())
+        .filter(|&(dx, dy)| is_floor(x + dx, y + dy))
+        .map(|(dx, dy)| {
+            let (mut prev, mut at) = ((x, y), (x + dx, y + dy));
+            for _ in 1..Y_JUNCTION_REACH {
+                let mut next = Direction::ALL
+                    .iter()
+                    .map(|d| (at.0 + d.to_delta().0, at.1 + d.to_delta().1))
+                    .filter(|&(nx, ny)| (nx, ny) != prev && is_floor(nx, ny));
+                match (next.next(), next.next()) {
+                    (Some(step), None) => (prev, at) = (at, step),
+                    _ => break,
+                }
+            }
+            (at.0 - x, at.1 - y)
+        })
+        .collect();
+    if arms.len() != 3 {
+        return false;
+    }
+    let dot = |a: (i32, i32), b: (i32, i32)| a.0 * b.0 + a.1 * b.1;
+    (0..3).any(|stem| {
+        let ahead = (-arms[stem].0, -arms[stem].1);
+        // cos > 0.5 without square roots: dot > 0 and 4 dot^2 > |a|^2 |b|^2
+        let within = |arm: (i32, i32)| dot(arm, ahead) > 0 && 4 * dot(arm, ahead).pow(2) > dot(arm, arm) * dot(ahead, ahead);
+        within(arms[(stem + 1) % 3]) && within(arms[(stem + 2) % 3])
+    })
+}
+
+/// Upgrade basic pieces of `marble_grid` to the advanced tiles `config`
+/// allows, judged from the floor of `grid` and the tiles' elevations:
+/// T-junctions whose branches split at a shallow angle (see
+/// [`Y_JUNCTION_REACH`]) become Y-junctions, cross junctions
+/// fed from three sides become merges, and straights beside a wall become
+/// one-way gates. Straights next to a drop of two or more levels become loop-de-loops, and
+/// curves next to a one-level step become half-pipes. The map border is left
+/// alone.
+pub fn place_advanced_tiles(marble_grid: &mut [Vec<MarbleTile>], grid: &[Vec<char>], config: &AdvancedTileConfig) {
+    let (height, width) = (marble_grid.len(), marble_grid.first().map_or(0, Vec::len));
+    place_advanced_tiles_in(marble_grid, grid, config, 0..width, 0..height);
+}
+
+/// [`place_advanced_tiles`] within columns `xs` and rows `ys`.
+fn place_advanced_tiles_in(
+    marble_grid: &mut [Vec<MarbleTile>],
+    grid: &[Vec<char>],
+    config: &AdvancedTileConfig,
+    xs: Range<usize>,
+    ys: Range<usize>,
+) {
+    let height = marble_grid.len();
+    let width = if height > 0 { marble_grid[0].len() } else { 0 };
+    // Advanced tiles never sit on the map border
+    let xs = xs.start.max(1)..xs.end.min(width.saturating_sub(1));
+    let ys = ys.start.max(1)..ys.end.min(height.saturating_sub(1));
+    
+    // Helper to check if a position is a floor tile
+    let is_floor = |x: i32, y: i32| -> bool {
+        if y >= 0 && (y as usize) < height && x >= 0 && (x as usize) < width {
+            grid[y as usize][x as usize] == TILE_FLOOR
+        } else {
+            false
+        }
+    };
+    
+    // Place Y-junctions where we have smooth 3-way connections
+    if config.y_junctions {
+        for y in ys.clone() {
+            for x in xs.clone() {
+                let tile = &marble_grid[y][x];
+                if tile.tile_type != TileType::TJunction {
+                    continue;
+                }
+            
+                let ix = x as i32;
+                let iy = y as i32;
+            
+                if diverges_smoothly(&is_floor, ix, iy) {
+                    marble_grid[y][x] = MarbleTile::with_params(
+                        TileType::YJunction,
+                        tile.elevation,
+                        tile.rotation,
+                        true
+                    );
+                }
+            }
+        }
+    }
+    
+    // Place merge tiles where multiple paths converge to a single output
+    if config.merges {
+        for y in ys.clone() {
+            for x in xs.clone() {
+                let tile = &marble_grid[y][x];
+                if tile.tile_type != TileType::CrossJunction {
+                    continue;
+                }
+            
+                let ix = x as i32;
+                let iy = y as i32;
+            
+                // Check if this cross junction has a clear "output" direction
+                // (one direction with more connections downstream)
+                let north_connections = count_connections_downstream(marble_grid, grid, ix, iy - 1, Direction::North);
+                let south_connections = count_connections_downstream(marble_grid, grid, ix, iy + 1, Direction::South);
+                let east_connections = count_connections_downstream(marble_grid, grid, ix + 1, iy, Direction::East);
+                let west_connections = count_connections_downstream(marble_grid, grid, ix - 1, iy, Direction::West);
+            
+                let connections = [north_connections, south_connections, east_connections, west_connections];
+                let max_connections = connections.iter().max().unwrap_or(&0);
+            
+                // If one direction has significantly more connections, it's likely a merge
+                if *max_connections >= 3 && connections.iter().filter(|&&c| c > 0).count() >= 3 {
+                    // Determine the output direction (the one with most connections)
+                    let output_dir = if north_connections == *max_connections { 0 }
+                                    else if east_connections == *max_connections { 1 }
+                                    else if south_connections == *max_connections { 2 }
+                                    else { 3 };
+                
+                    marble_grid[y][x] = MarbleTile::with_params(
+                        TileType::Merge,
+                        tile.elevation,
+                        output_dir,
+                        true
+                    );
+                }
+            }
+        }
+    }
+    
+    // Place one-way gates in narrow passages (relaxed conditions)
+    if config.one_way_gates {
+        for y in ys.clone() {
+            for x in xs.clone() {
+                let tile = &marble_grid[y][x];
+                if tile.tile_type != TileType::Straight {
+                    continue;
+                }
+            
+                let ix = x as i32;
+                let iy = y as i32;
+            
+                // Check if this is a narrow passage (straight line with walls on sides)
+                // Relaxed: only need walls on one side, not both
+                let is_narrow_passage = match tile.rotation {
+                    0 | 2 => { // Vertical passage
+                        (!is_floor(ix - 1, iy) || !is_floor(ix + 1, iy)) &&
+                        is_floor(ix, iy - 1) && is_floor(ix, iy + 1)
+                    },
+                    1 | 3 => { // Horizontal passage
+                        (!is_floor(ix, iy - 1) || !is_floor(ix, iy + 1)) &&
+                        is_floor(ix - 1, iy) && is_floor(ix + 1, iy)
+                    },
+                    _ => false,
+                };
+            
+                if is_narrow_passage {
+                    marble_grid[y][x] = MarbleTile::with_params(
+                        TileType::OneWayGate,
+                        tile.elevation,
+                        tile.rotation,
+                        true
+                    );
+                }
+            }
+        }
+    }
+    
+    // Place loop-de-loops where we have elevation changes of +2 or more
+    if config.loop_de_loops {
+        for y in ys.clone() {
+            for x in xs.clone() {
+                let tile = &marble_grid[y][x];
+                if tile.tile_type != TileType::Straight {
+                    continue;
+                }
+                
+                let ix = x as i32;
+                let iy = y as i32;
+                let current_elev = tile.elevation;
+                
+                // Check for large elevation changes that could support a loop
+                let has_large_elevation_change = 
+                    (is_floor(ix, iy - 1) && (get_elevation(marble_grid, ix, iy - 1) - current_elev).abs() >= 2) ||
+                    (is_floor(ix, iy + 1) && (get_elevation(marble_grid, ix, iy + 1) - current_elev).abs() >= 2) ||
+                    (is_floor(ix + 1, iy) && (get_elevation(marble_grid, ix + 1, iy) - current_elev).abs() >= 2) ||
+                    (is_floor(ix - 1, iy) && (get_elevation(marble_grid, ix - 1, iy) - current_elev).abs() >= 2);
+                
+                if has_large_elevation_change {
+                    marble_grid[y][x] = MarbleTile::with_params(
+                        TileType::LoopDeLoop,
+                        current_elev,
+                        tile.rotation,
+                        true
+                    );
+                }
+            }
+        }
+    }
+    
+    // Place half-pipes in curved sections with elevation changes
+    if config.half_pipes {
+        for y in ys.clone() {
+            for x in xs.clone() {
+                let tile = &marble_grid[y][x];
+                if tile.tile_type != TileType::Curve90 {
+                    continue;
+                }
+                
+                let ix = x as i32;
+                let iy = y as i32;
+                let current_elev = tile.elevation;
+                
+                // Check if this curve has elevation changes
+                let has_elevation_change = 
+                    (is_floor(ix, iy - 1) && (get_elevation(marble_grid, ix, iy - 1) - current_elev).abs() == 1) ||
+                    (is_floor(ix, iy + 1) && (get_elevation(marble_grid, ix, iy + 1) - current_elev).abs() == 1) ||
+                    (is_floor(ix + 1, iy) && (get_elevation(marble_grid, ix + 1, iy) - current_elev).abs() == 1) ||
+                    (is_floor(ix - 1, iy) && (get_elevation(marble_grid, ix - 1, iy) - current_elev).abs() == 1);
+                
+                if has_elevation_change {
+                    marble_grid[y][x] = MarbleTile::with_params(
+                        TileType::HalfPipe,
+                        current_elev,
+                        tile.rotation,
+                        true
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Shortest flat straight run, in tiles, that gets a launch pad
+pub const MIN_PAD_RUN: usize = 6;
+
+/// Shortest walk over passable tiles from the entrance room's tile nearest
+/// its center into the exit room, or `None` without both rooms or a path.
+fn main_path(marble_grid: &[Vec<MarbleTile>], rooms: &[Room]) -> Option<Vec<(i32, i32)>> {
+    let entrance = rooms.iter().find(|r| r.role == RoomRole::Entrance)?;
+    let exit = rooms.iter().find(|r| r.role == RoomRole::Exit)?;
+    let passable = |x: i32, y: i32| x >= 0 && y >= 0 && marble_grid.get(y as usize).and_then(|row| row.get(x as usize)).is_some_and(|t| t.tile_type.is_passable());
+    let inside = |room: &Room, (x, y): (i32, i32)| room.contains(x, y);
+    let (cx, cy) = entrance.center();
+    let start = (entrance.y..entrance.y + entrance.h)
+        .flat_map(|y| (entrance.x..entrance.x + entrance.w).map(move |x| (x, y)))
+        .filter(|&(x, y)| passable(x, y))
+        .min_by_key(|&(x, y)| ((x - cx).abs() + (y - cy).abs(), y, x))?;
+
+    let mut came_from = vec![vec![None; marble_grid.first().map_or(0, Vec::len)]; marble_grid.len()];
+    came_from[start.1 as usize][start.0 as usize] = Some(start);
+    let mut queue = VecDeque::from([start]);
+    while let Some(at) = queue.pop_front() {
+        if inside(exit, at) {
+            let mut path = vec![at];
+            while let Some(&prev) = path.last().filter(|&&p| p != start).and_then(|&(x, y)| came_from[y as usize][x as usize].as_ref()) {
+                path.push(prev);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for d in Direction::ALL {
+            let (nx, ny) = (at.0 + d.to_delta().0, at.1 + d.to_delta().1);
+            if passable(nx, ny) && came_from[ny as usize][nx as usize].is_none() {
+                came_from[ny as usize][nx as usize] = Some(at);
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+    None
+}
+
+/// Turn up to `max` tiles on the main path (the shortest walk from the
+/// entrance room to the exit room of `rooms`) into launch pads: one at the
+/// start, facing the first step, then one at the beginning of each flat
+/// straight run of at least [`MIN_PAD_RUN`] tiles, longest runs first. A
+/// run is straights, open platforms, or cross junctions (the inside of
+/// rooms and wide channels) at one elevation that the path crosses in one
+/// direction; side branches and stubs never get pads. Pads keep the walls
+/// of the tiles they replace. Returns the number of pads placed; none
+/// without an entrance and an exit room.
+pub fn place_launch_pads(marble_grid: &mut [Vec<MarbleTile>], rooms: &[Room], max: usize) -> usize {
+    let Some(path) = main_path(marble_grid, rooms) else { return 0 };
+    if max == 0 || path.len() < 2 {
+        return 0;
+    }
+    let tile = |(x, y): (i32, i32)| &marble_grid[y as usize][x as usize];
+    // Room interiors and wide channels are cross junctions
+    let plain = |at: (i32, i32)| matches!(tile(at).tile_type, TileType::Straight | TileType::OpenPlatform | TileType::CrossJunction);
+    let step = |i: usize| Direction::from_delta(path[i + 1].0 - path[i].0, path[i + 1].1 - path[i].1).expect("path steps are orthogonal");
+
+    let mut pads = Vec::new();
+    if plain(path[0]) {
+        pads.push((path[0], step(0)));
+    }
+    // Flat straight runs after the start, as (first index, length)
+    // (the start pad already launches the marble along its first stretch)
+    let mut runs = Vec::new();
+    let mut i = (1..path.len() - 1).find(|&k| step(k) != step(0)).unwrap_or(path.len());
+    while i + 1 < path.len() {
+        let mut end = i;
+        while end + 1 < path.len() && plain(path[end]) && step(end) == step(i) && tile(path[end]).elevation == tile(path[i]).elevation {
+            end += 1;
+        }
+        if end - i >= MIN_PAD_RUN {
+            runs.push((i, end - i));
+        }
+        i = end.max(i + 1);
+    }
+    runs.sort_by_key(|&(start, length)| (std::cmp::Reverse(length), start));
+    pads.extend(runs.into_iter().map(|(start, _)| (path[start], step(start))));
+
+    pads.truncate(max);
+    for &((x, y), facing) in &pads {
+        let tile = &mut marble_grid[y as usize][x as usize];
+        tile.tile_type = TileType::LaunchPad;
+        tile.rotation = facing as u8;
+    }
+    pads.len()
+}
+
+/// Turn the channel tiles inside every room of `rooms` into one open bowl:
+/// open platforms with a rim wall on each edge that borders void (an empty
+/// tile or the map edge), so the rim is open exactly where a channel or a
+/// neighboring room joins. Slopes, structures, obstacles, and advanced tiles
+/// are left as they are. Returns the number of tiles turned.
+pub fn open_bowl_rooms(marble_grid: &mut [Vec<MarbleTile>], rooms: &[Room]) -> usize {
+    let height = marble_grid.len() as i32;
+    let width = marble_grid.first().map_or(0, Vec::len) as i32;
+    let is_void = |grid: &[Vec<MarbleTile>], x: i32, y: i32| {
+        x < 0 || y < 0 || x >= width || y >= height || grid[y as usize][x as usize].tile_type == TileType::Empty
+    };
+
+    let mut turned = 0;
+    for room in rooms {
+        for y in room.y.max(0)..(room.y + room.h).min(height) {
+            for x in room.x.max(0)..(room.x + room.w).min(width) {
+                let tile = &marble_grid[y as usize][x as usize];
+                if !matches!(
+                    tile.tile_type,
+                    TileType::Straight | TileType::Curve90 | TileType::TJunction | TileType::YJunction | TileType::CrossJunction | TileType::OpenPlatform
+                ) {
+                    continue;
+                }
+                let walls = Direction::ALL.map(|d| is_void(marble_grid, x + d.to_delta().0, y + d.to_delta().1));
+                let tile = &mut marble_grid[y as usize][x as usize];
+                if tile.tile_type != TileType::OpenPlatform || tile.walls != walls {
+                    turned += 1;
+                }
+                tile.tile_type = TileType::OpenPlatform;
+                tile.rotation = 0;
+                tile.walls = walls;
+            }
+        }
+    }
+    turned
+}
+
+/// Helper function to count connections downstream from a position
+fn count_connections_downstream(
+    marble_grid: &[Vec<MarbleTile>],
+    grid: &[Vec<char>],
+    start_x: i32,
+    start_y: i32,
+    direction: Direction,
+) -> usize {
+    if start_y < 0 || (start_y as usize) >= marble_grid.len() ||
+       start_x < 0 || (start_x as usize) >= marble_grid[0].len() {
+        return 0;
+    }
+    
+    let mut count = 0;
+    let mut x = start_x;
+    let mut y = start_y;
+    
+    // Follow the path in the given direction
+    for _ in 0..10 { // Limit to prevent infinite loops
+        let (dx, dy) = direction.to_delta();
+        
+        x += dx;
+        y += dy;
+        
+        if y < 0 || (y as usize) >= marble_grid.len() ||
+           x < 0 || (x as usize) >= marble_grid[0].len() {
+            break;
+        }
+        
+        if grid[y as usize][x as usize] != TILE_FLOOR {
+            break;
+        }
+        
+        count += 1;
+        
+        // Stop if we hit a junction or dead end; merges were cross junctions
+        // a moment ago, so they stop the walk too and the scan order doesn't matter
+        let tile = &marble_grid[y as usize][x as usize];
+        if tile.tile_type == TileType::TJunction || 
+           tile.tile_type == TileType::CrossJunction ||
+           tile.tile_type == TileType::YJunction ||
+           tile.tile_type == TileType::Merge {
+            break;
+        }
+    }
+    
+    count
+}
+
+/// Helper function to get elevation from marble grid
+fn get_elevation(marble_grid: &[Vec<MarbleTile>], x: i32, y: i32) -> i32 {
+    if y >= 0 && (y as usize) < marble_grid.len() &&
+       x >= 0 && (x as usize) < marble_grid[0].len() {
+        marble_grid[y as usize][x as usize].elevation
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams};
+    use crate::tiles::compute_walls;
+
+    fn grid(rows: &[&str]) -> Vec<Vec<char>> {
+        rows.iter().map(|row| row.chars().collect()).collect()
+    }
+
+    #[test]
+    fn converts_any_grid_and_honours_the_advanced_tile_config() {
+        // A one-wide corridor between walls is lined with one-way gates
+        let corridor = grid(&["#######", "#.....#", "#######"]);
+        let tiles = grid_to_marble_tiles(&corridor, &MarbleTileConfig::default());
+        assert_eq!(tiles[1][1].tile_type, TileType::OpenPlatform);
+        assert!(tiles[1][2..5].iter().all(|t| t.tile_type == TileType::OneWayGate));
+
+        let plain = MarbleTileConfig { advanced: AdvancedTileConfig::none(), ..Default::default() };
+        let tiles = grid_to_marble_tiles(&corridor, &plain);
+        assert!(tiles[1][2..5].iter().all(|t| t.tile_type == TileType::Straight));
+
+        // Elevation steps turn straights into slopes
+        let elevation = vec![vec![0; 7], vec![0, 0, 0, 1, 1, 1, 0], vec![0; 7]];
+        let sloped = MarbleTileConfig { elevation: Some(&elevation), ..plain };
+        let tiles = grid_to_marble_tiles(&corridor, &sloped);
+        assert_eq!(tiles[1][3].tile_type, TileType::Slope);
+        assert_eq!(tiles[1][3].elevation, 1);
+    }
+
+    #[test]
+    fn launch_pads_go_on_the_main_path_only() {
+        // Entrance top left, a long corridor east with a stub below its
+        // corner, and the exit bottom right
+        let map = grid(&[
+            "################",
+            "#...############",
+            "#...############",
+            "#...############",
+            "##.#############",
+            "##.#############",
+            "##............##",
+            "##.#########.###",
+            "############.###",
+            "###########...##",
+            "###########...##",
+            "###########...##",
+            "################",
+        ]);
+        let rooms = [
+            Room { x: 1, y: 1, w: 3, h: 3, role: RoomRole::Entrance, ..Default::default() },
+            Room { x: 11, y: 9, w: 3, h: 3, role: RoomRole::Exit, ..Default::default() },
+        ];
+        let plain = MarbleTileConfig { rooms: &rooms, advanced: AdvancedTileConfig::none(), ..Default::default() };
+        let pads = |tiles: &[Vec<MarbleTile>]| -> Vec<(usize, usize, Direction)> {
+            (0..tiles.len())
+                .flat_map(|y| (0..tiles[y].len()).map(move |x| (x, y)))
+                .filter(|&(x, y)| tiles[y][x].tile_type == TileType::LaunchPad)
+                .map(|(x, y)| (x, y, tiles[y][x].connections()[0]))
+                .collect()
+        };
+
+        let mut tiles = grid_to_marble_tiles(&map, &plain);
+        assert!(pads(&tiles).is_empty());
+        let walls = tiles[6][3].walls;
+        assert_eq!(place_launch_pads(&mut tiles, &rooms, 3), 2);
+        // The start in the entrance room, and the flat run east (not the stub or the short drop)
+        assert_eq!(pads(&tiles), vec![(2, 2, Direction::South), (3, 6, Direction::East)]);
+        assert_eq!(tiles[6][3].walls, walls);
+
+        let mut tiles = grid_to_marble_tiles(&map, &plain);
+        assert_eq!(place_launch_pads(&mut tiles, &rooms, 1), 1);
+        assert_eq!(pads(&tiles), vec![(2, 2, Direction::South)]);
+        let mut tiles = grid_to_marble_tiles(&map, &plain);
+        assert_eq!(place_launch_pads(&mut tiles, &rooms[..1], 3), 0);
+
+        // Generation honours the cap
+        let params = GeneratorParams { seed: Some(3), mode: GenerationMode::Marble, max_launch_pads: 2, ..Default::default() };
+        let count = |params: &GeneratorParams| pads(generate(params).marble_tiles.as_ref().unwrap()).len();
+        assert!((1..=2).contains(&count(&params)));
+        assert_eq!(count(&GeneratorParams { max_launch_pads: 0, ..params }), 0);
+    }
+
+    #[test]
+    #[allow(clippy::needless_range_loop)]
+    fn bowl_rooms_are_open_inside_with_a_rim_open_at_channels() {
+        // A 4x3 room with a channel leaving east from its middle row
+        let map = grid(&["########", "#....###", "#......#", "#....###", "########"]);
+        let rooms = vec![Room { x: 1, y: 1, w: 4, h: 3, ..Default::default() }];
+        let mut tiles = grid_to_marble_tiles(&map, &MarbleTileConfig { rooms: &rooms, advanced: AdvancedTileConfig::none(), ..Default::default() });
+        compute_walls(&mut tiles);
+        // Detection already opens the interior; only the tile at the channel mouth is left
+        assert_eq!(tiles[2][4].tile_type, TileType::CrossJunction);
+        assert_eq!(open_bowl_rooms(&mut tiles, &rooms), 1);
+        assert_eq!(open_bowl_rooms(&mut tiles, &rooms), 0);
+
+        for y in 1..4 {
+            for x in 1..5 {
+                let tile = &tiles[y][x];
+                assert_eq!(tile.tile_type, TileType::OpenPlatform);
+                let rim = [y == 1, x == 4 && y != 2, y == 3, x == 1];
+                assert_eq!(tile.walls, rim, "({}, {})", x, y);
+            }
+        }
+        // The channel outside the room is untouched
+        assert_ne!(tiles[2][5].tile_type, TileType::OpenPlatform);
+        assert!(!tiles[2][4].has_wall(Direction::East) && !tiles[2][5].has_wall(Direction::West));
+    }
+
+    #[test]
+    fn room_interiors_are_platforms_and_corridor_mouths_junctions() {
+        // A 5x4 room entered from the west and left through the south
+        let map = grid(&["#######", "##.....", "##.....", "##.....", ".......", "####.##", "####.##"]);
+        let rooms = vec![Room { x: 2, y: 1, w: 5, h: 4, ..Default::default() }];
+        let plain = MarbleTileConfig { rooms: &rooms, advanced: AdvancedTileConfig::none(), ..Default::default() };
+        let tiles = grid_to_marble_tiles(&map, &plain);
+
+        let junctions: Vec<(usize, usize)> = (1..5)
+            .flat_map(|y| (2..7).map(move |x| (x, y)))
+            .filter(|&(x, y)| tiles[y][x].tile_type != TileType::OpenPlatform)
+            .collect();
+        assert_eq!(junctions, vec![(2, 4), (4, 4)]);
+        assert_eq!(tiles[4][2].tile_type, TileType::TJunction);
+        // Platforms keep walls only toward void
+        assert_eq!(tiles[1][2].walls, [true, false, false, true]);
+        assert_eq!(tiles[2][4].walls, [false; 4]);
+        assert_eq!(tiles[4][1].tile_type, TileType::Straight);
+
+        // Without rooms the detector sees only junctions
+        let tiles = grid_to_marble_tiles(&map, &MarbleTileConfig { rooms: &[], ..plain });
+        assert_eq!(tiles[2][4].tile_type, TileType::CrossJunction);
+    }
+
+    #[test]
+    fn y_junctions_only_where_branches_split_at_a_shallow_angle() {
+        // A stem from the south splitting into two staircases heading north-west and north-east
+        let y = grid(&[
+            "#########",
+            "##.###.##",
+            "##..#..##",
+            "###...###",
+            "####.####",
+            "####.####",
+            "#########",
+        ]);
+        // The same stem meeting a straight east-west corridor, and a room edge
+        let t = grid(&["#########", "#.......#", "####.####", "####.####", "#########"]);
+        let room = grid(&["#######", "#.....#", "#.....#", "#......", "#######"]);
+        let detect = |map: &[Vec<char>], y_junctions: bool| {
+            let config = MarbleTileConfig { advanced: AdvancedTileConfig { y_junctions, ..AdvancedTileConfig::none() }, ..Default::default() };
+            grid_to_marble_tiles(map, &config)
+        };
+
+        assert_eq!(detect(&y, true)[3][4].tile_type, TileType::YJunction);
+        assert_eq!(detect(&y, false)[3][4].tile_type, TileType::TJunction);
+        assert_eq!(detect(&t, true)[1][4].tile_type, TileType::TJunction);
+        let edges = detect(&room, true);
+        assert!(edges.iter().flatten().all(|tile| tile.tile_type != TileType::YJunction));
+        assert!(edges.iter().flatten().any(|tile| tile.tile_type == TileType::TJunction));
+    }
+}
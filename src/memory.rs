@@ -0,0 +1,178 @@
+//! Memory held by a level, for budgeting batch generation.
+//!
+//! A level's size is dominated by its per-tile layers. On 64-bit targets:
+//!
+//! | Layer | Bytes per tile | Present |
+//! |-------|----------------|---------|
+//! | `tiles` (ASCII) | 1, plus 24 per row | always, unless dropped |
+//! | `marble_tiles` | 40, plus its `metadata` text | marble mode |
+//! | `terrain` | 8 | overworld mode |
+//! | `river_map`, `zone_map` | 4 each | when rivers or zones are enabled |
+//!
+//! Every 2D layer also keeps 24 bytes per row. A 200x200 marble map thus
+//! holds about 1.6 MB of marble tiles and 40 KB of ASCII. Rooms,
+//! connections, obstacles, and the other lists grow with the room count,
+//! not the map, and are left out of [`Level::memory_bytes`].
+//!
+//! In marble mode the ASCII rows repeat what the marble tiles already say
+//! (floor where a tile is not empty, wall elsewhere; only the `skirt`, which
+//! marble tiles do not tell apart from wall, comes back as wall). Setting
+//! [`GeneratorParams::keep_ascii_tiles`] to false leaves `level.tiles`
+//! empty; [`Level::ascii_tiles`] derives the rows when they are needed and
+//! [`Level::restore_ascii_tiles`] puts them back. The crate's analyses and
+//! edits work on such lean levels and keep them lean, and levels cut from,
+//! joined to, or transformed from a lean level are lean too. Only the ASCII
+//! edits in [`crate::editing`] put the rows back, since they hold the edit.
+//!
+//! ```
+//! use level_generator::gen::{generate, GenerationMode, GeneratorParams};
+//!
+//! let params = GeneratorParams { seed: Some(3), mode: GenerationMode::Marble, ..Default::default() };
+//! let full = generate(&params);
+//! let lean = generate(&GeneratorParams { keep_ascii_tiles: false, ..params });
+//! assert!(lean.tiles.is_empty());
+//! assert_eq!(lean.ascii_tiles(), full.tiles);
+//! assert!(lean.memory_bytes() < full.memory_bytes());
+//! ```
+//!
+//! [`GeneratorParams::keep_ascii_tiles`]: crate::dungeon::GeneratorParams::keep_ascii_tiles
+
+use std::borrow::Cow;
+use std::mem::{size_of, size_of_val};
+
+use crate::dungeon::{Grid, Level, TILE_FLOOR, TILE_WALL};
+use crate::tiles::{MarbleTile, TileType};
+
+/// Heap bytes of a 2D layer: the row headers and `tile_bytes` per tile.
+fn layer_bytes<T>(rows: &[Vec<T>], tile_bytes: impl Fn(&T) -> usize) -> usize {
+    rows.iter().map(|row| size_of::<Vec<T>>() + row.iter().map(&tile_bytes).sum::<usize>()).sum()
+}
+
+/// The ASCII character a lean level derives for a marble tile: wall where
+/// it is empty, floor elsewhere.
+pub(crate) fn ascii_char(tile: &MarbleTile) -> char {
+    if tile.tile_type == TileType::Empty {
+        TILE_WALL
+    } else {
+        TILE_FLOOR
+    }
+}
+
+impl Level {
+    /// Approximate heap bytes held by the per-tile layers; see the module docs.
+    pub fn memory_bytes(&self) -> usize {
+        let ascii: usize = self.tiles.iter().map(|row| size_of::<String>() + row.len()).sum();
+        let marble = self.marble_tiles.as_deref().map_or(0, |tiles| layer_bytes(tiles, |tile| size_of_val(tile) + tile.metadata.len()));
+        let terrain = self.terrain.as_deref().map_or(0, |terrain| layer_bytes(terrain, size_of_val));
+        let ids = [&self.river_map, &self.zone_map].into_iter().flatten().map(|map| layer_bytes(map, size_of_val)).sum::<usize>();
+        ascii + marble + terrain + ids
+    }
+
+    /// The ASCII rows: `tiles` when the level has them, otherwise derived
+    /// from the marble tiles, floor where a tile is not empty and wall
+    /// elsewhere.
+    pub fn ascii_tiles(&self) -> Cow<'_, [String]> {
+        match &self.marble_tiles {
+            Some(marble) if self.tiles.is_empty() => Cow::Owned(
+                marble
+                    .iter()
+                    .map(|row| row.iter().map(ascii_char).collect())
+                    .collect(),
+            ),
+            _ => Cow::Borrowed(&self.tiles),
+        }
+    }
+
+    /// Fill `tiles` from the marble tiles if they were dropped.
+    pub fn restore_ascii_tiles(&mut self) {
+        if self.tiles.is_empty() {
+            self.tiles = self.ascii_tiles().into_owned();
+        }
+    }
+
+    /// Whether `tiles` was dropped and is derived from the marble tiles.
+    pub fn is_lean(&self) -> bool {
+        self.tiles.is_empty() && self.marble_tiles.is_some()
+    }
+
+    /// Replace the ASCII tiles with `grid` and run `finish` to bring the
+    /// other layers in line, dropping the rows again afterwards if the level
+    /// was lean.
+    pub(crate) fn set_ascii_tiles<R>(&mut self, grid: Grid, finish: impl FnOnce(&mut Self) -> R) -> R {
+        let lean = self.is_lean();
+        self.tiles = grid.into_iter().map(|row| row.into_iter().collect()).collect();
+        let result = finish(self);
+        if lean {
+            self.tiles = Vec::new();
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams};
+    use crate::editing::Rect;
+    use crate::gallery::thumbnail_svg;
+    use crate::stitch::StitchStrategy;
+    use crate::visibility::field_of_view;
+
+    #[test]
+    fn dropped_ascii_tiles_are_derived_from_marble_tiles() {
+        let params = GeneratorParams { seed: Some(1172), mode: GenerationMode::Marble, enable_elevation: true, enable_obstacles: true, ..Default::default() };
+        let full = generate(&params);
+        let mut lean = generate(&GeneratorParams { keep_ascii_tiles: false, ..params.clone() });
+        assert!(lean.tiles.is_empty());
+        assert_eq!(lean.ascii_tiles(), full.tiles);
+        assert_eq!(lean.memory_bytes() + full.tiles.len() * (size_of::<String>() + full.width as usize), full.memory_bytes());
+        lean.restore_ascii_tiles();
+        assert_eq!(lean.tiles, full.tiles);
+
+        // The skirt comes back as wall
+        let skirted = generate(&GeneratorParams { keep_ascii_tiles: false, skirt: 2, ..params.clone() });
+        assert_eq!(skirted.ascii_tiles()[0], TILE_WALL.to_string().repeat(skirted.width as usize));
+
+        // Classic levels have no marble tiles to derive from and keep their rows
+        let classic = generate(&GeneratorParams { keep_ascii_tiles: false, mode: GenerationMode::Classic, ..params });
+        assert_eq!(classic.tiles.len(), classic.height as usize);
+    }
+
+    #[test]
+    fn lean_levels_work_like_full_ones() {
+        let params = GeneratorParams { seed: Some(1172), width: 40, height: 24, rooms: 5, mode: GenerationMode::Marble, ..Default::default() };
+        let full = generate(&params);
+        let lean = generate(&GeneratorParams { keep_ascii_tiles: false, ..params.clone() });
+        let (w, h) = (full.width as i32, full.height as i32);
+
+        // Readers
+        assert!(lean.check_invariants().is_empty());
+        assert_eq!(thumbnail_svg(&lean), thumbnail_svg(&full));
+        let (x, y) = full.rooms[0].center();
+        assert_eq!(field_of_view(&lean, (x, y), 8, false), field_of_view(&full, (x, y), 8, false));
+        assert!(lean.main_path().is_some());
+        assert_eq!(lean.main_path(), full.main_path());
+
+        // Levels made from a lean level are lean and match the full ones
+        let same = |lean: &Level, full: &Level| {
+            assert!(lean.is_lean());
+            assert_eq!(lean.ascii_tiles(), full.tiles);
+            assert_eq!(lean.marble_tiles, full.marble_tiles);
+            assert!(lean.check_invariants().is_empty());
+        };
+        let rect = Rect::new(0, 0, w / 2, h);
+        same(&lean.crop(rect), &full.crop(rect));
+        same(&lean.upscale(2), &full.upscale(2));
+        let right = generate(&GeneratorParams { seed: Some(1173), ..params.clone() });
+        let lean_right = generate(&GeneratorParams { seed: Some(1173), keep_ascii_tiles: false, ..params.clone() });
+        same(&lean.concat_horizontal(&lean_right, StitchStrategy::ExitToEntrance), &full.concat_horizontal(&right, StitchStrategy::ExitToEntrance));
+
+        // Edits keep a lean level lean
+        let (mut lean_edit, mut full_edit) = (lean.clone(), full.clone());
+        lean_edit.update_marble_tiles(Rect::new(0, 0, w, h));
+        full_edit.update_marble_tiles(Rect::new(0, 0, w, h));
+        same(&lean_edit, &full_edit);
+        assert_eq!(lean_edit.add_side_routes(2), full_edit.add_side_routes(2));
+        same(&lean_edit, &full_edit);
+    }
+}
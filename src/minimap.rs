@@ -0,0 +1,227 @@
+//! Downsampled minimaps with fog-of-war reveal regions.
+//!
+//! [`Level::minimap`] shrinks the map by `scale`: each minimap cell covers a
+//! `scale` x `scale` block of tiles and is `.` if any of them is walkable
+//! (so one-tile corridors survive) and `#` otherwise. Room outlines are
+//! given in minimap cells, indexed like `Level::rooms`.
+//!
+//! The walkable tiles are also split into reveal regions for fog of war:
+//! one per zone when the level has zones, otherwise one per room, with each
+//! corridor tile going to the room nearest to it on foot. `region_map` holds
+//! the region of every minimap cell (the region most of its walkable tiles
+//! belong to, `-1` for none), so a game can uncover a whole region when the
+//! player first enters its room or zone. Levels with neither rooms nor zones
+//! have no regions.
+//!
+//! [`ExportOptions::minimap`](crate::export::ExportOptions::minimap) adds the
+//! minimap to the JSON.
+//!
+//! ```
+//! use level_generator::{generate, GeneratorParams};
+//!
+//! let level = generate(&GeneratorParams { seed: Some(1), width: 80, height: 40, ..Default::default() });
+//! let minimap = level.minimap(4);
+//! assert_eq!((minimap.width, minimap.height), (20, 10));
+//! assert_eq!(minimap.regions.len(), level.rooms.len());
+//! ```
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dungeon::{is_walkable_char, Level, Room};
+
+/// Rectangle in minimap cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct MinimapRect {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+/// What a reveal region stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum RegionKey {
+    /// Index into `Level::rooms`
+    Room(usize),
+    /// Id in `Level::zones`
+    Zone(usize),
+}
+
+/// One fog-of-war reveal region.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RevealRegion {
+    /// Region id, as stored in `Minimap::region_map`
+    pub id: usize,
+    pub key: RegionKey,
+    /// Minimap cells in the region
+    pub cell_count: usize,
+    /// Bounding box of those cells (all zero when there are none)
+    pub bounds: MinimapRect,
+}
+
+/// Downsampled map with room outlines and reveal regions; see the module docs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Minimap {
+    /// Tiles per minimap cell along each axis
+    pub scale: u32,
+    /// Width in cells
+    pub width: u32,
+    /// Height in cells
+    pub height: u32,
+    /// Rows of cells: `'.'` where any tile is walkable, `'#'` elsewhere
+    pub cells: Vec<String>,
+    /// Room outlines, indexed like `Level::rooms`
+    pub rooms: Vec<MinimapRect>,
+    /// Region id per cell (row-major, `-1` for cells without walkable tiles)
+    pub region_map: Vec<Vec<i32>>,
+    /// Regions referenced by `region_map`, indexed by id
+    pub regions: Vec<RevealRegion>,
+}
+
+fn outline(room: &Room, scale: i32) -> MinimapRect {
+    let (x0, y0) = (room.x.max(0) / scale, room.y.max(0) / scale);
+    let (x1, y1) = ((room.x + room.w).max(0) + scale - 1, (room.y + room.h).max(0) + scale - 1);
+    MinimapRect { x: x0 as u32, y: y0 as u32, w: (x1 / scale - x0).max(1) as u32, h: (y1 / scale - y0).max(1) as u32 }
+}
+
+impl Level {
+    /// Region per tile: zones if there are any, else the nearest room on foot.
+    fn reveal_partition(&self, walkable: &[Vec<bool>]) -> (Vec<Vec<i32>>, Vec<RegionKey>) {
+        if let (Some(zone_map), false) = (&self.zone_map, self.zones.is_empty()) {
+            let keys = self.zones.iter().map(|z| RegionKey::Zone(z.id)).collect();
+            return (zone_map.clone(), keys);
+        }
+        let mut region: Vec<Vec<i32>> = walkable.iter().map(|row| vec![-1; row.len()]).collect();
+        let mut queue = VecDeque::new();
+        for (i, room) in self.rooms.iter().enumerate() {
+            for y in room.y.max(0)..room.y + room.h {
+                for x in room.x.max(0)..room.x + room.w {
+                    if walkable.get(y as usize).and_then(|row| row.get(x as usize)) == Some(&true) && region[y as usize][x as usize] < 0 {
+                        region[y as usize][x as usize] = i as i32;
+                        queue.push_back((x, y));
+                    }
+                }
+            }
+        }
+        while let Some((x, y)) = queue.pop_front() {
+            let id = region[y as usize][x as usize];
+            for (nx, ny) in self.topology.neighbors(x, y) {
+                if nx >= 0 && ny >= 0 && walkable.get(ny as usize).and_then(|row| row.get(nx as usize)) == Some(&true) && region[ny as usize][nx as usize] < 0 {
+                    region[ny as usize][nx as usize] = id;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+        (region, (0..self.rooms.len()).map(RegionKey::Room).collect())
+    }
+
+    /// Minimap at `scale` tiles per cell (at least 1); see the module docs.
+    pub fn minimap(&self, scale: u32) -> Minimap {
+        let scale = scale.max(1);
+        let s = scale as usize;
+        let walkable: Vec<Vec<bool>> = self.ascii_tiles().iter().map(|row| row.chars().map(is_walkable_char).collect()).collect();
+        let (tile_map, keys) = self.reveal_partition(&walkable);
+        let (width, height) = ((self.width as usize).div_ceil(s), (self.height as usize).div_ceil(s));
+
+        let mut cells = Vec::with_capacity(height);
+        let mut region_map = vec![vec![-1; width]; height];
+        for (cy, cell_regions) in region_map.iter_mut().enumerate() {
+            let mut row = String::with_capacity(width);
+            for (cx, cell_region) in cell_regions.iter_mut().enumerate() {
+                let mut open = false;
+                let mut votes = vec![0usize; keys.len()];
+                for y in cy * s..((cy + 1) * s).min(walkable.len()) {
+                    for x in cx * s..((cx + 1) * s).min(walkable[y].len()) {
+                        if walkable[y][x] {
+                            open = true;
+                            if let Some(votes) = usize::try_from(tile_map[y][x]).ok().and_then(|id| votes.get_mut(id)) {
+                                *votes += 1;
+                            }
+                        }
+                    }
+                }
+                row.push(if open { '.' } else { '#' });
+                // Ties go to the lowest id
+                if let Some((id, _)) = votes.iter().enumerate().filter(|(_, &n)| n > 0).max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(&a.0))) {
+                    *cell_region = id as i32;
+                }
+            }
+            cells.push(row);
+        }
+
+        let regions = keys
+            .into_iter()
+            .enumerate()
+            .map(|(id, key)| {
+                let members: Vec<(u32, u32)> = region_map
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(y, row)| row.iter().enumerate().filter(|(_, &r)| r == id as i32).map(move |(x, _)| (x as u32, y as u32)))
+                    .collect();
+                let bounds = match (members.iter().map(|m| m.0).min(), members.iter().map(|m| m.1).min()) {
+                    (Some(x), Some(y)) => MinimapRect {
+                        x,
+                        y,
+                        w: members.iter().map(|m| m.0).max().unwrap_or(x) - x + 1,
+                        h: members.iter().map(|m| m.1).max().unwrap_or(y) - y + 1,
+                    },
+                    _ => MinimapRect { x: 0, y: 0, w: 0, h: 0 },
+                };
+                RevealRegion { id, key, cell_count: members.len(), bounds }
+            })
+            .collect();
+
+        Minimap {
+            scale,
+            width: width as u32,
+            height: height as u32,
+            cells,
+            rooms: self.rooms.iter().map(|room| outline(room, scale as i32)).collect(),
+            region_map,
+            regions,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GeneratorParams};
+    use crate::export::{to_json_with, ExportOptions};
+    use crate::zones::ZoneStrategy;
+
+    #[test]
+    fn minimaps_downsample_and_partition_the_floor() {
+        let params = GeneratorParams { seed: Some(6), width: 60, height: 30, rooms: 8, ..Default::default() };
+        let level = generate(&params);
+
+        // At scale 1 every walkable tile is in exactly the region of its nearest room
+        let full = level.minimap(1);
+        assert_eq!(full.cells, level.tiles);
+        for (i, room) in level.rooms.iter().enumerate() {
+            let (cx, cy) = room.center();
+            assert_eq!(full.region_map[cy as usize][cx as usize], i as i32);
+            assert_eq!(full.rooms[i], MinimapRect { x: room.x as u32, y: room.y as u32, w: room.w as u32, h: room.h as u32 });
+        }
+        let open = full.cells.iter().flat_map(|row| row.chars()).filter(|&c| c == '.').count();
+        assert_eq!(full.regions.iter().map(|r| r.cell_count).sum::<usize>(), open);
+
+        let small = level.minimap(3);
+        assert_eq!((small.width, small.height), (20, 10));
+        assert!(small.region_map.iter().flatten().zip(small.cells.iter().flat_map(|row| row.chars())).all(|(&r, c)| (r >= 0) == (c == '.')));
+
+        // Zoned levels reveal by zone
+        let zoned = generate(&GeneratorParams { zoning: ZoneStrategy::DistanceBands { band_width: 10 }, ..params });
+        let minimap = zoned.minimap(2);
+        assert_eq!(minimap.regions.iter().map(|r| r.key).collect::<Vec<_>>(), zoned.zones.iter().map(|z| RegionKey::Zone(z.id)).collect::<Vec<_>>());
+
+        let json: serde_json::Value = serde_json::from_str(&to_json_with(&zoned, &ExportOptions { minimap: Some(2), ..Default::default() })).unwrap();
+        assert_eq!(serde_json::from_value::<Minimap>(json["minimap"].clone()).unwrap(), minimap);
+    }
+}
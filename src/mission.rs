@@ -0,0 +1,373 @@
+//! Mission graphs: lock-and-key progression mapped onto the rooms.
+//!
+//! [`Level::assign_mission`] grows an abstract mission with a graph grammar,
+//! then gives each of its steps a room. The grammar starts from the axiom
+//! `Entrance -chain-> Boss -> Exit` and rewrites chains until none are left:
+//!
+//! - *challenge*: `a -chain-> b` becomes `a -> Challenge -chain-> b`
+//! - *lock*: `a -chain-> b` becomes `a -chain-> Key(k)` and
+//!   `a -> Lock(k) -chain-> b`, so the key hangs off a side branch before
+//!   its lock
+//! - *end*: `a -chain-> b` becomes `a -> b`
+//!
+//! Locks only grow on the main line, until [`MissionConfig::locks`] are in;
+//! challenges grow anywhere until [`MissionConfig::challenges`] are used.
+//!
+//! The main line follows the shortest room path from the entrance room to
+//! the exit room. Locks go in rooms on it that every route to the exit
+//! passes through, so they cannot be walked around, and the boss in the
+//! room before the exit (the exit itself when the two are adjacent). Each
+//! key goes in a room reachable without passing its own or a later lock,
+//! preferably a far dead end off the path, and challenges take free rooms
+//! between their neighbors. A step that finds no room is dropped, a lock
+//! together with its key, so small or loopy levels get fewer steps than
+//! asked for. Levels without an entrance and an exit room get no mission.
+//!
+//! ```
+//! use level_generator::mission::{MissionConfig, MissionStep};
+//! use level_generator::{generate, GeneratorParams};
+//!
+//! let level = generate(&GeneratorParams { seed: Some(3), rooms: 12, mission: Some(MissionConfig::default()), ..Default::default() });
+//! let mission = level.mission.as_ref().unwrap();
+//! for node in &mission.nodes {
+//!     if let MissionStep::Key(k) = node.step {
+//!         println!("key {} in room {}", k, node.room);
+//!     }
+//! }
+//! ```
+
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::dungeon::Level;
+use crate::graph::RoomGraph;
+use crate::roles::RoomRole;
+use crate::seeds::{stage_rng, SeedStage};
+
+/// How big a mission to grow; see the module docs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MissionConfig {
+    /// Lock and key pairs on the main line
+    pub locks: u32,
+    /// Challenges spread over the main line and the key branches
+    pub challenges: u32,
+}
+
+impl Default for MissionConfig {
+    fn default() -> Self {
+        Self { locks: 2, challenges: 2 }
+    }
+}
+
+/// What the player does at a mission node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum MissionStep {
+    /// Where the mission starts
+    Entrance,
+    /// A fight or puzzle on the way
+    Challenge,
+    /// Picks up key `k`
+    Key(u32),
+    /// Door that needs key `k`
+    Lock(u32),
+    /// The boss fight before the exit
+    Boss,
+    /// Where the mission ends
+    Exit,
+}
+
+/// A mission step and the room it happens in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct MissionNode {
+    pub step: MissionStep,
+    /// Index into `Level::rooms`
+    pub room: usize,
+}
+
+/// A mission graph: a tree rooted at the entrance.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Mission {
+    pub nodes: Vec<MissionNode>,
+    /// `(from, to)` index pairs into `nodes`: `to` comes after `from`
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl Level {
+    /// Grow a mission from `config` and the level seed and place it on the
+    /// rooms as `self.mission`; see the module docs. Calling it again
+    /// replaces the mission. Tiles and rooms are left alone.
+    pub fn assign_mission(&mut self, config: &MissionConfig) {
+        let mut grammar = Grammar { nodes: Vec::new(), locks: config.locks, challenges: config.challenges, keys: 0, rng: stage_rng(self.seed, SeedStage::Mission) };
+        let entrance = grammar.add(MissionStep::Entrance, None, true);
+        let boss = grammar.chain(entrance, MissionStep::Boss, true);
+        grammar.add(MissionStep::Exit, Some(boss), true);
+        self.mission = place(&grammar.nodes, self);
+    }
+}
+
+/// A mission node before it has a room.
+struct Draft {
+    step: MissionStep,
+    parent: Option<usize>,
+    /// On the line from the entrance to the exit, not a key branch
+    main: bool,
+}
+
+struct Grammar {
+    nodes: Vec<Draft>,
+    locks: u32,
+    challenges: u32,
+    keys: u32,
+    rng: StdRng,
+}
+
+impl Grammar {
+    fn add(&mut self, step: MissionStep, parent: Option<usize>, main: bool) -> usize {
+        self.nodes.push(Draft { step, parent, main });
+        self.nodes.len() - 1
+    }
+
+    /// Rewrite the chain from node `from` to a new `end` node until it is
+    /// gone, returning the `end` node. The main line ends only once its
+    /// locks are in.
+    fn chain(&mut self, mut from: usize, end: MissionStep, main: bool) -> usize {
+        loop {
+            let lock = if main && self.locks > 0 { 3 } else { 0 };
+            let challenge = if self.challenges > 0 { 2 } else { 0 };
+            let stop = if lock > 0 { 0 } else { 1 };
+            let pick = self.rng.random_range(0..lock + challenge + stop);
+            if pick < lock {
+                self.locks -= 1;
+                let k = self.keys;
+                self.keys += 1;
+                self.chain(from, MissionStep::Key(k), false);
+                from = self.add(MissionStep::Lock(k), Some(from), true);
+            } else if pick < lock + challenge {
+                self.challenges -= 1;
+                from = self.add(MissionStep::Challenge, Some(from), main);
+            } else {
+                return self.add(end, Some(from), main);
+            }
+        }
+    }
+}
+
+/// Predecessor of every room reachable from `start` without entering a
+/// `blocked` room; `start` is its own.
+fn search(adjacency: &[Vec<usize>], start: usize, blocked: &[bool]) -> Vec<Option<usize>> {
+    let mut prev = vec![None; adjacency.len()];
+    prev[start] = Some(start);
+    let mut queue = VecDeque::from([start]);
+    while let Some(room) = queue.pop_front() {
+        for &next in &adjacency[room] {
+            if prev[next].is_none() && !blocked[next] {
+                prev[next] = Some(room);
+                queue.push_back(next);
+            }
+        }
+    }
+    prev
+}
+
+/// Rooms from `start` to `goal` along the `search` predecessors.
+fn route(prev: &[Option<usize>], start: usize, goal: usize) -> Option<Vec<usize>> {
+    let mut rooms = vec![goal];
+    while *rooms.last().unwrap() != start {
+        rooms.push(prev[*rooms.last().unwrap()]?);
+    }
+    rooms.reverse();
+    Some(rooms)
+}
+
+/// Give the drafted steps rooms and drop the ones that get none.
+fn place(drafts: &[Draft], level: &Level) -> Option<Mission> {
+    let count = level.rooms.len();
+    let entrance = level.rooms.iter().position(|r| r.role == RoomRole::Entrance)?;
+    let exit = level.rooms.iter().position(|r| r.role == RoomRole::Exit)?;
+    let graph = RoomGraph::from_edges(count, &level.connections);
+    let adjacency = &graph.adjacency;
+    let open = vec![false; count];
+    let path = route(&search(adjacency, entrance, &open), entrance, exit).filter(|p| p.len() > 1)?;
+    let boss_at = path.len().saturating_sub(2).max(1);
+
+    // Rooms on the path no route to the exit gets around
+    let cuts: Vec<usize> = (1..boss_at)
+        .filter(|&i| {
+            let mut blocked = open.clone();
+            blocked[path[i]] = true;
+            search(adjacency, entrance, &blocked)[exit].is_none()
+        })
+        .collect();
+
+    let mut room: Vec<Option<usize>> = vec![None; drafts.len()];
+    let mut used = vec![false; count];
+    let take = |node: usize, r: usize, room: &mut [Option<usize>], used: &mut [bool]| {
+        room[node] = Some(r);
+        used[r] = true;
+    };
+    for (i, draft) in drafts.iter().enumerate() {
+        match draft.step {
+            MissionStep::Entrance => take(i, entrance, &mut room, &mut used),
+            MissionStep::Boss => take(i, path[boss_at], &mut room, &mut used),
+            MissionStep::Exit => take(i, exit, &mut room, &mut used),
+            _ => {}
+        }
+    }
+
+    // Locks spread evenly over the cut rooms; those beyond them are dropped
+    let locks: Vec<usize> = (0..drafts.len()).filter(|&i| matches!(drafts[i].step, MissionStep::Lock(_))).collect();
+    let kept = locks.len().min(cuts.len());
+    let mut lock_at: Vec<usize> = (0..kept).map(|j| cuts[(j + 1) * cuts.len() / (kept + 1)]).collect();
+    for (&node, &at) in locks.iter().zip(&lock_at) {
+        take(node, path[at], &mut room, &mut used);
+    }
+    let mut locks = locks[..kept].to_vec();
+
+    // Keys in the region before their lock, dropping the locks whose key
+    // finds no room
+    let on_path = {
+        let mut on = vec![false; count];
+        path.iter().for_each(|&r| on[r] = true);
+        on
+    };
+    let distance = graph.distances_from(entrance);
+    let key_of = |lock: usize| match drafts[lock].step {
+        MissionStep::Lock(k) => drafts.iter().position(|d| d.step == MissionStep::Key(k)).unwrap(),
+        _ => unreachable!(),
+    };
+    let region = |locks: &[usize], from: usize, room: &[Option<usize>]| {
+        let mut blocked = open.clone();
+        locks[from..].iter().for_each(|&lock| blocked[room[lock].unwrap()] = true);
+        search(adjacency, entrance, &blocked)
+    };
+    let mut j = 0;
+    while j < locks.len() {
+        let reach = region(&locks, j, &room);
+        let key = (0..count)
+            .filter(|&r| reach[r].is_some() && !used[r])
+            .min_by_key(|&r| (on_path[r], graph.degree(r) != 1, std::cmp::Reverse(distance[r]), r));
+        match key {
+            Some(r) => {
+                take(key_of(locks[j]), r, &mut room, &mut used);
+                j += 1;
+            }
+            None => {
+                used[room[locks[j]].take().unwrap()] = false;
+                locks.remove(j);
+                lock_at.remove(j);
+            }
+        }
+    }
+
+    // Main-line challenges on the path between their locks, or elsewhere in
+    // the part of the map their locks enclose
+    let regions: Vec<Vec<Option<usize>>> = (0..=locks.len()).map(|s| region(&locks, s, &room)).collect();
+    let mut segment = 0;
+    for (i, draft) in drafts.iter().enumerate() {
+        match draft.step {
+            MissionStep::Lock(_) if room[i].is_some() => segment += 1,
+            MissionStep::Challenge if draft.main => {
+                let from = if segment == 0 { 0 } else { lock_at[segment - 1] };
+                let to = lock_at.get(segment).copied().unwrap_or(boss_at);
+                let newly_open = |r: usize| regions[segment][r].is_some() && (segment == 0 || regions[segment - 1][r].is_none());
+                let spot = path[from + 1..to]
+                    .iter()
+                    .copied()
+                    .find(|&r| !used[r])
+                    .or_else(|| (0..count).filter(|&r| newly_open(r) && !used[r]).min_by_key(|&r| (distance[r], r)));
+                if let Some(r) = spot {
+                    take(i, r, &mut room, &mut used);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Key branch challenges on the way from the branch start to the key
+    let anchor = |mut node: usize, room: &[Option<usize>]| loop {
+        node = drafts[node].parent.unwrap();
+        if room[node].is_some() {
+            return node;
+        }
+    };
+    for (s, &lock) in locks.iter().enumerate() {
+        let key = key_of(lock);
+        let mut branch = Vec::new();
+        let mut node = key;
+        while !drafts[drafts[node].parent.unwrap()].main {
+            node = drafts[node].parent.unwrap();
+            branch.push(node);
+        }
+        branch.reverse();
+        let start = room[anchor(node, &room)].unwrap();
+        let Some(way) = route(&search(adjacency, start, &regions[s].iter().map(Option::is_none).collect::<Vec<_>>()), start, room[key].unwrap()) else {
+            continue;
+        };
+        let free: Vec<usize> = way[1..way.len() - 1].iter().copied().filter(|&r| !used[r]).collect();
+        for (&node, &r) in branch.iter().zip(&free) {
+            take(node, r, &mut room, &mut used);
+        }
+    }
+
+    // Contract the steps without a room
+    let mut index = vec![usize::MAX; drafts.len()];
+    let mut mission = Mission { nodes: Vec::new(), edges: Vec::new() };
+    for (i, draft) in drafts.iter().enumerate() {
+        let Some(r) = room[i] else { continue };
+        index[i] = mission.nodes.len();
+        if draft.parent.is_some() {
+            mission.edges.push((index[anchor(i, &room)], index[i]));
+        }
+        mission.nodes.push(MissionNode { step: draft.step, room: r });
+    }
+    Some(mission)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GeneratorParams};
+
+    #[test]
+    fn keys_come_before_locks_that_cannot_be_bypassed() {
+        let config = MissionConfig { locks: 2, challenges: 3 };
+        let mut locked = 0;
+        for seed in 0..20 {
+            let params = GeneratorParams { seed: Some(seed), rooms: 14, ..Default::default() };
+            let level = generate(&GeneratorParams { mission: Some(config), ..params.clone() });
+            assert_eq!(level.tiles, generate(&params).tiles);
+            assert_eq!(level.mission, generate(&GeneratorParams { mission: Some(config), ..params }).mission);
+            let Some(mission) = &level.mission else { continue };
+
+            let adjacency = RoomGraph::from_edges(level.rooms.len(), &level.connections).adjacency;
+            let step_room = |step: MissionStep| mission.nodes.iter().find(|n| n.step == step).map(|n| n.room);
+            let (entrance, exit) = (step_room(MissionStep::Entrance).unwrap(), step_room(MissionStep::Exit).unwrap());
+            let rooms: Vec<usize> = mission.nodes.iter().map(|n| n.room).collect();
+            let mut sorted = rooms.clone();
+            sorted.sort();
+            sorted.dedup();
+            assert!(sorted.len() + 1 >= rooms.len(), "steps share rooms: {:?}", mission.nodes);
+            assert_eq!(mission.edges.len(), mission.nodes.len() - 1);
+            assert!(mission.nodes.iter().filter(|n| matches!(n.step, MissionStep::Challenge)).count() <= 3);
+
+            let locks: Vec<(u32, usize)> = mission.nodes.iter().filter_map(|n| if let MissionStep::Lock(k) = n.step { Some((k, n.room)) } else { None }).collect();
+            locked = locked.max(locks.len());
+            for &(k, lock_room) in &locks {
+                let mut blocked = vec![false; level.rooms.len()];
+                blocked[lock_room] = true;
+                assert!(search(&adjacency, entrance, &blocked)[exit].is_none(), "lock {} can be walked around", k);
+                for &(later, r) in &locks {
+                    blocked[r] = later >= k;
+                }
+                let key = step_room(MissionStep::Key(k)).expect("lock without a key");
+                assert!(search(&adjacency, entrance, &blocked)[key].is_some(), "key {} is behind its lock", k);
+            }
+        }
+        assert_eq!(locked, 2);
+    }
+}
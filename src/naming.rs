@@ -0,0 +1,173 @@
+//! Flavor names for levels, rooms, and zones.
+//!
+//! [`Level::assign_names`] gives the level a title (`Level::name`), every
+//! room a name (`Room::name`), and every zone a name in place of its
+//! numbered one, all drawn from the word lists of a [`NameTheme`]. Rooms
+//! with a role get a noun that fits it ("The Gilded Vault" for a treasure
+//! room, "The Silent Gate" for the entrance); other rooms use the theme's
+//! own nouns. Names within a level are unique.
+//!
+//! Words are drawn from the level seed's `names` stream (see
+//! [`crate::seeds`]), so the same seed gives the same names and turning
+//! names on does not change anything else about the level. Setting
+//! `GeneratorParams::naming` names levels as they are generated.
+//!
+//! ```
+//! use level_generator::naming::NameTheme;
+//! use level_generator::{generate, GeneratorParams};
+//!
+//! let level = generate(&GeneratorParams { seed: Some(3), naming: Some(NameTheme::Cavern), ..Default::default() });
+//! assert!(level.name.as_deref().is_some_and(|name| name.starts_with("The ")));
+//! assert!(level.rooms.iter().all(|room| room.name.is_some()));
+//! ```
+
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::dungeon::Level;
+use crate::roles::RoomRole;
+use crate::seeds::{stage_rng, SeedStage};
+
+/// Word lists names are drawn from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NameTheme {
+    /// Crypts, galleries, and armories
+    #[default]
+    Dungeon,
+    /// Grottos, hollows, and pools
+    Cavern,
+    /// Overgrown courts and fallen sanctums
+    Ruins,
+    /// Squares, lanes, and yards
+    Town,
+    /// Brass chutes and spindles, for marble tracks
+    Clockwork,
+}
+
+/// Word lists of one theme
+struct Words {
+    adjectives: &'static [&'static str],
+    rooms: &'static [&'static str],
+    levels: &'static [&'static str],
+}
+
+impl NameTheme {
+    fn words(self) -> Words {
+        match self {
+            NameTheme::Dungeon => Words {
+                adjectives: &["Flooded", "Forgotten", "Silent", "Gilded", "Crumbling", "Bloodstained", "Shadowed", "Iron"],
+                rooms: &["Gallery", "Chamber", "Crypt", "Cellar", "Armory", "Cistern", "Ossuary", "Hall"],
+                levels: &["Depths", "Keep", "Catacombs", "Dungeon"],
+            },
+            NameTheme::Cavern => Words {
+                adjectives: &["Dripping", "Echoing", "Glowing", "Sunken", "Frozen", "Mossy", "Whispering", "Crystal"],
+                rooms: &["Grotto", "Hollow", "Cave", "Chasm", "Pool", "Den", "Fissure", "Cavern"],
+                levels: &["Caverns", "Deeps", "Warrens", "Underways"],
+            },
+            NameTheme::Ruins => Words {
+                adjectives: &["Broken", "Overgrown", "Ancient", "Fallen", "Sunlit", "Weathered", "Toppled", "Ivy-Clad"],
+                rooms: &["Court", "Colonnade", "Shrine", "Atrium", "Terrace", "Sanctum", "Rotunda", "Garden"],
+                levels: &["Ruins", "Remnants", "Citadel", "Temple"],
+            },
+            NameTheme::Town => Words {
+                adjectives: &["Old", "Bright", "Crooked", "Quiet", "High", "Copper", "Lantern", "Market"],
+                rooms: &["Square", "Row", "Corner", "Yard", "Lane", "Court", "Green", "Commons"],
+                levels: &["Quarter", "Borough", "Ward", "Township"],
+            },
+            NameTheme::Clockwork => Words {
+                adjectives: &["Spinning", "Brass", "Tilted", "Ticking", "Polished", "Rattling", "Gleaming", "Geared"],
+                rooms: &["Chute", "Spindle", "Run", "Bowl", "Gearworks", "Spiral", "Funnel", "Basin"],
+                levels: &["Machine", "Works", "Mechanism", "Contraption"],
+            },
+        }
+    }
+}
+
+/// Nouns for rooms whose role calls for one
+fn role_nouns(role: RoomRole) -> Option<&'static [&'static str]> {
+    match role {
+        RoomRole::Normal => None,
+        RoomRole::Entrance => Some(&["Gate", "Threshold", "Antechamber"]),
+        RoomRole::Exit => Some(&["Passage", "Stair", "Portal"]),
+        RoomRole::Hub => Some(&["Crossing", "Forum", "Nexus"]),
+        RoomRole::DeadEnd => Some(&["Nook", "Alcove", "Cell"]),
+        RoomRole::Treasure => Some(&["Vault", "Hoard", "Reliquary"]),
+        RoomRole::Boss => Some(&["Throne", "Lair", "Arena"]),
+        RoomRole::Vault => Some(&["Strongroom", "Sanctum", "Treasury"]),
+    }
+}
+
+/// Nouns for zones, shared by all themes
+const ZONE_NOUNS: &[&str] = &["Reaches", "Wing", "Stretch", "Expanse", "Verge", "Heart"];
+
+/// Draws from the tries a name needs before falling back to a numbered one
+const NAME_TRIES: usize = 16;
+
+fn pick<'a>(rng: &mut StdRng, words: &[&'a str]) -> &'a str {
+    words[rng.random_range(0..words.len())]
+}
+
+/// "The {adjective} {noun}", not yet in `taken`.
+fn unique_name(rng: &mut StdRng, adjectives: &[&str], nouns: &[&str], taken: &mut Vec<String>) -> String {
+    let mut name = String::new();
+    for _ in 0..NAME_TRIES {
+        name = format!("The {} {}", pick(rng, adjectives), pick(rng, nouns));
+        if !taken.contains(&name) {
+            break;
+        }
+    }
+    if taken.contains(&name) {
+        let base = name.clone();
+        name = (2..).map(|n| format!("{} {}", base, n)).find(|n| !taken.contains(n)).expect("some number is free");
+    }
+    taken.push(name.clone());
+    name
+}
+
+impl Level {
+    /// Name the level, its rooms, and its zones from `theme`; see the
+    /// module docs. Calling it again replaces the names.
+    pub fn assign_names(&mut self, theme: NameTheme) {
+        let words = theme.words();
+        let mut rng = stage_rng(self.seed, SeedStage::Names);
+        let mut taken = Vec::new();
+        self.name = Some(unique_name(&mut rng, words.adjectives, words.levels, &mut taken));
+        for room in &mut self.rooms {
+            let nouns = role_nouns(room.role).unwrap_or(words.rooms);
+            room.name = Some(unique_name(&mut rng, words.adjectives, nouns, &mut taken));
+        }
+        for zone in &mut self.zones {
+            zone.name = unique_name(&mut rng, words.adjectives, ZONE_NOUNS, &mut taken);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GeneratorParams};
+    use crate::zones::ZoneStrategy;
+
+    #[test]
+    fn names_are_unique_themed_and_seeded() {
+        let params = GeneratorParams { seed: Some(21), rooms: 20, zoning: ZoneStrategy::DistanceBands { band_width: 8 }, ..Default::default() };
+        let plain = generate(&params);
+        let named = generate(&GeneratorParams { naming: Some(NameTheme::Dungeon), ..params.clone() });
+        assert_eq!(plain.tiles, named.tiles);
+        assert!(plain.name.is_none() && plain.rooms.iter().all(|r| r.name.is_none()));
+
+        let mut names: Vec<&str> = named.rooms.iter().filter_map(|r| r.name.as_deref()).chain(named.zones.iter().map(|z| z.name.as_str())).collect();
+        assert_eq!(names.len(), named.rooms.len() + named.zones.len());
+        names.push(named.name.as_deref().unwrap());
+        assert!(names.iter().enumerate().all(|(i, n)| !names[..i].contains(n)));
+
+        let entrance = named.rooms.iter().find(|r| r.role == RoomRole::Entrance).and_then(|r| r.name.as_deref()).unwrap();
+        assert!(["Gate", "Threshold", "Antechamber"].iter().any(|noun| entrance.ends_with(noun)));
+
+        let mut again = plain.clone();
+        again.assign_names(NameTheme::Dungeon);
+        assert_eq!(again.name, named.name);
+        let json = serde_json::to_string(&named).unwrap();
+        assert_eq!(serde_json::from_str::<Level>(&json).unwrap().rooms[0].name, named.rooms[0].name);
+    }
+}
@@ -0,0 +1,525 @@
+//! Obstacle placement for marble rooms.
+//!
+//! Obstacles come in several shapes (see [`ObstacleShape`]). Each placed
+//! obstacle turns its footprint into `TileType::Obstacle` tiles tagged with
+//! `{"obstacle": id}` metadata and is listed once in `Level::obstacles`.
+//! Placement keeps a ring of at least one passable tile around every
+//! footprint, so the marble can always roll around an obstacle and
+//! obstacles never merge into each other or into walls. On top of that, every
+//! obstacle tile must leave a run of at least `clearance` passable tiles
+//! beside it, both across its row and across its column; an obstacle that
+//! would narrow its own or an earlier obstacle's section below that is tried
+//! elsewhere in the room and dropped if no spot works. Obstacles also keep
+//! a `doorway_radius` distance from the corridor mouths of connected rooms,
+//! the passable tiles just outside a room where a corridor comes in, so a
+//! room's entrance is never blocked right behind the doorway.
+//!
+//! Candidate spots come from Poisson-disk sampling of each room's interior
+//! ([`poisson_disk`]): no two spots are closer than the requested spacing,
+//! and the samples cover the interior without the clumps and empty corners
+//! of independent random picks. Each obstacle is centered on a spot, so the
+//! centers of any two obstacles in a room are at least `spacing` tiles apart.
+//!
+//! Instead of scattering, a room can get a structured field, picked per room
+//! from a list of [`ObstaclePattern`]s: slalom walls alternating from side
+//! to side, a pinball lattice of bumper triangles, or a gauntlet of funnels.
+//! Patterns run along the room's longer side, use `spacing` as their pitch,
+//! fill the whole interior regardless of the density, and obey the same ring
+//! and clearance rules; pieces that break them are left out.
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::dungeon::Room;
+use crate::ramp::DifficultyRamp;
+use crate::tiles::{MarbleTile, TileType};
+
+/// Rooms smaller than this many tiles get no obstacles.
+pub const MIN_OBSTACLE_ROOM_AREA: i32 = 30;
+
+/// Candidates tried around each active sample before it is retired.
+const MAX_ATTEMPTS: usize = 20;
+
+/// Footprint of an obstacle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum ObstacleShape {
+    /// Single-tile pillar
+    #[default]
+    Pillar,
+    /// Solid 2x2 block
+    Block,
+    /// Straight wall three tiles long, horizontal or vertical
+    WallSegment,
+    /// Three bumpers in a triangle with gaps the marble can pass between
+    BumperCluster,
+}
+
+impl ObstacleShape {
+    /// All shapes, in declaration order.
+    pub const ALL: [ObstacleShape; 4] = [
+        ObstacleShape::Pillar,
+        ObstacleShape::Block,
+        ObstacleShape::WallSegment,
+        ObstacleShape::BumperCluster,
+    ];
+
+    /// Tile offsets covered by this shape, relative to its top-left corner.
+    /// `vertical` only affects [`ObstacleShape::WallSegment`].
+    pub fn footprint(&self, vertical: bool) -> Vec<(i32, i32)> {
+        match self {
+            ObstacleShape::Pillar => vec![(0, 0)],
+            ObstacleShape::Block => vec![(0, 0), (1, 0), (0, 1), (1, 1)],
+            ObstacleShape::WallSegment if vertical => vec![(0, 0), (0, 1), (0, 2)],
+            ObstacleShape::WallSegment => vec![(0, 0), (1, 0), (2, 0)],
+            ObstacleShape::BumperCluster => vec![(1, 0), (0, 2), (2, 2)],
+        }
+    }
+}
+
+/// Layout of the obstacles in one room.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum ObstaclePattern {
+    /// Evenly spread random spots, as many as the density asks for
+    #[default]
+    Scatter,
+    /// Wall segments across the room, alternately near one side and the other
+    Slalom,
+    /// Bumper triangles on a staggered lattice
+    Pinball,
+    /// Pairs of pillars closing in toward the middle, funnel after funnel
+    Gauntlet,
+}
+
+impl ObstaclePattern {
+    /// All patterns, in declaration order.
+    pub const ALL: [ObstaclePattern; 4] =
+        [ObstaclePattern::Scatter, ObstaclePattern::Slalom, ObstaclePattern::Pinball, ObstaclePattern::Gauntlet];
+
+    /// Obstacles of a structured pattern in `room`, as center, shape, and
+    /// whether a wall segment stands vertically. Empty for
+    /// [`ObstaclePattern::Scatter`], which depends on the density instead.
+    pub fn layout(&self, room: &Room, spacing: u32) -> Vec<((i32, i32), ObstacleShape, bool)> {
+        // Work in (along, across) coordinates relative to the room, along its longer side
+        let across_x = room.h > room.w;
+        let (long, short) = if across_x { (room.h, room.w) } else { (room.w, room.h) };
+        let place = |u: i32, v: i32| if across_x { (room.x + v, room.y + u) } else { (room.x + u, room.y + v) };
+        let pitch = spacing.max(2) as i32;
+        let mut out = Vec::new();
+        match self {
+            ObstaclePattern::Scatter => {}
+            ObstaclePattern::Slalom => {
+                // Segments cross the flow: vertical when the room runs along x
+                for (k, u) in (2..long - 2).step_by(pitch as usize + 1).enumerate() {
+                    let v = if k % 2 == 0 { 2 } else { short - 3 };
+                    out.push((place(u, v), ObstacleShape::WallSegment, !across_x));
+                }
+            }
+            ObstaclePattern::Pinball => {
+                let step = pitch.max(4);
+                for (row, v) in (2..short - 2).step_by(step as usize).enumerate() {
+                    let offset = if row % 2 == 0 { 0 } else { step / 2 };
+                    for u in (2 + offset..long - 2).step_by(step as usize) {
+                        out.push((place(u, v), ObstacleShape::BumperCluster, false));
+                    }
+                }
+            }
+            ObstaclePattern::Gauntlet => {
+                let (middle, widest) = (short / 2, (short - 1) / 2 - 1);
+                let mut u = 2;
+                while u < long - 2 {
+                    // One funnel: pillar pairs every other tile, narrowing to three open tiles
+                    let mut gap = widest;
+                    while gap >= 2 && u < long - 2 {
+                        out.push((place(u, middle - gap), ObstacleShape::Pillar, false));
+                        out.push((place(u, middle + gap), ObstacleShape::Pillar, false));
+                        gap -= 1;
+                        u += 2;
+                    }
+                    u += pitch;
+                }
+            }
+        }
+        out
+    }
+}
+
+/// A placed obstacle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Obstacle {
+    /// Obstacle id, as stored in member tile metadata
+    pub id: usize,
+    pub shape: ObstacleShape,
+    /// Obstacle tiles as `(x, y)`
+    pub tiles: Vec<(i32, i32)>,
+    /// Marble tiles the obstacle replaced, in `tiles` order, so it can be removed again
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub covered: Vec<MarbleTile>,
+}
+
+/// Whether an obstacle may cover the tile.
+fn free_floor(tile: &MarbleTile) -> bool {
+    tile.tile_type.is_passable() && !matches!(tile.tile_type, TileType::Obstacle | TileType::Funnel)
+}
+
+/// Whether `footprint` can be placed: every member is free floor and every
+/// tile in the ring around it stays passable.
+pub(crate) fn fits(tiles: &[Vec<MarbleTile>], footprint: &[(i32, i32)]) -> bool {
+    let height = tiles.len() as i32;
+    let width = if height > 0 { tiles[0].len() as i32 } else { 0 };
+    let at = |x: i32, y: i32| (x >= 0 && y >= 0 && x < width && y < height).then(|| &tiles[y as usize][x as usize]);
+
+    footprint.iter().all(|&(x, y)| at(x, y).is_some_and(free_floor))
+        && footprint.iter().all(|&(x, y)| {
+            (-1..=1).all(|dy| {
+                (-1..=1).all(|dx| {
+                    let (nx, ny) = (x + dx, y + dy);
+                    footprint.contains(&(nx, ny))
+                        || at(nx, ny).is_some_and(|t| t.tile_type.is_passable() && t.tile_type != TileType::Obstacle)
+                })
+            })
+        })
+}
+
+/// Longest run of passable tiles directly beside `footprint` in either
+/// direction along `(dx, dy)`, measured from every footprint tile.
+fn side_clearance(tiles: &[Vec<MarbleTile>], footprint: &[(i32, i32)], dx: i32, dy: i32) -> u32 {
+    let open_at = |x: i32, y: i32| {
+        y >= 0
+            && (y as usize) < tiles.len()
+            && x >= 0
+            && (x as usize) < tiles[y as usize].len()
+            && free_floor(&tiles[y as usize][x as usize])
+    };
+    let run = |x: i32, y: i32, sx: i32, sy: i32| (1..).take_while(|&i| open_at(x + sx * i, y + sy * i)).count() as u32;
+    footprint
+        .iter()
+        .map(|&(x, y)| {
+            // Start from the edge of the footprint on this line
+            let (mut lo, mut hi) = ((x, y), (x, y));
+            while footprint.contains(&(lo.0 - dx, lo.1 - dy)) {
+                lo = (lo.0 - dx, lo.1 - dy);
+            }
+            while footprint.contains(&(hi.0 + dx, hi.1 + dy)) {
+                hi = (hi.0 + dx, hi.1 + dy);
+            }
+            run(lo.0, lo.1, -dx, -dy).max(run(hi.0, hi.1, dx, dy))
+        })
+        .min()
+        .unwrap_or(u32::MAX)
+}
+
+/// Whether an obstacle already written to `tiles` leaves at least
+/// `clearance` passable tiles across both its rows and its columns.
+fn has_clearance(tiles: &[Vec<MarbleTile>], footprint: &[(i32, i32)], clearance: u32) -> bool {
+    side_clearance(tiles, footprint, 1, 0) >= clearance && side_clearance(tiles, footprint, 0, 1) >= clearance
+}
+
+/// Put back the tiles `obstacles` covered. Obstacles without recorded
+/// tiles (e.g. loaded from older JSON) leave an open platform behind.
+pub fn remove_obstacles(tiles: &mut [Vec<MarbleTile>], obstacles: &[Obstacle]) {
+    for obstacle in obstacles {
+        for (i, &(x, y)) in obstacle.tiles.iter().enumerate() {
+            let Some(tile) = tiles.get_mut(y as usize).and_then(|row| row.get_mut(x as usize)) else { continue };
+            *tile = obstacle
+                .covered
+                .get(i)
+                .cloned()
+                .unwrap_or_else(|| MarbleTile::with_params(TileType::OpenPlatform, tile.elevation, 0, false));
+        }
+    }
+}
+
+/// Points in the rectangle `x0..x1` by `y0..y1` with no two closer than
+/// `spacing` tiles (Euclidean), spread over the whole rectangle: Bridson's
+/// sampling on the tile grid, with candidates drawn from the square ring
+/// between `spacing` and `2 * spacing` around an active point and kept only
+/// at distances in that range. Returned in the order they were found.
+pub fn poisson_disk(x0: i32, y0: i32, x1: i32, y1: i32, spacing: u32, rng: &mut StdRng) -> Vec<(i32, i32)> {
+    if x1 <= x0 || y1 <= y0 {
+        return Vec::new();
+    }
+    let r = spacing.max(1) as i32;
+    let too_close = |a: (i32, i32), b: (i32, i32)| (a.0 - b.0).pow(2) + (a.1 - b.1).pow(2) < r * r;
+    let mut samples = vec![(rng.random_range(x0..x1), rng.random_range(y0..y1))];
+    let mut active = vec![0];
+    while !active.is_empty() {
+        let slot = rng.random_range(0..active.len());
+        let (cx, cy) = samples[active[slot]];
+        let found = (0..MAX_ATTEMPTS).find_map(|_| {
+            let (dx, dy) = (rng.random_range(-2 * r..=2 * r), rng.random_range(-2 * r..=2 * r));
+            let p = (cx + dx, cy + dy);
+            let d2 = dx * dx + dy * dy;
+            (d2 >= r * r && d2 <= 4 * r * r && (x0..x1).contains(&p.0) && (y0..y1).contains(&p.1) && !samples.iter().any(|&q| too_close(p, q)))
+                .then_some(p)
+        });
+        match found {
+            Some(p) => {
+                active.push(samples.len());
+                samples.push(p);
+            }
+            None => {
+                active.swap_remove(slot);
+            }
+        }
+    }
+    samples
+}
+
+/// Place obstacles in rooms of at least [`MIN_OBSTACLE_ROOM_AREA`] tiles,
+/// laying out each room with a pattern picked uniformly from `patterns`
+/// (scatter when empty).
+///
+/// In scattered rooms the obstacle count scales with room area, `density`,
+/// and `ramp` at the room's center (see [`crate::ramp`]), each obstacle's shape is picked uniformly from `shapes`, and
+/// obstacles are centered on [`poisson_disk`] spots of the room interior
+/// `spacing` tiles apart, taken in random order. Other patterns follow
+/// [`ObstaclePattern::layout`]. `clearance` is the passable run every
+/// obstacle must leave beside it (see the module docs). `first_id` is the id
+/// given to the first new obstacle.
+#[allow(clippy::too_many_arguments)]
+pub fn place_obstacles(
+    tiles: &mut [Vec<MarbleTile>],
+    rooms: &[Room],
+    connections: &[(usize, usize)],
+    doorway_radius: u32,
+    shapes: &[ObstacleShape],
+    patterns: &[ObstaclePattern],
+    density: f32,
+    ramp: &DifficultyRamp,
+    clearance: u32,
+    spacing: u32,
+    first_id: usize,
+    rng: &mut StdRng,
+) -> Vec<Obstacle> {
+    let shapes = if shapes.is_empty() { &[ObstacleShape::Pillar][..] } else { shapes };
+    let mut placed = Vec::new();
+    let mouths = if doorway_radius > 0 { corridor_mouths(tiles, rooms, connections) } else { Vec::new() };
+    let radius = doorway_radius as i32;
+    let near_mouth = |&(x, y): &(i32, i32)| mouths.iter().any(|&(mx, my)| (x - mx).abs() <= radius && (y - my).abs() <= radius);
+
+    for room in rooms {
+        let room_area = room.w * room.h;
+        if room_area < MIN_OBSTACLE_ROOM_AREA {
+            continue;
+        }
+        let pattern = match patterns.len() {
+            0 => ObstaclePattern::Scatter,
+            1 => patterns[0],
+            n => patterns[rng.random_range(0..n)],
+        };
+        let (pieces, num_obstacles) = if pattern == ObstaclePattern::Scatter {
+            let mut spots = poisson_disk(room.x + 1, room.y + 1, room.x + room.w - 1, room.y + room.h - 1, spacing, rng);
+            spots.shuffle(rng);
+            let pieces: Vec<_> = spots
+                .into_iter()
+                .map(|spot| {
+                    let shape = if shapes.len() == 1 { shapes[0] } else { shapes[rng.random_range(0..shapes.len())] };
+                    (spot, shape, shape == ObstacleShape::WallSegment && rng.random_bool(0.5))
+                })
+                .collect();
+            let (cx, cy) = room.center();
+            let wanted = room_area as f32 * density * ramp.scale(cx, cy) * 0.1;
+            // A ramp may leave rooms near the start empty
+            (pieces, if ramp.strength() > 0.0 { wanted as usize } else { (wanted as i32).max(1) as usize })
+        } else {
+            (pattern.layout(room, spacing), usize::MAX)
+        };
+
+        let mut count = 0;
+        for ((sx, sy), shape, vertical) in pieces {
+            if count == num_obstacles {
+                break;
+            }
+            let offsets = shape.footprint(vertical);
+            let (span_x, span_y) = offsets.iter().fold((0, 0), |(w, h), &(x, y)| (w.max(x), h.max(y)));
+            let (ox, oy) = (sx - span_x / 2, sy - span_y / 2);
+            let footprint: Vec<(i32, i32)> = offsets.iter().map(|&(dx, dy)| (ox + dx, oy + dy)).collect();
+            if !footprint.iter().all(|&(x, y)| room.expanded(-1).contains(x, y)) {
+                continue;
+            }
+            if !fits(tiles, &footprint) || footprint.iter().any(near_mouth) {
+                continue;
+            }
+
+            let id = first_id + placed.len();
+            let previous: Vec<MarbleTile> = footprint.iter().map(|&(x, y)| tiles[y as usize][x as usize].clone()).collect();
+            for &(x, y) in &footprint {
+                let elevation = tiles[y as usize][x as usize].elevation;
+                tiles[y as usize][x as usize] = MarbleTile::with_params(TileType::Obstacle, elevation, 0, false)
+                    .with_metadata(format!("{{\"obstacle\":{}}}", id));
+            }
+
+            // The new obstacle may also narrow the section beside an earlier one
+            let clear = has_clearance(tiles, &footprint, clearance)
+                && placed.iter().all(|o: &Obstacle| has_clearance(tiles, &o.tiles, clearance));
+            if !clear {
+                for (&(x, y), tile) in footprint.iter().zip(previous) {
+                    tiles[y as usize][x as usize] = tile;
+                }
+                continue;
+            }
+            placed.push(Obstacle { id, shape, tiles: footprint, covered: previous });
+            count += 1;
+        }
+    }
+    placed
+}
+
+/// Passable tiles just outside the rooms that have a connection, where
+/// their corridors come in.
+pub fn corridor_mouths(tiles: &[Vec<MarbleTile>], rooms: &[Room], connections: &[(usize, usize)]) -> Vec<(i32, i32)> {
+    let passable = |x: i32, y: i32| x >= 0 && y >= 0 && tiles.get(y as usize).and_then(|row| row.get(x as usize)).is_some_and(|t| t.tile_type.is_passable());
+    let mut connected = vec![false; rooms.len()];
+    for &(a, b) in connections {
+        connected[a] = true;
+        connected[b] = true;
+    }
+    let mut mouths: Vec<(i32, i32)> = rooms
+        .iter()
+        .zip(connected)
+        .filter(|&(_, connected)| connected)
+        .flat_map(|(room, _)| room.expanded(1).perimeter_tiles())
+        .filter(|&(x, y)| passable(x, y) && !rooms.iter().any(|r| r.contains(x, y)))
+        .collect();
+    mouths.sort_unstable();
+    mouths.dedup();
+    mouths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn shaped_obstacles_keep_a_passable_ring() {
+        let mut tiles = vec![vec![MarbleTile::empty(); 16]; 12];
+        for row in tiles.iter_mut().take(11).skip(1) {
+            for tile in row.iter_mut().take(15).skip(1) {
+                *tile = MarbleTile::with_params(TileType::OpenPlatform, 0, 0, false);
+            }
+        }
+        let rooms = vec![Room { x: 1, y: 1, w: 14, h: 10, ..Default::default() }];
+        let mut rng = StdRng::seed_from_u64(5);
+
+        let obstacles = place_obstacles(&mut tiles, &rooms, &[], 0, &ObstacleShape::ALL, &[], 0.5, &DifficultyRamp::default(), 1, 3, 0, &mut rng);
+        assert!(!obstacles.is_empty());
+        for obstacle in &obstacles {
+            assert_eq!(obstacle.tiles.len(), obstacle.shape.footprint(false).len());
+            for &(x, y) in &obstacle.tiles {
+                assert_eq!(tiles[y as usize][x as usize].tile_type, TileType::Obstacle);
+                // No tile of another obstacle or wall touches this one
+                for (nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+                    let neighbor = &tiles[ny as usize][nx as usize];
+                    assert!(obstacle.tiles.contains(&(nx, ny)) || neighbor.tile_type == TileType::OpenPlatform);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn narrow_sections_keep_the_requested_clearance() {
+        // A 3-tall strip: a pillar in the middle row leaves one tile above and below
+        let mut tiles = vec![vec![MarbleTile::empty(); 24]; 5];
+        for row in tiles.iter_mut().take(4).skip(1) {
+            for tile in row.iter_mut().take(23).skip(1) {
+                *tile = MarbleTile::with_params(TileType::OpenPlatform, 0, 0, false);
+            }
+        }
+        let rooms = vec![Room { x: 0, y: 0, w: 24, h: 5, ..Default::default() }];
+        let mut rng = StdRng::seed_from_u64(2);
+
+        let mut narrow = tiles.clone();
+        let pillars = [ObstacleShape::Pillar];
+        assert!(place_obstacles(&mut narrow, &rooms, &[], 0, &pillars, &[], 1.0, &DifficultyRamp::default(), 2, 2, 0, &mut rng).is_empty());
+        assert_eq!(narrow, tiles);
+
+        let obstacles = place_obstacles(&mut tiles, &rooms, &[], 0, &pillars, &[], 1.0, &DifficultyRamp::default(), 1, 2, 0, &mut rng);
+        assert!(!obstacles.is_empty());
+        assert!(obstacles.iter().all(|o| has_clearance(&tiles, &o.tiles, 1)));
+    }
+
+    #[test]
+    fn obstacles_keep_clear_of_corridor_mouths() {
+        // A room with a corridor coming in from the left at row 6
+        let mut tiles = vec![vec![MarbleTile::empty(); 20]; 12];
+        for row in tiles.iter_mut().take(11).skip(1) {
+            for tile in row.iter_mut().take(19).skip(4) {
+                *tile = MarbleTile::with_params(TileType::OpenPlatform, 0, 0, false);
+            }
+        }
+        for tile in tiles[6].iter_mut().take(4) {
+            *tile = MarbleTile::with_params(TileType::Straight, 0, 0, false);
+        }
+        let rooms = vec![Room { x: 4, y: 1, w: 15, h: 10, ..Default::default() }, Room { x: 0, y: 5, w: 1, h: 3, ..Default::default() }];
+        let connections = [(0, 1)];
+        assert_eq!(corridor_mouths(&tiles, &rooms, &connections), vec![(1, 6), (3, 6)]);
+        assert!(corridor_mouths(&tiles, &rooms, &[]).is_empty());
+
+        let pillars = [ObstacleShape::Pillar];
+        let near = |obstacles: &[Obstacle]| obstacles.iter().flat_map(|o| &o.tiles).any(|&(x, y)| x <= 5 && (4..=8).contains(&y));
+        let mut open = tiles.clone();
+        let obstacles = place_obstacles(&mut open, &rooms, &connections, 0, &pillars, &[], 4.0, &DifficultyRamp::default(), 1, 1, 0, &mut StdRng::seed_from_u64(4));
+        assert!(near(&obstacles));
+        let obstacles = place_obstacles(&mut tiles, &rooms, &connections, 2, &pillars, &[], 4.0, &DifficultyRamp::default(), 1, 1, 0, &mut StdRng::seed_from_u64(4));
+        assert!(!obstacles.is_empty() && !near(&obstacles));
+    }
+
+    #[test]
+    fn poisson_spots_keep_their_spacing_and_cover_the_room() {
+        let mut rng = StdRng::seed_from_u64(9);
+        let spots = poisson_disk(0, 0, 30, 20, 4, &mut rng);
+        assert!(spots.iter().all(|&(x, y)| (0..30).contains(&x) && (0..20).contains(&y)));
+        for (i, a) in spots.iter().enumerate() {
+            assert!(spots[..i].iter().all(|b| (a.0 - b.0).pow(2) + (a.1 - b.1).pow(2) >= 16));
+        }
+        // Every 10x10 block of the room holds a spot: no empty corners
+        for (bx, by) in (0..3).flat_map(|bx| (0..2).map(move |by| (bx * 10, by * 10))) {
+            assert!(spots.iter().any(|&(x, y)| (bx..bx + 10).contains(&x) && (by..by + 10).contains(&y)), "({}, {})", bx, by);
+        }
+
+        // Obstacle centers in a room keep the spacing too
+        let mut tiles = vec![vec![MarbleTile::with_params(TileType::OpenPlatform, 0, 0, false); 32]; 22];
+        let rooms = vec![Room { x: 0, y: 0, w: 32, h: 22, ..Default::default() }];
+        let obstacles = place_obstacles(&mut tiles, &rooms, &[], 0, &[ObstacleShape::Pillar], &[], 1.0, &DifficultyRamp::default(), 1, 4, 0, &mut rng);
+        assert!(obstacles.len() > 10);
+        for (i, a) in obstacles.iter().enumerate() {
+            let (ax, ay) = a.tiles[0];
+            assert!(obstacles[..i].iter().all(|b| (ax - b.tiles[0].0).pow(2) + (ay - b.tiles[0].1).pow(2) >= 16));
+        }
+    }
+
+    #[test]
+    fn patterns_lay_out_their_shapes_along_the_room() {
+        let rooms = vec![Room { x: 0, y: 0, w: 30, h: 12, ..Default::default() }];
+        let open = vec![vec![MarbleTile::with_params(TileType::OpenPlatform, 0, 0, false); 30]; 12];
+        for pattern in ObstaclePattern::ALL {
+            let mut tiles = open.clone();
+            let mut rng = StdRng::seed_from_u64(3);
+            let obstacles = place_obstacles(&mut tiles, &rooms, &[], 0, &[ObstacleShape::Block], &[pattern], 0.1, &DifficultyRamp::default(), 1, 3, 0, &mut rng);
+            let shapes: Vec<ObstacleShape> = obstacles.iter().map(|o| o.shape).collect();
+            match pattern {
+                // The density caps scattered obstacles at 3
+                ObstaclePattern::Scatter => assert!(!shapes.is_empty() && shapes.len() <= 3 && shapes.iter().all(|&s| s == ObstacleShape::Block)),
+                ObstaclePattern::Slalom => {
+                    assert!(shapes.len() >= 5 && shapes.iter().all(|&s| s == ObstacleShape::WallSegment));
+                    // Vertical walls alternating between the top and the bottom of the room
+                    let tops: Vec<bool> = obstacles.iter().map(|o| o.tiles.iter().any(|&(_, y)| y == 1)).collect();
+                    assert!(tops.windows(2).all(|pair| pair[0] != pair[1]));
+                    assert!(obstacles.iter().all(|o| o.tiles.iter().all(|&(x, _)| x == o.tiles[0].0)));
+                }
+                ObstaclePattern::Pinball => assert!(shapes.len() >= 6 && shapes.iter().all(|&s| s == ObstacleShape::BumperCluster)),
+                ObstaclePattern::Gauntlet => {
+                    assert!(shapes.len() >= 8 && shapes.iter().all(|&s| s == ObstacleShape::Pillar));
+                    // Pillars come in pairs mirrored around the middle row
+                    assert!(obstacles.chunks(2).all(|pair| pair[0].tiles[0].0 == pair[1].tiles[0].0 && pair[0].tiles[0].1 + pair[1].tiles[0].1 == 12));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Required openings on the map edges, for stitching external content.
+//!
+//! [`GeneratorParams::edge_openings`] lists gaps the level must leave in its
+//! edges, e.g. where a hand-made hub area joins it. With openings requested,
+//! the outermost ring of tiles is wall (or skirt) apart from the openings,
+//! so the level meets what lies beyond only there. A corridor, as wide as
+//! its opening, runs from every opening to the nearest room: straight in
+//! from the edge to the room's row or column, then across to its center.
+//! Connections, stats, and the marble tiles along the new corridors are
+//! worked out again before the level is named or its mission planned.
+//!
+//! The corner tiles stay wall; openings reaching into a corner or past the
+//! end of their edge are cut short before it.
+//!
+//! ```
+//! use level_generator::gen::{generate, EdgeOpening, GeneratorParams};
+//! use level_generator::Direction;
+//!
+//! let hub = EdgeOpening::centered(Direction::North, 40, 3);
+//! let level = generate(&GeneratorParams { seed: Some(2), edge_openings: vec![hub], ..Default::default() });
+//! assert_eq!(&level.tiles[0][38..43], "#...#");
+//! assert!(level.check_invariants().is_empty());
+//! ```
+//!
+//! [`GeneratorParams::edge_openings`]: crate::dungeon::GeneratorParams::edge_openings
+
+use crate::dungeon::{Grid, Level, TILE_FLOOR, TILE_WALL};
+use crate::tiles::Direction;
+
+/// Gap in one edge of the map; see the module docs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EdgeOpening {
+    /// Edge of the map the opening is on
+    pub side: Direction,
+    /// First tile of the gap along the edge: x on the north and south
+    /// edges, y on the east and west edges
+    pub offset: u32,
+    /// Tiles across the gap
+    pub width: u32,
+}
+
+impl EdgeOpening {
+    /// Opening `width` tiles across, centered on tile `center` of the edge
+    /// (the extra tile of an even width falls after it).
+    pub fn centered(side: Direction, center: u32, width: u32) -> Self {
+        Self { side, offset: center.saturating_sub(width.saturating_sub(1) / 2), width }
+    }
+}
+
+impl Level {
+    /// Wall the outermost ring apart from `openings` and join each opening
+    /// to the nearest room; see the module docs. Does nothing without
+    /// openings.
+    pub fn carve_edge_openings(&mut self, openings: &[EdgeOpening]) {
+        if openings.is_empty() {
+            return;
+        }
+        let (width, height) = (self.width as i32, self.height as i32);
+        let mut grid: Grid = self.ascii_tiles().iter().map(|row| row.chars().collect()).collect();
+        for (y, row) in grid.iter_mut().enumerate() {
+            for (x, tile) in row.iter_mut().enumerate() {
+                let (x, y) = (x as i32, y as i32);
+                if *tile == TILE_FLOOR && (x == 0 || y == 0 || x == width - 1 || y == height - 1) {
+                    *tile = TILE_WALL;
+                }
+            }
+        }
+
+        for opening in openings {
+            let length = match opening.side {
+                Direction::North | Direction::South => width,
+                Direction::East | Direction::West => height,
+            };
+            let offset = opening.offset.clamp(1, length.max(1) as u32) as i32;
+            let across = (opening.width.min(length as u32) as i32).min(length - 1 - offset);
+            if across <= 0 {
+                continue;
+            }
+            let gap = match opening.side {
+                Direction::North => (offset, 0),
+                Direction::East => (width - 1, offset),
+                Direction::South => (offset, height - 1),
+                Direction::West => (0, offset),
+            };
+            let target = self
+                .rooms
+                .iter()
+                .map(|r| r.center())
+                .min_by_key(|&(x, y)| (x - gap.0).abs() + (y - gap.1).abs())
+                .unwrap_or((width / 2, height / 2));
+            // Straight in from the edge to the target's row or column, then across to it
+            let turn = match opening.side {
+                Direction::North | Direction::South => (gap.0, target.1),
+                Direction::East | Direction::West => (target.0, gap.1),
+            };
+            carve_band(&mut grid, gap, turn, across);
+            carve_band(&mut grid, turn, target, across);
+        }
+
+        self.set_ascii_tiles(grid, |level| {
+            level.rebuild_marble_tiles();
+            level.relink();
+        });
+    }
+}
+
+/// Carve the axis-aligned run from `from` to `to`, `width` tiles across,
+/// widening right of and below the run. Tiles off the map are skipped.
+fn carve_band(grid: &mut Grid, from: (i32, i32), to: (i32, i32), width: i32) {
+    let (x0, x1) = (from.0.min(to.0), from.0.max(to.0) + width - 1);
+    let (y0, y1) = (from.1.min(to.1), from.1.max(to.1) + width - 1);
+    let rows = grid.len() as i32;
+    for row in &mut grid[y0.clamp(0, rows) as usize..(y1 + 1).clamp(0, rows) as usize] {
+        let columns = row.len() as i32;
+        row[x0.clamp(0, columns) as usize..(x1 + 1).clamp(0, columns) as usize].fill(TILE_FLOOR);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams};
+    use crate::tiles::TileType;
+
+    #[test]
+    fn every_opening_gets_a_corridor() {
+        let openings = vec![
+            EdgeOpening::centered(Direction::North, 40, 3),
+            EdgeOpening::centered(Direction::West, 12, 2),
+            EdgeOpening { side: Direction::South, offset: 76, width: 9 },
+        ];
+        for mode in [GenerationMode::Classic, GenerationMode::Marble] {
+            let level = generate(&GeneratorParams { seed: Some(1174), mode, enable_elevation: true, edge_openings: openings.clone(), ..Default::default() });
+            assert!(level.check_invariants().is_empty(), "{mode:?}");
+
+            // Floor on the edges exactly at the openings, cut short before the corner
+            let floor = |x: usize, y: usize| level.tiles[y].as_bytes()[x] == TILE_FLOOR as u8;
+            assert_eq!((0..80).filter(|&x| floor(x, 0)).collect::<Vec<_>>(), [39, 40, 41]);
+            assert_eq!((0..25).filter(|&y| floor(0, y)).collect::<Vec<_>>(), [12, 13]);
+            assert_eq!((0..80).filter(|&x| floor(x, 24)).collect::<Vec<_>>(), [76, 77, 78]);
+            assert!((0..25).all(|y| !floor(79, y)));
+            if let Some(tiles) = &level.marble_tiles {
+                assert!((39..42).all(|x| tiles[0][x].tile_type != TileType::Empty));
+            }
+        }
+    }
+}
@@ -0,0 +1,306 @@
+//! Outdoor/overworld generation.
+//!
+//! Instead of carving rooms out of solid rock, overworld mode produces open
+//! terrain:
+//!
+//! 1) Build a heightmap from a few octaves of seeded value noise and
+//!    normalize it to `[0, 1]`.
+//! 2) Classify each tile as water (low), rock (high), or grass, and quantize
+//!    height into the usual `[-max_elevation, max_elevation]` range.
+//! 3) Scatter points of interest (small clearings, stored as `Room`s so roles
+//!    and zoning work unchanged) on dry, walkable land.
+//! 4) Connect consecutive points of interest with winding paths found by a
+//!    cheapest-path search whose cost favors flat ground, avoids water and
+//!    rock, and adds per-tile jitter so paths meander.
+
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::dungeon::{GeneratorParams, Room, TILE_FLOOR, TILE_GRASS, TILE_ROCK, TILE_WATER};
+use crate::roles::RoomRole;
+
+/// Heights below this fraction are water.
+const WATER_LEVEL: f32 = 0.3;
+/// Heights above this fraction are rock.
+const ROCK_LEVEL: f32 = 0.8;
+
+/// Surface type of an overworld tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum TerrainType {
+    Grass,
+    Rock,
+    Water,
+    /// Path or point-of-interest clearing
+    Path,
+}
+
+/// One overworld tile: surface type and elevation.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TerrainTile {
+    pub terrain: TerrainType,
+    pub elevation: i32,
+}
+
+/// Output of the overworld generator.
+pub(crate) struct Overworld {
+    pub tiles: Vec<String>,
+    pub rooms: Vec<Room>,
+    pub terrain: Vec<Vec<TerrainTile>>,
+}
+
+/// Generate a normalized `[0, 1]` heightmap from layered value noise.
+#[allow(clippy::needless_range_loop)]
+fn heightmap(width: usize, height: usize, rng: &mut StdRng) -> Vec<Vec<f32>> {
+    let mut map = vec![vec![0.0f32; width]; height];
+    let base_cell = (width.max(height) / 4).max(4);
+
+    let mut amplitude = 1.0f32;
+    let mut cell = base_cell;
+    for _octave in 0..3 {
+        let lw = width / cell + 2;
+        let lh = height / cell + 2;
+        let lattice: Vec<Vec<f32>> = (0..lh)
+            .map(|_| (0..lw).map(|_| rng.random_range(0.0f32..1.0)).collect())
+            .collect();
+        for y in 0..height {
+            for x in 0..width {
+                let gx = x as f32 / cell as f32;
+                let gy = y as f32 / cell as f32;
+                let (x0, y0) = (gx as usize, gy as usize);
+                let (tx, ty) = (smooth(gx - x0 as f32), smooth(gy - y0 as f32));
+                let top = lerp(lattice[y0][x0], lattice[y0][x0 + 1], tx);
+                let bottom = lerp(lattice[y0 + 1][x0], lattice[y0 + 1][x0 + 1], tx);
+                map[y][x] += lerp(top, bottom, ty) * amplitude;
+            }
+        }
+        amplitude *= 0.5;
+        cell = (cell / 2).max(2);
+    }
+
+    // Normalize to [0, 1]
+    let min = map.iter().flatten().copied().fold(f32::MAX, f32::min);
+    let max = map.iter().flatten().copied().fold(f32::MIN, f32::max);
+    let range = (max - min).max(1e-6);
+    for v in map.iter_mut().flatten() {
+        *v = (*v - min) / range;
+    }
+    map
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn smooth(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Generate an overworld map.
+pub(crate) fn generate_overworld(
+    width: usize,
+    height: usize,
+    min_room: u32,
+    params: &GeneratorParams,
+    rng: &mut StdRng,
+) -> Overworld {
+    let heights = heightmap(width, height, rng);
+    let max_elev = params.max_elevation.max(0) as f32;
+
+    let mut terrain: Vec<Vec<TerrainTile>> = heights
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&h| TerrainTile {
+                    terrain: if h < WATER_LEVEL {
+                        TerrainType::Water
+                    } else if h > ROCK_LEVEL {
+                        TerrainType::Rock
+                    } else {
+                        TerrainType::Grass
+                    },
+                    elevation: ((h * 2.0 - 1.0) * max_elev).round() as i32,
+                })
+                .collect()
+        })
+        .collect();
+
+    // Scatter points of interest on grass, keeping them apart
+    let poi_size = min_room.max(3) as i32;
+    let mut rooms: Vec<Room> = Vec::new();
+    let attempts = params.rooms.saturating_mul(20).max(100);
+    for _ in 0..attempts {
+        if rooms.len() as u32 >= params.rooms {
+            break;
+        }
+        if width as i32 <= poi_size + 2 || height as i32 <= poi_size + 2 {
+            break;
+        }
+        let x = rng.random_range(1..width as i32 - poi_size - 1);
+        let y = rng.random_range(1..height as i32 - poi_size - 1);
+        let candidate = Room { x, y, w: poi_size, h: poi_size, elevation: None, role: RoomRole::Normal, name: None, ceiling: None, guard: None, id: 0 };
+        let (cx, cy) = candidate.center();
+        if terrain[cy as usize][cx as usize].terrain != TerrainType::Grass {
+            continue;
+        }
+        let spacing = poi_size * 2;
+        let too_close = rooms.iter().any(|r| {
+            let (rx, ry) = r.center();
+            (rx - cx).abs() < spacing && (ry - cy).abs() < spacing
+        });
+        if too_close {
+            continue;
+        }
+        let elevation = terrain[cy as usize][cx as usize].elevation;
+        rooms.push(Room { elevation: Some(elevation), ..candidate });
+    }
+    rooms.sort_by_key(|r| r.center().0);
+
+    // Clear each point of interest to its center elevation
+    for room in &rooms {
+        let elevation = room.elevation.unwrap_or(0);
+        for y in room.y..room.y + room.h {
+            for x in room.x..room.x + room.w {
+                terrain[y as usize][x as usize] = TerrainTile { terrain: TerrainType::Path, elevation };
+            }
+        }
+    }
+
+    // Per-tile jitter makes cheapest paths meander
+    let jitter: Vec<Vec<u32>> = (0..height)
+        .map(|_| (0..width).map(|_| rng.random_range(0..15)).collect())
+        .collect();
+    for pair in rooms.windows(2) {
+        let path = winding_path(&terrain, &jitter, pair[0].center(), pair[1].center());
+        for (x, y) in path {
+            let tile = &mut terrain[y][x];
+            tile.terrain = TerrainType::Path;
+        }
+    }
+
+    let tiles = terrain
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|t| match t.terrain {
+                    TerrainType::Grass => TILE_GRASS,
+                    TerrainType::Rock => TILE_ROCK,
+                    TerrainType::Water => TILE_WATER,
+                    TerrainType::Path => TILE_FLOOR,
+                })
+                .collect()
+        })
+        .collect();
+
+    Overworld { tiles, rooms, terrain }
+}
+
+/// Cheapest path between two points over the terrain (Dijkstra, 4-connected).
+fn winding_path(
+    terrain: &[Vec<TerrainTile>],
+    jitter: &[Vec<u32>],
+    from: (i32, i32),
+    to: (i32, i32),
+) -> Vec<(usize, usize)> {
+    let height = terrain.len();
+    let width = if height > 0 { terrain[0].len() } else { 0 };
+    let idx = |x: usize, y: usize| y * width + x;
+
+    let start = (from.0 as usize, from.1 as usize);
+    let goal = (to.0 as usize, to.1 as usize);
+    let mut cost = vec![u32::MAX; width * height];
+    let mut prev = vec![usize::MAX; width * height];
+    let mut heap = BinaryHeap::new();
+    cost[idx(start.0, start.1)] = 0;
+    heap.push(Reverse((0u32, start.0, start.1)));
+
+    while let Some(Reverse((c, x, y))) = heap.pop() {
+        if (x, y) == goal {
+            break;
+        }
+        if c > cost[idx(x, y)] {
+            continue;
+        }
+        for (dx, dy) in [(0i32, -1i32), (1, 0), (0, 1), (-1, 0)] {
+            let nx = x as i32 + dx;
+            let ny = y as i32 + dy;
+            if nx < 0 || ny < 0 || nx as usize >= width || ny as usize >= height {
+                continue;
+            }
+            let (ux, uy) = (nx as usize, ny as usize);
+            let here = terrain[y][x];
+            let there = terrain[uy][ux];
+            let step = 10
+                + 40 * here.elevation.abs_diff(there.elevation)
+                + jitter[uy][ux]
+                + match there.terrain {
+                    TerrainType::Path => 0,
+                    TerrainType::Grass => 5,
+                    TerrainType::Rock => 80,
+                    TerrainType::Water => 250,
+                };
+            let next = c + step;
+            if next < cost[idx(ux, uy)] {
+                cost[idx(ux, uy)] = next;
+                prev[idx(ux, uy)] = idx(x, y);
+                heap.push(Reverse((next, ux, uy)));
+            }
+        }
+    }
+
+    let mut path = Vec::new();
+    let mut at = idx(goal.0, goal.1);
+    if cost[at] == u32::MAX {
+        return path;
+    }
+    while at != usize::MAX {
+        path.push((at % width, at / width));
+        at = prev[at];
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams, TILE_FLOOR};
+
+    #[test]
+    fn overworld_points_of_interest_are_connected() {
+        let params = GeneratorParams {
+            width: 60,
+            height: 40,
+            rooms: 6,
+            seed: Some(21),
+            mode: GenerationMode::Overworld,
+            ..Default::default()
+        };
+        let level = generate(&params);
+        let terrain = level.terrain.as_ref().expect("overworld emits terrain");
+        assert_eq!(terrain.len(), level.height as usize);
+        assert!(level.rooms.len() >= 2);
+
+        // Every point of interest is reachable from the first along path tiles
+        let grid: Vec<Vec<char>> = level.tiles.iter().map(|r| r.chars().collect()).collect();
+        let (sx, sy) = level.rooms[0].center();
+        let mut seen = vec![vec![false; grid[0].len()]; grid.len()];
+        let mut stack = vec![(sx as usize, sy as usize)];
+        seen[sy as usize][sx as usize] = true;
+        while let Some((x, y)) = stack.pop() {
+            for (nx, ny) in [(x + 1, y), (x.wrapping_sub(1), y), (x, y + 1), (x, y.wrapping_sub(1))] {
+                if ny < grid.len() && nx < grid[0].len() && !seen[ny][nx] && grid[ny][nx] == TILE_FLOOR {
+                    seen[ny][nx] = true;
+                    stack.push((nx, ny));
+                }
+            }
+        }
+        for room in &level.rooms {
+            let (cx, cy) = room.center();
+            assert!(seen[cy as usize][cx as usize]);
+        }
+    }
+}
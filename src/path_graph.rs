@@ -0,0 +1,494 @@
+//! Path graph of the track network.
+//!
+//! [`Level::path_graph`] condenses the walkable tiles into a graph a game or
+//! an exporter can reason about without walking tiles. Nodes are the rooms,
+//! the dead ends of corridors, and the hubs where three or more of those
+//! meet; edges are the corridor segments between them.
+//!
+//! Corridor tiles (walkable tiles outside every room) are split into
+//! connected pieces, as in [`crate::graph`]. A piece touching exactly two
+//! rooms or dead ends becomes one edge between them. A piece touching more
+//! gets a junction node on the tile with the smallest total walk to all of
+//! them, and an edge from each. Every edge keeps the tiles of a shortest
+//! walk between its ends (room ends start at the room's center), preferring
+//! to go straight on, so it can be turned into a spline, plus its length,
+//! the elevation gained from start to end, and how often it turns. The walk
+//! is also cut into straight runs, so gameplay code can treat long
+//! straights differently, with speed zones or boosters.
+//!
+//! Edges carry a [`RouteTag`] where they belong to a route: a side route
+//! carved by [`crate::routes`] gets [`RouteTag::Side`] with its index into
+//! `Level::side_routes`, and the edges of the shortest route from the
+//! entrance room to the exit room without side routes get
+//! [`RouteTag::Main`]. Other edges are untagged.
+//!
+//! Edges are undirected; one-way gates are left to
+//! [`Level::reachability`](crate::gates::Reachability).
+//!
+//! ```
+//! use level_generator::{generate, GenerationMode, GeneratorParams, RoomRole};
+//!
+//! let level = generate(&GeneratorParams { seed: Some(3), mode: GenerationMode::Marble, ..Default::default() });
+//! let graph = level.path_graph();
+//! let entrance = level.rooms.iter().position(|r| r.role == RoomRole::Entrance).unwrap();
+//! let exit = level.rooms.iter().position(|r| r.role == RoomRole::Exit).unwrap();
+//! let route = graph.shortest_route(graph.room_node(entrance).unwrap(), graph.room_node(exit).unwrap()).unwrap();
+//! assert!(route.iter().map(|&e| graph.edges[e].length).sum::<u32>() > 0);
+//! ```
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::dungeon::Level;
+use crate::roles::RoomRole;
+use crate::routes::SIDE_ROUTE_WIDTH;
+use crate::visibility::{blocks_movement, tile_elevation};
+
+/// What a [`PathNode`] stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum PathNodeKind {
+    /// A room, by index into `Level::rooms`
+    Room(usize),
+    /// A corridor tile where three or more segments meet
+    Junction,
+    /// The end of a corridor that leads nowhere
+    DeadEnd,
+}
+
+/// Route a [`PathEdge`] belongs to; see the module docs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum RouteTag {
+    /// On the main path from the entrance to the exit
+    Main,
+    /// Along a side route, by index into `Level::side_routes`
+    Side(usize),
+}
+
+/// Node of a [`PathGraph`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PathNode {
+    pub kind: PathNodeKind,
+    /// Tile the node sits on (a room's center tile)
+    pub tile: (i32, i32),
+}
+
+/// Corridor segment between two nodes of a [`PathGraph`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PathEdge {
+    /// Node indices of the two ends
+    pub from: usize,
+    pub to: usize,
+    /// Steps from `from` to `to`
+    pub length: u32,
+    /// Elevation of the `to` end minus that of the `from` end
+    pub elevation_delta: i32,
+    /// Changes of direction along `tiles`
+    pub turns: u32,
+    /// Turns per step, from 0 (straight) to 1 (turning every step)
+    pub curvature: f32,
+    /// Tiles walked from `from` to `to`, both ends included
+    pub tiles: Vec<(i32, i32)>,
+    /// Straight stretches of `tiles` in walking order; their lengths add up
+    /// to `length`
+    pub runs: Vec<StraightRun>,
+    /// Route the edge belongs to, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub route: Option<RouteTag>,
+}
+
+impl PathEdge {
+    /// Tiles of `run`, both ends included.
+    pub fn run_tiles(&self, run: &StraightRun) -> &[(i32, i32)] {
+        &self.tiles[run.start..=run.start + run.length as usize]
+    }
+}
+
+/// Stretch of a [`PathEdge`] walked without turning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct StraightRun {
+    /// Index into `PathEdge::tiles` of the first tile
+    pub start: usize,
+    /// Steps taken in the same direction
+    pub length: u32,
+}
+
+/// Rooms, dead ends, and junctions joined by corridor segments; see the
+/// module docs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PathGraph {
+    /// Rooms first, in room order, then dead ends and junctions
+    pub nodes: Vec<PathNode>,
+    pub edges: Vec<PathEdge>,
+}
+
+impl PathGraph {
+    /// Node of room `room`.
+    pub fn room_node(&self, room: usize) -> Option<usize> {
+        self.nodes.iter().position(|n| n.kind == PathNodeKind::Room(room))
+    }
+
+    /// Indices of the edges at `node`.
+    pub fn edges_at(&self, node: usize) -> impl Iterator<Item = usize> + '_ {
+        (0..self.edges.len()).filter(move |&e| self.edges[e].from == node || self.edges[e].to == node)
+    }
+
+    /// Edges of the shortest route by length from node `from` to node `to`,
+    /// in walking order. `None` when `to` cannot be reached.
+    pub fn shortest_route(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+        let mut best = vec![u32::MAX; self.nodes.len()];
+        let mut via = vec![None; self.nodes.len()];
+        let mut queue = BinaryHeap::new();
+        *best.get_mut(from)? = 0;
+        queue.push(Reverse((0, from)));
+        while let Some(Reverse((d, node))) = queue.pop() {
+            if d > best[node] {
+                continue;
+            }
+            for e in self.edges_at(node) {
+                let edge = &self.edges[e];
+                let next = if edge.from == node { edge.to } else { edge.from };
+                if d + edge.length < best[next] {
+                    best[next] = d + edge.length;
+                    via[next] = Some(e);
+                    queue.push(Reverse((best[next], next)));
+                }
+            }
+        }
+        if *best.get(to)? == u32::MAX {
+            return None;
+        }
+        let mut route = Vec::new();
+        let mut node = to;
+        while let Some(e) = via[node] {
+            route.push(e);
+            node = if self.edges[e].from == node { self.edges[e].to } else { self.edges[e].from };
+        }
+        route.reverse();
+        Some(route)
+    }
+
+    /// Most routes from node `from` to node `to` that share no edge, from
+    /// a max-flow with every edge carrying one route either way. 0 when
+    /// `to` cannot be reached or is `from`.
+    pub fn edge_disjoint_routes(&self, from: usize, to: usize) -> u32 {
+        self.min_cut(from, to).0
+    }
+
+    /// [`PathGraph::edge_disjoint_routes`], plus the nodes reachable from
+    /// `from` and the nodes that still reach `to` once those routes are all
+    /// taken. An edge from the first set to the second adds a route.
+    pub(crate) fn min_cut(&self, from: usize, to: usize) -> (u32, Vec<bool>, Vec<bool>) {
+        let n = self.nodes.len();
+        // Flow along each edge: 1 from `from` to `to`, -1 the other way
+        let mut flow = vec![0i32; self.edges.len()];
+        // Whether edge `e` has room for a route stepping off `node`
+        let open = |flow: &[i32], e: usize, node: usize| if self.edges[e].from == node { flow[e] < 1 } else { flow[e] > -1 };
+        let other = |e: usize, node: usize| if self.edges[e].from == node { self.edges[e].to } else { self.edges[e].from };
+        if from >= n || to >= n || from == to {
+            return (0, vec![false; n], vec![false; n]);
+        }
+        let mut routes = 0;
+        loop {
+            let mut via: Vec<Option<usize>> = vec![None; n];
+            let mut near = vec![false; n];
+            near[from] = true;
+            let mut queue = VecDeque::from([from]);
+            while let Some(node) = queue.pop_front() {
+                for e in self.edges_at(node) {
+                    let next = other(e, node);
+                    if open(&flow, e, node) && !near[next] {
+                        near[next] = true;
+                        via[next] = Some(e);
+                        queue.push_back(next);
+                    }
+                }
+            }
+            if !near[to] {
+                let mut far = vec![false; n];
+                far[to] = true;
+                let mut queue = VecDeque::from([to]);
+                while let Some(node) = queue.pop_front() {
+                    for e in self.edges_at(node) {
+                        let back = other(e, node);
+                        if open(&flow, e, back) && !far[back] {
+                            far[back] = true;
+                            queue.push_back(back);
+                        }
+                    }
+                }
+                return (routes, near, far);
+            }
+            let mut node = to;
+            while let Some(e) = via[node] {
+                node = other(e, node);
+                flow[e] += if self.edges[e].from == node { 1 } else { -1 };
+            }
+            routes += 1;
+        }
+    }
+}
+
+impl Level {
+    /// Graph of the walkable network; see the module docs.
+    pub fn path_graph(&self) -> PathGraph {
+        let (width, height) = (self.width as usize, self.height as usize);
+        let open = |(x, y): (i32, i32)| x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height && !blocks_movement(self, x, y);
+        let room_of = |(x, y): (i32, i32)| self.rooms.iter().position(|r| r.contains(x, y));
+        let corridor = |at: (i32, i32)| open(at) && room_of(at).is_none();
+        let neighbors = |(x, y): (i32, i32)| self.topology.neighbors(x, y).into_iter().filter(|&n| open(n));
+
+        let mut graph = PathGraph::default();
+        for (i, room) in self.rooms.iter().enumerate() {
+            let (cx, cy) = room.center();
+            // Nearest open tile of the room to its center
+            let tile = (room.y..room.y + room.h)
+                .flat_map(|y| (room.x..room.x + room.w).map(move |x| (x, y)))
+                .filter(|&at| open(at))
+                .min_by_key(|&(x, y)| ((x - cx).pow(2) + (y - cy).pow(2), y, x))
+                .unwrap_or((cx, cy));
+            graph.nodes.push(PathNode { kind: PathNodeKind::Room(i), tile });
+        }
+
+        let mut piece = vec![vec![None; width]; height];
+        let mut pieces = 0;
+        for y in 0..height as i32 {
+            for x in 0..width as i32 {
+                if !corridor((x, y)) || piece[y as usize][x as usize].is_some() {
+                    continue;
+                }
+                // Flood one corridor piece and note the rooms and dead ends it touches
+                let mut tiles = vec![(x, y)];
+                piece[y as usize][x as usize] = Some(pieces);
+                let mut ends = Vec::new();
+                let mut i = 0;
+                while i < tiles.len() {
+                    let at = tiles[i];
+                    i += 1;
+                    let around: Vec<(i32, i32)> = neighbors(at).collect();
+                    if around.len() == 1 && corridor(around[0]) {
+                        graph.nodes.push(PathNode { kind: PathNodeKind::DeadEnd, tile: at });
+                        ends.push(graph.nodes.len() - 1);
+                    }
+                    for n in around {
+                        match room_of(n) {
+                            Some(room) => ends.push(room),
+                            None if piece[n.1 as usize][n.0 as usize].is_none() => {
+                                piece[n.1 as usize][n.0 as usize] = Some(pieces);
+                                tiles.push(n);
+                            }
+                            None => {}
+                        }
+                    }
+                }
+                ends.sort_unstable();
+                ends.dedup();
+
+                // Walks from each end through this piece and the rooms at the ends
+                // (only its own room when there is a junction in between)
+                let own = |end: usize| match graph.nodes[end].kind {
+                    PathNodeKind::Room(room) => Some(room),
+                    _ => None,
+                };
+                let rooms: Vec<Option<usize>> = ends.iter().map(|&end| own(end)).collect();
+                let walks: Vec<Vec<Vec<Option<u32>>>> = ends
+                    .iter()
+                    .map(|&end| {
+                        let walkable = |at: (i32, i32)| match room_of(at) {
+                            None => piece[at.1 as usize][at.0 as usize] == Some(pieces),
+                            Some(room) if ends.len() == 2 => rooms.contains(&Some(room)),
+                            Some(room) => own(end) == Some(room),
+                        };
+                        walk(graph.nodes[end].tile, (width, height), |at| open(at) && walkable(at), &neighbors)
+                    })
+                    .collect();
+                let pairs: Vec<(usize, usize)> = match ends.len() {
+                    0 | 1 => Vec::new(),
+                    2 => vec![(0, 1)],
+                    _ => {
+                        let total = |&(x, y): &(i32, i32)| walks.iter().map(|w| w[y as usize][x as usize].map_or(u64::MAX / 8, u64::from)).sum::<u64>();
+                        let hub = tiles.iter().copied().min_by_key(|at| (total(at), at.1, at.0)).unwrap_or((x, y));
+                        graph.nodes.push(PathNode { kind: PathNodeKind::Junction, tile: hub });
+                        ends.push(graph.nodes.len() - 1);
+                        (0..ends.len() - 1).map(|k| (k, ends.len() - 1)).collect()
+                    }
+                };
+                for (a, b) in pairs {
+                    let target = graph.nodes[ends[b]].tile;
+                    if let Some(path) = trace(&walks[a], target, &neighbors) {
+                        graph.edges.push(edge(self, ends[a], ends[b], path));
+                    }
+                }
+                pieces += 1;
+            }
+        }
+        tag_routes(self, &mut graph);
+        graph
+    }
+}
+
+/// Tag the edges of the side routes of `level`, then those of the main
+/// path around them.
+fn tag_routes(level: &Level, graph: &mut PathGraph) {
+    let half = SIDE_ROUTE_WIDTH / 2;
+    for (i, route) in level.side_routes.iter().enumerate() {
+        let ends = [PathNodeKind::Room(route.from), PathNodeKind::Room(route.to)];
+        // Walks may keep to either side of the center line
+        let on_route = |&(x, y): &(i32, i32)| route.tiles.iter().any(|&(rx, ry)| (x - rx).abs() <= half && (y - ry).abs() <= half);
+        for edge in graph.edges.iter_mut().filter(|e| e.route.is_none() && e.tiles.iter().any(on_route)) {
+            if ends.contains(&graph.nodes[edge.from].kind) && ends.contains(&graph.nodes[edge.to].kind) {
+                edge.route = Some(RouteTag::Side(i));
+            }
+        }
+    }
+    let room = |role: RoomRole| level.rooms.iter().position(|r| r.role == role).and_then(|i| graph.room_node(i));
+    let (Some(entrance), Some(exit)) = (room(RoomRole::Entrance), room(RoomRole::Exit)) else { return };
+    let main = PathGraph { nodes: graph.nodes.clone(), edges: graph.edges.iter().filter(|e| e.route.is_none()).cloned().collect() };
+    let untagged: Vec<usize> = (0..graph.edges.len()).filter(|&e| graph.edges[e].route.is_none()).collect();
+    for e in main.shortest_route(entrance, exit).unwrap_or_default() {
+        graph.edges[untagged[e]].route = Some(RouteTag::Main);
+    }
+}
+
+/// Steps from `start` to every tile `allowed` accepts.
+fn walk<N: Iterator<Item = (i32, i32)>>(
+    start: (i32, i32),
+    (width, height): (usize, usize),
+    allowed: impl Fn((i32, i32)) -> bool,
+    neighbors: &impl Fn((i32, i32)) -> N,
+) -> Vec<Vec<Option<u32>>> {
+    let mut distance = vec![vec![None; width]; height];
+    if !allowed(start) {
+        return distance;
+    }
+    distance[start.1 as usize][start.0 as usize] = Some(0);
+    let mut queue = VecDeque::from([start]);
+    while let Some(at) = queue.pop_front() {
+        let d = distance[at.1 as usize][at.0 as usize].unwrap_or(0);
+        for n in neighbors(at) {
+            if allowed(n) && distance[n.1 as usize][n.0 as usize].is_none() {
+                distance[n.1 as usize][n.0 as usize] = Some(d + 1);
+                queue.push_back(n);
+            }
+        }
+    }
+    distance
+}
+
+/// Shortest walk from the start of `distance` to `target`, start first,
+/// going straight on wherever that is as short as turning.
+fn trace<N: Iterator<Item = (i32, i32)>>(
+    distance: &[Vec<Option<u32>>],
+    target: (i32, i32),
+    neighbors: &impl Fn((i32, i32)) -> N,
+) -> Option<Vec<(i32, i32)>> {
+    let at = |(x, y): (i32, i32)| distance[y as usize][x as usize];
+    let mut path = vec![target];
+    let mut d = at(target)?;
+    let mut heading = None;
+    while d > 0 {
+        let here = *path.last().unwrap_or(&target);
+        let back: Vec<(i32, i32)> = neighbors(here).filter(|&n| at(n) == Some(d - 1)).collect();
+        let next = back.iter().copied().find(|&n| Some((n.0 - here.0, n.1 - here.1)) == heading).or(back.first().copied())?;
+        heading = Some((next.0 - here.0, next.1 - here.1));
+        path.push(next);
+        d -= 1;
+    }
+    path.reverse();
+    Some(path)
+}
+
+/// Edge from node `from` to node `to` along `tiles`.
+fn edge(level: &Level, from: usize, to: usize, tiles: Vec<(i32, i32)>) -> PathEdge {
+    let steps: Vec<(i32, i32)> = tiles.windows(2).map(|w| (w[1].0 - w[0].0, w[1].1 - w[0].1)).collect();
+    let turns = steps.windows(2).filter(|w| w[0] != w[1]).count() as u32;
+    let length = steps.len() as u32;
+    let mut runs: Vec<StraightRun> = Vec::new();
+    for (i, step) in steps.iter().enumerate() {
+        match runs.last_mut() {
+            Some(run) if steps[i - 1] == *step => run.length += 1,
+            _ => runs.push(StraightRun { start: i, length: 1 }),
+        }
+    }
+    let (first, last) = (tiles[0], tiles[tiles.len() - 1]);
+    PathEdge {
+        from,
+        to,
+        length,
+        elevation_delta: tile_elevation(level, last.0, last.1) - tile_elevation(level, first.0, first.1),
+        turns,
+        curvature: if length > 1 { turns as f32 / (length - 1) as f32 } else { 0.0 },
+        tiles,
+        runs,
+        route: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::Room;
+
+    #[test]
+    fn corridors_become_edges_between_rooms_junctions_and_dead_ends() {
+        // Three rooms on one branching corridor, plus a stub
+        let mut level = Level::from_ascii(
+            "###############\n\
+             #...#######...#\n\
+             #...#######...#\n\
+             #...........###\n\
+             #####.#####.###\n\
+             #####.#####.###\n\
+             ####...####.###\n\
+             ####...########\n\
+             ###############",
+        )
+        .unwrap();
+        level.rooms = vec![
+            Room { x: 1, y: 1, w: 3, h: 3, ..Default::default() },
+            Room { x: 11, y: 1, w: 3, h: 2, ..Default::default() },
+            Room { x: 4, y: 6, w: 3, h: 2, ..Default::default() },
+        ];
+        let graph = level.path_graph();
+        let kinds: Vec<PathNodeKind> = graph.nodes.iter().map(|n| n.kind).collect();
+        assert_eq!(&kinds[..3], &[PathNodeKind::Room(0), PathNodeKind::Room(1), PathNodeKind::Room(2)]);
+        assert!(kinds.contains(&PathNodeKind::DeadEnd) && kinds.contains(&PathNodeKind::Junction));
+        assert_eq!(graph.nodes.iter().find(|n| n.kind == PathNodeKind::DeadEnd).unwrap().tile, (11, 6));
+
+        // Every end of the corridor hangs off the junction
+        let hub = kinds.iter().position(|&k| k == PathNodeKind::Junction).unwrap();
+        assert_eq!(graph.edges.len(), 4);
+        assert!(graph.edges.iter().all(|e| e.to == hub && e.tiles.len() as u32 == e.length + 1));
+        for e in &graph.edges {
+            assert_eq!(e.tiles[0], graph.nodes[e.from].tile);
+            assert_eq!(*e.tiles.last().unwrap(), graph.nodes[e.to].tile);
+            assert!(e.tiles.windows(2).all(|w| (w[0].0 - w[1].0).abs() + (w[0].1 - w[1].1).abs() == 1));
+        }
+
+        // Room 0 to room 2 runs through the junction
+        let route = graph.shortest_route(graph.room_node(0).unwrap(), graph.room_node(2).unwrap()).unwrap();
+        assert_eq!(route.len(), 2);
+        let straight = graph.edges.iter().find(|e| e.from == graph.room_node(2).unwrap()).unwrap();
+        assert_eq!((straight.turns, straight.curvature), (0, 0.0));
+        assert!(graph.edges.iter().any(|e| e.turns > 0 && e.curvature > 0.0));
+
+        // Runs split each walk at its turns
+        assert_eq!(straight.runs, vec![StraightRun { start: 0, length: straight.length }]);
+        for e in &graph.edges {
+            assert_eq!(e.runs.len() as u32, e.turns + 1);
+            assert_eq!(e.runs.iter().map(|r| r.length).sum::<u32>(), e.length);
+            for run in &e.runs {
+                let tiles = e.run_tiles(run);
+                let step = (tiles[1].0 - tiles[0].0, tiles[1].1 - tiles[0].1);
+                assert!(tiles.windows(2).all(|w| (w[1].0 - w[0].0, w[1].1 - w[0].1) == step));
+            }
+        }
+    }
+}
@@ -0,0 +1,19 @@
+//! The types most programs need, for a glob import.
+//!
+//! ```
+//! use level_generator::prelude::*;
+//!
+//! let level = generate(&GeneratorParams { seed: Some(1), mode: GenerationMode::Marble, ..Default::default() });
+//! assert!(level.check_invariants().is_empty());
+//! let tiles = level.marble_tiles.as_ref().unwrap();
+//! assert!(tiles.iter().flatten().any(|t: &MarbleTile| t.tile_type != TileType::Empty));
+//! ```
+
+pub use crate::analysis::{InvariantViolation, LevelStats};
+pub use crate::editing::{Prefab, Rect};
+pub use crate::gen::{
+    generate, generate_with, generate_with_scratch, try_generate, CancelToken, ConnectionStrategy, GenerationMode, GeneratorParams,
+    GeneratorScratch, GridTopology, Level, LevelGenerator, Room, RoomRole, RoomSizeDistribution,
+};
+pub use crate::stitch::StitchStrategy;
+pub use crate::tiles::{Diag, Direction, MarbleTile, TileType};
@@ -0,0 +1,144 @@
+//! Difficulty that rises along the main path.
+//!
+//! Hazards spread evenly over a level make it feel undesigned. With
+//! [`GeneratorParams::difficulty_ramp`] above 0, marble obstacles and moving
+//! hazards thin out near the start and crowd toward the goal instead.
+//!
+//! Progress comes from the distance field of the level: the walking
+//! distance of a tile from the entrance room's center, divided by that of
+//! the exit room's center (or of the farthest tile without an exit) and
+//! capped at 1. A ramp of strength `r` scales density by `1 - r` at
+//! progress 0 and by `1 + r` at progress 1, linearly between, so the level
+//! as a whole keeps about the density asked for:
+//!
+//! - Scattered obstacles: each room's count is scaled by the progress at its
+//!   center. A room whose scaled count rounds to 0 gets no obstacles, where
+//!   flat density places at least one. Obstacle patterns fill their rooms
+//!   whatever the density and are not ramped.
+//! - Moving hazards: each candidate spot is kept with probability
+//!   `scale / (1 + r)`, so spots near the goal are the likeliest to be used.
+//!
+//! Tiles the entrance cannot reach count as flat (scale 1).
+//!
+//! ```
+//! use level_generator::gen::{generate, GenerationMode, GeneratorParams};
+//!
+//! let level = generate(&GeneratorParams {
+//!     seed: Some(3),
+//!     mode: GenerationMode::Marble,
+//!     enable_obstacles: true,
+//!     difficulty_ramp: 1.0,
+//!     ..Default::default()
+//! });
+//! let ramp = level.difficulty_ramp(1.0);
+//! for obstacle in &level.obstacles {
+//!     let (x, y) = obstacle.tiles[0];
+//!     println!("obstacle {} at progress {:.2}", obstacle.id, ramp.progress(x, y).unwrap_or(0.0));
+//! }
+//! ```
+//!
+//! [`GeneratorParams::difficulty_ramp`]: crate::dungeon::GeneratorParams::difficulty_ramp
+
+use crate::dungeon::{Level, Room};
+use crate::hex::GridTopology;
+use crate::roles::RoomRole;
+use crate::zones::{bfs, start_tile};
+
+/// Path progress of every tile and the ramp strength; see the module docs.
+/// The default is flat.
+#[derive(Debug, Clone, Default)]
+pub struct DifficultyRamp {
+    /// Progress per tile, `None` where the entrance does not reach
+    progress: Vec<Vec<Option<f32>>>,
+    strength: f32,
+}
+
+impl DifficultyRamp {
+    /// Ramp of `strength` (clamped to 0.0 to 1.0, NaN as 0.0) over the
+    /// walkable tiles of `grid`, from the entrance room of `rooms` to the
+    /// exit room. A strength of 0 skips the distance field.
+    pub fn new(grid: &[Vec<char>], rooms: &[Room], topology: GridTopology, strength: f32) -> Self {
+        let strength = if strength.is_nan() { 0.0 } else { strength.clamp(0.0, 1.0) };
+        let Some(start) = start_tile(grid, rooms).filter(|_| strength > 0.0) else { return Self::default() };
+        let (distance, _) = bfs(grid, &[(start, 0)], topology);
+        let at = |(x, y): (i32, i32)| distance.get(usize::try_from(y).ok()?)?.get(usize::try_from(x).ok()?).copied().flatten();
+        let goal = rooms
+            .iter()
+            .find(|r| r.role == RoomRole::Exit)
+            .and_then(|exit| at(exit.center()))
+            .or_else(|| distance.iter().flatten().flatten().copied().max())
+            .unwrap_or(0)
+            .max(1);
+        let progress = distance.iter().map(|row| row.iter().map(|d| d.map(|d| (d as f32 / goal as f32).min(1.0))).collect()).collect();
+        Self { progress, strength }
+    }
+
+    /// Ramp strength, 0 when flat.
+    pub fn strength(&self) -> f32 {
+        self.strength
+    }
+
+    /// Progress of tile `(x, y)` from 0 at the entrance to 1 at the exit,
+    /// or `None` if the entrance does not reach it or the ramp is flat.
+    pub fn progress(&self, x: i32, y: i32) -> Option<f32> {
+        *self.progress.get(usize::try_from(y).ok()?)?.get(usize::try_from(x).ok()?)?
+    }
+
+    /// Density factor at tile `(x, y)`, between `1 - strength` and
+    /// `1 + strength`.
+    pub fn scale(&self, x: i32, y: i32) -> f32 {
+        self.progress(x, y).map_or(1.0, |p| 1.0 + self.strength * (2.0 * p - 1.0))
+    }
+}
+
+impl Level {
+    /// The ramp of `strength` generation used on this level; see the module
+    /// docs.
+    pub fn difficulty_ramp(&self, strength: f32) -> DifficultyRamp {
+        let grid: Vec<Vec<char>> = self.ascii_tiles().iter().map(|row| row.chars().collect()).collect();
+        DifficultyRamp::new(&grid, &self.rooms, self.topology, strength)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams};
+
+    #[test]
+    fn hazards_crowd_toward_the_goal() {
+        let params = GeneratorParams {
+            seed: Some(1184),
+            mode: GenerationMode::Marble,
+            width: 120,
+            height: 60,
+            rooms: 16,
+            enable_obstacles: true,
+            obstacle_density: 0.5,
+            enable_dynamics: true,
+            dynamic_count: 8,
+            ..Default::default()
+        };
+        let flat = generate(&params);
+        let ramped = generate(&GeneratorParams { difficulty_ramp: 1.0, ..params.clone() });
+        assert_eq!(ramped.tiles, generate(&GeneratorParams { difficulty_ramp: 1.0, ..params.clone() }).tiles);
+        assert!(ramped.check_invariants().is_empty());
+
+        // Mean progress of the obstacles moves toward the goal
+        let ramp = ramped.difficulty_ramp(1.0);
+        let mean = |level: &Level| {
+            let spots: Vec<f32> = level.obstacles.iter().filter_map(|o| ramp.progress(o.tiles[0].0, o.tiles[0].1)).collect();
+            spots.iter().sum::<f32>() / spots.len().max(1) as f32
+        };
+        assert!(mean(&ramped) > mean(&flat), "{} <= {}", mean(&ramped), mean(&flat));
+
+        // From 1 - r at the entrance to 1 + r at the exit
+        let entrance = ramped.rooms.iter().find(|r| r.role == RoomRole::Entrance).unwrap().center();
+        let exit = ramped.rooms.iter().find(|r| r.role == RoomRole::Exit).unwrap().center();
+        let half = ramped.difficulty_ramp(0.5);
+        assert_eq!((half.progress(entrance.0, entrance.1), half.progress(exit.0, exit.1)), (Some(0.0), Some(1.0)));
+        assert_eq!((half.scale(entrance.0, entrance.1), half.scale(exit.0, exit.1)), (0.5, 1.5));
+        assert_eq!(ramped.difficulty_ramp(0.0).scale(exit.0, exit.1), 1.0);
+        assert_eq!(ramped.difficulty_ramp(f32::NAN).strength(), 0.0);
+    }
+}
@@ -0,0 +1,198 @@
+//! Partial regeneration: reroll one stage of a generated level and keep the
+//! rest.
+//!
+//! Every stage draws from its own random stream (see [`crate::seeds`]), so a
+//! stage can be rerun on a finished level with a new seed while the layout
+//! stays exactly as it is. Passing the level's own seed reproduces the
+//! original result. Obstacles record the tiles they cover, which is what lets
+//! [`Level::regenerate_obstacles`] take them out again.
+//!
+//! Advanced marble tiles (Y-junctions, merges, one-way gates, loops, and
+//! half-pipes) involve no randomness: they follow from the layout and
+//! elevations. [`Level::regenerate_advanced_tiles`] therefore takes the set
+//! of kinds to place rather than a seed.
+
+use serde_json::Value;
+
+use crate::anchors::add_anchors;
+use crate::gates::orient_gates;
+use crate::dungeon::{GeneratorParams, Level};
+use crate::dynamics::place_dynamics;
+use crate::marble::{grid_to_marble_tiles, AdvancedTileConfig, MarbleTileConfig};
+use crate::obstacles::{place_obstacles, remove_obstacles};
+use crate::rivers::trace_rivers;
+use crate::seeds::{stage_rng, SeedStage};
+use crate::stats::LevelStats;
+use crate::tiles::{compute_walls, TileType};
+use crate::voxel::{add_bridges, VoxelMap};
+
+impl Level {
+    /// Replace the level's obstacles with a fresh placement drawn from
+    /// `seed`, using the obstacle settings in `params` (nothing is placed
+    /// when `params.enable_obstacles` is off).
+    ///
+    /// Walls are recomputed, one-way gates are turned toward the exit again,
+    /// and the river map and voxel map are rebuilt if
+    /// the level has them. Moving hazard markers never overlap obstacles and
+    /// are kept. Does nothing for levels without marble tiles.
+    pub fn regenerate_obstacles(&mut self, params: &GeneratorParams, seed: u64) {
+        let ramp = self.difficulty_ramp(params.difficulty_ramp);
+        let Some(tiles) = self.marble_tiles.as_mut() else { return };
+        remove_obstacles(tiles, &self.obstacles);
+        self.obstacles = if params.enable_obstacles {
+            place_obstacles(
+                tiles,
+                &self.rooms,
+                &self.connections,
+                params.effective_obstacle_doorway_radius(),
+                &params.obstacle_shapes,
+                &params.obstacle_patterns,
+                params.obstacle_density,
+                &ramp,
+                params.effective_obstacle_clearance(),
+                params.obstacle_spacing,
+                0,
+                &mut stage_rng(seed, SeedStage::Obstacles),
+            )
+        } else {
+            Vec::new()
+        };
+        compute_walls(tiles);
+        orient_gates(tiles, &self.rooms);
+        add_anchors(tiles, &self.obstacles);
+
+        if self.river_map.is_some() {
+            self.river_map = Some(trace_rivers(tiles, &self.rooms, params.river_count));
+        }
+        if self.voxels.is_some() {
+            let mut map = VoxelMap::from_tiles(tiles);
+            add_bridges(&mut map, &self.rooms, &self.connections, params.voxel_bridges);
+            self.voxels = Some(map);
+        }
+        self.refresh_stats();
+    }
+
+    /// Replace the level's moving hazard markers with a fresh set drawn from
+    /// `seed` (none when `params.enable_dynamics` is off). Does nothing for
+    /// levels without marble tiles.
+    pub fn regenerate_dynamics(&mut self, params: &GeneratorParams, seed: u64) {
+        let Some(tiles) = &self.marble_tiles else { return };
+        self.dynamics = if params.enable_dynamics {
+            place_dynamics(tiles, &self.rooms, params.dynamic_count, &self.difficulty_ramp(params.difficulty_ramp), &mut stage_rng(seed, SeedStage::Dynamics))
+        } else {
+            Vec::new()
+        };
+    }
+
+    /// Place the advanced tiles of `config` afresh: every advanced tile is
+    /// turned back into its basic piece, and the kinds `config` enables are
+    /// placed again as generation would place them.
+    ///
+    /// Obstacles, structures, launch pads, and bowls are kept, and so are
+    /// elevations. One-way gates are turned toward the exit and prefab
+    /// anchors are rewritten. Does nothing for levels without marble tiles.
+    pub fn regenerate_advanced_tiles(&mut self, config: &AdvancedTileConfig) {
+        const ADVANCED: [TileType; 5] = [TileType::YJunction, TileType::Merge, TileType::OneWayGate, TileType::LoopDeLoop, TileType::HalfPipe];
+        let Some(tiles) = &self.marble_tiles else { return };
+        let grid: Vec<Vec<char>> = self.ascii_tiles().iter().map(|row| row.chars().collect()).collect();
+        let elevation: Vec<Vec<i32>> = tiles.iter().map(|row| row.iter().map(|t| t.elevation).collect()).collect();
+        let enable_elevation = self.rooms.iter().any(|r| r.elevation.is_some());
+        let detect = |advanced: AdvancedTileConfig| {
+            grid_to_marble_tiles(&grid, &MarbleTileConfig { rooms: &self.rooms, elevation: enable_elevation.then_some(&elevation[..]), advanced })
+        };
+        let (basic, fresh) = (detect(AdvancedTileConfig::none()), detect(*config));
+
+        let Some(tiles) = self.marble_tiles.as_mut() else { return };
+        for (y, row) in tiles.iter_mut().enumerate() {
+            for (x, tile) in row.iter_mut().enumerate() {
+                // Tiles later stages turned into something else stay as they are
+                let new = &fresh[y][x];
+                if tile.tile_type != basic[y][x].tile_type && !ADVANCED.contains(&tile.tile_type) {
+                    continue;
+                }
+                if (tile.tile_type, tile.rotation) == (new.tile_type, new.rotation) {
+                    continue;
+                }
+                if let Ok(Value::Object(mut fields)) = serde_json::from_str::<Value>(&tile.metadata) {
+                    fields.remove("anchor");
+                    tile.metadata = if fields.is_empty() { String::new() } else { Value::Object(fields).to_string() };
+                }
+                tile.tile_type = new.tile_type;
+                tile.rotation = new.rotation;
+            }
+        }
+        compute_walls(tiles);
+        orient_gates(tiles, &self.rooms);
+        add_anchors(tiles, &self.obstacles);
+        self.refresh_stats();
+    }
+
+    /// Recompute tile statistics, keeping the generation-time fields.
+    pub(crate) fn refresh_stats(&mut self) {
+        let old = std::mem::take(&mut self.stats);
+        self.stats = LevelStats::compute(self, old.rooms_requested, old.stage_timings);
+        self.stats.room_relaxation = old.room_relaxation;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams, Level};
+    use crate::marble::AdvancedTileConfig;
+    use crate::tiles::TileType;
+
+    #[test]
+    fn rerolled_obstacles_keep_the_layout() {
+        let params = GeneratorParams {
+            seed: Some(5),
+            mode: GenerationMode::Marble,
+            enable_obstacles: true,
+            obstacle_density: 0.5,
+            enable_dynamics: true,
+            channel_width: 3,
+            ..Default::default()
+        };
+        let original = generate(&params);
+        assert!(!original.obstacles.is_empty());
+
+        let mut level = original.clone();
+        level.regenerate_obstacles(&params, 99);
+        assert_eq!(level.tiles, original.tiles);
+        assert_eq!(level.dynamics, original.dynamics);
+        assert_ne!(level.obstacles, original.obstacles);
+        assert_eq!(level.check_invariants(), vec![]);
+
+        // The original seed brings the original placement back, tile for tile
+        level.regenerate_obstacles(&params, original.seed);
+        assert_eq!(level.marble_tiles, original.marble_tiles);
+        assert_eq!(level.obstacles, original.obstacles);
+
+        // Turning obstacles off clears every obstacle tile
+        level.regenerate_obstacles(&GeneratorParams { enable_obstacles: false, ..params.clone() }, 0);
+        assert!(level.obstacles.is_empty());
+        assert!(level.marble_tiles.iter().flatten().flatten().all(|t| t.metadata.is_empty() || !t.metadata.contains("obstacle")));
+
+        level.regenerate_dynamics(&params, original.seed);
+        assert_eq!(level.dynamics.len(), original.dynamics.len());
+    }
+
+    #[test]
+    fn advanced_tiles_are_placed_again() {
+        let params = GeneratorParams { seed: Some(8), mode: GenerationMode::Marble, channel_width: 1, enable_elevation: true, enable_obstacles: true, ..Default::default() };
+        let original = generate(&params);
+        let advanced = |level: &Level| {
+            level.marble_tiles.iter().flatten().flatten().filter(|t| matches!(t.tile_type, TileType::OneWayGate | TileType::LoopDeLoop | TileType::HalfPipe)).count()
+        };
+        assert!(advanced(&original) > 0);
+
+        let mut level = original.clone();
+        level.regenerate_advanced_tiles(&AdvancedTileConfig::none());
+        assert_eq!(advanced(&level), 0);
+        assert_eq!(level.obstacles, original.obstacles);
+        assert_eq!(level.check_invariants(), vec![]);
+
+        // The generator's own set puts every advanced tile back
+        level.regenerate_advanced_tiles(&AdvancedTileConfig { y_junctions: params.enable_y_junctions, ..Default::default() });
+        assert_eq!(level.marble_tiles, original.marble_tiles);
+    }
+}
@@ -0,0 +1,7 @@
+//! Rendering levels for people: ASCII art, the isometric HTML/SVG view, and
+//! seed galleries of thumbnails. Level diffs render themselves with
+//! `LevelDiff::to_ascii` and `to_html`.
+
+pub use crate::gallery::{gallery_html, thumbnail_svg};
+pub use crate::isometric::generate_html;
+pub use crate::visualize::{to_ascii, to_ascii_elevation};
@@ -0,0 +1,133 @@
+//! Rivers traced downhill across the elevation map.
+//!
+//! Each river starts at a high point (the center of one of the highest rooms)
+//! and repeatedly steps to the passable neighbor with the lowest
+//! `(elevation, plateau distance)` key. Plateau distance is the walking
+//! distance, within a flat area, to the nearest tile that has a lower
+//! neighbor, so rivers cross flat rooms toward the downhill exit instead of
+//! stalling. A river ends when it reaches a pit (no lower key around it) or
+//! runs into an existing river, which it joins as a tributary.
+
+use std::collections::VecDeque;
+
+use crate::dungeon::Room;
+use crate::tiles::MarbleTile;
+
+const NEIGHBORS: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+/// Trace up to `count` rivers over `tiles`.
+///
+/// Returns a layer with the river id per tile (row-major, `-1` elsewhere).
+#[allow(clippy::needless_range_loop)]
+pub fn trace_rivers(tiles: &[Vec<MarbleTile>], rooms: &[Room], count: u32) -> Vec<Vec<i32>> {
+    let height = tiles.len();
+    let width = if height > 0 { tiles[0].len() } else { 0 };
+    let mut river_map = vec![vec![-1i32; width]; height];
+
+    let passable = |x: i32, y: i32| -> bool {
+        x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height
+            && tiles[y as usize][x as usize].tile_type.is_passable()
+    };
+    let elev = |x: usize, y: usize| tiles[y][x].elevation;
+
+    // Plateau distance: BFS from tiles with a lower neighbor, staying level
+    let mut plateau = vec![vec![u32::MAX; width]; height];
+    let mut queue = VecDeque::new();
+    for y in 0..height {
+        for x in 0..width {
+            if !passable(x as i32, y as i32) {
+                continue;
+            }
+            let has_lower = NEIGHBORS.iter().any(|&(dx, dy)| {
+                let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                passable(nx, ny) && elev(nx as usize, ny as usize) < elev(x, y)
+            });
+            if has_lower {
+                plateau[y][x] = 0;
+                queue.push_back((x, y));
+            }
+        }
+    }
+    while let Some((x, y)) = queue.pop_front() {
+        for (dx, dy) in NEIGHBORS {
+            let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+            if !passable(nx, ny) {
+                continue;
+            }
+            let (ux, uy) = (nx as usize, ny as usize);
+            if elev(ux, uy) == elev(x, y) && plateau[uy][ux] == u32::MAX {
+                plateau[uy][ux] = plateau[y][x] + 1;
+                queue.push_back((ux, uy));
+            }
+        }
+    }
+
+    // Sources: centers of the highest rooms, one river per room
+    let mut sources: Vec<(usize, usize)> = rooms
+        .iter()
+        .map(|r| r.center())
+        .filter(|&(cx, cy)| passable(cx, cy))
+        .map(|(cx, cy)| (cx as usize, cy as usize))
+        .collect();
+    sources.sort_by_key(|&(x, y)| (std::cmp::Reverse(elev(x, y)), y, x));
+
+    let mut river_id = 0;
+    for (sx, sy) in sources {
+        if river_id as u32 >= count {
+            break;
+        }
+        if river_map[sy][sx] != -1 {
+            continue;
+        }
+
+        let mut path = vec![(sx, sy)];
+        let (mut x, mut y) = (sx, sy);
+        loop {
+            let key = (elev(x, y), plateau[y][x]);
+            let next = NEIGHBORS
+                .iter()
+                .map(|&(dx, dy)| (x as i32 + dx, y as i32 + dy))
+                .filter(|&(nx, ny)| passable(nx, ny))
+                .map(|(nx, ny)| (nx as usize, ny as usize))
+                .map(|(nx, ny)| ((elev(nx, ny), plateau[ny][nx]), (nx, ny)))
+                .filter(|&(k, _)| k < key)
+                .min();
+            let Some((_, (nx, ny))) = next else { break };
+            if river_map[ny][nx] != -1 {
+                break; // joins an existing river
+            }
+            path.push((nx, ny));
+            (x, y) = (nx, ny);
+        }
+
+        // A river that never leaves its source plateau is just a spring
+        if path.iter().any(|&(px, py)| elev(px, py) < elev(sx, sy)) {
+            for (px, py) in path {
+                river_map[py][px] = river_id;
+            }
+            river_id += 1;
+        }
+    }
+
+    river_map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tiles::TileType;
+
+    #[test]
+    fn river_flows_downhill() {
+        // A 1-tile-wide ramp from elevation 3 on the left to 0 on the right
+        let row: Vec<MarbleTile> = [3, 3, 2, 1, 0, 0]
+            .iter()
+            .map(|&e| MarbleTile::with_params(TileType::Straight, e, 1, true))
+            .collect();
+        let tiles = vec![row];
+        let rooms = vec![Room { x: 0, y: 0, w: 2, h: 1, ..Default::default() }];
+
+        let map = trace_rivers(&tiles, &rooms, 1);
+        assert_eq!(map[0], vec![-1, 0, 0, 0, 0, -1]);
+    }
+}
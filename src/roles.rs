@@ -0,0 +1,239 @@
+//! Semantic room roles.
+//!
+//! After generation every room is labelled with a role that quest and
+//! encounter systems can key off. Roles are derived from the room connection
+//! graph (see [`crate::graph`]):
+//!
+//! - **Entrance**: the room nearest `start_point`, or else one picked by
+//!   [`EndpointPlacement`]
+//! - **Exit**: the room farthest (in hops) from the entrance, or the other
+//!   room [`EndpointPlacement`] picked
+//! - **Boss**: the largest room in the far half of the level
+//! - **Hub**: the room with the highest degree (at least 3 connections)
+//! - **Treasure**: the dead-end room farthest from the entrance
+//! - **DeadEnd**: any other room with a single connection
+//!
+//! Rooms that match none of the above are `Normal`. The vault pass (see
+//! [`crate::vaults`]) later turns some rooms into a **Vault**.
+//!
+//! [`Level::main_path_length`] measures the walk from the entrance to the
+//! exit, the level's main path.
+
+use serde::{Deserialize, Serialize};
+
+use crate::dungeon::{Level, Room};
+use crate::graph::RoomGraph;
+use crate::zones::{bfs, start_tile};
+
+/// Semantic role of a room.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum RoomRole {
+    /// Ordinary room with no special meaning
+    #[default]
+    Normal,
+    /// Where the player (or marble) starts
+    Entrance,
+    /// Goal room, farthest from the entrance
+    Exit,
+    /// Best-connected room
+    Hub,
+    /// Room with a single connection
+    DeadEnd,
+    /// Reward room at the end of a side branch
+    Treasure,
+    /// Large, distant arena room
+    Boss,
+    /// Room sealed down to a single entrance (see [`crate::vaults`]); never
+    /// assigned by [`assign_roles`]
+    Vault,
+}
+
+/// How the entrance and exit rooms are picked when no start point is given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndpointPlacement {
+    /// The first room, and the room farthest from it (algorithm versions 1
+    /// and 2)
+    FirstRoom,
+    /// The two rooms the most hops apart in the room graph; ties go to the
+    /// lower room indices, the lower one being the entrance
+    FarthestApart,
+    /// A highest room as the entrance and a lowest as the exit, the most
+    /// hops apart among those; `FarthestApart` when all rooms are level
+    ElevationExtremes,
+}
+
+/// Assign a role to every room in place, picking the entrance and exit as
+/// [`EndpointPlacement::FirstRoom`] does.
+///
+/// `start` is an optional grid-space point used to pick the entrance.
+pub fn assign_roles(rooms: &mut [Room], graph: &RoomGraph, start: Option<(i32, i32)>) {
+    assign_roles_with(rooms, graph, start, EndpointPlacement::FirstRoom);
+}
+
+/// Assign a role to every room in place.
+///
+/// `start` is an optional grid-space point used to pick the entrance;
+/// without one, `placement` picks the entrance and exit.
+pub fn assign_roles_with(rooms: &mut [Room], graph: &RoomGraph, start: Option<(i32, i32)>, placement: EndpointPlacement) {
+    for room in rooms.iter_mut() {
+        room.role = RoomRole::Normal;
+    }
+    if rooms.is_empty() {
+        return;
+    }
+
+    let (entrance, goal) = match start {
+        Some((sx, sy)) => {
+            let nearest = (0..rooms.len()).min_by_key(|&i| {
+                let (cx, cy) = rooms[i].center();
+                (cx - sx).pow(2) + (cy - sy).pow(2)
+            });
+            (nearest.unwrap_or(0), None)
+        }
+        None => endpoints(rooms, graph, placement),
+    };
+    rooms[entrance].role = RoomRole::Entrance;
+    if rooms.len() == 1 {
+        return;
+    }
+
+    let dist = graph.distances_from(entrance);
+    let hops = |i: usize| dist.get(i).copied().flatten();
+    let area = |r: &Room| r.w * r.h;
+
+    // Exit: farthest reachable room; ties go to the larger room
+    let exit = goal.or_else(|| {
+        (0..rooms.len())
+            .filter(|&i| i != entrance && hops(i).is_some())
+            .max_by_key(|&i| (hops(i), area(&rooms[i]), std::cmp::Reverse(i)))
+    });
+    if let Some(exit) = exit {
+        rooms[exit].role = RoomRole::Exit;
+    }
+
+    let max_hops = (0..rooms.len()).filter_map(hops).max().unwrap_or(0);
+    let unassigned = |rooms: &[Room], i: usize| rooms[i].role == RoomRole::Normal;
+
+    // Boss: largest room in the far half of the level
+    let boss = (0..rooms.len())
+        .filter(|&i| unassigned(rooms, i) && hops(i).is_some_and(|d| d * 2 >= max_hops))
+        .max_by_key(|&i| (area(&rooms[i]), hops(i), std::cmp::Reverse(i)));
+    if let Some(boss) = boss {
+        rooms[boss].role = RoomRole::Boss;
+    }
+
+    // Hub: highest degree, only meaningful at 3+ connections
+    let hub = (0..rooms.len())
+        .filter(|&i| unassigned(rooms, i) && graph.degree(i) >= 3)
+        .max_by_key(|&i| (graph.degree(i), std::cmp::Reverse(i)));
+    if let Some(hub) = hub {
+        rooms[hub].role = RoomRole::Hub;
+    }
+
+    // Dead ends; the farthest one holds the treasure
+    let dead_ends: Vec<usize> = (0..rooms.len())
+        .filter(|&i| unassigned(rooms, i) && graph.degree(i) <= 1)
+        .collect();
+    let treasure = dead_ends
+        .iter()
+        .copied()
+        .max_by_key(|&i| (hops(i), std::cmp::Reverse(i)));
+    for &i in &dead_ends {
+        rooms[i].role = if Some(i) == treasure { RoomRole::Treasure } else { RoomRole::DeadEnd };
+    }
+}
+
+/// Entrance and, unless left to the farthest-room rule, exit picked by
+/// `placement`; see [`EndpointPlacement`].
+fn endpoints(rooms: &[Room], graph: &RoomGraph, placement: EndpointPlacement) -> (usize, Option<usize>) {
+    let all: Vec<usize> = (0..rooms.len()).collect();
+    // Most hops from one of `starts` to one of `goals`
+    let farthest = |starts: &[usize], goals: &[usize]| {
+        let mut best: Option<(usize, usize, usize)> = None;
+        for &a in starts {
+            let dist = graph.distances_from(a);
+            for &b in goals {
+                if let Some(hops) = dist.get(b).copied().flatten().filter(|_| a != b) {
+                    if best.is_none_or(|(most, _, _)| hops > most) {
+                        best = Some((hops, a, b));
+                    }
+                }
+            }
+        }
+        best.map(|(_, a, b)| (a, Some(b)))
+    };
+    let pair = match placement {
+        EndpointPlacement::FirstRoom => None,
+        EndpointPlacement::FarthestApart => farthest(&all, &all),
+        EndpointPlacement::ElevationExtremes => {
+            let height = |i: &usize| rooms[*i].elevation.unwrap_or(0);
+            let (top, bottom) = (all.iter().map(height).max().unwrap_or(0), all.iter().map(height).min().unwrap_or(0));
+            let highest: Vec<usize> = all.iter().copied().filter(|i| height(i) == top).collect();
+            let lowest: Vec<usize> = all.iter().copied().filter(|i| height(i) == bottom).collect();
+            farthest(&highest, &lowest).or_else(|| farthest(&all, &all))
+        }
+    };
+    pair.unwrap_or((0, None))
+}
+
+impl Level {
+    /// Hops in the room graph from the entrance room to the exit room, or
+    /// `None` without both or a link between them.
+    pub fn entrance_exit_hops(&self) -> Option<u32> {
+        let entrance = self.rooms.iter().position(|r| r.role == RoomRole::Entrance)?;
+        let exit = self.rooms.iter().position(|r| r.role == RoomRole::Exit)?;
+        let graph = RoomGraph::from_edges(self.rooms.len(), &self.connections);
+        graph.distances_from(entrance)[exit].map(|hops| hops as u32)
+    }
+
+    /// Shortest walk in tiles from the entrance room's center to the exit
+    /// room's center, or `None` without an exit or a path to it.
+    pub fn main_path_length(&self) -> Option<u32> {
+        let exit = self.rooms.iter().find(|r| r.role == RoomRole::Exit)?;
+        let (ex, ey) = exit.center();
+        let grid: Vec<Vec<char>> = self.ascii_tiles().iter().map(|row| row.chars().collect()).collect();
+        let start = start_tile(&grid, &self.rooms)?;
+        let (distance, _) = bfs(&grid, &[(start, 0)], self.topology);
+        *distance.get(ey as usize)?.get(ex as usize)?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn room(x: i32, w: i32) -> Room {
+        Room { x, y: 0, w, h: 4, ..Default::default() }
+    }
+
+    #[test]
+    fn star_layout_roles() {
+        // 0 - 1 - 2 - 3, with 4 and 5 hanging off room 1
+        let mut rooms = vec![room(0, 4), room(10, 4), room(20, 4), room(30, 8), room(40, 4), room(50, 4)];
+        let graph = RoomGraph::from_edges(6, &[(0, 1), (1, 2), (2, 3), (1, 4), (1, 5)]);
+        assign_roles(&mut rooms, &graph, None);
+
+        assert_eq!(rooms[0].role, RoomRole::Entrance);
+        assert_eq!(rooms[3].role, RoomRole::Exit);
+        assert_eq!(rooms[1].role, RoomRole::Hub);
+        assert_eq!(rooms[2].role, RoomRole::Boss);
+        assert_eq!(rooms[4].role, RoomRole::Treasure);
+        assert_eq!(rooms[5].role, RoomRole::DeadEnd);
+
+        // 1 - 0 - 2 - 3: rooms 1 and 3 are the farthest apart, not 0 and 3
+        let path = RoomGraph::from_edges(4, &[(0, 1), (0, 2), (2, 3)]);
+        let mut line = rooms[..4].to_vec();
+        assign_roles_with(&mut line, &path, None, EndpointPlacement::FarthestApart);
+        assert_eq!((line[1].role, line[3].role), (RoomRole::Entrance, RoomRole::Exit));
+
+        // On a slope, the highest room starts and the lowest ends
+        for (i, room) in rooms.iter_mut().enumerate() {
+            room.elevation = Some([3, 2, 1, 2, 0, 1][i]);
+        }
+        assign_roles_with(&mut rooms, &graph, None, EndpointPlacement::ElevationExtremes);
+        assert_eq!((rooms[0].role, rooms[4].role), (RoomRole::Entrance, RoomRole::Exit));
+        assign_roles_with(&mut rooms, &graph, Some((52, 2)), EndpointPlacement::ElevationExtremes);
+        assert_eq!((rooms[5].role, rooms[3].role), (RoomRole::Entrance, RoomRole::Exit));
+    }
+}
@@ -0,0 +1,345 @@
+//! Room WFC mode: wave function collapse over room archetypes.
+//!
+//! Room WFC mode generates in two levels:
+//!
+//! 1) Collapse a coarse grid of room archetypes (see [`RoomArchetype`]).
+//!    Each coarse cell is an archetype with doors on some of its four sides;
+//!    neighbors must agree on the door between them, and a door only joins
+//!    archetypes that fit together: arenas never open onto arenas, and
+//!    vaults hang off hubs and shafts.
+//! 2) Keep the largest group of cells joined by doors; the rest is rock.
+//! 3) Realize each cell in its block of the map: an arena is a large room,
+//!    a hub a small square, a vault a small room at the far end from its
+//!    door, and a shaft a long three-wide hall between its two doors. Every
+//!    door becomes a one-wide corridor between the rooms it joins.
+//!
+//! The coarse grid settles the level's overall shape before any tile is
+//! carved, so the rooms form one layout spread over the map and the kinds
+//! of space alternate, which placing rooms one by one does not give. Blocks are
+//! `max_room + 2` tiles on a side; the room count follows from the map size
+//! and the collapse, not from `rooms`. [`collapse_archetypes`] returns the
+//! coarse grid alone, e.g. to realize it with another generator.
+//!
+//! ```
+//! use level_generator::room_wfc::{collapse_archetypes, RoomArchetype};
+//! use rand::SeedableRng;
+//!
+//! let cells = collapse_archetypes(6, 3, &mut rand::rngs::StdRng::seed_from_u64(2));
+//! // Vaults have a single door
+//! assert!(cells.iter().flatten().filter(|c| c.archetype == RoomArchetype::Vault).all(|c| c.doors.iter().filter(|&&d| d).count() == 1));
+//! ```
+
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::Serialize;
+use std::collections::VecDeque;
+
+use crate::dungeon::{carve_horizontal_tunnel, carve_vertical_tunnel, Room, TILE_FLOOR, TILE_WALL};
+
+/// Steps `[up, right, down, left]`, the order of [`ArchetypeCell::doors`].
+const DELTAS: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+/// Kind of space a coarse cell of room WFC mode becomes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum RoomArchetype {
+    /// Solid rock, no doors
+    Empty,
+    /// Large open room with any doors
+    Arena,
+    /// Small crossing room with three or four doors
+    Hub,
+    /// Small room with a single door onto a hub or shaft
+    Vault,
+    /// Long narrow hall with doors at both ends
+    Shaft,
+}
+
+impl RoomArchetype {
+    pub const ALL: [RoomArchetype; 5] = [RoomArchetype::Empty, RoomArchetype::Arena, RoomArchetype::Hub, RoomArchetype::Vault, RoomArchetype::Shaft];
+
+    /// Relative chance of each door layout of the archetype when a cell
+    /// collapses.
+    fn weight(self) -> u32 {
+        match self {
+            RoomArchetype::Empty => 2,
+            RoomArchetype::Arena => 1,
+            RoomArchetype::Hub => 3,
+            RoomArchetype::Vault => 2,
+            RoomArchetype::Shaft => 6,
+        }
+    }
+
+    /// Whether the archetype can have doors on the sides set in `doors`.
+    fn allows(self, doors: [bool; 4]) -> bool {
+        let count = doors.iter().filter(|&&d| d).count();
+        match self {
+            RoomArchetype::Empty => count == 0,
+            RoomArchetype::Arena => true,
+            RoomArchetype::Hub => count >= 3,
+            RoomArchetype::Vault => count == 1,
+            RoomArchetype::Shaft => doors == [true, false, true, false] || doors == [false, true, false, true],
+        }
+    }
+
+    /// Whether a door may join this archetype to `other`.
+    fn joins(self, other: RoomArchetype) -> bool {
+        use RoomArchetype::*;
+        !matches!((self, other), (Arena, Arena) | (Arena, Vault) | (Vault, Arena) | (Vault, Vault))
+    }
+}
+
+/// One coarse cell: its archetype and which of its sides, `[up, right,
+/// down, left]`, have a door.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ArchetypeCell {
+    pub archetype: RoomArchetype,
+    pub doors: [bool; 4],
+}
+
+impl ArchetypeCell {
+    const EMPTY: ArchetypeCell = ArchetypeCell { archetype: RoomArchetype::Empty, doors: [false; 4] };
+}
+
+/// Every archetype with every door layout it allows.
+fn variants() -> Vec<ArchetypeCell> {
+    RoomArchetype::ALL
+        .into_iter()
+        .flat_map(|archetype| (0..16u8).map(move |bits| ArchetypeCell { archetype, doors: std::array::from_fn(|d| bits & 1 << d != 0) }))
+        .filter(|cell| cell.archetype.allows(cell.doors))
+        .collect()
+}
+
+/// Collapse a `cols` x `rows` grid of room archetypes, with no doors leading
+/// off the grid. Falls back to solid rock if every attempt contradicts
+/// itself.
+#[allow(clippy::needless_range_loop)]
+pub fn collapse_archetypes(cols: usize, rows: usize, rng: &mut StdRng) -> Vec<Vec<ArchetypeCell>> {
+    let variants = variants();
+    let mask = |keep: &dyn Fn(&ArchetypeCell) -> bool| variants.iter().enumerate().filter(|(_, v)| keep(v)).fold(0u32, |m, (i, _)| m | 1 << i);
+    // compat[v][dir] = variants that may sit next to variant v in `dir`
+    let compat: Vec<[u32; 4]> = variants
+        .iter()
+        .map(|a| std::array::from_fn(|dir| mask(&|b| a.doors[dir] == b.doors[(dir + 2) % 4] && (!a.doors[dir] || a.archetype.joins(b.archetype)))))
+        .collect();
+    let neighbor = |i: usize, dir: usize| {
+        let (x, y) = ((i % cols) as i32 + DELTAS[dir].0, (i / cols) as i32 + DELTAS[dir].1);
+        (x >= 0 && y >= 0 && (x as usize) < cols && (y as usize) < rows).then(|| y as usize * cols + x as usize)
+    };
+
+    'attempt: for _ in 0..10 {
+        // No doors off the grid
+        let mut domains: Vec<u32> = (0..cols * rows)
+            .map(|i| (0..4).filter(|&dir| neighbor(i, dir).is_none()).fold(mask(&|_| true), |d, dir| d & mask(&|v| !v.doors[dir])))
+            .collect();
+        let mut queue = VecDeque::new();
+        // Fewest options left, the first cell on ties
+        while let Some(i) = (0..domains.len()).filter(|&i| domains[i].count_ones() > 1).min_by_key(|&i| (domains[i].count_ones(), i)) {
+            let options: Vec<usize> = (0..variants.len()).filter(|&v| domains[i] & 1 << v != 0).collect();
+            let total: u32 = options.iter().map(|&v| variants[v].archetype.weight()).sum();
+            let mut roll = rng.random_range(0..total);
+            let choice = options.iter().copied().find(|&v| match roll.checked_sub(variants[v].archetype.weight()) {
+                Some(rest) => {
+                    roll = rest;
+                    false
+                }
+                None => true,
+            });
+            domains[i] = 1 << choice.expect("roll is below the total weight");
+
+            queue.push_back(i);
+            while let Some(i) = queue.pop_front() {
+                for dir in 0..4 {
+                    let Some(n) = neighbor(i, dir) else { continue };
+                    let allowed = (0..variants.len()).filter(|&v| domains[i] & 1 << v != 0).fold(0, |m, v| m | compat[v][dir]);
+                    let narrowed = domains[n] & allowed;
+                    if narrowed == 0 {
+                        continue 'attempt;
+                    }
+                    if narrowed != domains[n] {
+                        domains[n] = narrowed;
+                        queue.push_back(n);
+                    }
+                }
+            }
+        }
+        if domains.contains(&0) {
+            continue;
+        }
+        let cells: Vec<ArchetypeCell> = domains.iter().map(|d| variants[d.trailing_zeros() as usize]).collect();
+        return cells.chunks(cols.max(1)).map(<[ArchetypeCell]>::to_vec).collect();
+    }
+    vec![vec![ArchetypeCell::EMPTY; cols]; rows]
+}
+
+/// Turn every cell outside the largest group joined by doors into rock; a
+/// grid with no rooms at all gets a lone arena in the middle.
+fn keep_largest_group(cells: &mut [Vec<ArchetypeCell>]) {
+    let (rows, cols) = (cells.len(), cells.first().map_or(0, Vec::len));
+    let mut group = vec![vec![usize::MAX; cols]; rows];
+    let mut sizes = Vec::new();
+    for y0 in 0..rows {
+        for x0 in 0..cols {
+            if group[y0][x0] != usize::MAX || cells[y0][x0].archetype == RoomArchetype::Empty {
+                continue;
+            }
+            let id = sizes.len();
+            group[y0][x0] = id;
+            let mut size = 0;
+            let mut queue = VecDeque::from([(x0, y0)]);
+            while let Some((x, y)) = queue.pop_front() {
+                size += 1;
+                for (dir, (dx, dy)) in DELTAS.iter().enumerate() {
+                    let (nx, ny) = ((x as i32 + dx) as usize, (y as i32 + dy) as usize);
+                    if cells[y][x].doors[dir] && group[ny][nx] == usize::MAX {
+                        group[ny][nx] = id;
+                        queue.push_back((nx, ny));
+                    }
+                }
+            }
+            sizes.push(size);
+        }
+    }
+    let Some(largest) = (0..sizes.len()).max_by_key(|&id| (sizes[id], std::cmp::Reverse(id))) else {
+        if rows > 0 && cols > 0 {
+            cells[rows / 2][cols / 2].archetype = RoomArchetype::Arena;
+        }
+        return;
+    };
+    for (y, row) in cells.iter_mut().enumerate() {
+        for (x, cell) in row.iter_mut().enumerate() {
+            if group[y][x] != largest {
+                *cell = ArchetypeCell::EMPTY;
+            }
+        }
+    }
+}
+
+/// The room `cell` becomes in the `side` x `side` block at `(bx, by)`.
+fn realize(cell: &ArchetypeCell, bx: i32, by: i32, side: i32, min_room: i32, rng: &mut StdRng) -> Option<Room> {
+    // One tile of rock around every room keeps neighboring blocks apart
+    let inner = side - 2;
+    let (x0, y0) = (bx + 1, by + 1);
+    let room = |x: i32, y: i32, w: i32, h: i32| Room { x, y, w, h, ..Default::default() };
+    let centered = |size: i32| (inner - size) / 2;
+    match cell.archetype {
+        RoomArchetype::Empty => None,
+        RoomArchetype::Arena => {
+            let low = (inner * 2 / 3).max(min_room).min(inner);
+            let (w, h) = (rng.random_range(low..=inner), rng.random_range(low..=inner));
+            Some(room(x0 + rng.random_range(0..=inner - w), y0 + rng.random_range(0..=inner - h), w, h))
+        }
+        RoomArchetype::Hub => {
+            let size = (inner / 2).max(3).min(inner);
+            Some(room(x0 + centered(size), y0 + centered(size), size, size))
+        }
+        RoomArchetype::Vault => {
+            // At the far end of the block from the door
+            let size = (inner / 3).max(2).min(inner);
+            let far = inner - size;
+            let (x, y) = match cell.doors {
+                [true, ..] => (centered(size), far),
+                [_, true, ..] => (0, centered(size)),
+                [_, _, true, _] => (centered(size), 0),
+                _ => (far, centered(size)),
+            };
+            Some(room(x0 + x, y0 + y, size, size))
+        }
+        RoomArchetype::Shaft => {
+            let narrow = inner.min(3);
+            match cell.doors[0] {
+                true => Some(room(x0 + centered(narrow), y0, narrow, inner)),
+                false => Some(room(x0, y0 + centered(narrow), inner, narrow)),
+            }
+        }
+    }
+}
+
+/// Generate a room WFC map: the tiles and the realized rooms.
+pub(crate) fn generate_room_wfc(width: usize, height: usize, min_room: u32, max_room: u32, rng: &mut StdRng) -> (Vec<String>, Vec<Room>) {
+    let side = (max_room as usize + 2).max(7).min(width).min(height);
+    let (cols, rows) = (width / side, height / side);
+    let mut cells = collapse_archetypes(cols, rows, rng);
+    keep_largest_group(&mut cells);
+
+    // The coarse grid sits in the middle of the map
+    let (ox, oy) = ((width - cols * side) / 2, (height - rows * side) / 2);
+    let mut grid = vec![vec![TILE_WALL; width]; height];
+    let mut placed: Vec<Vec<Option<Room>>> = vec![vec![None; cols]; rows];
+    for (y, row) in cells.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            let (bx, by) = ((ox + x * side) as i32, (oy + y * side) as i32);
+            placed[y][x] = realize(cell, bx, by, side as i32, min_room as i32, rng);
+            if let Some(room) = &placed[y][x] {
+                for ty in room.y..room.y + room.h {
+                    for tx in room.x..room.x + room.w {
+                        grid[ty as usize][tx as usize] = TILE_FLOOR;
+                    }
+                }
+            }
+        }
+    }
+
+    // Doors right and down: along the row of this room's center, then along
+    // the column of the neighbor's
+    for (y, row) in cells.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            for dir in [1, 2] {
+                if !cell.doors[dir] {
+                    continue;
+                }
+                let (nx, ny) = (x + (dir == 1) as usize, y + (dir == 2) as usize);
+                let (Some(from), Some(to)) = (&placed[y][x], &placed[ny][nx]) else { continue };
+                let ((fx, fy), (tx, ty)) = (from.center(), to.center());
+                if dir == 1 {
+                    carve_horizontal_tunnel(&mut grid, fx, tx, fy);
+                    carve_vertical_tunnel(&mut grid, fy, ty, tx);
+                } else {
+                    carve_vertical_tunnel(&mut grid, fy, ty, fx);
+                    carve_horizontal_tunnel(&mut grid, fx, tx, ty);
+                }
+            }
+        }
+    }
+
+    let tiles = grid.iter().map(|row| row.iter().collect()).collect();
+    (tiles, placed.into_iter().flatten().flatten().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams};
+    use rand::SeedableRng;
+
+    #[test]
+    fn archetypes_follow_their_adjacency_rules() {
+        for seed in 0..20 {
+            let cells = collapse_archetypes(7, 4, &mut StdRng::seed_from_u64(seed));
+            assert!(cells.iter().flatten().any(|c| c.archetype != RoomArchetype::Empty));
+            for (y, row) in cells.iter().enumerate() {
+                for (x, cell) in row.iter().enumerate() {
+                    assert!(cell.archetype.allows(cell.doors));
+                    for (dir, (dx, dy)) in DELTAS.iter().enumerate() {
+                        let neighbor = cells.get((y as i32 + dy) as usize).and_then(|r| r.get((x as i32 + dx) as usize));
+                        match neighbor {
+                            Some(n) => {
+                                assert_eq!(cell.doors[dir], n.doors[(dir + 2) % 4]);
+                                assert!(!cell.doors[dir] || cell.archetype.joins(n.archetype), "{:?} {:?}", cell, n);
+                            }
+                            None => assert!(!cell.doors[dir]),
+                        }
+                    }
+                }
+            }
+        }
+
+        // Realized levels are connected rooms and corridors, the same per seed
+        for seed in 0..10 {
+            let params = GeneratorParams { seed: Some(seed), width: 90, height: 50, mode: GenerationMode::RoomWfc, ..Default::default() };
+            let level = generate(&params);
+            assert!(level.rooms.len() >= 2, "seed {}", seed);
+            assert!(level.check_invariants().is_empty(), "seed {}: {:?}", seed, level.check_invariants());
+            assert_eq!(generate(&params).tiles, level.tiles);
+        }
+    }
+}
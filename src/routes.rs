@@ -0,0 +1,476 @@
+//! Side routes that split off the main path and rejoin it later.
+//!
+//! Branches in a generated level are a byproduct of where its rooms landed.
+//! With [`GeneratorParams::side_routes`] set, marble levels get up to that
+//! many routes carved on purpose. Each one leaves a room on the main path
+//! (the shortest route from the entrance room to the exit room in the
+//! [path graph](crate::path_graph)) and rejoins it at a room one to
+//! [`MAX_ROOM_SPAN`] rooms further along, through solid rock all the way.
+//! Routes are [`SIDE_ROUTE_WIDTH`] tiles wide and keep a wall between
+//! themselves and every other channel, so each stays a corridor of its own:
+//! the path graph shows it as a single edge between its two rooms, tagged
+//! [`RouteTag::Side`](crate::path_graph::RouteTag::Side).
+//!
+//! A route shorter than the stretch of main path it bypasses is a
+//! [`SideRouteKind::Shortcut`], a risk for a reward: a pillar obstacle
+//! stands on its center line every [`SHORTCUT_PILLAR_SPACING`] tiles,
+//! leaving a one-tile lane on either side. Any other route is a
+//! [`SideRouteKind::Scenic`] loop, longer and clear. Room pairs with the
+//! longest stretch of main path between them are tried first, and no pair
+//! gets two routes. With elevation, a route ramps evenly from one room's
+//! height to the other's, and a way too short to climb a level per step is
+//! passed over. Connections, stats, and marble tiles are worked out again
+//! after carving; routes are listed in `Level::side_routes`.
+//!
+//! Races need more than one viable line. [`Level::alternate_routes`] counts
+//! the routes from the entrance room to the exit room that share no path
+//! graph edge, and with [`GeneratorParams::min_alternate_routes`] set, side
+//! routes are carved until there are that many. Each one bridges the
+//! bottleneck of the max-flow behind the count, between the nearest pair of
+//! rooms across it that rock allows, and so adds a route. Where no pair
+//! can be bridged, a route to the nearest room past the bottleneck moves it
+//! further along to try again; once no room is left to reach, the level
+//! keeps the routes it has. Bridges ramp between room heights like any
+//! other side route, so elevation can leave fewer pairs to bridge. These
+//! routes may join any two rooms apart from vaults, which keep their single
+//! entrance, and count as shortcuts or scenic loops against the walk
+//! between their rooms.
+//!
+//! ```
+//! use level_generator::gen::{generate, GenerationMode, GeneratorParams};
+//! use level_generator::path_graph::RouteTag;
+//!
+//! let level = generate(&GeneratorParams { seed: Some(4), mode: GenerationMode::Marble, rooms: 12, side_routes: 2, ..Default::default() });
+//! let graph = level.path_graph();
+//! for (i, route) in level.side_routes.iter().enumerate() {
+//!     println!("{:?} from room {} to room {}: {} steps against {}", route.kind, route.from, route.to, route.length, route.bypassed);
+//!     assert!(graph.edges.iter().any(|e| e.route == Some(RouteTag::Side(i))));
+//! }
+//! ```
+//!
+//! [`GeneratorParams::side_routes`]: crate::dungeon::GeneratorParams::side_routes
+//! [`GeneratorParams::min_alternate_routes`]: crate::dungeon::GeneratorParams::min_alternate_routes
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::dungeon::{is_walkable_char, Grid, Level, Room, TILE_FLOOR, TILE_WALL};
+use crate::editing::Rect;
+use crate::obstacles::{fits, Obstacle, ObstacleShape};
+use crate::path_graph::{PathGraph, PathNodeKind};
+use crate::roles::RoomRole;
+use crate::tiles::{MarbleTile, TileType};
+
+/// Width of a side route in tiles.
+pub const SIDE_ROUTE_WIDTH: i32 = 3;
+
+/// Tiles between the pillars on a shortcut.
+pub const SHORTCUT_PILLAR_SPACING: usize = 4;
+
+/// Most main path rooms a side route skips ahead.
+pub const MAX_ROOM_SPAN: usize = 3;
+
+/// Whether a [`SideRoute`] saves distance or costs it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum SideRouteKind {
+    /// Shorter than the main path it bypasses, with pillars in the way
+    Shortcut,
+    /// At least as long as the main path it bypasses, and clear
+    Scenic,
+}
+
+/// Route carved off the main path; see the module docs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SideRoute {
+    pub kind: SideRouteKind,
+    /// Rooms where the route leaves and rejoins the main path, by index
+    /// into `Level::rooms`, in main path order; for routes added for
+    /// [`Level::ensure_alternate_routes`], the rooms on the entrance and
+    /// exit side of the bottleneck
+    pub from: usize,
+    pub to: usize,
+    /// Center line of the route, from the tile outside `from` to the tile
+    /// outside `to`
+    pub tiles: Vec<(i32, i32)>,
+    /// Steps from the center of `from` to the center of `to` along the route
+    pub length: u32,
+    /// Steps between the same rooms along the main path (the shortest walk
+    /// for alternate routes, `u32::MAX` if there was none)
+    pub bypassed: u32,
+    /// Ids of the pillars on a shortcut, into `Level::obstacles`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub obstacles: Vec<usize>,
+}
+
+impl Level {
+    /// Carve up to `count` side routes; see the module docs. Returns the
+    /// number carved.
+    pub fn add_side_routes(&mut self, count: u32) -> u32 {
+        if count == 0 {
+            return 0;
+        }
+        let stops = main_rooms(self, &self.path_graph());
+        let mut pairs = Vec::new();
+        for (i, &(from, at)) in stops.iter().enumerate() {
+            for &(to, until) in stops.iter().skip(i + 1).take(MAX_ROOM_SPAN) {
+                pairs.push((from, to, until - at));
+            }
+        }
+        // Stable, so equal stretches keep main path order
+        pairs.sort_by_key(|&(_, _, bypassed)| Reverse(bypassed));
+
+        let mut grid: Grid = self.ascii_tiles().iter().map(|row| row.chars().collect()).collect();
+        let mut routes: Vec<SideRoute> = Vec::new();
+        for (from, to, bypassed) in pairs {
+            if routes.len() as u32 == count {
+                break;
+            }
+            routes.extend(route(&mut grid, &self.rooms, from, to, bypassed));
+        }
+        let carved = routes.len() as u32;
+        self.finish_routes(grid, routes);
+        carved
+    }
+
+    /// Edge-disjoint routes from the entrance room to the exit room in the
+    /// path graph (see [`PathGraph::edge_disjoint_routes`]); 0 without both
+    /// rooms.
+    pub fn alternate_routes(&self) -> u32 {
+        let graph = self.path_graph();
+        match endpoints(self, &graph) {
+            Some((start, goal)) => graph.edge_disjoint_routes(start, goal),
+            None => 0,
+        }
+    }
+
+    /// Carve side routes until [`Level::alternate_routes`] reaches `min`,
+    /// as far as the rock allows; see the module docs. Returns the number
+    /// carved.
+    pub fn ensure_alternate_routes(&mut self, min: u32) -> u32 {
+        let mut carved = 0;
+        loop {
+            let graph = self.path_graph();
+            let Some((start, goal)) = endpoints(self, &graph) else { return carved };
+            let (routes, near, far) = graph.min_cut(start, goal);
+            if routes >= min {
+                return carved;
+            }
+            // Bridge the bottleneck between the nearest rooms across it, or
+            // else reach out to a room past it to try again from there
+            let on = |side: &[bool], room: usize| graph.room_node(room).is_some_and(|node| side[node]);
+            let rank = |&(a, b): &(usize, usize)| {
+                let ((ax, ay), (bx, by)) = (self.rooms[a].center(), self.rooms[b].center());
+                (!on(&far, b), (ax - bx).abs() + (ay - by).abs())
+            };
+            // Vaults keep their single entrance
+            let rooms = (0..self.rooms.len()).filter(|&i| self.rooms[i].role != RoomRole::Vault);
+            let mut pairs: Vec<(usize, usize)> =
+                rooms.clone().filter(|&a| on(&near, a)).flat_map(|a| rooms.clone().filter(|&b| !on(&near, b)).map(move |b| (a, b))).collect();
+            pairs.sort_by_key(rank);
+
+            let mut grid: Grid = self.ascii_tiles().iter().map(|row| row.chars().collect()).collect();
+            let walked = |a: usize, b: usize| {
+                let route = graph.room_node(a).zip(graph.room_node(b)).and_then(|(a, b)| graph.shortest_route(a, b));
+                route.map_or(u32::MAX, |edges| edges.iter().map(|&e| graph.edges[e].length).sum())
+            };
+            let Some(bridge) = pairs.into_iter().find_map(|(a, b)| route(&mut grid, &self.rooms, a, b, walked(a, b))) else { return carved };
+            self.finish_routes(grid, vec![bridge]);
+            carved += 1;
+        }
+    }
+
+    /// Write `grid` with `routes` carved into it back to the level and
+    /// record the routes.
+    fn finish_routes(&mut self, grid: Grid, mut routes: Vec<SideRoute>) {
+        if routes.is_empty() {
+            return;
+        }
+        self.set_ascii_tiles(grid, |level| {
+            level.rebuild_marble_tiles();
+            for route in &routes {
+                level.grade(route);
+            }
+            for route in routes.iter_mut().filter(|r| r.kind == SideRouteKind::Shortcut) {
+                route.obstacles = level.place_pillars(&route.tiles);
+            }
+            level.relink();
+        });
+        self.side_routes.extend(routes);
+    }
+
+    /// Ramp the floor of `route` evenly from its first room's elevation to
+    /// its last's, instead of the step where floor spreading from either
+    /// end meets.
+    fn grade(&mut self, route: &SideRoute) {
+        let (Some(low), Some(high)) = (self.rooms[route.from].elevation, self.rooms[route.to].elevation) else { return };
+        let Some(tiles) = self.marble_tiles.as_mut() else { return };
+        let floor = brush(&self.rooms, &route.tiles);
+        let mut area: Option<Rect> = None;
+        for (&(x, y), &(walked, left)) in &steps(&self.rooms, route.from, route.to, &floor) {
+            let climbed = if walked + left == 0 { 0 } else { ((high - low) * walked).div_euclid(walked + left) };
+            tiles[y as usize][x as usize].elevation = low + climbed;
+            area = Some(area.map_or(Rect::new(x, y, 1, 1), |r| r.cover(x, y)));
+        }
+        if let Some(area) = area {
+            self.update_marble_tiles(area);
+        }
+    }
+
+    /// Pillars along the center line of a shortcut, skipping spots where
+    /// one would not leave room around it. Returns their ids.
+    fn place_pillars(&mut self, line: &[(i32, i32)]) -> Vec<usize> {
+        let Some(tiles) = self.marble_tiles.as_mut() else { return Vec::new() };
+        let mut ids = Vec::new();
+        for &(x, y) in line.iter().take(line.len().saturating_sub(2)).skip(SHORTCUT_PILLAR_SPACING).step_by(SHORTCUT_PILLAR_SPACING) {
+            if !fits(tiles, &[(x, y)]) {
+                continue;
+            }
+            let id = self.obstacles.iter().map(|o| o.id + 1).max().unwrap_or(0);
+            let tile = &mut tiles[y as usize][x as usize];
+            let pillar = MarbleTile::with_params(TileType::Obstacle, tile.elevation, 0, false).with_metadata(format!("{{\"obstacle\":{}}}", id));
+            let covered = std::mem::replace(tile, pillar);
+            self.obstacles.push(Obstacle { id, shape: ObstacleShape::Pillar, tiles: vec![(x, y)], covered: vec![covered] });
+            ids.push(id);
+        }
+        ids
+    }
+}
+
+/// Nodes of the entrance and exit rooms in `graph`.
+fn endpoints(level: &Level, graph: &PathGraph) -> Option<(usize, usize)> {
+    let room = |role: RoomRole| level.rooms.iter().position(|r| r.role == role).and_then(|i| graph.room_node(i));
+    Some((room(RoomRole::Entrance)?, room(RoomRole::Exit)?))
+}
+
+/// Rooms on the main path from the entrance to the exit, each with the
+/// steps walked to reach it. Empty without both rooms or a route between.
+fn main_rooms(level: &Level, graph: &PathGraph) -> Vec<(usize, u32)> {
+    let Some((start, goal)) = endpoints(level, graph) else { return Vec::new() };
+    let Some(route) = graph.shortest_route(start, goal) else { return Vec::new() };
+    let PathNodeKind::Room(entrance) = graph.nodes[start].kind else { return Vec::new() };
+    let mut stops = vec![(entrance, 0)];
+    let (mut node, mut walked) = (start, 0);
+    for e in route {
+        let edge = &graph.edges[e];
+        node = if edge.from == node { edge.to } else { edge.from };
+        walked += edge.length;
+        if let PathNodeKind::Room(room) = graph.nodes[node].kind {
+            stops.push((room, walked));
+        }
+    }
+    stops
+}
+
+/// Side route from room `from` to room `to` carved into `grid`, against
+/// `bypassed` steps between them without it. `None` where [`dig`] finds no
+/// way, or the shortest walk across it is too short to climb between the
+/// rooms' elevations a level per step.
+fn route(grid: &mut Grid, rooms: &[Room], from: usize, to: usize, bypassed: u32) -> Option<SideRoute> {
+    let tiles = dig(grid, rooms, from, to)?;
+    let floor = brush(rooms, &tiles);
+    let rise = rooms[from].elevation.zip(rooms[to].elevation).map_or(0, |(a, b)| (a - b).abs());
+    let across = steps(rooms, from, to, &floor).values().map(|&(walked, left)| walked + left).min().unwrap_or(0);
+    if rise > across {
+        return None;
+    }
+    for &(x, y) in &floor {
+        grid[y as usize][x as usize] = TILE_FLOOR;
+    }
+    let steps = |(ax, ay): (i32, i32), (bx, by): (i32, i32)| ((ax - bx).abs() + (ay - by).abs()) as u32;
+    let (first, last) = (tiles[0], tiles[tiles.len() - 1]);
+    let length = steps(rooms[from].center(), first) + tiles.len() as u32 - 1 + steps(last, rooms[to].center());
+    let kind = if length < bypassed { SideRouteKind::Shortcut } else { SideRouteKind::Scenic };
+    Some(SideRoute { kind, from, to, tiles, length, bypassed, obstacles: Vec::new() })
+}
+
+/// Center line of a route from room `from` to room `to` through solid
+/// rock, keeping a wall between the carved route and any floor outside
+/// those two rooms. `None` if there is no way through or it would be
+/// shorter than three tiles.
+fn dig(grid: &Grid, rooms: &[Room], from: usize, to: usize) -> Option<Vec<(i32, i32)>> {
+    let (height, width) = (grid.len() as i32, grid.first().map_or(0, Vec::len) as i32);
+    let (start, goal) = (&rooms[from], &rooms[to]);
+    // Floor this far from the center line would touch the carved route
+    let reach = SIDE_ROUTE_WIDTH / 2 + 1;
+
+    // Floor the route must keep away from, summed for window counts
+    let mut taken = vec![vec![0u32; width as usize + 1]; height as usize + 1];
+    for y in 0..height {
+        for x in 0..width {
+            let floor = is_walkable_char(grid[y as usize][x as usize]) && !start.contains(x, y) && !goal.contains(x, y);
+            let (ux, uy) = (x as usize, y as usize);
+            taken[uy + 1][ux + 1] = taken[uy][ux + 1] + taken[uy + 1][ux] - taken[uy][ux] + u32::from(floor);
+        }
+    }
+    let clear = |(x, y): (i32, i32)| {
+        if x < reach || y < reach || x >= width - reach || y >= height - reach || grid[y as usize][x as usize] != TILE_WALL {
+            return false;
+        }
+        let (x0, y0, x1, y1) = ((x - reach) as usize, (y - reach) as usize, (x + reach + 1) as usize, (y + reach + 1) as usize);
+        taken[y1][x1] + taken[y0][x0] - taken[y0][x1] - taken[y1][x0] == 0 && !rooms.iter().any(|r| r.contains(x, y))
+    };
+    let sides = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+    let beside = |(x, y): (i32, i32), room: &Room| sides.iter().any(|&(dx, dy)| room.contains(x + dx, y + dy));
+
+    let mut came: Vec<Vec<Option<(i32, i32)>>> = vec![vec![None; width as usize]; height as usize];
+    let mut queue = VecDeque::new();
+    for y in start.y - 1..=start.y + start.h {
+        for x in start.x - 1..=start.x + start.w {
+            if clear((x, y)) && beside((x, y), start) {
+                came[y as usize][x as usize] = Some((x, y));
+                queue.push_back((x, y));
+            }
+        }
+    }
+    while let Some(at) = queue.pop_front() {
+        if beside(at, goal) {
+            let mut line = vec![at];
+            while let Some(back) = came[line[line.len() - 1].1 as usize][line[line.len() - 1].0 as usize].filter(|&b| b != line[line.len() - 1]) {
+                line.push(back);
+            }
+            line.reverse();
+            return (line.len() >= 3).then_some(line);
+        }
+        for (dx, dy) in sides {
+            let next = (at.0 + dx, at.1 + dy);
+            if clear(next) && came[next.1 as usize][next.0 as usize].is_none() {
+                came[next.1 as usize][next.0 as usize] = Some(at);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+/// Tiles [`SIDE_ROUTE_WIDTH`] wide along `line`, outside every room.
+fn brush(rooms: &[Room], line: &[(i32, i32)]) -> Vec<(i32, i32)> {
+    let half = SIDE_ROUTE_WIDTH / 2;
+    let mut tiles: Vec<(i32, i32)> = line
+        .iter()
+        .flat_map(|&(x, y)| (y - half..=y + half).flat_map(move |ty| (x - half..=x + half).map(move |tx| (tx, ty))))
+        .filter(|&(x, y)| !rooms.iter().any(|r| r.contains(x, y)))
+        .collect();
+    tiles.sort_unstable();
+    tiles.dedup();
+    tiles
+}
+
+/// Steps from each tile of `floor` to the nearest one beside room `from`
+/// and to the nearest one beside room `to`, walking on `floor` only.
+fn steps(rooms: &[Room], from: usize, to: usize, floor: &[(i32, i32)]) -> HashMap<(i32, i32), (i32, i32)> {
+    let sides = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+    let walk = |room: &Room| {
+        let mut seen: HashMap<(i32, i32), i32> = HashMap::new();
+        let mut queue: VecDeque<(i32, i32)> = floor.iter().copied().filter(|&(x, y)| sides.iter().any(|&(dx, dy)| room.contains(x + dx, y + dy))).collect();
+        for &at in &queue {
+            seen.insert(at, 0);
+        }
+        while let Some((x, y)) = queue.pop_front() {
+            let next = seen[&(x, y)] + 1;
+            for (dx, dy) in sides {
+                let at = (x + dx, y + dy);
+                if !seen.contains_key(&at) && floor.binary_search(&at).is_ok() {
+                    seen.insert(at, next);
+                    queue.push_back(at);
+                }
+            }
+        }
+        seen
+    };
+    let (there, back) = (walk(&rooms[from]), walk(&rooms[to]));
+    there.iter().filter_map(|(&at, &a)| Some((at, (a, *back.get(&at)?)))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams};
+    use crate::path_graph::RouteTag;
+
+    #[test]
+    fn side_routes_leave_and_rejoin_the_main_path() {
+        let params = GeneratorParams { seed: Some(1185), mode: GenerationMode::Marble, width: 120, height: 80, rooms: 14, side_routes: 3, ..Default::default() };
+        let level = generate(&params);
+        assert_eq!(level.tiles, generate(&params).tiles);
+        assert!(!level.side_routes.is_empty() && level.side_routes.len() <= 3);
+        assert!(level.check_invariants().is_empty());
+
+        // Measured before carving, since shortcuts move the main path
+        let plain = generate(&GeneratorParams { side_routes: 0, ..params.clone() });
+        let stops: Vec<usize> = main_rooms(&plain, &plain.path_graph()).iter().map(|&(room, _)| room).collect();
+        let graph = level.path_graph();
+        for (i, route) in level.side_routes.iter().enumerate() {
+            // Both ends on the main path, in order, as its own tagged edge
+            let (a, b) = (stops.iter().position(|&r| r == route.from), stops.iter().position(|&r| r == route.to));
+            assert!(a.is_some() && b.is_some() && a < b, "{:?}", route);
+            let tagged: Vec<_> = graph.edges.iter().filter(|e| e.route == Some(RouteTag::Side(i))).collect();
+            assert_eq!(tagged.len(), 1);
+            let ends = [graph.nodes[tagged[0].from].kind, graph.nodes[tagged[0].to].kind];
+            assert!(ends.contains(&PathNodeKind::Room(route.from)) && ends.contains(&PathNodeKind::Room(route.to)));
+            assert_eq!(route.kind == SideRouteKind::Shortcut, route.length < route.bypassed);
+            assert!(route.obstacles.iter().all(|&id| level.obstacles.iter().any(|o| o.id == id)));
+        }
+        assert!(graph.edges.iter().any(|e| e.route == Some(RouteTag::Main)));
+
+        // Carving only ever opens tiles, and not without being asked
+        assert!(plain.side_routes.is_empty());
+        for (row, before) in level.tiles.iter().zip(&plain.tiles) {
+            assert!(row.chars().zip(before.chars()).all(|(now, was)| now == was || was == TILE_WALL));
+        }
+
+        // Routes between rooms at different heights ramp from one to the other
+        let hilly = generate(&GeneratorParams { seed: Some(28), mode: GenerationMode::Marble, enable_elevation: true, max_elevation: 4, max_elevation_change: 2, side_routes: 3, ..Default::default() });
+        assert!(hilly.check_invariants().is_empty());
+        let tiles = hilly.marble_tiles.as_ref().unwrap();
+        let rises: Vec<(i32, i32)> = hilly.side_routes.iter().map(|r| (hilly.rooms[r.from].elevation.unwrap(), hilly.rooms[r.to].elevation.unwrap())).collect();
+        assert!(rises.iter().any(|(a, b)| a != b));
+        for (route, &(a, b)) in hilly.side_routes.iter().zip(&rises) {
+            let heights: Vec<i32> = route.tiles.iter().map(|&(x, y)| tiles[y as usize][x as usize].elevation).collect();
+            assert!(heights.iter().all(|h| (a.min(b)..=a.max(b)).contains(h)));
+            assert!(heights.windows(2).all(|w| (w[0] - w[1]).abs() <= 1));
+        }
+    }
+
+    #[test]
+    fn alternate_routes_are_guaranteed_where_rock_allows() {
+        let params = GeneratorParams { seed: Some(1186), mode: GenerationMode::Marble, width: 120, height: 80, rooms: 12, ..Default::default() };
+        let plain = generate(&params);
+        let level = generate(&GeneratorParams { min_alternate_routes: 3, ..params.clone() });
+        assert!(plain.alternate_routes() < 3);
+        assert_eq!(level.alternate_routes(), 3);
+        // Some routes only lead past the bottleneck for the next to start from
+        assert!(level.side_routes.len() as u32 >= 3 - plain.alternate_routes());
+        assert!(level.path_graph().edges.iter().filter(|e| matches!(e.route, Some(RouteTag::Side(_)))).count() == level.side_routes.len());
+        assert!(level.check_invariants().is_empty());
+
+        // Bridges never open a vault up
+        let vaulted = generate(&GeneratorParams { vaults: 2, min_alternate_routes: 3, ..params.clone() });
+        let vaults: Vec<usize> = (0..vaulted.rooms.len()).filter(|&i| vaulted.rooms[i].role == RoomRole::Vault).collect();
+        assert!(!vaults.is_empty() && !vaulted.side_routes.is_empty());
+        for i in vaults {
+            assert_eq!(vaulted.room_entrances(i).len(), 1);
+            assert!(vaulted.side_routes.iter().all(|r| r.from != i && r.to != i));
+        }
+
+        // Bridges between rooms at different heights ramp like side routes
+        let hilly = generate(&GeneratorParams { seed: Some(4), mode: GenerationMode::Marble, enable_elevation: true, max_elevation: 4, max_elevation_change: 2, min_alternate_routes: 3, ..Default::default() });
+        assert!(hilly.side_routes.iter().any(|r| hilly.rooms[r.from].elevation != hilly.rooms[r.to].elevation));
+        assert!(hilly.check_invariants().is_empty());
+
+        // Two lanes side by side and a bottleneck of one
+        let mut graph = PathGraph::default();
+        for tile in [(0, 0), (1, 0), (2, 0), (3, 0)] {
+            graph.nodes.push(crate::path_graph::PathNode { kind: PathNodeKind::Junction, tile });
+        }
+        let edge = |from, to| crate::path_graph::PathEdge { from, to, length: 1, elevation_delta: 0, turns: 0, curvature: 0.0, tiles: Vec::new(), runs: Vec::new(), route: None };
+        graph.edges = vec![edge(0, 1), edge(0, 1), edge(1, 2), edge(2, 3), edge(3, 2)];
+        assert_eq!((graph.edge_disjoint_routes(0, 1), graph.edge_disjoint_routes(0, 3), graph.edge_disjoint_routes(2, 3)), (2, 1, 2));
+        let (routes, near, far) = graph.min_cut(0, 3);
+        assert_eq!((routes, near, far), (1, vec![true, true, false, false], vec![false, false, true, true]));
+        assert_eq!(graph.edge_disjoint_routes(0, 0), 0);
+    }
+}
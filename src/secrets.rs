@@ -0,0 +1,169 @@
+//! Secret passages between neighboring rooms.
+//!
+//! Rooms often sit a wall or two apart without a corridor between them. With
+//! [`GeneratorParams::secret_passages`] set, classic levels get up to that
+//! many hidden shortcuts: for room pairs that are not joined directly (no
+//! entry in `connections`) and face each other across at most
+//! [`MAX_SECRET_GAP`] tiles of solid wall, the wall tiles across the middle
+//! of the shared side become [`TILE_SECRET_DOOR`] (`S`). The narrowest gaps
+//! go first.
+//!
+//! Secret doors are not walkable (see [`is_walkable_char`]), so the
+//! connection graph, roles, zones, stats such as the main path, and every
+//! other analysis see them as wall; a game decides when players find them.
+//! [`Level::reveal_secret_passages`] turns them into floor.
+//!
+//! ```
+//! use level_generator::gen::{generate, GeneratorParams};
+//! use level_generator::tiles::TILE_SECRET_DOOR;
+//!
+//! let level = generate(&GeneratorParams { seed: Some(6), rooms: 16, secret_passages: 3, ..Default::default() });
+//! let doors = level.tiles.iter().flat_map(|row| row.chars()).filter(|&c| c == TILE_SECRET_DOOR).count();
+//! println!("{} secret door tiles", doors);
+//! ```
+//!
+//! [`GeneratorParams::secret_passages`]: crate::dungeon::GeneratorParams::secret_passages
+//! [`is_walkable_char`]: crate::tiles::is_walkable_char
+
+use std::collections::HashSet;
+
+use crate::dungeon::{intersects_with_margin, Grid, Level, Room, TILE_FLOOR, TILE_WALL};
+use crate::hex::GridTopology;
+use crate::tiles::TILE_SECRET_DOOR;
+
+/// Widest wall, in tiles, a secret passage runs through.
+pub const MAX_SECRET_GAP: i32 = 3;
+
+impl Level {
+    /// Carve up to `count` secret passages; see the module docs. Returns
+    /// the number carved, 0 on hex grids.
+    pub fn add_secret_passages(&mut self, count: u32) -> u32 {
+        if self.topology == GridTopology::Hex {
+            return 0;
+        }
+        let mut grid: Grid = self.tiles.iter().map(|row| row.chars().collect()).collect();
+        let joined: HashSet<(usize, usize)> = self.connections.iter().copied().collect();
+        let mut candidates: Vec<Vec<(i32, i32)>> = Vec::new();
+        for (a, room) in self.rooms.iter().enumerate() {
+            for (b, other) in self.rooms.iter().enumerate().skip(a + 1) {
+                if joined.contains(&(a, b)) || !intersects_with_margin(room, other, MAX_SECRET_GAP) {
+                    continue;
+                }
+                if let Some(passage) = passage_between(room, other) {
+                    candidates.push(passage);
+                }
+            }
+        }
+        // Stable, so equal gaps keep room order
+        candidates.sort_by_key(Vec::len);
+
+        let wall = |grid: &Grid, (x, y): (i32, i32)| grid.get(y as usize).and_then(|row| row.get(x as usize)) == Some(&TILE_WALL);
+        let mut carved = 0;
+        for passage in candidates {
+            if carved == count {
+                break;
+            }
+            // Solid wall only: a corridor in the gap would already join the rooms
+            if !passage.iter().all(|&tile| wall(&grid, tile)) {
+                continue;
+            }
+            for &(x, y) in &passage {
+                grid[y as usize][x as usize] = TILE_SECRET_DOOR;
+            }
+            carved += 1;
+        }
+        if carved > 0 {
+            let doors = grid.iter().flatten().filter(|&&c| c == TILE_SECRET_DOOR).count();
+            self.tiles = grid.into_iter().map(|row| row.into_iter().collect()).collect();
+            self.count_secret_doors(doors);
+        }
+        carved
+    }
+
+    /// Turn every secret door into floor.
+    pub fn reveal_secret_passages(&mut self) {
+        for row in &mut self.tiles {
+            if row.contains(TILE_SECRET_DOOR) {
+                *row = row.replace(TILE_SECRET_DOOR, &TILE_FLOOR.to_string());
+            }
+        }
+        self.refresh_stats();
+    }
+
+    /// Move `doors` tiles from wall to secret door in the tile histogram.
+    fn count_secret_doors(&mut self, doors: usize) {
+        let histogram = &mut self.stats.tile_histogram;
+        let before = histogram.get(&TILE_SECRET_DOOR.to_string()).copied().unwrap_or(0);
+        if let Some(walls) = histogram.get_mut(&TILE_WALL.to_string()) {
+            *walls = walls.saturating_sub(doors - before);
+        }
+        histogram.insert(TILE_SECRET_DOOR.to_string(), doors);
+    }
+}
+
+/// Tiles between two rooms facing each other across at most
+/// [`MAX_SECRET_GAP`] tiles, through the middle of the side they share.
+fn passage_between(a: &Room, b: &Room) -> Option<Vec<(i32, i32)>> {
+    let (top, bottom) = (a.y.max(b.y), (a.y + a.h).min(b.y + b.h));
+    let (left, right) = (a.x.max(b.x), (a.x + a.w).min(b.x + b.w));
+    if top < bottom {
+        // Side by side: run east-west along the middle shared row
+        let (west, east) = if a.x < b.x { (a, b) } else { (b, a) };
+        let y = (top + bottom - 1) / 2;
+        let gap = (west.x + west.w)..east.x;
+        (1..=MAX_SECRET_GAP).contains(&gap.len().try_into().ok()?).then(|| gap.map(|x| (x, y)).collect())
+    } else if left < right {
+        // One above the other: run north-south along the middle shared column
+        let (north, south) = if a.y < b.y { (a, b) } else { (b, a) };
+        let x = (left + right - 1) / 2;
+        let gap = (north.y + north.h)..south.y;
+        (1..=MAX_SECRET_GAP).contains(&gap.len().try_into().ok()?).then(|| gap.map(|y| (x, y)).collect())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GeneratorParams};
+
+    #[test]
+    fn passages_join_unconnected_neighbors_out_of_sight() {
+        let params = GeneratorParams { seed: Some(1181), rooms: 20, min_room: 4, max_room: 8, secret_passages: 4, ..Default::default() };
+        let plain = generate(&GeneratorParams { secret_passages: 0, ..params.clone() });
+        let mut level = generate(&params);
+        let doors: Vec<(usize, usize)> = level
+            .tiles
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| row.chars().enumerate().filter(|&(_, c)| c == TILE_SECRET_DOOR).map(move |(x, _)| (x, y)))
+            .collect();
+        assert!(!doors.is_empty());
+        assert!(level.check_invariants().is_empty());
+
+        // Doors only replace wall, and pathfinding does not see them
+        for &(x, y) in &doors {
+            assert_eq!(plain.tiles[y].as_bytes()[x], TILE_WALL as u8);
+        }
+        assert_eq!(level.connections, plain.connections);
+        assert_eq!(level.stats.main_path_length, plain.stats.main_path_length);
+        assert_eq!(level.stats.tile_histogram.get("S"), Some(&doors.len()));
+        assert_eq!(level.stats.tile_histogram.get("#").unwrap() + doors.len(), *plain.stats.tile_histogram.get("#").unwrap());
+
+        // Revealed, they open new room-to-room links
+        level.reveal_secret_passages();
+        assert!(level.tiles.iter().all(|row| !row.contains(TILE_SECRET_DOOR)));
+        assert!(level.check_invariants().is_empty());
+        let mut revealed = level.clone();
+        revealed.relink();
+        assert!(revealed.connections.len() > plain.connections.len());
+
+        // Straight across the middle of the shared side, for short gaps only
+        let room = |x, y, w, h| Room { x, y, w, h, ..Default::default() };
+        assert_eq!(passage_between(&room(1, 1, 4, 4), &room(7, 2, 4, 6)), Some(vec![(5, 3), (6, 3)]));
+        assert_eq!(passage_between(&room(1, 1, 4, 4), &room(2, 6, 2, 2)), Some(vec![(2, 5)]));
+        assert_eq!(passage_between(&room(1, 1, 4, 4), &room(9, 1, 4, 4)), None);
+        assert_eq!(passage_between(&room(1, 1, 4, 4), &room(6, 6, 4, 4)), None);
+    }
+}
@@ -0,0 +1,155 @@
+//! Sharded worlds: a grid of sectors generated independently.
+//!
+//! A [`SectorGrid`] splits a world too large for one level into
+//! `columns` x `rows` sectors, each generated on its own as a [`Level`] of
+//! the params' `width` x `height`. Everything a sector depends on is derived
+//! from the world seed and the sector's position, so a server can generate
+//! only the sectors it hosts and still match the ones next door:
+//!
+//! - Each sector generates from its own seed, [`SectorGrid::sector_seed`].
+//! - Each border between two sectors has one opening, a gap of
+//!   `channel_width` tiles in marble mode and one tile otherwise, whose
+//!   position along the border is drawn from the world seed and the border
+//!   alone (see [`SectorGrid::openings`]). Both sectors carve the same gap.
+//!
+//! The openings are carved as [`crate::openings`] describes: the outermost
+//! ring of tiles of a sector is wall (or skirt) apart from its openings, so
+//! floor meets floor across a border exactly at the openings, and a
+//! corridor joins every opening to the nearest room. Borders on the edge of
+//! the world have no opening. Heights are not part of the contract: with
+//! elevation enabled, the two ends of an opening take the heights of their
+//! own sectors.
+//!
+//! ```
+//! use level_generator::gen::{GenerationMode, GeneratorParams, SectorGrid};
+//!
+//! let params = GeneratorParams { seed: Some(9), width: 40, height: 30, rooms: 5, mode: GenerationMode::Marble, ..Default::default() };
+//! let world = SectorGrid::new(params, 100, 100);
+//! // Two servers, one sector each
+//! let west = world.generate_sector(41, 7);
+//! let east = world.generate_sector(42, 7);
+//! for (a, b) in west.tiles.iter().zip(&east.tiles) {
+//!     assert_eq!(a.ends_with('.'), b.starts_with('.'));
+//! }
+//! ```
+
+use crate::dungeon::{generate, GenerationMode, GeneratorParams, Level, MIN_MAP_DIM};
+use crate::openings::EdgeOpening;
+use crate::seeds::{mix, stage_seed, SeedStage};
+use crate::tiles::Direction;
+
+/// Tag mixed into sector seeds.
+const SECTOR_TAG: u64 = 1;
+/// Tag mixed into the seeds of borders on a sector's west side.
+const WEST_BORDER_TAG: u64 = 2;
+/// Tag mixed into the seeds of borders on a sector's north side.
+const NORTH_BORDER_TAG: u64 = 3;
+
+/// A world of `columns` x `rows` sectors; see the module docs.
+#[derive(Debug, Clone)]
+pub struct SectorGrid {
+    /// Params of every sector: `width` and `height` are the size of one
+    /// sector and `seed` is the world seed (`None` is 0)
+    pub params: GeneratorParams,
+    /// Sectors across the world
+    pub columns: u32,
+    /// Sectors down the world
+    pub rows: u32,
+}
+
+impl SectorGrid {
+    pub fn new(params: GeneratorParams, columns: u32, rows: u32) -> Self {
+        Self { params, columns, rows }
+    }
+
+    /// Seed sector `(sx, sy)` generates from.
+    pub fn sector_seed(&self, sx: u32, sy: u32) -> u64 {
+        self.cell_seed(sx, sy, SECTOR_TAG)
+    }
+
+    /// Openings of sector `(sx, sy)`, clockwise from north. A border's
+    /// opening is the same seen from either side.
+    pub fn openings(&self, sx: u32, sy: u32) -> Vec<EdgeOpening> {
+        let width = self.opening_width();
+        let (sector_width, sector_height) = (self.params.width.max(MIN_MAP_DIM), self.params.height.max(MIN_MAP_DIM));
+        // The border between a sector and its east or south neighbor belongs to the neighbor
+        let borders = [
+            (Direction::North, sy > 0, (sx, sy, NORTH_BORDER_TAG), sector_width),
+            (Direction::East, sx + 1 < self.columns, (sx + 1, sy, WEST_BORDER_TAG), sector_height),
+            (Direction::South, sy + 1 < self.rows, (sx, sy + 1, NORTH_BORDER_TAG), sector_width),
+            (Direction::West, sx > 0, (sx, sy, WEST_BORDER_TAG), sector_height),
+        ];
+        borders
+            .into_iter()
+            .filter(|&(_, inside, _, _)| inside)
+            .map(|(side, _, (x, y, tag), length)| {
+                // Keep clear of the corners, where the ring of the other sides runs
+                let margin = self.params.skirt.saturating_add(self.params.border).saturating_add(1);
+                let span = length.saturating_sub(margin.saturating_mul(2).saturating_add(width));
+                let offset = if span == 0 { length.saturating_sub(width) / 2 } else { margin + (self.cell_seed(x, y, tag) % (span as u64 + 1)) as u32 };
+                EdgeOpening { side, offset, width }
+            })
+            .collect()
+    }
+
+    /// Generate sector `(sx, sy)` with its openings carved and joined to its
+    /// rooms. The sector's openings take the place of the params'
+    /// `edge_openings`.
+    pub fn generate_sector(&self, sx: u32, sy: u32) -> Level {
+        generate(&GeneratorParams { seed: Some(self.sector_seed(sx, sy)), edge_openings: self.openings(sx, sy), ..self.params.clone() })
+    }
+
+    /// Tiles across an opening.
+    fn opening_width(&self) -> u32 {
+        match self.params.mode {
+            GenerationMode::Marble => self.params.channel_width.max(1),
+            _ => 1,
+        }
+    }
+
+    /// Seed of cell `(x, y)` of the sector grid under `tag`.
+    fn cell_seed(&self, x: u32, y: u32, tag: u64) -> u64 {
+        let world = stage_seed(self.params.seed.unwrap_or(0), SeedStage::Sectors);
+        mix(world ^ mix(((x as u64) << 32 | y as u64) ^ mix(tag)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::TILE_FLOOR;
+
+    #[test]
+    fn neighboring_sectors_meet_at_their_openings() {
+        for mode in [GenerationMode::Classic, GenerationMode::Marble] {
+            let params = GeneratorParams { seed: Some(1173), width: 36, height: 24, rooms: 5, mode, enable_elevation: true, ..Default::default() };
+            let world = SectorGrid::new(params, 3, 3);
+            let sectors: Vec<Vec<Level>> = (0..3).map(|sy| (0..3).map(|sx| world.generate_sector(sx, sy)).collect()).collect();
+
+            // Floor tiles of a level's column or row
+            let column = |level: &Level, x: usize| (0..24).filter(|&y| level.tiles[y].as_bytes()[x] == TILE_FLOOR as u8).collect::<Vec<_>>();
+            let row = |level: &Level, y: usize| (0..36).filter(|&x| level.tiles[y].as_bytes()[x] == TILE_FLOOR as u8).collect::<Vec<_>>();
+            let across = world.opening_width() as usize;
+            for (sy, sector_row) in sectors.iter().enumerate() {
+                for (sx, level) in sector_row.iter().enumerate() {
+                    assert!(level.check_invariants().is_empty(), "{mode:?} sector ({sx}, {sy})");
+                    if let Some(east) = sector_row.get(sx + 1) {
+                        assert_eq!(column(level, 35), column(east, 0));
+                        assert_eq!(column(level, 35).len(), across);
+                    }
+                    if let Some(south) = sectors.get(sy + 1) {
+                        assert_eq!(row(level, 23), row(&south[sx], 0));
+                        assert_eq!(row(level, 23).len(), across);
+                    }
+                }
+            }
+            // No openings onto the edge of the world
+            assert!(column(&sectors[1][0], 0).is_empty() && row(&sectors[0][1], 0).is_empty());
+            assert!(column(&sectors[1][2], 35).is_empty() && row(&sectors[2][1], 23).is_empty());
+
+            // A sector generated on its own matches the one generated with its neighbors
+            assert_eq!(world.generate_sector(1, 1).tiles, sectors[1][1].tiles);
+            assert_ne!(world.sector_seed(1, 2), world.sector_seed(2, 1));
+        }
+    }
+}
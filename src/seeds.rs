@@ -0,0 +1,322 @@
+//! Hierarchical seeding.
+//!
+//! The level seed is not used as one shared random stream. Instead every
+//! generation stage draws from its own stream, derived from the level seed
+//! and the stage (see [`SeedStage`]). Turning a stage on or off, or changing
+//! how much randomness it consumes, therefore leaves the random choices of
+//! every other stage untouched: switching the connection strategy keeps the
+//! same rooms and the same obstacle rolls, and toggling obstacles keeps the
+//! same moving hazard rolls.
+//!
+//! # Daily seeds
+//!
+//! [`Seed::daily`] turns a calendar date into the level seed of the day, so
+//! every player generates the same level on the same day without a server.
+//! The scheme is fixed, so other clients can reproduce it:
+//!
+//! 1. Write the date as `YYYY-MM-DD` (the year zero-padded to four digits),
+//!    followed by `:` and the salt, e.g. `2026-10-17:ranked`.
+//! 2. Hash its UTF-8 bytes with 64-bit FNV-1a (offset basis
+//!    `0xcbf29ce484222325`, prime `0x100000001b3`).
+//! 3. Pass the hash through the SplitMix64 finalizer: add
+//!    `0x9e3779b97f4a7c15`, then `z = (z ^ z >> 30) * 0xbf58476d1ce4e5b9`,
+//!    `z = (z ^ z >> 27) * 0x94d049bb133111eb`, `z ^ z >> 31`, all wrapping.
+//!
+//! The salt tells apart games, or modes of one game, that share a calendar;
+//! use `""` for none. A daily seed only pins the level while the params and
+//! the algorithm version stay the same (see [`crate::versions`]).
+//!
+//! ```
+//! use level_generator::seeds::{Date, Seed};
+//!
+//! let date: Date = "2026-10-17".parse().unwrap();
+//! assert_eq!(Seed::daily(date, "ranked"), Seed::daily(Date::new(2026, 10, 17).unwrap(), "ranked"));
+//! assert_ne!(Seed::daily(date, "ranked"), Seed::daily(date, "casual"));
+//! let seed: u64 = Seed::daily(date, "").into();
+//! # let _ = seed;
+//! ```
+
+use core::fmt::{self, Write};
+use core::str::FromStr;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// A generation stage with its own random stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeedStage {
+    /// Room sizes, positions, and elevations
+    Rooms,
+    /// Corridor shapes and dead-end trimming
+    Corridors,
+    /// Marble obstacle placement
+    Obstacles,
+    /// Marble moving hazard markers
+    Dynamics,
+    /// Whole-map generators (WFC, overworld, settlement)
+    Layout,
+    /// Seeds of the levels regenerated for `GeneratorParams::min_path_length`
+    Retry,
+    /// Flavor names for the level, rooms, and zones (see [`crate::naming`])
+    Names,
+    /// Route choices of simulated marbles (see [`crate::traffic`])
+    Traffic,
+    /// WFC fills of masked regions (see [`crate::wfc_region`])
+    WfcFill,
+    /// Mission grammar rewrites (see [`crate::mission`])
+    Mission,
+    /// Sector seeds and border openings of a sharded world (see
+    /// [`crate::sectors`])
+    Sectors,
+}
+
+impl SeedStage {
+    /// Stable name mixed into the stage seed.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SeedStage::Rooms => "rooms",
+            SeedStage::Corridors => "corridors",
+            SeedStage::Obstacles => "obstacles",
+            SeedStage::Dynamics => "dynamics",
+            SeedStage::Layout => "layout",
+            SeedStage::Retry => "retry",
+            SeedStage::Names => "names",
+            SeedStage::Traffic => "traffic",
+            SeedStage::WfcFill => "wfc_fill",
+            SeedStage::Mission => "mission",
+            SeedStage::Sectors => "sectors",
+        }
+    }
+}
+
+/// SplitMix64 finalizer.
+pub(crate) fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Seed of `stage`'s stream for the level seed `seed`.
+pub fn stage_seed(seed: u64, stage: SeedStage) -> u64 {
+    // FNV-1a over the stage name keeps the derivation independent of enum order
+    let tag = stage.name().bytes().fold(0xcbf2_9ce4_8422_2325u64, |h, b| (h ^ b as u64).wrapping_mul(0x0100_0000_01b3));
+    mix(seed ^ mix(tag))
+}
+
+/// Random stream for `stage` under the level seed `seed`.
+pub fn stage_rng(seed: u64, stage: SeedStage) -> StdRng {
+    StdRng::seed_from_u64(stage_seed(seed, stage))
+}
+
+/// A level seed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Seed(pub u64);
+
+impl Seed {
+    /// Seed of the level of the day `date` under `salt`; see the module docs.
+    pub fn daily(date: Date, salt: &str) -> Self {
+        let mut hash = Fnv1a(0xcbf2_9ce4_8422_2325);
+        write!(hash, "{}:{}", date, salt).expect("hashing never fails");
+        Seed(mix(hash.0))
+    }
+
+    /// Seed of today's level (UTC) under `salt`.
+    #[cfg(feature = "std")]
+    pub fn today(salt: &str) -> Self {
+        Self::daily(Date::today(), salt)
+    }
+}
+
+impl From<Seed> for u64 {
+    fn from(seed: Seed) -> u64 {
+        seed.0
+    }
+}
+
+impl fmt::Display for Seed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A day of the proleptic Gregorian calendar, years 0 to 9999.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    year: u16,
+    month: u8,
+    day: u8,
+}
+
+impl Date {
+    /// The date, or `None` if the day does not exist.
+    pub fn new(year: u16, month: u8, day: u8) -> Option<Self> {
+        let days = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => 29,
+            2 => 28,
+            _ => return None,
+        };
+        (year <= 9999 && (1..=days).contains(&day)).then_some(Date { year, month, day })
+    }
+
+    /// The date `days` days after 1970-01-01.
+    pub fn from_unix_days(days: i64) -> Option<Self> {
+        // Civil from days, counted in 400-year eras starting on March 1st
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + i64::from(month <= 2);
+        Date::new(u16::try_from(year).ok()?, month as u8, day as u8)
+    }
+
+    /// Today's date in UTC, from the system clock.
+    #[cfg(feature = "std")]
+    pub fn today() -> Self {
+        let seconds = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        Date::from_unix_days((seconds / 86_400) as i64).expect("the system clock is within years 0-9999")
+    }
+
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+}
+
+/// `YYYY-MM-DD`, the form [`Seed::daily`] hashes.
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// Error parsing a [`Date`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateError;
+
+impl fmt::Display for DateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid date (expected an existing day as YYYY-MM-DD)")
+    }
+}
+
+impl core::error::Error for DateError {}
+
+/// Parses `YYYY-MM-DD`; the parts may leave out leading zeros.
+impl FromStr for Date {
+    type Err = DateError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().split('-');
+        let mut part = || parts.next().filter(|p| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit())).ok_or(DateError);
+        let (year, month, day) = (part()?, part()?, part()?);
+        if parts.next().is_some() {
+            return Err(DateError);
+        }
+        Date::new(year.parse().map_err(|_| DateError)?, month.parse().map_err(|_| DateError)?, day.parse().map_err(|_| DateError)?).ok_or(DateError)
+    }
+}
+
+/// 64-bit FNV-1a over the bytes written to it.
+struct Fnv1a(u64);
+
+impl Write for Fnv1a {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 = s.bytes().fold(self.0, |h, b| (h ^ b as u64).wrapping_mul(0x0100_0000_01b3));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams, RoomSizeDistribution};
+    use crate::editing::Rect;
+    use crate::graph::ConnectionStrategy;
+    use crate::roles::EndpointPlacement;
+    use crate::testing::snapshot;
+
+    #[test]
+    fn toggling_a_stage_leaves_other_stages_alone() {
+        let stages = [SeedStage::Rooms, SeedStage::Corridors, SeedStage::Obstacles, SeedStage::Dynamics, SeedStage::Layout, SeedStage::Retry, SeedStage::Names, SeedStage::Traffic, SeedStage::WfcFill];
+        for (i, a) in stages.iter().enumerate() {
+            for b in &stages[i + 1..] {
+                assert_ne!(stage_seed(7, *a), stage_seed(7, *b));
+            }
+        }
+
+        // Corridors drawn differently must not reshuffle obstacle placement in
+        // the rooms (doorway clearance aside, which follows the corridors)
+        let chain = GeneratorParams {
+            seed: Some(11),
+            mode: GenerationMode::Marble,
+            enable_obstacles: true,
+            obstacle_density: 0.6,
+            obstacle_doorway_radius: Some(0),
+            ..Default::default()
+        };
+        let tree = GeneratorParams {
+            connection_strategy: ConnectionStrategy::MinimumSpanningTree { loop_fraction: 0.3 },
+            ..chain.clone()
+        };
+        let (a, b) = (generate(&chain), generate(&tree));
+        assert_ne!(a.connections, b.connections);
+        assert!(!a.obstacles.is_empty());
+        assert_eq!(a.obstacles, b.obstacles);
+        assert!(a.rooms.iter().zip(&b.rooms).all(|(a, b)| (a.x, a.y, a.w, a.h) == (b.x, b.y, b.w, b.h)));
+    }
+
+    #[test]
+    fn daily_seeds_follow_the_documented_scheme() {
+        // Reference values worked out from the module docs outside this crate
+        let day = |s: &str| s.parse::<Date>().unwrap();
+        assert_eq!(Seed::daily(day("2026-10-17"), ""), Seed(16_178_845_090_939_156_862));
+        assert_eq!(Seed::daily(day("2026-10-17"), "ranked"), Seed(16_045_345_517_135_500_075));
+        assert_eq!(Seed::daily(day("2000-2-29"), ""), Seed(3_595_194_008_122_677_956));
+
+        for bad in ["2026-02-29", "2026-13-01", "2026-10", "2026-10-17-1", "26-10-+1", ""] {
+            assert_eq!(bad.parse::<Date>(), Err(DateError), "{bad}");
+        }
+        assert_eq!(Date::from_unix_days(0), Date::new(1970, 1, 1));
+        assert_eq!(Date::from_unix_days(20_743), Date::new(2026, 10, 17));
+        assert_eq!(Date::from_unix_days(11_016), Date::new(2000, 2, 29));
+        assert_eq!(Date::from_unix_days(-1), Date::new(1969, 12, 31));
+    }
+
+    #[test]
+    fn elevated_marble_levels_are_the_same_every_run() {
+        // FNV-1a of the snapshots, so a change in iteration order or float
+        // behaviour between runs or platforms shows up as a different hash
+        let fingerprint = |text: &str| text.bytes().fold(0xcbf2_9ce4_8422_2325u64, |h, b| (h ^ b as u64).wrapping_mul(0x0100_0000_01b3));
+        let expected = [0xfc17_1360_2a47_f996u64, 0xaf23_75ea_bbd6_68a3, 0x3e0f_1b3d_b123_8b58];
+        for (seed, expected) in (0..3).zip(expected) {
+            let params = GeneratorParams {
+                seed: Some(seed),
+                mode: GenerationMode::Marble,
+                enable_elevation: true,
+                max_elevation: 3,
+                max_elevation_change: 2,
+                room_size_distribution: RoomSizeDistribution::Bimodal,
+                endpoints: Some(EndpointPlacement::FirstRoom),
+                ..Default::default()
+            };
+            let mut level = generate(&params);
+            assert_eq!(snapshot(&level), snapshot(&generate(&params)));
+            level.carve_rect(Rect::new(10, 10, 30, 3));
+            level.update_marble_tiles(Rect::new(10, 10, 30, 3));
+            assert_eq!(fingerprint(&snapshot(&level)), expected, "seed {}", seed);
+        }
+    }
+}
@@ -0,0 +1,263 @@
+//! City/village generation.
+//!
+//! Settlement mode lays out a town on open ground:
+//!
+//! 1) Build a road network, either a regular grid of streets or an organic
+//!    network of a wandering main road plus random-walk branches.
+//! 2) Clear plazas around road intersections.
+//! 3) Place building footprints flush against roads. Each building is a
+//!    walled `Room` with a single door opening onto the road, so roles,
+//!    zoning, and the connection graph work unchanged.
+//!
+//! Roads, plazas, doors, and building interiors are floor (`.`), building
+//! walls are `#`, and everything else is open ground (`,`).
+
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::Serialize;
+
+use crate::dungeon::{GeneratorParams, Room, TILE_FLOOR, TILE_GRASS, TILE_WALL};
+
+const DIRS: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+/// Road network style for settlement mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum RoadLayout {
+    /// Regular street grid
+    #[default]
+    Grid,
+    /// Wandering main road with random-walk side streets
+    Organic,
+}
+
+/// Output of the settlement generator.
+pub(crate) struct Settlement {
+    pub tiles: Vec<String>,
+    /// Building interiors
+    pub rooms: Vec<Room>,
+    pub plazas: Vec<Room>,
+}
+
+/// Generate a settlement map.
+pub(crate) fn generate_settlement(
+    width: usize,
+    height: usize,
+    min_room: u32,
+    max_room: u32,
+    params: &GeneratorParams,
+    rng: &mut StdRng,
+) -> Settlement {
+    let mut grid = vec![vec![TILE_GRASS; width]; height];
+
+    match params.road_layout {
+        RoadLayout::Grid => grid_roads(&mut grid, min_room, rng),
+        RoadLayout::Organic => organic_roads(&mut grid, rng),
+    }
+
+    let plazas = place_plazas(&mut grid, min_room, params.plazas, rng);
+    let rooms = place_buildings(&mut grid, min_room, max_room, params.rooms, rng);
+
+    let tiles = grid.iter().map(|row| row.iter().collect()).collect();
+    Settlement { tiles, rooms, plazas }
+}
+
+fn in_bounds(grid: &[Vec<char>], x: i32, y: i32) -> bool {
+    y >= 0 && x >= 0 && (y as usize) < grid.len() && (x as usize) < grid[0].len()
+}
+
+/// Streets every `spacing` tiles, leaving room for two rows of buildings per block.
+fn grid_roads(grid: &mut [Vec<char>], min_room: u32, rng: &mut StdRng) {
+    let height = grid.len();
+    let width = grid[0].len();
+    let spacing = 2 * (min_room as usize + 2) + 1;
+
+    let offset_x = rng.random_range(1..=spacing.min(width - 2));
+    let offset_y = rng.random_range(1..=spacing.min(height - 2));
+    for x in (offset_x..width - 1).step_by(spacing) {
+        for row in grid.iter_mut().take(height - 1).skip(1) {
+            row[x] = TILE_FLOOR;
+        }
+    }
+    for y in (offset_y..height - 1).step_by(spacing) {
+        for tile in grid[y].iter_mut().take(width - 1).skip(1) {
+            *tile = TILE_FLOOR;
+        }
+    }
+}
+
+/// A main road wandering across the long axis, then random-walk branches.
+fn organic_roads(grid: &mut [Vec<char>], rng: &mut StdRng) {
+    let height = grid.len() as i32;
+    let width = grid[0].len() as i32;
+    let inner = |x: i32, y: i32| x >= 1 && y >= 1 && x < width - 1 && y < height - 1;
+
+    // Main road
+    let horizontal = width >= height;
+    let (mut x, mut y) = if horizontal {
+        (1, rng.random_range(height / 4..=(3 * height / 4).max(height / 4)))
+    } else {
+        (rng.random_range(width / 4..=(3 * width / 4).max(width / 4)), 1)
+    };
+    while inner(x, y) {
+        grid[y as usize][x as usize] = TILE_FLOOR;
+        if rng.random_bool(0.25) {
+            let drift = if rng.random_bool(0.5) { 1 } else { -1 };
+            let (nx, ny) = if horizontal { (x, y + drift) } else { (x + drift, y) };
+            if inner(nx, ny) {
+                (x, y) = (nx, ny);
+                grid[y as usize][x as usize] = TILE_FLOOR;
+            }
+        }
+        if horizontal { x += 1 } else { y += 1 }
+    }
+
+    // Branches start from existing road and stop at the edge or on joining another road
+    let branches = (width * height / 150).max(3);
+    let max_len = (width.max(height) / 2).max(8);
+    for _ in 0..branches {
+        let roads: Vec<(i32, i32)> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|&(x, y)| grid[y as usize][x as usize] == TILE_FLOOR)
+            .collect();
+        if roads.is_empty() {
+            break;
+        }
+        let (mut x, mut y) = roads[rng.random_range(0..roads.len())];
+        let mut dir = rng.random_range(0..4);
+        let len = rng.random_range(6..=max_len);
+        for step in 0..len {
+            if rng.random_bool(0.15) {
+                dir = (dir + if rng.random_bool(0.5) { 1 } else { 3 }) % 4;
+            }
+            let (nx, ny) = (x + DIRS[dir].0, y + DIRS[dir].1);
+            if !inner(nx, ny) {
+                break;
+            }
+            (x, y) = (nx, ny);
+            if grid[y as usize][x as usize] == TILE_FLOOR && step > 1 {
+                break;
+            }
+            grid[y as usize][x as usize] = TILE_FLOOR;
+        }
+    }
+}
+
+/// Clear up to `count` square plazas centered on road intersections.
+fn place_plazas(grid: &mut [Vec<char>], min_room: u32, count: u32, rng: &mut StdRng) -> Vec<Room> {
+    let size = min_room as i32 + 2;
+    let road_neighbors = |grid: &[Vec<char>], x: i32, y: i32| {
+        DIRS.iter()
+            .filter(|&&(dx, dy)| in_bounds(grid, x + dx, y + dy) && grid[(y + dy) as usize][(x + dx) as usize] == TILE_FLOOR)
+            .count()
+    };
+    let mut intersections: Vec<(i32, i32)> = Vec::new();
+    for (y, row) in grid.iter().enumerate() {
+        for (x, &c) in row.iter().enumerate() {
+            if c == TILE_FLOOR && road_neighbors(grid, x as i32, y as i32) >= 3 {
+                intersections.push((x as i32, y as i32));
+            }
+        }
+    }
+
+    let mut plazas: Vec<Room> = Vec::new();
+    while plazas.len() < count as usize && !intersections.is_empty() {
+        let (cx, cy) = intersections.swap_remove(rng.random_range(0..intersections.len()));
+        let plaza = Room { x: cx - size / 2, y: cy - size / 2, w: size, h: size, ..Default::default() };
+        if !in_bounds(grid, plaza.x - 1, plaza.y - 1) || !in_bounds(grid, plaza.x + plaza.w, plaza.y + plaza.h) {
+            continue;
+        }
+        if plazas.iter().any(|p| p.intersects(&plaza)) {
+            continue;
+        }
+        for y in plaza.y..plaza.y + plaza.h {
+            for x in plaza.x..plaza.x + plaza.w {
+                grid[y as usize][x as usize] = TILE_FLOOR;
+            }
+        }
+        plazas.push(plaza);
+    }
+    plazas
+}
+
+/// Place up to `count` walled buildings, each with a door opening onto a road.
+fn place_buildings(grid: &mut [Vec<char>], min_room: u32, max_room: u32, count: u32, rng: &mut StdRng) -> Vec<Room> {
+    let roads: Vec<(i32, i32)> = grid
+        .iter()
+        .enumerate()
+        .flat_map(|(y, row)| row.iter().enumerate().filter(|(_, &c)| c == TILE_FLOOR).map(move |(x, _)| (x as i32, y as i32)))
+        .collect();
+    let mut rooms: Vec<Room> = Vec::new();
+    if roads.is_empty() {
+        return rooms;
+    }
+
+    let attempts = (count * 30).max(200);
+    for _ in 0..attempts {
+        if rooms.len() as u32 >= count {
+            break;
+        }
+        let (rx, ry) = roads[rng.random_range(0..roads.len())];
+        let (dx, dy) = DIRS[rng.random_range(0..4)];
+        let (door_x, door_y) = (rx + dx, ry + dy);
+        let w = rng.random_range(min_room as i32..=max_room as i32);
+        let h = rng.random_range(min_room as i32..=max_room as i32);
+
+        // Footprint including walls; the door sits on the wall facing the road
+        let (fw, fh) = (w + 2, h + 2);
+        let (fx, fy) = match (dx, dy) {
+            (0, 1) => (door_x - rng.random_range(1..=w), door_y),
+            (0, _) => (door_x - rng.random_range(1..=w), door_y - (fh - 1)),
+            (1, _) => (door_x, door_y - rng.random_range(1..=h)),
+            _ => (door_x - (fw - 1), door_y - rng.random_range(1..=h)),
+        };
+        if !in_bounds(grid, fx, fy) || !in_bounds(grid, fx + fw - 1, fy + fh - 1) {
+            continue;
+        }
+        let clear = (fy..fy + fh).all(|y| (fx..fx + fw).all(|x| grid[y as usize][x as usize] == TILE_GRASS));
+        if !clear {
+            continue;
+        }
+
+        for y in fy..fy + fh {
+            for x in fx..fx + fw {
+                let edge = y == fy || y == fy + fh - 1 || x == fx || x == fx + fw - 1;
+                grid[y as usize][x as usize] = if edge { TILE_WALL } else { TILE_FLOOR };
+            }
+        }
+        grid[door_y as usize][door_x as usize] = TILE_FLOOR;
+        rooms.push(Room { x: fx + 1, y: fy + 1, w, h, ..Default::default() });
+    }
+    rooms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode};
+
+    #[test]
+    fn buildings_open_onto_the_road_network() {
+        for layout in [RoadLayout::Grid, RoadLayout::Organic] {
+            let params = GeneratorParams {
+                width: 80,
+                height: 40,
+                rooms: 10,
+                seed: Some(5),
+                mode: GenerationMode::Settlement,
+                road_layout: layout,
+                ..Default::default()
+            };
+            let level = generate(&params);
+            assert!(level.rooms.len() >= 4, "{:?}: only {} buildings", layout, level.rooms.len());
+
+            // Every building shares the road component, so each pair is connected
+            let n = level.rooms.len();
+            assert_eq!(level.connections.len(), n * (n - 1) / 2, "{:?}", layout);
+            for (i, a) in level.rooms.iter().enumerate() {
+                for b in &level.rooms[i + 1..] {
+                    assert!(!a.intersects(b));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! Centerline splines of the channels.
+//!
+//! [`Level::channel_splines`] turns every edge of the
+//! [path graph](crate::path_graph) into a Catmull-Rom spline, for engines
+//! that build tracks from splines rather than tiles. The control points are
+//! the tile centers the edge walks through, thinned with Ramer-Douglas-Peucker
+//! so that staircases become diagonals and straight runs keep only their
+//! ends. The curve passes through every control point; repeat the first and
+//! last point as the outer handles.
+//!
+//! Each spline carries the channel width: the median cross-section of its
+//! corridor tiles, measured across the direction of travel.
+//!
+//! ```
+//! use level_generator::{generate, GenerationMode, GeneratorParams};
+//!
+//! let level = generate(&GeneratorParams { seed: Some(3), mode: GenerationMode::Marble, ..Default::default() });
+//! let splines = level.channel_splines();
+//! assert!(splines.iter().all(|s| s.points.len() >= 2 && s.width >= 1));
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::dungeon::Level;
+use crate::visibility::{blocks_movement, tile_elevation};
+
+/// Farthest a walked tile center may lie from the spline's control polygon
+pub const SPLINE_TOLERANCE: f32 = 0.75;
+
+/// Widest cross-section measured for a channel, in tiles
+const MAX_WIDTH: u32 = 9;
+
+/// Centerline of one corridor segment.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ChannelSpline {
+    /// Index into `PathGraph::edges`
+    pub edge: usize,
+    /// Path graph nodes at the start and end
+    pub from: usize,
+    pub to: usize,
+    /// Channel width in tiles
+    pub width: u32,
+    /// Catmull-Rom control points as `[x, y, elevation]`, in tiles (tile
+    /// centers sit at `.5`)
+    pub points: Vec<[f32; 3]>,
+}
+
+impl ChannelSpline {
+    /// Cubic Bézier segments `[start, control, control, end]` tracing the
+    /// same curve, one per pair of neighboring control points.
+    pub fn bezier_segments(&self) -> Vec<[[f32; 3]; 4]> {
+        let p = &self.points;
+        let at = |i: isize| p[i.clamp(0, p.len() as isize - 1) as usize];
+        (0..p.len() as isize - 1)
+            .map(|i| {
+                let (p0, p1, p2, p3) = (at(i - 1), at(i), at(i + 1), at(i + 2));
+                let c1 = [0, 1, 2].map(|k| p1[k] + (p2[k] - p0[k]) / 6.0);
+                let c2 = [0, 1, 2].map(|k| p2[k] - (p3[k] - p1[k]) / 6.0);
+                [p1, c1, c2, p2]
+            })
+            .collect()
+    }
+}
+
+impl Level {
+    /// Splines along the edges of [`Level::path_graph`]; see the module docs.
+    pub fn channel_splines(&self) -> Vec<ChannelSpline> {
+        let graph = self.path_graph();
+        graph
+            .edges
+            .iter()
+            .enumerate()
+            .map(|(i, edge)| {
+                let centers: Vec<[f32; 3]> = edge
+                    .tiles
+                    .iter()
+                    .map(|&(x, y)| [x as f32 + 0.5, y as f32 + 0.5, tile_elevation(self, x, y) as f32])
+                    .collect();
+                let mut keep = vec![false; centers.len()];
+                keep[0] = true;
+                keep[centers.len() - 1] = true;
+                simplify(&centers, &mut keep, 0, centers.len() - 1);
+                ChannelSpline {
+                    edge: i,
+                    from: edge.from,
+                    to: edge.to,
+                    width: self.channel_width(&edge.tiles),
+                    points: centers.into_iter().zip(keep).filter(|&(_, k)| k).map(|(p, _)| p).collect(),
+                }
+            })
+            .collect()
+    }
+
+    /// Median cross-section of the corridor tiles of `tiles`.
+    fn channel_width(&self, tiles: &[(i32, i32)]) -> u32 {
+        let in_room = |x: i32, y: i32| self.rooms.iter().any(|r| r.contains(x, y));
+        let open = |x: i32, y: i32| !blocks_movement(self, x, y) && !in_room(x, y);
+        let mut widths: Vec<u32> = (0..tiles.len())
+            .filter(|&i| open(tiles[i].0, tiles[i].1))
+            .map(|i| {
+                let (before, after) = (tiles[i.saturating_sub(1)], tiles[(i + 1).min(tiles.len() - 1)]);
+                // Across is perpendicular to the step through this tile
+                let (ax, ay) = if before.1 == after.1 { (0, 1) } else { (1, 0) };
+                let (x, y) = tiles[i];
+                let side = |sign: i32| (1..).take_while(|&k| open(x + sign * k * ax, y + sign * k * ay)).take(MAX_WIDTH as usize).count() as u32;
+                (1 + side(1) + side(-1)).min(MAX_WIDTH)
+            })
+            .collect();
+        widths.sort_unstable();
+        widths.get(widths.len() / 2).copied().unwrap_or(1)
+    }
+}
+
+/// Ramer-Douglas-Peucker: mark the points between `first` and `last` that
+/// lie farther than [`SPLINE_TOLERANCE`] from the chord between them.
+fn simplify(points: &[[f32; 3]], keep: &mut [bool], first: usize, last: usize) {
+    if last <= first + 1 {
+        return;
+    }
+    let (a, b) = (points[first], points[last]);
+    let ab = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let chord = ab[0] * ab[0] + ab[1] * ab[1] + ab[2] * ab[2];
+    // Squared distance to the chord line, or to `a` when the ends coincide
+    let distance = |p: [f32; 3]| {
+        let ap = [p[0] - a[0], p[1] - a[1], p[2] - a[2]];
+        let square = ap[0] * ap[0] + ap[1] * ap[1] + ap[2] * ap[2];
+        if chord == 0.0 {
+            return square;
+        }
+        let cross = [ap[1] * ab[2] - ap[2] * ab[1], ap[2] * ab[0] - ap[0] * ab[2], ap[0] * ab[1] - ap[1] * ab[0]];
+        (cross[0] * cross[0] + cross[1] * cross[1] + cross[2] * cross[2]) / chord
+    };
+    let (far, d) = (first + 1..last)
+        .map(|i| (i, distance(points[i])))
+        .fold((first, 0.0), |best, cur| if cur.1 > best.1 { cur } else { best });
+    if d > SPLINE_TOLERANCE * SPLINE_TOLERANCE {
+        keep[far] = true;
+        simplify(points, keep, first, far);
+        simplify(points, keep, far, last);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::Room;
+
+    #[test]
+    fn straight_runs_keep_their_ends_and_corners_stay() {
+        // A two-wide corridor with one corner between two rooms
+        let mut level = Level::from_ascii(
+            "############\n\
+             #...########\n\
+             #..........#\n\
+             #..........#\n\
+             #########..#\n\
+             #########..#\n\
+             #########..#\n\
+             ########...#\n\
+             ########...#\n\
+             ############",
+        )
+        .unwrap();
+        level.rooms = vec![Room { x: 1, y: 1, w: 3, h: 3, ..Default::default() }, Room { x: 8, y: 7, w: 3, h: 2, ..Default::default() }];
+        let splines = level.channel_splines();
+        let spline = splines.iter().find(|s| s.from == 0 && s.to == 1).unwrap();
+        assert_eq!(spline.width, 2);
+        assert!(spline.points.len() >= 3 && spline.points.len() < 6, "{:?}", spline.points);
+
+        // The curve starts and ends on the room nodes and joins up
+        let graph = level.path_graph();
+        let start = graph.nodes[0].tile;
+        assert_eq!(spline.points[0], [start.0 as f32 + 0.5, start.1 as f32 + 0.5, 0.0]);
+        let segments = spline.bezier_segments();
+        assert_eq!(segments.len(), spline.points.len() - 1);
+        assert!(segments.windows(2).all(|w| w[0][3] == w[1][0]));
+    }
+}
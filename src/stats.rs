@@ -0,0 +1,324 @@
+//! Generation statistics.
+//!
+//! `LevelStats` is computed at the end of `generate` and attached to the
+//! returned `Level`. It summarizes how well the generator met its targets
+//! (rooms placed vs requested), how the map is composed (floor ratio,
+//! corridor length, tile histogram, elevation range), and how long each
+//! generation stage took.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Instant;
+
+use crate::dungeon::{is_walkable_char, Level, TILE_FLOOR};
+use crate::duration::RunModel;
+use crate::tiles::TileType;
+
+/// Wall-clock time spent in a single generation stage.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct StageTiming {
+    /// Stage name (e.g. `"rooms"`, `"connect"`, `"marble_tiles"`)
+    pub stage: String,
+    /// Elapsed time in microseconds
+    pub micros: u64,
+}
+
+/// Summary statistics for a generated level.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct LevelStats {
+    /// Fraction of tiles that are walkable (0.0 to 1.0)
+    pub floor_ratio: f32,
+    /// Tiles the level is built from: non-empty marble tiles in marble
+    /// mode, walkable tiles otherwise (`floor_ratio` times the map area)
+    pub tile_count: usize,
+    /// Number of rooms requested via `GeneratorParams::rooms`
+    pub rooms_requested: u32,
+    /// Number of rooms actually placed
+    pub rooms_placed: u32,
+    /// `rooms_placed / rooms_requested` (1.0 when no rooms were requested)
+    pub room_fill: f32,
+    /// Relaxation steps room placement needed: 0 none, 1 rooms allowed to
+    /// touch, 2 room sizes capped as well (see `GeneratorParams::relax_room_placement`)
+    pub room_relaxation: u32,
+    /// Whether generation stopped early on its time budget or cancel token
+    /// (see `GeneratorParams::time_budget`)
+    pub budget_exceeded: bool,
+    /// Number of floor tiles lying outside every room (corridors/channels)
+    pub corridor_tiles: usize,
+    /// Tile counts keyed by tile type (marble mode) or ASCII character
+    pub tile_histogram: BTreeMap<String, usize>,
+    /// Lowest elevation of any passable tile (marble and overworld modes)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elevation_min: Option<i32>,
+    /// Highest elevation of any passable tile (marble and overworld modes)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elevation_max: Option<i32>,
+    /// Shortest walk from the entrance to the exit room, in tiles (see
+    /// `Level::main_path_length`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub main_path_length: Option<u32>,
+    /// Length of the main path in meters, slopes included (marble mode,
+    /// see `Level::estimate_run`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub main_path_meters: Option<f32>,
+    /// Estimated seconds for a marble to roll the main path (marble mode,
+    /// see `Level::estimate_run`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub main_path_seconds: Option<f32>,
+    /// Hops in the room graph from the entrance to the exit room (see
+    /// `Level::entrance_exit_hops`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entrance_exit_hops: Option<u32>,
+    /// Levels generated to meet `GeneratorParams::min_path_length`; 1 when
+    /// the first one did or no minimum was set
+    pub attempts: u32,
+    /// Whether the level meets `GeneratorParams::target_floor_ratio` and
+    /// `max_total_tiles`; absent when neither is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tile_budget_met: Option<bool>,
+    /// Time spent in each generation stage, in execution order
+    pub stage_timings: Vec<StageTiming>,
+}
+
+impl LevelStats {
+    /// Compute statistics for `level`.
+    ///
+    /// `rooms_requested` is the room target from the generator params and
+    /// `stage_timings` the per-stage timings recorded during generation
+    /// (pass an empty vector when recomputing stats for an edited level).
+    /// `room_relaxation` starts at 0 and `attempts` at 1; `generate` fills
+    /// them in.
+    pub fn compute(level: &Level, rooms_requested: u32, stage_timings: Vec<StageTiming>) -> Self {
+        let total = (level.width as usize * level.height as usize).max(1);
+
+        let mut tile_histogram: BTreeMap<String, usize> = BTreeMap::new();
+        let mut floor = 0usize;
+        let mut elevation_min: Option<i32> = None;
+        let mut elevation_max: Option<i32> = None;
+
+        if let Some(marble_tiles) = &level.marble_tiles {
+            for tile in marble_tiles.iter().flatten() {
+                *tile_histogram.entry(format!("{:?}", tile.tile_type)).or_insert(0) += 1;
+                if tile.tile_type != TileType::Empty {
+                    floor += 1;
+                }
+                if tile.tile_type.is_passable() {
+                    elevation_min = Some(elevation_min.map_or(tile.elevation, |m| m.min(tile.elevation)));
+                    elevation_max = Some(elevation_max.map_or(tile.elevation, |m| m.max(tile.elevation)));
+                }
+            }
+        } else {
+            if let Some(terrain) = &level.terrain {
+                for tile in terrain.iter().flatten() {
+                    elevation_min = Some(elevation_min.map_or(tile.elevation, |m| m.min(tile.elevation)));
+                    elevation_max = Some(elevation_max.map_or(tile.elevation, |m| m.max(tile.elevation)));
+                }
+            }
+            for ch in level.tiles.iter().flat_map(|row| row.chars()) {
+                *tile_histogram.entry(ch.to_string()).or_insert(0) += 1;
+                if is_walkable_char(ch) {
+                    floor += 1;
+                }
+            }
+        }
+
+        let corridor_tiles = count_corridor_tiles(level);
+        let run = level.estimate_run(&RunModel::default());
+
+        Self {
+            floor_ratio: floor as f32 / total as f32,
+            tile_count: floor,
+            rooms_requested,
+            rooms_placed: level.rooms.len() as u32,
+            room_fill: if rooms_requested == 0 { 1.0 } else { level.rooms.len() as f32 / rooms_requested as f32 },
+            room_relaxation: 0,
+            budget_exceeded: false,
+            corridor_tiles,
+            tile_histogram,
+            elevation_min,
+            elevation_max,
+            main_path_length: level.main_path_length(),
+            main_path_meters: run.map(|run| run.meters),
+            main_path_seconds: run.map(|run| run.seconds),
+            entrance_exit_hops: level.entrance_exit_hops(),
+            attempts: 1,
+            tile_budget_met: None,
+            stage_timings,
+        }
+    }
+
+    /// Total generation time across all recorded stages, in microseconds.
+    pub fn total_micros(&self) -> u64 {
+        self.stage_timings.iter().map(|t| t.micros).sum()
+    }
+
+    /// Per-stage timing table with each stage's share of the total, for
+    /// spotting hotspots (`--bench-profile` in the CLI).
+    pub fn stage_profile(&self) -> String {
+        let total = self.total_micros().max(1) as f64;
+        let mut out = format!("{:<16} {:>10} {:>7}\n", "stage", "µs", "share");
+        for timing in &self.stage_timings {
+            out += &format!("{:<16} {:>10} {:>6.1}%\n", timing.stage, timing.micros, timing.micros as f64 * 100.0 / total);
+        }
+        out + &format!("{:<16} {:>10} {:>6.1}%", "total", self.total_micros(), 100.0)
+    }
+}
+
+/// Count floor tiles that are not inside any room rectangle.
+fn count_corridor_tiles(level: &Level) -> usize {
+    let mut count = 0;
+    for (y, row) in level.ascii_tiles().iter().enumerate() {
+        for (x, ch) in row.chars().enumerate() {
+            if ch != TILE_FLOOR {
+                continue;
+            }
+            let (ix, iy) = (x as i32, y as i32);
+            let in_room = level.rooms.iter().any(|r| r.contains(ix, iy));
+            if !in_room {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+impl fmt::Display for LevelStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Level statistics:")?;
+        writeln!(f, "  Floor ratio:    {:.1}%", self.floor_ratio * 100.0)?;
+        writeln!(f, "  Tiles:          {}", self.tile_count)?;
+        if let Some(met) = self.tile_budget_met {
+            writeln!(f, "  Tile budget:    {}", if met { "met" } else { "missed" })?;
+        }
+        writeln!(
+            f,
+            "  Rooms:          {} placed / {} requested ({:.0}%)",
+            self.rooms_placed,
+            self.rooms_requested,
+            self.room_fill * 100.0
+        )?;
+        if self.room_relaxation > 0 {
+            writeln!(f, "  Relaxation:     {} step(s)", self.room_relaxation)?;
+        }
+        if self.budget_exceeded {
+            writeln!(f, "  Time budget:    exceeded, optional stages skipped")?;
+        }
+        writeln!(f, "  Corridor tiles: {}", self.corridor_tiles)?;
+        if let (Some(min), Some(max)) = (self.elevation_min, self.elevation_max) {
+            writeln!(f, "  Elevation:      {} to {}", min, max)?;
+        }
+        if let Some(length) = self.main_path_length {
+            writeln!(f, "  Main path:      {} tiles", length)?;
+        }
+        if let Some(hops) = self.entrance_exit_hops {
+            writeln!(f, "  Entrance-exit:  {} room hop(s)", hops)?;
+        }
+        if let (Some(meters), Some(seconds)) = (self.main_path_meters, self.main_path_seconds) {
+            writeln!(f, "  Run:            about {:.0} s over {:.1} m", seconds, meters)?;
+        }
+        if self.attempts > 1 {
+            writeln!(f, "  Attempts:       {}", self.attempts)?;
+        }
+        writeln!(f, "  Tile histogram:")?;
+        for (tile, count) in &self.tile_histogram {
+            writeln!(f, "    {:<16} {}", tile, count)?;
+        }
+        writeln!(f, "  Stage timings:")?;
+        for timing in &self.stage_timings {
+            writeln!(f, "    {:<16} {} µs", timing.stage, timing.micros)?;
+        }
+        write!(f, "    {:<16} {} µs", "total", self.total_micros())
+    }
+}
+
+/// Records elapsed time between successive generation stages.
+pub(crate) struct StageTimer {
+    last: Instant,
+    timings: Vec<StageTiming>,
+}
+
+impl StageTimer {
+    pub(crate) fn start() -> Self {
+        Self { last: Instant::now(), timings: Vec::new() }
+    }
+
+    /// Close the current stage under `name` and start timing the next one.
+    pub(crate) fn mark(&mut self, name: &str) {
+        let now = Instant::now();
+        self.timings.push(StageTiming {
+            stage: name.to_string(),
+            micros: now.duration_since(self.last).as_micros() as u64,
+        });
+        self.last = now;
+    }
+
+    pub(crate) fn finish(self) -> Vec<StageTiming> {
+        self.timings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams};
+
+    #[test]
+    fn classic_stats_match_tiles() {
+        let params = GeneratorParams { seed: Some(42), ..Default::default() };
+        let level = generate(&params);
+        let stats = &level.stats;
+
+        let floors: usize = level.tiles.iter().map(|r| r.chars().filter(|&c| c == TILE_FLOOR).count()).sum();
+        let total = (level.width * level.height) as f32;
+        assert!((stats.floor_ratio - floors as f32 / total).abs() < 1e-6);
+        assert_eq!(stats.rooms_requested, params.rooms);
+        assert_eq!(stats.rooms_placed as usize, level.rooms.len());
+        assert_eq!(stats.tile_histogram.get("."), Some(&floors));
+        assert!(stats.to_string().lines().any(|line| line.trim_start().starts_with(". ")));
+        assert!(stats.corridor_tiles > 0);
+        assert!(stats.elevation_min.is_none());
+        assert!(stats.stage_timings.iter().any(|t| t.stage == "rooms"));
+    }
+
+    #[test]
+    fn crowded_maps_report_room_fill_and_relaxation() {
+        let crowded = GeneratorParams { seed: Some(3), width: 30, height: 20, rooms: 30, ..Default::default() };
+        let strict = generate(&crowded).stats;
+        assert!(strict.rooms_placed < 30 && strict.room_relaxation == 0);
+        assert!((strict.room_fill - strict.rooms_placed as f32 / 30.0).abs() < 1e-6);
+
+        let relaxed = generate(&GeneratorParams { relax_room_placement: true, ..crowded.clone() }).stats;
+        assert_eq!(relaxed.room_relaxation, 2);
+        assert!(relaxed.rooms_placed > strict.rooms_placed);
+
+        let tiny_budget = generate(&GeneratorParams { room_attempts: Some(1), ..crowded }).stats;
+        assert!(tiny_budget.rooms_placed <= 1);
+    }
+
+    #[test]
+    fn marble_stats_report_elevation_range() {
+        let params = GeneratorParams {
+            seed: Some(7),
+            mode: GenerationMode::Marble,
+            enable_elevation: true,
+            ..Default::default()
+        };
+        let level = generate(&params);
+        let stats = &level.stats;
+
+        let min = stats.elevation_min.expect("marble levels report elevation");
+        let max = stats.elevation_max.expect("marble levels report elevation");
+        assert!(min <= max);
+        let counted: usize = stats.tile_histogram.values().sum();
+        assert_eq!(counted, (level.width * level.height) as usize);
+
+        let profile = stats.stage_profile();
+        let stages: Vec<&str> = profile.lines().skip(1).map(|line| line.split_whitespace().next().unwrap()).collect();
+        assert_eq!(&stages[..4], ["rooms", "connect", "roles", "zones"]);
+        assert_eq!(&stages[4..], ["elevation", "marble_tiles", "total"]);
+    }
+}
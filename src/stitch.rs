@@ -0,0 +1,456 @@
+//! Cropping levels and joining them side by side.
+//!
+//! [`Level::crop`] cuts a rectangle out of a level and
+//! [`Level::concat_horizontal`] puts a second level to the right of the
+//! first, so long marble courses can be chained from generated sections.
+//! Both return levels that pass [`Level::check_invariants`]:
+//!
+//! - Marble tiles are detected again along the cut edges and the seam (see
+//!   [`Level::update_marble_tiles`]), so channels that ran off the crop end
+//!   in a wall and channels meeting at the seam join up.
+//! - A crop keeps the largest connected stretch of floor and fills in floor
+//!   it cut off. Obstacles and structures cut by the crop are taken out
+//!   whole, rooms are clipped to it, and side routes are dropped.
+//! - A join carves a channel across the seam as [`StitchStrategy`] says, in
+//!   the width of the left level's channels, and lifts the right level so
+//!   the rooms it joins sit at the same height. The left level's exit and
+//!   the right level's entrance become normal rooms, so a chain of sections
+//!   keeps one entrance and one exit.
+//!
+//! Room connections, corridor ceilings, anchors, zone sizes, and stats are
+//! worked out again for the result. Ids of the right level's obstacles,
+//! structures, moving hazards, zones, and rivers move past the left level's,
+//! and its side routes follow its rooms.
+//! Levels of different modes can be joined, but layers only one side has
+//! are dropped, apart from the zone and river maps, which read `-1` on the
+//! other side. Hex crops start on an even row so the offset rows still line
+//! up. Crops and joins of a lean level (see [`crate::memory`]) are lean.
+//!
+//! ```
+//! use level_generator::{generate, GenerationMode, GeneratorParams, Rect, StitchStrategy};
+//!
+//! let section = |seed| generate(&GeneratorParams { seed: Some(seed), width: 40, height: 20, rooms: 4, mode: GenerationMode::Marble, ..Default::default() });
+//! let course = section(1).concat_horizontal(&section(2), StitchStrategy::ExitToEntrance);
+//! assert_eq!(course.width, 80);
+//! assert!(course.check_invariants().is_empty());
+//! let start = course.crop(Rect::new(0, 0, 30, 20));
+//! assert!(start.check_invariants().is_empty());
+//! ```
+
+use std::collections::VecDeque;
+
+use serde_json::Value;
+
+use crate::anchors::add_anchors;
+use crate::dungeon::{carve_arc_channel, carve_horizontal_tunnel, carve_vertical_tunnel, number_rooms, Grid, Level, Room, TILE_FLOOR, TILE_WALL};
+use crate::editing::Rect;
+use crate::graph::room_connections;
+use crate::hex::GridTopology;
+use crate::obstacles::remove_obstacles;
+use crate::overworld::{TerrainTile, TerrainType};
+use crate::roles::RoomRole;
+use crate::stats::LevelStats;
+use crate::tiles::MarbleTile;
+use crate::voxel::VoxelMap;
+
+/// Width of the tile strip detected again along a cut or a seam.
+const SEAM: i32 = 2;
+
+/// Radius of the turn in a marble channel carved across a seam.
+const STITCH_RADIUS: i32 = 2;
+
+/// How [`Level::concat_horizontal`] joins the two levels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StitchStrategy {
+    /// Carve a channel from the left level's exit room to the right
+    /// level's entrance room (the rooms nearest the seam when a level has
+    /// no such role)
+    #[default]
+    ExitToEntrance,
+    /// Carve a channel between the two rooms closest to each other across
+    /// the seam
+    NearestRooms,
+    /// Carve nothing; the levels join only where floor already meets floor
+    /// across the seam, so sections must be drawn to line up
+    Abut,
+}
+
+impl Level {
+    /// The part of this level inside `rect` (clipped to the map); see the
+    /// module docs.
+    pub fn crop(&self, rect: Rect) -> Level {
+        let mut rect = rect.clip(self.width as i32, self.height as i32);
+        if self.topology == GridTopology::Hex && rect.y % 2 == 1 {
+            rect = Rect::new(rect.x, rect.y - 1, rect.w, rect.h + 1);
+        }
+        let inside = |&(x, y): &(i32, i32)| rect.contains(x, y);
+        let shift = |(x, y): (i32, i32)| (x - rect.x, y - rect.y);
+        let (x0, y0, w, h) = (rect.x as usize, rect.y as usize, rect.w.max(0) as usize, rect.h.max(0) as usize);
+
+        let mut level = self.clone();
+        // Obstacles cut by the crop give back the tiles they covered
+        let (kept, cut): (Vec<_>, Vec<_>) = std::mem::take(&mut level.obstacles).into_iter().partition(|o| o.tiles.iter().all(inside));
+        if let Some(tiles) = &mut level.marble_tiles {
+            remove_obstacles(tiles, &cut);
+        }
+        level.obstacles = kept;
+        for obstacle in &mut level.obstacles {
+            obstacle.tiles = obstacle.tiles.iter().map(|&at| shift(at)).collect();
+        }
+        let mut dirty: Vec<Rect> = Vec::new();
+        level.structures.retain(|s| {
+            let members: Vec<(i32, i32)> = s.tiles.iter().copied().filter(inside).map(shift).collect();
+            if members.len() < s.tiles.len() {
+                dirty.extend(members.iter().map(|&(x, y)| Rect::new(x, y, 1, 1)));
+            }
+            members.len() == s.tiles.len()
+        });
+        for structure in &mut level.structures {
+            structure.tiles = structure.tiles.iter().map(|&at| shift(at)).collect();
+            for port in [&mut structure.entry, &mut structure.exit] {
+                (port.x, port.y) = shift((port.x, port.y));
+            }
+        }
+        level.dynamics.retain(|d| rect.contains(d.x, d.y));
+        for element in &mut level.dynamics {
+            (element.x, element.y) = shift((element.x, element.y));
+        }
+
+        level.width = w as u32;
+        level.height = h as u32;
+        level.tiles = self.ascii_tiles()[y0..y0 + h].iter().map(|row| row.chars().skip(x0).take(w).collect()).collect();
+        level.marble_tiles = level.marble_tiles.as_deref().map(|g| slice(g, rect));
+        level.terrain = self.terrain.as_deref().map(|g| slice(g, rect));
+        level.river_map = self.river_map.as_deref().map(|g| slice(g, rect));
+        level.zone_map = self.zone_map.as_deref().map(|g| slice(g, rect));
+        level.voxels = self.voxels.as_ref().map(|voxels| {
+            let mut map = VoxelMap::new(w as u32, h as u32);
+            for (x, y, z, tile) in voxels.iter().filter(|&(x, y, _, _)| rect.contains(x, y)) {
+                map.insert(x - rect.x, y - rect.y, z, tile.clone());
+            }
+            map
+        });
+        let clip_room = |room: &Room| {
+            let r = Rect::new(room.x, room.y, room.w, room.h).clip(rect.x + rect.w, rect.y + rect.h);
+            let r = Rect::new(r.x.max(rect.x), r.y.max(rect.y), r.w - (rect.x - r.x).max(0), r.h - (rect.y - r.y).max(0));
+            (r.w > 0 && r.h > 0).then(|| Room { x: r.x - rect.x, y: r.y - rect.y, w: r.w, h: r.h, ..room.clone() })
+        };
+        level.rooms = self.rooms.iter().filter_map(clip_room).collect();
+        // Rooms cut away would leave routes pointing at the wrong ones
+        level.side_routes.clear();
+        level.plazas = self.plazas.iter().filter_map(clip_room).collect();
+
+        // Re-detect along the cut edges and where structures were cut, then
+        // wherever cut-off floor was filled in
+        fill_cut_off_floor(&mut level);
+        let (w, h) = (w as i32, h as i32);
+        dirty.extend([Rect::new(0, 0, w, SEAM), Rect::new(0, h - SEAM, w, SEAM), Rect::new(0, 0, SEAM, h), Rect::new(w - SEAM, 0, SEAM, h)]);
+        for region in dirty {
+            level.update_marble_tiles(region);
+        }
+        level.rebuild_marble_tiles();
+        level.relink();
+        if self.is_lean() {
+            level.tiles = Vec::new();
+        }
+        level
+    }
+
+    /// This level with `other` placed to its right and the two joined as
+    /// `stitch` says; see the module docs.
+    pub fn concat_horizontal(&self, other: &Level, stitch: StitchStrategy) -> Level {
+        let (left_width, right_width) = (self.width as i32, other.width as i32);
+        let (width, height) = ((left_width + right_width) as usize, self.height.max(other.height) as usize);
+        let moved = |room: &Room| (room.center().0 + left_width, room.center().1);
+
+        // The rooms to join, and how far to lift the right level to match them
+        let (from, to) = match stitch {
+            StitchStrategy::NearestRooms => {
+                let pairs = (0..self.rooms.len()).flat_map(|i| (0..other.rooms.len()).map(move |j| (i, j)));
+                let distance = |&(i, j): &(usize, usize)| {
+                    let ((ax, ay), (bx, by)) = (self.rooms[i].center(), moved(&other.rooms[j]));
+                    (ax - bx).pow(2) + (ay - by).pow(2)
+                };
+                pairs.min_by_key(distance).map_or((None, None), |(i, j)| (Some(i), Some(j)))
+            }
+            _ => (seam_room(self, RoomRole::Exit, true), seam_room(other, RoomRole::Entrance, false)),
+        };
+        let lift = match (from.and_then(|i| self.rooms[i].elevation), to.and_then(|j| other.rooms[j].elevation)) {
+            (Some(a), Some(b)) if stitch != StitchStrategy::Abut => a - b,
+            _ => 0,
+        };
+
+        // Ids on the right move past those on the left
+        let obstacle_base = self.obstacles.iter().map(|o| o.id + 1).max().unwrap_or(0);
+        let structure_base = self.structures.iter().map(|s| s.id + 1).max().unwrap_or(0);
+        let dynamic_base = self.dynamics.iter().map(|d| d.id + 1).max().unwrap_or(0);
+        let river_base = self.river_map.iter().flatten().flatten().map(|&r| r + 1).max().unwrap_or(0);
+        let zone_base = self.zones.len() as i32;
+        let lift_tile = |tile: &MarbleTile| {
+            let mut tile = tile.clone();
+            tile.elevation += lift;
+            if let Ok(Value::Object(mut fields)) = serde_json::from_str::<Value>(&tile.metadata) {
+                for (key, base) in [("obstacle", obstacle_base), ("structure", structure_base)] {
+                    if let Some(id) = fields.get(key).and_then(Value::as_u64) {
+                        fields.insert(key.into(), Value::from(id + base as u64));
+                    }
+                }
+                tile.metadata = Value::Object(fields).to_string();
+            }
+            tile
+        };
+
+        let mut level = self.clone();
+        level.width = width as u32;
+        level.height = height as u32;
+        let (left_tiles, right_tiles) = (self.ascii_tiles(), other.ascii_tiles());
+        let rows = |y: usize, tiles: &[String], width: i32| tiles.get(y).cloned().unwrap_or_else(|| TILE_WALL.to_string().repeat(width as usize));
+        level.tiles = (0..height).map(|y| rows(y, &left_tiles, left_width) + &rows(y, &right_tiles, right_width)).collect();
+        level.marble_tiles = match (&self.marble_tiles, &other.marble_tiles) {
+            (Some(a), Some(b)) => Some(join(a, &b.iter().map(|row| row.iter().map(lift_tile).collect()).collect::<Vec<Vec<_>>>(), (left_width, right_width), height, MarbleTile::empty())),
+            _ => None,
+        };
+        let rock = TerrainTile { terrain: TerrainType::Rock, elevation: 0 };
+        level.terrain = match (&self.terrain, &other.terrain) {
+            (Some(a), Some(b)) => Some(join(a, b, (left_width, right_width), height, rock)),
+            _ => None,
+        };
+        let ids = |map: &Option<Vec<Vec<i32>>>, base: i32, w: i32, h: u32| {
+            map.clone().map(|m| m.iter().map(|row| row.iter().map(|&id| if id >= 0 { id + base } else { id }).collect()).collect()).unwrap_or_else(|| vec![vec![-1; w as usize]; h as usize])
+        };
+        if self.river_map.is_some() || other.river_map.is_some() {
+            level.river_map = Some(join(&ids(&self.river_map, 0, left_width, self.height), &ids(&other.river_map, river_base, right_width, other.height), (left_width, right_width), height, -1));
+        }
+        if self.zone_map.is_some() || other.zone_map.is_some() {
+            level.zone_map = Some(join(&ids(&self.zone_map, 0, left_width, self.height), &ids(&other.zone_map, zone_base, right_width, other.height), (left_width, right_width), height, -1));
+        }
+        level.zones.extend(other.zones.iter().map(|z| {
+            let mut zone = z.clone();
+            zone.id += zone_base as usize;
+            zone
+        }));
+
+        let shift_room = |room: &Room| Room { x: room.x + left_width, elevation: room.elevation.map(|e| e + lift), ..room.clone() };
+        level.rooms.extend(other.rooms.iter().map(shift_room));
+        level.plazas.extend(other.plazas.iter().map(shift_room));
+        level.obstacles.extend(other.obstacles.iter().map(|o| {
+            let mut obstacle = o.clone();
+            obstacle.id += obstacle_base;
+            obstacle.tiles = o.tiles.iter().map(|&(x, y)| (x + left_width, y)).collect();
+            obstacle.covered = o.covered.iter().map(lift_tile).collect();
+            obstacle
+        }));
+        level.structures.extend(other.structures.iter().map(|s| {
+            let mut structure = s.clone();
+            structure.id += structure_base;
+            structure.tiles = s.tiles.iter().map(|&(x, y)| (x + left_width, y)).collect();
+            for port in [&mut structure.entry, &mut structure.exit] {
+                port.x += left_width;
+                port.elevation += lift;
+            }
+            structure
+        }));
+        level.side_routes.extend(other.side_routes.iter().map(|r| {
+            let mut route = r.clone();
+            (route.from, route.to) = (r.from + self.rooms.len(), r.to + self.rooms.len());
+            route.tiles = r.tiles.iter().map(|&(x, y)| (x + left_width, y)).collect();
+            route.obstacles = r.obstacles.iter().map(|id| id + obstacle_base).collect();
+            route
+        }));
+        level.dynamics.extend(other.dynamics.iter().map(|d| {
+            let mut element = d.clone();
+            element.id += dynamic_base;
+            element.x += left_width;
+            element.elevation += lift;
+            element
+        }));
+        level.voxels = match (&level.marble_tiles, &self.voxels, &other.voxels) {
+            (Some(_), None, None) | (None, _, _) => None,
+            (Some(_), left, right) => {
+                let mut map = VoxelMap::new(width as u32, height as u32);
+                let sides = [(self, left, 0, 0), (other, right, left_width, lift)];
+                for (side, voxels, dx, dz) in sides {
+                    let lifted = voxels.clone().or_else(|| side.marble_tiles.as_deref().map(VoxelMap::from_tiles));
+                    for (x, y, z, tile) in lifted.iter().flat_map(|m| m.iter()) {
+                        map.insert(x + dx, y, z + dz, if dx > 0 { lift_tile(tile) } else { tile.clone() });
+                    }
+                }
+                Some(map)
+            }
+        };
+        level.stats = LevelStats {
+            rooms_requested: self.stats.rooms_requested + other.stats.rooms_requested,
+            room_relaxation: self.stats.room_relaxation.max(other.stats.room_relaxation),
+            ..LevelStats::default()
+        };
+
+        // The course runs from the left level into the right one
+        let right = |j: usize| self.rooms.len() + j;
+        for i in [seam_room(self, RoomRole::Exit, true).filter(|&i| self.rooms[i].role == RoomRole::Exit), seam_room(other, RoomRole::Entrance, false).filter(|&j| other.rooms[j].role == RoomRole::Entrance).map(right)].into_iter().flatten() {
+            level.rooms[i].role = RoomRole::Normal;
+        }
+        if let (Some(i), Some(j), false) = (from, to, stitch == StitchStrategy::Abut) {
+            let (start, end) = (level.rooms[i].center(), level.rooms[right(j)].center());
+            let mut grid: Grid = level.tiles.iter().map(|row| row.chars().collect()).collect();
+            if level.marble_tiles.is_some() {
+                let mut widths: Vec<u32> = self.channel_splines().iter().map(|s| s.width).collect();
+                widths.sort_unstable();
+                let channel = widths.get(widths.len() / 2).copied().unwrap_or(2) as i32;
+                carve_arc_channel(&mut grid, start, end, true, channel, STITCH_RADIUS);
+            } else {
+                carve_horizontal_tunnel(&mut grid, start.0, end.0, start.1);
+                carve_vertical_tunnel(&mut grid, start.1, end.1, end.0);
+            }
+            level.tiles = grid.into_iter().map(|row| row.into_iter().collect()).collect();
+        }
+
+        level.update_marble_tiles(Rect::new(left_width - SEAM / 2, 0, SEAM, height as i32));
+        level.rebuild_marble_tiles();
+        level.relink();
+        if self.is_lean() {
+            level.tiles = Vec::new();
+        }
+        level
+    }
+
+    /// Number the rooms and work out connections, corridor ceilings,
+    /// anchors, zone sizes, and stats again after the tiles changed. The
+    /// mission is dropped, since the rooms it was placed on moved.
+    pub(crate) fn relink(&mut self) {
+        number_rooms(&mut self.rooms);
+        self.connections = room_connections(&self.ascii_tiles(), &self.rooms, self.topology);
+        self.mission = None;
+        if let Some(cap) = self.corridor_ceilings.iter().copied().max() {
+            let ceiling = |i: usize| self.rooms[i].ceiling.unwrap_or(cap);
+            self.corridor_ceilings = self.connections.iter().map(|&(a, b)| ceiling(a).min(ceiling(b)).min(cap)).collect();
+        }
+        if let Some(tiles) = &mut self.marble_tiles {
+            add_anchors(tiles, &self.obstacles);
+        }
+        if let Some(map) = &self.zone_map {
+            for zone in &mut self.zones {
+                zone.tile_count = map.iter().flatten().filter(|&&id| id == zone.id as i32).count();
+            }
+        }
+        self.refresh_stats();
+    }
+}
+
+/// Room with `role`, or else the room whose center lies nearest the east
+/// (`east`) or west edge.
+fn seam_room(level: &Level, role: RoomRole, east: bool) -> Option<usize> {
+    let edge = |i: &usize| {
+        let x = level.rooms[*i].center().0;
+        if east { -x } else { x }
+    };
+    (0..level.rooms.len()).find(|&i| level.rooms[i].role == role).or_else(|| (0..level.rooms.len()).min_by_key(edge))
+}
+
+/// The part of `grid` inside `rect`, which lies within it.
+fn slice<T: Clone>(grid: &[Vec<T>], rect: Rect) -> Vec<Vec<T>> {
+    let (x, w) = (rect.x as usize, rect.w as usize);
+    grid[rect.y as usize..(rect.y + rect.h) as usize].iter().map(|row| row[x..x + w].to_vec()).collect()
+}
+
+/// Rows of `left` followed by rows of `right`, each padded with `pad` to
+/// its side's width and `height` rows.
+fn join<T: Clone>(left: &[Vec<T>], right: &[Vec<T>], (left_width, right_width): (i32, i32), height: usize, pad: T) -> Vec<Vec<T>> {
+    let row = |side: &[Vec<T>], y: usize, width: i32| side.get(y).cloned().unwrap_or_else(|| vec![pad.clone(); width as usize]);
+    (0..height).map(|y| [row(left, y, left_width), row(right, y, right_width)].concat()).collect()
+}
+
+/// Fill in every floor tile outside the largest connected stretch of floor.
+fn fill_cut_off_floor(level: &mut Level) {
+    let mut grid: Grid = level.tiles.iter().map(|row| row.chars().collect()).collect();
+    let (width, height) = (level.width as i32, level.height as i32);
+    let mut region = vec![vec![usize::MAX; width as usize]; height as usize];
+    let mut sizes: Vec<usize> = Vec::new();
+    for sy in 0..height {
+        for sx in 0..width {
+            if grid[sy as usize][sx as usize] != TILE_FLOOR || region[sy as usize][sx as usize] != usize::MAX {
+                continue;
+            }
+            let id = sizes.len();
+            let mut size = 0;
+            region[sy as usize][sx as usize] = id;
+            let mut queue = VecDeque::from([(sx, sy)]);
+            while let Some((x, y)) = queue.pop_front() {
+                size += 1;
+                for (nx, ny) in level.topology.neighbors(x, y) {
+                    if nx >= 0 && ny >= 0 && nx < width && ny < height && grid[ny as usize][nx as usize] == TILE_FLOOR && region[ny as usize][nx as usize] == usize::MAX {
+                        region[ny as usize][nx as usize] = id;
+                        queue.push_back((nx, ny));
+                    }
+                }
+            }
+            sizes.push(size);
+        }
+    }
+    // The first of the largest regions stays
+    let Some(largest) = (0..sizes.len()).max_by_key(|&i| (sizes[i], usize::MAX - i)) else { return };
+    for (row, ids) in grid.iter_mut().zip(&region) {
+        for (ch, &id) in row.iter_mut().zip(ids) {
+            if id != usize::MAX && id != largest {
+                *ch = TILE_WALL;
+            }
+        }
+    }
+    level.tiles = grid.into_iter().map(|row| row.into_iter().collect()).collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams};
+
+    fn section(seed: u64) -> Level {
+        generate(&GeneratorParams {
+            seed: Some(seed),
+            width: 50,
+            height: 24,
+            rooms: 5,
+            mode: GenerationMode::Marble,
+            enable_elevation: true,
+            enable_obstacles: true,
+            enable_dynamics: true,
+            channel_width: 3,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn crops_and_joins_stay_valid_courses() {
+        let (a, b) = (section(11), section(12));
+        for stitch in [StitchStrategy::ExitToEntrance, StitchStrategy::NearestRooms] {
+            let course = a.concat_horizontal(&b, stitch);
+            assert_eq!((course.width, course.height), (100, 24));
+            assert!(course.check_invariants().is_empty(), "{:?}", course.check_invariants());
+            let roles = |role| course.rooms.iter().filter(|r| r.role == role).count();
+            assert_eq!((roles(RoomRole::Entrance), roles(RoomRole::Exit)), (1, 1));
+            assert_eq!(course.rooms.len(), a.rooms.len() + b.rooms.len());
+
+            // One walk leads from the left entrance to the right exit
+            let graph = course.path_graph();
+            let entrance = course.rooms.iter().position(|r| r.role == RoomRole::Entrance).unwrap();
+            let exit = course.rooms.iter().position(|r| r.role == RoomRole::Exit).unwrap();
+            assert!(entrance < a.rooms.len() && exit >= a.rooms.len());
+            assert!(graph.shortest_route(graph.room_node(entrance).unwrap(), graph.room_node(exit).unwrap()).is_some());
+
+            let mut ids: Vec<usize> = course.obstacles.iter().map(|o| o.id).collect();
+            ids.dedup();
+            assert_eq!(ids.len(), a.obstacles.len() + b.obstacles.len());
+        }
+        // Sections joined without a channel stay apart
+        assert!(!a.concat_horizontal(&b, StitchStrategy::Abut).check_invariants().is_empty());
+
+        let course = a.concat_horizontal(&b, StitchStrategy::ExitToEntrance);
+        for rect in [Rect::new(20, 3, 45, 15), Rect::new(-5, -5, 30, 40), Rect::new(60, 0, 100, 10)] {
+            let cropped = course.crop(rect);
+            let clipped = rect.clip(100, 24);
+            assert_eq!((cropped.width, cropped.height), (clipped.w as u32, clipped.h as u32));
+            assert!(cropped.check_invariants().is_empty(), "{:?}", cropped.check_invariants());
+            assert!(cropped.rooms.iter().all(|r| r.x >= 0 && r.y >= 0 && r.x + r.w <= clipped.w && r.y + r.h <= clipped.h));
+            assert!(cropped.obstacles.iter().flat_map(|o| &o.tiles).all(|&(x, y)| x >= 0 && y >= 0 && x < clipped.w && y < clipped.h));
+        }
+    }
+}
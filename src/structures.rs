@@ -0,0 +1,270 @@
+//! Multi-tile marble structures.
+//!
+//! Some track features span several tiles and only make sense as a group.
+//! They are still stored tile by tile in `Level::marble_tiles` (each member
+//! tile carries its structure id in `metadata`), and every group is also
+//! described once in `Level::structures` with its footprint and the ports
+//! where the marble enters and leaves.
+//!
+//! - [`StructureKind::SpiralRamp`]: replaces a run of slopes that drops three
+//!   or more levels in one compact patch with a single helical ramp.
+//! - [`StructureKind::Funnel`]: a circular bowl centered in a large open room
+//!   that drains through its center tile to one level below the room.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::dungeon::Room;
+use crate::tiles::{Direction, MarbleTile, TileType};
+
+/// Minimum drop (in elevation levels) for a slope run to become a spiral ramp.
+pub const SPIRAL_RAMP_MIN_DROP: i32 = 3;
+
+/// Smallest funnel radius in tiles; rooms need sides of at least `2 * r + 3`
+/// (the bowl plus a one-tile rim).
+pub const FUNNEL_MIN_RADIUS: i32 = 2;
+/// Largest funnel radius in tiles.
+pub const FUNNEL_MAX_RADIUS: i32 = 4;
+
+const SIDES: [(i32, i32, Direction); 4] = [
+    (0, -1, Direction::North),
+    (1, 0, Direction::East),
+    (0, 1, Direction::South),
+    (-1, 0, Direction::West),
+];
+
+/// Kind of multi-tile structure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum StructureKind {
+    /// Helical ramp descending several levels
+    SpiralRamp,
+    /// Circular bowl draining to its center, one level down
+    Funnel,
+}
+
+/// Where the marble enters or leaves a structure: a member tile and the
+/// side of that tile it crosses (`None` for a vertical exit such as a drain).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Port {
+    pub x: i32,
+    pub y: i32,
+    pub elevation: i32,
+    pub side: Option<Direction>,
+}
+
+/// A multi-tile structure placed on the marble grid.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Structure {
+    /// Structure id, as stored in member tile metadata
+    pub id: usize,
+    pub kind: StructureKind,
+    /// Member tiles as `(x, y)`
+    pub tiles: Vec<(i32, i32)>,
+    /// Highest port; the marble comes in here
+    pub entry: Port,
+    /// Lowest port; the marble leaves here
+    pub exit: Port,
+}
+
+impl Structure {
+    /// Elevation levels between entry and exit.
+    pub fn drop(&self) -> i32 {
+        self.entry.elevation - self.exit.elevation
+    }
+}
+
+/// Replace compact slope runs dropping [`SPIRAL_RAMP_MIN_DROP`]+ levels with
+/// spiral ramps. `first_id` is the id given to the first new structure.
+pub fn place_spiral_ramps(tiles: &mut [Vec<MarbleTile>], first_id: usize) -> Vec<Structure> {
+    let height = tiles.len();
+    let width = if height > 0 { tiles[0].len() } else { 0 };
+    let at = |x: i32, y: i32| -> Option<(usize, usize)> {
+        (x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height).then_some((x as usize, y as usize))
+    };
+
+    let mut seen = vec![vec![false; width]; height];
+    let mut structures = Vec::new();
+    for sy in 0..height {
+        for sx in 0..width {
+            if seen[sy][sx] || tiles[sy][sx].tile_type != TileType::Slope {
+                continue;
+            }
+
+            // Collect one 4-connected run of slope tiles
+            let mut run = Vec::new();
+            let mut queue = VecDeque::from([(sx, sy)]);
+            seen[sy][sx] = true;
+            while let Some((x, y)) = queue.pop_front() {
+                run.push((x as i32, y as i32));
+                for (dx, dy, _) in SIDES {
+                    if let Some((nx, ny)) = at(x as i32 + dx, y as i32 + dy) {
+                        if !seen[ny][nx] && tiles[ny][nx].tile_type == TileType::Slope {
+                            seen[ny][nx] = true;
+                            queue.push_back((nx, ny));
+                        }
+                    }
+                }
+            }
+
+            // Ports: sides of run tiles that open onto other passable track
+            let mut ports: Vec<Port> = Vec::new();
+            for &(x, y) in &run {
+                for (dx, dy, side) in SIDES {
+                    let Some((nx, ny)) = at(x + dx, y + dy) else { continue };
+                    let neighbor = &tiles[ny][nx];
+                    if neighbor.tile_type.is_passable() && neighbor.tile_type != TileType::Slope {
+                        let elevation = tiles[y as usize][x as usize].elevation;
+                        ports.push(Port { x, y, elevation, side: Some(side) });
+                    }
+                }
+            }
+            let (Some(&entry), Some(&exit)) = (
+                ports.iter().max_by_key(|p| (p.elevation, -p.y, -p.x)),
+                ports.iter().min_by_key(|p| (p.elevation, p.y, p.x)),
+            ) else {
+                continue;
+            };
+            if entry.elevation - exit.elevation < SPIRAL_RAMP_MIN_DROP {
+                continue;
+            }
+
+            // Face each tile toward its lowest neighbor in the run (the exit tile faces out)
+            let id = first_id + structures.len();
+            let elevation = |x: i32, y: i32| tiles[y as usize][x as usize].elevation;
+            let rotations: Vec<u8> = run
+                .iter()
+                .map(|&(x, y)| {
+                    SIDES
+                        .iter()
+                        .filter(|&&(dx, dy, _)| run.contains(&(x + dx, y + dy)) && elevation(x + dx, y + dy) < elevation(x, y))
+                        .min_by_key(|&&(dx, dy, _)| elevation(x + dx, y + dy))
+                        .map_or(exit.side.map_or(0, |d| d as u8), |&(_, _, side)| side as u8)
+                })
+                .collect();
+            for (&(x, y), rotation) in run.iter().zip(rotations) {
+                let tile = &mut tiles[y as usize][x as usize];
+                *tile = MarbleTile::with_params(TileType::SpiralRamp, tile.elevation, rotation, true)
+                    .with_metadata(format!("{{\"structure\":{}}}", id));
+            }
+            run.sort_by_key(|&(x, y)| (y, x));
+            structures.push(Structure { id, kind: StructureKind::SpiralRamp, tiles: run, entry, exit });
+        }
+    }
+    structures
+}
+
+/// Place a funnel bowl in every room large enough to hold one.
+///
+/// The bowl covers the tiles within its radius of the room center, with a
+/// one-tile rim left for walking around it. Its entry is the rim tile nearest
+/// the room's first corridor opening; its exit is the center drain, one
+/// level below the room. `first_id` is the id given to the first new structure.
+pub fn place_funnels(tiles: &mut [Vec<MarbleTile>], rooms: &[Room], first_id: usize) -> Vec<Structure> {
+    let height = tiles.len() as i32;
+    let width = if height > 0 { tiles[0].len() as i32 } else { 0 };
+    let passable = |tiles: &[Vec<MarbleTile>], x: i32, y: i32| {
+        x >= 0 && y >= 0 && x < width && y < height && tiles[y as usize][x as usize].tile_type.is_passable()
+    };
+
+    let mut structures = Vec::new();
+    for room in rooms {
+        let radius = ((room.w.min(room.h) - 3) / 2).min(FUNNEL_MAX_RADIUS);
+        if radius < FUNNEL_MIN_RADIUS {
+            continue;
+        }
+        let (cx, cy) = room.center();
+        let elevation = room.elevation.unwrap_or(0);
+        let mut bowl: Vec<(i32, i32)> = Vec::new();
+        for y in cy - radius..=cy + radius {
+            for x in cx - radius..=cx + radius {
+                let inside = (x - cx).pow(2) + (y - cy).pow(2) <= radius * radius;
+                if inside && passable(tiles, x, y) {
+                    bowl.push((x, y));
+                }
+            }
+        }
+
+        // Corridor opening: first passable tile just outside the room
+        let opening = room.expanded(1).perimeter_tiles().into_iter().filter(|&(x, y)| passable(tiles, x, y)).min_by_key(|&(x, y)| (y, x));
+        let target = opening.unwrap_or((cx, room.y));
+        let rim = bowl
+            .iter()
+            .copied()
+            .filter(|&(x, y)| (x - cx).pow(2) + (y - cy).pow(2) > (radius - 1).pow(2))
+            .min_by_key(|&(x, y)| ((x - target.0).pow(2) + (y - target.1).pow(2), y, x));
+        let Some((ex, ey)) = rim else { continue };
+        let side = SIDES
+            .iter()
+            .max_by_key(|&&(dx, dy, _)| dx * (ex - cx) + dy * (ey - cy))
+            .map(|&(_, _, side)| side);
+
+        let id = first_id + structures.len();
+        for &(x, y) in &bowl {
+            let drain = (x, y) == (cx, cy);
+            let z = if drain { elevation - 1 } else { elevation };
+            let metadata = if drain {
+                format!("{{\"structure\":{},\"drain\":true}}", id)
+            } else {
+                format!("{{\"structure\":{}}}", id)
+            };
+            tiles[y as usize][x as usize] = MarbleTile::with_params(TileType::Funnel, z, 0, false).with_metadata(metadata);
+        }
+        structures.push(Structure {
+            id,
+            kind: StructureKind::Funnel,
+            tiles: bowl,
+            entry: Port { x: ex, y: ey, elevation, side },
+            exit: Port { x: cx, y: cy, elevation: elevation - 1, side: None },
+        });
+    }
+    structures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steep_slope_run_becomes_spiral_ramp() {
+        // Track at elevation 4 dropping through four slopes to elevation 0
+        let elevations = [4, 4, 3, 2, 1, 0, 0];
+        let mut tiles: Vec<Vec<MarbleTile>> = vec![elevations
+            .iter()
+            .enumerate()
+            .map(|(i, &e)| {
+                let kind = if (1..=4).contains(&i) { TileType::Slope } else { TileType::Straight };
+                MarbleTile::with_params(kind, e, 1, true)
+            })
+            .collect()];
+
+        let structures = place_spiral_ramps(&mut tiles, 0);
+        assert_eq!(structures.len(), 1);
+        let ramp = &structures[0];
+        assert_eq!(ramp.tiles.len(), 4);
+        assert_eq!((ramp.entry.x, ramp.entry.side), (1, Some(Direction::West)));
+        assert_eq!((ramp.exit.x, ramp.exit.side), (4, Some(Direction::East)));
+        assert_eq!(ramp.drop(), 3);
+        assert!(tiles[0][1..5].iter().all(|t| t.tile_type == TileType::SpiralRamp));
+        assert_eq!(tiles[0][2].rotation, Direction::East as u8);
+    }
+
+    #[test]
+    fn funnel_drains_one_level_down() {
+        let mut tiles = vec![vec![MarbleTile::with_params(TileType::OpenPlatform, 2, 0, false); 11]; 11];
+        let rooms = vec![Room { x: 1, y: 1, w: 9, h: 9, elevation: Some(2), ..Default::default() }];
+
+        let structures = place_funnels(&mut tiles, &rooms, 5);
+        assert_eq!(structures.len(), 1);
+        let funnel = &structures[0];
+        assert_eq!(funnel.id, 5);
+        assert_eq!(funnel.kind, StructureKind::Funnel);
+        assert_eq!((funnel.exit.x, funnel.exit.y, funnel.exit.elevation), (5, 5, 1));
+        assert_eq!(funnel.drop(), 1);
+        assert_eq!(tiles[5][5].tile_type, TileType::Funnel);
+        // Rim of the room stays walkable floor
+        assert_eq!(tiles[1][1].tile_type, TileType::OpenPlatform);
+    }
+}
@@ -0,0 +1,214 @@
+//! Golden-level snapshot helpers for tests.
+//!
+//! [`snapshot`] writes a level as canonical compact text: a header line,
+//! rooms, connections, structures, and the tile layers as one character per
+//! tile. Marble levels add three grids:
+//!
+//! - `marble`: tile type (see [`marble_char`])
+//! - `rotation`: `0`-`3`
+//! - `elevation`: `0`-`9`, then `A`-`Z` for 10-35; `a`-`z` for -1 to -26
+//!
+//! Stats and tile metadata are left out, so snapshots only change when the
+//! layout does. [`assert_snapshot`] compares a level against a stored file and
+//! panics with a line diff on mismatch; set `UPDATE_SNAPSHOTS=1` to rewrite it.
+//!
+//! ```no_run
+//! use level_generator::{generate, testing, GeneratorParams};
+//!
+//! let level = generate(&GeneratorParams { seed: Some(42), ..Default::default() });
+//! testing::assert_snapshot(&level, "tests/snapshots/classic_42.txt");
+//! ```
+
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use crate::dungeon::Level;
+use crate::tiles::{MarbleTile, TileType};
+
+/// Environment variable that makes [`assert_snapshot`] rewrite snapshots.
+pub const UPDATE_ENV: &str = "UPDATE_SNAPSHOTS";
+
+/// Differing lines shown before a snapshot diff is cut short.
+const MAX_DIFF_LINES: usize = 20;
+
+/// Single-character code for a marble tile type in snapshots.
+pub fn marble_char(tile_type: TileType) -> char {
+    match tile_type {
+        TileType::Empty => '#',
+        TileType::Straight => '-',
+        TileType::Curve90 => 'c',
+        TileType::TJunction => 't',
+        TileType::YJunction => 'y',
+        TileType::CrossJunction => '+',
+        TileType::Slope => '/',
+        TileType::OpenPlatform => '.',
+        TileType::Obstacle => 'O',
+        TileType::Merge => 'm',
+        TileType::OneWayGate => '>',
+        TileType::LoopDeLoop => 'o',
+        TileType::HalfPipe => 'u',
+        TileType::LaunchPad => '!',
+        TileType::Bridge => '=',
+        TileType::Tunnel => 'n',
+        TileType::Spiral => '@',
+        TileType::ElevatorDrop => 'v',
+        TileType::SpiralRamp => 's',
+        TileType::Funnel => 'f',
+    }
+}
+
+fn elevation_char(elevation: i32) -> char {
+    match elevation {
+        0..=9 => (b'0' + elevation as u8) as char,
+        10..=35 => (b'A' + (elevation - 10) as u8) as char,
+        -26..=-1 => (b'a' + (-elevation - 1) as u8) as char,
+        _ => '?',
+    }
+}
+
+fn push_grid(out: &mut String, name: &str, tiles: &[Vec<MarbleTile>], cell: impl Fn(&MarbleTile) -> char) {
+    out.push_str(name);
+    out.push('\n');
+    for row in tiles {
+        out.extend(row.iter().map(&cell));
+        out.push('\n');
+    }
+}
+
+/// Canonical compact text form of `level`.
+pub fn snapshot(level: &Level) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "level {}x{} seed {} topology {:?}", level.width, level.height, level.seed, level.topology);
+
+    let _ = writeln!(out, "rooms {}", level.rooms.len());
+    for (i, room) in level.rooms.iter().enumerate() {
+        let elevation = room.elevation.map_or("-".to_string(), |e| e.to_string());
+        let _ = writeln!(
+            out,
+            "  {}: {},{} {}x{} elev {} {:?}",
+            i, room.x, room.y, room.w, room.h, elevation, room.role
+        );
+    }
+    let connections: Vec<String> = level.connections.iter().map(|(a, b)| format!("{}-{}", a, b)).collect();
+    let _ = writeln!(out, "connections {}", connections.join(" "));
+    for s in &level.structures {
+        let _ = writeln!(
+            out,
+            "structure {} {:?} tiles {} entry {},{}@{} exit {},{}@{}",
+            s.id,
+            s.kind,
+            s.tiles.len(),
+            s.entry.x,
+            s.entry.y,
+            s.entry.elevation,
+            s.exit.x,
+            s.exit.y,
+            s.exit.elevation
+        );
+    }
+
+    out.push_str("tiles\n");
+    for row in level.ascii_tiles().iter() {
+        out.push_str(row);
+        out.push('\n');
+    }
+    if let Some(tiles) = &level.marble_tiles {
+        push_grid(&mut out, "marble", tiles, |t| marble_char(t.tile_type));
+        push_grid(&mut out, "rotation", tiles, |t| (b'0' + t.rotation % 4) as char);
+        push_grid(&mut out, "elevation", tiles, |t| elevation_char(t.elevation));
+    }
+    out
+}
+
+/// Line diff between two snapshots, or `None` if they are equal.
+///
+/// Lines are compared by position; each differing line is shown as a
+/// `-expected` / `+actual` pair with its line number.
+pub fn snapshot_diff(expected: &str, actual: &str) -> Option<String> {
+    if expected == actual {
+        return None;
+    }
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let mut out = String::new();
+    let mut shown = 0;
+    let mut differing = 0;
+    for i in 0..expected.len().max(actual.len()) {
+        let (e, a) = (expected.get(i), actual.get(i));
+        if e == a {
+            continue;
+        }
+        differing += 1;
+        if shown == MAX_DIFF_LINES {
+            continue;
+        }
+        shown += 1;
+        let _ = writeln!(out, "line {}:", i + 1);
+        if let Some(e) = e {
+            let _ = writeln!(out, "  -{}", e);
+        }
+        if let Some(a) = a {
+            let _ = writeln!(out, "  +{}", a);
+        }
+    }
+    if differing > shown {
+        let _ = writeln!(out, "... and {} more differing lines", differing - shown);
+    }
+    if differing == 0 {
+        // Only trailing newlines differ
+        out.push_str("snapshots differ only in trailing whitespace\n");
+    }
+    Some(out)
+}
+
+/// Compare `level` against the snapshot stored at `path`.
+///
+/// Writes the snapshot instead when the file does not exist yet or when
+/// `UPDATE_SNAPSHOTS` is set. Panics with a readable line diff on mismatch.
+pub fn assert_snapshot(level: &Level, path: impl AsRef<Path>) {
+    let path = path.as_ref();
+    let actual = snapshot(level);
+    if std::env::var_os(UPDATE_ENV).is_some() || !path.exists() {
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                let _ = fs::create_dir_all(parent);
+            }
+        }
+        fs::write(path, &actual).unwrap_or_else(|e| panic!("write snapshot {}: {}", path.display(), e));
+        return;
+    }
+    let expected = fs::read_to_string(path).unwrap_or_else(|e| panic!("read snapshot {}: {}", path.display(), e));
+    if let Some(diff) = snapshot_diff(&expected, &actual) {
+        panic!(
+            "level does not match snapshot {} (set {}=1 to update)\n{}",
+            path.display(),
+            UPDATE_ENV,
+            diff
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams};
+
+    #[test]
+    fn snapshot_is_stable_and_diffs_are_readable() {
+        let params = GeneratorParams { seed: Some(8), mode: GenerationMode::Marble, enable_elevation: true, ..Default::default() };
+        let level = generate(&params);
+        let text = snapshot(&level);
+        assert_eq!(text, snapshot(&generate(&params)));
+        assert!(text.starts_with("level 80x25 seed 8 topology Square\n"));
+        assert!(text.contains("\nmarble\n") && text.contains("\nelevation\n"));
+        assert_eq!(snapshot_diff(&text, &text), None);
+
+        let mut edited = level.clone();
+        edited.tiles[0].replace_range(0..1, ".");
+        let diff = snapshot_diff(&text, &snapshot(&edited)).unwrap();
+        let line = text.lines().position(|l| l == "tiles").unwrap() + 2;
+        assert!(diff.starts_with(&format!("line {}:\n  -#", line)), "{}", diff);
+        assert!(diff.contains("\n  +."));
+    }
+}
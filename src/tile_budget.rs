@@ -0,0 +1,106 @@
+//! Floor-ratio and tile-count budgets.
+//!
+//! `GeneratorParams::target_floor_ratio` (give or take
+//! `floor_ratio_tolerance`) and `GeneratorParams::max_total_tiles` bound how
+//! much of the map a level fills. Tiles are counted as in
+//! `stats.tile_count`: non-empty marble tiles in marble mode, walkable tiles
+//! otherwise, so in marble mode the budget is the number of track pieces.
+//!
+//! A level outside its budget is regenerated from the same seed with the
+//! room count scaled toward the target. When the room count cannot move
+//! any further (one room left, or the map has no space for more), marble
+//! mode narrows or widens its channels instead. This stops after
+//! `budget_retries` adjustments, or as soon as an adjustment would repeat
+//! one already tried; the level closest to the budget is then returned with
+//! `stats.tile_budget_met` set to `false`.
+
+use crate::dungeon::{GenerationMode, GeneratorParams, Level, MIN_ROOM_DIM};
+
+/// Widest channels an adjustment will try
+const MAX_CHANNEL_WIDTH: u32 = 4;
+
+/// Whether `params` sets a floor ratio or tile count budget.
+pub(crate) fn has_budget(params: &GeneratorParams) -> bool {
+    params.target_floor_ratio.is_some() || params.max_total_tiles.is_some()
+}
+
+/// How far `level` is outside the budget of `params`, as a fraction of the
+/// map area; 0 when it meets it.
+pub(crate) fn budget_miss(level: &Level, params: &GeneratorParams) -> f32 {
+    let area = (level.width as f32 * level.height as f32).max(1.0);
+    let ratio_miss = params.target_floor_ratio.map_or(0.0, |target| ((level.stats.floor_ratio - target).abs() - params.floor_ratio_tolerance).max(0.0));
+    let tile_miss = params.max_total_tiles.map_or(0.0, |max| level.stats.tile_count.saturating_sub(max as usize) as f32 / area);
+    ratio_miss + tile_miss
+}
+
+/// Params for the next attempt at a level that missed its budget: rooms
+/// scaled toward the target tile count, or channels one tile narrower or
+/// wider. `None` when every option is exhausted or already in `tried`
+/// (pairs of room count and channel width).
+pub(crate) fn adjust(level: &Level, params: &GeneratorParams, tried: &[(u32, u32)]) -> Option<GeneratorParams> {
+    let area = level.width as f32 * level.height as f32;
+    let count = level.stats.tile_count as f32;
+    let mut goal = params.target_floor_ratio.map_or(count, |target| {
+        let ratio = level.stats.floor_ratio;
+        if (ratio - target).abs() <= params.floor_ratio_tolerance { count } else { target * area }
+    });
+    if let Some(max) = params.max_total_tiles {
+        goal = goal.min(max as f32);
+    }
+    if !goal.is_finite() {
+        return None;
+    }
+    let grow = goal > count;
+
+    // No more rooms than the map has squares of the smallest room for
+    let fit = (level.width / (MIN_ROOM_DIM + 1)) * (level.height / (MIN_ROOM_DIM + 1));
+    let scaled = ((params.rooms as f32 * goal / count.max(1.0)).round() as u32).min(fit);
+    let rooms = if grow {
+        // Rooms the map had no space for will not fit next time either
+        (level.stats.rooms_placed >= params.rooms && params.rooms < fit).then(|| scaled.max(params.rooms + 1))
+    } else {
+        (params.rooms > 1).then(|| scaled.clamp(1, params.rooms - 1))
+    };
+    let channel_width = match params.mode {
+        GenerationMode::Marble if grow => (params.channel_width < MAX_CHANNEL_WIDTH).then(|| params.channel_width + 1),
+        GenerationMode::Marble => (params.channel_width > 1).then(|| params.channel_width - 1),
+        _ => None,
+    };
+
+    let candidates = [rooms.map(|rooms| (rooms, params.channel_width)), channel_width.map(|width| (params.rooms, width))];
+    let (rooms, channel_width) = candidates.into_iter().flatten().find(|candidate| !tried.contains(candidate))?;
+    Some(GeneratorParams { rooms, channel_width, ..params.clone() })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams};
+
+    #[test]
+    fn levels_are_fitted_to_their_tile_budget() {
+        let params = GeneratorParams { seed: Some(3), mode: GenerationMode::Marble, ..Default::default() };
+        let free = generate(&params);
+        assert_eq!(free.stats.tile_budget_met, None);
+
+        let max = free.stats.tile_count as u32 * 2 / 3;
+        let capped = generate(&GeneratorParams { max_total_tiles: Some(max), ..params.clone() });
+        assert_eq!(capped.stats.tile_budget_met, Some(true));
+        assert!(capped.stats.tile_count <= max as usize);
+        assert!(capped.stats.rooms_requested < params.rooms);
+
+        let target = free.stats.floor_ratio + 0.1;
+        let fuller = generate(&GeneratorParams { target_floor_ratio: Some(target), floor_ratio_tolerance: 0.03, ..params.clone() });
+        assert_eq!(fuller.stats.tile_budget_met, Some(true));
+        assert!((fuller.stats.floor_ratio - target).abs() <= 0.03);
+
+        // An impossible budget is reported rather than met
+        let impossible = generate(&GeneratorParams { max_total_tiles: Some(1), ..params.clone() });
+        assert_eq!(impossible.stats.tile_budget_met, Some(false));
+        assert!(impossible.stats.tile_count < free.stats.tile_count);
+
+        // Goals past the map never ask for more rooms than it can hold
+        assert!(super::adjust(&free, &GeneratorParams { target_floor_ratio: Some(f32::INFINITY), ..params.clone() }, &[]).is_none());
+        let next = super::adjust(&free, &GeneratorParams { target_floor_ratio: Some(1.0), floor_ratio_tolerance: 0.0, ..params }, &[]).unwrap();
+        assert!(next.rooms <= 120);
+    }
+}
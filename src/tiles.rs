@@ -2,11 +2,51 @@
 //!
 //! This module defines the various tile types that can be placed in a marble
 //! level, including straight paths, curves, junctions, slopes, and obstacles.
+//! It also defines the character tiles of the ASCII layer. The conversion
+//! from them to marble tiles is re-exported here, as are the prefab anchors
+//! stored in tile metadata (see [`crate::anchors`]) and the one-way gate
+//! helpers (see [`crate::gates`]); those need the `std` feature.
 
-use serde::Serialize;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "std")]
+pub use crate::anchors::{add_anchors, tile_anchor, Anchor};
+#[cfg(feature = "std")]
+pub use crate::gates::{gate_direction, orient_gates};
+#[cfg(feature = "std")]
+pub use crate::marble::{grid_to_marble_tiles, open_bowl_rooms, place_advanced_tiles, place_launch_pads, AdvancedTileConfig, MarbleTileConfig, MIN_PAD_RUN, Y_JUNCTION_REACH};
+
+/// 2D tile grid stored row-major as characters.
+pub type Grid = Vec<Vec<char>>;
+
+/// Wall tile character.
+pub const TILE_WALL: char = '#';
+/// Floor tile character.
+pub const TILE_FLOOR: char = '.';
+/// Grass tile character (overworld mode).
+pub const TILE_GRASS: char = ',';
+/// Water tile character (overworld mode).
+pub const TILE_WATER: char = '~';
+/// Rock tile character (overworld mode).
+pub const TILE_ROCK: char = '^';
+/// Void tile character: nothing at all, as in the skirt around a level.
+pub const TILE_VOID: char = ' ';
+/// Secret door tile character: a hidden passage through a wall (classic
+/// mode, see `secrets`), walkable only once revealed.
+pub const TILE_SECRET_DOOR: char = 'S';
+
+/// Whether an ASCII tile can be walked on (floors, grass, and WFC pipes).
+/// Secret doors are not.
+pub fn is_walkable_char(ch: char) -> bool {
+    !matches!(ch, TILE_WALL | TILE_WATER | TILE_ROCK | TILE_VOID | TILE_SECRET_DOOR)
+}
 
 /// Core tile types for marble level generation
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum TileType {
     /// Empty space / wall / void
     Empty,
@@ -28,7 +68,8 @@ pub enum TileType {
     Obstacle,
     /// Merge tile (multiple inputs converge to one output)
     Merge,
-    /// One-way gate (directional flow)
+    /// One-way gate, passable only toward `Direction::North` rotated by the
+    /// tile's rotation (see [`crate::gates`])
     OneWayGate,
     /// Loop-de-loop section
     LoopDeLoop,
@@ -40,9 +81,41 @@ pub enum TileType {
     Bridge,
     /// Tunnel (path goes under another)
     Tunnel,
+    /// Helix stacked in one column, climbing one level per voxel
+    Spiral,
+    /// Vertical drop shaft stacked in one column
+    ElevatorDrop,
+    /// Member of a multi-tile spiral ramp (see `structures`)
+    SpiralRamp,
+    /// Member of a multi-tile funnel bowl (see `structures`)
+    Funnel,
 }
 
 impl TileType {
+    /// Every tile type, in declaration order
+    pub const ALL: [TileType; 20] = [
+        TileType::Empty,
+        TileType::Straight,
+        TileType::Curve90,
+        TileType::TJunction,
+        TileType::YJunction,
+        TileType::CrossJunction,
+        TileType::Slope,
+        TileType::OpenPlatform,
+        TileType::Obstacle,
+        TileType::Merge,
+        TileType::OneWayGate,
+        TileType::LoopDeLoop,
+        TileType::HalfPipe,
+        TileType::LaunchPad,
+        TileType::Bridge,
+        TileType::Tunnel,
+        TileType::Spiral,
+        TileType::ElevatorDrop,
+        TileType::SpiralRamp,
+        TileType::Funnel,
+    ];
+
     /// Returns true if this tile type is passable (not a wall)
     pub fn is_passable(&self) -> bool {
         !matches!(self, TileType::Empty | TileType::Obstacle)
@@ -60,9 +133,47 @@ impl TileType {
                 | TileType::Slope
                 | TileType::Merge
                 | TileType::LoopDeLoop
+                | TileType::Spiral
+                | TileType::ElevatorDrop
+                | TileType::SpiralRamp
         )
     }
 
+    /// Directions this tile type connects in at rotation 0; a tile with
+    /// rotation `r` connects in each of them rotated `r` steps clockwise
+    pub fn base_connections(&self) -> Vec<Direction> {
+        match self {
+            TileType::Empty | TileType::Obstacle => vec![],
+            TileType::Straight => vec![Direction::North, Direction::South],
+            TileType::Curve90 => vec![Direction::North, Direction::East],
+            TileType::TJunction => vec![Direction::North, Direction::East, Direction::South],
+            TileType::YJunction => vec![Direction::North, Direction::East, Direction::South],
+            TileType::CrossJunction => vec![
+                Direction::North,
+                Direction::East,
+                Direction::South,
+                Direction::West,
+            ],
+            TileType::Slope | TileType::SpiralRamp => vec![Direction::North, Direction::South],
+            TileType::OpenPlatform | TileType::Funnel => vec![
+                Direction::North,
+                Direction::East,
+                Direction::South,
+                Direction::West,
+            ],
+            TileType::Merge => vec![Direction::North, Direction::East, Direction::West],
+            TileType::OneWayGate => vec![Direction::North, Direction::South],
+            TileType::LoopDeLoop => vec![Direction::North, Direction::South],
+            TileType::HalfPipe => vec![Direction::North, Direction::South],
+            TileType::LaunchPad => vec![Direction::North],
+            TileType::Bridge => vec![Direction::North, Direction::South],
+            TileType::Tunnel => vec![Direction::North, Direction::South],
+            // Vertical connectors link to the voxels above and below; only the
+            // horizontal side they face is listed here
+            TileType::Spiral | TileType::ElevatorDrop => vec![Direction::North],
+        }
+    }
+
     /// Returns the ASCII character representation for this tile type
     pub fn to_ascii(&self, has_walls: bool) -> char {
         match (self, has_walls) {
@@ -75,7 +186,8 @@ impl TileType {
 }
 
 /// Connection directions for tile compatibility
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum Direction {
     North = 0,
     East = 1,
@@ -84,6 +196,24 @@ pub enum Direction {
 }
 
 impl Direction {
+    /// All four directions, clockwise from North
+    pub const ALL: [Direction; 4] = [Direction::North, Direction::East, Direction::South, Direction::West];
+
+    /// Grid offset `(dx, dy)` one step in this direction; y grows southward
+    pub fn to_delta(&self) -> (i32, i32) {
+        match self {
+            Direction::North => (0, -1),
+            Direction::East => (1, 0),
+            Direction::South => (0, 1),
+            Direction::West => (-1, 0),
+        }
+    }
+
+    /// Direction of a one-step orthogonal offset, or `None` for anything else
+    pub fn from_delta(dx: i32, dy: i32) -> Option<Direction> {
+        Self::ALL.into_iter().find(|d| d.to_delta() == (dx, dy))
+    }
+
     /// Returns the opposite direction
     pub fn opposite(&self) -> Direction {
         match self {
@@ -107,8 +237,100 @@ impl Direction {
     }
 }
 
+/// Diagonal directions, for 8-connected (octile) features. Together with
+/// [`Direction`] they cover all eight neighbours of a tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Diag {
+    NorthEast = 0,
+    SouthEast = 1,
+    SouthWest = 2,
+    NorthWest = 3,
+}
+
+impl Diag {
+    /// All four diagonals, clockwise from NorthEast
+    pub const ALL: [Diag; 4] = [Diag::NorthEast, Diag::SouthEast, Diag::SouthWest, Diag::NorthWest];
+
+    /// Grid offset `(dx, dy)` one step in this direction; y grows southward
+    pub fn to_delta(&self) -> (i32, i32) {
+        let (a, b) = self.sides();
+        let ((ax, ay), (bx, by)) = (a.to_delta(), b.to_delta());
+        (ax + bx, ay + by)
+    }
+
+    /// Direction of a one-step diagonal offset, or `None` for anything else
+    pub fn from_delta(dx: i32, dy: i32) -> Option<Diag> {
+        Self::ALL.into_iter().find(|d| d.to_delta() == (dx, dy))
+    }
+
+    /// Returns the opposite direction
+    pub fn opposite(&self) -> Diag {
+        self.rotate(2)
+    }
+
+    /// Rotate direction clockwise by given number of 90° steps
+    pub fn rotate(&self, steps: u8) -> Diag {
+        Self::ALL[(*self as usize + steps as usize) % 4]
+    }
+
+    /// The two orthogonal directions this diagonal lies between, clockwise
+    /// (a diagonal step can be taken as these two steps in either order)
+    pub fn sides(&self) -> (Direction, Direction) {
+        let first = Direction::ALL[*self as usize];
+        (first, first.rotate(1))
+    }
+}
+
+/// Connection directions on a hex grid (pointy-top, odd-r offset layout)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HexDirection {
+    East = 0,
+    SouthEast = 1,
+    SouthWest = 2,
+    West = 3,
+    NorthWest = 4,
+    NorthEast = 5,
+}
+
+impl HexDirection {
+    /// All six directions, clockwise from East
+    pub const ALL: [HexDirection; 6] = [
+        HexDirection::East,
+        HexDirection::SouthEast,
+        HexDirection::SouthWest,
+        HexDirection::West,
+        HexDirection::NorthWest,
+        HexDirection::NorthEast,
+    ];
+
+    /// Returns the opposite direction
+    pub fn opposite(&self) -> HexDirection {
+        self.rotate(3)
+    }
+
+    /// Rotate direction clockwise by given number of 60° steps
+    pub fn rotate(&self, steps: u8) -> HexDirection {
+        Self::ALL[(*self as usize + steps as usize) % 6]
+    }
+
+    /// Offset `(dx, dy)` to the neighbor in this direction from a tile in row `y`
+    pub fn offset(&self, y: i32) -> (i32, i32) {
+        // Odd rows are shifted right, so their diagonal neighbors lean right too
+        let shift = y & 1;
+        match self {
+            HexDirection::East => (1, 0),
+            HexDirection::West => (-1, 0),
+            HexDirection::SouthEast => (shift, 1),
+            HexDirection::SouthWest => (shift - 1, 1),
+            HexDirection::NorthEast => (shift, -1),
+            HexDirection::NorthWest => (shift - 1, -1),
+        }
+    }
+}
+
 /// A marble tile with type, elevation, rotation, and wall information
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct MarbleTile {
     /// The type of tile
     pub tile_type: TileType,
@@ -116,8 +338,8 @@ pub struct MarbleTile {
     pub elevation: i32,
     /// Rotation in 90° increments (0-3)
     pub rotation: u8,
-    /// Whether this tile has walls
-    pub has_walls: bool,
+    /// Walls on each edge, indexed by `Direction` (north, east, south, west)
+    pub walls: [bool; 4],
     /// Additional metadata for game engines (JSON string)
     pub metadata: String,
 }
@@ -129,7 +351,7 @@ impl MarbleTile {
             tile_type: TileType::Empty,
             elevation: 0,
             rotation: 0,
-            has_walls: false,
+            walls: [false; 4],
             metadata: String::new(),
         }
     }
@@ -140,7 +362,7 @@ impl MarbleTile {
             tile_type,
             elevation: 0,
             rotation: 0,
-            has_walls: tile_type.has_default_walls(),
+            walls: [tile_type.has_default_walls(); 4],
             metadata: String::new(),
         }
     }
@@ -156,11 +378,21 @@ impl MarbleTile {
             tile_type,
             elevation,
             rotation: rotation % 4,
-            has_walls,
+            walls: [has_walls; 4],
             metadata: String::new(),
         }
     }
 
+    /// Returns true if any edge of this tile is walled
+    pub fn has_walls(&self) -> bool {
+        self.walls.iter().any(|&w| w)
+    }
+
+    /// Returns true if the edge facing `direction` is walled
+    pub fn has_wall(&self, direction: Direction) -> bool {
+        self.walls[direction as usize]
+    }
+
     /// Set metadata for this tile
     pub fn with_metadata(mut self, metadata: String) -> Self {
         self.metadata = metadata;
@@ -169,36 +401,9 @@ impl MarbleTile {
 
     /// Get the connections this tile has (based on type and rotation)
     pub fn connections(&self) -> Vec<Direction> {
-        let base_connections = match self.tile_type {
-            TileType::Empty | TileType::Obstacle => vec![],
-            TileType::Straight => vec![Direction::North, Direction::South],
-            TileType::Curve90 => vec![Direction::North, Direction::East],
-            TileType::TJunction => vec![Direction::North, Direction::East, Direction::South],
-            TileType::YJunction => vec![Direction::North, Direction::East, Direction::South],
-            TileType::CrossJunction => vec![
-                Direction::North,
-                Direction::East,
-                Direction::South,
-                Direction::West,
-            ],
-            TileType::Slope => vec![Direction::North, Direction::South],
-            TileType::OpenPlatform => vec![
-                Direction::North,
-                Direction::East,
-                Direction::South,
-                Direction::West,
-            ],
-            TileType::Merge => vec![Direction::North, Direction::East, Direction::West],
-            TileType::OneWayGate => vec![Direction::North, Direction::South],
-            TileType::LoopDeLoop => vec![Direction::North, Direction::South],
-            TileType::HalfPipe => vec![Direction::North, Direction::South],
-            TileType::LaunchPad => vec![Direction::North],
-            TileType::Bridge => vec![Direction::North, Direction::South],
-            TileType::Tunnel => vec![Direction::North, Direction::South],
-        };
-
         // Rotate connections based on tile rotation
-        base_connections
+        self.tile_type
+            .base_connections()
             .into_iter()
             .map(|d| d.rotate(self.rotation))
             .collect()
@@ -221,7 +426,7 @@ impl MarbleTile {
         }
         // For slopes, check elevation compatibility (diff of ±1)
         match (&self.tile_type, &other.tile_type) {
-            (TileType::Slope, _) | (_, TileType::Slope) => {
+            (TileType::Slope | TileType::SpiralRamp, _) | (_, TileType::Slope | TileType::SpiralRamp) => {
                 (self.elevation - other.elevation).abs() <= 1
             }
             _ => self.elevation == other.elevation,
@@ -230,7 +435,7 @@ impl MarbleTile {
 
     /// Convert to ASCII character for legacy output
     pub fn to_ascii(&self) -> char {
-        self.tile_type.to_ascii(self.has_walls)
+        self.tile_type.to_ascii(self.has_walls())
     }
 }
 
@@ -240,17 +445,59 @@ impl Default for MarbleTile {
     }
 }
 
+/// Trim walls on walled tiles down to the edges that border void (an empty
+/// tile or the map edge), so adjacent channel tiles share an open edge.
+/// Tiles without walls (open platforms, obstacles) are left unchanged.
+pub fn compute_walls(tiles: &mut [Vec<MarbleTile>]) {
+    let height = tiles.len() as i32;
+    let width = if height > 0 { tiles[0].len() as i32 } else { 0 };
+    let void: Vec<Vec<bool>> = tiles
+        .iter()
+        .map(|row| row.iter().map(|t| t.tile_type == TileType::Empty).collect())
+        .collect();
+    let is_void = |x: i32, y: i32| x < 0 || y < 0 || x >= width || y >= height || void[y as usize][x as usize];
+
+    for (y, row) in tiles.iter_mut().enumerate() {
+        for (x, tile) in row.iter_mut().enumerate() {
+            if !tile.has_walls() {
+                continue;
+            }
+            let (x, y) = (x as i32, y as i32);
+            tile.walls = [is_void(x, y - 1), is_void(x + 1, y), is_void(x, y + 1), is_void(x - 1, y)];
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn directions_convert_to_and_from_deltas() {
+        for d in Direction::ALL {
+            let (dx, dy) = d.to_delta();
+            assert_eq!(Direction::from_delta(dx, dy), Some(d));
+            assert_eq!(d.opposite().to_delta(), (-dx, -dy));
+        }
+        for d in Diag::ALL {
+            let (dx, dy) = d.to_delta();
+            assert_eq!(Diag::from_delta(dx, dy), Some(d));
+            assert_eq!(d.opposite().to_delta(), (-dx, -dy));
+        }
+        assert_eq!(Diag::SouthWest.to_delta(), (-1, 1));
+        assert_eq!(Diag::NorthWest.sides(), (Direction::West, Direction::North));
+        assert_eq!(Direction::from_delta(1, 1), None);
+        assert_eq!(Diag::from_delta(0, 2), None);
+        assert_eq!(serde_json::to_string(&Diag::NorthEast).unwrap(), "\"NorthEast\"");
+    }
+
     #[test]
     fn test_tile_creation() {
         let tile = MarbleTile::new(TileType::Straight);
         assert_eq!(tile.tile_type, TileType::Straight);
         assert_eq!(tile.elevation, 0);
         assert_eq!(tile.rotation, 0);
-        assert!(tile.has_walls);
+        assert!(tile.has_walls());
     }
 
     #[test]
@@ -290,6 +537,21 @@ mod tests {
         assert!(slope.compatible_with(&ground, Direction::North));
         assert!(slope.compatible_with(&elevated, Direction::North));
     }
-}
 
+    #[test]
+    #[allow(clippy::needless_range_loop)]
+    fn test_walls_only_border_void() {
+        // A straight east-west channel three tiles long inside a void border
+        let mut tiles = vec![vec![MarbleTile::empty(); 5]; 3];
+        for x in 1..4 {
+            tiles[1][x] = MarbleTile::with_params(TileType::Straight, 0, 1, true);
+        }
+        compute_walls(&mut tiles);
 
+        let middle = &tiles[1][2];
+        assert!(middle.has_wall(Direction::North) && middle.has_wall(Direction::South));
+        assert!(!middle.has_wall(Direction::East) && !middle.has_wall(Direction::West));
+        assert!(tiles[1][1].has_wall(Direction::West));
+        assert!(!tiles[0][0].has_walls());
+    }
+}
@@ -0,0 +1,294 @@
+//! Multi-marble traffic simulation for finding congestion.
+//!
+//! [`Level::simulate_traffic`] releases marbles in batches, like the start
+//! of a race, onto the entrance room's tiles nearest the exit, and moves
+//! them in ticks toward the exit room's center. Each tick
+//! a marble steps to a neighboring tile one step closer to the exit, picking
+//! at random among free ones, so marbles spread over routes of equal length
+//! and meet again where they merge. A tile holds one marble at a time; a
+//! marble whose every next tile is taken waits, and the wait is charged to
+//! the tile it wanted. Marbles are moved front-runners first, and marbles
+//! of a batch that find no free start tile wait for the next tick.
+//!
+//! The [`TrafficReport`] lists the tiles with the most waiting (the
+//! hotspots, usually merges that jam), how many marbles passed each
+//! junction, and travel times against the free-flow time of a lone marble.
+//! Route choices come from the level seed's `traffic` stream, so the same
+//! level and config give the same report.
+//!
+//! ```
+//! use level_generator::traffic::TrafficConfig;
+//! use level_generator::{generate, GenerationMode, GeneratorParams};
+//!
+//! let level = generate(&GeneratorParams { seed: Some(2), mode: GenerationMode::Marble, ..Default::default() });
+//! let report = level.simulate_traffic(&TrafficConfig { marbles: 30, ..Default::default() }).unwrap();
+//! assert_eq!(report.finished, 30);
+//! assert!(report.max_travel >= report.free_flow);
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::acceptance::junction_tiles;
+use crate::dungeon::Level;
+use crate::roles::RoomRole;
+use crate::seeds::{stage_rng, SeedStage};
+use crate::visibility::blocks_movement;
+use crate::zones::start_tile;
+
+/// Hotspots listed in a [`TrafficReport`]
+pub const MAX_HOTSPOTS: usize = 5;
+
+/// Settings for [`Level::simulate_traffic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrafficConfig {
+    /// Marbles to release
+    pub marbles: u32,
+    /// Marbles released together
+    pub batch: u32,
+    /// Ticks between batches
+    pub release_interval: u32,
+    /// Ticks after which the simulation stops, finished or not
+    pub max_ticks: u32,
+}
+
+impl Default for TrafficConfig {
+    fn default() -> Self {
+        Self { marbles: 20, batch: 4, release_interval: 2, max_ticks: 10_000 }
+    }
+}
+
+/// A tile where marbles waited.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Hotspot {
+    pub x: i32,
+    pub y: i32,
+    /// Ticks marbles spent waiting to enter the tile
+    pub waits: u32,
+    /// Marbles that passed through the tile
+    pub visits: u32,
+    /// Whether the tile is a junction
+    pub junction: bool,
+}
+
+/// Marbles through one junction.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct JunctionLoad {
+    pub x: i32,
+    pub y: i32,
+    pub visits: u32,
+    /// Share of the released marbles that passed through, 0 to 1
+    pub utilization: f32,
+}
+
+/// Result of [`Level::simulate_traffic`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TrafficReport {
+    /// Marbles placed on the track
+    pub released: u32,
+    /// Marbles that reached the exit
+    pub finished: u32,
+    /// Ticks simulated
+    pub ticks: u32,
+    /// Ticks a lone marble needs from the entrance tile nearest the exit
+    pub free_flow: u32,
+    /// Mean ticks from release to exit over finished marbles
+    pub mean_travel: f32,
+    /// Longest ticks from release to exit
+    pub max_travel: u32,
+    /// Ticks spent waiting, summed over all marbles and releases
+    pub total_waits: u32,
+    /// Tiles with the most waiting, worst first (at most [`MAX_HOTSPOTS`])
+    pub hotspots: Vec<Hotspot>,
+    /// Every junction, in row order
+    pub junctions: Vec<JunctionLoad>,
+}
+
+impl Level {
+    /// Simulate marbles from the entrance to the exit; see the module docs.
+    /// `None` without an exit room or a path to it.
+    pub fn simulate_traffic(&self, config: &TrafficConfig) -> Option<TrafficReport> {
+        let (width, height) = (self.width as usize, self.height as usize);
+        let exit = self.rooms.iter().find(|r| r.role == RoomRole::Exit)?;
+        let (gx, gy) = exit.center();
+        let grid: Vec<Vec<char>> = self.ascii_tiles().iter().map(|row| row.chars().collect()).collect();
+        let start = start_tile(&grid, &self.rooms)?;
+        let open = |x: i32, y: i32| x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height && !blocks_movement(self, x, y);
+        if !open(gx, gy) {
+            return None;
+        }
+
+        // Steps to the exit from every tile
+        let mut distance: Vec<Vec<Option<u32>>> = vec![vec![None; width]; height];
+        distance[gy as usize][gx as usize] = Some(0);
+        let mut queue = VecDeque::from([(gx, gy)]);
+        while let Some((x, y)) = queue.pop_front() {
+            let d = distance[y as usize][x as usize].unwrap_or(0);
+            for (nx, ny) in self.topology.neighbors(x, y) {
+                if open(nx, ny) && distance[ny as usize][nx as usize].is_none() {
+                    distance[ny as usize][nx as usize] = Some(d + 1);
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+        // Start tiles: the entrance room's reachable tiles, nearest the exit first
+        let entrance = self.rooms.iter().find(|r| r.role == RoomRole::Entrance).filter(|r| r.contains(start.0 as i32, start.1 as i32));
+        let mut starts: Vec<(i32, i32)> = match entrance {
+            Some(room) => (room.y..room.y + room.h).flat_map(|y| (room.x..room.x + room.w).map(move |x| (x, y))).filter(|&(x, y)| open(x, y) && distance[y as usize][x as usize].is_some()).collect(),
+            None => vec![(start.0 as i32, start.1 as i32)],
+        };
+        starts.sort_by_key(|&(x, y)| distance[y as usize][x as usize]);
+        let free_flow = starts.first().and_then(|&(x, y)| distance[y as usize][x as usize])?;
+
+        let mut rng = stage_rng(self.seed, SeedStage::Traffic);
+        let mut occupied = vec![vec![false; width]; height];
+        let mut waits = vec![vec![0u32; width]; height];
+        let mut visits = vec![vec![0u32; width]; height];
+        // (position, release tick) of marbles on the track
+        let mut marbles: Vec<((i32, i32), u32)> = Vec::new();
+        let mut travel: Vec<u32> = Vec::new();
+        let (mut released, mut tick, mut batch_left) = (0, 0, 0);
+        while tick < config.max_ticks && (released < config.marbles || !marbles.is_empty()) {
+            tick += 1;
+            marbles.sort_by_key(|&((x, y), _)| distance[y as usize][x as usize]);
+            let mut still = Vec::with_capacity(marbles.len());
+            for ((x, y), since) in std::mem::take(&mut marbles) {
+                let d = distance[y as usize][x as usize].unwrap_or(0);
+                let closer: Vec<(i32, i32)> = self.topology.neighbors(x, y).into_iter().filter(|&(nx, ny)| open(nx, ny) && distance[ny as usize][nx as usize] == Some(d.saturating_sub(1))).collect();
+                let free: Vec<(i32, i32)> = closer.iter().copied().filter(|&(nx, ny)| !occupied[ny as usize][nx as usize]).collect();
+                if free.is_empty() {
+                    if let Some(&(nx, ny)) = closer.get(rng.random_range(0..closer.len().max(1))) {
+                        waits[ny as usize][nx as usize] += 1;
+                    }
+                    still.push(((x, y), since));
+                    continue;
+                }
+                let (nx, ny) = free[rng.random_range(0..free.len())];
+                occupied[y as usize][x as usize] = false;
+                visits[ny as usize][nx as usize] += 1;
+                if (nx, ny) == (gx, gy) {
+                    travel.push(tick - since);
+                } else {
+                    occupied[ny as usize][nx as usize] = true;
+                    still.push(((nx, ny), since));
+                }
+            }
+            marbles = still;
+
+            if (tick - 1) % config.release_interval.max(1) == 0 {
+                batch_left += config.batch.max(1);
+            }
+            batch_left = batch_left.min(config.marbles - released);
+            for &(sx, sy) in &starts {
+                if batch_left == 0 {
+                    break;
+                }
+                if occupied[sy as usize][sx as usize] {
+                    continue;
+                }
+                released += 1;
+                batch_left -= 1;
+                visits[sy as usize][sx as usize] += 1;
+                if (sx, sy) == (gx, gy) {
+                    travel.push(0);
+                } else {
+                    occupied[sy as usize][sx as usize] = true;
+                    marbles.push(((sx, sy), tick));
+                }
+            }
+            if batch_left > 0 {
+                let (sx, sy) = starts[0];
+                waits[sy as usize][sx as usize] += batch_left;
+            }
+        }
+
+        let junctions = junction_tiles(self);
+        let mut hotspots: Vec<Hotspot> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|&(x, y)| waits[y][x] > 0)
+            .map(|(x, y)| Hotspot {
+                x: x as i32,
+                y: y as i32,
+                waits: waits[y][x],
+                visits: visits[y][x],
+                junction: junctions.contains(&(x as i32, y as i32)),
+            })
+            .collect();
+        hotspots.sort_by_key(|h| std::cmp::Reverse(h.waits));
+        hotspots.truncate(MAX_HOTSPOTS);
+
+        Some(TrafficReport {
+            released,
+            finished: travel.len() as u32,
+            ticks: tick,
+            free_flow,
+            mean_travel: if travel.is_empty() { 0.0 } else { travel.iter().sum::<u32>() as f32 / travel.len() as f32 },
+            max_travel: travel.iter().copied().max().unwrap_or(0),
+            total_waits: waits.iter().flatten().sum(),
+            hotspots,
+            junctions: junctions
+                .into_iter()
+                .map(|(x, y)| {
+                    let visits = visits[y as usize][x as usize];
+                    JunctionLoad { x, y, visits, utilization: visits as f32 / released.max(1) as f32 }
+                })
+                .collect(),
+        })
+    }
+}
+
+impl fmt::Display for TrafficReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Traffic:")?;
+        writeln!(f, "  Marbles:        {} finished / {} released in {} ticks", self.finished, self.released, self.ticks)?;
+        writeln!(f, "  Travel:         {:.1} mean, {} max, {} free flow", self.mean_travel, self.max_travel, self.free_flow)?;
+        writeln!(f, "  Waiting:        {} ticks", self.total_waits)?;
+        for spot in &self.hotspots {
+            let kind = if spot.junction { " (junction)" } else { "" };
+            writeln!(f, "  Hotspot:        ({}, {}){}: {} waits, {} marbles", spot.x, spot.y, kind, spot.waits, spot.visits)?;
+        }
+        let busiest = self.junctions.iter().max_by(|a, b| a.utilization.total_cmp(&b.utilization));
+        if let Some(junction) = busiest {
+            writeln!(f, "  Busiest junction: ({}, {}) used by {:.0}% of marbles", junction.x, junction.y, junction.utilization * 100.0)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::Room;
+
+    #[test]
+    fn merges_where_lanes_narrow_become_hotspots() {
+        // Marbles leave the entrance in two lanes that narrow to one before the exit
+        let mut level = Level::from_ascii("##########\n#........#\n#.....####\n##########").unwrap();
+        level.rooms = vec![
+            Room { x: 1, y: 1, w: 3, h: 2, role: RoomRole::Entrance, ..Default::default() },
+            Room { x: 7, y: 1, w: 2, h: 1, role: RoomRole::Exit, ..Default::default() },
+        ];
+
+        let report = level.simulate_traffic(&TrafficConfig { marbles: 12, ..Default::default() }).unwrap();
+        assert_eq!((report.released, report.finished), (12, 12));
+        assert_eq!(report.free_flow, 5);
+        assert!(report.mean_travel >= 5.0 && report.max_travel > 5);
+        let worst = &report.hotspots[0];
+        assert!(worst.y == 1 && (5..=6).contains(&worst.x), "{:?}", report.hotspots);
+
+        // Spaced out, the marbles never meet
+        let calm = level.simulate_traffic(&TrafficConfig { marbles: 12, batch: 1, release_interval: 8, ..Default::default() }).unwrap();
+        assert_eq!((calm.total_waits, calm.max_travel), (0, 5));
+        assert!(calm.hotspots.is_empty());
+        assert_eq!(level.simulate_traffic(&TrafficConfig { marbles: 12, ..Default::default() }), Some(report));
+
+        level.rooms[1].role = RoomRole::Normal;
+        assert!(level.simulate_traffic(&TrafficConfig::default()).is_none());
+    }
+}
@@ -0,0 +1,348 @@
+//! Rotating, mirroring, and scaling finished levels.
+//!
+//! [`Level::rotate90`], [`Level::mirror_x`], and [`Level::mirror_y`] return a
+//! copy of the level turned a quarter clockwise or flipped east-west or
+//! north-south, for duplicating multiplayer maps or augmenting training
+//! data. Every layer moves with the grid: the ASCII tiles, marble tiles,
+//! terrain, rivers, zones, rooms and plazas, obstacles, structures and
+//! their ports, side routes, moving hazards, and voxels.
+//!
+//! Marble tiles get walls and rotations that connect the transformed
+//! neighbors. A mirror cannot be matched by turning a tile, so its rotation
+//! is picked to give the mirrored connections, keeping the tile's facing
+//! (a gate's open side, a pad's launch direction, a slope's climb) wherever
+//! the tile type allows. Anchors are worked out again on the new grid (see
+//! [`add_anchors`]).
+//!
+//! [`Level::upscale`] blows a level up by a whole factor, so a small layout
+//! that is quick to iterate on can be brought to final game scale. Every
+//! tile becomes a `factor` x `factor` block, which widens channels in
+//! proportion, and the marble tiles are then detected again over the whole
+//! map (see [`Level::update_marble_tiles`]) with the heights carried over.
+//! Rooms, plazas, terrain, rivers, zones, and moving hazards scale along;
+//! voxels are built again from the new tiles. Obstacles, structures, and
+//! side routes are dropped, since their shapes do not scale; re-place obstacles with
+//! [`Level::regenerate_obstacles`].
+//!
+//! Hex levels come back unchanged: odd-r offset rows do not survive a
+//! quarter turn, an east-west flip, or a blow-up.
+//!
+//! ```
+//! use level_generator::{generate, GenerationMode, GeneratorParams};
+//!
+//! let level = generate(&GeneratorParams { seed: Some(3), width: 50, height: 20, mode: GenerationMode::Marble, ..Default::default() });
+//! let turned = level.rotate90();
+//! assert_eq!((turned.width, turned.height), (20, 50));
+//! assert_eq!(turned.rotate90().rotate90().rotate90().tiles, level.tiles);
+//! assert_eq!(level.upscale(2).width, 100);
+//! ```
+
+use crate::anchors::add_anchors;
+use crate::dungeon::{Level, Room};
+use crate::editing::Rect;
+use crate::hex::GridTopology;
+use crate::dynamics::DynamicElement;
+use crate::structures::Port;
+use crate::tiles::{Direction, MarbleTile};
+use crate::voxel::VoxelMap;
+
+/// A transform of a `width` x `height` grid.
+#[derive(Clone, Copy)]
+enum Transform {
+    /// Quarter turn clockwise
+    Rotate,
+    /// East-west flip
+    MirrorX,
+    /// North-south flip
+    MirrorY,
+}
+
+impl Transform {
+    fn size(self, (width, height): (usize, usize)) -> (usize, usize) {
+        match self {
+            Transform::Rotate => (height, width),
+            Transform::MirrorX | Transform::MirrorY => (width, height),
+        }
+    }
+
+    /// Where tile `(x, y)` of a `width` x `height` grid ends up.
+    fn point(self, (x, y): (i32, i32), (width, height): (usize, usize)) -> (i32, i32) {
+        let (w, h) = (width as i32, height as i32);
+        match self {
+            Transform::Rotate => (h - 1 - y, x),
+            Transform::MirrorX => (w - 1 - x, y),
+            Transform::MirrorY => (x, h - 1 - y),
+        }
+    }
+
+    fn direction(self, d: Direction) -> Direction {
+        match (self, d) {
+            (Transform::Rotate, d) => d.rotate(1),
+            (Transform::MirrorX, Direction::East | Direction::West) | (Transform::MirrorY, Direction::North | Direction::South) => d.opposite(),
+            (_, d) => d,
+        }
+    }
+
+    fn grid<T: Clone>(self, grid: &[Vec<T>]) -> Vec<Vec<T>> {
+        let size = (grid.first().map_or(0, Vec::len), grid.len());
+        let (width, height) = self.size(size);
+        (0..height as i32)
+            .map(|y| {
+                (0..width as i32)
+                    .map(|x| {
+                        // The inverse of a mirror is itself; of a clockwise turn, three more
+                        let (sx, sy) = match self {
+                            Transform::Rotate => (y, size.1 as i32 - 1 - x),
+                            _ => self.point((x, y), size),
+                        };
+                        grid[sy as usize][sx as usize].clone()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn room(self, room: &Room, size: (usize, usize)) -> Room {
+        let (ax, ay) = self.point((room.x, room.y), size);
+        let (bx, by) = self.point((room.x + room.w - 1, room.y + room.h - 1), size);
+        Room { x: ax.min(bx), y: ay.min(by), w: (ax - bx).abs() + 1, h: (ay - by).abs() + 1, ..room.clone() }
+    }
+
+    fn tile(self, tile: &MarbleTile) -> MarbleTile {
+        let mut walls = [false; 4];
+        for d in Direction::ALL {
+            walls[self.direction(d) as usize] = tile.walls[d as usize];
+        }
+        let base = tile.tile_type.base_connections();
+        let mut wanted: Vec<Direction> = tile.connections().into_iter().map(|d| self.direction(d)).collect();
+        wanted.sort_by_key(|&d| d as u8);
+        let facing = base.first().map(|d| self.direction(d.rotate(tile.rotation)));
+        let connects = |r: u8| {
+            let mut got: Vec<Direction> = base.iter().map(|d| d.rotate(r)).collect();
+            got.sort_by_key(|&d| d as u8);
+            got == wanted
+        };
+        let rotation = (0..4)
+            .filter(|&r| connects(r))
+            .min_by_key(|&r| (base.first().map(|d| d.rotate(r)) != facing, r))
+            .unwrap_or(tile.rotation);
+        MarbleTile { rotation, walls, ..tile.clone() }
+    }
+
+    fn port(self, port: &Port, size: (usize, usize)) -> Port {
+        let (x, y) = self.point((port.x, port.y), size);
+        Port { x, y, side: port.side.map(|d| self.direction(d)), ..*port }
+    }
+
+    fn level(self, level: &Level) -> Level {
+        if level.topology == GridTopology::Hex {
+            return level.clone();
+        }
+        let size = (level.width as usize, level.height as usize);
+        let (width, height) = self.size(size);
+        let point = |at: (i32, i32)| self.point(at, size);
+        let chars: Vec<Vec<char>> = level.tiles.iter().map(|row| row.chars().collect()).collect();
+
+        let mut out = level.clone();
+        out.width = width as u32;
+        out.height = height as u32;
+        out.tiles = self.grid(&chars).into_iter().map(|row| row.into_iter().collect()).collect();
+        out.marble_tiles = level.marble_tiles.as_ref().map(|tiles| self.grid(tiles).iter().map(|row| row.iter().map(|t| self.tile(t)).collect()).collect());
+        out.terrain = level.terrain.as_ref().map(|t| self.grid(t));
+        out.river_map = level.river_map.as_ref().map(|m| self.grid(m));
+        out.zone_map = level.zone_map.as_ref().map(|m| self.grid(m));
+        out.rooms = level.rooms.iter().map(|r| self.room(r, size)).collect();
+        out.plazas = level.plazas.iter().map(|r| self.room(r, size)).collect();
+        for obstacle in &mut out.obstacles {
+            obstacle.tiles = obstacle.tiles.iter().map(|&at| point(at)).collect();
+            obstacle.covered = obstacle.covered.iter().map(|t| self.tile(t)).collect();
+        }
+        for structure in &mut out.structures {
+            structure.tiles = structure.tiles.iter().map(|&at| point(at)).collect();
+            structure.entry = self.port(&structure.entry, size);
+            structure.exit = self.port(&structure.exit, size);
+        }
+        for route in &mut out.side_routes {
+            route.tiles = route.tiles.iter().map(|&at| point(at)).collect();
+        }
+        for element in &mut out.dynamics {
+            (element.x, element.y) = point((element.x, element.y));
+            // Hazards run east or south, whichever way the channel turned
+            element.axis = match self.direction(element.axis) {
+                Direction::West => Direction::East,
+                Direction::North => Direction::South,
+                d => d,
+            };
+        }
+        out.voxels = level.voxels.as_ref().map(|voxels| {
+            let mut map = VoxelMap::new(width as u32, height as u32);
+            for (x, y, z, tile) in voxels.iter() {
+                let (x, y) = point((x, y));
+                map.insert(x, y, z, self.tile(tile));
+            }
+            map
+        });
+        if let Some(tiles) = &mut out.marble_tiles {
+            add_anchors(tiles, &out.obstacles);
+        }
+        out
+    }
+}
+
+impl Level {
+    /// This level turned a quarter clockwise; see the module docs.
+    pub fn rotate90(&self) -> Level {
+        Transform::Rotate.level(self)
+    }
+
+    /// This level flipped east-west; see the module docs.
+    pub fn mirror_x(&self) -> Level {
+        Transform::MirrorX.level(self)
+    }
+
+    /// This level flipped north-south; see the module docs.
+    pub fn mirror_y(&self) -> Level {
+        Transform::MirrorY.level(self)
+    }
+
+    /// This level scaled up `factor` times in both directions; see the
+    /// module docs. A factor of 0 or 1 returns a copy.
+    pub fn upscale(&self, factor: u32) -> Level {
+        if factor <= 1 || self.topology == GridTopology::Hex {
+            return self.clone();
+        }
+        let f = factor as i32;
+        let mut out = self.clone();
+        out.width = self.width * factor;
+        out.height = self.height * factor;
+        let chars: Vec<Vec<char>> = self.tiles.iter().map(|row| row.chars().collect()).collect();
+        out.tiles = scale(&chars, factor).into_iter().map(|row| row.into_iter().collect()).collect();
+        out.terrain = self.terrain.as_deref().map(|t| scale(t, factor));
+        out.river_map = self.river_map.as_deref().map(|m| scale(m, factor));
+        out.zone_map = self.zone_map.as_deref().map(|m| scale(m, factor));
+        for zone in &mut out.zones {
+            zone.tile_count *= (factor * factor) as usize;
+        }
+        let room = |r: &Room| Room { x: r.x * f, y: r.y * f, w: r.w * f, h: r.h * f, ..r.clone() };
+        out.rooms = self.rooms.iter().map(room).collect();
+        out.plazas = self.plazas.iter().map(room).collect();
+        out.obstacles.clear();
+        out.structures.clear();
+        out.side_routes.clear();
+
+        // Blocks keep their tile's height; detection sorts out types and walls
+        out.marble_tiles = self.marble_tiles.as_deref().map(|tiles| scale(&tiles.iter().map(|row| row.iter().map(|t| MarbleTile { elevation: t.elevation, ..MarbleTile::new(t.tile_type) }).collect()).collect::<Vec<Vec<_>>>(), factor));
+        out.update_marble_tiles(Rect::new(0, 0, out.width as i32, out.height as i32));
+        out.dynamics = self.dynamics.iter().map(|d| DynamicElement { x: d.x * f + f / 2, y: d.y * f + f / 2, span: d.span * factor, ..d.clone() }).collect();
+        if let Some(tiles) = &mut out.marble_tiles {
+            add_anchors(tiles, &out.obstacles);
+            if out.voxels.is_some() {
+                out.voxels = Some(VoxelMap::from_tiles(tiles));
+            }
+        }
+        out.refresh_stats();
+        out
+    }
+}
+
+/// `grid` with every cell repeated `factor` times across and down.
+fn scale<T: Clone>(grid: &[Vec<T>], factor: u32) -> Vec<Vec<T>> {
+    let f = factor as usize;
+    grid.iter().flat_map(|row| std::iter::repeat_n(row.iter().flat_map(|cell| std::iter::repeat_n(cell.clone(), f)).collect::<Vec<T>>(), f)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams};
+    use crate::export::to_json;
+    use crate::gates::gate_direction;
+    use crate::tiles::TileType;
+
+    #[test]
+    fn transforms_move_every_layer_and_undo_themselves() {
+        let level = generate(&GeneratorParams {
+            seed: Some(7),
+            width: 50,
+            height: 30,
+            rooms: 8,
+            mode: GenerationMode::Marble,
+            channel_width: 3,
+            enable_elevation: true,
+            enable_obstacles: true,
+            enable_dynamics: true,
+            enable_funnels: true,
+            enable_voxels: true,
+            ..Default::default()
+        });
+        assert!(!level.obstacles.is_empty() && !level.dynamics.is_empty() && level.voxels.is_some());
+        let original = to_json(&level);
+        assert_eq!(to_json(&level.rotate90().rotate90().rotate90().rotate90()), original);
+        assert_eq!(to_json(&level.mirror_x().mirror_x()), original);
+        assert_eq!(to_json(&level.mirror_y().mirror_y()), original);
+        assert_eq!(to_json(&level.rotate90().rotate90()), to_json(&level.mirror_x().mirror_y()));
+
+        // Tiles connect and wall off the turned and flipped directions
+        let tiles = level.marble_tiles.as_ref().unwrap();
+        for (transform, moved) in [(Transform::Rotate, level.rotate90()), (Transform::MirrorX, level.mirror_x()), (Transform::MirrorY, level.mirror_y())] {
+            assert!(moved.check_invariants().is_empty());
+            let moved_tiles = moved.marble_tiles.as_ref().unwrap();
+            for (y, row) in tiles.iter().enumerate() {
+                for (x, tile) in row.iter().enumerate() {
+                    let (mx, my) = transform.point((x as i32, y as i32), (50, 30));
+                    let after = &moved_tiles[my as usize][mx as usize];
+                    let mut want: Vec<Direction> = tile.connections().into_iter().map(|d| transform.direction(d)).collect();
+                    let mut got = after.connections();
+                    want.sort_by_key(|&d| d as u8);
+                    got.sort_by_key(|&d| d as u8);
+                    assert_eq!(got, want, "{:?} at ({x}, {y})", tile.tile_type);
+                    assert!(Direction::ALL.iter().all(|&d| after.has_wall(transform.direction(d)) == tile.has_wall(d)));
+                    assert_eq!(gate_direction(after), gate_direction(tile).map(|d| transform.direction(d)));
+                }
+            }
+            let (room, after) = (&level.rooms[0], &moved.rooms[0]);
+            let (cx, cy) = transform.point((room.x, room.y), (50, 30));
+            assert!(cx >= after.x && cx < after.x + after.w && cy >= after.y && cy < after.y + after.h);
+            assert_eq!(room.w * room.h, after.w * after.h);
+        }
+
+        // A curve bending north-east bends north-west in the mirror
+        let curve = MarbleTile::new(TileType::Curve90);
+        let mirrored = Transform::MirrorX.tile(&curve);
+        assert_eq!(mirrored.rotation, 3);
+        assert_eq!(Transform::MirrorY.tile(&MarbleTile::new(TileType::Slope)).rotation, 2);
+    }
+
+    #[test]
+    fn upscaled_levels_widen_channels_and_keep_heights() {
+        let level = generate(&GeneratorParams {
+            seed: Some(4),
+            width: 30,
+            height: 20,
+            rooms: 4,
+            mode: GenerationMode::Marble,
+            enable_elevation: true,
+            enable_dynamics: true,
+            enable_voxels: true,
+            ..Default::default()
+        });
+        let big = level.upscale(3);
+        assert_eq!((big.width, big.height), (90, 60));
+        assert!(big.check_invariants().is_empty(), "{:?}", big.check_invariants());
+        assert_eq!(to_json(&level.upscale(1)), to_json(&level));
+
+        let (room, scaled) = (&level.rooms[0], &big.rooms[0]);
+        assert_eq!((scaled.x, scaled.y, scaled.w, scaled.h), (room.x * 3, room.y * 3, room.w * 3, room.h * 3));
+        let (tiles, big_tiles) = (level.marble_tiles.as_ref().unwrap(), big.marble_tiles.as_ref().unwrap());
+        for (y, row) in tiles.iter().enumerate() {
+            for (x, tile) in row.iter().enumerate() {
+                let after = &big_tiles[y * 3 + 1][x * 3 + 1];
+                assert_eq!(after.tile_type == TileType::Empty, tile.tile_type == TileType::Empty);
+                assert_eq!(after.elevation, tile.elevation);
+            }
+        }
+        assert!(big.dynamics.iter().all(|d| big_tiles[d.y as usize][d.x as usize].tile_type.is_passable()));
+        let width = |l: &Level| l.channel_splines().iter().map(|s| s.width).max().unwrap_or(0);
+        assert!(width(&big) >= width(&level) * 3);
+    }
+}
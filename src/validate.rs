@@ -0,0 +1,201 @@
+//! Param validation: the values [`generate`] cannot use as given.
+//!
+//! `generate` never panics on params. A value outside its supported range is
+//! clamped to the nearest one it supports before generation starts, and
+//! [`GeneratorParams::validate`] lists every such value so callers can
+//! reject or report them instead:
+//!
+//! | Field | Used as |
+//! |-------|---------|
+//! | `width`, `height` | at least [`MIN_MAP_DIM`] |
+//! | `rooms` | at most one per `MIN_ROOM_DIM + 1` square of the map |
+//! | `min_room` | at least [`MIN_ROOM_DIM`], at most the longer map side |
+//! | `max_room` | at least `min_room + 1`, at most one more than the longer map side |
+//! | `border`, `skirt` | together at most half the shorter map side, the skirt first |
+//! | `channel_curvature`, `dead_end_removal`, `difficulty_ramp`, `loop_fraction`, `target_floor_ratio` | within 0.0 to 1.0, NaN as 0.0 |
+//! | `floor_ratio_tolerance` | at least 0.0, the default (0.05) when NaN or infinite |
+//! | `trend_strength` | the default (0.5) when NaN or infinite |
+//! | `trend_vector` | none when a component is NaN or infinite |
+//!
+//! Other values are used as given, even far out of their usual range:
+//! zero rooms, an `obstacle_density` above 1.0, or elevations in the
+//! thousands generate a level like any other.
+//!
+//! ```
+//! use level_generator::gen::GeneratorParams;
+//!
+//! let params = GeneratorParams { width: 3, channel_curvature: 2.0, ..Default::default() };
+//! let err = params.validate().unwrap_err();
+//! assert_eq!(err.clamps.len(), 2);
+//! assert_eq!(params.clamped().channel_curvature, 1.0);
+//! ```
+//!
+//! [`generate`]: crate::dungeon::generate
+
+use std::fmt;
+
+use crate::dungeon::{GeneratorParams, MIN_MAP_DIM, MIN_ROOM_DIM};
+use crate::graph::ConnectionStrategy;
+
+/// One param value `generate` clamps, and the value it uses instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clamp {
+    /// Field name as in [`GeneratorParams`]
+    pub field: &'static str,
+    /// The value given, formatted with `Debug`
+    pub given: String,
+    /// The value used, formatted with `Debug`
+    pub used: String,
+}
+
+/// Error from [`GeneratorParams::validate`]: values outside their supported
+/// ranges.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidParams {
+    /// The clamped values, in field order
+    pub clamps: Vec<Clamp>,
+}
+
+impl fmt::Display for InvalidParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "generator params out of range:")?;
+        for (i, clamp) in self.clamps.iter().enumerate() {
+            let separator = if i == 0 { " " } else { ", " };
+            write!(f, "{}{} {} (uses {})", separator, clamp.field, clamp.given, clamp.used)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for InvalidParams {}
+
+/// `value` within 0.0 to 1.0, NaN as 0.0.
+fn fraction(value: f32) -> f32 {
+    if value.is_nan() {
+        0.0
+    } else {
+        value.clamp(0.0, 1.0)
+    }
+}
+
+impl GeneratorParams {
+    /// These params with every value `generate` cannot use as given clamped
+    /// to the nearest supported one; see the module docs.
+    pub fn clamped(&self) -> GeneratorParams {
+        let (width, height) = (self.width.max(MIN_MAP_DIM), self.height.max(MIN_MAP_DIM));
+        let (longer, half) = (width.max(height), width.min(height) / 2);
+        let min_room = self.min_room.clamp(MIN_ROOM_DIM, longer);
+        let skirt = self.skirt.min(half);
+        // One room per smallest room and its margin
+        let cells = |side: u32| side / (MIN_ROOM_DIM + 1);
+        GeneratorParams {
+            width,
+            height,
+            rooms: self.rooms.min(cells(width).saturating_mul(cells(height))),
+            min_room,
+            max_room: self.max_room.clamp(min_room.saturating_add(1), longer.saturating_add(1)),
+            border: self.border.min(half - skirt),
+            skirt,
+            channel_curvature: fraction(self.channel_curvature),
+            trend_vector: self.trend_vector.filter(|&(x, y, z)| x.is_finite() && y.is_finite() && z.is_finite()),
+            trend_strength: if self.trend_strength.is_finite() { self.trend_strength } else { GeneratorParams::default().trend_strength },
+            connection_strategy: match self.connection_strategy {
+                ConnectionStrategy::MinimumSpanningTree { loop_fraction } => ConnectionStrategy::MinimumSpanningTree { loop_fraction: fraction(loop_fraction) },
+                strategy => strategy,
+            },
+            dead_end_removal: fraction(self.dead_end_removal),
+            target_floor_ratio: self.target_floor_ratio.map(fraction),
+            floor_ratio_tolerance: if self.floor_ratio_tolerance.is_finite() { self.floor_ratio_tolerance.max(0.0) } else { GeneratorParams::default().floor_ratio_tolerance },
+            difficulty_ramp: fraction(self.difficulty_ramp),
+            ..self.clone()
+        }
+    }
+
+    /// `Ok` when `generate` uses every value as given, otherwise the values
+    /// it clamps (see [`clamped`](Self::clamped)).
+    pub fn validate(&self) -> Result<(), InvalidParams> {
+        let used = self.clamped();
+        let mut clamps = Vec::new();
+        let mut check = |field: &'static str, given: String, used: String| {
+            if given != used {
+                clamps.push(Clamp { field, given, used });
+            }
+        };
+        check("width", format!("{:?}", self.width), format!("{:?}", used.width));
+        check("height", format!("{:?}", self.height), format!("{:?}", used.height));
+        check("rooms", format!("{:?}", self.rooms), format!("{:?}", used.rooms));
+        check("min_room", format!("{:?}", self.min_room), format!("{:?}", used.min_room));
+        check("max_room", format!("{:?}", self.max_room), format!("{:?}", used.max_room));
+        check("border", format!("{:?}", self.border), format!("{:?}", used.border));
+        check("skirt", format!("{:?}", self.skirt), format!("{:?}", used.skirt));
+        check("channel_curvature", format!("{:?}", self.channel_curvature), format!("{:?}", used.channel_curvature));
+        check("trend_vector", format!("{:?}", self.trend_vector), format!("{:?}", used.trend_vector));
+        check("trend_strength", format!("{:?}", self.trend_strength), format!("{:?}", used.trend_strength));
+        check("connection_strategy", format!("{:?}", self.connection_strategy), format!("{:?}", used.connection_strategy));
+        check("dead_end_removal", format!("{:?}", self.dead_end_removal), format!("{:?}", used.dead_end_removal));
+        check("difficulty_ramp", format!("{:?}", self.difficulty_ramp), format!("{:?}", used.difficulty_ramp));
+        check("target_floor_ratio", format!("{:?}", self.target_floor_ratio), format!("{:?}", used.target_floor_ratio));
+        check("floor_ratio_tolerance", format!("{:?}", self.floor_ratio_tolerance), format!("{:?}", used.floor_ratio_tolerance));
+        if clamps.is_empty() {
+            Ok(())
+        } else {
+            Err(InvalidParams { clamps })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode};
+
+    #[test]
+    fn out_of_range_values_are_reported_and_clamped() {
+        assert_eq!(GeneratorParams::default().validate(), Ok(()));
+
+        let params = GeneratorParams {
+            rooms: 0,
+            min_room: 50,
+            max_room: 2,
+            max_elevation: -4,
+            obstacle_density: 9.0,
+            trend_strength: f32::NAN,
+            trend_vector: Some((1.0, f32::INFINITY, 0.0)),
+            connection_strategy: ConnectionStrategy::MinimumSpanningTree { loop_fraction: -1.0 },
+            ..Default::default()
+        };
+        let used = params.clamped();
+        assert_eq!((used.rooms, used.min_room, used.max_room, used.max_elevation, used.obstacle_density), (0, 50, 51, -4, 9.0));
+        assert_eq!(used.trend_strength, 0.5);
+        assert!(used.trend_vector.is_none());
+
+        let err = params.validate().unwrap_err();
+        let fields: Vec<&str> = err.clamps.iter().map(|c| c.field).collect();
+        assert_eq!(fields, ["max_room", "trend_vector", "trend_strength", "connection_strategy"]);
+        assert!(err.to_string().starts_with("generator params out of range: max_room 2 (uses 51), trend_vector"));
+        assert_eq!(used.validate(), Ok(()));
+
+        // Sizes past the map are cut down to it before any arithmetic on them
+        let huge = GeneratorParams { min_room: u32::MAX, max_room: 3_000_000_000, border: u32::MAX, skirt: 30, guaranteed_large_rooms: 1, ..Default::default() };
+        let used = huge.clamped();
+        assert_eq!((used.min_room, used.max_room, used.skirt, used.border), (80, 81, 12, 0));
+        let fields: Vec<&str> = huge.validate().unwrap_err().clamps.iter().map(|c| c.field).collect();
+        assert_eq!(fields, ["min_room", "max_room", "border", "skirt"]);
+        let crowded = GeneratorParams { rooms: u32::MAX, target_floor_ratio: Some(f32::INFINITY), floor_ratio_tolerance: -1.0, ..Default::default() };
+        let used = crowded.clamped();
+        assert_eq!((used.rooms, used.target_floor_ratio, used.floor_ratio_tolerance), (120, Some(1.0), 0.0));
+        assert_eq!(GeneratorParams { floor_ratio_tolerance: f32::NAN, ..Default::default() }.clamped().floor_ratio_tolerance, 0.05);
+        let regressions = [
+            GeneratorParams { min_room: u32::MAX, ..Default::default() },
+            GeneratorParams { border: u32::MAX, ..Default::default() },
+            GeneratorParams { skirt: u32::MAX, ..Default::default() },
+            GeneratorParams { max_room: 3_000_000_000, guaranteed_large_rooms: 1, ..Default::default() },
+            GeneratorParams { rooms: u32::MAX, mode: GenerationMode::Marble, ..Default::default() },
+            GeneratorParams { target_floor_ratio: Some(f32::INFINITY), mode: GenerationMode::Marble, ..Default::default() },
+            GeneratorParams { target_floor_ratio: Some(0.9), floor_ratio_tolerance: -1.0, ..Default::default() },
+        ];
+        for params in regressions {
+            generate(&params);
+        }
+    }
+}
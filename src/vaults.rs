@@ -0,0 +1,248 @@
+//! Vaults: rooms with exactly one way in.
+//!
+//! With [`GeneratorParams::vaults`] set, up to that many rooms are sealed
+//! down to a single entrance and get [`RoomRole::Vault`]. An entrance is a
+//! run of corridor tiles right outside the room, as listed by
+//! [`Level::room_entrances`]; all but one of a vault's entrances are walled
+//! up, and the corridor stubs left leading to them are filled in.
+//!
+//! Treasure rooms are tried first, then dead ends, then ordinary rooms, then
+//! the rest; the entrance and exit rooms never become vaults. A vault keeps
+//! the entrance nearest the level's start, or the next nearest if sealing
+//! the others would cut off any part of the level; rooms that cannot be
+//! sealed without that, or that touch another room directly, are passed
+//! over. Connections, stats, and marble tiles are worked out again after
+//! sealing.
+//!
+//! [`GeneratorParams::vault_guard`] also records a [`VaultGuard`] on each
+//! vault (`Room::guard`), for the game to place at the one entrance: a door
+//! to unlock, or an obstacle to get past. The tiles stay walkable either
+//! way.
+//!
+//! ```
+//! use level_generator::gen::{generate, GeneratorParams, RoomRole, VaultGuard};
+//!
+//! let level = generate(&GeneratorParams { seed: Some(7), rooms: 14, vaults: 2, vault_guard: Some(VaultGuard::Door), ..Default::default() });
+//! for (i, room) in level.rooms.iter().enumerate().filter(|(_, r)| r.role == RoomRole::Vault) {
+//!     assert_eq!(level.room_entrances(i).len(), 1);
+//!     assert_eq!(room.guard, Some(VaultGuard::Door));
+//! }
+//! ```
+//!
+//! [`GeneratorParams::vaults`]: crate::dungeon::GeneratorParams::vaults
+//! [`GeneratorParams::vault_guard`]: crate::dungeon::GeneratorParams::vault_guard
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dungeon::{is_walkable_char, Grid, Level, Room, TILE_WALL};
+use crate::hex::GridTopology;
+use crate::roles::RoomRole;
+use crate::zones::{bfs, start_tile};
+
+/// What guards the entrance of a vault; see the module docs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum VaultGuard {
+    /// A door or gate, locked or not
+    Door,
+    /// Something to get past: a monster, a trap, a bumper
+    Obstacle,
+}
+
+impl Level {
+    /// Runs of walkable tiles outside every room that border room `room`,
+    /// one per corridor mouth, in scan order. Empty for an unknown room.
+    pub fn room_entrances(&self, room: usize) -> Vec<Vec<(i32, i32)>> {
+        let grid: Grid = self.ascii_tiles().iter().map(|row| row.chars().collect()).collect();
+        match self.rooms.get(room) {
+            Some(target) => entrances(&grid, &self.rooms, target, self.topology),
+            None => Vec::new(),
+        }
+    }
+
+    /// Seal up to `count` rooms down to one entrance and tag them as vaults
+    /// guarded by `guard`; see the module docs. Returns the number of
+    /// vaults made.
+    pub fn add_vaults(&mut self, count: u32, guard: Option<VaultGuard>) -> u32 {
+        let mut grid: Grid = self.ascii_tiles().iter().map(|row| row.chars().collect()).collect();
+        let rank = |role: RoomRole| match role {
+            RoomRole::Treasure => 0,
+            RoomRole::DeadEnd => 1,
+            RoomRole::Normal => 2,
+            _ => 3,
+        };
+        let mut candidates: Vec<usize> =
+            (0..self.rooms.len()).filter(|&i| !matches!(self.rooms[i].role, RoomRole::Entrance | RoomRole::Exit | RoomRole::Vault)).collect();
+        candidates.sort_by_key(|&i| rank(self.rooms[i].role));
+
+        let mut vaults = Vec::new();
+        for i in candidates {
+            if vaults.len() as u32 == count {
+                break;
+            }
+            if let Some(sealed) = seal(&grid, &self.rooms, i, self.topology) {
+                grid = sealed;
+                vaults.push(i);
+            }
+        }
+        if vaults.is_empty() {
+            return 0;
+        }
+        self.set_ascii_tiles(grid, |level| {
+            level.rebuild_marble_tiles();
+            level.relink();
+        });
+        for &i in &vaults {
+            self.rooms[i].role = RoomRole::Vault;
+            self.rooms[i].guard = guard;
+        }
+        vaults.len() as u32
+    }
+}
+
+/// Whether `(x, y)` is on the grid and walkable.
+fn walkable(grid: &Grid, (x, y): (i32, i32)) -> bool {
+    x >= 0 && y >= 0 && grid.get(y as usize).and_then(|row| row.get(x as usize)).is_some_and(|&c| is_walkable_char(c))
+}
+
+/// Entrances of `room`; see [`Level::room_entrances`].
+fn entrances(grid: &Grid, rooms: &[Room], room: &Room, topology: GridTopology) -> Vec<Vec<(i32, i32)>> {
+    let mut mouth: Vec<(i32, i32)> = Vec::new();
+    for y in room.y..room.y + room.h {
+        for x in room.x..room.x + room.w {
+            for n in topology.neighbors(x, y) {
+                if walkable(grid, n) && !rooms.iter().any(|r| r.contains(n.0, n.1)) && !mouth.contains(&n) {
+                    mouth.push(n);
+                }
+            }
+        }
+    }
+    mouth.sort_by_key(|&(x, y)| (y, x));
+
+    // Tiles of one mouth are neighbors of each other
+    let mut seen: HashSet<(i32, i32)> = HashSet::new();
+    let mut groups = Vec::new();
+    for &first in &mouth {
+        if !seen.insert(first) {
+            continue;
+        }
+        let mut group = vec![first];
+        let mut next = 0;
+        while let Some(&(x, y)) = group.get(next) {
+            for n in topology.neighbors(x, y) {
+                if mouth.contains(&n) && seen.insert(n) {
+                    group.push(n);
+                }
+            }
+            next += 1;
+        }
+        group.sort_by_key(|&(x, y)| (y, x));
+        groups.push(group);
+    }
+    groups
+}
+
+/// Walkable tiles not reachable from the level's start.
+fn unreachable(grid: &Grid, rooms: &[Room], topology: GridTopology) -> usize {
+    let Some(start) = start_tile(grid, rooms) else { return 0 };
+    let (distance, _) = bfs(grid, &[(start, 0)], topology);
+    grid.iter().flatten().zip(distance.iter().flatten()).filter(|&(&c, d)| is_walkable_char(c) && d.is_none()).count()
+}
+
+/// `grid` with room `i` sealed down to one entrance, or `None` if it has
+/// none, touches another room, or cannot be sealed without cutting off
+/// part of the level.
+fn seal(grid: &Grid, rooms: &[Room], i: usize, topology: GridTopology) -> Option<Grid> {
+    let room = &rooms[i];
+    let touches_room = (room.y..room.y + room.h).any(|y| {
+        (room.x..room.x + room.w).any(|x| {
+            topology.neighbors(x, y).into_iter().any(|n| walkable(grid, n) && !room.contains(n.0, n.1) && rooms.iter().any(|r| r.contains(n.0, n.1)))
+        })
+    });
+    let mut mouths = entrances(grid, rooms, room, topology);
+    if touches_room || mouths.is_empty() {
+        return None;
+    }
+    if mouths.len() == 1 {
+        return Some(grid.clone());
+    }
+
+    // Keep the entrance nearest the start if the level allows it
+    let (distance, _) = match start_tile(grid, rooms) {
+        Some(start) => bfs(grid, &[(start, 0)], topology),
+        None => return None,
+    };
+    let near = |mouth: &Vec<(i32, i32)>| mouth.iter().filter_map(|&(x, y)| distance[y as usize][x as usize]).min().unwrap_or(u32::MAX);
+    mouths.sort_by_key(near);
+    let cut_off = unreachable(grid, rooms, topology);
+    (0..mouths.len()).find_map(|keep| {
+        let mut sealed = grid.clone();
+        let walled: Vec<(i32, i32)> = mouths.iter().enumerate().filter(|&(m, _)| m != keep).flat_map(|(_, mouth)| mouth.iter().copied()).collect();
+        for &(x, y) in &walled {
+            sealed[y as usize][x as usize] = TILE_WALL;
+        }
+        fill_stubs(&mut sealed, rooms, walled, topology);
+        (unreachable(&sealed, rooms, topology) <= cut_off).then_some(sealed)
+    })
+}
+
+/// Wall corridor tiles with at most one walkable neighbor, starting next to
+/// `from` and working back until the corridor meets something else.
+fn fill_stubs(grid: &mut Grid, rooms: &[Room], from: Vec<(i32, i32)>, topology: GridTopology) {
+    let mut stack: Vec<(i32, i32)> = from.iter().flat_map(|&(x, y)| topology.neighbors(x, y)).collect();
+    while let Some(tile) = stack.pop() {
+        if !walkable(grid, tile) || rooms.iter().any(|r| r.contains(tile.0, tile.1)) {
+            continue;
+        }
+        let open: Vec<(i32, i32)> = topology.neighbors(tile.0, tile.1).into_iter().filter(|&n| walkable(grid, n)).collect();
+        if open.len() <= 1 {
+            grid[tile.1 as usize][tile.0 as usize] = TILE_WALL;
+            stack.extend(open);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams};
+
+    #[test]
+    fn vaults_keep_a_single_entrance() {
+        let params = GeneratorParams { seed: Some(1182), rooms: 18, vaults: 3, ..Default::default() };
+        let plain = generate(&GeneratorParams { vaults: 0, ..params.clone() });
+        for level in [generate(&params), generate(&GeneratorParams { mode: GenerationMode::Marble, ..params.clone() })] {
+            let vaults: Vec<usize> = (0..level.rooms.len()).filter(|&i| level.rooms[i].role == RoomRole::Vault).collect();
+            assert!(!vaults.is_empty() && vaults.len() <= 3);
+            assert!(level.check_invariants().is_empty());
+            for &i in &vaults {
+                assert_eq!(level.room_entrances(i).len(), 1);
+                assert!(level.rooms[i].guard.is_none());
+            }
+            assert!(level.rooms.iter().any(|r| r.role == RoomRole::Entrance) && level.rooms.iter().any(|r| r.role == RoomRole::Exit));
+        }
+
+        // Sealing only ever walls tiles
+        let level = generate(&params);
+        for (row, before) in level.tiles.iter().zip(&plain.tiles) {
+            assert!(row.chars().zip(before.chars()).all(|(now, was)| now == was || now == TILE_WALL));
+        }
+        let guarded = generate(&GeneratorParams { vault_guard: Some(VaultGuard::Obstacle), ..params });
+        assert!(guarded.rooms.iter().filter(|r| r.role == RoomRole::Vault).all(|r| r.guard == Some(VaultGuard::Obstacle)));
+
+        // A two-tile corridor mouth and a one-tile one
+        let map = "\
+#########
+#...#####
+#.......#
+#.......#
+##.######
+##.######";
+        let mut level = Level::from_ascii_with(map, crate::marble::AdvancedTileConfig::none()).unwrap();
+        level.rooms = vec![Room { x: 1, y: 1, w: 3, h: 3, ..Default::default() }];
+        assert_eq!(level.room_entrances(0), vec![vec![(4, 2), (4, 3)], vec![(2, 4)]]);
+        assert!(level.room_entrances(1).is_empty());
+    }
+}
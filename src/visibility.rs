@@ -0,0 +1,215 @@
+//! Line of sight and field of view over a generated level.
+//!
+//! Sight and movement use different rules: walls, rock, and void block both,
+//! while water and marble obstacles (pillars, bumpers) only block movement.
+//! With `use_elevation`, terrain higher than the sightline also blocks sight,
+//! using marble tile or overworld terrain elevations.
+//!
+//! Both queries work on square grids; hex levels are treated as square.
+
+use crate::dungeon::{is_walkable_char, Level, TILE_ROCK, TILE_SECRET_DOOR, TILE_VOID, TILE_WALL};
+use crate::memory::ascii_char;
+use crate::tiles::TileType;
+
+/// Octant transforms `(xx, xy, yx, yy)` for recursive shadowcasting.
+const OCTANTS: [(i32, i32, i32, i32); 8] = [
+    (1, 0, 0, 1),
+    (0, 1, 1, 0),
+    (0, -1, 1, 0),
+    (-1, 0, 0, 1),
+    (-1, 0, 0, -1),
+    (0, -1, -1, 0),
+    (0, 1, -1, 0),
+    (1, 0, 0, -1),
+];
+
+fn tile_char(level: &Level, x: i32, y: i32) -> Option<char> {
+    if x < 0 || y < 0 {
+        return None;
+    }
+    if level.is_lean() {
+        return level.marble_tiles.as_ref()?.get(y as usize)?.get(x as usize).map(ascii_char);
+    }
+    level.tiles.get(y as usize)?.chars().nth(x as usize)
+}
+
+/// Whether the tile at `(x, y)` blocks sight. Tiles off the map block sight.
+pub fn blocks_sight(level: &Level, x: i32, y: i32) -> bool {
+    tile_char(level, x, y).is_none_or(|ch| matches!(ch, TILE_WALL | TILE_ROCK | TILE_VOID | TILE_SECRET_DOOR))
+}
+
+/// Whether the tile at `(x, y)` blocks movement. Tiles off the map block movement.
+pub fn blocks_movement(level: &Level, x: i32, y: i32) -> bool {
+    let obstacle = level
+        .marble_tiles
+        .as_ref()
+        .and_then(|tiles| tiles.get(y as usize)?.get(x as usize))
+        .is_some_and(|tile| tile.tile_type == TileType::Obstacle);
+    obstacle || tile_char(level, x, y).is_none_or(|ch| !is_walkable_char(ch))
+}
+
+/// Elevation of the tile at `(x, y)` (marble or terrain), or 0 when the level has none.
+pub fn tile_elevation(level: &Level, x: i32, y: i32) -> i32 {
+    if x < 0 || y < 0 {
+        return 0;
+    }
+    let (ux, uy) = (x as usize, y as usize);
+    if let Some(tile) = level.marble_tiles.as_ref().and_then(|t| t.get(uy)?.get(ux)) {
+        return tile.elevation;
+    }
+    if let Some(tile) = level.terrain.as_ref().and_then(|t| t.get(uy)?.get(ux)) {
+        return tile.elevation;
+    }
+    0
+}
+
+/// Tiles on the Bresenham line from `a` to `b`, inclusive.
+pub fn line(a: (i32, i32), b: (i32, i32)) -> Vec<(i32, i32)> {
+    let (dx, dy) = ((b.0 - a.0).abs(), -(b.1 - a.1).abs());
+    let (sx, sy) = ((b.0 - a.0).signum(), (b.1 - a.1).signum());
+    let (mut x, mut y) = a;
+    let mut err = dx + dy;
+    let mut points = vec![a];
+    while (x, y) != b {
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+        points.push((x, y));
+    }
+    points
+}
+
+/// Whether `b` is visible from `a`.
+///
+/// Only the tiles strictly between the endpoints are tested, so a wall can
+/// see and be seen. With `use_elevation`, a tile also blocks when it rises
+/// above the straight sightline between the endpoint elevations.
+pub fn line_of_sight(level: &Level, a: (i32, i32), b: (i32, i32), use_elevation: bool) -> bool {
+    let points = line(a, b);
+    let (az, bz) = (tile_elevation(level, a.0, a.1) as f32, tile_elevation(level, b.0, b.1) as f32);
+    let last = points.len() - 1;
+    points.iter().enumerate().skip(1).take(last.saturating_sub(1)).all(|(i, &(x, y))| {
+        let sightline = az + (bz - az) * i as f32 / last as f32;
+        let above = use_elevation && tile_elevation(level, x, y) as f32 > sightline;
+        !blocks_sight(level, x, y) && !above
+    })
+}
+
+/// Tiles visible from `origin` within `radius`, as a row-major `[y][x]` grid.
+///
+/// Uses recursive shadowcasting; opaque tiles facing the viewer are marked
+/// visible. With `use_elevation`, tiles higher than the
+/// origin block sight beyond them.
+pub fn field_of_view(level: &Level, origin: (i32, i32), radius: i32, use_elevation: bool) -> Vec<Vec<bool>> {
+    let (width, height) = (level.width as i32, level.height as i32);
+    let mut visible = vec![vec![false; width as usize]; height as usize];
+    if origin.0 < 0 || origin.1 < 0 || origin.0 >= width || origin.1 >= height {
+        return visible;
+    }
+    visible[origin.1 as usize][origin.0 as usize] = true;
+
+    let eye = tile_elevation(level, origin.0, origin.1);
+    let opaque = |x: i32, y: i32| blocks_sight(level, x, y) || (use_elevation && tile_elevation(level, x, y) > eye);
+    for octant in OCTANTS {
+        cast_light(&mut visible, &opaque, origin, radius, 1, 1.0, 0.0, octant);
+    }
+    visible
+}
+
+#[allow(clippy::too_many_arguments)]
+fn cast_light(
+    visible: &mut [Vec<bool>],
+    opaque: &impl Fn(i32, i32) -> bool,
+    origin: (i32, i32),
+    radius: i32,
+    row: i32,
+    mut start: f32,
+    end: f32,
+    (xx, xy, yx, yy): (i32, i32, i32, i32),
+) {
+    if start < end {
+        return;
+    }
+    let mut next_start = start;
+    for distance in row..=radius {
+        let mut blocked = false;
+        let dy = -distance;
+        for dx in -distance..=0 {
+            let l_slope = (dx as f32 - 0.5) / (dy as f32 + 0.5);
+            let r_slope = (dx as f32 + 0.5) / (dy as f32 - 0.5);
+            if start < r_slope {
+                continue;
+            }
+            if end > l_slope {
+                break;
+            }
+
+            let x = origin.0 + dx * xx + dy * xy;
+            let y = origin.1 + dx * yx + dy * yy;
+            let on_map = y >= 0 && (y as usize) < visible.len() && x >= 0 && (x as usize) < visible[0].len();
+            if on_map && dx * dx + dy * dy <= radius * radius {
+                visible[y as usize][x as usize] = true;
+            }
+
+            if blocked {
+                if opaque(x, y) {
+                    next_start = r_slope;
+                } else {
+                    blocked = false;
+                    start = next_start;
+                }
+            } else if opaque(x, y) && distance < radius {
+                blocked = true;
+                cast_light(visible, opaque, origin, radius, distance + 1, start, l_slope, (xx, xy, yx, yy));
+                next_start = r_slope;
+            }
+        }
+        if blocked {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tiles::MarbleTile;
+
+    fn level_from(rows: &[&str]) -> Level {
+        Level {
+            width: rows[0].len() as u32,
+            height: rows.len() as u32,
+            tiles: rows.iter().map(|r| r.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn walls_block_sight_and_obstacles_do_not() {
+        let mut level = level_from(&[
+            "#######",
+            "#..#..#",
+            "#.....#",
+            "#######",
+        ]);
+        assert!(!line_of_sight(&level, (1, 1), (5, 1), false));
+        assert!(line_of_sight(&level, (1, 2), (5, 2), false));
+
+        let fov = field_of_view(&level, (1, 1), 10, false);
+        assert!(fov[1][3] && !fov[1][5]);
+        assert!(fov[2][5]);
+
+        // A marble obstacle blocks movement but leaves the view open
+        let mut marble = vec![vec![MarbleTile::new(TileType::OpenPlatform); 7]; 4];
+        marble[2][3] = MarbleTile::new(TileType::Obstacle);
+        level.marble_tiles = Some(marble);
+        assert!(blocks_movement(&level, 3, 2));
+        assert!(line_of_sight(&level, (1, 2), (5, 2), false));
+    }
+}
@@ -1,8 +1,84 @@
-use crate::dungeon::Level;
+use crate::dungeon::{is_walkable_char, Level};
+use crate::hex::GridTopology;
+use crate::visibility::tile_elevation;
 
 /// Convert a `Level` into a single ASCII string for preview.
+///
+/// Hex levels are drawn with a space between tiles and odd rows indented by
+/// one column, so each tile sits between its two neighbors in the rows above
+/// and below.
 pub fn to_ascii(level: &Level) -> String {
-    level.tiles.join("\n")
+    layout(&level.ascii_tiles(), level.topology)
 }
 
+/// Like [`to_ascii`], with every walkable tile drawn as a digit for its
+/// elevation (marble or terrain), for checking elevation smoothing by eye.
+///
+/// When every walkable tile lies between 0 and 9, the digit is the
+/// elevation itself. Otherwise the range from the lowest to the highest
+/// walkable tile is split into ten equal contour bands, `0` the lowest and
+/// `9` the highest. Other tiles keep their ASCII character.
+pub fn to_ascii_elevation(level: &Level) -> String {
+    let tiles = level.ascii_tiles();
+    let walkable = || {
+        tiles.iter().enumerate().flat_map(|(y, row)| row.chars().enumerate().filter(|&(_, c)| is_walkable_char(c)).map(move |(x, _)| (x as i32, y as i32)))
+    };
+    let elevations = walkable().map(|(x, y)| tile_elevation(level, x, y));
+    let (low, high) = elevations.fold((i32::MAX, i32::MIN), |(low, high), e| (low.min(e), high.max(e)));
+    let digit = |elevation: i32| {
+        let band = if low >= 0 && high <= 9 {
+            elevation
+        } else {
+            ((elevation - low) as i64 * 10 / ((high - low) as i64 + 1)) as i32
+        };
+        char::from_digit(band as u32, 10).unwrap_or('?')
+    };
+    let rows: Vec<String> = tiles
+        .iter()
+        .enumerate()
+        .map(|(y, row)| row.chars().enumerate().map(|(x, c)| if is_walkable_char(c) { digit(tile_elevation(level, x as i32, y as i32)) } else { c }).collect())
+        .collect();
+    layout(&rows, level.topology)
+}
+
+/// Rows of tile characters joined into one string, hex rows offset.
+fn layout(rows: &[String], topology: GridTopology) -> String {
+    match topology {
+        GridTopology::Square | GridTopology::Octile => rows.join("\n"),
+        GridTopology::Hex => rows
+            .iter()
+            .enumerate()
+            .map(|(y, row)| {
+                let cells: Vec<String> = row.chars().map(String::from).collect();
+                let indent = if y % 2 == 1 { " " } else { "" };
+                format!("{}{}", indent, cells.join(" ")).trim_end().to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams};
+
+    #[test]
+    fn floors_show_their_elevation() {
+        let mut level = Level::from_ascii("######\n#....#\n#....#\n######").unwrap();
+        for (y, row) in level.marble_tiles.iter_mut().flatten().enumerate() {
+            for (x, tile) in row.iter_mut().enumerate() {
+                tile.elevation = x as i32 + y as i32;
+            }
+        }
+        assert_eq!(to_ascii_elevation(&level), "######\n#2345#\n#3456#\n######");
+
+        // Out of digit range, ten bands from the lowest floor to the highest
+        for tile in level.marble_tiles.iter_mut().flatten().flatten() {
+            tile.elevation = tile.elevation * 100 - 250;
+        }
+        assert_eq!(to_ascii_elevation(&level), "######\n#0247#\n#2479#\n######");
+        let generated = generate(&GeneratorParams { seed: Some(1187), mode: GenerationMode::Marble, enable_elevation: true, ..Default::default() });
+        assert_eq!(to_ascii_elevation(&generated).len(), to_ascii(&generated).len());
+    }
+}
@@ -0,0 +1,296 @@
+//! Sparse 3D voxel representation for marble levels.
+//!
+//! The marble tile grid stores one tile per `(x, y)` with elevation as an
+//! attribute, so two tracks can never share a column. A [`VoxelMap`] keys
+//! tiles by `(x, y, z)` instead, which allows true vertical structures:
+//!
+//! - **Bridges** (`TileType::Bridge`) run above whatever lies beneath them.
+//! - **Spirals** (`TileType::Spiral`) stack in one column to climb from a
+//!   room up to bridge height.
+//! - **Elevator drops** (`TileType::ElevatorDrop`) stack in one column to drop
+//!   the marble from bridge height into the room below.
+//!
+//! Vertical links between stacked connector voxels are implicit. The voxel
+//! map is built from the final marble tiles, so every 2D tile also appears
+//! as a voxel at its own elevation.
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+
+use crate::dungeon::Room;
+use crate::tiles::{MarbleTile, TileType};
+
+/// One occupied cell of a [`VoxelMap`].
+#[derive(Debug, Clone, serde::Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Voxel {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    pub tile: MarbleTile,
+}
+
+/// Sparse map of marble tiles keyed by `(x, y, z)`.
+///
+/// Serializes as `{ "width", "height", "voxels": [{ "x", "y", "z", "tile" }] }`
+/// with voxels ordered by `z`, then `y`, then `x`.
+#[derive(Debug, Clone, Default)]
+pub struct VoxelMap {
+    pub width: u32,
+    pub height: u32,
+    // Keyed by (z, y, x) so iteration goes layer by layer
+    cells: BTreeMap<(i32, i32, i32), MarbleTile>,
+}
+
+impl VoxelMap {
+    /// Create an empty map with the given footprint.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self { width, height, cells: BTreeMap::new() }
+    }
+
+    /// Lift a 2D marble tile grid into voxels; empty tiles are skipped.
+    pub fn from_tiles(tiles: &[Vec<MarbleTile>]) -> Self {
+        let height = tiles.len();
+        let width = if height > 0 { tiles[0].len() } else { 0 };
+        let mut map = Self::new(width as u32, height as u32);
+        for (y, row) in tiles.iter().enumerate() {
+            for (x, tile) in row.iter().enumerate() {
+                if tile.tile_type != TileType::Empty {
+                    map.insert(x as i32, y as i32, tile.elevation, tile.clone());
+                }
+            }
+        }
+        map
+    }
+
+    /// Tile at `(x, y, z)`, if any.
+    pub fn get(&self, x: i32, y: i32, z: i32) -> Option<&MarbleTile> {
+        self.cells.get(&(z, y, x))
+    }
+
+    /// Place `tile` at `(x, y, z)`, replacing any existing voxel. The tile's
+    /// elevation is set to `z`.
+    pub fn insert(&mut self, x: i32, y: i32, z: i32, mut tile: MarbleTile) {
+        tile.elevation = z;
+        self.cells.insert((z, y, x), tile);
+    }
+
+    /// Number of occupied voxels.
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Returns true if no voxel is occupied.
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Lowest and highest occupied layer.
+    pub fn z_range(&self) -> Option<(i32, i32)> {
+        let min = self.cells.keys().next()?.0;
+        let max = self.cells.keys().next_back()?.0;
+        Some((min, max))
+    }
+
+    /// Occupied voxels in column `(x, y)`, lowest first.
+    pub fn column(&self, x: i32, y: i32) -> Vec<(i32, &MarbleTile)> {
+        self.cells
+            .iter()
+            .filter(|((_, vy, vx), _)| *vx == x && *vy == y)
+            .map(|((z, _, _), tile)| (*z, tile))
+            .collect()
+    }
+
+    /// All voxels as `(x, y, z, tile)`, ordered by `z`, then `y`, then `x`.
+    pub fn iter(&self) -> impl Iterator<Item = (i32, i32, i32, &MarbleTile)> {
+        self.cells.iter().map(|(&(z, y, x), tile)| (x, y, z, tile))
+    }
+
+    /// Number of columns holding more than one voxel.
+    pub fn stacked_columns(&self) -> usize {
+        let mut counts: BTreeMap<(i32, i32), usize> = BTreeMap::new();
+        for &(_, y, x) in self.cells.keys() {
+            *counts.entry((x, y)).or_default() += 1;
+        }
+        counts.values().filter(|&&n| n > 1).count()
+    }
+
+    /// Highest occupied layer in column `(x, y)`.
+    fn top(&self, x: i32, y: i32) -> Option<i32> {
+        self.column(x, y).last().map(|&(z, _)| z)
+    }
+}
+
+impl Serialize for VoxelMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let voxels: Vec<Voxel> = self
+            .iter()
+            .map(|(x, y, z, tile)| Voxel { x, y, z, tile: tile.clone() })
+            .collect();
+        let mut s = serializer.serialize_struct("VoxelMap", 3)?;
+        s.serialize_field("width", &self.width)?;
+        s.serialize_field("height", &self.height)?;
+        s.serialize_field("voxels", &voxels)?;
+        s.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for VoxelMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Repr {
+            width: u32,
+            height: u32,
+            voxels: Vec<Voxel>,
+        }
+        let repr = Repr::deserialize(deserializer)?;
+        let mut map = VoxelMap::new(repr.width, repr.height);
+        for voxel in repr.voxels {
+            map.insert(voxel.x, voxel.y, voxel.z, voxel.tile);
+        }
+        Ok(map)
+    }
+}
+
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for VoxelMap {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "VoxelMap".into()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        // Same shape as the `Serialize` impl
+        #[derive(schemars::JsonSchema)]
+        #[allow(dead_code)]
+        struct Repr {
+            width: u32,
+            height: u32,
+            voxels: Vec<Voxel>,
+        }
+        Repr::json_schema(generator)
+    }
+}
+
+/// Rotation (0-3) of a step from `a` to the adjacent cell `b`.
+fn step_rotation(a: (i32, i32), b: (i32, i32)) -> u8 {
+    match (b.0 - a.0, b.1 - a.1) {
+        (0, -1) => 0,
+        (1, 0) => 1,
+        (0, 1) => 2,
+        _ => 3,
+    }
+}
+
+/// L-shaped path (horizontal first) between two points, inclusive.
+fn l_path(from: (i32, i32), to: (i32, i32)) -> Vec<(i32, i32)> {
+    let mut path = vec![from];
+    let (mut x, mut y) = from;
+    while x != to.0 {
+        x += (to.0 - x).signum();
+        path.push((x, y));
+    }
+    while y != to.1 {
+        y += (to.1 - y).signum();
+        path.push((x, y));
+    }
+    path
+}
+
+/// Add up to `count` elevated bridges between rooms that are not already
+/// connected, nearest pairs first.
+///
+/// Each bridge leaves the higher room through a spiral stacked on its edge
+/// tile, crosses over everything beneath it, and ends in an elevator drop
+/// onto the edge tile of the lower room. Returns the number of bridges built.
+pub fn add_bridges(map: &mut VoxelMap, rooms: &[Room], connections: &[(usize, usize)], count: u32) -> u32 {
+    let mut pairs: Vec<(i32, usize, usize)> = Vec::new();
+    for a in 0..rooms.len() {
+        for b in a + 1..rooms.len() {
+            if connections.contains(&(a, b)) {
+                continue;
+            }
+            let (ax, ay) = rooms[a].center();
+            let (bx, by) = rooms[b].center();
+            pairs.push(((ax - bx).abs() + (ay - by).abs(), a, b));
+        }
+    }
+    pairs.sort();
+
+    let mut built = 0;
+    for (_, a, b) in pairs {
+        if built >= count {
+            break;
+        }
+        // Flow downhill: start from the higher room
+        let (src, dst) = if rooms[a].elevation.unwrap_or(0) >= rooms[b].elevation.unwrap_or(0) {
+            (&rooms[a], &rooms[b])
+        } else {
+            (&rooms[b], &rooms[a])
+        };
+        let path = l_path(src.center(), dst.center());
+        let Some(exit) = path.iter().position(|&(x, y)| !src.contains(x, y)) else { continue };
+        let Some(entry) = path.iter().position(|&(x, y)| dst.contains(x, y)) else { continue };
+        if exit == 0 || entry <= exit {
+            continue;
+        }
+        let span = &path[exit..entry];
+        let (src_edge, dst_edge) = (path[exit - 1], path[entry]);
+        let src_z = map.top(src_edge.0, src_edge.1).unwrap_or(src.elevation.unwrap_or(0));
+        let dst_z = map.top(dst_edge.0, dst_edge.1).unwrap_or(dst.elevation.unwrap_or(0));
+
+        // Clear everything beneath the span (including earlier bridges)
+        let floor = span.iter().filter_map(|&(x, y)| map.top(x, y)).max().unwrap_or(i32::MIN);
+        let bridge_z = src_z.max(floor + 1).max(dst_z + 1);
+
+        for z in src_z + 1..=bridge_z {
+            let tile = MarbleTile::with_params(TileType::Spiral, z, step_rotation(src_edge, span[0]), true);
+            map.insert(src_edge.0, src_edge.1, z, tile);
+        }
+        let mut prev = src_edge;
+        for &cell in span {
+            let tile = MarbleTile::with_params(TileType::Bridge, bridge_z, step_rotation(prev, cell), true);
+            map.insert(cell.0, cell.1, bridge_z, tile);
+            prev = cell;
+        }
+        for z in dst_z + 1..=bridge_z {
+            let tile = MarbleTile::with_params(TileType::ElevatorDrop, z, step_rotation(prev, dst_edge), true);
+            map.insert(dst_edge.0, dst_edge.1, z, tile);
+        }
+        built += 1;
+    }
+    built
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::needless_range_loop)]
+    fn bridge_crosses_over_existing_track() {
+        // Two rooms on a floor with a north-south track between them
+        let mut tiles = vec![vec![MarbleTile::empty(); 12]; 5];
+        for row in tiles.iter_mut() {
+            row[6] = MarbleTile::with_params(TileType::Straight, 0, 0, true);
+        }
+        for y in 1..4 {
+            for x in (1..4).chain(9..12) {
+                tiles[y][x] = MarbleTile::with_params(TileType::OpenPlatform, 0, 0, false);
+            }
+        }
+        let rooms = vec![
+            Room { x: 1, y: 1, w: 3, h: 3, elevation: Some(0), ..Default::default() },
+            Room { x: 9, y: 1, w: 3, h: 3, elevation: Some(0), ..Default::default() },
+        ];
+        let mut map = VoxelMap::from_tiles(&tiles);
+        assert_eq!(add_bridges(&mut map, &rooms, &[], 1), 1);
+
+        // The bridge passes over the track at (6, 2) one layer up
+        assert_eq!(map.get(6, 2, 0).map(|t| t.tile_type), Some(TileType::Straight));
+        assert_eq!(map.get(6, 2, 1).map(|t| t.tile_type), Some(TileType::Bridge));
+        assert_eq!(map.get(3, 2, 1).map(|t| t.tile_type), Some(TileType::Spiral));
+        assert_eq!(map.get(9, 2, 1).map(|t| t.tile_type), Some(TileType::ElevatorDrop));
+        assert_eq!(map.stacked_columns(), 3);
+    }
+}
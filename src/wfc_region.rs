@@ -0,0 +1,226 @@
+//! WFC inside part of a map.
+//!
+//! [`Level::fill_wfc`] runs wave function collapse only on the cells of a
+//! mask, of any shape, and leaves the rest of the map alone. The cells
+//! around the mask are fixed boundary constraints, like the map edge is for
+//! a whole-map run: on WFC maps the fill must meet their pipes, so it joins
+//! the maze around it; on other maps the fill's corridors may run out into
+//! the surrounding floor but stop at walls. That makes WFC a pass over any
+//! mode's output, e.g. to fill a cavern interior with maze structure.
+//!
+//! On WFC maps the region gets pipe characters. On other maps the pipes are
+//! collapsed at half resolution and drawn as one-tile corridors between
+//! walls, and floor the maze cuts off from the rest of the map is walled up
+//! again. Marble tiles are rebuilt over the
+//! region and stats refreshed; rooms, zones, and the other layers stay as
+//! they are. The tileset has no dead ends, so a pipe that would need exactly
+//! one opening cannot be met; such masks on WFC maps make
+//! [`Level::fill_wfc`] fail.
+//!
+//! ```
+//! use level_generator::{generate, GeneratorParams};
+//!
+//! let mut level = generate(&GeneratorParams { seed: Some(4), width: 60, height: 40, rooms: 4, max_room: 20, ..Default::default() });
+//! let room = level.rooms.iter().max_by_key(|r| r.w * r.h).unwrap().clone();
+//! // The room's interior, one tile in from its walls
+//! let mask: Vec<Vec<bool>> = (0..level.height as i32)
+//!     .map(|y| (0..level.width as i32).map(|x| x > room.x && x < room.x + room.w - 1 && y > room.y && y < room.y + room.h - 1).collect())
+//!     .collect();
+//! assert!(level.fill_wfc(&mask, 1));
+//! assert!(level.check_invariants().is_empty());
+//! ```
+
+use rand::rngs::StdRng;
+use std::collections::VecDeque;
+
+use crate::budget::Deadline;
+use crate::dungeon::{collapse_wfc, is_walkable_char, wfc_edges, wfc_tile_index, Level, WfcCell, TILE_FLOOR, TILE_WALL};
+use crate::hex::GridTopology;
+use crate::scratch::GeneratorScratch;
+use crate::seeds::{stage_rng, SeedStage};
+
+impl Level {
+    /// Collapse a WFC maze, drawn from `seed`, into the cells where
+    /// `mask[y][x]` is true; see the module docs. Cells missing from `mask`
+    /// count as outside it. Returns false and leaves the level unchanged
+    /// when no maze fits the boundary. Hex levels are not supported and
+    /// always return false.
+    pub fn fill_wfc(&mut self, mask: &[Vec<bool>], seed: u64) -> bool {
+        if self.topology == GridTopology::Hex {
+            return false;
+        }
+        let mut grid: Vec<Vec<char>> = self.ascii_tiles().iter().map(|row| row.chars().collect()).collect();
+        let inside = |x: usize, y: usize| mask.get(y).and_then(|row| row.get(x)).copied().unwrap_or(false);
+
+        let mut rng = stage_rng(seed, SeedStage::WfcFill);
+        let filled = if grid.iter().flatten().all(|&ch| wfc_tile_index(ch).is_some()) {
+            fill_pipes(&mut grid, &inside, &mut rng)
+        } else {
+            fill_maze(&mut grid, &inside, &mut rng)
+        };
+        if !filled {
+            return false;
+        }
+        self.set_ascii_tiles(grid, |level| {
+            if level.marble_tiles.is_some() {
+                level.rebuild_marble_tiles();
+            } else {
+                level.refresh_stats();
+            }
+        });
+        true
+    }
+}
+
+/// Collapse pipes into the masked cells of a WFC map, meeting the pipes
+/// around them.
+fn fill_pipes(grid: &mut [Vec<char>], inside: &impl Fn(usize, usize) -> bool, rng: &mut StdRng) -> bool {
+    let height = grid.len();
+    let width = grid.first().map_or(0, Vec::len);
+    let cells: Vec<WfcCell> = (0..width * height)
+        .map(|i| match inside(i % width, i / width) {
+            true => WfcCell::Free,
+            false => WfcCell::Fixed(wfc_tile_index(grid[i / width][i % width]).unwrap()),
+        })
+        .collect();
+    let Some(pipes) = collapse_wfc(width, height, &cells, rng, &Deadline::unlimited(), &mut GeneratorScratch::default()) else {
+        return false;
+    };
+    for (y, row) in pipes.iter().enumerate() {
+        for (x, ch) in row.chars().enumerate().filter(|&(x, _)| inside(x, y)) {
+            grid[y][x] = ch;
+        }
+    }
+    true
+}
+
+/// Carve a maze into the masked cells of a floor-and-wall map. Pipes are
+/// collapsed at half resolution, each drawn as two by two tiles: the pipe's
+/// own tile, its links right and down, and a wall. Pipes whose own tile is
+/// outside the mask are open on floor and closed elsewhere.
+#[allow(clippy::needless_range_loop)]
+fn fill_maze(grid: &mut [Vec<char>], inside: &impl Fn(usize, usize) -> bool, rng: &mut StdRng) -> bool {
+    let height = grid.len();
+    let width = grid.first().map_or(0, Vec::len);
+    let (pipe_width, pipe_height) = (width.div_ceil(2), height.div_ceil(2));
+    let blank = wfc_tile_index(' ').unwrap();
+    let cells: Vec<WfcCell> = (0..pipe_width * pipe_height)
+        .map(|i| {
+            let (x, y) = (i % pipe_width * 2, i / pipe_width * 2);
+            match inside(x, y) {
+                true => WfcCell::Free,
+                false if is_walkable_char(grid[y][x]) => WfcCell::Open,
+                false => WfcCell::Fixed(blank),
+            }
+        })
+        .collect();
+    let Some(pipes) = collapse_wfc(pipe_width, pipe_height, &cells, rng, &Deadline::unlimited(), &mut GeneratorScratch::default()) else {
+        return false;
+    };
+    // Open and closed pipes come out blank, so a link is there if either end
+    // has it
+    let pipes: Vec<Vec<[bool; 4]>> = pipes.iter().map(|row| row.chars().map(|ch| wfc_edges(ch).unwrap()).collect()).collect();
+    let pipe = |x: usize, y: usize| pipes.get(y).and_then(|row| row.get(x)).copied().unwrap_or([false; 4]);
+    for y in 0..height {
+        for x in (0..width).filter(|&x| inside(x, y)) {
+            let (px, py) = (x / 2, y / 2);
+            let floor = match (x % 2, y % 2) {
+                (0, 0) => pipe(px, py).contains(&true),
+                (1, 0) => pipe(px, py)[1] || pipe(px + 1, py)[3],
+                (0, 1) => pipe(px, py)[2] || pipe(px, py + 1)[0],
+                _ => false,
+            };
+            grid[y][x] = if floor { TILE_FLOOR } else { TILE_WALL };
+        }
+    }
+    wall_off_pockets(grid, inside);
+    true
+}
+
+/// Turn the walkable cells inside the mask that cannot reach any walkable
+/// cell outside it back into wall. If nothing outside is walkable, the
+/// largest walkable area inside is kept.
+fn wall_off_pockets(grid: &mut [Vec<char>], inside: &impl Fn(usize, usize) -> bool) {
+    let height = grid.len();
+    let width = grid.first().map_or(0, Vec::len);
+    let mut area = vec![vec![usize::MAX; width]; height];
+    let mut sizes = Vec::new();
+    let mut reaches_outside = Vec::new();
+    for y0 in 0..height {
+        for x0 in 0..width {
+            if area[y0][x0] != usize::MAX || !is_walkable_char(grid[y0][x0]) {
+                continue;
+            }
+            let id = sizes.len();
+            let (mut size, mut outside) = (0, false);
+            area[y0][x0] = id;
+            let mut queue = VecDeque::from([(x0, y0)]);
+            while let Some((x, y)) = queue.pop_front() {
+                size += 1;
+                outside |= !inside(x, y);
+                let neighbors = [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)];
+                for (nx, ny) in neighbors {
+                    if nx < width && ny < height && area[ny][nx] == usize::MAX && is_walkable_char(grid[ny][nx]) {
+                        area[ny][nx] = id;
+                        queue.push_back((nx, ny));
+                    }
+                }
+            }
+            sizes.push(size);
+            reaches_outside.push(outside);
+        }
+    }
+    let largest = (0..sizes.len()).max_by_key(|&id| (sizes[id], std::cmp::Reverse(id)));
+    let any_outside = reaches_outside.contains(&true);
+    for y in 0..height {
+        for x in 0..width {
+            let id = area[y][x];
+            if id != usize::MAX && inside(x, y) && !reaches_outside[id] && (any_outside || Some(id) != largest) {
+                grid[y][x] = TILE_WALL;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GenerationMode, GeneratorParams};
+    use crate::tiles::TileType;
+
+    #[test]
+    fn fills_only_the_masked_cells() {
+        // A diamond in the middle of the map
+        let diamond = |width: u32, height: u32| -> Vec<Vec<bool>> {
+            let (cx, cy) = (width as i32 / 2, height as i32 / 2);
+            (0..height as i32).map(|y| (0..width as i32).map(|x| (x - cx).abs() + (y - cy).abs() <= 9).collect()).collect()
+        };
+        let unmasked_equal = |a: &Level, b: &Level, mask: &[Vec<bool>]| {
+            a.tiles.iter().zip(&b.tiles).enumerate().all(|(y, (ra, rb))| ra.chars().zip(rb.chars()).enumerate().all(|(x, (ca, cb))| mask[y][x] || ca == cb))
+        };
+
+        // On a WFC map the fill joins the pipes around it
+        let wfc = generate(&GeneratorParams { seed: Some(3), width: 40, height: 30, mode: GenerationMode::Wfc, ..Default::default() });
+        let mask = diamond(40, 30);
+        let mut filled = wfc.clone();
+        assert!(filled.fill_wfc(&mask, 5));
+        assert!(unmasked_equal(&wfc, &filled, &mask) && filled.tiles != wfc.tiles);
+        assert!(filled.check_invariants().is_empty(), "{:?}", filled.check_invariants());
+        let mut again = wfc.clone();
+        again.fill_wfc(&mask, 5);
+        assert_eq!(again.tiles, filled.tiles);
+
+        // On a marble map the maze becomes floor and wall, still one track
+        let marble = generate(&GeneratorParams { seed: Some(2), width: 60, height: 40, rooms: 3, min_room: 14, max_room: 24, mode: GenerationMode::Marble, ..Default::default() });
+        let room = marble.rooms.iter().max_by_key(|r| r.w * r.h).unwrap();
+        let mask: Vec<Vec<bool>> = (0..40).map(|y| (0..60).map(|x| x > room.x + 1 && x < room.x + room.w - 2 && y > room.y + 1 && y < room.y + room.h - 2).collect()).collect();
+        let mut filled = marble.clone();
+        assert!(filled.fill_wfc(&mask, 1));
+        assert!(unmasked_equal(&marble, &filled, &mask));
+        let walls = |level: &Level| level.tiles.iter().flat_map(|r| r.chars()).filter(|&c| c == TILE_WALL).count();
+        assert!(walls(&filled) > walls(&marble));
+        assert!(filled.check_invariants().is_empty(), "{:?}", filled.check_invariants());
+        let tiles = filled.marble_tiles.as_ref().unwrap();
+        assert!((0..40).any(|y| (0..60).any(|x| mask[y][x] && tiles[y][x].tile_type == TileType::Empty)));
+    }
+}
@@ -0,0 +1,307 @@
+//! Region/zone partitioning.
+//!
+//! The zoning pass splits walkable tiles into contiguous, named zones that
+//! downstream systems can use for difficulty ramps, music regions, or
+//! fog-of-war chunks. Two strategies are available:
+//!
+//! - [`ZoneStrategy::RoomClusters`]: rooms are ordered by hop distance from
+//!   the entrance and split into `zones` consecutive clusters; every floor
+//!   tile joins the cluster of its nearest room.
+//! - [`ZoneStrategy::DistanceBands`]: tiles are bucketed by walking distance
+//!   from the start in bands of `band_width` tiles.
+//!
+//! Either way each raw zone is then split into connected components (4-, 6-,
+//! or 8-connected, following the level's topology), so every emitted zone is
+//! contiguous.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::dungeon::{is_walkable_char as is_walkable, Room};
+use crate::graph::RoomGraph;
+use crate::hex::GridTopology;
+use crate::roles::RoomRole;
+
+/// How to partition the map into zones.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ZoneStrategy {
+    /// No zoning pass
+    #[default]
+    None,
+    /// Group rooms into `zones` clusters ordered by distance from the entrance
+    RoomClusters { zones: u32 },
+    /// Bucket tiles by walking distance from the start, `band_width` tiles per band
+    DistanceBands { band_width: u32 },
+}
+
+/// A contiguous zone of walkable tiles.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Zone {
+    /// Zone id, as stored in `Level::zone_map`
+    pub id: usize,
+    /// Human-readable name
+    pub name: String,
+    /// Number of tiles in the zone
+    pub tile_count: usize,
+    /// Smallest walking distance from the start of any tile in the zone
+    pub min_distance: u32,
+    /// Largest walking distance from the start of any tile in the zone
+    pub max_distance: u32,
+}
+
+/// Result of the zoning pass.
+#[derive(Debug, Clone)]
+pub struct Zoning {
+    /// Zone id per tile, row-major; `-1` for walls
+    pub zone_map: Vec<Vec<i32>>,
+    /// Zone descriptions, indexed by id
+    pub zones: Vec<Zone>,
+}
+
+/// Multi-source BFS over walkable tiles. Returns distance and source index per tile.
+pub(crate) fn bfs(
+    grid: &[Vec<char>],
+    sources: &[((usize, usize), usize)],
+    topology: GridTopology,
+) -> (Vec<Vec<Option<u32>>>, Vec<Vec<usize>>) {
+    let height = grid.len();
+    let width = if height > 0 { grid[0].len() } else { 0 };
+    let mut dist = vec![vec![None; width]; height];
+    let mut source = vec![vec![usize::MAX; width]; height];
+    let mut queue = VecDeque::new();
+    for &((x, y), s) in sources {
+        if y < height && x < width && is_walkable(grid[y][x]) && dist[y][x].is_none() {
+            dist[y][x] = Some(0);
+            source[y][x] = s;
+            queue.push_back((x, y));
+        }
+    }
+    while let Some((x, y)) = queue.pop_front() {
+        let d = dist[y][x].unwrap_or(0);
+        for (nx, ny) in topology.neighbors(x as i32, y as i32) {
+            if nx < 0 || ny < 0 || nx as usize >= width || ny as usize >= height {
+                continue;
+            }
+            let (ux, uy) = (nx as usize, ny as usize);
+            if is_walkable(grid[uy][ux]) && dist[uy][ux].is_none() {
+                dist[uy][ux] = Some(d + 1);
+                source[uy][ux] = source[y][x];
+                queue.push_back((ux, uy));
+            }
+        }
+    }
+    (dist, source)
+}
+
+/// Pick the zoning start tile: entrance room center, else the first walkable tile.
+pub(crate) fn start_tile(grid: &[Vec<char>], rooms: &[Room]) -> Option<(usize, usize)> {
+    let entrance = rooms.iter().find(|r| r.role == RoomRole::Entrance).or(rooms.first());
+    if let Some(room) = entrance {
+        let (cx, cy) = room.center();
+        if cx >= 0 && cy >= 0 {
+            return Some((cx as usize, cy as usize));
+        }
+    }
+    for (y, row) in grid.iter().enumerate() {
+        if let Some(x) = row.iter().position(|&c| is_walkable(c)) {
+            return Some((x, y));
+        }
+    }
+    None
+}
+
+/// Partition the walkable tiles of `tiles` into zones.
+///
+/// Returns `None` for `ZoneStrategy::None` or a map without walkable tiles.
+pub fn compute_zones(
+    tiles: &[String],
+    rooms: &[Room],
+    graph: &RoomGraph,
+    strategy: ZoneStrategy,
+    topology: GridTopology,
+) -> Option<Zoning> {
+    if strategy == ZoneStrategy::None {
+        return None;
+    }
+    let grid: Vec<Vec<char>> = tiles.iter().map(|r| r.chars().collect()).collect();
+    let height = grid.len();
+    let width = if height > 0 { grid[0].len() } else { 0 };
+
+    let start = start_tile(&grid, rooms)?;
+    let (start_dist, _) = bfs(&grid, &[(start, 0)], topology);
+
+    // Raw (possibly disconnected) zone label per tile
+    let raw: Vec<Vec<Option<usize>>> = match strategy {
+        ZoneStrategy::None => return None,
+        ZoneStrategy::DistanceBands { band_width } => {
+            let band = band_width.max(1);
+            start_dist
+                .iter()
+                .map(|row| row.iter().map(|d| d.map(|d| (d / band) as usize)).collect())
+                .collect()
+        }
+        ZoneStrategy::RoomClusters { zones } if !rooms.is_empty() => {
+            let zones = (zones.max(1) as usize).min(rooms.len());
+            let entrance = rooms.iter().position(|r| r.role == RoomRole::Entrance).unwrap_or(0);
+            let hops = graph.distances_from(entrance);
+            let mut order: Vec<usize> = (0..rooms.len()).collect();
+            order.sort_by_key(|&i| (hops.get(i).copied().flatten().unwrap_or(usize::MAX), i));
+            let mut cluster_of = vec![0usize; rooms.len()];
+            for (rank, &room) in order.iter().enumerate() {
+                cluster_of[room] = rank * zones / rooms.len();
+            }
+
+            let mut sources = Vec::new();
+            for (i, room) in rooms.iter().enumerate() {
+                for y in room.y.max(0)..(room.y + room.h).min(height as i32) {
+                    for x in room.x.max(0)..(room.x + room.w).min(width as i32) {
+                        sources.push(((x as usize, y as usize), i));
+                    }
+                }
+            }
+            let (dist, source) = bfs(&grid, &sources, topology);
+            (0..height)
+                .map(|y| {
+                    (0..width)
+                        .map(|x| dist[y][x].map(|_| cluster_of[source[y][x]]))
+                        .collect()
+                })
+                .collect()
+        }
+        // No rooms to cluster: everything reachable is one zone
+        ZoneStrategy::RoomClusters { .. } => start_dist
+            .iter()
+            .map(|row| row.iter().map(|d| d.map(|_| 0)).collect())
+            .collect(),
+    };
+
+    // Split raw zones into contiguous components
+    let mut zone_map = vec![vec![-1i32; width]; height];
+    let mut components: Vec<(usize, Vec<(usize, usize)>)> = Vec::new();
+    for sy in 0..height {
+        for sx in 0..width {
+            let Some(label) = raw[sy][sx] else { continue };
+            if zone_map[sy][sx] != -1 {
+                continue;
+            }
+            let marker = components.len() as i32;
+            let mut cells = vec![(sx, sy)];
+            let mut queue = VecDeque::from([(sx, sy)]);
+            zone_map[sy][sx] = marker;
+            while let Some((x, y)) = queue.pop_front() {
+                for (nx, ny) in topology.neighbors(x as i32, y as i32) {
+                    if nx < 0 || ny < 0 || nx as usize >= width || ny as usize >= height {
+                        continue;
+                    }
+                    let (ux, uy) = (nx as usize, ny as usize);
+                    if zone_map[uy][ux] == -1 && raw[uy][ux] == Some(label) {
+                        zone_map[uy][ux] = marker;
+                        cells.push((ux, uy));
+                        queue.push_back((ux, uy));
+                    }
+                }
+            }
+            components.push((label, cells));
+        }
+    }
+
+    // Order zones by distance from the start, then renumber
+    let min_dist = |cells: &[(usize, usize)]| {
+        cells.iter().filter_map(|&(x, y)| start_dist[y][x]).min().unwrap_or(u32::MAX)
+    };
+    let mut order: Vec<usize> = (0..components.len()).collect();
+    order.sort_by_key(|&i| (min_dist(&components[i].1), i));
+
+    // Raw zones split into several components get letter suffixes ("Band 2a")
+    let prefix = match strategy {
+        ZoneStrategy::DistanceBands { .. } => "Band",
+        _ => "Cluster",
+    };
+    let label_count = |label: usize| components.iter().filter(|(l, _)| *l == label).count();
+    let mut label_seen: Vec<usize> = Vec::new();
+
+    let mut zones = Vec::with_capacity(components.len());
+    for (id, &ci) in order.iter().enumerate() {
+        let (label, cells) = &components[ci];
+        let dists: Vec<u32> = cells.iter().filter_map(|&(x, y)| start_dist[y][x]).collect();
+        for &(x, y) in cells {
+            zone_map[y][x] = id as i32;
+        }
+        let part = label_seen.iter().filter(|&&l| l == *label).count();
+        label_seen.push(*label);
+        let name = if label_count(*label) > 1 {
+            format!("{} {}{}", prefix, label + 1, (b'a' + (part % 26) as u8) as char)
+        } else {
+            format!("{} {}", prefix, label + 1)
+        };
+        zones.push(Zone {
+            id,
+            name,
+            tile_count: cells.len(),
+            min_distance: dists.iter().copied().min().unwrap_or(0),
+            max_distance: dists.iter().copied().max().unwrap_or(0),
+        });
+    }
+
+    Some(Zoning { zone_map, zones })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::{generate, GeneratorParams};
+
+    fn assert_contiguous(zoning: &Zoning) {
+        for zone in &zoning.zones {
+            let cells: Vec<(usize, usize)> = zoning
+                .zone_map
+                .iter()
+                .enumerate()
+                .flat_map(|(y, row)| row.iter().enumerate().filter(|(_, &z)| z == zone.id as i32).map(move |(x, _)| (x, y)))
+                .collect();
+            assert_eq!(cells.len(), zone.tile_count);
+            let mut seen = vec![cells[0]];
+            let mut queue = VecDeque::from([cells[0]]);
+            while let Some((x, y)) = queue.pop_front() {
+                for &(cx, cy) in &cells {
+                    if x.abs_diff(cx) + y.abs_diff(cy) == 1 && !seen.contains(&(cx, cy)) {
+                        seen.push((cx, cy));
+                        queue.push_back((cx, cy));
+                    }
+                }
+            }
+            assert_eq!(seen.len(), cells.len(), "zone {} is not contiguous", zone.id);
+        }
+    }
+
+    #[test]
+    fn distance_bands_are_contiguous_and_cover_floor() {
+        let params = GeneratorParams {
+            seed: Some(11),
+            zoning: ZoneStrategy::DistanceBands { band_width: 8 },
+            ..Default::default()
+        };
+        let level = generate(&params);
+        let zone_map = level.zone_map.as_ref().expect("zoning enabled");
+        let floors = level.tiles.iter().flat_map(|r| r.chars()).filter(|&c| c == '.').count();
+        let zoned = zone_map.iter().flatten().filter(|&&z| z >= 0).count();
+        assert_eq!(floors, zoned);
+        assert!(level.zones.len() > 1);
+
+        let zoning = Zoning { zone_map: zone_map.clone(), zones: level.zones.clone() };
+        assert_contiguous(&zoning);
+    }
+
+    #[test]
+    fn room_clusters_respect_requested_count() {
+        let params = GeneratorParams {
+            seed: Some(11),
+            zoning: ZoneStrategy::RoomClusters { zones: 3 },
+            ..Default::default()
+        };
+        let level = generate(&params);
+        assert!(level.zones.len() >= 3);
+        assert_eq!(level.zones[0].min_distance, 0);
+    }
+}